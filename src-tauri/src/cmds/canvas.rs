@@ -0,0 +1,74 @@
+use tauri::AppHandle;
+
+use crate::scene::{
+    canvas::{self, CanvasConfig},
+    scenes,
+};
+
+/// 获取附加画布
+#[tauri::command]
+pub fn get_canvases() -> Result<Vec<CanvasConfig>, String> {
+    Ok(scenes().canvases.clone())
+}
+
+/// 添加附加画布
+///
+/// # 返回值
+///
+/// - `Ok(String)`: 成功时返回新画布的 UUID
+/// - `Err(String)`: 如果名称已被占用或尺寸、帧率无效,返回错误信息字符串
+#[tauri::command]
+pub fn add_canvas(
+    app: AppHandle,
+    name: &str,
+    width: u32,
+    height: u32,
+    fps_num: u32,
+    fps_den: u32,
+) -> Result<String, String> {
+    CanvasConfig::new(name, width, height, fps_num, fps_den)
+        .and_then(|canvas| canvas::add_canvas(&app, canvas))
+        .map_err(|e| e.to_string())
+}
+
+/// 修改附加画布
+#[tauri::command]
+pub fn update_canvas(
+    app: AppHandle,
+    uuid: &str,
+    name: &str,
+    width: u32,
+    height: u32,
+    fps_num: u32,
+    fps_den: u32,
+) -> Result<(), String> {
+    canvas::update_canvas(&app, uuid, name, width, height, fps_num, fps_den)
+        .map_err(|e| e.to_string())
+}
+
+/// 删除附加画布及其场景
+#[tauri::command]
+pub fn remove_canvas(app: AppHandle, uuid: &str) -> Result<(), String> {
+    canvas::remove_canvas(&app, uuid).map_err(|e| e.to_string())
+}
+
+/// 获取输出绑定的画布，为空时为主画布
+///
+/// # 参数
+///
+/// * `output` - 输出在配置文件中的配置节，如 `VirtualCam` 或 `Recording`
+#[tauri::command]
+pub fn get_output_canvas(output: &str) -> Result<Option<String>, String> {
+    Ok(canvas::output_canvas(output))
+}
+
+/// 把输出绑定到画布，为空时绑定到主画布
+///
+/// # 参数
+///
+/// * `output` - 输出在配置文件中的配置节
+/// * `canvas` - 画布 UUID
+#[tauri::command]
+pub fn set_output_canvas(output: &str, canvas: Option<String>) -> Result<(), String> {
+    canvas::set_output_canvas(output, canvas.as_deref()).map_err(|e| e.to_string())
+}
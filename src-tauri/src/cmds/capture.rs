@@ -0,0 +1,95 @@
+#[cfg(target_os = "linux")]
+use crate::sources::{
+    audio_capture::{self, AudioDevice, CaptureKind},
+    v4l2::{self, CameraDevice, VideoMode},
+    x11::{self, X11Screen, X11Window},
+    ALSA_INPUT_SOURCE, PULSE_INPUT_SOURCE, PULSE_OUTPUT_SOURCE,
+};
+
+/// 获取 X 服务器的屏幕列表，供屏幕采集来源选择
+///
+/// # 参数
+///
+/// * `server` - X 服务器地址，为空时使用 `DISPLAY` 环境变量
+#[cfg(target_os = "linux")]
+#[tauri::command]
+pub fn get_x11_screens(server: &str) -> Result<Vec<X11Screen>, String> {
+    x11::list_screens(server).map_err(|e| e.to_string())
+}
+
+/// 获取 X 服务器的顶层窗口列表，供窗口采集来源选择
+///
+/// # 参数
+///
+/// * `server` - X 服务器地址，为空时使用 `DISPLAY` 环境变量
+#[cfg(target_os = "linux")]
+#[tauri::command]
+pub fn get_x11_windows(server: &str) -> Result<Vec<X11Window>, String> {
+    x11::list_windows(server).map_err(|e| e.to_string())
+}
+
+/// 获取音频采集来源可以选择的设备，第一项为默认设备
+///
+/// # 参数
+///
+/// * `kind` - 音频采集来源类型 ID
+#[cfg(target_os = "linux")]
+#[tauri::command]
+pub fn get_audio_devices(kind: &str) -> Result<Vec<AudioDevice>, String> {
+    let kind = match kind {
+        PULSE_INPUT_SOURCE => CaptureKind::PulseInput,
+        PULSE_OUTPUT_SOURCE => CaptureKind::PulseOutput,
+        ALSA_INPUT_SOURCE => CaptureKind::AlsaInput,
+        _ => return Err(format!("not an audio capture source: {}", kind)),
+    };
+
+    audio_capture::list_devices(kind).map_err(|e| e.to_string())
+}
+
+/// 获取视频采集设备列表
+#[cfg(target_os = "linux")]
+#[tauri::command]
+pub fn get_video_devices() -> Result<Vec<CameraDevice>, String> {
+    v4l2::list_devices().map_err(|e| e.to_string())
+}
+
+/// 获取视频采集设备支持的格式、分辨率和帧率
+///
+/// # 参数
+///
+/// * `device` - 设备 ID，如 `/dev/video0`
+#[cfg(target_os = "linux")]
+#[tauri::command]
+pub fn get_video_modes(device: &str) -> Result<Vec<VideoMode>, String> {
+    v4l2::list_modes(device).map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+#[tauri::command]
+pub fn get_x11_screens(_server: &str) -> Result<Vec<()>, String> {
+    Err("X11 capture is only supported on Linux".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+#[tauri::command]
+pub fn get_x11_windows(_server: &str) -> Result<Vec<()>, String> {
+    Err("X11 capture is only supported on Linux".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+#[tauri::command]
+pub fn get_audio_devices(_kind: &str) -> Result<Vec<()>, String> {
+    Err("audio capture is only supported on Linux".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+#[tauri::command]
+pub fn get_video_devices() -> Result<Vec<()>, String> {
+    Err("video capture devices are only supported on Linux".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+#[tauri::command]
+pub fn get_video_modes(_device: &str) -> Result<Vec<()>, String> {
+    Err("video capture devices are only supported on Linux".to_string())
+}
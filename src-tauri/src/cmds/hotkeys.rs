@@ -0,0 +1,53 @@
+use tauri::AppHandle;
+
+use crate::hotkeys::{registry, registry::HotkeyInfo, shortcut::sync_shortcuts};
+
+/// 获取所有热键动作及其绑定
+///
+/// # 返回值
+///
+/// - `Ok(Vec<HotkeyInfo>)`: 成功时返回热键信息列表
+/// - `Err(String)`: 如果出现错误,返回错误信息字符串
+#[tauri::command]
+pub fn get_hotkeys() -> Result<Vec<HotkeyInfo>, String> {
+    Ok(registry::hotkeys())
+}
+
+/// 设置热键动作的快捷键绑定
+///
+/// 绑定会保存到当前配置文件,并立即同步到系统全局快捷键。
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `id` - 动作 ID
+/// * `bindings` - 快捷键列表,为空时清除绑定
+///
+/// # 返回值
+///
+/// - `Ok(())`: 如果成功设置了绑定
+/// - `Err(String)`: 如果快捷键无效或保存失败,返回错误信息字符串
+#[tauri::command]
+pub fn set_hotkey_bindings(app: AppHandle, id: &str, bindings: Vec<String>) -> Result<(), String> {
+    registry::set_bindings(id, &bindings).map_err(|e| e.to_string())?;
+
+    sync_shortcuts(&app).map_err(|e| e.to_string())
+}
+
+/// 注入合成按键事件
+///
+/// 此函数绕过系统全局快捷键,直接走热键分发路径,供测试和调试使用。
+///
+/// # 参数
+///
+/// * `shortcut` - 快捷键字符串
+/// * `pressed` - 是否为按下事件
+///
+/// # 返回值
+///
+/// - `Ok(usize)`: 成功时返回被触发的动作数量
+/// - `Err(String)`: 如果快捷键无效,返回错误信息字符串
+#[tauri::command]
+pub fn inject_hotkey(shortcut: &str, pressed: bool) -> Result<usize, String> {
+    registry::inject_key_event(shortcut, pressed).map_err(|e| e.to_string())
+}
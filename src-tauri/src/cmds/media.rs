@@ -0,0 +1,147 @@
+use crate::{
+    scene::{collection::SceneCollection, scenes},
+    sources::{
+        media_status, send_media_command, supports_media_command, MediaCommand, MediaStatus,
+    },
+};
+
+/// 检查来源存在并且支持命令
+///
+/// # 参数
+///
+/// * `collection` - 场景集合
+/// * `uuid` - 来源 UUID
+/// * `command` - 媒体控制命令
+fn check_media_command(
+    collection: &SceneCollection,
+    uuid: &str,
+    command: MediaCommand,
+) -> Result<(), String> {
+    let source = collection
+        .source(uuid)
+        .ok_or_else(|| format!("source not found: {}", uuid))?;
+    if !supports_media_command(&source.kind, command) {
+        return Err(format!(
+            "{} is not supported by source type {}",
+            command.name(),
+            source.kind
+        ));
+    }
+    Ok(())
+}
+
+/// 检查后向来源发送媒体控制命令
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+/// * `command` - 媒体控制命令
+///
+/// # 返回值
+///
+/// - `Ok(())`: 命令已发送
+/// - `Err(String)`: 来源不存在或不支持命令时返回错误信息字符串
+fn send_command(uuid: &str, command: MediaCommand) -> Result<(), String> {
+    check_media_command(&scenes(), uuid, command)?;
+    send_media_command(uuid, command);
+    Ok(())
+}
+
+/// 播放媒体来源
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+#[tauri::command]
+pub fn play_media(uuid: &str) -> Result<(), String> {
+    send_command(uuid, MediaCommand::Play)
+}
+
+/// 暂停媒体来源
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+#[tauri::command]
+pub fn pause_media(uuid: &str) -> Result<(), String> {
+    send_command(uuid, MediaCommand::Pause)
+}
+
+/// 从头播放媒体来源
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+#[tauri::command]
+pub fn restart_media(uuid: &str) -> Result<(), String> {
+    send_command(uuid, MediaCommand::Restart)
+}
+
+/// 停止媒体来源
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+#[tauri::command]
+pub fn stop_media(uuid: &str) -> Result<(), String> {
+    send_command(uuid, MediaCommand::Stop)
+}
+
+/// 媒体来源跳转到指定位置
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+/// * `time` - 目标位置（毫秒）
+#[tauri::command]
+pub fn seek_media(uuid: &str, time: u64) -> Result<(), String> {
+    send_command(uuid, MediaCommand::Seek(time))
+}
+
+/// 获取媒体来源的播放状态和进度
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+///
+/// # 返回值
+///
+/// - `Ok(Some(MediaStatus))`: 来源正在运行时返回播放进度
+/// - `Ok(None)`: 来源没有运行
+#[tauri::command]
+pub fn get_media_status(uuid: &str) -> Result<Option<MediaStatus>, String> {
+    Ok(media_status(uuid))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        scene::source::Source,
+        sources::{IMAGE_SOURCE, MEDIA_SOURCE, SLIDESHOW_SOURCE},
+    };
+
+    #[test]
+    fn commands_require_a_source_that_supports_them() {
+        let mut collection = SceneCollection::new("test");
+        let media = collection
+            .add_source(Source::new("Media", MEDIA_SOURCE, json!({})))
+            .unwrap();
+        let slideshow = collection
+            .add_source(Source::new("Slideshow", SLIDESHOW_SOURCE, json!({})))
+            .unwrap();
+        let image = collection
+            .add_source(Source::new("Image", IMAGE_SOURCE, json!({})))
+            .unwrap();
+        let check = |uuid: &str, command| check_media_command(&collection, uuid, command);
+
+        assert!(check(&media, MediaCommand::Play).is_ok());
+        assert!(check(&media, MediaCommand::Seek(1000)).is_ok());
+        assert!(check(&slideshow, MediaCommand::Stop).is_ok());
+        assert!(check(&slideshow, MediaCommand::Seek(1000)).is_err());
+        assert!(check(&image, MediaCommand::Play).is_err());
+        assert!(check("missing", MediaCommand::Play).is_err());
+    }
+}
@@ -1,3 +1,12 @@
 #![allow(dead_code)]
 
+pub mod canvas;
+pub mod capture;
+pub mod hotkeys;
 pub mod locale;
+pub mod media;
+pub mod outputs;
+pub mod projector;
+pub mod scene;
+pub mod stats;
+pub mod transform;
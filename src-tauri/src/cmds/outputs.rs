@@ -0,0 +1,184 @@
+use tauri::AppHandle;
+
+use crate::{
+    outputs::{
+        autoconfig::{self, AutoConfigOptions, AutoConfigResult},
+        encoders::{encoder_groups, EncoderGroupInfo},
+        recording::{self, RecordingStatus},
+        services::{self, StreamService},
+        streaming::{self, StreamDestination},
+        virtualcam,
+    },
+    protocols::{
+        net::{self, NetworkInterface, NetworkSettings},
+        stream::StreamStatus,
+    },
+};
+
+/// 启动虚拟摄像机
+#[tauri::command]
+pub fn start_virtualcam(app: AppHandle) -> Result<(), String> {
+    virtualcam::start_virtualcam(&app).map_err(|e| e.to_string())
+}
+
+/// 停止虚拟摄像机
+#[tauri::command]
+pub fn stop_virtualcam() -> Result<(), String> {
+    virtualcam::stop_virtualcam();
+    Ok(())
+}
+
+/// 获取虚拟摄像机是否正在运行
+#[tauri::command]
+pub fn get_virtualcam_active() -> Result<bool, String> {
+    Ok(virtualcam::virtualcam_active())
+}
+
+/// 开始录制
+#[tauri::command]
+pub fn start_recording(app: AppHandle) -> Result<(), String> {
+    recording::start_recording(&app).map_err(|e| e.to_string())
+}
+
+/// 停止录制
+#[tauri::command]
+pub fn stop_recording() -> Result<(), String> {
+    recording::stop_recording();
+    Ok(())
+}
+
+/// 暂停录制
+#[tauri::command]
+pub fn pause_recording() -> Result<(), String> {
+    recording::pause_recording().map_err(|e| e.to_string())
+}
+
+/// 恢复录制
+#[tauri::command]
+pub fn resume_recording() -> Result<(), String> {
+    recording::resume_recording().map_err(|e| e.to_string())
+}
+
+/// 在下一个关键帧处分割录制文件
+#[tauri::command]
+pub fn split_recording() -> Result<(), String> {
+    recording::split_recording().map_err(|e| e.to_string())
+}
+
+/// 在录制的当前位置添加章节标记
+#[tauri::command]
+pub fn add_recording_chapter(name: Option<String>) -> Result<(), String> {
+    recording::add_chapter(name).map_err(|e| e.to_string())
+}
+
+/// 获取录制输出的状态
+#[tauri::command]
+pub fn get_recording_status() -> Result<RecordingStatus, String> {
+    Ok(recording::recording_status())
+}
+
+/// 获取当前配置文件的推流目标
+#[tauri::command]
+pub fn get_stream_destinations() -> Result<Vec<StreamDestination>, String> {
+    Ok(streaming::load_destinations())
+}
+
+/// 保存推流目标
+#[tauri::command]
+pub fn set_stream_destinations(destinations: Vec<StreamDestination>) -> Result<(), String> {
+    streaming::save_destinations(&destinations).map_err(|e| e.to_string())
+}
+
+/// 启动所有启用的推流目标
+#[tauri::command]
+pub fn start_streaming(app: AppHandle) -> Result<(), String> {
+    streaming::start_streaming(&app).map_err(|e| e.to_string())
+}
+
+/// 停止所有推流目标
+#[tauri::command]
+pub fn stop_streaming() -> Result<(), String> {
+    streaming::stop_streaming();
+    Ok(())
+}
+
+/// 启动指定的推流目标
+#[tauri::command]
+pub fn start_stream_destination(app: AppHandle, id: String) -> Result<(), String> {
+    streaming::start_destination(&app, &id).map_err(|e| e.to_string())
+}
+
+/// 停止指定的推流目标
+#[tauri::command]
+pub fn stop_stream_destination(id: String) -> Result<(), String> {
+    streaming::stop_destination(&id);
+    Ok(())
+}
+
+/// 获取各推流输出的状态
+#[tauri::command]
+pub fn get_streaming_status() -> Result<Vec<StreamStatus>, String> {
+    Ok(streaming::stream_statuses())
+}
+
+/// 获取运行中的共享编码器
+#[tauri::command]
+pub fn get_encoder_groups() -> Result<Vec<EncoderGroupInfo>, String> {
+    Ok(encoder_groups())
+}
+
+/// 获取服务目录中的推流服务
+#[tauri::command]
+pub fn get_stream_services() -> Result<Vec<StreamService>, String> {
+    Ok(services::services().to_vec())
+}
+
+/// 检查推流目标的设置，选择了服务时同时检查服务的限制
+#[tauri::command]
+pub fn validate_stream_destination(destination: StreamDestination) -> Result<(), String> {
+    destination.validate().map_err(|e| e.to_string())
+}
+
+/// 获取推流目标使用服务推荐设置后的结果，不保存
+#[tauri::command]
+pub fn use_recommended_settings(
+    destination: StreamDestination,
+) -> Result<StreamDestination, String> {
+    services::recommended_settings(&destination).map_err(|e| e.to_string())
+}
+
+/// 运行自动配置，测试带宽和编码速度后返回推荐设置，进度通过事件报告
+///
+/// 测试耗时较长，在后台线程中运行
+#[tauri::command(async)]
+pub fn run_autoconfig(
+    app: AppHandle,
+    options: AutoConfigOptions,
+) -> Result<AutoConfigResult, String> {
+    autoconfig::run_autoconfig(&app, &options).map_err(|e| e.to_string())
+}
+
+/// 取消正在运行的自动配置
+#[tauri::command]
+pub fn cancel_autoconfig() -> Result<(), String> {
+    autoconfig::cancel_autoconfig();
+    Ok(())
+}
+
+/// 获取当前配置文件的网络输出设置
+#[tauri::command]
+pub fn get_network_settings() -> Result<NetworkSettings, String> {
+    Ok(NetworkSettings::load())
+}
+
+/// 保存网络输出设置，下次连接推流服务器时生效
+#[tauri::command]
+pub fn set_network_settings(settings: NetworkSettings) -> Result<(), String> {
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// 获取可以绑定的本机网络接口地址
+#[tauri::command]
+pub fn get_network_interfaces() -> Result<Vec<NetworkInterface>, String> {
+    net::network_interfaces().map_err(|e| e.to_string())
+}
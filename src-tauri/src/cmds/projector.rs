@@ -0,0 +1,47 @@
+use tauri::AppHandle;
+
+use crate::{
+    scene::projector::ProjectorKind,
+    ui::projector::{self, MonitorInfo, ProjectorInfo},
+};
+
+/// 打开投影仪，`monitor` 为 `None` 时为窗口模式
+#[tauri::command]
+pub fn open_projector(
+    app: AppHandle,
+    kind: ProjectorKind,
+    monitor: Option<usize>,
+) -> Result<String, String> {
+    projector::open_projector(&app, projector::new_projector(kind, monitor))
+        .map_err(|e| e.to_string())
+}
+
+/// 关闭投影仪
+#[tauri::command]
+pub fn close_projector(app: AppHandle, label: String) -> Result<(), String> {
+    projector::close_projector(&app, &label).map_err(|e| e.to_string())
+}
+
+/// 设置投影仪是否置顶和锁定宽高比
+#[tauri::command]
+pub fn set_projector_options(
+    app: AppHandle,
+    label: String,
+    always_on_top: bool,
+    aspect_lock: bool,
+) -> Result<(), String> {
+    projector::set_projector_options(&app, &label, always_on_top, aspect_lock)
+        .map_err(|e| e.to_string())
+}
+
+/// 获取打开的投影仪
+#[tauri::command]
+pub fn get_projectors() -> Result<Vec<ProjectorInfo>, String> {
+    Ok(projector::projectors())
+}
+
+/// 获取可以全屏显示投影仪的显示器
+#[tauri::command]
+pub fn get_monitors(app: AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    projector::monitors(&app).map_err(|e| e.to_string())
+}
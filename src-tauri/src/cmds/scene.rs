@@ -0,0 +1,204 @@
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::scene::{
+    clipboard,
+    collection::SceneCollection,
+    edit::{self, OrderMovement},
+    item::SceneItem,
+    scenes,
+    source::Filter,
+    undo,
+};
+
+/// 获取当前场景集合
+///
+/// # 返回值
+///
+/// - `Ok(SceneCollection)`: 成功时返回场景集合的快照
+/// - `Err(String)`: 如果出现错误,返回错误信息字符串
+#[tauri::command]
+pub fn get_scene_collection() -> Result<SceneCollection, String> {
+    Ok(scenes().clone())
+}
+
+/// 设置当前场景
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+#[tauri::command]
+pub fn set_current_scene(app: AppHandle, scene: &str) -> Result<(), String> {
+    edit::set_current_scene(&app, scene).map_err(|e| e.to_string())
+}
+
+/// 添加场景
+///
+/// # 参数
+///
+/// * `name` - 场景名称
+/// * `canvas` - 画布 UUID，为空时添加到主画布
+///
+/// # 返回值
+///
+/// - `Ok(String)`: 成功时返回新场景的 UUID
+/// - `Err(String)`: 如果名称已被占用,返回错误信息字符串
+#[tauri::command]
+pub fn add_scene(app: AppHandle, name: &str, canvas: Option<String>) -> Result<String, String> {
+    edit::add_scene(&app, name, canvas.as_deref()).map_err(|e| e.to_string())
+}
+
+/// 创建来源并添加到场景
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `name` - 来源名称
+/// * `kind` - 来源类型 ID
+/// * `settings` - 来源设置
+///
+/// # 返回值
+///
+/// - `Ok(SceneItem)`: 成功时返回新创建的场景项
+/// - `Err(String)`: 如果出现错误,返回错误信息字符串
+#[tauri::command]
+pub fn add_source(
+    app: AppHandle,
+    scene: &str,
+    name: &str,
+    kind: &str,
+    settings: Value,
+) -> Result<SceneItem, String> {
+    edit::add_source(&app, scene, name, kind, settings).map_err(|e| e.to_string())
+}
+
+/// 删除场景项
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `items` - 场景项 ID 列表
+#[tauri::command]
+pub fn remove_scene_items(app: AppHandle, scene: &str, items: Vec<u64>) -> Result<(), String> {
+    edit::remove_items(&app, scene, &items).map_err(|e| e.to_string())
+}
+
+/// 选中场景项
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `items` - 被选中的场景项 ID 列表
+#[tauri::command]
+pub fn select_scene_items(app: AppHandle, scene: &str, items: Vec<u64>) -> Result<(), String> {
+    edit::select_items(&app, scene, &items).map_err(|e| e.to_string())
+}
+
+/// 调整场景项顺序
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `items` - 要移动的场景项 ID 列表
+/// * `movement` - 移动方式: `up`、`down`、`top` 或 `bottom`
+#[tauri::command]
+pub fn move_scene_items(
+    app: AppHandle,
+    scene: &str,
+    items: Vec<u64>,
+    movement: OrderMovement,
+) -> Result<(), String> {
+    edit::move_items(&app, scene, &items, movement).map_err(|e| e.to_string())
+}
+
+/// 设置场景项的完整顺序
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `order` - 场景项 ID 列表(从底到顶)
+#[tauri::command]
+pub fn set_scene_item_order(app: AppHandle, scene: &str, order: Vec<u64>) -> Result<(), String> {
+    edit::set_item_order(&app, scene, &order).map_err(|e| e.to_string())
+}
+
+/// 重命名来源或场景
+///
+/// # 参数
+///
+/// * `uuid` - 来源或场景 UUID
+/// * `name` - 新名称
+#[tauri::command]
+pub fn rename_source(app: AppHandle, uuid: &str, name: &str) -> Result<(), String> {
+    edit::rename(&app, uuid, name).map_err(|e| e.to_string())
+}
+
+/// 设置来源的滤镜列表
+///
+/// # 参数
+///
+/// * `source` - 来源 UUID
+/// * `filters` - 新的滤镜列表
+/// * `merge` - 是否与相邻的滤镜变化合并为一次撤销
+#[tauri::command]
+pub fn set_source_filters(
+    app: AppHandle,
+    source: &str,
+    filters: Vec<Filter>,
+    merge: bool,
+) -> Result<(), String> {
+    edit::set_filters(&app, source, filters, merge).map_err(|e| e.to_string())
+}
+
+/// 复制场景项到内部剪贴板
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `items` - 场景项 ID 列表
+#[tauri::command]
+pub fn copy_scene_items(app: AppHandle, scene: &str, items: Vec<u64>) -> Result<(), String> {
+    clipboard::copy_items(&app, scene, &items).map_err(|e| e.to_string())
+}
+
+/// 粘贴内部剪贴板中的场景项
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `duplicate` - 为 `true` 时深拷贝来源及其设置和滤镜,否则引用原来源
+///
+/// # 返回值
+///
+/// - `Ok(Vec<SceneItem>)`: 成功时返回新创建的场景项
+/// - `Err(String)`: 如果剪贴板为空或原来源已不存在,返回错误信息字符串
+#[tauri::command]
+pub fn paste_scene_items(
+    app: AppHandle,
+    scene: &str,
+    duplicate: bool,
+) -> Result<Vec<SceneItem>, String> {
+    clipboard::paste_items(&app, scene, duplicate).map_err(|e| e.to_string())
+}
+
+/// 撤销最近的编辑
+///
+/// # 返回值
+///
+/// - `Ok(String)`: 成功时返回被撤销操作的名称
+/// - `Err(String)`: 如果没有可撤销的操作,返回错误信息字符串
+#[tauri::command]
+pub fn undo(app: AppHandle) -> Result<String, String> {
+    undo::undo(&app).map_err(|e| e.to_string())
+}
+
+/// 重做最近撤销的编辑
+///
+/// # 返回值
+///
+/// - `Ok(String)`: 成功时返回被重做操作的名称
+/// - `Err(String)`: 如果没有可重做的操作,返回错误信息字符串
+#[tauri::command]
+pub fn redo(app: AppHandle) -> Result<String, String> {
+    undo::redo(&app).map_err(|e| e.to_string())
+}
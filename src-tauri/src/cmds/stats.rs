@@ -0,0 +1,19 @@
+use crate::stats::{self, Stats};
+
+/// 获取最近一次的统计数据
+///
+/// # 返回值
+///
+/// - `Ok(Stats)`: 成功时返回统计数据
+/// - `Err(String)`: 如果出现错误,返回错误信息字符串
+#[tauri::command]
+pub fn get_stats() -> Result<Stats, String> {
+    Ok(stats::stats())
+}
+
+/// 重置统计数据
+#[tauri::command]
+pub fn reset_stats() -> Result<(), String> {
+    stats::reset_stats();
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use tauri::AppHandle;
+
+use crate::{
+    scene::{clipboard, edit::transform_items, scenes, transform::Transform},
+    sources::source_size,
+};
+
+/// 获取场景项的变换
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `item` - 场景项 ID
+///
+/// # 返回值
+///
+/// - `Ok(Transform)`: 成功时返回场景项的变换
+/// - `Err(String)`: 如果场景或场景项不存在,返回错误信息字符串
+#[tauri::command]
+pub fn get_scene_item_transform(scene: &str, item: u64) -> Result<Transform, String> {
+    let collection = scenes();
+    let scene = collection.try_scene(scene).map_err(|e| e.to_string())?;
+
+    match scene.item(item) {
+        Some(item) => Ok(item.transform),
+        None => Err(format!("scene item not found: {}", item)),
+    }
+}
+
+/// 设置场景项的变换
+///
+/// 变换会先经过校验,旋转角度会被归一化到 `[0, 360)`。来源正在运行时裁剪不能超出来源尺寸。
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `item` - 场景项 ID
+/// * `transform` - 新的变换
+/// * `merge` - 是否与相邻的变换合并为一次撤销,编辑面板中连续调整数值时应为 `true`
+///
+/// # 返回值
+///
+/// - `Ok(Transform)`: 成功时返回实际应用的变换
+/// - `Err(String)`: 如果变换无效或场景项不存在,返回错误信息字符串
+#[tauri::command]
+pub fn set_scene_item_transform(
+    app: AppHandle,
+    scene: &str,
+    item: u64,
+    transform: Transform,
+    merge: bool,
+) -> Result<Transform, String> {
+    let transform = transform.validated().map_err(|e| e.to_string())?;
+    let source = scenes()
+        .try_scene(scene)
+        .ok()
+        .and_then(|scene| scene.item(item))
+        .map(|item| item.source.clone());
+    if let Some((width, height)) = source.as_deref().and_then(source_size) {
+        transform
+            .validate_crop(width, height)
+            .map_err(|e| e.to_string())?;
+    }
+
+    transform_items(&app, scene, &[(item, transform)], "Undo.Transform", merge)
+        .map_err(|e| e.to_string())?;
+
+    Ok(transform)
+}
+
+/// 复制场景项的变换
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `item` - 场景项 ID
+#[tauri::command]
+pub fn copy_scene_item_transform(
+    app: AppHandle,
+    scene: &str,
+    item: u64,
+) -> Result<Transform, String> {
+    clipboard::copy_transform(&app, scene, item).map_err(|e| e.to_string())
+}
+
+/// 将复制的变换应用到场景项
+///
+/// # 参数
+///
+/// * `scene` - 场景 UUID
+/// * `items` - 场景项 ID 列表
+#[tauri::command]
+pub fn paste_scene_item_transform(
+    app: AppHandle,
+    scene: &str,
+    items: Vec<u64>,
+) -> Result<(), String> {
+    clipboard::paste_transform(&app, scene, &items).map_err(|e| e.to_string())
+}
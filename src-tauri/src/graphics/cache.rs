@@ -0,0 +1,365 @@
+/// 纹理缓存模块
+///
+/// 同一文件只解码和上传一次，以路径和修改时间为键，文件被修改后自动重新加载
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::anyhow;
+use log::{info, warn};
+use wgpu::{Device, Queue};
+
+use crate::{graphics::texture::Texture, media::animation::AnimatedImage, Result};
+
+/// 缓存键
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    path: PathBuf,
+    modified: SystemTime,
+    mipmaps: bool,
+}
+
+/// 缓存的图片
+///
+/// 纹理中是第一帧，静态图片的来源直接共用它，动画由各来源的 [`AnimatedTexture`] 播放
+pub struct CachedImage {
+    pub image: AnimatedImage,
+    pub texture: Texture,
+}
+
+/// 来源播放动画使用的纹理
+///
+/// 同一文件只解码一次，但每个来源有自己的纹理和播放进度，
+/// 显示同一动画的多个来源不会互相覆盖纹理中的帧
+pub struct AnimatedTexture {
+    image: Arc<CachedImage>,
+    /// 动画的纹理，静态图片为 `None`，使用缓存中的纹理
+    texture: Option<Texture>,
+    /// 纹理中当前的帧
+    frame: usize,
+}
+
+impl AnimatedTexture {
+    /// 为来源创建纹理，静态图片不创建新纹理
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `queue` - WGPU 队列
+    /// * `image` - 缓存的图片
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，创建纹理失败时返回错误
+    pub fn new(device: &Device, queue: &Queue, image: Arc<CachedImage>) -> Result<Self> {
+        let texture = match image.image.is_animated() {
+            true => Some(Texture::from_rgba(
+                device,
+                queue,
+                &image.image.frames[0].image,
+                Some("animation"),
+                false,
+            )?),
+            false => None,
+        };
+
+        Ok(Self {
+            image,
+            texture,
+            frame: 0,
+        })
+    }
+
+    /// 缓存的图片
+    pub fn image(&self) -> &Arc<CachedImage> {
+        &self.image
+    }
+
+    /// 播放到指定时间时显示的纹理，需要时上传对应的帧
+    ///
+    /// # 参数
+    ///
+    /// * `queue` - WGPU 队列
+    /// * `elapsed` - 从开始播放经过的时间
+    pub fn texture_at(&mut self, queue: &Queue, elapsed: Duration) -> &Texture {
+        let frame = self.image.image.frame_at(elapsed);
+        if let Some(texture) = &self.texture {
+            if frame != self.frame {
+                match texture.write(queue, &self.image.image.frames[frame].image) {
+                    Ok(_) => self.frame = frame,
+                    Err(e) => warn!("failed to upload animation frame {}: {}", frame, e),
+                }
+            }
+        }
+
+        self.texture()
+    }
+
+    /// 当前显示的纹理
+    pub fn texture(&self) -> &Texture {
+        self.texture.as_ref().unwrap_or(&self.image.texture)
+    }
+
+    /// 纹理中当前的帧
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+}
+
+/// 纹理缓存，与创建纹理的设备绑定
+#[derive(Default)]
+pub struct TextureCache {
+    entries: HashMap<CacheKey, Arc<CachedImage>>,
+}
+
+impl TextureCache {
+    /// 获取文件对应的纹理，不在缓存中时加载
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `queue` - WGPU 队列
+    /// * `path` - 图片路径
+    /// * `mipmaps` - 是否生成多级渐远纹理
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Arc<CachedImage>>`，文件不存在或无法解码时返回错误
+    pub fn get(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        path: &Path,
+        mipmaps: bool,
+    ) -> Result<Arc<CachedImage>> {
+        let modified = fs::metadata(path)?.modified()?;
+        if let Some(entry) = self.lookup(path, modified, mipmaps) {
+            return Ok(entry);
+        }
+
+        let image = AnimatedImage::load(path)?;
+        self.insert(device, queue, path, modified, image, mipmaps)
+    }
+
+    /// 查找缓存中指定版本的文件
+    ///
+    /// # 参数
+    ///
+    /// * `path` - 图片路径
+    /// * `modified` - 文件修改时间
+    /// * `mipmaps` - 是否有多级渐远纹理
+    pub fn lookup(
+        &self,
+        path: &Path,
+        modified: SystemTime,
+        mipmaps: bool,
+    ) -> Option<Arc<CachedImage>> {
+        self.entries
+            .get(&CacheKey {
+                path: path.to_path_buf(),
+                modified,
+                mipmaps,
+            })
+            .cloned()
+    }
+
+    /// 上传已解码的图片并加入缓存，用于在后台线程预先解码
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `queue` - WGPU 队列
+    /// * `path` - 图片路径
+    /// * `modified` - 解码时文件的修改时间
+    /// * `image` - 解码后的图片
+    /// * `mipmaps` - 是否生成多级渐远纹理，动画每帧都要重新生成，因此只用于静态图片
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Arc<CachedImage>>`，上传失败时返回错误
+    pub fn insert(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        path: &Path,
+        modified: SystemTime,
+        image: AnimatedImage,
+        mipmaps: bool,
+    ) -> Result<Arc<CachedImage>> {
+        let key = CacheKey {
+            path: path.to_path_buf(),
+            modified,
+            mipmaps,
+        };
+
+        // 同一路径的旧版本不会再被使用
+        self.entries.retain(|cached, _| cached.path != key.path);
+
+        let label = path.to_string_lossy();
+        let first = &image
+            .frames
+            .first()
+            .ok_or(anyhow!("image has no frames"))?
+            .image;
+        let texture = Texture::from_rgba(
+            device,
+            queue,
+            first,
+            Some(&label),
+            mipmaps && !image.is_animated(),
+        )?;
+        info!(
+            "loaded image {}: {}x{}, {} frames",
+            label,
+            image.width,
+            image.height,
+            image.frames.len()
+        );
+
+        let entry = Arc::new(CachedImage { image, texture });
+        self.entries.insert(key, entry.clone());
+
+        Ok(entry)
+    }
+
+    /// 释放没有被来源引用的纹理
+    pub fn purge(&mut self) {
+        self.entries.retain(|_, entry| Arc::strong_count(entry) > 1);
+    }
+
+    /// 缓存的文件数量
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+    use crate::{graphics::canvas::create_headless_device, media::animation::AnimationFrame};
+
+    /// 每帧一种纯色、每帧 100 毫秒的动画
+    fn animation(frames: usize) -> AnimatedImage {
+        AnimatedImage {
+            width: 8,
+            height: 6,
+            frames: (0..frames)
+                .map(|i| AnimationFrame {
+                    image: RgbaImage::from_pixel(8, 6, Rgba([i as u8 * 60, 0, 0, 255])),
+                    delay: Duration::from_millis(100),
+                })
+                .collect(),
+            loop_count: None,
+        }
+    }
+
+    #[test]
+    fn sources_play_animations_independently() {
+        let Ok((device, queue)) = create_headless_device(true) else {
+            eprintln!("skipping: no fallback adapter");
+            return;
+        };
+
+        let mut cache = TextureCache::default();
+        let path = Path::new("animation.gif");
+        let image = cache
+            .insert(
+                &device,
+                &queue,
+                path,
+                SystemTime::UNIX_EPOCH,
+                animation(3),
+                true,
+            )
+            .unwrap();
+
+        // 动画不生成多级渐远纹理
+        assert!(!image.texture.has_mipmaps());
+
+        // 两个来源显示同一动画，各自的纹理和帧互不影响
+        let mut first = AnimatedTexture::new(&device, &queue, image.clone()).unwrap();
+        let mut second = AnimatedTexture::new(&device, &queue, image.clone()).unwrap();
+        assert!(!std::ptr::eq(first.texture(), second.texture()));
+        assert!(!std::ptr::eq(first.texture(), &image.texture));
+
+        for (a, b, frame_a, frame_b) in [
+            (150, 0, 1, 0),
+            (250, 120, 2, 1),
+            (320, 250, 0, 2),
+            (120, 90, 1, 0),
+        ] {
+            let texture = first.texture_at(&queue, Duration::from_millis(a)) as *const Texture;
+            assert!(std::ptr::eq(texture, first.texture()));
+            second.texture_at(&queue, Duration::from_millis(b));
+            assert_eq!(first.frame(), frame_a, "{} ms", a);
+            assert_eq!(second.frame(), frame_b, "{} ms", b);
+        }
+    }
+
+    #[test]
+    fn static_images_keep_mipmaps() {
+        let Ok((device, queue)) = create_headless_device(true) else {
+            eprintln!("skipping: no fallback adapter");
+            return;
+        };
+
+        let mut cache = TextureCache::default();
+        let path = Path::new("static.png");
+        let image = cache
+            .insert(
+                &device,
+                &queue,
+                path,
+                SystemTime::UNIX_EPOCH,
+                animation(1),
+                true,
+            )
+            .unwrap();
+        assert!(image.texture.has_mipmaps());
+
+        // 静态图片直接使用缓存中的纹理
+        let mut texture = AnimatedTexture::new(&device, &queue, image.clone()).unwrap();
+        assert!(std::ptr::eq(
+            texture.texture_at(&queue, Duration::from_secs(5)),
+            &image.texture
+        ));
+        assert_eq!(texture.frame(), 0);
+    }
+
+    #[test]
+    fn insert_replaces_older_versions() {
+        let Ok((device, queue)) = create_headless_device(true) else {
+            eprintln!("skipping: no fallback adapter");
+            return;
+        };
+
+        let mut cache = TextureCache::default();
+        let path = Path::new("image.png");
+        let old = SystemTime::UNIX_EPOCH;
+        let new = old + Duration::from_secs(1);
+        cache
+            .insert(&device, &queue, path, old, animation(1), false)
+            .unwrap();
+        cache
+            .insert(&device, &queue, path, new, animation(1), false)
+            .unwrap();
+
+        assert_eq!(cache.len(), 1);
+        assert!(cache.lookup(path, old, false).is_none());
+        assert!(cache.lookup(path, new, false).is_some());
+        assert!(cache.lookup(path, new, true).is_none());
+
+        cache.purge();
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+/// 离屏画布模块
+///
+/// 节目画面在离屏纹理上合成，并回读到 CPU 供输出使用。画布和来源使用同一个设备，
+/// 来源纹理可以直接合成到画布上
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use wgpu::{
+    BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Device, DeviceDescriptor, Extent3d,
+    Features, ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Instance, Limits, MapMode,
+    MemoryHints, Origin3d, PowerPreference, Queue, RequestAdapterOptions, Texture, TextureAspect,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+};
+
+use crate::{
+    graphics::compositor::{Compositor, Layer},
+    Result,
+};
+
+/// 画布纹理格式
+pub const CANVAS_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+lazy_static! {
+    /// 画布和来源共用的设备，来源的纹理可以直接在画布上合成
+    static ref SHARED_DEVICE: Mutex<Option<(Arc<Device>, Arc<Queue>)>> = Mutex::new(None);
+}
+
+/// 创建无窗口的设备和队列
+///
+/// # 参数
+///
+/// * `force_fallback` - 是否强制使用软件适配器
+///
+/// # 返回值
+///
+/// 返回 `Result<(Device, Queue)>`，找不到适配器时返回错误
+pub fn create_headless_device(force_fallback: bool) -> Result<(Device, Queue)> {
+    let instance = Instance::default();
+
+    let adapter =
+        tauri::async_runtime::block_on(instance.request_adapter(&RequestAdapterOptions {
+            power_preference: PowerPreference::default(),
+            force_fallback_adapter: force_fallback,
+            compatible_surface: None,
+        }))
+        .ok_or(anyhow!("failed to find an appropriate adapter"))?;
+
+    let (device, queue) = tauri::async_runtime::block_on(adapter.request_device(
+        &DeviceDescriptor {
+            label: Some("canvas"),
+            required_features: Features::empty(),
+            required_limits: Limits::downlevel_defaults().using_resolution(adapter.limits()),
+            memory_hints: MemoryHints::Performance,
+        },
+        None,
+    ))?;
+
+    Ok((device, queue))
+}
+
+/// 获取画布和来源共用的设备，第一次调用时创建
+///
+/// # 返回值
+///
+/// 返回 `Result<(Arc<Device>, Arc<Queue>)>`，找不到适配器时返回错误，下次调用时重试
+pub fn shared_device() -> Result<(Arc<Device>, Arc<Queue>)> {
+    let mut shared = SHARED_DEVICE.lock().unwrap();
+    if let Some((device, queue)) = &*shared {
+        return Ok((device.clone(), queue.clone()));
+    }
+
+    let (device, queue) = create_headless_device(false)?;
+    let (device, queue) = (Arc::new(device), Arc::new(queue));
+    *shared = Some((device.clone(), queue.clone()));
+
+    Ok((device, queue))
+}
+
+/// 将纹理读回 CPU，返回紧密排列的像素数据
+///
+/// # 参数
+///
+/// * `device` - WGPU 设备
+/// * `queue` - WGPU 队列
+/// * `texture` - 源纹理，需要 `COPY_SRC` 用途
+/// * `bytes_per_pixel` - 每像素字节数
+///
+/// # 返回值
+///
+/// 返回 `Result<Vec<u8>>`，表示读回的像素数据
+pub fn read_texture(
+    device: &Device,
+    queue: &Queue,
+    texture: &Texture,
+    bytes_per_pixel: u32,
+) -> Result<Vec<u8>> {
+    let (width, height) = (texture.width(), texture.height());
+    let row = width * bytes_per_pixel;
+    let padded_row = row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("readback"),
+        size: padded_row as u64 * height as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+    encoder.copy_texture_to_buffer(
+        ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv()??;
+
+    let mut data = Vec::with_capacity((row * height) as usize);
+    {
+        let mapped = slice.get_mapped_range();
+        for chunk in mapped.chunks(padded_row as usize) {
+            data.extend_from_slice(&chunk[..row as usize]);
+        }
+    }
+    buffer.unmap();
+
+    Ok(data)
+}
+
+/// 离屏画布
+pub struct Canvas {
+    pub device: Arc<Device>,
+    pub queue: Arc<Queue>,
+    pub texture: Texture,
+    pub view: TextureView,
+    compositor: Compositor,
+}
+
+impl Canvas {
+    /// 创建离屏画布，使用与来源共用的设备
+    ///
+    /// # 参数
+    ///
+    /// * `width` - 画布宽度
+    /// * `height` - 画布高度
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，成功时包含新创建的画布
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        let (device, queue) = shared_device()?;
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("canvas"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: CANVAS_FORMAT,
+            // 多视图在 sRGB 纹理上绘制后复制到画布
+            usage: TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC
+                | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let compositor = Compositor::new(&device, CANVAS_FORMAT);
+
+        Ok(Self {
+            device,
+            queue,
+            texture,
+            view,
+            compositor,
+        })
+    }
+
+    /// 画布宽度
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    /// 画布高度
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+
+    /// 合成一帧画面
+    ///
+    /// # 参数
+    ///
+    /// * `layers` - 场景中可见的场景项，按从底到顶排列
+    pub fn render(&mut self, layers: &[Layer]) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        let size = (self.width(), self.height());
+        self.compositor.encode(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            layers,
+            &self.view,
+            size,
+        );
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// 将画布读回为 RGBA 数据
+    pub fn read_rgba(&self) -> Result<Vec<u8>> {
+        read_texture(&self.device, &self.queue, &self.texture, 4)
+    }
+}
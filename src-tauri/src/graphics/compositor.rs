@@ -0,0 +1,645 @@
+/// 场景合成模块
+///
+/// 把场景中可见场景项的来源纹理按变换（位置、旋转、缩放、边界框、裁剪）
+/// 从底到顶绘制到画布上
+use std::{borrow::Cow, num::NonZeroU64};
+
+use bytemuck::{Pod, Zeroable};
+use glam::Vec2;
+use wgpu::{
+    BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, BlendState, Buffer, BufferBinding,
+    BufferBindingType, BufferDescriptor, BufferUsages, ColorTargetState, ColorWrites,
+    CommandEncoder, Device, FragmentState, MultisampleState, PipelineLayoutDescriptor,
+    PrimitiveState, PrimitiveTopology, Queue, RenderPipeline, RenderPipelineDescriptor, Sampler,
+    SamplerBindingType, ShaderModuleDescriptor, ShaderSource, ShaderStages, TextureFormat,
+    TextureSampleType, TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::{
+    graphics::texture::Texture,
+    scene::transform::{BoundsType, Transform, ALIGN_BOTTOM, ALIGN_LEFT, ALIGN_RIGHT, ALIGN_TOP},
+};
+
+/// 合成的一层，即一个可见的场景项
+pub struct Layer<'a> {
+    /// 来源纹理
+    pub texture: &'a Texture,
+    /// 来源尺寸，裁剪按此尺寸计算
+    pub size: (u32, u32),
+    /// 场景项变换
+    pub transform: &'a Transform,
+}
+
+/// 场景项在画布上的位置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItemGeometry {
+    /// 四个角在画布上的像素坐标：左上、右上、左下、右下
+    pub corners: [Vec2; 4],
+    /// 纹理坐标：左、上、右、下，翻转时左右或上下互换
+    pub uv: [f32; 4],
+}
+
+/// 计算对齐点相对于矩形左上角的偏移
+fn align_offset(alignment: u32, size: Vec2) -> Vec2 {
+    let x = if alignment & ALIGN_LEFT != 0 {
+        0.
+    } else if alignment & ALIGN_RIGHT != 0 {
+        size.x
+    } else {
+        size.x / 2.
+    };
+    let y = if alignment & ALIGN_TOP != 0 {
+        0.
+    } else if alignment & ALIGN_BOTTOM != 0 {
+        size.y
+    } else {
+        size.y / 2.
+    };
+
+    Vec2::new(x, y)
+}
+
+/// 计算场景项在画布上的位置
+///
+/// 没有边界框时按缩放绘制裁剪后的来源，负缩放以定位点为轴翻转；有边界框时按边界框类型
+/// 缩放来源并按边界框内的对齐方式放置，定位点和旋转以边界框为准
+///
+/// # 参数
+///
+/// * `transform` - 场景项变换
+/// * `size` - 来源尺寸
+///
+/// # 返回值
+///
+/// 返回 `Option<ItemGeometry>`，来源为空或被完全裁剪时返回 `None`
+pub fn item_geometry(transform: &Transform, size: (u32, u32)) -> Option<ItemGeometry> {
+    let source = Vec2::new(size.0 as f32, size.1 as f32);
+    let crop = &transform.crop;
+    let cropped = Vec2::new(
+        source.x - crop.left as f32 - crop.right as f32,
+        source.y - crop.top as f32 - crop.bottom as f32,
+    );
+    if cropped.x <= 0. || cropped.y <= 0. {
+        return None;
+    }
+
+    let mut uv = [
+        crop.left as f32 / source.x,
+        crop.top as f32 / source.y,
+        (source.x - crop.right as f32) / source.x,
+        (source.y - crop.bottom as f32) / source.y,
+    ];
+
+    // 场景项矩形内绘制区域的左上角和右下角，以及定位点所在的矩形
+    let (mut min, mut max, frame) = if transform.bounds_type == BoundsType::None {
+        let scaled = cropped * transform.scale;
+        (Vec2::ZERO, scaled, scaled)
+    } else {
+        let bounds = transform.bounds;
+        let ratio = bounds / cropped;
+        let scale = match transform.bounds_type {
+            BoundsType::Stretch => ratio,
+            BoundsType::ScaleInner | BoundsType::None => Vec2::splat(ratio.min_element()),
+            BoundsType::ScaleOuter => Vec2::splat(ratio.max_element()),
+            BoundsType::ScaleToWidth => Vec2::splat(ratio.x),
+            BoundsType::ScaleToHeight => Vec2::splat(ratio.y),
+            BoundsType::MaxOnly => Vec2::splat(ratio.min_element().min(1.)),
+        };
+        let content = cropped * scale;
+        let min = align_offset(transform.bounds_alignment, bounds - content);
+        let max = min + content;
+
+        // 边界框中的负缩放翻转纹理
+        if transform.scale.x < 0. {
+            uv.swap(0, 2);
+        }
+        if transform.scale.y < 0. {
+            uv.swap(1, 3);
+        }
+        (min, max, bounds)
+    };
+
+    if transform.bounds_type != BoundsType::None && transform.crop_to_bounds {
+        let (clipped_min, clipped_max) = (min.max(Vec2::ZERO), max.min(frame));
+        if clipped_min.x >= clipped_max.x || clipped_min.y >= clipped_max.y {
+            return None;
+        }
+
+        // 按被裁掉的比例收缩纹理坐标
+        let span = max - min;
+        let (u0, v0, u1, v1) = (uv[0], uv[1], uv[2], uv[3]);
+        uv = [
+            u0 + (u1 - u0) * (clipped_min.x - min.x) / span.x,
+            v0 + (v1 - v0) * (clipped_min.y - min.y) / span.y,
+            u0 + (u1 - u0) * (clipped_max.x - min.x) / span.x,
+            v0 + (v1 - v0) * (clipped_max.y - min.y) / span.y,
+        ];
+        (min, max) = (clipped_min, clipped_max);
+    }
+
+    let origin = align_offset(transform.alignment, frame);
+    let rotation = Vec2::from_angle(transform.rot.to_radians());
+    let corners = [
+        Vec2::new(min.x, min.y),
+        Vec2::new(max.x, min.y),
+        Vec2::new(min.x, max.y),
+        Vec2::new(max.x, max.y),
+    ]
+    .map(|corner| transform.pos + rotation.rotate(corner - origin));
+
+    Some(ItemGeometry { corners, uv })
+}
+
+/// 着色器参数，布局与 `composite.wgsl` 中的 `Params` 一致
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    top: [f32; 4],
+    bottom: [f32; 4],
+    uv: [f32; 4],
+    canvas: [f32; 4],
+}
+
+/// 场景合成器
+pub struct Compositor {
+    pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    sampler: Sampler,
+    /// 每层一份参数，按动态偏移绑定
+    uniform: Buffer,
+    /// 参数缓冲区能容纳的层数
+    capacity: usize,
+    /// 相邻两层参数之间的字节数
+    stride: u64,
+    /// 输出格式不是 sRGB 时在着色器中编码
+    encode_srgb: bool,
+}
+
+impl Compositor {
+    /// 创建场景合成器
+    ///
+    /// 输出为 sRGB 格式时由 GPU 编码，其他格式（如画布）在着色器中编码，
+    /// 两种输出保存的都是编码后的值
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `format` - 输出纹理格式
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let stride = (std::mem::size_of::<Params>() as u64)
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+        let capacity = 16;
+        let uniform = Self::create_uniform(device, stride, capacity);
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("composite"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("shaders/composite.wgsl"))),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("composite"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: NonZeroU64::new(std::mem::size_of::<Params>() as u64),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("composite"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("composite"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            uniform,
+            capacity,
+            stride,
+            encode_srgb: !format.is_srgb(),
+        }
+    }
+
+    fn create_uniform(device: &Device, stride: u64, capacity: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("composite params"),
+            size: stride * capacity as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// 记录合成命令，先清空为黑色，再从底到顶绘制各层
+    ///
+    /// 参数缓冲区在提交前写入，因此同一次提交中只能调用一次
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `queue` - WGPU 队列
+    /// * `encoder` - 命令编码器
+    /// * `layers` - 按从底到顶排列的层
+    /// * `view` - 输出纹理视图
+    /// * `size` - 画布尺寸，场景项的像素坐标按此尺寸映射到输出，输出可以小于画布
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        layers: &[Layer],
+        view: &TextureView,
+        size: (u32, u32),
+    ) {
+        let encode_srgb = if self.encode_srgb { 1. } else { 0. };
+        let canvas = [size.0 as f32, size.1 as f32, encode_srgb, 0.];
+        let drawn: Vec<(&Texture, Params)> = layers
+            .iter()
+            .filter_map(|layer| {
+                let geometry = item_geometry(layer.transform, layer.size)?;
+                let [tl, tr, bl, br] = geometry.corners;
+                Some((
+                    layer.texture,
+                    Params {
+                        top: [tl.x, tl.y, tr.x, tr.y],
+                        bottom: [bl.x, bl.y, br.x, br.y],
+                        uv: geometry.uv,
+                        canvas,
+                    },
+                ))
+            })
+            .collect();
+
+        if drawn.len() > self.capacity {
+            self.capacity = drawn.len().next_power_of_two();
+            self.uniform = Self::create_uniform(device, self.stride, self.capacity);
+        }
+        let mut data = vec![0u8; (self.stride as usize) * drawn.len()];
+        for (i, (_, params)) in drawn.iter().enumerate() {
+            let offset = i * self.stride as usize;
+            data[offset..offset + std::mem::size_of::<Params>()]
+                .copy_from_slice(bytemuck::bytes_of(params));
+        }
+        if !data.is_empty() {
+            queue.write_buffer(&self.uniform, 0, &data);
+        }
+
+        let bind_groups: Vec<wgpu::BindGroup> = drawn
+            .iter()
+            .map(|(texture, _)| {
+                device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("composite"),
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: BindingResource::TextureView(&texture.view),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: BindingResource::Sampler(&self.sampler),
+                        },
+                        BindGroupEntry {
+                            binding: 2,
+                            resource: BindingResource::Buffer(BufferBinding {
+                                buffer: &self.uniform,
+                                offset: 0,
+                                size: NonZeroU64::new(std::mem::size_of::<Params>() as u64),
+                            }),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("composite"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        for (i, bind_group) in bind_groups.iter().enumerate() {
+            render_pass.set_bind_group(0, bind_group, &[(i as u64 * self.stride) as u32]);
+            render_pass.draw(0..4, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+    use crate::{
+        graphics::canvas::{create_headless_device, read_texture, CANVAS_FORMAT},
+        scene::transform::{Crop, ALIGN_CENTER},
+    };
+
+    fn assert_corners(geometry: &ItemGeometry, expected: [(f32, f32); 4]) {
+        for (corner, (x, y)) in geometry.corners.iter().zip(expected) {
+            assert!(
+                (corner.x - x).abs() < 1e-3 && (corner.y - y).abs() < 1e-3,
+                "{:?} != {:?}",
+                geometry.corners,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn position_scale_and_alignment() {
+        let transform = Transform {
+            pos: Vec2::new(10., 20.),
+            scale: Vec2::new(2., 0.5),
+            ..Default::default()
+        };
+        let geometry = item_geometry(&transform, (100, 40)).unwrap();
+        assert_corners(
+            &geometry,
+            [(10., 20.), (210., 20.), (10., 40.), (210., 40.)],
+        );
+        assert_eq!(geometry.uv, [0., 0., 1., 1.]);
+
+        // 定位点在右下角时场景项位于位置的左上方
+        let transform = Transform {
+            pos: Vec2::new(100., 100.),
+            alignment: ALIGN_RIGHT | ALIGN_BOTTOM,
+            ..Default::default()
+        };
+        let geometry = item_geometry(&transform, (40, 20)).unwrap();
+        assert_corners(
+            &geometry,
+            [(60., 80.), (100., 80.), (60., 100.), (100., 100.)],
+        );
+
+        // 负缩放以定位点为轴翻转
+        let transform = Transform {
+            pos: Vec2::new(100., 0.),
+            scale: Vec2::new(-1., 1.),
+            ..Default::default()
+        };
+        let geometry = item_geometry(&transform, (40, 20)).unwrap();
+        assert_corners(&geometry, [(100., 0.), (60., 0.), (100., 20.), (60., 20.)]);
+    }
+
+    #[test]
+    fn rotation_is_around_the_alignment_point() {
+        let transform = Transform {
+            pos: Vec2::new(50., 50.),
+            rot: 90.,
+            alignment: ALIGN_CENTER,
+            ..Default::default()
+        };
+        let geometry = item_geometry(&transform, (40, 20)).unwrap();
+        // 顺时针旋转 90 度后左上角到了右上方
+        assert_corners(&geometry, [(60., 30.), (60., 70.), (40., 30.), (40., 70.)]);
+    }
+
+    #[test]
+    fn crop_shrinks_the_item_and_texture_coordinates() {
+        let transform = Transform {
+            crop: Crop {
+                left: 10,
+                top: 0,
+                right: 30,
+                bottom: 5,
+            },
+            ..Default::default()
+        };
+        let geometry = item_geometry(&transform, (100, 20)).unwrap();
+        assert_corners(&geometry, [(0., 0.), (60., 0.), (0., 15.), (60., 15.)]);
+        assert_eq!(geometry.uv, [0.1, 0., 0.7, 0.75]);
+
+        let transform = Transform {
+            crop: Crop {
+                left: 50,
+                right: 50,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(item_geometry(&transform, (100, 20)).is_none());
+        assert!(item_geometry(&Transform::default(), (0, 0)).is_none());
+    }
+
+    #[test]
+    fn bounds_types() {
+        let bounded = |bounds_type, bounds_alignment| Transform {
+            bounds_type,
+            bounds_alignment,
+            bounds: Vec2::new(200., 200.),
+            ..Default::default()
+        };
+
+        let geometry = item_geometry(&bounded(BoundsType::Stretch, ALIGN_CENTER), (100, 50));
+        assert_corners(
+            &geometry.unwrap(),
+            [(0., 0.), (200., 0.), (0., 200.), (200., 200.)],
+        );
+
+        // 缩放到内侧后在边界框中居中
+        let geometry = item_geometry(&bounded(BoundsType::ScaleInner, ALIGN_CENTER), (100, 50));
+        assert_corners(
+            &geometry.unwrap(),
+            [(0., 50.), (200., 50.), (0., 150.), (200., 150.)],
+        );
+
+        // 缩放到外侧后超出边界框
+        let geometry = item_geometry(&bounded(BoundsType::ScaleOuter, ALIGN_TOP), (100, 50));
+        assert_corners(
+            &geometry.unwrap(),
+            [(-100., 0.), (300., 0.), (-100., 200.), (300., 200.)],
+        );
+
+        let geometry = item_geometry(&bounded(BoundsType::ScaleToHeight, ALIGN_LEFT), (100, 50));
+        assert_corners(
+            &geometry.unwrap(),
+            [(0., 0.), (400., 0.), (0., 200.), (400., 200.)],
+        );
+
+        // 仅限最大尺寸时小来源保持原尺寸，并按边界框内的对齐方式放置
+        let geometry = item_geometry(
+            &bounded(BoundsType::MaxOnly, ALIGN_RIGHT | ALIGN_BOTTOM),
+            (100, 50),
+        );
+        assert_corners(
+            &geometry.unwrap(),
+            [(100., 150.), (200., 150.), (100., 200.), (200., 200.)],
+        );
+    }
+
+    #[test]
+    fn crop_to_bounds_clips_the_texture() {
+        let transform = Transform {
+            bounds_type: BoundsType::ScaleOuter,
+            bounds_alignment: ALIGN_CENTER,
+            bounds: Vec2::new(200., 200.),
+            crop_to_bounds: true,
+            ..Default::default()
+        };
+        let geometry = item_geometry(&transform, (100, 50)).unwrap();
+        assert_corners(&geometry, [(0., 0.), (200., 0.), (0., 200.), (200., 200.)]);
+        assert_eq!(geometry.uv, [0.25, 0., 0.75, 1.]);
+
+        // 边界框中的负缩放翻转纹理而不移动位置
+        let transform = Transform {
+            scale: Vec2::new(-1., 1.),
+            ..transform
+        };
+        let geometry = item_geometry(&transform, (100, 50)).unwrap();
+        assert_corners(&geometry, [(0., 0.), (200., 0.), (0., 200.), (200., 200.)]);
+        assert_eq!(geometry.uv, [0.75, 0., 0.25, 1.]);
+    }
+
+    #[test]
+    fn layers_are_drawn_bottom_to_top() {
+        let Ok((device, queue)) = create_headless_device(true) else {
+            eprintln!("skipping: no fallback adapter");
+            return;
+        };
+
+        let output = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: 16,
+                height: 8,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: CANVAS_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = output.create_view(&Default::default());
+
+        let solid = |color| {
+            Texture::from_rgba(
+                &device,
+                &queue,
+                &RgbaImage::from_pixel(4, 4, Rgba(color)),
+                Some("layer"),
+                false,
+            )
+            .unwrap()
+        };
+        let red = solid([255, 0, 0, 255]);
+        let half_blue = solid([0, 0, 255, 128]);
+
+        // 红色铺满左半边，半透明蓝色覆盖中间
+        let left = Transform {
+            bounds_type: BoundsType::Stretch,
+            bounds: Vec2::new(8., 8.),
+            ..Default::default()
+        };
+        let middle = Transform {
+            pos: Vec2::new(4., 0.),
+            scale: Vec2::new(2., 2.),
+            ..Default::default()
+        };
+        let layers = [
+            Layer {
+                texture: &red,
+                size: (4, 4),
+                transform: &left,
+            },
+            Layer {
+                texture: &half_blue,
+                size: (4, 4),
+                transform: &middle,
+            },
+        ];
+
+        let mut compositor = Compositor::new(&device, CANVAS_FORMAT);
+        let mut encoder = device.create_command_encoder(&Default::default());
+        compositor.encode(&device, &queue, &mut encoder, &layers, &view, (16, 8));
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let data = read_texture(&device, &queue, &output, 4).unwrap();
+        let pixel = |x: usize, y: usize| {
+            let offset = (y * 16 + x) * 4;
+            [data[offset], data[offset + 1], data[offset + 2]]
+        };
+        assert_eq!(pixel(1, 4), [255, 0, 0]);
+        assert_eq!(pixel(14, 4), [0, 0, 0]);
+        // 编码后的值按透明度混合
+        let [r, g, b] = pixel(6, 4);
+        assert!((120..=135).contains(&r) && g == 0 && (120..=135).contains(&b));
+        let [r, g, b] = pixel(10, 4);
+        assert!(r == 0 && g == 0 && (120..=135).contains(&b));
+    }
+}
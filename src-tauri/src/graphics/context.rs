@@ -4,10 +4,11 @@ use std::{borrow::Cow, sync::Arc};
 use anyhow::Ok;
 use tauri::{AppHandle, PhysicalSize, Window};
 use wgpu::{
-    Device, DeviceDescriptor, Features, FragmentState, Instance, Limits, MemoryHints,
-    MultisampleState, PipelineLayoutDescriptor, PowerPreference, PresentMode, PrimitiveState,
-    Queue, RenderPipeline, RenderPipelineDescriptor, RequestAdapterOptions, ShaderModuleDescriptor,
-    ShaderSource, Surface, SurfaceConfiguration, TextureUsages, VertexState,
+    CommandEncoder, Device, DeviceDescriptor, Features, FragmentState, Instance, Limits,
+    MemoryHints, MultisampleState, PipelineLayoutDescriptor, PowerPreference, PresentMode,
+    PrimitiveState, Queue, RenderPipeline, RenderPipelineDescriptor, RequestAdapterOptions,
+    ShaderModuleDescriptor, ShaderSource, Surface, SurfaceConfiguration, TextureFormat,
+    TextureUsages, TextureView, VertexState,
 };
 
 use crate::{
@@ -70,21 +71,7 @@ impl Context {
         // 创建着色器模块
         let shader = device.create_shader_module(ShaderModuleDescriptor {
             label: None,
-            source: ShaderSource::Wgsl(Cow::Borrowed(
-                r#"
-    @vertex
-    fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> @builtin(position) vec4<f32> {
-        let x = f32(i32(in_vertex_index) - 1);
-        let y = f32(i32(in_vertex_index & 1u) * 2 - 1);
-        return vec4<f32>(x, y, 0.0, 1.0);
-    }
-
-    @fragment
-    fn fs_main() -> @location(0) vec4<f32> {
-        return vec4<f32>(1.0, 0.0, 0.0, 1.0);
-    }
-    "#,
-            )),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("shaders/triangle.wgsl"))),
         });
 
         // 创建管线布局
@@ -195,4 +182,54 @@ impl Context {
         self.queue.submit(std::iter::once(encoder.finish()));
         frame.present();
     }
+
+    /// 图形设备
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// 命令队列
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// 交换链纹理格式，在此窗口中绘制的管线需要使用该格式
+    pub fn format(&self) -> TextureFormat {
+        self.config.format
+    }
+
+    /// 使用自定义的绘制命令渲染一帧，如多视图
+    ///
+    /// # 参数
+    ///
+    /// * `draw` - 记录绘制命令，参数依次为设备、队列、命令编码器、当前帧的纹理视图和尺寸
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，无法获取交换链纹理时返回错误
+    pub fn render_with(
+        &self,
+        draw: impl FnOnce(&Device, &Queue, &mut CommandEncoder, &TextureView, (u32, u32)),
+    ) -> Result<()> {
+        let frame = self.surface.get_current_texture()?;
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        draw(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            &view,
+            (self.config.width, self.config.height),
+        );
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+        frame.present();
+
+        Ok(())
+    }
 }
@@ -0,0 +1,503 @@
+/// GPU 像素格式转换模块
+///
+/// 使用计算着色器将 RGBA 画布转换为 NV12、I420、I444 和 P010，结果写入一个存储缓冲区，
+/// 各平面按顺序排列，每行按 4 字节对齐
+use std::borrow::Cow;
+
+use anyhow::anyhow;
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferDescriptor, BufferUsages,
+    CommandEncoder, CommandEncoderDescriptor, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, Device, PipelineLayoutDescriptor, Queue, ShaderModuleDescriptor,
+    ShaderSource, ShaderStages, TextureSampleType, TextureView, TextureViewDimension,
+};
+
+use crate::{
+    graphics::readback::Readback,
+    media::{
+        color::{ColorParams, ColorRange, ColorSpace},
+        frame::VideoFormat,
+    },
+    Result,
+};
+
+/// 计算着色器工作组大小
+const WORKGROUP_SIZE: u32 = 8;
+
+/// 着色器参数，布局与 `convert.wgsl` 中的 `Params` 一致
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    size: [u32; 4],
+    plane: [u32; 4],
+    offsets: [u32; 4],
+    coef: [f32; 4],
+    range: [f32; 4],
+}
+
+/// 输出平面在存储缓冲区中的布局
+#[derive(Debug, Clone, Copy)]
+pub struct PlaneLayout {
+    /// 平面起始位置（字节）
+    pub offset: usize,
+    /// 每行字节数（含对齐填充）
+    pub stride: usize,
+    /// 每行有效字节数
+    pub row_bytes: usize,
+    /// 行数
+    pub rows: usize,
+}
+
+/// GPU 像素格式转换器
+pub struct Converter {
+    pub width: u32,
+    pub height: u32,
+    pub format: VideoFormat,
+    pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+    /// 每个平面的参数缓冲区
+    uniforms: Vec<Buffer>,
+    /// 每个平面的工作组数量
+    dispatches: Vec<(u32, u32)>,
+    planes: Vec<PlaneLayout>,
+    /// 转换结果
+    output: Buffer,
+}
+
+impl Converter {
+    /// 创建转换器
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `width` - 画布宽度
+    /// * `height` - 画布高度
+    /// * `format` - 目标格式，支持 `Nv12`、`I420`、`I444` 和 `P010`
+    /// * `space` - 色彩空间
+    /// * `range` - 色彩范围
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，格式不支持时返回错误
+    pub fn new(
+        device: &Device,
+        width: u32,
+        height: u32,
+        format: VideoFormat,
+        space: ColorSpace,
+        range: ColorRange,
+    ) -> Result<Self> {
+        let (cw, ch) = (width.div_ceil(2), height.div_ceil(2));
+
+        // (平面类型, 水平下采样, 垂直下采样, 每行采样数, 行数)
+        let planes: Vec<(u32, u32, u32, u32, u32)> = match format {
+            VideoFormat::Nv12 | VideoFormat::P010 => {
+                vec![(0, 1, 1, width, height), (3, 2, 2, cw * 2, ch)]
+            }
+            VideoFormat::I420 => vec![
+                (0, 1, 1, width, height),
+                (1, 2, 2, cw, ch),
+                (2, 2, 2, cw, ch),
+            ],
+            VideoFormat::I444 => vec![
+                (0, 1, 1, width, height),
+                (1, 1, 1, width, height),
+                (2, 1, 1, width, height),
+            ],
+            _ => return Err(anyhow!("unsupported GPU conversion: {:?}", format)),
+        };
+
+        let bits = if format == VideoFormat::P010 { 10 } else { 8 };
+        let bytes_per_sample = if bits > 8 { 2 } else { 1 };
+        let color = ColorParams::new(space, range, bits);
+
+        let mut uniforms = vec![];
+        let mut dispatches = vec![];
+        let mut layouts = vec![];
+        let mut offset = 0;
+        for (kind, sub_x, sub_y, samples, rows) in planes {
+            let row_bytes = (samples * bytes_per_sample) as usize;
+            let words_per_row = row_bytes.div_ceil(4) as u32;
+
+            let params = Params {
+                size: [width, height, samples, rows],
+                plane: [kind, sub_x, sub_y, bits],
+                offsets: [(offset / 4) as u32, words_per_row, color.transfer.id(), 0],
+                coef: [color.kr, color.kb, color.sdr_white, color.max],
+                range: [color.y_scale, color.y_offset, color.c_scale, color.c_offset],
+            };
+            uniforms.push(device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("convert params"),
+                contents: bytemuck::bytes_of(&params),
+                usage: BufferUsages::UNIFORM,
+            }));
+            dispatches.push((
+                words_per_row.div_ceil(WORKGROUP_SIZE),
+                rows.div_ceil(WORKGROUP_SIZE),
+            ));
+            layouts.push(PlaneLayout {
+                offset,
+                stride: words_per_row as usize * 4,
+                row_bytes,
+                rows: rows as usize,
+            });
+
+            offset += words_per_row as usize * 4 * rows as usize;
+        }
+
+        let output = device.create_buffer(&BufferDescriptor {
+            label: Some("convert output"),
+            size: offset as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("convert"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("shaders/convert.wgsl"))),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("convert"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: false },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("convert"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("convert"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Ok(Self {
+            width,
+            height,
+            format,
+            pipeline,
+            bind_group_layout,
+            uniforms,
+            dispatches,
+            planes: layouts,
+            output,
+        })
+    }
+
+    /// 记录转换命令
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `encoder` - 命令编码器
+    /// * `source` - 源纹理视图，尺寸需与转换器一致
+    pub fn encode(&self, device: &Device, encoder: &mut CommandEncoder, source: &TextureView) {
+        let bind_groups: Vec<_> = self
+            .uniforms
+            .iter()
+            .map(|uniform| {
+                device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("convert"),
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(source),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: uniform.as_entire_binding(),
+                        },
+                        BindGroupEntry {
+                            binding: 2,
+                            resource: self.output.as_entire_binding(),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("convert"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        for (bind_group, (x, y)) in bind_groups.iter().zip(&self.dispatches) {
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(*x, *y, 1);
+        }
+    }
+
+    /// 转换结果缓冲区
+    pub fn output(&self) -> &Buffer {
+        &self.output
+    }
+
+    /// 转换结果缓冲区的字节数
+    pub fn output_size(&self) -> u64 {
+        self.output.size()
+    }
+
+    /// 各平面的布局
+    pub fn planes(&self) -> &[PlaneLayout] {
+        &self.planes
+    }
+
+    /// 去除每行的对齐填充，得到紧密排列的帧数据
+    ///
+    /// # 参数
+    ///
+    /// * `data` - 从结果缓冲区读回的数据
+    pub fn unpack(&self, data: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(self.format.frame_size(self.width, self.height));
+
+        for plane in &self.planes {
+            for row in 0..plane.rows {
+                let start = plane.offset + row * plane.stride;
+                frame.extend_from_slice(&data[start..start + plane.row_bytes]);
+            }
+        }
+
+        frame
+    }
+
+    /// 转换纹理并阻塞等待结果
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `queue` - WGPU 队列
+    /// * `source` - 源纹理视图
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Vec<u8>>`，表示紧密排列的帧数据
+    pub fn convert(&self, device: &Device, queue: &Queue, source: &TextureView) -> Result<Vec<u8>> {
+        let mut readback = Readback::new(device, self.output_size(), 1);
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+        self.encode(device, &mut encoder, source);
+        readback.enqueue(&mut encoder, &self.output, 0);
+        queue.submit(std::iter::once(encoder.finish()));
+        readback.map_submitted();
+
+        let (_, data) = readback
+            .read(device, true)
+            .ok_or(anyhow!("failed to read converted frame"))?;
+
+        Ok(self.unpack(&data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::{
+        Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, TextureAspect, TextureDescriptor,
+        TextureDimension, TextureUsages,
+    };
+
+    use super::*;
+    use crate::{
+        graphics::canvas::{create_headless_device, CANVAS_FORMAT},
+        media::{convert::convert_rgba, frame::VideoFrame},
+    };
+
+    /// 参考帧：纯色块、灰阶和渐变，宽高为奇数以覆盖色度下采样的边缘
+    fn reference_frame(width: u32, height: u32) -> VideoFrame {
+        let colors: [[u8; 3]; 8] = [
+            [0, 0, 0],
+            [255, 255, 255],
+            [255, 0, 0],
+            [0, 255, 0],
+            [0, 0, 255],
+            [255, 255, 0],
+            [128, 128, 128],
+            [16, 200, 96],
+        ];
+
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let rgb = if y < height / 2 {
+                    colors[(x as usize / 3) % colors.len()]
+                } else {
+                    [
+                        (x * 255 / (width - 1)) as u8,
+                        (y * 255 / (height - 1)) as u8,
+                        ((x + y) * 7 % 256) as u8,
+                    ]
+                };
+                data.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+            }
+        }
+
+        VideoFrame {
+            width,
+            height,
+            format: VideoFormat::Rgba,
+            timestamp: 0,
+            data,
+        }
+    }
+
+    /// 将帧上传为画布格式的纹理
+    fn upload(device: &Device, queue: &Queue, frame: &VideoFrame) -> TextureView {
+        let size = Extent3d {
+            width: frame.width,
+            height: frame.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("reference"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: CANVAS_FORMAT,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &frame.data,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(frame.width * 4),
+                rows_per_image: Some(frame.height),
+            },
+            size,
+        );
+
+        texture.create_view(&Default::default())
+    }
+
+    /// 读取第 `i` 个码值
+    fn sample(data: &[u8], format: VideoFormat, i: usize) -> i32 {
+        if format == VideoFormat::P010 {
+            (u16::from_le_bytes([data[i * 2], data[i * 2 + 1]]) >> 6) as i32
+        } else {
+            data[i] as i32
+        }
+    }
+
+    #[test]
+    fn gpu_conversion_matches_cpu_reference() {
+        let (device, queue) = match create_headless_device(true) {
+            Ok(device) => device,
+            Err(e) => {
+                eprintln!("skipping: no fallback adapter: {}", e);
+                return;
+            }
+        };
+
+        let frame = reference_frame(37, 21);
+        let view = upload(&device, &queue, &frame);
+
+        for format in [
+            VideoFormat::Nv12,
+            VideoFormat::I420,
+            VideoFormat::I444,
+            VideoFormat::P010,
+        ] {
+            for space in [
+                ColorSpace::Bt601,
+                ColorSpace::Bt709,
+                ColorSpace::Srgb,
+                ColorSpace::Bt2100Pq,
+                ColorSpace::Bt2100Hlg,
+            ] {
+                for range in [ColorRange::Partial, ColorRange::Full] {
+                    let converter =
+                        Converter::new(&device, frame.width, frame.height, format, space, range)
+                            .unwrap();
+                    let gpu = converter.convert(&device, &queue, &view).unwrap();
+                    let cpu = convert_rgba(&frame, format, space, range).unwrap();
+                    assert_eq!(gpu.len(), cpu.data.len(), "{:?} {:?}", format, space);
+
+                    let samples = if format == VideoFormat::P010 {
+                        gpu.len() / 2
+                    } else {
+                        gpu.len()
+                    };
+                    for i in 0..samples {
+                        let (g, c) = (sample(&gpu, format, i), sample(&cpu.data, format, i));
+                        assert!(
+                            (g - c).abs() <= 1,
+                            "{:?} {:?} {:?} sample {}: gpu {} cpu {}",
+                            format,
+                            space,
+                            range,
+                            i,
+                            g,
+                            c
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_formats_are_rejected() {
+        let Ok((device, _)) = create_headless_device(true) else {
+            return;
+        };
+
+        for format in [VideoFormat::Rgba, VideoFormat::Yuyv] {
+            assert!(Converter::new(
+                &device,
+                16,
+                16,
+                format,
+                ColorSpace::Bt709,
+                ColorRange::Partial
+            )
+            .is_err());
+        }
+    }
+}
@@ -1,3 +1,11 @@
+pub mod cache;
+pub mod canvas;
+pub mod compositor;
 pub mod context;
+pub mod convert;
 pub mod device;
+pub mod multiview;
+pub mod readback;
+pub mod scaler;
 pub mod texture;
+pub mod transition;
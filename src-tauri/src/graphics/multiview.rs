@@ -0,0 +1,820 @@
+/// 多视图模块
+///
+/// 把预览、节目和多个场景的缩略图按网格布局绘制到同一画面，
+/// 带有场景名称标签和指示预览、节目场景的边框，可以绘制到窗口或离屏纹理
+use std::{borrow::Cow, collections::HashMap, num::NonZeroU64};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, BlendState, Buffer, BufferBinding,
+    BufferBindingType, BufferDescriptor, BufferUsages, ColorTargetState, ColorWrites,
+    CommandEncoder, Device, FragmentState, MultisampleState, PipelineLayoutDescriptor,
+    PrimitiveState, PrimitiveTopology, Queue, RenderPipeline, RenderPipelineDescriptor,
+    SamplerBindingType, ShaderModuleDescriptor, ShaderSource, ShaderStages, TextureFormat,
+    TextureSampleType, TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::{
+    graphics::texture::Texture,
+    media::text::{render_text, TextAlign, TextStyle},
+    utils::{config::get_config, locale::t},
+    Result,
+};
+
+/// 多视图最多显示的场景数
+pub const MAX_MULTIVIEW_SCENES: usize = 24;
+
+/// 每帧最多的绘制次数，每个格子最多需要内容、标签背景、标签和边框四次绘制
+const MAX_DRAWS: usize = (MAX_MULTIVIEW_SCENES + 2) * 4;
+
+/// 普通格子的边框颜色
+const OUTLINE_COLOR: [f32; 4] = [0.3, 0.3, 0.3, 1.];
+
+/// 预览场景的边框颜色
+const PREVIEW_COLOR: [f32; 4] = [0., 0.8, 0.2, 1.];
+
+/// 节目场景的边框颜色
+const PROGRAM_COLOR: [f32; 4] = [0.85, 0., 0., 1.];
+
+/// 标签背景颜色
+const LABEL_BACKGROUND: [f32; 4] = [0., 0., 0., 0.6];
+
+/// 多视图布局，编号与 OBS 全局配置中的 `MultiviewLayout` 一致
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MultiviewLayout {
+    /// 预览和节目在上，8 个场景在下
+    #[default]
+    HorizontalTop8,
+    /// 8 个场景在上，预览和节目在下
+    HorizontalBottom8,
+    /// 8 个场景在左，预览和节目在右
+    VerticalLeft8,
+    /// 预览和节目在左，8 个场景在右
+    VerticalRight8,
+    /// 预览和节目在上，24 个场景在下
+    HorizontalTop24,
+    /// 预览和节目在上，18 个场景在下
+    HorizontalTop18,
+    /// 只显示 4 个场景
+    Scenes4,
+    /// 只显示 9 个场景
+    Scenes9,
+    /// 只显示 16 个场景
+    Scenes16,
+}
+
+impl MultiviewLayout {
+    /// 从配置中的编号解析
+    pub fn from_id(id: u32) -> Option<Self> {
+        match id {
+            0 => Some(Self::HorizontalTop8),
+            1 => Some(Self::HorizontalBottom8),
+            2 => Some(Self::VerticalLeft8),
+            3 => Some(Self::VerticalRight8),
+            4 => Some(Self::HorizontalTop24),
+            5 => Some(Self::HorizontalTop18),
+            6 => Some(Self::Scenes4),
+            7 => Some(Self::Scenes9),
+            8 => Some(Self::Scenes16),
+            _ => None,
+        }
+    }
+
+    /// 布局名称的翻译键
+    pub fn locale_key(&self) -> &'static str {
+        match self {
+            Self::HorizontalTop8 => "Basic.Settings.General.MultiviewLayout.Horizontal.Top",
+            Self::HorizontalBottom8 => "Basic.Settings.General.MultiviewLayout.Horizontal.Bottom",
+            Self::VerticalLeft8 => "Basic.Settings.General.MultiviewLayout.Vertical.Left",
+            Self::VerticalRight8 => "Basic.Settings.General.MultiviewLayout.Vertical.Right",
+            Self::HorizontalTop24 => {
+                "Basic.Settings.General.MultiviewLayout.Horizontal.Extended.Top"
+            }
+            Self::HorizontalTop18 => {
+                "Basic.Settings.General.MultiviewLayout.Horizontal.18Scene.Top"
+            }
+            Self::Scenes4 => "Basic.Settings.General.MultiviewLayout.4Scene",
+            Self::Scenes9 => "Basic.Settings.General.MultiviewLayout.9Scene",
+            Self::Scenes16 => "Basic.Settings.General.MultiviewLayout.16Scene",
+        }
+    }
+
+    /// 布局能显示的场景数
+    pub fn scene_count(&self) -> usize {
+        match self {
+            Self::HorizontalTop24 => 24,
+            Self::HorizontalTop18 => 18,
+            Self::Scenes4 => 4,
+            Self::Scenes9 => 9,
+            Self::Scenes16 => 16,
+            _ => 8,
+        }
+    }
+
+    /// 计算各格子的位置
+    ///
+    /// # 参数
+    ///
+    /// * `width` - 画面宽度
+    /// * `height` - 画面高度
+    pub fn cells(&self, width: f32, height: f32) -> MultiviewCells {
+        let rect = |x: f32, y: f32, w: f32, h: f32| Rect {
+            x: x * width,
+            y: y * height,
+            width: w * width,
+            height: h * height,
+        };
+        // 场景按行排列，从 (x, y) 开始，每行 columns 个
+        let grid = |count: usize, columns: usize, x: f32, y: f32, w: f32, h: f32| {
+            (0..count)
+                .map(|i| {
+                    let (column, row) = ((i % columns) as f32, (i / columns) as f32);
+                    rect(x + column * w, y + row * h, w, h)
+                })
+                .collect()
+        };
+
+        match self {
+            Self::HorizontalTop8 => MultiviewCells {
+                preview: Some(rect(0., 0., 0.5, 0.5)),
+                program: Some(rect(0.5, 0., 0.5, 0.5)),
+                scenes: grid(8, 4, 0., 0.5, 0.25, 0.25),
+            },
+            Self::HorizontalBottom8 => MultiviewCells {
+                preview: Some(rect(0., 0.5, 0.5, 0.5)),
+                program: Some(rect(0.5, 0.5, 0.5, 0.5)),
+                scenes: grid(8, 4, 0., 0., 0.25, 0.25),
+            },
+            Self::VerticalLeft8 => MultiviewCells {
+                preview: Some(rect(0.5, 0., 0.5, 0.5)),
+                program: Some(rect(0.5, 0.5, 0.5, 0.5)),
+                scenes: grid(8, 2, 0., 0., 0.25, 0.25),
+            },
+            Self::VerticalRight8 => MultiviewCells {
+                preview: Some(rect(0., 0., 0.5, 0.5)),
+                program: Some(rect(0., 0.5, 0.5, 0.5)),
+                scenes: grid(8, 2, 0.5, 0., 0.25, 0.25),
+            },
+            Self::HorizontalTop24 => MultiviewCells {
+                preview: Some(rect(1. / 6., 0., 1. / 3., 1. / 3.)),
+                program: Some(rect(0.5, 0., 1. / 3., 1. / 3.)),
+                scenes: grid(24, 6, 0., 1. / 3., 1. / 6., 1. / 6.),
+            },
+            Self::HorizontalTop18 => MultiviewCells {
+                preview: Some(rect(0., 0., 0.5, 0.5)),
+                program: Some(rect(0.5, 0., 0.5, 0.5)),
+                scenes: grid(18, 6, 0., 0.5, 1. / 6., 1. / 6.),
+            },
+            Self::Scenes4 | Self::Scenes9 | Self::Scenes16 => {
+                let columns = (self.scene_count() as f32).sqrt() as usize;
+                let size = 1. / columns as f32;
+                MultiviewCells {
+                    preview: None,
+                    program: None,
+                    scenes: grid(self.scene_count(), columns, 0., 0., size, size),
+                }
+            }
+        }
+    }
+}
+
+/// 多视图设置，保存在全局配置的 `[BasicWindow]` 节
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultiviewSettings {
+    pub layout: MultiviewLayout,
+    /// 是否显示场景名称
+    pub draw_names: bool,
+}
+
+impl Default for MultiviewSettings {
+    fn default() -> Self {
+        Self {
+            layout: MultiviewLayout::default(),
+            draw_names: true,
+        }
+    }
+}
+
+impl MultiviewSettings {
+    /// 从全局配置读取多视图设置
+    pub fn load() -> Self {
+        let layout = get_config("BasicWindow", "MultiviewLayout")
+            .and_then(|value| value.parse::<u32>().ok())
+            .and_then(MultiviewLayout::from_id)
+            .unwrap_or_default();
+        let draw_names =
+            get_config("BasicWindow", "MultiviewDrawNames").is_none_or(|value| value != "false");
+
+        Self { layout, draw_names }
+    }
+}
+
+/// 矩形区域（像素）
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    /// 点是否在矩形内
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// 向内收缩
+    fn inset(&self, amount: f32) -> Self {
+        let amount = amount.min(self.width / 2.).min(self.height / 2.);
+        Self {
+            x: self.x + amount,
+            y: self.y + amount,
+            width: self.width - amount * 2.,
+            height: self.height - amount * 2.,
+        }
+    }
+
+    /// 保持宽高比缩放内容并居中放入矩形
+    fn fit(&self, width: f32, height: f32) -> Self {
+        let scale = (self.width / width).min(self.height / height);
+        let (fit_width, fit_height) = (width * scale, height * scale);
+        Self {
+            x: self.x + (self.width - fit_width) / 2.,
+            y: self.y + (self.height - fit_height) / 2.,
+            width: fit_width,
+            height: fit_height,
+        }
+    }
+}
+
+/// 多视图各格子的位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiviewCells {
+    pub preview: Option<Rect>,
+    pub program: Option<Rect>,
+    pub scenes: Vec<Rect>,
+}
+
+impl MultiviewCells {
+    /// 查找点所在的格子
+    ///
+    /// # 参数
+    ///
+    /// * `x` - 横坐标
+    /// * `y` - 纵坐标
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<MultiviewHit> {
+        if self.preview.is_some_and(|rect| rect.contains(x, y)) {
+            return Some(MultiviewHit::Preview);
+        }
+        if self.program.is_some_and(|rect| rect.contains(x, y)) {
+            return Some(MultiviewHit::Program);
+        }
+        self.scenes
+            .iter()
+            .position(|rect| rect.contains(x, y))
+            .map(MultiviewHit::Scene)
+    }
+}
+
+/// 多视图中点击的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiviewHit {
+    Preview,
+    Program,
+    /// 场景格子的序号
+    Scene(usize),
+}
+
+/// 多视图中的一个场景
+pub struct MultiviewScene<'a> {
+    pub uuid: &'a str,
+    pub name: &'a str,
+    /// 场景画面，没有时只绘制边框和名称
+    pub texture: Option<&'a Texture>,
+}
+
+/// 绘制一帧多视图需要的内容
+#[derive(Default)]
+pub struct MultiviewFrame<'a> {
+    pub preview: Option<&'a Texture>,
+    pub program: Option<&'a Texture>,
+    /// 预览场景的 UUID，对应的场景格子显示预览边框
+    pub preview_scene: Option<&'a str>,
+    /// 节目场景的 UUID，对应的场景格子显示节目边框
+    pub program_scene: Option<&'a str>,
+    pub scenes: &'a [MultiviewScene<'a>],
+}
+
+/// 着色器参数，布局与 `multiview.wgsl` 中的 `Params` 一致
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    rect: [f32; 4],
+    color: [f32; 4],
+    output: [f32; 4],
+}
+
+/// 绘制模式，与 `multiview.wgsl` 一致
+#[derive(Clone, Copy)]
+enum Mode {
+    Texture,
+    Fill,
+    Outline,
+}
+
+/// 一次绘制
+struct Draw<'a> {
+    rect: Rect,
+    color: [f32; 4],
+    mode: Mode,
+    texture: Option<&'a Texture>,
+}
+
+/// 在格子中按比例绘制纹理，四周留出边框的位置
+fn content(rect: Rect, texture: Option<&Texture>, border: f32) -> Option<Draw<'_>> {
+    texture.map(|texture| {
+        let (width, height) = (texture.texture.width(), texture.texture.height());
+        Draw {
+            rect: rect.inset(border).fit(width as f32, height as f32),
+            color: [1.; 4],
+            mode: Mode::Texture,
+            texture: Some(texture),
+        }
+    })
+}
+
+/// 多视图渲染器
+pub struct Multiview {
+    pub settings: MultiviewSettings,
+    pipeline: RenderPipeline,
+    texture_layout: BindGroupLayout,
+    uniform: Buffer,
+    uniform_bind_group: BindGroup,
+    /// 参数在缓冲区中的间隔，满足动态偏移的对齐要求
+    stride: u64,
+    /// 纯色和边框绘制时绑定的空白纹理
+    blank: Texture,
+    /// 标签纹理，按文字和字号缓存
+    labels: HashMap<(String, u32), Option<Texture>>,
+}
+
+impl Multiview {
+    /// 创建多视图渲染器
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `queue` - WGPU 队列
+    /// * `format` - 输出纹理格式
+    /// * `settings` - 多视图设置
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        format: TextureFormat,
+        settings: MultiviewSettings,
+    ) -> Result<Self> {
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let stride = (std::mem::size_of::<Params>() as u64).next_multiple_of(alignment);
+        let uniform = device.create_buffer(&BufferDescriptor {
+            label: Some("multiview params"),
+            size: stride * MAX_DRAWS as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("multiview"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("shaders/multiview.wgsl"))),
+        });
+
+        let uniform_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("multiview params"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX_FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: NonZeroU64::new(std::mem::size_of::<Params>() as u64),
+                },
+                count: None,
+            }],
+        });
+        let texture_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("multiview texture"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let uniform_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("multiview params"),
+            layout: &uniform_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::Buffer(BufferBinding {
+                    buffer: &uniform,
+                    offset: 0,
+                    size: NonZeroU64::new(std::mem::size_of::<Params>() as u64),
+                }),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("multiview"),
+            bind_group_layouts: &[&uniform_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("multiview"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let blank = Texture::from_rgba(
+            device,
+            queue,
+            &image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4])),
+            Some("multiview blank"),
+            false,
+        )?;
+
+        Ok(Self {
+            settings,
+            pipeline,
+            texture_layout,
+            uniform,
+            uniform_bind_group,
+            stride,
+            blank,
+            labels: HashMap::new(),
+        })
+    }
+
+    /// 查找点击位置所在的格子
+    ///
+    /// # 参数
+    ///
+    /// * `x` - 横坐标
+    /// * `y` - 纵坐标
+    /// * `width` - 画面宽度
+    /// * `height` - 画面高度
+    pub fn hit_test(&self, x: f32, y: f32, width: f32, height: f32) -> Option<MultiviewHit> {
+        self.settings.layout.cells(width, height).hit_test(x, y)
+    }
+
+    /// 获取标签纹理，文字为空或无法绘制时返回 `None`
+    fn label(&mut self, device: &Device, queue: &Queue, text: &str, size: u32) -> Option<&Texture> {
+        self.labels
+            .entry((text.to_string(), size))
+            .or_insert_with(|| {
+                let style = TextStyle {
+                    size: size as f32,
+                    align: TextAlign::Center,
+                    ..Default::default()
+                };
+                render_text(text, &style).and_then(|image| {
+                    Texture::from_rgba(device, queue, &image, Some("multiview label"), false).ok()
+                })
+            })
+            .as_ref()
+    }
+
+    /// 记录绘制多视图的命令
+    ///
+    /// 参数缓冲区在提交前写入，因此同一次提交中只能调用一次
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `queue` - WGPU 队列
+    /// * `encoder` - 命令编码器
+    /// * `frame` - 要绘制的内容
+    /// * `view` - 输出纹理视图，格式需要与创建时一致
+    /// * `size` - 输出尺寸
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        frame: &MultiviewFrame,
+        view: &TextureView,
+        size: (u32, u32),
+    ) {
+        let (width, height) = (size.0.max(1) as f32, size.1.max(1) as f32);
+        let cells = self.settings.layout.cells(width, height);
+        let border = (height / 270.).round().max(1.);
+        let label_size = (height / 40.).round().max(10.) as u32;
+
+        // 先准备标签纹理，之后只借用不可变的引用
+        let mut labels: Vec<(Rect, String)> = vec![];
+        if self.settings.draw_names {
+            if let Some(rect) = cells.preview {
+                labels.push((rect, t("StudioMode.Preview").unwrap_or_default()));
+            }
+            if let Some(rect) = cells.program {
+                labels.push((rect, t("StudioMode.Program").unwrap_or_default()));
+            }
+            for (rect, scene) in cells.scenes.iter().zip(frame.scenes) {
+                labels.push((*rect, scene.name.to_string()));
+            }
+        }
+        for (_, text) in &labels {
+            self.label(device, queue, text, label_size);
+        }
+        // 场景改名或画面尺寸变化后释放不再使用的标签
+        self.labels.retain(|(text, size), _| {
+            *size == label_size && labels.iter().any(|(_, label)| label == text)
+        });
+
+        let mut draws: Vec<Draw> = vec![];
+        let outline = |rect: Rect, color: [f32; 4]| Draw {
+            rect,
+            color,
+            mode: Mode::Outline,
+            texture: None,
+        };
+
+        if let Some(rect) = cells.preview {
+            draws.extend(content(rect, frame.preview, border));
+            draws.push(outline(rect, PREVIEW_COLOR));
+        }
+        if let Some(rect) = cells.program {
+            draws.extend(content(rect, frame.program, border));
+            draws.push(outline(rect, PROGRAM_COLOR));
+        }
+        for (i, rect) in cells.scenes.iter().enumerate() {
+            let scene = frame.scenes.get(i);
+            draws.extend(content(
+                *rect,
+                scene.and_then(|scene| scene.texture),
+                border,
+            ));
+            let uuid = scene.map(|scene| scene.uuid);
+            let color = if uuid.is_some() && uuid == frame.program_scene {
+                PROGRAM_COLOR
+            } else if uuid.is_some() && uuid == frame.preview_scene {
+                PREVIEW_COLOR
+            } else {
+                OUTLINE_COLOR
+            };
+            draws.push(outline(*rect, color));
+        }
+
+        for (rect, text) in &labels {
+            let Some(Some(texture)) = self.labels.get(&(text.clone(), label_size)) else {
+                continue;
+            };
+            let (w, h) = (
+                texture.texture.width() as f32,
+                texture.texture.height() as f32,
+            );
+            // 标签放在格子底部居中，过宽时缩小
+            let scale = ((rect.width - border * 4.) / w).clamp(0., 1.);
+            let (w, h) = (w * scale, h * scale);
+            let label = Rect {
+                x: rect.x + (rect.width - w) / 2.,
+                y: rect.y + rect.height - border * 2. - h,
+                width: w,
+                height: h,
+            };
+            let padding = label_size as f32 / 4.;
+            draws.push(Draw {
+                rect: Rect {
+                    x: label.x - padding,
+                    y: label.y,
+                    width: label.width + padding * 2.,
+                    height: label.height,
+                },
+                color: LABEL_BACKGROUND,
+                mode: Mode::Fill,
+                texture: None,
+            });
+            draws.push(Draw {
+                rect: label,
+                color: [1.; 4],
+                mode: Mode::Texture,
+                texture: Some(texture),
+            });
+        }
+        draws.truncate(MAX_DRAWS);
+
+        let mut params = vec![0u8; self.stride as usize * draws.len()];
+        for (i, draw) in draws.iter().enumerate() {
+            let mode = match draw.mode {
+                Mode::Texture => 0.,
+                Mode::Fill => 1.,
+                Mode::Outline => 2.,
+            };
+            let value = Params {
+                rect: [draw.rect.x, draw.rect.y, draw.rect.width, draw.rect.height],
+                color: draw.color,
+                output: [width, height, mode, border],
+            };
+            let offset = i * self.stride as usize;
+            params[offset..offset + std::mem::size_of::<Params>()]
+                .copy_from_slice(bytemuck::bytes_of(&value));
+        }
+        if !params.is_empty() {
+            queue.write_buffer(&self.uniform, 0, &params);
+        }
+
+        let bind_groups: Vec<BindGroup> = draws
+            .iter()
+            .map(|draw| {
+                let texture = draw.texture.unwrap_or(&self.blank);
+                device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("multiview texture"),
+                    layout: &self.texture_layout,
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: BindingResource::TextureView(&texture.view),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: BindingResource::Sampler(&texture.sampler),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("multiview"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        for (i, bind_group) in bind_groups.iter().enumerate() {
+            let offset = (i as u64 * self.stride) as u32;
+            render_pass.set_bind_group(0, &self.uniform_bind_group, &[offset]);
+            render_pass.set_bind_group(1, bind_group, &[]);
+            render_pass.draw(0..4, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUTS: [MultiviewLayout; 9] = [
+        MultiviewLayout::HorizontalTop8,
+        MultiviewLayout::HorizontalBottom8,
+        MultiviewLayout::VerticalLeft8,
+        MultiviewLayout::VerticalRight8,
+        MultiviewLayout::HorizontalTop24,
+        MultiviewLayout::HorizontalTop18,
+        MultiviewLayout::Scenes4,
+        MultiviewLayout::Scenes9,
+        MultiviewLayout::Scenes16,
+    ];
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn overlaps(a: &Rect, b: &Rect) -> bool {
+        let eps = 1e-3;
+        a.x + eps < b.x + b.width
+            && b.x + eps < a.x + a.width
+            && a.y + eps < b.y + b.height
+            && b.y + eps < a.y + a.height
+    }
+
+    #[test]
+    fn layout_ids_round_trip() {
+        for (id, layout) in LAYOUTS.iter().enumerate() {
+            assert_eq!(MultiviewLayout::from_id(id as u32), Some(*layout));
+        }
+        assert_eq!(MultiviewLayout::from_id(9), None);
+    }
+
+    #[test]
+    fn cells_fill_the_frame_without_overlapping() {
+        let (width, height) = (1920., 1080.);
+        for layout in LAYOUTS {
+            let cells = layout.cells(width, height);
+            assert_eq!(cells.scenes.len(), layout.scene_count(), "{:?}", layout);
+
+            let all: Vec<Rect> = cells
+                .preview
+                .into_iter()
+                .chain(cells.program)
+                .chain(cells.scenes.iter().copied())
+                .collect();
+            for (i, a) in all.iter().enumerate() {
+                assert!(
+                    a.x >= -1e-3
+                        && a.y >= -1e-3
+                        && a.x + a.width <= width + 1e-3
+                        && a.y + a.height <= height + 1e-3,
+                    "{:?} cell {} out of frame: {:?}",
+                    layout,
+                    i,
+                    a
+                );
+                for b in &all[i + 1..] {
+                    assert!(!overlaps(a, b), "{:?}: {:?} overlaps {:?}", layout, a, b);
+                }
+            }
+
+            // 除了 24 个场景的布局在预览和节目两侧留空，格子铺满整个画面
+            let area: f32 = all.iter().map(|rect| rect.width * rect.height).sum();
+            if layout != MultiviewLayout::HorizontalTop24 {
+                assert!((area - width * height).abs() < 1., "{:?}", layout);
+            }
+        }
+    }
+
+    #[test]
+    fn cells_follow_the_layout() {
+        let cells = MultiviewLayout::HorizontalTop8.cells(800., 600.);
+        assert_eq!(cells.preview, Some(rect(0., 0., 400., 300.)));
+        assert_eq!(cells.program, Some(rect(400., 0., 400., 300.)));
+        assert_eq!(cells.scenes[0], rect(0., 300., 200., 150.));
+        assert_eq!(cells.scenes[5], rect(200., 450., 200., 150.));
+
+        let cells = MultiviewLayout::VerticalLeft8.cells(800., 600.);
+        assert_eq!(cells.preview, Some(rect(400., 0., 400., 300.)));
+        assert_eq!(cells.scenes[1], rect(200., 0., 200., 150.));
+        assert_eq!(cells.scenes[2], rect(0., 150., 200., 150.));
+
+        let cells = MultiviewLayout::Scenes9.cells(900., 600.);
+        assert_eq!(cells.preview, None);
+        assert_eq!(cells.program, None);
+        assert_eq!(cells.scenes[4], rect(300., 200., 300., 200.));
+    }
+
+    #[test]
+    fn hit_test_finds_the_cell() {
+        let cells = MultiviewLayout::HorizontalTop8.cells(800., 600.);
+        assert_eq!(cells.hit_test(10., 10.), Some(MultiviewHit::Preview));
+        assert_eq!(cells.hit_test(799., 299.), Some(MultiviewHit::Program));
+        assert_eq!(cells.hit_test(0., 300.), Some(MultiviewHit::Scene(0)));
+        assert_eq!(cells.hit_test(650., 500.), Some(MultiviewHit::Scene(7)));
+        // 右边和下边不属于格子
+        assert_eq!(cells.hit_test(400., 10.), Some(MultiviewHit::Program));
+        assert_eq!(cells.hit_test(800., 10.), None);
+        assert_eq!(cells.hit_test(10., 600.), None);
+        assert_eq!(cells.hit_test(-1., 10.), None);
+
+        let cells = MultiviewLayout::Scenes4.cells(800., 600.);
+        assert_eq!(cells.hit_test(10., 10.), Some(MultiviewHit::Scene(0)));
+        assert_eq!(cells.hit_test(410., 310.), Some(MultiviewHit::Scene(3)));
+    }
+}
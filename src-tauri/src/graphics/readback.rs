@@ -0,0 +1,163 @@
+/// 异步回读模块
+///
+/// 使用多个暂存缓冲区轮转，GPU 复制完成后再映射读取，避免每帧阻塞等待 GPU
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use wgpu::{Buffer, BufferDescriptor, BufferUsages, CommandEncoder, Device, MapMode};
+
+/// 暂存缓冲区状态：空闲
+const IDLE: u8 = 0;
+/// 暂存缓冲区状态：等待映射
+const PENDING: u8 = 1;
+/// 暂存缓冲区状态：已映射
+const MAPPED: u8 = 2;
+/// 暂存缓冲区状态：映射失败
+const FAILED: u8 = 3;
+
+/// 暂存缓冲区
+struct Staging {
+    buffer: Buffer,
+    state: Arc<AtomicU8>,
+    /// 数据对应的时间戳
+    timestamp: u64,
+    /// 是否已调用 `map_async`
+    requested: bool,
+}
+
+/// 异步回读队列
+pub struct Readback {
+    size: u64,
+    /// 空闲的暂存缓冲区
+    free: Vec<Staging>,
+    /// 按提交顺序排列的等待中的暂存缓冲区
+    pending: VecDeque<Staging>,
+}
+
+impl Readback {
+    /// 创建异步回读队列
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `size` - 每次回读的字节数
+    /// * `depth` - 暂存缓冲区数量，即最多同时等待的帧数
+    pub fn new(device: &Device, size: u64, depth: usize) -> Self {
+        let free = (0..depth.max(1))
+            .map(|_| Staging {
+                buffer: device.create_buffer(&BufferDescriptor {
+                    label: Some("staging"),
+                    size,
+                    usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                state: Arc::new(AtomicU8::new(IDLE)),
+                timestamp: 0,
+                requested: false,
+            })
+            .collect();
+
+        Self {
+            size,
+            free,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// 将源缓冲区复制到空闲的暂存缓冲区
+    ///
+    /// # 参数
+    ///
+    /// * `encoder` - 命令编码器
+    /// * `source` - 源缓冲区，需要 `COPY_SRC` 用途
+    /// * `timestamp` - 数据对应的时间戳
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `bool`，没有空闲的暂存缓冲区时返回 `false`，这一帧被丢弃
+    pub fn enqueue(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        source: &Buffer,
+        timestamp: u64,
+    ) -> bool {
+        let Some(mut staging) = self.free.pop() else {
+            return false;
+        };
+
+        encoder.copy_buffer_to_buffer(source, 0, &staging.buffer, 0, self.size);
+        staging.state.store(PENDING, Ordering::Release);
+        staging.timestamp = timestamp;
+        staging.requested = false;
+        self.pending.push_back(staging);
+
+        true
+    }
+
+    /// 请求映射已提交的暂存缓冲区，必须在 `queue.submit` 之后调用
+    pub fn map_submitted(&mut self) {
+        for staging in self.pending.iter_mut().filter(|staging| !staging.requested) {
+            let state = staging.state.clone();
+            staging
+                .buffer
+                .slice(..)
+                .map_async(MapMode::Read, move |result| {
+                    let value = if result.is_ok() { MAPPED } else { FAILED };
+                    state.store(value, Ordering::Release);
+                });
+            staging.requested = true;
+        }
+    }
+
+    /// 等待中的回读数量
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 按提交顺序取出最早完成的回读数据
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `wait` - 为 `true` 时阻塞等待最早的回读完成
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Option<(u64, Vec<u8>)>`，即时间戳和数据，最早的回读尚未完成时返回 `None`
+    pub fn read(&mut self, device: &Device, wait: bool) -> Option<(u64, Vec<u8>)> {
+        let front = self.pending.front()?;
+        if !front.requested {
+            return None;
+        }
+
+        device.poll(if wait {
+            wgpu::Maintain::Wait
+        } else {
+            wgpu::Maintain::Poll
+        });
+
+        let state = front.state.load(Ordering::Acquire);
+        if state != MAPPED && state != FAILED {
+            return None;
+        }
+
+        let staging = self.pending.pop_front()?;
+        let data = if state == MAPPED {
+            let data = staging.buffer.slice(..).get_mapped_range().to_vec();
+            staging.buffer.unmap();
+            Some((staging.timestamp, data))
+        } else {
+            None
+        };
+
+        staging.state.store(IDLE, Ordering::Release);
+        self.free.push(staging);
+
+        data
+    }
+}
@@ -0,0 +1,360 @@
+/// GPU 输出缩放模块
+///
+/// 将画布缩放到输出分辨率，结果保存在缩放器自己的纹理中，供格式转换或回读使用
+use std::borrow::Cow;
+
+use anyhow::anyhow;
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferUsages, CommandEncoder,
+    Device, Extent3d, FragmentState, MultisampleState, PipelineLayoutDescriptor, PrimitiveState,
+    RenderPipeline, RenderPipelineDescriptor, ShaderModuleDescriptor, ShaderSource, ShaderStages,
+    Texture, TextureDescriptor, TextureDimension, TextureSampleType, TextureUsages, TextureView,
+    TextureViewDimension, VertexState,
+};
+
+use crate::{graphics::canvas::CANVAS_FORMAT, media::scale::ScaleFilter, Result};
+
+/// 着色器参数，布局与 `scale.wgsl` 中的 `Params` 一致
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    source: [f32; 4],
+    mode: [u32; 4],
+}
+
+/// GPU 缩放器
+pub struct Scaler {
+    pub filter: ScaleFilter,
+    /// 缩放结果
+    pub texture: Texture,
+    pub view: TextureView,
+    pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    uniform: Buffer,
+}
+
+impl Scaler {
+    /// 创建缩放器
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `source` - 源尺寸
+    /// * `target` - 目标尺寸
+    /// * `filter` - 缩放滤镜
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，尺寸无效时返回错误
+    pub fn new(
+        device: &Device,
+        source: (u32, u32),
+        target: (u32, u32),
+        filter: ScaleFilter,
+    ) -> Result<Self> {
+        if source.0 == 0 || source.1 == 0 || target.0 == 0 || target.1 == 0 {
+            return Err(anyhow!(
+                "invalid scale size: {}x{} -> {}x{}",
+                source.0,
+                source.1,
+                target.0,
+                target.1
+            ));
+        }
+
+        let params = Params {
+            source: [
+                source.0 as f32,
+                source.1 as f32,
+                source.0 as f32 / target.0 as f32,
+                source.1 as f32 / target.1 as f32,
+            ],
+            mode: [filter.id(), 0, 0, 0],
+        };
+        let uniform = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("scale params"),
+            contents: bytemuck::bytes_of(&params),
+            usage: BufferUsages::UNIFORM,
+        });
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("scaled"),
+            size: Extent3d {
+                width: target.0,
+                height: target.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: CANVAS_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("scale"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("shaders/scale.wgsl"))),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("scale"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: false },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("scale"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("scale"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(CANVAS_FORMAT.into())],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Ok(Self {
+            filter,
+            texture,
+            view,
+            pipeline,
+            bind_group_layout,
+            uniform,
+        })
+    }
+
+    /// 目标宽度
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    /// 目标高度
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+
+    /// 记录缩放命令
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `encoder` - 命令编码器
+    /// * `source` - 源纹理视图，尺寸需与创建时一致
+    pub fn encode(&self, device: &Device, encoder: &mut CommandEncoder, source: &TextureView) {
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("scale"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: self.uniform.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("scale"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::{
+        CommandEncoderDescriptor, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, TextureAspect,
+    };
+
+    use super::*;
+    use crate::{
+        graphics::canvas::{create_headless_device, read_texture},
+        media::{
+            frame::{VideoFormat, VideoFrame},
+            scale::scale_rgba,
+        },
+    };
+
+    /// 参考帧：棋盘格叠加渐变，带半透明的 alpha
+    fn reference_frame(width: u32, height: u32) -> VideoFrame {
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let checker = if (x / 3 + y / 2) % 2 == 0 { 230 } else { 20 };
+                data.extend_from_slice(&[
+                    checker,
+                    (x * 255 / (width - 1)) as u8,
+                    (y * 255 / (height - 1)) as u8,
+                    (128 + (x + y) % 128) as u8,
+                ]);
+            }
+        }
+
+        VideoFrame {
+            width,
+            height,
+            format: VideoFormat::Rgba,
+            timestamp: 0,
+            data,
+        }
+    }
+
+    /// 将帧上传为画布格式的纹理
+    fn upload(device: &Device, queue: &Queue, frame: &VideoFrame) -> TextureView {
+        let size = Extent3d {
+            width: frame.width,
+            height: frame.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("reference"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: CANVAS_FORMAT,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &frame.data,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(frame.width * 4),
+                rows_per_image: Some(frame.height),
+            },
+            size,
+        );
+
+        texture.create_view(&Default::default())
+    }
+
+    #[test]
+    fn gpu_scaling_matches_cpu_reference() {
+        let (device, queue) = match create_headless_device(true) {
+            Ok(device) => device,
+            Err(e) => {
+                eprintln!("skipping: no fallback adapter: {}", e);
+                return;
+            }
+        };
+
+        // 缩小、放大以及两个方向比例不同的情况
+        for (source, target) in [
+            ((48, 30), (20, 13)),
+            ((17, 11), (40, 27)),
+            ((32, 9), (11, 20)),
+        ] {
+            let frame = reference_frame(source.0, source.1);
+            let view = upload(&device, &queue, &frame);
+
+            for filter in [
+                ScaleFilter::Point,
+                ScaleFilter::Bilinear,
+                ScaleFilter::Bicubic,
+                ScaleFilter::Lanczos,
+                ScaleFilter::Area,
+            ] {
+                let scaler = Scaler::new(&device, source, target, filter).unwrap();
+                let mut encoder =
+                    device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+                scaler.encode(&device, &mut encoder, &view);
+                queue.submit(std::iter::once(encoder.finish()));
+
+                let gpu = read_texture(&device, &queue, &scaler.texture, 4).unwrap();
+                let cpu = scale_rgba(&frame, target.0, target.1, filter).unwrap();
+                assert_eq!(gpu.len(), cpu.data.len());
+
+                for (i, (g, c)) in gpu.iter().zip(&cpu.data).enumerate() {
+                    assert!(
+                        (*g as i32 - *c as i32).abs() <= 1,
+                        "{:?} {:?} -> {:?} pixel {} channel {}: gpu {} cpu {}",
+                        filter,
+                        source,
+                        target,
+                        i / 4,
+                        i % 4,
+                        g,
+                        c
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zero_sizes_are_rejected() {
+        let Ok((device, _)) = create_headless_device(true) else {
+            return;
+        };
+
+        assert!(Scaler::new(&device, (0, 10), (10, 10), ScaleFilter::Bilinear).is_err());
+        assert!(Scaler::new(&device, (10, 10), (10, 0), ScaleFilter::Bilinear).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+// 把来源纹理按场景项的变换绘制到画布上
+//
+// 来源纹理为 sRGB 格式，采样得到线性值；画布为 Unorm 格式，保存编码后的值，
+// 因此输出到画布前手动做 sRGB 编码，输出到 sRGB 纹理时由 GPU 编码
+
+struct Params {
+    // 左上角 x, y, 右上角 x, y（画布像素）
+    top: vec4<f32>,
+    // 左下角 x, y, 右下角 x, y（画布像素）
+    bottom: vec4<f32>,
+    // 纹理坐标 左, 上, 右, 下
+    uv: vec4<f32>,
+    // 画布宽度, 高度, 是否手动编码 sRGB, 未使用
+    canvas: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var linear_sampler: sampler;
+@group(0) @binding(2) var<uniform> params: Params;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // 三角形带的顶点顺序：左上, 右上, 左下, 右下
+    var corners = array<vec2<f32>, 4>(params.top.xy, params.top.zw, params.bottom.xy, params.bottom.zw);
+    let corner = corners[index];
+    let u = select(params.uv.x, params.uv.z, (index & 1u) == 1u);
+    let v = select(params.uv.y, params.uv.w, index >= 2u);
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(
+        corner.x / params.canvas.x * 2.0 - 1.0,
+        1.0 - corner.y / params.canvas.y * 2.0,
+        0.0,
+        1.0,
+    );
+    out.uv = vec2<f32>(u, v);
+    return out;
+}
+
+fn encode_srgb(linear: vec3<f32>) -> vec3<f32> {
+    let low = linear * 12.92;
+    let high = 1.055 * pow(linear, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, linear <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source_texture, linear_sampler, in.uv);
+    let rgb = select(color.rgb, encode_srgb(color.rgb), params.canvas.z > 0.5);
+    return vec4<f32>(rgb, color.a);
+}
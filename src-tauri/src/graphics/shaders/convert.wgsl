@@ -0,0 +1,140 @@
+// RGBA 画布转换为 YUV 平面
+//
+// 每个调用写出一个 u32：8 位格式包含 4 个采样，16 位格式包含 2 个采样
+
+struct Params {
+    // 源宽度, 源高度, 每行采样数, 行数
+    size: vec4<u32>,
+    // 平面类型(0=Y 1=U 2=V 3=UV 交错), 水平下采样, 垂直下采样, 位深
+    plane: vec4<u32>,
+    // 平面起始字偏移, 每行字数, 传递函数(0=无 1=PQ 2=HLG), 未使用
+    offsets: vec4<u32>,
+    // Kr, Kb, SDR 白色亮度, 码值上限
+    coef: vec4<f32>,
+    // Y 缩放, Y 偏移, UV 缩放, UV 偏移
+    range: vec4<f32>,
+}
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> output: array<u32>;
+
+fn srgb_to_linear(v: vec3<f32>) -> vec3<f32> {
+    return select(pow((v + 0.055) / 1.055, vec3<f32>(2.4)), v / 12.92, v <= vec3<f32>(0.04045));
+}
+
+fn linear_to_pq(v: vec3<f32>) -> vec3<f32> {
+    let m1 = 0.1593017578125;
+    let m2 = 78.84375;
+    let c1 = 0.8359375;
+    let c2 = 18.8515625;
+    let c3 = 18.6875;
+
+    let l = pow(max(v, vec3<f32>(0.0)), vec3<f32>(m1));
+    return pow((c1 + c2 * l) / (1.0 + c3 * l), vec3<f32>(m2));
+}
+
+fn linear_to_hlg(v: vec3<f32>) -> vec3<f32> {
+    let a = 0.17883277;
+    let b = 0.28466892;
+    let c = 0.55991073;
+
+    let x = max(v, vec3<f32>(0.0));
+    return select(a * log(12.0 * x - b) + c, sqrt(3.0 * x), x <= vec3<f32>(1.0 / 12.0));
+}
+
+fn encode_transfer(rgb: vec3<f32>) -> vec3<f32> {
+    let transfer = params.offsets.z;
+    if transfer == 0u {
+        return rgb;
+    }
+
+    let l = srgb_to_linear(rgb);
+    let linear = vec3<f32>(
+        0.6274 * l.r + 0.3293 * l.g + 0.0433 * l.b,
+        0.0691 * l.r + 0.9195 * l.g + 0.0114 * l.b,
+        0.0164 * l.r + 0.0880 * l.g + 0.8956 * l.b,
+    );
+    let sdr_white = params.coef.z;
+
+    if transfer == 1u {
+        return linear_to_pq(linear * sdr_white / 10000.0);
+    }
+
+    let display = linear * sdr_white / 1000.0;
+    let luma = dot(display, vec3<f32>(0.2627, 0.6780, 0.0593));
+    let gain = select(0.0, pow(luma, -0.2 / 1.2), luma > 0.0);
+    return linear_to_hlg(display * gain);
+}
+
+fn average_rgb(x: u32, y: u32) -> vec3<f32> {
+    let sx = params.plane.y;
+    let sy = params.plane.z;
+    let max_x = params.size.x - 1u;
+    let max_y = params.size.y - 1u;
+
+    var sum = vec3<f32>(0.0);
+    for (var dy = 0u; dy < sy; dy++) {
+        for (var dx = 0u; dx < sx; dx++) {
+            let coord = vec2<u32>(min(x * sx + dx, max_x), min(y * sy + dy, max_y));
+            // 与 CPU 参考实现一致，先量化到 8 位
+            sum += round(textureLoad(source, coord, 0).rgb * 255.0) / 255.0;
+        }
+    }
+
+    return sum / f32(sx * sy);
+}
+
+fn yuv_sample(x: u32, y: u32, component: u32) -> u32 {
+    let rgb = encode_transfer(average_rgb(x, y));
+    let kr = params.coef.x;
+    let kb = params.coef.y;
+
+    let luma = kr * rgb.r + (1.0 - kr - kb) * rgb.g + kb * rgb.b;
+    var value: f32;
+    if component == 0u {
+        value = params.range.y + params.range.x * luma;
+    } else if component == 1u {
+        value = params.range.w + params.range.z * (rgb.b - luma) / (2.0 * (1.0 - kb));
+    } else {
+        value = params.range.w + params.range.z * (rgb.r - luma) / (2.0 * (1.0 - kr));
+    }
+
+    return u32(clamp(round(value), 0.0, params.coef.w));
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let words_per_row = params.offsets.y;
+    if id.x >= words_per_row || id.y >= params.size.w {
+        return;
+    }
+
+    let kind = params.plane.x;
+    let bits = params.plane.w;
+    let per_word = select(4u, 2u, bits > 8u);
+
+    var word = 0u;
+    for (var i = 0u; i < per_word; i++) {
+        let s = id.x * per_word + i;
+        if s >= params.size.z {
+            break;
+        }
+
+        var x = s;
+        var component = kind;
+        if kind == 3u {
+            x = s / 2u;
+            component = 1u + s % 2u;
+        }
+
+        var value = yuv_sample(x, id.y, component);
+        if bits > 8u {
+            // 16 位存储时数据放在高位
+            value = value << (16u - bits);
+        }
+        word |= value << (i * (32u / per_word));
+    }
+
+    output[params.offsets.x + id.y * words_per_row + id.x] = word;
+}
@@ -0,0 +1,59 @@
+// 多视图绘制
+//
+// 每次绘制一个矩形，可以是缩放后的纹理、纯色或矩形边框
+
+struct Params {
+    // 矩形左上角和尺寸（像素）
+    rect: vec4<f32>,
+    // 纯色和边框的颜色，绘制纹理时作为乘数
+    color: vec4<f32>,
+    // 输出宽度, 输出高度, 模式(0=纹理 1=纯色 2=边框), 边框宽度
+    output: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(1) @binding(0) var image: texture_2d<f32>;
+@group(1) @binding(1) var image_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // 三角形带的四个角
+    let corner = vec2<f32>(f32(index & 1u), f32((index >> 1u) & 1u));
+    let pixel = params.rect.xy + corner * params.rect.zw;
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(
+        pixel.x / params.output.x * 2.0 - 1.0,
+        1.0 - pixel.y / params.output.y * 2.0,
+        0.0,
+        1.0,
+    );
+    out.uv = corner;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // 在统一控制流中采样
+    let sampled = textureSample(image, image_sampler, in.uv);
+    let mode = u32(params.output.z);
+
+    if mode == 0u {
+        return sampled * params.color;
+    }
+    if mode == 1u {
+        return params.color;
+    }
+
+    let pixel = in.uv * params.rect.zw;
+    let edge = min(min(pixel.x, pixel.y), min(params.rect.z - pixel.x, params.rect.w - pixel.y));
+    if edge > params.output.w {
+        discard;
+    }
+    return params.color;
+}
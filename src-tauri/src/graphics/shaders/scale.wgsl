@@ -0,0 +1,129 @@
+// 输出缩放
+//
+// 滤镜核与 CPU 参考实现 `media::scale` 一致，缩小时按比例放大滤镜核以避免混叠
+
+struct Params {
+    // 源宽度, 源高度, 水平比例, 垂直比例
+    source: vec4<f32>,
+    // 滤镜(0=最近邻 1=双线性 2=双三次 3=Lanczos 4=区域), 未使用
+    mode: vec4<u32>,
+}
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> params: Params;
+
+const PI: f32 = 3.14159265358979;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    // 覆盖整个视口的三角形
+    let x = f32(i32(index & 1u) * 4 - 1);
+    let y = f32(i32(index >> 1u) * 4 - 1);
+    return vec4<f32>(x, y, 0.0, 1.0);
+}
+
+fn radius(kind: u32) -> f32 {
+    switch kind {
+        case 1u: { return 1.0; }
+        case 2u: { return 2.0; }
+        case 3u: { return 3.0; }
+        default: { return 0.0; }
+    }
+}
+
+fn weight(kind: u32, d: f32) -> f32 {
+    let x = abs(d);
+
+    switch kind {
+        case 1u: {
+            return max(1.0 - x, 0.0);
+        }
+        case 2u: {
+            let a = -0.5;
+            if x < 1.0 {
+                return (a + 2.0) * x * x * x - (a + 3.0) * x * x + 1.0;
+            } else if x < 2.0 {
+                return a * x * x * x - 5.0 * a * x * x + 8.0 * a * x - 4.0 * a;
+            }
+            return 0.0;
+        }
+        case 3u: {
+            if x < 1e-5 {
+                return 1.0;
+            } else if x < 3.0 {
+                let px = PI * x;
+                return 3.0 * sin(px) * sin(px / 3.0) / (px * px);
+            }
+            return 0.0;
+        }
+        default: {
+            return 0.0;
+        }
+    }
+}
+
+// 一个方向上的采样范围 [first, last] 和滤镜核缩放比例
+struct Range {
+    first: i32,
+    last: i32,
+    start: f32,
+    end: f32,
+    center: f32,
+    scale: f32,
+}
+
+fn tap_range(kind: u32, dst: f32, ratio: f32) -> Range {
+    var range: Range;
+
+    if kind == 0u {
+        let i = i32(floor((dst + 0.5) * ratio));
+        range.first = i;
+        range.last = i;
+    } else if kind == 4u {
+        range.start = dst * ratio;
+        range.end = (dst + 1.0) * ratio;
+        range.first = i32(floor(range.start));
+        range.last = i32(ceil(range.end)) - 1;
+    } else {
+        range.scale = max(ratio, 1.0);
+        let support = radius(kind) * range.scale;
+        range.center = (dst + 0.5) * ratio - 0.5;
+        range.first = i32(ceil(range.center - support));
+        range.last = i32(floor(range.center + support));
+    }
+
+    return range;
+}
+
+fn tap_weight(kind: u32, range: Range, i: i32) -> f32 {
+    if kind == 0u {
+        return 1.0;
+    } else if kind == 4u {
+        return min(range.end, f32(i) + 1.0) - max(range.start, f32(i));
+    }
+    return weight(kind, (f32(i) - range.center) / range.scale);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let kind = params.mode.x;
+    let max_coord = vec2<i32>(params.source.xy) - 1;
+    let dst = floor(position.xy);
+
+    let rx = tap_range(kind, dst.x, params.source.z);
+    let ry = tap_range(kind, dst.y, params.source.w);
+
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var y = ry.first; y <= ry.last; y++) {
+        let wy = tap_weight(kind, ry, y);
+        for (var x = rx.first; x <= rx.last; x++) {
+            let w = tap_weight(kind, rx, x) * wy;
+            let coord = clamp(vec2<i32>(x, y), vec2<i32>(0), max_coord);
+            sum += textureLoad(source, coord, 0) * w;
+            total += w;
+        }
+    }
+
+    return clamp(select(vec4<f32>(0.0), sum / total, total != 0.0), vec4<f32>(0.0), vec4<f32>(1.0));
+}
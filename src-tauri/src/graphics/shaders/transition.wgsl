@@ -0,0 +1,76 @@
+// 两个纹理之间的转场
+//
+// 纹理按比例缩放并居中放入输出区域，超出部分透明
+
+struct Params {
+    // 输出宽度, 输出高度, 进度, 起始纹理不透明度
+    output: vec4<f32>,
+    // 起始纹理宽度, 高度, 目标纹理宽度, 高度
+    sizes: vec4<f32>,
+    // 转场类型(0=直接切换 1=淡入淡出 2=擦除 3=滑动), 未使用
+    mode: vec4<u32>,
+}
+
+@group(0) @binding(0) var from_texture: texture_2d<f32>;
+@group(0) @binding(1) var to_texture: texture_2d<f32>;
+@group(0) @binding(2) var linear_sampler: sampler;
+@group(0) @binding(3) var<uniform> params: Params;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    // 覆盖整个视口的三角形
+    let x = f32(i32(index & 1u) * 4 - 1);
+    let y = f32(i32(index >> 1u) * 4 - 1);
+    return vec4<f32>(x, y, 0.0, 1.0);
+}
+
+// 输出坐标转换为纹理坐标，超出纹理范围时返回负值
+fn fit_uv(position: vec2<f32>, size: vec2<f32>) -> vec2<f32> {
+    let area = params.output.xy;
+    let scale = min(area.x / size.x, area.y / size.y);
+    let drawn = size * scale;
+    return (position - (area - drawn) * 0.5) / drawn;
+}
+
+fn inside(uv: vec2<f32>) -> bool {
+    return all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let kind = params.mode.x;
+    let t = clamp(params.output.z, 0.0, 1.0);
+    let width = params.output.x;
+
+    // 擦除时起始纹理不动，滑动时随目标纹理一起移出
+    var from_offset = 0.0;
+    var to_offset = 0.0;
+    if kind == 2u {
+        to_offset = width * (1.0 - t);
+    } else if kind == 3u {
+        from_offset = -width * t;
+        to_offset = width * (1.0 - t);
+    }
+
+    let from_uv = fit_uv(position.xy - vec2<f32>(from_offset, 0.0), params.sizes.xy);
+    let to_uv = fit_uv(position.xy - vec2<f32>(to_offset, 0.0), params.sizes.zw);
+
+    // 在统一控制流中采样，再按位置选择
+    let from_color = select(vec4<f32>(0.0), textureSample(from_texture, linear_sampler, from_uv), inside(from_uv)) * params.output.w;
+    let to_color = select(vec4<f32>(0.0), textureSample(to_texture, linear_sampler, to_uv), inside(to_uv));
+
+    switch kind {
+        case 0u: {
+            return to_color;
+        }
+        case 1u: {
+            // 按预乘透明度混合，避免透明区域把颜色拉暗
+            let alpha = mix(from_color.a, to_color.a, t);
+            let rgb = mix(from_color.rgb * from_color.a, to_color.rgb * to_color.a, t);
+            return select(vec4<f32>(0.0), vec4<f32>(rgb / alpha, alpha), alpha > 0.0);
+        }
+        default: {
+            return select(from_color, to_color, position.x >= to_offset);
+        }
+    }
+}
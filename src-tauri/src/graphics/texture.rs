@@ -1,4 +1,10 @@
-use image::GenericImageView;
+use std::borrow::Cow;
+
+use anyhow::anyhow;
+use image::{
+    imageops::{self, FilterType},
+    RgbaImage,
+};
 
 use crate::Result;
 
@@ -47,8 +53,31 @@ impl Texture {
         img: &image::DynamicImage,
         label: Option<&str>,
     ) -> Result<Self> {
-        let rgba = img.to_rgba8();
-        let dimensions = img.dimensions();
+        Self::from_rgba(device, queue, &img.to_rgba8(), label, false)
+    }
+
+    /// 从 RGBA 图像创建纹理
+    ///
+    /// # 参数
+    /// * `device` - WGPU 设备
+    /// * `queue` - WGPU 队列
+    /// * `rgba` - RGBA 图像
+    /// * `label` - 可选的纹理标签
+    /// * `mipmaps` - 是否生成多级渐远纹理，用于缩小显示的来源
+    ///
+    /// # 返回
+    /// 返回 `Result<Self>`，成功时包含创建的 `Texture` 对象
+    pub fn from_rgba(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rgba: &RgbaImage,
+        label: Option<&str>,
+        mipmaps: bool,
+    ) -> Result<Self> {
+        let dimensions = rgba.dimensions();
+        if dimensions.0 == 0 || dimensions.1 == 0 {
+            return Err(anyhow!("empty image"));
+        }
 
         // 创建纹理大小描述符
         let size = wgpu::Extent3d {
@@ -56,11 +85,16 @@ impl Texture {
             height: dimensions.1,
             depth_or_array_layers: 1,
         };
+        let mip_level_count = if mipmaps {
+            size.max_mips(wgpu::TextureDimension::D2)
+        } else {
+            1
+        };
         // 创建 WGPU 纹理
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label,
             size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
@@ -68,40 +102,142 @@ impl Texture {
             view_formats: &[],
         });
 
-        // 将图像数据写入纹理
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            size,
-        );
-
         // 创建纹理视图
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        // 创建采样器
+        // 创建采样器，有多级渐远纹理时在各级之间线性插值
+        let (min_filter, mipmap_filter) = if mipmaps {
+            (wgpu::FilterMode::Linear, wgpu::FilterMode::Linear)
+        } else {
+            (wgpu::FilterMode::Nearest, wgpu::FilterMode::Nearest)
+        };
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            min_filter,
+            mipmap_filter,
             ..Default::default()
         });
 
-        Ok(Self {
+        let texture = Self {
             texture,
             view,
             sampler,
-        })
+        };
+        texture.write(queue, rgba)?;
+
+        Ok(texture)
+    }
+
+    /// 创建可作为渲染目标的纹理
+    ///
+    /// # 参数
+    /// * `device` - WGPU 设备
+    /// * `width` - 宽度
+    /// * `height` - 高度
+    /// * `label` - 可选的纹理标签
+    ///
+    /// # 返回
+    /// 返回新创建的 `Texture` 对象，内容为透明
+    pub fn render_target(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        label: Option<&str>,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    /// 是否有多级渐远纹理
+    pub fn has_mipmaps(&self) -> bool {
+        self.texture.mip_level_count() > 1
+    }
+
+    /// 将图像写入纹理，有多级渐远纹理时同时更新各级
+    ///
+    /// # 参数
+    /// * `queue` - WGPU 队列
+    /// * `rgba` - RGBA 图像，尺寸需与纹理一致
+    ///
+    /// # 返回
+    /// 返回 `Result<()>`，尺寸不一致时返回错误
+    pub fn write(&self, queue: &wgpu::Queue, rgba: &RgbaImage) -> Result<()> {
+        if rgba.dimensions() != (self.texture.width(), self.texture.height()) {
+            return Err(anyhow!(
+                "image size {}x{} does not match texture {}x{}",
+                rgba.width(),
+                rgba.height(),
+                self.texture.width(),
+                self.texture.height()
+            ));
+        }
+
+        let mut level = Cow::Borrowed(rgba);
+        for mip_level in 0..self.texture.mip_level_count() {
+            if mip_level > 0 {
+                // 每级尺寸减半，用三角滤波缩小上一级
+                let (width, height) = ((level.width() / 2).max(1), (level.height() / 2).max(1));
+                level = Cow::Owned(imageops::resize(
+                    level.as_ref(),
+                    width,
+                    height,
+                    FilterType::Triangle,
+                ));
+            }
+
+            // 将图像数据写入纹理
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &self.texture,
+                    mip_level,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                level.as_raw(),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * level.width()),
+                    rows_per_image: Some(level.height()),
+                },
+                wgpu::Extent3d {
+                    width: level.width(),
+                    height: level.height(),
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,326 @@
+/// 转场模块
+///
+/// 在两个纹理之间按进度混合，供场景切换和幻灯片等来源使用
+use std::{borrow::Cow, time::Duration};
+
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType, BufferUsages,
+    CommandEncoder, Device, FragmentState, MultisampleState, PipelineLayoutDescriptor,
+    PrimitiveState, Queue, RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType,
+    ShaderModuleDescriptor, ShaderSource, ShaderStages, TextureFormat, TextureSampleType,
+    TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::graphics::texture::Texture;
+
+/// 转场类型
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionKind {
+    /// 直接切换
+    Cut,
+    /// 淡入淡出
+    #[default]
+    Fade,
+    /// 新画面从右侧擦入
+    Swipe,
+    /// 新画面从右侧推入，旧画面同时移出
+    Slide,
+}
+
+impl TransitionKind {
+    /// 从名称解析，如 `fade`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cut" => Some(Self::Cut),
+            "fade" => Some(Self::Fade),
+            "swipe" => Some(Self::Swipe),
+            "slide" => Some(Self::Slide),
+            _ => None,
+        }
+    }
+
+    /// 着色器中使用的编号
+    pub fn id(&self) -> u32 {
+        match self {
+            Self::Cut => 0,
+            Self::Fade => 1,
+            Self::Swipe => 2,
+            Self::Slide => 3,
+        }
+    }
+}
+
+/// 计算转场进度
+///
+/// # 参数
+///
+/// * `elapsed` - 转场开始后经过的时间
+/// * `duration` - 转场时长
+///
+/// # 返回值
+///
+/// 返回 0 到 1 之间的进度，时长为零时直接返回 1
+pub fn progress(elapsed: Duration, duration: Duration) -> f32 {
+    if duration.is_zero() {
+        return 1.;
+    }
+
+    (elapsed.as_secs_f32() / duration.as_secs_f32()).clamp(0., 1.)
+}
+
+/// 着色器参数，布局与 `transition.wgsl` 中的 `Params` 一致
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    output: [f32; 4],
+    sizes: [f32; 4],
+    mode: [u32; 4],
+}
+
+/// 转场渲染器
+pub struct Transition {
+    pub kind: TransitionKind,
+    pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    sampler: Sampler,
+    uniform: Buffer,
+}
+
+impl Transition {
+    /// 创建转场渲染器
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `format` - 输出纹理格式
+    /// * `kind` - 转场类型
+    pub fn new(device: &Device, format: TextureFormat, kind: TransitionKind) -> Self {
+        let uniform = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("transition params"),
+            contents: bytemuck::bytes_of(&Params::zeroed()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("transition"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("shaders/transition.wgsl"))),
+        });
+
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("transition"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("transition"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("transition"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(format.into())],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            kind,
+            pipeline,
+            bind_group_layout,
+            sampler,
+            uniform,
+        }
+    }
+
+    /// 记录转场命令
+    ///
+    /// 参数缓冲区在提交前写入，因此同一次提交中只能调用一次
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `queue` - WGPU 队列
+    /// * `encoder` - 命令编码器
+    /// * `from` - 起始纹理，为 `None` 时从透明开始
+    /// * `to` - 目标纹理
+    /// * `progress` - 进度，0 到 1
+    /// * `output` - 输出纹理
+    #[allow(clippy::too_many_arguments)]
+    pub fn encode(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        from: Option<&Texture>,
+        to: &Texture,
+        progress: f32,
+        output: &Texture,
+    ) {
+        let size = (output.texture.width(), output.texture.height());
+        self.encode_view(
+            device,
+            queue,
+            encoder,
+            from,
+            to,
+            progress,
+            &output.view,
+            size,
+        );
+    }
+
+    /// 记录转场命令，输出到纹理视图，如窗口的交换链纹理
+    ///
+    /// 参数缓冲区在提交前写入，因此同一次提交中只能调用一次
+    ///
+    /// # 参数
+    ///
+    /// * `device` - WGPU 设备
+    /// * `queue` - WGPU 队列
+    /// * `encoder` - 命令编码器
+    /// * `from` - 起始纹理，为 `None` 时从透明开始
+    /// * `to` - 目标纹理
+    /// * `progress` - 进度，0 到 1
+    /// * `view` - 输出纹理视图
+    /// * `size` - 输出尺寸
+    #[allow(clippy::too_many_arguments)]
+    pub fn encode_view(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        from: Option<&Texture>,
+        to: &Texture,
+        progress: f32,
+        view: &TextureView,
+        size: (u32, u32),
+    ) {
+        // 没有起始纹理时用透明的目标纹理占位，淡入淡出从透明开始，其他转场直接完成
+        let (from, from_alpha, progress) = match (from, self.kind) {
+            (Some(from), _) => (from, 1., progress),
+            (None, TransitionKind::Fade) => (to, 0., progress),
+            (None, _) => (to, 0., 1.),
+        };
+        let texture_size = |texture: &Texture| {
+            (
+                texture.texture.width() as f32,
+                texture.texture.height() as f32,
+            )
+        };
+        let (from_w, from_h) = texture_size(from);
+        let (to_w, to_h) = texture_size(to);
+        let (out_w, out_h) = (size.0 as f32, size.1 as f32);
+
+        let params = Params {
+            output: [out_w, out_h, progress, from_alpha],
+            sizes: [from_w, from_h, to_w, to_h],
+            mode: [self.kind.id(), 0, 0, 0],
+        };
+        queue.write_buffer(&self.uniform, 0, bytemuck::bytes_of(&params));
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("transition"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&from.view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&to.view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(&self.sampler),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: self.uniform.as_entire_binding(),
+                },
+            ],
+        });
+
+        self.draw(encoder, &bind_group, view);
+    }
+
+    fn draw(&self, encoder: &mut CommandEncoder, bind_group: &wgpu::BindGroup, view: &TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("transition"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
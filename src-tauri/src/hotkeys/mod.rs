@@ -0,0 +1,347 @@
+#![allow(dead_code)]
+
+pub mod registry;
+pub mod shortcut;
+
+use std::sync::Arc;
+
+use log::error;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    hotkeys::registry::{register_hotkey, unregister_hotkey, HotkeyCallback},
+    outputs::{
+        recording::{
+            add_chapter, pause_recording, resume_recording, split_recording, start_recording,
+            stop_recording,
+        },
+        streaming::{start_streaming, stop_streaming},
+        virtualcam::{start_virtualcam, stop_virtualcam},
+    },
+    scene::{
+        edit::{set_current_scene, set_source_muted},
+        scenes,
+    },
+    sources::{has_audio, media_commands, send_media_command, MediaCommand},
+    utils::locale::t,
+    Result,
+};
+
+/// 发送给前端的热键事件名称
+pub const HOTKEY_EVENT: &str = "hotkey";
+
+/// 发送给前端的热键事件
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyEvent {
+    pub id: String,
+    pub pressed: bool,
+}
+
+/// 创建向前端转发热键事件的回调
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `pressed` - 是否为按下事件
+///
+/// # 返回值
+///
+/// 返回热键回调
+pub fn emit_callback(app: &AppHandle, pressed: bool) -> HotkeyCallback {
+    let app = app.clone();
+
+    Arc::new(move |id: &str| {
+        let event = HotkeyEvent {
+            id: id.to_string(),
+            pressed,
+        };
+
+        if let Err(e) = app.emit(HOTKEY_EVENT, event) {
+            error!("failed to emit hotkey event {}: {}", id, e);
+        }
+    })
+}
+
+/// 注册场景切换热键
+///
+/// 动作 ID 使用场景 UUID，场景重命名后再次调用即可更新描述，绑定保持不变
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `name` - 场景名称
+///
+/// # 返回值
+///
+/// 返回 `Result<String>`，表示注册的动作 ID
+pub fn register_scene_hotkey(app: &AppHandle, scene: &str, name: &str) -> Result<String> {
+    let id = format!("OBSBasic.SelectScene.{}", scene);
+    let description = format!("{} '{}'", t("Basic.Hotkeys.SelectScene")?, name);
+
+    let handle = app.clone();
+    let uuid = scene.to_string();
+    let on_press: HotkeyCallback = Arc::new(move |_id: &str| {
+        if let Err(e) = set_current_scene(&handle, &uuid) {
+            error!("failed to switch scene {}: {}", uuid, e);
+        }
+    });
+
+    register_hotkey(&id, &description, Some(on_press), None);
+
+    Ok(id)
+}
+
+/// 静音相关热键：动作 ID 前缀、描述的翻译键、按下和松开时设置的静音状态
+const MUTE_HOTKEYS: [(&str, &str, bool, Option<bool>); 4] = [
+    ("libobs.mute", "Mute", true, None),
+    ("libobs.unmute", "Unmute", false, None),
+    ("libobs.push-to-mute", "Push-to-mute", true, Some(false)),
+    ("libobs.push-to-talk", "Push-to-talk", false, Some(true)),
+];
+
+/// 创建设置来源静音状态的回调
+fn mute_callback(app: &AppHandle, uuid: &str, muted: bool) -> HotkeyCallback {
+    let app = app.clone();
+    let uuid = uuid.to_string();
+
+    Arc::new(move |id: &str| {
+        if let Err(e) = set_source_muted(&app, &uuid, muted) {
+            error!("hotkey {} failed: {}", id, e);
+        }
+    })
+}
+
+/// 注册来源的热键，有音频的来源注册静音相关热键，支持媒体控制的来源注册媒体控制热键
+///
+/// 动作 ID 使用来源 UUID，场景集合加载、来源添加和重命名时调用，重命名后绑定保持不变
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `uuid` - 来源 UUID
+/// * `name` - 来源名称
+/// * `kind` - 来源类型 ID
+///
+/// # 返回值
+///
+/// 返回 `Result<Vec<String>>`，表示注册的动作 ID 列表
+pub fn register_source_hotkeys(
+    app: &AppHandle,
+    uuid: &str,
+    name: &str,
+    kind: &str,
+) -> Result<Vec<String>> {
+    let mut ids = vec![];
+
+    if has_audio(kind) {
+        for (prefix, key, pressed, released) in MUTE_HOTKEYS {
+            let id = format!("{}.{}", prefix, uuid);
+            let description = format!("{} '{}'", t(key)?, name);
+            let on_release = released.map(|muted| mute_callback(app, uuid, muted));
+
+            register_hotkey(
+                &id,
+                &description,
+                Some(mute_callback(app, uuid, pressed)),
+                on_release,
+            );
+            ids.push(id);
+        }
+    }
+    ids.extend(register_media_hotkeys(uuid, name, kind)?);
+
+    Ok(ids)
+}
+
+/// 注销来源的所有热键，来源从场景集合中移除时调用
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+/// * `kind` - 来源类型 ID
+pub fn unregister_source_hotkeys(uuid: &str, kind: &str) {
+    for (prefix, ..) in MUTE_HOTKEYS {
+        unregister_hotkey(&format!("{}.{}", prefix, uuid));
+    }
+    for command in media_commands(kind) {
+        unregister_hotkey(&media_hotkey_id(command, uuid));
+    }
+}
+
+/// 媒体控制热键的动作 ID
+fn media_hotkey_id(command: &MediaCommand, uuid: &str) -> String {
+    format!("MediaSource.{}.{}", command.name(), uuid)
+}
+
+/// 注册来源的媒体控制热键，如幻灯片的下一张、上一张
+///
+/// 动作 ID 使用来源 UUID，来源重命名后再次调用即可更新描述
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+/// * `name` - 来源名称
+/// * `kind` - 来源类型 ID，不支持媒体控制的类型不注册任何热键
+///
+/// # 返回值
+///
+/// 返回 `Result<Vec<String>>`，表示注册的动作 ID 列表
+pub fn register_media_hotkeys(uuid: &str, name: &str, kind: &str) -> Result<Vec<String>> {
+    let mut ids = vec![];
+
+    for command in media_commands(kind) {
+        let id = media_hotkey_id(command, uuid);
+        let description = format!("{} '{}'", t(command.locale_key())?, name);
+
+        let source = uuid.to_string();
+        let command = *command;
+        let on_press: HotkeyCallback =
+            Arc::new(move |_id: &str| send_media_command(&source, command));
+
+        register_hotkey(&id, &description, Some(on_press), None);
+        ids.push(id);
+    }
+
+    Ok(ids)
+}
+
+/// 设置热键系统，注册内置动作并同步全局快捷键
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn setup_hotkeys(app: &AppHandle) -> Result<()> {
+    for (id, key) in [
+        ("OBSBasic.StartReplayBuffer", "Basic.Main.StartReplayBuffer"),
+        ("OBSBasic.StopReplayBuffer", "Basic.Main.StopReplayBuffer"),
+        ("ReplayBuffer.Save", "Basic.Main.SaveReplay"),
+        ("OBSBasic.Transition", "Transition"),
+    ] {
+        register_hotkey(id, &t(key)?, Some(emit_callback(app, true)), None);
+    }
+
+    let handle = app.clone();
+    let start: HotkeyCallback = Arc::new(move |_id: &str| {
+        if let Err(e) = start_streaming(&handle) {
+            error!("failed to start streaming: {}", e);
+        }
+    });
+    register_hotkey(
+        "OBSBasic.StartStreaming",
+        &t("Basic.Main.StartStreaming")?,
+        Some(start),
+        None,
+    );
+    let stop: HotkeyCallback = Arc::new(|_id: &str| stop_streaming());
+    register_hotkey(
+        "OBSBasic.StopStreaming",
+        &t("Basic.Main.StopStreaming")?,
+        Some(stop),
+        None,
+    );
+
+    let handle = app.clone();
+    let start: HotkeyCallback = Arc::new(move |_id: &str| {
+        if let Err(e) = start_recording(&handle) {
+            error!("failed to start recording: {}", e);
+        }
+    });
+    register_hotkey(
+        "OBSBasic.StartRecording",
+        &t("Basic.Main.StartRecording")?,
+        Some(start),
+        None,
+    );
+    let stop: HotkeyCallback = Arc::new(|_id: &str| stop_recording());
+    register_hotkey(
+        "OBSBasic.StopRecording",
+        &t("Basic.Main.StopRecording")?,
+        Some(stop),
+        None,
+    );
+    // 没有在录制时忽略，只记录日志
+    for (id, key, action) in [
+        (
+            "OBSBasic.PauseRecording",
+            "Basic.Main.PauseRecording",
+            pause_recording as fn() -> Result<()>,
+        ),
+        (
+            "OBSBasic.UnpauseRecording",
+            "Basic.Main.UnpauseRecording",
+            resume_recording,
+        ),
+        (
+            "OBSBasic.SplitFile",
+            "Basic.Main.SplitFile",
+            split_recording,
+        ),
+        (
+            "OBSBasic.AddChapterMarker",
+            "Basic.Main.AddChapterMarker",
+            || add_chapter(None),
+        ),
+    ] {
+        let callback: HotkeyCallback = Arc::new(move |id: &str| {
+            if let Err(e) = action() {
+                error!("hotkey {} failed: {}", id, e);
+            }
+        });
+        register_hotkey(id, &t(key)?, Some(callback), None);
+    }
+
+    let handle = app.clone();
+    let start: HotkeyCallback = Arc::new(move |_id: &str| {
+        if let Err(e) = start_virtualcam(&handle) {
+            error!("failed to start virtual camera: {}", e);
+        }
+    });
+    register_hotkey(
+        "OBSBasic.StartVirtualCam",
+        &t("Basic.Main.StartVirtualCam")?,
+        Some(start),
+        None,
+    );
+    let stop: HotkeyCallback = Arc::new(|_id: &str| stop_virtualcam());
+    register_hotkey(
+        "OBSBasic.StopVirtualCam",
+        &t("Basic.Main.StopVirtualCam")?,
+        Some(stop),
+        None,
+    );
+
+    // 先收集来源，下面的 `scenes` 变量会遮蔽同名函数
+    let sources: Vec<(String, String, String)> = scenes()
+        .sources
+        .values()
+        .map(|source| {
+            (
+                source.uuid.clone(),
+                source.name.clone(),
+                source.kind.clone(),
+            )
+        })
+        .collect();
+    for (uuid, name, kind) in sources {
+        register_source_hotkeys(app, &uuid, &name, &kind)?;
+    }
+
+    let scenes: Vec<(String, String)> = scenes()
+        .scenes
+        .iter()
+        .map(|scene| (scene.uuid.clone(), scene.name.clone()))
+        .collect();
+    for (uuid, name) in scenes {
+        register_scene_hotkey(app, &uuid, &name)?;
+    }
+
+    shortcut::sync_shortcuts(app)?;
+
+    Ok(())
+}
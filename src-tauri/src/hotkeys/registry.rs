@@ -0,0 +1,407 @@
+/// 热键注册表模块
+///
+/// 各子系统在此注册热键动作，按键事件（全局快捷键或测试注入）统一经由 `dispatch` 分发
+use std::{
+    collections::{BTreeMap, HashSet},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use serde::Serialize;
+use tauri_plugin_global_shortcut::Shortcut;
+
+use crate::{
+    utils::profile::{get_profile_config, remove_profile_config, set_profile_config},
+    Result,
+};
+
+/// 配置文件中保存热键绑定的配置节
+pub const HOTKEYS_SECTION: &str = "Hotkeys";
+
+/// 热键回调类型，参数为触发的动作 ID
+pub type HotkeyCallback = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// 热键动作
+#[derive(Clone)]
+pub struct HotkeyAction {
+    /// 动作 ID，同时作为配置文件中的键名
+    pub id: String,
+    /// 动作描述（已翻译的文本）
+    pub description: String,
+    /// 按下时的回调
+    pub on_press: Option<HotkeyCallback>,
+    /// 松开时的回调
+    pub on_release: Option<HotkeyCallback>,
+}
+
+/// 提供给前端的热键信息
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyInfo {
+    pub id: String,
+    pub description: String,
+    pub bindings: Vec<String>,
+}
+
+/// 热键注册表
+#[derive(Default)]
+struct Registry {
+    /// 已注册的动作
+    actions: BTreeMap<String, HotkeyAction>,
+    /// 动作 ID 到快捷键的绑定
+    bindings: BTreeMap<String, Vec<String>>,
+    /// 当前处于按下状态的快捷键，用于过滤按键重复
+    pressed: HashSet<String>,
+}
+
+lazy_static! {
+    static ref HOTKEYS: Mutex<Registry> = Mutex::new(Registry::default());
+}
+
+/// 将快捷键字符串规范化
+///
+/// # 参数
+///
+/// * `shortcut` - 快捷键字符串，例如 `Ctrl+Shift+F1`
+///
+/// # 返回值
+///
+/// 返回 `Result<String>`，表示规范化后的快捷键字符串
+pub fn normalize_shortcut(shortcut: &str) -> Result<String> {
+    match Shortcut::from_str(shortcut) {
+        Ok(shortcut) => Ok(shortcut.into_string()),
+        Err(e) => Err(anyhow!("invalid shortcut {}: {}", shortcut, e)),
+    }
+}
+
+/// 从配置文件加载动作的绑定
+fn load_bindings(id: &str) -> Vec<String> {
+    let value = match get_profile_config(HOTKEYS_SECTION, id) {
+        Some(value) => value,
+        None => return vec![],
+    };
+
+    match serde_json::from_str::<Vec<String>>(&value) {
+        Ok(bindings) => bindings
+            .iter()
+            .filter_map(|binding| match normalize_shortcut(binding) {
+                Ok(binding) => Some(binding),
+                Err(e) => {
+                    warn!("ignore hotkey binding of {}: {}", id, e);
+                    None
+                }
+            })
+            .collect(),
+        Err(e) => {
+            warn!("failed to parse hotkey bindings of {}: {}", id, e);
+            vec![]
+        }
+    }
+}
+
+/// 注册热键动作
+///
+/// 已保存在当前配置文件中的绑定会被一并加载
+///
+/// # 参数
+///
+/// * `id` - 动作 ID
+/// * `description` - 动作描述
+/// * `on_press` - 按下时的回调
+/// * `on_release` - 松开时的回调
+pub fn register_hotkey(
+    id: &str,
+    description: &str,
+    on_press: Option<HotkeyCallback>,
+    on_release: Option<HotkeyCallback>,
+) {
+    let bindings = load_bindings(id);
+
+    let mut registry = HOTKEYS.lock().unwrap();
+    registry.actions.insert(
+        id.to_string(),
+        HotkeyAction {
+            id: id.to_string(),
+            description: description.to_string(),
+            on_press,
+            on_release,
+        },
+    );
+
+    if !bindings.is_empty() {
+        registry.bindings.insert(id.to_string(), bindings);
+    }
+}
+
+/// 注销热键动作
+///
+/// 绑定仍保留在配置文件中，以便同名动作再次注册时恢复
+///
+/// # 参数
+///
+/// * `id` - 动作 ID
+pub fn unregister_hotkey(id: &str) {
+    let mut registry = HOTKEYS.lock().unwrap();
+    registry.actions.remove(id);
+    registry.bindings.remove(id);
+}
+
+/// 重命名热键动作，绑定随之迁移
+///
+/// # 参数
+///
+/// * `id` - 原动作 ID
+/// * `new_id` - 新动作 ID
+/// * `description` - 新的动作描述
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn rename_hotkey(id: &str, new_id: &str, description: &str) -> Result<()> {
+    let bindings = {
+        let mut registry = HOTKEYS.lock().unwrap();
+        let mut action = match registry.actions.remove(id) {
+            Some(action) => action,
+            None => return Err(anyhow!("hotkey not registered: {}", id)),
+        };
+
+        action.id = new_id.to_string();
+        action.description = description.to_string();
+        registry.actions.insert(new_id.to_string(), action);
+
+        let bindings = registry.bindings.remove(id).unwrap_or_default();
+        if !bindings.is_empty() {
+            registry
+                .bindings
+                .insert(new_id.to_string(), bindings.clone());
+        }
+
+        bindings
+    };
+
+    remove_profile_config(HOTKEYS_SECTION, id)?;
+    if !bindings.is_empty() {
+        set_profile_config(HOTKEYS_SECTION, new_id, &serde_json::to_string(&bindings)?)?;
+    }
+
+    Ok(())
+}
+
+/// 设置动作的快捷键绑定并保存到当前配置文件
+///
+/// # 参数
+///
+/// * `id` - 动作 ID
+/// * `bindings` - 快捷键列表，为空时清除绑定
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn set_bindings(id: &str, bindings: &[String]) -> Result<()> {
+    let mut normalized: Vec<String> = vec![];
+    for binding in bindings {
+        let binding = normalize_shortcut(binding)?;
+        if !normalized.contains(&binding) {
+            normalized.push(binding);
+        }
+    }
+
+    {
+        let mut registry = HOTKEYS.lock().unwrap();
+        if !registry.actions.contains_key(id) {
+            return Err(anyhow!("hotkey not registered: {}", id));
+        }
+
+        if normalized.is_empty() {
+            registry.bindings.remove(id);
+        } else {
+            registry.bindings.insert(id.to_string(), normalized.clone());
+        }
+    }
+
+    if normalized.is_empty() {
+        remove_profile_config(HOTKEYS_SECTION, id)
+    } else {
+        set_profile_config(HOTKEYS_SECTION, id, &serde_json::to_string(&normalized)?)
+    }
+}
+
+/// 获取所有已注册动作的信息
+///
+/// # 返回值
+///
+/// 返回按动作 ID 排序的 `HotkeyInfo` 列表
+pub fn hotkeys() -> Vec<HotkeyInfo> {
+    let registry = HOTKEYS.lock().unwrap();
+
+    registry
+        .actions
+        .values()
+        .map(|action| HotkeyInfo {
+            id: action.id.clone(),
+            description: action.description.clone(),
+            bindings: registry
+                .bindings
+                .get(&action.id)
+                .cloned()
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// 获取所有已绑定的快捷键（去重）
+///
+/// # 返回值
+///
+/// 返回快捷键列表
+pub fn bound_shortcuts() -> Vec<String> {
+    let registry = HOTKEYS.lock().unwrap();
+
+    let mut shortcuts: Vec<String> = registry.bindings.values().flatten().cloned().collect();
+    shortcuts.sort();
+    shortcuts.dedup();
+    shortcuts
+}
+
+/// 分发按键事件
+///
+/// 同一快捷键绑定到多个动作时全部触发；按住期间重复的按下事件会被忽略
+///
+/// # 参数
+///
+/// * `shortcut` - 规范化后的快捷键字符串
+/// * `pressed` - 是否为按下事件
+///
+/// # 返回值
+///
+/// 返回被触发的动作数量
+pub fn dispatch(shortcut: &str, pressed: bool) -> usize {
+    let callbacks: Vec<(String, HotkeyCallback)> = {
+        let mut registry = HOTKEYS.lock().unwrap();
+
+        if pressed {
+            if !registry.pressed.insert(shortcut.to_string()) {
+                return 0;
+            }
+        } else if !registry.pressed.remove(shortcut) {
+            return 0;
+        }
+
+        registry
+            .bindings
+            .iter()
+            .filter(|(_, bindings)| bindings.iter().any(|binding| binding == shortcut))
+            .filter_map(|(id, _)| registry.actions.get(id))
+            .filter_map(|action| {
+                let callback = if pressed {
+                    action.on_press.clone()
+                } else {
+                    action.on_release.clone()
+                };
+
+                callback.map(|callback| (action.id.clone(), callback))
+            })
+            .collect()
+    };
+
+    debug!(
+        "hotkey {} {}: {} action(s)",
+        shortcut,
+        if pressed { "pressed" } else { "released" },
+        callbacks.len()
+    );
+
+    // 在锁外执行回调，允许回调中再次访问注册表
+    for (id, callback) in &callbacks {
+        callback(id);
+    }
+
+    callbacks.len()
+}
+
+/// 注入合成按键事件，走与全局快捷键相同的分发路径，供测试和调试使用
+///
+/// # 参数
+///
+/// * `shortcut` - 快捷键字符串，无需预先规范化
+/// * `pressed` - 是否为按下事件
+///
+/// # 返回值
+///
+/// 返回 `Result<usize>`，表示被触发的动作数量
+pub fn inject_key_event(shortcut: &str, pressed: bool) -> Result<usize> {
+    let shortcut = normalize_shortcut(shortcut)?;
+
+    Ok(dispatch(&shortcut, pressed))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// 只在内存中绑定，不写入配置文件
+    fn bind(id: &str, shortcut: &str) {
+        HOTKEYS
+            .lock()
+            .unwrap()
+            .bindings
+            .insert(id.to_string(), vec![normalize_shortcut(shortcut).unwrap()]);
+    }
+
+    fn counter(count: &Arc<AtomicUsize>) -> HotkeyCallback {
+        let count = count.clone();
+        Arc::new(move |_id: &str| {
+            count.fetch_add(1, Ordering::SeqCst);
+        })
+    }
+
+    #[test]
+    fn injected_key_triggers_all_bound_actions() {
+        let pressed = Arc::new(AtomicUsize::new(0));
+        let released = Arc::new(AtomicUsize::new(0));
+        register_hotkey(
+            "test.inject.a",
+            "A",
+            Some(counter(&pressed)),
+            Some(counter(&released)),
+        );
+        register_hotkey("test.inject.b", "B", Some(counter(&pressed)), None);
+        bind("test.inject.a", "Ctrl+Shift+F7");
+        bind("test.inject.b", "Ctrl+Shift+F7");
+
+        // 修饰键的大小写和顺序不影响匹配
+        assert_eq!(inject_key_event("shift+ctrl+f7", true).unwrap(), 2);
+        assert_eq!(pressed.load(Ordering::SeqCst), 2);
+
+        // 按住期间的重复按下被忽略
+        assert_eq!(inject_key_event("Ctrl+Shift+F7", true).unwrap(), 0);
+        assert_eq!(pressed.load(Ordering::SeqCst), 2);
+
+        // 只有注册了松开回调的动作被触发
+        assert_eq!(inject_key_event("Ctrl+Shift+F7", false).unwrap(), 1);
+        assert_eq!(released.load(Ordering::SeqCst), 1);
+        assert_eq!(inject_key_event("Ctrl+Shift+F7", false).unwrap(), 0);
+
+        unregister_hotkey("test.inject.a");
+        unregister_hotkey("test.inject.b");
+        assert_eq!(inject_key_event("Ctrl+Shift+F7", true).unwrap(), 0);
+        inject_key_event("Ctrl+Shift+F7", false).unwrap();
+    }
+
+    #[test]
+    fn injected_key_ignores_unbound_and_invalid_shortcuts() {
+        let pressed = Arc::new(AtomicUsize::new(0));
+        register_hotkey("test.inject.c", "C", Some(counter(&pressed)), None);
+        bind("test.inject.c", "Alt+F9");
+
+        assert_eq!(inject_key_event("Alt+F10", true).unwrap(), 0);
+        assert!(inject_key_event("Alt+NoSuchKey", true).is_err());
+        assert_eq!(pressed.load(Ordering::SeqCst), 0);
+
+        unregister_hotkey("test.inject.c");
+    }
+}
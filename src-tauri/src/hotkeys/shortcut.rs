@@ -0,0 +1,47 @@
+/// 全局快捷键模块，将注册表中的绑定同步到 Tauri 全局快捷键
+use log::{debug, error};
+use tauri::{plugin::TauriPlugin, AppHandle, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+use crate::{hotkeys::registry, Result};
+
+/// 创建全局快捷键插件
+///
+/// # 返回值
+///
+/// 返回 `Result<TauriPlugin<Wry>>`，表示全局快捷键插件
+pub fn setup_shortcut() -> Result<TauriPlugin<Wry>> {
+    let shortcut_plugin = tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|_app, shortcut, event| {
+            let pressed = event.state() == ShortcutState::Pressed;
+
+            registry::dispatch(&shortcut.to_string(), pressed);
+        })
+        .build();
+
+    Ok(shortcut_plugin)
+}
+
+/// 将注册表中的绑定同步到系统全局快捷键
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn sync_shortcuts(app: &AppHandle) -> Result<()> {
+    let global_shortcut = app.global_shortcut();
+    global_shortcut.unregister_all()?;
+
+    for shortcut in registry::bound_shortcuts() {
+        // 单个快捷键被其他程序占用时不影响其余快捷键
+        match global_shortcut.register(shortcut.as_str()) {
+            Ok(_) => debug!("register global shortcut: {}", shortcut),
+            Err(e) => error!("failed to register global shortcut {}: {}", shortcut, e),
+        }
+    }
+
+    Ok(())
+}
@@ -4,7 +4,13 @@ use tauri::Manager;
 /// 导入模块
 mod cmds;
 mod graphics;
+mod hotkeys;
+mod media;
+mod outputs;
 mod protocols;
+mod scene;
+mod sources;
+mod stats;
 mod ui;
 mod utils;
 
@@ -34,6 +40,12 @@ pub fn run() {
 
         /// 设置全局配置
         utils::config::setup_global_config(app.app_handle())?;
+        /// 设置当前配置文件
+        utils::profile::setup_profile_config(app.app_handle())?;
+        /// 加载场景集合
+        scene::setup_scenes(app.app_handle())?;
+        /// 启动场景集合中的来源
+        sources::manager::setup_sources(app.app_handle())?;
 
         /// 设置菜单
         ui::menu::setup_menus(app.app_handle())?;
@@ -42,12 +54,33 @@ pub fn run() {
 
         /// 设置布局
         ui::layout::setup_layout(app.app_handle())?;
+        /// 设置浏览器来源
+        ui::browser::setup_browser(app.app_handle())?;
+        /// 重新打开保存的投影仪
+        ui::projector::setup_projectors(app.app_handle())?;
+
+        /// 设置热键
+        hotkeys::setup_hotkeys(app.app_handle())?;
+
+        /// 启动统计采集
+        stats::setup_stats(app.app_handle())?;
+
+        /// 设置虚拟摄像机
+        outputs::virtualcam::setup_virtualcam(app.app_handle())?;
+        /// 加载推流服务目录
+        outputs::services::load_services(app.app_handle())?;
+        /// 设置推流
+        outputs::streaming::setup_streaming(app.app_handle())?;
+        /// 设置录制
+        outputs::recording::setup_recording(app.app_handle())?;
 
         Ok(())
     });
 
     /// 添加日志插件
     builder = builder.plugin(utils::log::setup_log().unwrap());
+    /// 添加全局快捷键插件
+    builder = builder.plugin(hotkeys::shortcut::setup_shortcut().unwrap());
     /// 添加窗口状态插件
     builder = builder.plugin(
         tauri_plugin_window_state::Builder::default()
@@ -62,7 +95,78 @@ pub fn run() {
     builder = builder.invoke_handler(tauri::generate_handler![
         cmds::locale::get_locale,
         cmds::locale::set_locale,
-        cmds::locale::get_locale_messages
+        cmds::locale::get_locale_messages,
+        cmds::hotkeys::get_hotkeys,
+        cmds::hotkeys::set_hotkey_bindings,
+        cmds::hotkeys::inject_hotkey,
+        cmds::scene::get_scene_collection,
+        cmds::scene::set_current_scene,
+        cmds::scene::add_scene,
+        cmds::scene::add_source,
+        cmds::scene::remove_scene_items,
+        cmds::scene::select_scene_items,
+        cmds::scene::move_scene_items,
+        cmds::scene::set_scene_item_order,
+        cmds::scene::rename_source,
+        cmds::scene::set_source_filters,
+        cmds::scene::copy_scene_items,
+        cmds::scene::paste_scene_items,
+        cmds::transform::get_scene_item_transform,
+        cmds::transform::set_scene_item_transform,
+        cmds::transform::copy_scene_item_transform,
+        cmds::transform::paste_scene_item_transform,
+        cmds::scene::undo,
+        cmds::scene::redo,
+        cmds::stats::get_stats,
+        cmds::stats::reset_stats,
+        cmds::outputs::start_virtualcam,
+        cmds::outputs::stop_virtualcam,
+        cmds::outputs::get_virtualcam_active,
+        cmds::outputs::start_recording,
+        cmds::outputs::stop_recording,
+        cmds::outputs::pause_recording,
+        cmds::outputs::resume_recording,
+        cmds::outputs::split_recording,
+        cmds::outputs::add_recording_chapter,
+        cmds::outputs::get_recording_status,
+        cmds::outputs::get_stream_destinations,
+        cmds::outputs::set_stream_destinations,
+        cmds::outputs::start_streaming,
+        cmds::outputs::stop_streaming,
+        cmds::outputs::start_stream_destination,
+        cmds::outputs::stop_stream_destination,
+        cmds::outputs::get_streaming_status,
+        cmds::outputs::get_encoder_groups,
+        cmds::outputs::get_stream_services,
+        cmds::outputs::validate_stream_destination,
+        cmds::outputs::use_recommended_settings,
+        cmds::outputs::run_autoconfig,
+        cmds::outputs::cancel_autoconfig,
+        cmds::outputs::get_network_settings,
+        cmds::outputs::set_network_settings,
+        cmds::outputs::get_network_interfaces,
+        cmds::media::play_media,
+        cmds::media::pause_media,
+        cmds::media::restart_media,
+        cmds::media::stop_media,
+        cmds::media::seek_media,
+        cmds::media::get_media_status,
+        cmds::capture::get_x11_screens,
+        cmds::capture::get_x11_windows,
+        cmds::capture::get_audio_devices,
+        cmds::capture::get_video_devices,
+        cmds::capture::get_video_modes,
+        cmds::projector::open_projector,
+        cmds::projector::close_projector,
+        cmds::projector::set_projector_options,
+        cmds::projector::get_projectors,
+        cmds::projector::get_monitors,
+        cmds::canvas::get_canvases,
+        cmds::canvas::add_canvas,
+        cmds::canvas::update_canvas,
+        cmds::canvas::remove_canvas,
+        cmds::canvas::get_output_canvas,
+        cmds::canvas::set_output_canvas
     ]);
 
     /// 构建并运行 Tauri 应用程序
@@ -70,8 +174,11 @@ pub fn run() {
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
 
-    /// 运行应用程序并处理布局事件
+    /// 运行应用程序并处理布局和投影仪事件
     app.run(|app, event| {
         ui::layout::layout_event(app, &event).expect("layout event error");
+        if let Err(e) = ui::projector::projector_event(app, &event) {
+            log::warn!("projector event error: {}", e);
+        }
     });
 }
@@ -0,0 +1,735 @@
+/// AAC 编码器模块
+///
+/// 最简的 AAC-LC 编码：只使用长窗口和正弦窗，所有频带共用全局增益作为比例因子，
+/// 非零频带用转义码本（11）编码。全局增益按每帧的比特预算二分查找，没有心理声学模型和比特池。
+/// 每帧 1024 个采样，编码包为不带 ADTS 头的原始帧，解码配置（AudioSpecificConfig）由
+/// `AudioEncoderSettings::decoder_config` 生成
+use anyhow::anyhow;
+use symphonia::core::dsp::{complex::Complex, fft::Fft};
+
+use crate::{
+    media::{
+        audio::AudioFrame,
+        encoder::{AudioEncoderSettings, EncodedPacket, FramePriority, PacketKind},
+    },
+    Result,
+};
+
+/// 每帧每个声道的采样数
+pub const AAC_FRAME_SAMPLES: usize = 1024;
+
+/// 采样率序号对应的采样率
+const SAMPLE_RATES: [u32; 12] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000,
+];
+
+/// 44.1 kHz 和 48 kHz 长窗口的频带边界
+const SWB_OFFSETS_LONG: [usize; 50] = [
+    0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 48, 56, 64, 72, 80, 88, 96, 108, 120, 132, 144, 160,
+    176, 196, 216, 240, 264, 292, 320, 352, 384, 416, 448, 480, 512, 544, 576, 608, 640, 672, 704,
+    736, 768, 800, 832, 864, 896, 928, 1024,
+];
+
+/// 转义码本（11）的码长，下标为 `17 * |x| + |y|`，16 表示转义
+#[rustfmt::skip]
+const ESC_CODEBOOK_LENS: [u8; 289] = [
+     4,  5,  6,  7,  8,  8,  9, 10, 10, 10, 11, 11, 12, 11, 12, 12,
+    10,  5,  4,  5,  6,  7,  7,  8,  8,  9,  9,  9, 10, 10, 10, 10,
+    11,  8,  6,  5,  5,  6,  7,  7,  8,  8,  8,  9,  9,  9, 10, 10,
+    10, 10,  8,  7,  6,  6,  6,  7,  7,  8,  8,  8,  9,  9,  9, 10,
+    10, 10, 10,  8,  8,  7,  7,  7,  7,  8,  8,  8,  8,  9,  9,  9,
+    10, 10, 10, 10,  8,  8,  7,  7,  7,  7,  8,  8,  8,  9,  9,  9,
+     9, 10, 10, 10, 10,  8,  9,  8,  8,  8,  8,  8,  8,  8,  9,  9,
+     9, 10, 10, 10, 10, 10,  8,  9,  8,  8,  8,  8,  8,  8,  9,  9,
+     9, 10, 10, 10, 10, 10, 10,  8, 10,  9,  8,  8,  9,  9,  9,  9,
+     9, 10, 10, 10, 10, 10, 10, 11,  8, 10,  9,  9,  9,  9,  9,  9,
+     9, 10, 10, 10, 10, 10, 10, 11, 11,  8, 11,  9,  9,  9,  9,  9,
+     9, 10, 10, 10, 10, 10, 11, 10, 11, 11,  8, 11, 10,  9,  9, 10,
+     9, 10, 10, 10, 10, 10, 11, 11, 11, 11, 11,  8, 11, 10, 10, 10,
+    10, 10, 10, 10, 10, 10, 10, 11, 11, 11, 11, 11,  9, 11, 10,  9,
+     9, 10, 10, 10, 10, 10, 10, 11, 11, 11, 11, 11, 11,  9, 11, 10,
+    10, 10, 10, 10, 10, 10, 10, 10, 11, 11, 11, 11, 11, 11,  9, 12,
+    10, 10, 10, 10, 10, 10, 10, 11, 11, 11, 11, 11, 11, 12, 12,  9,
+     9,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  9,
+     5,
+];
+
+/// 转义码本（11）的码字
+#[rustfmt::skip]
+const ESC_CODEBOOK_CODES: [u16; 289] = [
+    0x000, 0x006, 0x019, 0x03d, 0x09c, 0x0c6, 0x1a7, 0x390,
+    0x3c2, 0x3df, 0x7e6, 0x7f3, 0xffb, 0x7ec, 0xffa, 0xffe,
+    0x38e, 0x005, 0x001, 0x008, 0x014, 0x037, 0x042, 0x092,
+    0x0af, 0x191, 0x1a5, 0x1b5, 0x39e, 0x3c0, 0x3a2, 0x3cd,
+    0x7d6, 0x0ae, 0x017, 0x007, 0x009, 0x018, 0x039, 0x040,
+    0x08e, 0x0a3, 0x0b8, 0x199, 0x1ac, 0x1c1, 0x3b1, 0x396,
+    0x3be, 0x3ca, 0x09d, 0x03c, 0x015, 0x016, 0x01a, 0x03b,
+    0x044, 0x091, 0x0a5, 0x0be, 0x196, 0x1ae, 0x1b9, 0x3a1,
+    0x391, 0x3a5, 0x3d5, 0x094, 0x09a, 0x036, 0x038, 0x03a,
+    0x041, 0x08c, 0x09b, 0x0b0, 0x0c3, 0x19e, 0x1ab, 0x1bc,
+    0x39f, 0x38f, 0x3a9, 0x3cf, 0x093, 0x0bf, 0x03e, 0x03f,
+    0x043, 0x045, 0x09e, 0x0a7, 0x0b9, 0x194, 0x1a2, 0x1ba,
+    0x1c3, 0x3a6, 0x3a7, 0x3bb, 0x3d4, 0x09f, 0x1a0, 0x08f,
+    0x08d, 0x090, 0x098, 0x0a6, 0x0b6, 0x0c4, 0x19f, 0x1af,
+    0x1bf, 0x399, 0x3bf, 0x3b4, 0x3c9, 0x3e7, 0x0a8, 0x1b6,
+    0x0ab, 0x0a4, 0x0aa, 0x0b2, 0x0c2, 0x0c5, 0x198, 0x1a4,
+    0x1b8, 0x38c, 0x3a4, 0x3c4, 0x3c6, 0x3dd, 0x3e8, 0x0ad,
+    0x3af, 0x192, 0x0bd, 0x0bc, 0x18e, 0x197, 0x19a, 0x1a3,
+    0x1b1, 0x38d, 0x398, 0x3b7, 0x3d3, 0x3d1, 0x3db, 0x7dd,
+    0x0b4, 0x3de, 0x1a9, 0x19b, 0x19c, 0x1a1, 0x1aa, 0x1ad,
+    0x1b3, 0x38b, 0x3b2, 0x3b8, 0x3ce, 0x3e1, 0x3e0, 0x7d2,
+    0x7e5, 0x0b7, 0x7e3, 0x1bb, 0x1a8, 0x1a6, 0x1b0, 0x1b2,
+    0x1b7, 0x39b, 0x39a, 0x3ba, 0x3b5, 0x3d6, 0x7d7, 0x3e4,
+    0x7d8, 0x7ea, 0x0ba, 0x7e8, 0x3a0, 0x1bd, 0x1b4, 0x38a,
+    0x1c4, 0x392, 0x3aa, 0x3b0, 0x3bc, 0x3d7, 0x7d4, 0x7dc,
+    0x7db, 0x7d5, 0x7f0, 0x0c1, 0x7fb, 0x3c8, 0x3a3, 0x395,
+    0x39d, 0x3ac, 0x3ae, 0x3c5, 0x3d8, 0x3e2, 0x3e6, 0x7e4,
+    0x7e7, 0x7e0, 0x7e9, 0x7f7, 0x190, 0x7f2, 0x393, 0x1be,
+    0x1c0, 0x394, 0x397, 0x3ad, 0x3c3, 0x3c1, 0x3d2, 0x7da,
+    0x7d9, 0x7df, 0x7eb, 0x7f4, 0x7fa, 0x195, 0x7f8, 0x3bd,
+    0x39c, 0x3ab, 0x3a8, 0x3b3, 0x3b9, 0x3d0, 0x3e3, 0x3e5,
+    0x7e2, 0x7de, 0x7ed, 0x7f1, 0x7f9, 0x7fc, 0x193, 0xffd,
+    0x3dc, 0x3b6, 0x3c7, 0x3cc, 0x3cb, 0x3d9, 0x3da, 0x7d3,
+    0x7e1, 0x7ee, 0x7ef, 0x7f5, 0x7f6, 0xffc, 0xfff, 0x19d,
+    0x1c2, 0x0b5, 0x0a1, 0x096, 0x097, 0x095, 0x099, 0x0a0,
+    0x0a2, 0x0ac, 0x0a9, 0x0b1, 0x0b3, 0x0bb, 0x0c0, 0x18f,
+    0x004,
+];
+
+/// 码本编号
+const ZERO_HCB: u32 = 0;
+const ESC_HCB: u32 = 11;
+
+/// 语法元素类型
+const ID_SCE: u32 = 0;
+const ID_CPE: u32 = 1;
+const ID_END: u32 = 7;
+
+/// 转义码本能表示的最大量化值
+const MAX_QUANT: u32 = 8191;
+
+/// 比例因子差值为 0 的码字（1 位的 `0`）
+const SCF_ZERO_BITS: u32 = 1;
+
+/// 每个声道每帧的最大比特数
+const MAX_CHANNEL_BITS: usize = 6144;
+
+/// 输入时间戳与按采样数推算的时间戳相差超过该时长（纳秒）时重新对齐
+const SYNC_TOLERANCE: u64 = 20_000_000;
+
+/// 采样率对应的序号
+///
+/// # 参数
+///
+/// * `sample_rate` - 采样率
+///
+/// # 返回值
+///
+/// 返回 `Option<u8>`，不是 AAC 支持的采样率时返回 `None`
+pub fn sample_rate_index(sample_rate: u32) -> Option<u8> {
+    SAMPLE_RATES
+        .iter()
+        .position(|rate| *rate == sample_rate)
+        .map(|index| index as u8)
+}
+
+/// 静音帧，所有频带都为零
+///
+/// # 参数
+///
+/// * `channels` - 声道数，1 或 2
+pub fn silent_frame(channels: u16) -> Vec<u8> {
+    let channels = channels.clamp(1, 2) as usize;
+    let mut writer = BitWriter::default();
+    write_element_header(&mut writer, channels);
+    for _ in 0..channels {
+        // 全局增益和 ics_info，没有频带
+        writer.put(0, 8);
+        writer.put(0, 11);
+        // 没有 pulse、TNS 和增益控制
+        writer.put(0, 3);
+    }
+    writer.put(ID_END, 3);
+
+    writer.finish()
+}
+
+/// 写入单声道（SCE）或双声道（CPE）元素的头部
+fn write_element_header(writer: &mut BitWriter, channels: usize) {
+    if channels == 1 {
+        writer.put(ID_SCE, 3);
+        writer.put(0, 4);
+    } else {
+        writer.put(ID_CPE, 3);
+        writer.put(0, 4);
+        // 两个声道各自使用独立的窗口信息
+        writer.put(0, 1);
+    }
+}
+
+/// 按位写入，高位在前
+#[derive(Default)]
+struct BitWriter {
+    data: Vec<u8>,
+    /// 未写满一个字节的位
+    acc: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u32, bits: u32) {
+        for i in (0..bits).rev() {
+            self.acc = (self.acc << 1) | ((value >> i) & 1);
+            self.bits += 1;
+            if self.bits == 8 {
+                self.data.push(self.acc as u8);
+                (self.acc, self.bits) = (0, 0);
+            }
+        }
+    }
+
+    /// 已写入的位数
+    fn len(&self) -> usize {
+        self.data.len() * 8 + self.bits as usize
+    }
+
+    /// 补零到整字节
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.put(0, 8 - self.bits);
+        }
+        self.data
+    }
+}
+
+/// 长窗口的 MDCT，2048 个输入采样变换为 1024 个频谱系数
+///
+/// 折叠为 1024 点的 DCT-IV，再通过 512 点的复数 FFT 计算
+struct Mdct {
+    fft: Fft,
+    /// 正弦窗
+    window: Vec<f32>,
+    /// FFT 前后的旋转因子
+    twiddle: Vec<Complex>,
+    buffer: Vec<Complex>,
+    output: Vec<Complex>,
+}
+
+impl Mdct {
+    fn new() -> Self {
+        let n = 2 * AAC_FRAME_SAMPLES;
+        let window = (0..n)
+            .map(|i| (std::f64::consts::PI * (i as f64 + 0.5) / n as f64).sin() as f32)
+            .collect();
+        let twiddle = (0..AAC_FRAME_SAMPLES / 2)
+            .map(|i| {
+                let theta = -std::f64::consts::PI * (i as f64 + 0.25) / AAC_FRAME_SAMPLES as f64;
+                Complex::new(theta.cos() as f32, theta.sin() as f32)
+            })
+            .collect();
+
+        Self {
+            fft: Fft::new(AAC_FRAME_SAMPLES / 2),
+            window,
+            twiddle,
+            buffer: vec![Complex::default(); AAC_FRAME_SAMPLES / 2],
+            output: vec![Complex::default(); AAC_FRAME_SAMPLES / 2],
+        }
+    }
+
+    /// 加窗并变换，`X[k] = 2 * Σ w[n] x[n] cos(π / M * (n + 1/2 + M/2) * (k + 1/2))`
+    ///
+    /// # 参数
+    ///
+    /// * `input` - 上一帧和当前帧的采样
+    /// * `spectrum` - 输出的频谱系数
+    fn forward(&mut self, input: &[f32], spectrum: &mut [f32]) {
+        let m = AAC_FRAME_SAMPLES;
+        let half = m / 2;
+        let z = |i: usize| self.window[i] * input[i];
+
+        // 折叠：(a, b, c, d) -> (-c_r - d, a - b_r)
+        let folded = |i: usize| {
+            if i < half {
+                -z(3 * half - 1 - i) - z(3 * half + i)
+            } else {
+                z(i - half) - z(3 * half - 1 - i)
+            }
+        };
+        for (i, value) in self.buffer.iter_mut().enumerate() {
+            *value = Complex::new(folded(2 * i), folded(m - 1 - 2 * i)) * self.twiddle[i];
+        }
+        self.fft.fft(&self.buffer, &mut self.output);
+        for (i, value) in self.output.iter().enumerate() {
+            let value = *value * self.twiddle[i] * 2.;
+            spectrum[2 * i] = value.re;
+            spectrum[m - 1 - 2 * i] = -value.im;
+        }
+    }
+}
+
+/// AAC-LC 编码器
+pub struct AacEncoder {
+    settings: AudioEncoderSettings,
+    mdct: Mdct,
+    /// 每个声道上一帧和当前帧的采样（16 位整数范围）
+    blocks: Vec<Vec<f32>>,
+    /// 等待凑满一帧的交错采样
+    pending: Vec<f32>,
+    /// 第 0 个采样的时间戳（纳秒），可以为负
+    anchor: Option<i64>,
+    /// 已收到的采样帧数
+    received: u64,
+    /// 已编码的帧数
+    encoded: u64,
+    /// 带宽以内的频带数
+    bands: usize,
+    /// 每帧的比特预算
+    frame_bits: usize,
+}
+
+impl AacEncoder {
+    /// 创建音频编码器
+    ///
+    /// # 参数
+    ///
+    /// * `settings` - 输出的采样率、声道数和码率
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，采样率不是 44.1/48 kHz 或声道数不是 1、2 时返回错误
+    pub fn new(settings: AudioEncoderSettings) -> Result<Self> {
+        if !matches!(settings.sample_rate, 44100 | 48000) {
+            return Err(anyhow!(
+                "unsupported AAC sample rate: {}",
+                settings.sample_rate
+            ));
+        }
+        if !matches!(settings.channels, 1 | 2) {
+            return Err(anyhow!("unsupported AAC channels: {}", settings.channels));
+        }
+        if settings.bitrate == 0 {
+            return Err(anyhow!("invalid AAC bitrate: {}", settings.bitrate));
+        }
+
+        let channels = settings.channels as usize;
+        // 每个声道的码率越高，保留的带宽越宽
+        let cutoff = (settings.bitrate as usize * 200 / channels).clamp(8000, 16000);
+        let bands = SWB_OFFSETS_LONG[..SWB_OFFSETS_LONG.len() - 1]
+            .iter()
+            .take_while(|offset| **offset * settings.sample_rate as usize / 2048 < cutoff)
+            .count();
+        let frame_bits = (settings.bitrate as usize * 1000 * AAC_FRAME_SAMPLES
+            / settings.sample_rate as usize)
+            .min(MAX_CHANNEL_BITS * channels);
+
+        Ok(Self {
+            settings,
+            mdct: Mdct::new(),
+            blocks: vec![vec![0.; 2 * AAC_FRAME_SAMPLES]; channels],
+            pending: vec![],
+            anchor: None,
+            received: 0,
+            encoded: 0,
+            bands,
+            frame_bits,
+        })
+    }
+
+    /// 编码设置
+    pub fn settings(&self) -> &AudioEncoderSettings {
+        &self.settings
+    }
+
+    /// `frames` 个采样帧的时长（纳秒）
+    fn duration(&self, frames: i64) -> i64 {
+        (frames as i128 * 1_000_000_000 / self.settings.sample_rate as i128) as i64
+    }
+
+    /// 第 `index` 个采样帧的时间戳（纳秒）
+    fn sample_time(&self, index: i64) -> i64 {
+        self.anchor.unwrap_or(0) + self.duration(index)
+    }
+
+    /// 转换为输出格式，每凑满 1024 个采样编码一帧
+    ///
+    /// 第 n 帧解码得到的是第 n - 1 帧的采样（第一帧之前为静音），时间戳按此对齐，
+    /// 最早不早于 0
+    ///
+    /// # 参数
+    ///
+    /// * `frame` - 混音后的音频块
+    /// * `pts` - 显示时间戳（纳秒）
+    ///
+    /// # 返回值
+    ///
+    /// 返回编码出的包，采样不足一帧时为空
+    pub fn encode(&mut self, frame: &AudioFrame, pts: u64) -> Vec<EncodedPacket> {
+        let frame = frame.convert(self.settings.sample_rate, self.settings.channels);
+        let received = self.received as i64;
+        if self.anchor.is_none() || self.sample_time(received).abs_diff(pts as i64) > SYNC_TOLERANCE
+        {
+            self.anchor = Some(pts as i64 - self.duration(received));
+        }
+        self.received += frame.frames() as u64;
+        self.pending.extend_from_slice(&frame.data);
+
+        let channels = self.settings.channels as usize;
+        let frame_len = AAC_FRAME_SAMPLES * channels;
+        let mut packets = vec![];
+        while self.pending.len() >= frame_len {
+            for (channel, block) in self.blocks.iter_mut().enumerate() {
+                block.copy_within(AAC_FRAME_SAMPLES.., 0);
+                for (i, sample) in block[AAC_FRAME_SAMPLES..].iter_mut().enumerate() {
+                    *sample = self.pending[i * channels + channel].clamp(-1., 1.) * 32768.;
+                }
+            }
+            self.pending.drain(..frame_len);
+
+            let data = self.encode_frame();
+            let start = (self.encoded as i64 - 1) * AAC_FRAME_SAMPLES as i64;
+            let pts = self.sample_time(start).max(0) as u64;
+            self.encoded += 1;
+
+            packets.push(EncodedPacket {
+                kind: PacketKind::Audio,
+                pts,
+                dts: pts,
+                keyframe: true,
+                priority: FramePriority::Audio,
+                header: None,
+                data,
+            });
+        }
+
+        packets
+    }
+
+    /// 编码缓存中的一帧
+    fn encode_frame(&mut self) -> Vec<u8> {
+        let mut spectra = vec![vec![0f32; AAC_FRAME_SAMPLES]; self.blocks.len()];
+        for (block, spectrum) in self.blocks.iter().zip(spectra.iter_mut()) {
+            self.mdct.forward(block, spectrum);
+        }
+
+        // 最大值不能超过转义码本的范围，增益越小量化越精细
+        let end = SWB_OFFSETS_LONG[self.bands];
+        let peak = spectra
+            .iter()
+            .flat_map(|spectrum| spectrum[..end].iter())
+            .fold(0f32, |peak, value| peak.max(value.abs()));
+        let min_gain = if peak > 0. {
+            let ratio = peak as f64 / (MAX_QUANT as f64).powf(4. / 3.);
+            (100. + 4. * ratio.log2()).ceil().clamp(0., 255.) as u32
+        } else {
+            0
+        };
+
+        let (mut low, mut high) = (min_gain, 255);
+        while low < high {
+            let gain = (low + high) / 2;
+            if self.write_frame(&spectra, gain).len() <= self.frame_bits {
+                high = gain;
+            } else {
+                low = gain + 1;
+            }
+        }
+
+        self.write_frame(&spectra, low).finish()
+    }
+
+    /// 按全局增益量化并写入一帧
+    fn write_frame(&self, spectra: &[Vec<f32>], gain: u32) -> BitWriter {
+        let step = 2f64.powf(0.25 * (gain as f64 - 100.)) as f32;
+        let end = SWB_OFFSETS_LONG[self.bands];
+
+        let mut writer = BitWriter::default();
+        write_element_header(&mut writer, spectra.len());
+        for spectrum in spectra {
+            let quantized: Vec<i32> = spectrum[..end]
+                .iter()
+                .map(|value| {
+                    let magnitude = ((value.abs() / step).powf(0.75) + 0.4054) as u32;
+                    magnitude.min(MAX_QUANT) as i32 * value.signum() as i32
+                })
+                .collect();
+            self.write_channel(&mut writer, &quantized, gain);
+        }
+        writer.put(ID_END, 3);
+
+        writer
+    }
+
+    /// 写入一个声道的 `individual_channel_stream`
+    fn write_channel(&self, writer: &mut BitWriter, quantized: &[i32], gain: u32) {
+        let codebooks: Vec<u32> = SWB_OFFSETS_LONG[..=self.bands]
+            .windows(2)
+            .map(|band| {
+                if quantized[band[0]..band[1]].iter().all(|value| *value == 0) {
+                    ZERO_HCB
+                } else {
+                    ESC_HCB
+                }
+            })
+            .collect();
+        let max_sfb = codebooks
+            .iter()
+            .rposition(|codebook| *codebook != ZERO_HCB)
+            .map_or(0, |band| band + 1);
+
+        writer.put(gain, 8);
+        // ics_info：长窗口、正弦窗、没有预测
+        writer.put(0, 1);
+        writer.put(0, 2);
+        writer.put(0, 1);
+        writer.put(max_sfb as u32, 6);
+        writer.put(0, 1);
+
+        // section_data：相邻且码本相同的频带合并为一段
+        let mut band = 0;
+        while band < max_sfb {
+            let codebook = codebooks[band];
+            let length = codebooks[band..max_sfb]
+                .iter()
+                .take_while(|item| **item == codebook)
+                .count();
+            writer.put(codebook, 4);
+            let mut remaining = length;
+            while remaining >= 31 {
+                writer.put(31, 5);
+                remaining -= 31;
+            }
+            writer.put(remaining as u32, 5);
+            band += length;
+        }
+
+        // scale_factor_data：所有频带的比例因子等于全局增益，差值都为 0
+        for codebook in &codebooks[..max_sfb] {
+            if *codebook != ZERO_HCB {
+                writer.put(0, SCF_ZERO_BITS);
+            }
+        }
+
+        // 没有 pulse、TNS 和增益控制
+        writer.put(0, 3);
+
+        for (band, codebook) in codebooks[..max_sfb].iter().enumerate() {
+            if *codebook == ZERO_HCB {
+                continue;
+            }
+            let values = &quantized[SWB_OFFSETS_LONG[band]..SWB_OFFSETS_LONG[band + 1]];
+            for pair in values.chunks_exact(2) {
+                let (x, y) = (pair[0].unsigned_abs(), pair[1].unsigned_abs());
+                let index = (17 * x.min(16) + y.min(16)) as usize;
+                writer.put(
+                    ESC_CODEBOOK_CODES[index] as u32,
+                    ESC_CODEBOOK_LENS[index] as u32,
+                );
+                for value in pair.iter().filter(|value| **value != 0) {
+                    writer.put((*value < 0) as u32, 1);
+                }
+                for value in [x, y].into_iter().filter(|value| *value >= 16) {
+                    // 前缀为 n 个 1 和一个 0，之后是 n + 4 位的余数
+                    let n = 31 - value.leading_zeros() - 4;
+                    writer.put((1 << (n + 1)) - 2, n + 1);
+                    writer.put(value - (1 << (n + 4)), n + 4);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symphonia::core::{
+        audio::{Channels, SampleBuffer},
+        codecs::{CodecParameters, DecoderOptions, CODEC_TYPE_AAC},
+        formats::Packet,
+    };
+
+    use super::*;
+
+    const RATE: u32 = 44100;
+
+    fn settings(channels: u16) -> AudioEncoderSettings {
+        AudioEncoderSettings {
+            sample_rate: RATE,
+            channels,
+            bitrate: 80 * channels as u32,
+        }
+    }
+
+    /// 每个声道一个正弦波，频率各不相同
+    fn sine(channels: u16, start: usize, frames: usize) -> Vec<f32> {
+        let mut data = Vec::with_capacity(frames * channels as usize);
+        for i in start..start + frames {
+            for channel in 0..channels {
+                let freq = 440. * (channel + 1) as f64;
+                let phase = 2. * std::f64::consts::PI * freq * i as f64 / RATE as f64;
+                data.push((0.5 * phase.sin()) as f32);
+            }
+        }
+        data
+    }
+
+    fn audio_frame(channels: u16, data: Vec<f32>) -> AudioFrame {
+        AudioFrame {
+            sample_rate: RATE,
+            channels,
+            timestamp: 0,
+            data,
+        }
+    }
+
+    /// 用 symphonia 解码，返回交错的采样
+    fn decode(config: [u8; 2], channels: u16, packets: &[EncodedPacket]) -> Vec<f32> {
+        let layout = match channels {
+            1 => Channels::FRONT_LEFT,
+            _ => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        };
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(CODEC_TYPE_AAC)
+            .with_sample_rate(RATE)
+            .with_channels(layout)
+            .with_extra_data(Box::new(config));
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&params, &DecoderOptions::default())
+            .unwrap();
+
+        let mut output = vec![];
+        for (i, packet) in packets.iter().enumerate() {
+            let packet = Packet::new_from_slice(0, i as u64 * 1024, 1024, &packet.data);
+            let decoded = decoder.decode(&packet).unwrap();
+            let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+            buffer.copy_interleaved_ref(decoded);
+            output.extend_from_slice(buffer.samples());
+        }
+        output
+    }
+
+    #[test]
+    fn mdct_matches_direct_formula() {
+        let m = AAC_FRAME_SAMPLES;
+        let input: Vec<f32> = (0..2 * m)
+            .map(|i| ((i * 7919 % 1000) as f32 / 500. - 1.) * 1000.)
+            .collect();
+        let mut mdct = Mdct::new();
+        let mut spectrum = vec![0.; m];
+        mdct.forward(&input, &mut spectrum);
+
+        for k in [0, 1, 2, 100, 511, 512, 777, m - 1] {
+            let terms: Vec<f64> = (0..2 * m)
+                .map(|n| {
+                    let angle = std::f64::consts::PI / m as f64
+                        * (n as f64 + 0.5 + m as f64 / 2.)
+                        * (k as f64 + 0.5);
+                    2. * mdct.window[n] as f64 * input[n] as f64 * angle.cos()
+                })
+                .collect();
+            let expected: f64 = terms.iter().sum();
+            // 单精度累加的误差与各项的绝对值之和成正比
+            let scale: f64 = terms.iter().map(|term| term.abs()).sum();
+            let error = (spectrum[k] as f64 - expected).abs();
+            assert!(error < 1e-5 * scale, "k={k}: {} vs {expected}", spectrum[k]);
+        }
+    }
+
+    #[test]
+    fn round_trip_through_decoder() {
+        for channels in [1, 2] {
+            let mut encoder = AacEncoder::new(settings(channels)).unwrap();
+            let frames = RATE as usize;
+            let input = sine(channels, 0, frames);
+            let mut packets = vec![];
+            // 按 10 ms 分块送入，与混音器的块大小无关
+            for (i, chunk) in input.chunks(441 * channels as usize).enumerate() {
+                let pts = i as u64 * 10_000_000;
+                packets.extend(encoder.encode(&audio_frame(channels, chunk.to_vec()), pts));
+            }
+            assert_eq!(packets.len(), frames / AAC_FRAME_SAMPLES);
+            let budget = settings(channels).bitrate as usize * 1000 * 1024 / RATE as usize;
+            assert!(packets
+                .iter()
+                .all(|packet| packet.data.len() * 8 <= budget + 7));
+
+            // 解码输出比输入晚一帧
+            let output = decode(settings(channels).decoder_config(), channels, &packets);
+            let delay = AAC_FRAME_SAMPLES * channels as usize;
+            let compared = &output[delay..];
+            let (mut signal, mut noise) = (0f64, 0f64);
+            for (decoded, original) in compared.iter().zip(&input) {
+                signal += (*original as f64).powi(2);
+                noise += (*decoded as f64 - *original as f64).powi(2);
+            }
+            let snr = 10. * (signal / noise).log10();
+            assert!(snr > 30., "{channels} channels: SNR {snr:.1} dB");
+        }
+    }
+
+    #[test]
+    fn silent_frame_decodes_to_silence() {
+        for channels in [1, 2] {
+            let packet = EncodedPacket {
+                kind: PacketKind::Audio,
+                pts: 0,
+                dts: 0,
+                keyframe: true,
+                priority: FramePriority::Audio,
+                header: None,
+                data: silent_frame(channels),
+            };
+            let output = decode(settings(channels).decoder_config(), channels, &[packet]);
+            assert_eq!(output.len(), AAC_FRAME_SAMPLES * channels as usize);
+            assert!(output.iter().all(|sample| *sample == 0.));
+        }
+    }
+
+    #[test]
+    fn packet_timestamps_follow_sample_count() {
+        let mut encoder = AacEncoder::new(settings(2)).unwrap();
+        let frame_ns = AAC_FRAME_SAMPLES as u64 * 1_000_000_000 / RATE as u64;
+        let chunk = |start| audio_frame(2, sine(2, start, 441));
+
+        // 输入从 100 ms 开始，第一帧对应之前的静音
+        let mut packets = vec![];
+        for i in 0..300 {
+            packets.extend(encoder.encode(&chunk(i * 441), 100_000_000 + i as u64 * 10_000_000));
+        }
+        assert_eq!(packets[0].pts, 100_000_000 - frame_ns);
+        for (i, packet) in packets.iter().enumerate() {
+            let expected = 100_000_000 + (i as u64 * 1024 * 1_000_000_000 / RATE as u64);
+            assert!(packet.pts.abs_diff(expected - frame_ns) <= 1, "packet {i}");
+            assert_eq!(packet.dts, packet.pts);
+        }
+
+        // 输入时间戳跳变超过容差后重新对齐
+        let last = packets.last().unwrap().pts;
+        let gap = 10_000_000_000;
+        let mut later = vec![];
+        for i in 0..20 {
+            later.extend(encoder.encode(&chunk(i * 441), gap + i as u64 * 10_000_000));
+        }
+        assert!(later[0].pts > last + gap / 2);
+        assert!(later.windows(2).all(|pair| pair[1].pts > pair[0].pts));
+
+        // 时间戳最早为 0
+        let mut encoder = AacEncoder::new(settings(1)).unwrap();
+        let packets = encoder.encode(&audio_frame(1, sine(1, 0, 2048)), 0);
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].pts, 0);
+        assert_eq!(packets[1].pts, 0);
+    }
+
+    #[test]
+    fn rejects_unsupported_settings() {
+        let mut invalid = settings(2);
+        invalid.sample_rate = 22050;
+        assert!(AacEncoder::new(invalid).is_err());
+        let mut invalid = settings(2);
+        invalid.channels = 6;
+        assert!(AacEncoder::new(invalid).is_err());
+        assert_eq!(settings(2).decoder_config(), [0x12, 0x10]);
+        assert_eq!(settings(1).decoder_config(), [0x12, 0x08]);
+    }
+}
@@ -0,0 +1,463 @@
+/// 图片解码模块
+///
+/// 支持静态图片和 GIF、APNG、WebP 动画，动画的每一帧都是合成后的完整 RGBA 图像
+use std::{fs, io::Cursor, path::Path, time::Duration};
+
+use anyhow::anyhow;
+use image::{
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    metadata::LoopCount,
+    AnimationDecoder, ImageFormat, RgbaImage,
+};
+
+use crate::Result;
+
+/// 帧延迟低于此值时按浏览器的做法视为 100 毫秒
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(10);
+
+/// 帧延迟过短时使用的默认延迟
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// 动画中的一帧
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    /// RGBA 图像
+    pub image: RgbaImage,
+    /// 显示时长
+    pub delay: Duration,
+}
+
+/// 解码后的图片，静态图片只有一帧
+#[derive(Debug, Clone)]
+pub struct AnimatedImage {
+    pub width: u32,
+    pub height: u32,
+    pub frames: Vec<AnimationFrame>,
+    /// 播放次数，为 `None` 时无限循环
+    pub loop_count: Option<u32>,
+}
+
+impl AnimatedImage {
+    /// 从文件加载图片
+    ///
+    /// # 参数
+    ///
+    /// * `path` - 图片路径
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，文件无法读取或解码时返回错误
+    pub fn load(path: &Path) -> Result<Self> {
+        // TGA 等格式没有文件头标识，用扩展名作为后备
+        Self::decode_with_hint(&fs::read(path)?, ImageFormat::from_path(path).ok())
+    }
+
+    /// 从内存中解码图片，格式由文件头判断
+    ///
+    /// # 参数
+    ///
+    /// * `bytes` - 图片数据
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，格式不支持或数据损坏时返回错误
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        Self::decode_with_hint(bytes, None)
+    }
+
+    /// 解码图片，无法从文件头判断格式时使用 `hint`
+    fn decode_with_hint(bytes: &[u8], hint: Option<ImageFormat>) -> Result<Self> {
+        let format = image::guess_format(bytes)
+            .ok()
+            .or(hint)
+            .ok_or(anyhow!("unknown image format"))?;
+
+        match format {
+            ImageFormat::Gif => Self::from_decoder(GifDecoder::new(Cursor::new(bytes))?),
+            ImageFormat::Png => {
+                let decoder = PngDecoder::new(Cursor::new(bytes))?;
+                if decoder.is_apng()? {
+                    return Self::from_decoder(decoder.apng()?);
+                }
+                Self::from_static(bytes, format)
+            }
+            ImageFormat::WebP => {
+                let decoder = WebPDecoder::new(Cursor::new(bytes))?;
+                if decoder.has_animation() {
+                    return Self::from_decoder(decoder);
+                }
+                Self::from_static(bytes, format)
+            }
+            _ => Self::from_static(bytes, format),
+        }
+    }
+
+    /// 解码静态图片
+    fn from_static(bytes: &[u8], format: ImageFormat) -> Result<Self> {
+        let image = image::load_from_memory_with_format(bytes, format)?.to_rgba8();
+
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            frames: vec![AnimationFrame {
+                image,
+                delay: Duration::ZERO,
+            }],
+            loop_count: None,
+        })
+    }
+
+    /// 解码全部动画帧
+    fn from_decoder<'a>(decoder: impl AnimationDecoder<'a>) -> Result<Self> {
+        let loop_count = match decoder.loop_count() {
+            LoopCount::Infinite => None,
+            LoopCount::Finite(count) => Some(count.get()),
+        };
+
+        let mut frames = vec![];
+        for frame in decoder.into_frames() {
+            let frame = frame?;
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay = Duration::from_micros(numer as u64 * 1000 / denom.max(1) as u64);
+
+            frames.push(AnimationFrame {
+                image: frame.into_buffer(),
+                delay: if delay < MIN_FRAME_DELAY {
+                    DEFAULT_FRAME_DELAY
+                } else {
+                    delay
+                },
+            });
+        }
+
+        let (width, height) = frames
+            .first()
+            .map(|frame| frame.image.dimensions())
+            .ok_or(anyhow!("animation has no frames"))?;
+
+        Ok(Self {
+            width,
+            height,
+            frames,
+            loop_count,
+        })
+    }
+
+    /// 是否是多帧动画
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// 播放一遍的总时长
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.delay).sum()
+    }
+
+    /// 计算播放到指定时间时显示的帧
+    ///
+    /// # 参数
+    ///
+    /// * `elapsed` - 从开始播放经过的时间
+    ///
+    /// # 返回值
+    ///
+    /// 返回帧索引，播放次数用完后停在最后一帧
+    pub fn frame_at(&self, elapsed: Duration) -> usize {
+        let duration = self.duration();
+        if !self.is_animated() || duration.is_zero() {
+            return 0;
+        }
+
+        let loops = elapsed.as_nanos() / duration.as_nanos();
+        if matches!(self.loop_count, Some(count) if loops >= count as u128) {
+            return self.frames.len() - 1;
+        }
+
+        let mut position = Duration::from_nanos((elapsed.as_nanos() % duration.as_nanos()) as u64);
+        for (i, frame) in self.frames.iter().enumerate() {
+            if position < frame.delay {
+                return i;
+            }
+            position -= frame.delay;
+        }
+
+        self.frames.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{
+        codecs::{
+            gif::{GifEncoder, Repeat},
+            png::PngEncoder,
+            webp::WebPEncoder,
+        },
+        Delay, ExtendedColorType, Frame, ImageEncoder, Rgba,
+    };
+
+    use super::*;
+
+    /// 测试帧的颜色
+    const COLORS: [[u8; 4]; 3] = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+
+    fn solid(color: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(4, 3, Rgba(color))
+    }
+
+    fn animation(delays_ms: &[u64], loop_count: Option<u32>) -> AnimatedImage {
+        AnimatedImage {
+            width: 4,
+            height: 3,
+            frames: delays_ms
+                .iter()
+                .map(|&delay| AnimationFrame {
+                    image: solid(COLORS[0]),
+                    delay: Duration::from_millis(delay),
+                })
+                .collect(),
+            loop_count,
+        }
+    }
+
+    fn assert_colors(image: &AnimatedImage) {
+        assert_eq!((image.width, image.height), (4, 3));
+        assert_eq!(image.frames.len(), COLORS.len());
+        for (frame, color) in image.frames.iter().zip(COLORS) {
+            assert_eq!(frame.image.get_pixel(2, 1).0, color);
+        }
+    }
+
+    /// 在一段数据前后加上长度和 CRC，组成 PNG 数据块
+    fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut crc = 0xffff_ffffu32;
+        for &byte in kind.iter().chain(data) {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xedb8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        chunk.extend_from_slice(&(!crc).to_be_bytes());
+        chunk
+    }
+
+    /// 把 PNG 文件拆成数据块
+    fn png_chunks(bytes: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        let mut chunks = vec![];
+        let mut offset = 8;
+        while offset < bytes.len() {
+            let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+            let kind = bytes[offset + 4..offset + 8].try_into().unwrap();
+            chunks.push((kind, bytes[offset + 8..offset + 8 + length].to_vec()));
+            offset += length + 12;
+        }
+        chunks
+    }
+
+    /// 用每帧单独编码的 PNG 拼出 APNG
+    fn apng(delays_ms: &[u16], plays: u32) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut sequence = 0u32;
+        for (i, (color, delay)) in COLORS.iter().zip(delays_ms).enumerate() {
+            let mut png = vec![];
+            PngEncoder::new(&mut png)
+                .write_image(solid(*color).as_raw(), 4, 3, ExtendedColorType::Rgba8)
+                .unwrap();
+            let chunks = png_chunks(&png);
+
+            if i == 0 {
+                bytes.extend(png_chunk(b"IHDR", &chunks[0].1));
+                let mut actl = (COLORS.len() as u32).to_be_bytes().to_vec();
+                actl.extend_from_slice(&plays.to_be_bytes());
+                bytes.extend(png_chunk(b"acTL", &actl));
+            }
+
+            let mut fctl = sequence.to_be_bytes().to_vec();
+            for value in [4u32, 3, 0, 0] {
+                fctl.extend_from_slice(&value.to_be_bytes());
+            }
+            fctl.extend_from_slice(&delay.to_be_bytes());
+            fctl.extend_from_slice(&1000u16.to_be_bytes());
+            fctl.extend_from_slice(&[0, 0]);
+            bytes.extend(png_chunk(b"fcTL", &fctl));
+            sequence += 1;
+
+            for (kind, data) in chunks.iter().filter(|(kind, _)| kind == b"IDAT") {
+                if i == 0 {
+                    bytes.extend(png_chunk(kind, data));
+                } else {
+                    let mut fdat = sequence.to_be_bytes().to_vec();
+                    fdat.extend_from_slice(data);
+                    bytes.extend(png_chunk(b"fdAT", &fdat));
+                    sequence += 1;
+                }
+            }
+        }
+        bytes.extend(png_chunk(b"IEND", &[]));
+        bytes
+    }
+
+    /// 组成 RIFF 数据块，奇数长度补齐一个字节
+    fn riff_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = kind.to_vec();
+        chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn u24(value: u32) -> [u8; 3] {
+        let [a, b, c, _] = value.to_le_bytes();
+        [a, b, c]
+    }
+
+    /// 用每帧单独编码的无损 WebP 拼出动画 WebP
+    fn animated_webp(delay_ms: u32, loops: u16) -> Vec<u8> {
+        let mut vp8x = vec![0x12, 0, 0, 0];
+        vp8x.extend(u24(3));
+        vp8x.extend(u24(2));
+        let mut anim = vec![0; 4];
+        anim.extend_from_slice(&loops.to_le_bytes());
+
+        let mut body = b"WEBP".to_vec();
+        body.extend(riff_chunk(b"VP8X", &vp8x));
+        body.extend(riff_chunk(b"ANIM", &anim));
+        for color in COLORS {
+            let mut webp = vec![];
+            WebPEncoder::new_lossless(&mut webp)
+                .encode(solid(color).as_raw(), 4, 3, ExtendedColorType::Rgba8)
+                .unwrap();
+            // 编码结果为 RIFF 头加一个 VP8L 数据块
+            let vp8l = &webp[12..];
+            assert_eq!(&vp8l[..4], b"VP8L");
+
+            let mut anmf = vec![];
+            anmf.extend(u24(0));
+            anmf.extend(u24(0));
+            anmf.extend(u24(3));
+            anmf.extend(u24(2));
+            anmf.extend(u24(delay_ms));
+            anmf.push(0x02);
+            anmf.extend_from_slice(vp8l);
+            body.extend(riff_chunk(b"ANMF", &anmf));
+        }
+
+        riff_chunk(b"RIFF", &body)
+    }
+
+    #[test]
+    fn finite_loops_stop_on_the_last_frame() {
+        let image = animation(&[100, 100, 100], Some(2));
+        assert_eq!(image.frame_at(Duration::from_millis(150)), 1);
+        assert_eq!(image.frame_at(Duration::from_millis(350)), 0);
+        assert_eq!(image.frame_at(Duration::from_millis(550)), 2);
+        // 播放两遍后停在最后一帧
+        assert_eq!(image.frame_at(Duration::from_millis(650)), 2);
+        assert_eq!(image.frame_at(Duration::from_secs(60)), 2);
+
+        let image = animation(&[100, 100, 100], None);
+        assert_eq!(image.frame_at(Duration::from_millis(650)), 0);
+        assert_eq!(
+            image.frame_at(Duration::from_secs(60) + Duration::from_millis(150)),
+            1
+        );
+    }
+
+    #[test]
+    fn static_images_show_the_first_frame() {
+        let image = animation(&[0], None);
+        assert!(!image.is_animated());
+        assert_eq!(image.frame_at(Duration::from_secs(5)), 0);
+    }
+
+    #[test]
+    fn short_delays_are_clamped() {
+        let image = AnimatedImage::decode(&apng(&[5, 0, 40], 0)).unwrap();
+        let delays: Vec<_> = image.frames.iter().map(|frame| frame.delay).collect();
+        assert_eq!(
+            delays,
+            [
+                DEFAULT_FRAME_DELAY,
+                DEFAULT_FRAME_DELAY,
+                Duration::from_millis(40)
+            ]
+        );
+        assert_eq!(image.loop_count, None);
+    }
+
+    #[test]
+    fn decodes_gif() {
+        let mut bytes = vec![];
+        {
+            let mut encoder = GifEncoder::new(&mut bytes);
+            encoder.set_repeat(Repeat::Finite(3)).unwrap();
+            for color in COLORS {
+                encoder
+                    .encode_frame(Frame::from_parts(
+                        solid(color),
+                        0,
+                        0,
+                        Delay::from_numer_denom_ms(50, 1),
+                    ))
+                    .unwrap();
+            }
+        }
+
+        let image = AnimatedImage::decode(&bytes).unwrap();
+        assert_colors(&image);
+        assert!(image.is_animated());
+        assert_eq!(image.duration(), Duration::from_millis(150));
+        assert!(image.loop_count.is_some());
+    }
+
+    #[test]
+    fn decodes_apng() {
+        let image = AnimatedImage::decode(&apng(&[20, 30, 40], 2)).unwrap();
+        assert_colors(&image);
+        assert_eq!(image.duration(), Duration::from_millis(90));
+        assert_eq!(image.loop_count, Some(2));
+    }
+
+    #[test]
+    fn decodes_animated_webp() {
+        let image = AnimatedImage::decode(&animated_webp(60, 4)).unwrap();
+        assert_colors(&image);
+        assert_eq!(image.duration(), Duration::from_millis(180));
+        assert_eq!(image.loop_count, Some(4));
+    }
+
+    #[test]
+    fn loads_bmp_and_tga_by_extension() {
+        let dir = std::env::temp_dir().join(format!("animation-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        for extension in ["bmp", "tga"] {
+            let path = dir.join(format!("frame.{}", extension));
+            solid(COLORS[2]).save(&path).unwrap();
+
+            let image = AnimatedImage::load(&path).unwrap();
+            assert_eq!((image.width, image.height), (4, 3));
+            assert!(!image.is_animated());
+            assert_eq!(image.frames[0].image.get_pixel(0, 0).0, COLORS[2]);
+        }
+
+        // TGA 没有文件头标识，只能通过扩展名识别
+        let tga = fs::read(dir.join("frame.tga")).unwrap();
+        assert!(AnimatedImage::decode(&tga).is_err());
+        let renamed = dir.join("frame.img");
+        fs::write(&renamed, &tga).unwrap();
+        assert!(AnimatedImage::load(&renamed).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
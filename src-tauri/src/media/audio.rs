@@ -0,0 +1,152 @@
+/// 音频帧模块
+use serde::{Deserialize, Serialize};
+
+use crate::utils::profile::get_profile_config;
+
+/// 音频帧，采样为交错排列的 32 位浮点数
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioFrame {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// 时间戳（纳秒），使用合成时钟
+    pub timestamp: u64,
+    pub data: Vec<f32>,
+}
+
+impl AudioFrame {
+    /// 每个声道的采样数
+    pub fn frames(&self) -> usize {
+        self.data.len() / self.channels.max(1) as usize
+    }
+
+    /// 音频时长（纳秒）
+    pub fn duration_ns(&self) -> u64 {
+        self.frames() as u64 * 1_000_000_000 / self.sample_rate.max(1) as u64
+    }
+
+    /// 转换为指定的采样率和声道数
+    ///
+    /// 声道数不同时，单声道复制到所有声道，其他情况按声道序号对应，多出的声道平均混入前两个声道；
+    /// 采样率不同时使用线性插值
+    ///
+    /// # 参数
+    ///
+    /// * `sample_rate` - 目标采样率
+    /// * `channels` - 目标声道数
+    pub fn convert(&self, sample_rate: u32, channels: u16) -> AudioFrame {
+        let remixed = remix(&self.data, self.channels.max(1), channels.max(1));
+        let data = if sample_rate == self.sample_rate {
+            remixed
+        } else {
+            resample(&remixed, channels.max(1), self.sample_rate, sample_rate)
+        };
+
+        AudioFrame {
+            sample_rate,
+            channels,
+            timestamp: self.timestamp,
+            data,
+        }
+    }
+}
+
+/// 转换声道数
+fn remix(data: &[f32], from: u16, to: u16) -> Vec<f32> {
+    if from == to {
+        return data.to_vec();
+    }
+
+    let (from, to) = (from as usize, to as usize);
+    let mut output = Vec::with_capacity(data.len() / from * to);
+    for frame in data.chunks_exact(from) {
+        if from == 1 {
+            output.extend(std::iter::repeat_n(frame[0], to));
+            continue;
+        }
+
+        let extra = &frame[from.min(to)..];
+        let mix = if to <= 2 && !extra.is_empty() {
+            extra.iter().sum::<f32>() / extra.len() as f32
+        } else {
+            0.
+        };
+        for channel in 0..to {
+            let sample = frame.get(channel).copied().unwrap_or(0.);
+            output.push(match (to, channel) {
+                (1, _) => frame.iter().sum::<f32>() / from as f32,
+                (2, _) => sample + mix,
+                _ => sample,
+            });
+        }
+    }
+
+    output
+}
+
+/// 线性插值重采样
+fn resample(data: &[f32], channels: u16, from: u32, to: u32) -> Vec<f32> {
+    let channels = channels as usize;
+    let frames = data.len() / channels;
+    if frames == 0 {
+        return vec![];
+    }
+
+    let output_frames = (frames as u64 * to as u64 / from as u64) as usize;
+    let step = from as f64 / to as f64;
+    let mut output = Vec::with_capacity(output_frames * channels);
+    for i in 0..output_frames {
+        let position = i as f64 * step;
+        let index = position as usize;
+        let next = (index + 1).min(frames - 1);
+        let t = (position - index as f64) as f32;
+        for channel in 0..channels {
+            let a = data[index * channels + channel];
+            let b = data[next * channels + channel];
+            output.push(a + (b - a) * t);
+        }
+    }
+
+    output
+}
+
+/// 音频设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioInfo {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl Default for AudioInfo {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48000,
+            channels: 2,
+        }
+    }
+}
+
+impl AudioInfo {
+    /// 从当前配置文件的 `[Audio]` 节读取音频设置
+    pub fn load() -> Self {
+        let default = Self::default();
+
+        let sample_rate = get_profile_config("Audio", "SampleRate")
+            .and_then(|value| value.parse::<u32>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(default.sample_rate);
+        let channels = match get_profile_config("Audio", "ChannelSetup").as_deref() {
+            Some("Mono") => 1,
+            Some("2.1") => 3,
+            Some("4.0") => 4,
+            Some("4.1") => 5,
+            Some("5.1") => 6,
+            Some("7.1") => 8,
+            _ => default.channels,
+        };
+
+        Self {
+            sample_rate,
+            channels,
+        }
+    }
+}
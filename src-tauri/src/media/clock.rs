@@ -0,0 +1,57 @@
+/// 合成时钟模块
+///
+/// 视频合成、音频混音和媒体来源共用同一个时间起点，时间戳都是从起点开始的纳秒数
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// 时钟起点，第一次使用时确定
+    static ref EPOCH: Instant = Instant::now();
+}
+
+/// 当前的合成时钟时间（纳秒）
+pub fn clock_ns() -> u64 {
+    EPOCH.elapsed().as_nanos() as u64
+}
+
+/// 合成时钟时间对应的时刻
+///
+/// # 参数
+///
+/// * `timestamp` - 合成时钟时间（纳秒）
+pub fn instant_at(timestamp: u64) -> Instant {
+    *EPOCH + Duration::from_nanos(timestamp)
+}
+
+/// 时间戳与时钟相差超过该值（纳秒）时重新对齐
+const MAX_DRIFT_NS: u64 = 50_000_000;
+
+/// 采集音频的时间戳，按采样数连续递增，与时钟偏差过大时重新对齐
+///
+/// 用于音频采集设备、浏览器页面等只能在收到数据时读取时钟的来源
+#[derive(Default)]
+pub struct CaptureTimestamps {
+    next: Option<u64>,
+}
+
+impl CaptureTimestamps {
+    /// 计算刚收到的一块音频的时间戳
+    ///
+    /// # 参数
+    ///
+    /// * `frames` - 每声道的采样数
+    /// * `sample_rate` - 采样率
+    pub fn stamp(&mut self, frames: usize, sample_rate: u32) -> u64 {
+        let duration = frames as u64 * 1_000_000_000 / sample_rate.max(1) as u64;
+        // 收到时最后一个采样刚刚采集完成
+        let measured = clock_ns().saturating_sub(duration);
+        let timestamp = match self.next {
+            Some(next) if next.abs_diff(measured) <= MAX_DRIFT_NS => next,
+            _ => measured,
+        };
+        self.next = Some(timestamp + duration);
+
+        timestamp
+    }
+}
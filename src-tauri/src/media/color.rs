@@ -0,0 +1,274 @@
+/// 色彩空间模块
+///
+/// GPU 转换着色器与 CPU 参考实现共用这里的系数，保证两者结果一致
+use serde::{Deserialize, Serialize};
+
+use crate::utils::profile::get_profile_config;
+
+/// 色彩空间
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorSpace {
+    /// BT.601
+    Bt601,
+    /// BT.709
+    #[default]
+    Bt709,
+    /// sRGB，使用 BT.709 矩阵
+    Srgb,
+    /// BT.2100 PQ，BT.2020 矩阵
+    Bt2100Pq,
+    /// BT.2100 HLG，BT.2020 矩阵
+    Bt2100Hlg,
+}
+
+impl ColorSpace {
+    /// 从配置文件中的名称解析，如 `709`、`2100PQ`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "601" => Some(Self::Bt601),
+            "709" => Some(Self::Bt709),
+            "sRGB" => Some(Self::Srgb),
+            "2100PQ" => Some(Self::Bt2100Pq),
+            "2100HLG" => Some(Self::Bt2100Hlg),
+            _ => None,
+        }
+    }
+
+    /// 亮度系数 (Kr, Kb)
+    pub fn coefficients(&self) -> (f32, f32) {
+        match self {
+            Self::Bt601 => (0.299, 0.114),
+            Self::Bt709 | Self::Srgb => (0.2126, 0.0722),
+            Self::Bt2100Pq | Self::Bt2100Hlg => (0.2627, 0.0593),
+        }
+    }
+
+    /// 传递函数
+    pub fn transfer(&self) -> Transfer {
+        match self {
+            Self::Bt2100Pq => Transfer::Pq,
+            Self::Bt2100Hlg => Transfer::Hlg,
+            _ => Transfer::None,
+        }
+    }
+}
+
+/// 色彩范围
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorRange {
+    /// 有限范围，8 位时 Y 为 16-235，UV 为 16-240
+    #[default]
+    Partial,
+    /// 全范围
+    Full,
+}
+
+impl ColorRange {
+    /// 从配置文件中的名称解析，如 `Partial`、`Full`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Partial" => Some(Self::Partial),
+            "Full" => Some(Self::Full),
+            _ => None,
+        }
+    }
+}
+
+/// 传递函数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transfer {
+    /// 保持画布的 sRGB 编码
+    None,
+    /// SMPTE ST 2084
+    Pq,
+    /// ARIB STD-B67
+    Hlg,
+}
+
+impl Transfer {
+    /// 着色器中使用的编号
+    pub fn id(&self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Pq => 1,
+            Self::Hlg => 2,
+        }
+    }
+}
+
+/// HDR 输出时 SDR 白色对应的亮度（尼特）
+pub const DEFAULT_SDR_WHITE: f32 = 300.;
+
+/// 从当前配置文件的 `[Video]` 节读取色彩空间和范围
+///
+/// # 返回值
+///
+/// 返回 `(ColorSpace, ColorRange)`，未配置时为 BT.709 有限范围
+pub fn load_color_settings() -> (ColorSpace, ColorRange) {
+    let space = get_profile_config("Video", "ColorSpace")
+        .and_then(|name| ColorSpace::from_name(&name))
+        .unwrap_or_default();
+    let range = get_profile_config("Video", "ColorRange")
+        .and_then(|name| ColorRange::from_name(&name))
+        .unwrap_or_default();
+
+    (space, range)
+}
+
+/// 转换参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorParams {
+    pub kr: f32,
+    pub kb: f32,
+    /// Y 的缩放和偏移（码值）
+    pub y_scale: f32,
+    pub y_offset: f32,
+    /// UV 的缩放和偏移（码值）
+    pub c_scale: f32,
+    pub c_offset: f32,
+    /// 码值上限
+    pub max: f32,
+    pub transfer: Transfer,
+    pub sdr_white: f32,
+}
+
+impl ColorParams {
+    /// 计算转换参数
+    ///
+    /// # 参数
+    ///
+    /// * `space` - 色彩空间
+    /// * `range` - 色彩范围
+    /// * `bits` - 位深，8 或 10
+    pub fn new(space: ColorSpace, range: ColorRange, bits: u32) -> Self {
+        let (kr, kb) = space.coefficients();
+        let shift = (1u32 << (bits - 8)) as f32;
+        let max = ((1u32 << bits) - 1) as f32;
+
+        let (y_scale, y_offset, c_scale) = match range {
+            ColorRange::Partial => (219. * shift, 16. * shift, 224. * shift),
+            ColorRange::Full => (max, 0., max),
+        };
+
+        Self {
+            kr,
+            kb,
+            y_scale,
+            y_offset,
+            c_scale,
+            c_offset: 128. * shift,
+            max,
+            transfer: space.transfer(),
+            sdr_white: DEFAULT_SDR_WHITE,
+        }
+    }
+
+    /// 将画布 RGB（0-1，sRGB 编码）转换为 YUV 码值
+    ///
+    /// # 参数
+    ///
+    /// * `rgb` - RGB 值
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `[Y, U, V]`，已四舍五入并限制在码值范围内
+    pub fn rgb_to_yuv(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let [r, g, b] = encode_transfer(rgb, self.transfer, self.sdr_white);
+
+        let y = self.kr * r + (1. - self.kr - self.kb) * g + self.kb * b;
+        let u = (b - y) / (2. * (1. - self.kb));
+        let v = (r - y) / (2. * (1. - self.kr));
+
+        [
+            (self.y_offset + self.y_scale * y)
+                .round()
+                .clamp(0., self.max),
+            (self.c_offset + self.c_scale * u)
+                .round()
+                .clamp(0., self.max),
+            (self.c_offset + self.c_scale * v)
+                .round()
+                .clamp(0., self.max),
+        ]
+    }
+
+    /// 将 YUV 码值转换为 RGB（0-1），不处理传递函数，用于解码 SDR 视频
+    ///
+    /// # 参数
+    ///
+    /// * `yuv` - YUV 码值
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `[R, G, B]`，已限制在 0 到 1 之间
+    pub fn yuv_to_rgb(&self, yuv: [f32; 3]) -> [f32; 3] {
+        let y = (yuv[0] - self.y_offset) / self.y_scale;
+        let u = (yuv[1] - self.c_offset) / self.c_scale;
+        let v = (yuv[2] - self.c_offset) / self.c_scale;
+
+        let r = y + 2. * (1. - self.kr) * v;
+        let b = y + 2. * (1. - self.kb) * u;
+        let g = (y - self.kr * r - self.kb * b) / (1. - self.kr - self.kb);
+
+        [r.clamp(0., 1.), g.clamp(0., 1.), b.clamp(0., 1.)]
+    }
+}
+
+/// sRGB 解码为线性值
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// 线性值（1.0 = 10000 尼特）编码为 PQ
+pub fn linear_to_pq(value: f32) -> f32 {
+    let (m1, m2) = (2610. / 16384., 2523. / 4096. * 128.);
+    let (c1, c2, c3) = (3424. / 4096., 2413. / 4096. * 32., 2392. / 4096. * 32.);
+
+    let l = value.max(0.).powf(m1);
+    ((c1 + c2 * l) / (1. + c3 * l)).powf(m2)
+}
+
+/// 场景线性值（0-1）编码为 HLG
+pub fn linear_to_hlg(value: f32) -> f32 {
+    let a: f32 = 0.17883277;
+    let (b, c) = (1. - 4. * a, 0.5 - a * (4. * a).ln());
+
+    let value = value.max(0.);
+    if value <= 1. / 12. {
+        (3. * value).sqrt()
+    } else {
+        a * (12. * value - b).ln() + c
+    }
+}
+
+/// 将画布 RGB 编码为目标传递函数，HDR 时同时转换到 BT.2020 色域
+pub fn encode_transfer(rgb: [f32; 3], transfer: Transfer, sdr_white: f32) -> [f32; 3] {
+    if transfer == Transfer::None {
+        return rgb;
+    }
+
+    let [r, g, b] = rgb.map(srgb_to_linear);
+    let linear = [
+        0.6274 * r + 0.3293 * g + 0.0433 * b,
+        0.0691 * r + 0.9195 * g + 0.0114 * b,
+        0.0164 * r + 0.0880 * g + 0.8956 * b,
+    ];
+
+    match transfer {
+        Transfer::Pq => linear.map(|value| linear_to_pq(value * sdr_white / 10000.)),
+        Transfer::Hlg => {
+            // 标称峰值 1000 尼特，先做 OOTF 的逆变换（系统伽马 1.2）回到场景光
+            let display = linear.map(|value| value * sdr_white / 1000.);
+            let luma = 0.2627 * display[0] + 0.6780 * display[1] + 0.0593 * display[2];
+            let gain = if luma > 0. { luma.powf(-0.2 / 1.2) } else { 0. };
+            display.map(|value| linear_to_hlg(value * gain))
+        }
+        Transfer::None => rgb,
+    }
+}
@@ -0,0 +1,185 @@
+/// CPU 像素格式转换模块
+///
+/// 与 `graphics::convert` 的 GPU 转换使用相同的算法，也作为其参考实现
+use anyhow::anyhow;
+
+use crate::{
+    media::{
+        color::{ColorParams, ColorRange, ColorSpace},
+        frame::{VideoFormat, VideoFrame},
+    },
+    Result,
+};
+
+/// 对 RGBA 帧中 `(x, y)` 起的 `sx` x `sy` 区域取平均，返回 0-1 的 RGB
+fn average_rgb(frame: &VideoFrame, x: usize, y: usize, sx: usize, sy: usize) -> [f32; 3] {
+    let (w, h) = (frame.width as usize, frame.height as usize);
+    let mut sum = [0f32; 3];
+
+    for dy in 0..sy {
+        for dx in 0..sx {
+            let i = ((y + dy).min(h - 1) * w + (x + dx).min(w - 1)) * 4;
+            for (c, value) in sum.iter_mut().enumerate() {
+                *value += frame.data[i + c] as f32 / 255.;
+            }
+        }
+    }
+
+    sum.map(|value| value / (sx * sy) as f32)
+}
+
+/// 将 RGBA 帧转换为指定格式
+///
+/// # 参数
+///
+/// * `frame` - RGBA 帧
+/// * `format` - 目标格式
+/// * `space` - 色彩空间
+/// * `range` - 色彩范围
+///
+/// # 返回值
+///
+/// 返回 `Result<VideoFrame>`，源帧不是 RGBA 时返回错误
+pub fn convert_rgba(
+    frame: &VideoFrame,
+    format: VideoFormat,
+    space: ColorSpace,
+    range: ColorRange,
+) -> Result<VideoFrame> {
+    if frame.format != VideoFormat::Rgba {
+        return Err(anyhow!("source frame is not RGBA: {:?}", frame.format));
+    }
+
+    let bits = if format == VideoFormat::P010 { 10 } else { 8 };
+    let params = ColorParams::new(space, range, bits);
+    let (w, h) = (frame.width as usize, frame.height as usize);
+    let yuv = |x: usize, y: usize, sx: usize, sy: usize| {
+        params.rgb_to_yuv(average_rgb(frame, x, y, sx, sy))
+    };
+
+    let mut data = Vec::with_capacity(format.frame_size(frame.width, frame.height));
+    let mut push = |value: f32| {
+        if bits > 8 {
+            // P010 将 10 位数据放在 16 位的高位
+            data.extend_from_slice(&((value as u16) << 6).to_le_bytes());
+        } else {
+            data.push(value as u8);
+        }
+    };
+
+    match format {
+        VideoFormat::Rgba => return Ok(frame.clone()),
+        VideoFormat::Yuyv => {
+            for y in 0..h {
+                for x in (0..w).step_by(2) {
+                    let [_, u, v] = yuv(x, y, 2, 1);
+                    push(yuv(x, y, 1, 1)[0]);
+                    push(u);
+                    push(yuv(x + 1, y, 1, 1)[0]);
+                    push(v);
+                }
+            }
+        }
+        VideoFormat::I444 => {
+            for c in 0..3 {
+                for y in 0..h {
+                    for x in 0..w {
+                        push(yuv(x, y, 1, 1)[c]);
+                    }
+                }
+            }
+        }
+        VideoFormat::Nv12 | VideoFormat::P010 | VideoFormat::I420 => {
+            for y in 0..h {
+                for x in 0..w {
+                    push(yuv(x, y, 1, 1)[0]);
+                }
+            }
+
+            let interleaved = format != VideoFormat::I420;
+            let planes: &[&[usize]] = if interleaved {
+                &[&[1, 2]]
+            } else {
+                &[&[1], &[2]]
+            };
+            for components in planes {
+                for y in (0..h).step_by(2) {
+                    for x in (0..w).step_by(2) {
+                        let value = yuv(x, y, 2, 2);
+                        for c in components.iter() {
+                            push(value[*c]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(VideoFrame {
+        width: frame.width,
+        height: frame.height,
+        format,
+        timestamp: frame.timestamp,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1 像素高的 RGBA 帧
+    fn row(pixels: &[[u8; 4]]) -> VideoFrame {
+        VideoFrame {
+            width: pixels.len() as u32,
+            height: 1,
+            format: VideoFormat::Rgba,
+            timestamp: 7,
+            data: pixels.concat(),
+        }
+    }
+
+    #[test]
+    fn yuyv_packs_two_lumas_with_shared_chroma() {
+        let white = [255, 255, 255, 255];
+        let black = [0, 0, 0, 255];
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+        let frame = row(&[white, black, red, blue]);
+
+        let yuyv = convert_rgba(
+            &frame,
+            VideoFormat::Yuyv,
+            ColorSpace::Bt709,
+            ColorRange::Partial,
+        )
+        .unwrap();
+        assert_eq!(yuyv.format, VideoFormat::Yuyv);
+        assert_eq!(yuyv.timestamp, 7);
+        assert_eq!(yuyv.data.len(), VideoFormat::Yuyv.frame_size(4, 1));
+        // 色度取两个像素的平均值：白黑为灰，红蓝为紫
+        assert_eq!(yuyv.data, [235, 128, 16, 128, 63, 171, 32, 179]);
+
+        let full = convert_rgba(
+            &frame,
+            VideoFormat::Yuyv,
+            ColorSpace::Bt709,
+            ColorRange::Full,
+        )
+        .unwrap();
+        assert_eq!(full.data[..4], [255, 128, 0, 128]);
+    }
+
+    #[test]
+    fn only_rgba_frames_are_converted() {
+        let mut frame = row(&[[0, 0, 0, 255], [0, 0, 0, 255]]);
+        frame.format = VideoFormat::Yuyv;
+        assert!(convert_rgba(
+            &frame,
+            VideoFormat::Yuyv,
+            ColorSpace::Bt709,
+            ColorRange::Partial
+        )
+        .is_err());
+    }
+}
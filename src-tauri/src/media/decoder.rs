@@ -0,0 +1,861 @@
+/// 媒体文件解码模块
+///
+/// 在 CPU 上解封装和解码本地媒体文件，不依赖硬件解码器。
+/// MP4/MOV 文件支持 H.264 视频和 AAC 音频，其他文件通过 symphonia 作为纯音频文件解码，
+/// 支持 MP3、AAC、FLAC、Vorbis、WAV 等格式
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    fs::File,
+    io::BufReader,
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use image::{Rgba, RgbaImage};
+use openh264::{decoder::Decoder as H264Decoder, formats::YUVSource};
+use symphonia::core::{
+    audio::{Channels, SampleBuffer},
+    codecs::{CodecParameters, Decoder as AudioDecoder, DecoderOptions, CODEC_TYPE_AAC},
+    errors::Error as SymphoniaError,
+    formats::{FormatOptions, FormatReader, Packet, SeekMode, SeekTo},
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+    units::{Time, TimeBase},
+};
+
+use crate::{
+    media::{
+        audio::AudioFrame,
+        color::{ColorParams, ColorRange, ColorSpace},
+    },
+    Result,
+};
+
+/// 解码后的媒体数据，时间戳都是从文件开始计算的纳秒数
+#[derive(Debug, Clone)]
+pub enum Decoded {
+    Video {
+        pts: u64,
+        image: RgbaImage,
+    },
+    /// 音频帧的 `timestamp` 为文件中的时间戳
+    Audio(AudioFrame),
+}
+
+impl Decoded {
+    /// 显示时间戳（纳秒）
+    pub fn pts(&self) -> u64 {
+        match self {
+            Self::Video { pts, .. } => *pts,
+            Self::Audio(frame) => frame.timestamp,
+        }
+    }
+}
+
+/// 媒体文件读取器
+pub trait MediaReader: Send {
+    /// 文件时长，未知时为 `None`
+    fn duration(&self) -> Option<Duration>;
+
+    /// 是否包含视频
+    fn has_video(&self) -> bool;
+
+    /// 读取下一份解码数据，视频和音频按时间顺序交错返回
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Option<Decoded>>`，文件结束时返回 `None`
+    fn read(&mut self) -> Result<Option<Decoded>>;
+
+    /// 跳转到指定位置，之后读取的数据从该位置开始
+    ///
+    /// # 参数
+    ///
+    /// * `position` - 目标位置
+    fn seek(&mut self, position: Duration) -> Result<()>;
+}
+
+/// 打开媒体文件
+///
+/// # 参数
+///
+/// * `path` - 文件路径
+///
+/// # 返回值
+///
+/// 返回 `Result<Box<dyn MediaReader>>`，格式或编码不支持时返回错误
+pub fn open_media(path: &Path) -> Result<Box<dyn MediaReader>> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match extension.as_str() {
+        "mp4" | "m4v" | "mov" | "m4a" => Ok(Box::new(Mp4MediaReader::open(path)?)),
+        _ => Ok(Box::new(AudioFileReader::open(path, &extension)?)),
+    }
+}
+
+/// 时间戳换算为纳秒
+fn to_ns(value: u64, timescale: u32) -> u64 {
+    (value as u128 * 1_000_000_000 / timescale.max(1) as u128) as u64
+}
+
+/// 纳秒换算为时间戳
+fn from_ns(ns: u64, timescale: u32) -> u64 {
+    (ns as u128 * timescale as u128 / 1_000_000_000) as u64
+}
+
+/// 将 symphonia 解码出的音频转换为交错的浮点采样
+fn audio_frame(
+    decoder: &mut dyn AudioDecoder,
+    packet: &Packet,
+    timestamp: u64,
+) -> Result<Option<AudioFrame>> {
+    let decoded = match decoder.decode(packet) {
+        Ok(decoded) => decoded,
+        // 损坏的包跳过即可
+        Err(SymphoniaError::DecodeError(_)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if decoded.frames() == 0 {
+        return Ok(None);
+    }
+
+    let spec = *decoded.spec();
+    let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+    buffer.copy_interleaved_ref(decoded);
+
+    Ok(Some(AudioFrame {
+        sample_rate: spec.rate,
+        channels: spec.channels.count() as u16,
+        timestamp,
+        data: buffer.samples().to_vec(),
+    }))
+}
+
+/// MP4 中一条轨道的采样时间表
+struct SampleTable {
+    track_id: u32,
+    timescale: u32,
+    /// 各采样的解码时间戳
+    times: Vec<u64>,
+    /// 关键帧的采样编号（从 1 开始），为 `None` 时所有采样都是关键帧
+    sync: Option<Vec<u32>>,
+    /// 下一个要读取的采样编号（从 1 开始）
+    next: u32,
+}
+
+impl SampleTable {
+    fn new(track: &mp4::Mp4Track) -> Self {
+        let stbl = &track.trak.mdia.minf.stbl;
+        let mut times = vec![];
+        let mut time = 0;
+        for entry in &stbl.stts.entries {
+            for _ in 0..entry.sample_count {
+                times.push(time);
+                time += entry.sample_delta as u64;
+            }
+        }
+
+        Self {
+            track_id: track.track_id(),
+            timescale: track.timescale(),
+            times,
+            sync: stbl.stss.as_ref().map(|stss| stss.entries.clone()),
+            next: 1,
+        }
+    }
+
+    /// 下一个采样的解码时间（纳秒），没有更多采样时为 `None`
+    fn next_time(&self) -> Option<u64> {
+        self.times
+            .get(self.next as usize - 1)
+            .map(|time| to_ns(*time, self.timescale))
+    }
+
+    /// 跳转到不晚于 `position` 的采样，`keyframe` 为真时还要求是关键帧
+    fn seek(&mut self, position: u64, keyframe: bool) {
+        let target = from_ns(position, self.timescale);
+        let index = self.times.partition_point(|time| *time <= target).max(1) as u32;
+        self.next = match (&self.sync, keyframe) {
+            (Some(sync), true) => sync
+                .iter()
+                .rev()
+                .find(|sample| **sample <= index)
+                .copied()
+                .unwrap_or(1),
+            _ => index,
+        };
+    }
+}
+
+/// H.264 视频轨道
+struct VideoTrack {
+    table: SampleTable,
+    decoder: H264Decoder,
+    /// 长度前缀的字节数
+    length_size: usize,
+    /// 关键帧前插入的 SPS 和 PPS，Annex B 格式
+    parameter_sets: Vec<u8>,
+    /// 已送入解码器但还没输出的显示时间戳，解码器按显示顺序输出
+    pending: BinaryHeap<Reverse<u64>>,
+    /// 平均帧时长（纳秒）
+    frame_duration: u64,
+}
+
+impl VideoTrack {
+    fn new(track: &mp4::Mp4Track) -> Result<Self> {
+        let avc1 = track
+            .trak
+            .mdia
+            .minf
+            .stbl
+            .stsd
+            .avc1
+            .as_ref()
+            .ok_or(anyhow!("missing avc1 box"))?;
+
+        let mut parameter_sets = vec![];
+        for nal in avc1
+            .avcc
+            .sequence_parameter_sets
+            .iter()
+            .chain(&avc1.avcc.picture_parameter_sets)
+        {
+            parameter_sets.extend_from_slice(&[0, 0, 0, 1]);
+            parameter_sets.extend_from_slice(&nal.bytes);
+        }
+
+        let table = SampleTable::new(track);
+        let frame_duration = track.duration().as_nanos() as u64 / table.times.len().max(1) as u64;
+
+        Ok(Self {
+            table,
+            decoder: H264Decoder::new()?,
+            length_size: avc1.avcc.length_size_minus_one as usize + 1,
+            parameter_sets,
+            pending: BinaryHeap::new(),
+            frame_duration,
+        })
+    }
+
+    /// 将长度前缀格式的采样转换为 Annex B 格式
+    fn annex_b(&self, sample: &[u8], keyframe: bool) -> Vec<u8> {
+        let mut output = Vec::with_capacity(sample.len() + self.parameter_sets.len());
+        if keyframe {
+            output.extend_from_slice(&self.parameter_sets);
+        }
+
+        let mut rest = sample;
+        while rest.len() > self.length_size {
+            let length = rest[..self.length_size]
+                .iter()
+                .fold(0usize, |length, byte| (length << 8) | *byte as usize);
+            let end = (self.length_size + length).min(rest.len());
+            output.extend_from_slice(&[0, 0, 0, 1]);
+            output.extend_from_slice(&rest[self.length_size..end]);
+            rest = &rest[end..];
+        }
+
+        output
+    }
+
+    /// 解码一个采样，可能输出零或一帧
+    fn decode(&mut self, sample: &mp4::Mp4Sample) -> Result<Option<Decoded>> {
+        let pts = sample.start_time as i64 + sample.rendering_offset as i64;
+        let pts = to_ns(pts.max(0) as u64, self.table.timescale);
+        self.pending.push(Reverse(pts));
+
+        let packet = self.annex_b(&sample.bytes, sample.is_sync);
+        let image = match self.decoder.decode(&packet) {
+            Ok(Some(yuv)) => Some(yuv_to_rgba(&yuv)),
+            Ok(None) => None,
+            // 缺少参考帧等错误只影响这一帧，不再等待它输出
+            Err(_) => {
+                self.pending.retain(|Reverse(pending)| *pending != pts);
+                None
+            }
+        };
+
+        Ok(image.and_then(|image| self.output(image)))
+    }
+
+    /// 解码器剩余的帧
+    fn flush(&mut self) -> Vec<Decoded> {
+        let images: Vec<RgbaImage> = self
+            .decoder
+            .flush_remaining()
+            .map(|frames| frames.iter().map(yuv_to_rgba).collect())
+            .unwrap_or_default();
+
+        images
+            .into_iter()
+            .filter_map(|image| self.output(image))
+            .collect()
+    }
+
+    fn output(&mut self, image: RgbaImage) -> Option<Decoded> {
+        let Reverse(pts) = self.pending.pop()?;
+        Some(Decoded::Video { pts, image })
+    }
+
+    fn seek(&mut self, position: u64) -> Result<()> {
+        self.table.seek(position, true);
+        self.decoder = H264Decoder::new()?;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// 将解码出的 YUV 图像转换为 RGBA
+///
+/// H.264 码流通常是有限范围，高清视频使用 BT.709，标清视频使用 BT.601
+fn yuv_to_rgba(yuv: &openh264::decoder::DecodedYUV) -> RgbaImage {
+    let (width, height) = yuv.dimensions();
+    let (y_stride, u_stride, v_stride) = yuv.strides();
+    let space = if height >= 720 {
+        ColorSpace::Bt709
+    } else {
+        ColorSpace::Bt601
+    };
+    let params = ColorParams::new(space, ColorRange::Partial, 8);
+    let (y_plane, u_plane, v_plane) = (yuv.y(), yuv.u(), yuv.v());
+
+    RgbaImage::from_fn(width as u32, height as u32, |x, y| {
+        let (x, y) = (x as usize, y as usize);
+        let [r, g, b] = params.yuv_to_rgb([
+            y_plane[y * y_stride + x] as f32,
+            u_plane[y / 2 * u_stride + x / 2] as f32,
+            v_plane[y / 2 * v_stride + x / 2] as f32,
+        ]);
+        Rgba([
+            (r * 255.).round() as u8,
+            (g * 255.).round() as u8,
+            (b * 255.).round() as u8,
+            255,
+        ])
+    })
+}
+
+/// AAC 音频轨道
+struct AudioTrack {
+    table: SampleTable,
+    decoder: Box<dyn AudioDecoder>,
+}
+
+impl AudioTrack {
+    fn new(track: &mp4::Mp4Track) -> Result<Self> {
+        let mp4a = track
+            .trak
+            .mdia
+            .minf
+            .stbl
+            .stsd
+            .mp4a
+            .as_ref()
+            .ok_or(anyhow!("missing mp4a box"))?;
+        let config = &mp4a
+            .esds
+            .as_ref()
+            .ok_or(anyhow!("missing esds box"))?
+            .es_desc
+            .dec_config
+            .dec_specific;
+
+        // AudioSpecificConfig：对象类型 5 位、采样率序号 4 位、声道配置 4 位
+        let audio_config = [
+            (config.profile << 3) | (config.freq_index >> 1),
+            ((config.freq_index & 1) << 7) | (config.chan_conf << 3),
+        ];
+        let channels = match mp4a.channelcount {
+            1 => Channels::FRONT_LEFT,
+            _ => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        };
+
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(CODEC_TYPE_AAC)
+            .with_sample_rate(mp4a.samplerate.value() as u32)
+            .with_channels(channels)
+            .with_extra_data(Box::new(audio_config));
+        let decoder = symphonia::default::get_codecs().make(&params, &DecoderOptions::default())?;
+
+        Ok(Self {
+            table: SampleTable::new(track),
+            decoder,
+        })
+    }
+
+    fn decode(&mut self, sample: &mp4::Mp4Sample) -> Result<Option<Decoded>> {
+        let packet = Packet::new_from_slice(
+            self.table.track_id,
+            sample.start_time,
+            sample.duration as u64,
+            &sample.bytes,
+        );
+        let timestamp = to_ns(sample.start_time, self.table.timescale);
+
+        Ok(audio_frame(self.decoder.as_mut(), &packet, timestamp)?.map(Decoded::Audio))
+    }
+}
+
+/// MP4/MOV 文件读取器
+struct Mp4MediaReader {
+    reader: mp4::Mp4Reader<BufReader<File>>,
+    video: Option<VideoTrack>,
+    audio: Option<AudioTrack>,
+    duration: Duration,
+    /// 跳转后早于该时间（纳秒）的数据不输出
+    skip_before: u64,
+    /// 已解码但还没返回的数据
+    queue: VecDeque<Decoded>,
+    /// 视频解码器已经清空
+    flushed: bool,
+}
+
+impl Mp4MediaReader {
+    fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+        let reader = mp4::Mp4Reader::read_header(BufReader::new(file), size)?;
+
+        let mut video = None;
+        let mut audio = None;
+        for track in reader.tracks().values() {
+            match track.media_type() {
+                Ok(mp4::MediaType::H264) if video.is_none() => {
+                    video = Some(VideoTrack::new(track)?)
+                }
+                Ok(mp4::MediaType::AAC) if audio.is_none() => audio = Some(AudioTrack::new(track)?),
+                _ => {}
+            }
+        }
+        if video.is_none() && audio.is_none() {
+            return Err(anyhow!("no supported tracks in {}", path.display()));
+        }
+
+        Ok(Self {
+            duration: reader.duration(),
+            reader,
+            video,
+            audio,
+            skip_before: 0,
+            queue: VecDeque::new(),
+            flushed: false,
+        })
+    }
+
+    /// 数据是否早于跳转位置，视频帧在显示时长内覆盖跳转位置时保留
+    fn skipped(&self, decoded: &Decoded) -> bool {
+        let end = match decoded {
+            Decoded::Video { pts, .. } => {
+                pts + self.video.as_ref().map_or(0, |video| video.frame_duration)
+            }
+            Decoded::Audio(frame) => frame.timestamp + frame.duration_ns(),
+        };
+        end <= self.skip_before
+    }
+}
+
+impl MediaReader for Mp4MediaReader {
+    fn duration(&self) -> Option<Duration> {
+        Some(self.duration)
+    }
+
+    fn has_video(&self) -> bool {
+        self.video.is_some()
+    }
+
+    fn read(&mut self) -> Result<Option<Decoded>> {
+        loop {
+            if let Some(decoded) = self.queue.pop_front() {
+                if self.skipped(&decoded) {
+                    continue;
+                }
+                return Ok(Some(decoded));
+            }
+
+            // 按解码时间交错读取两条轨道
+            let video_time = self
+                .video
+                .as_ref()
+                .and_then(|video| video.table.next_time());
+            let audio_time = self
+                .audio
+                .as_ref()
+                .and_then(|audio| audio.table.next_time());
+            let read_video = match (video_time, audio_time) {
+                (Some(video), Some(audio)) => video <= audio,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => {
+                    if self.flushed {
+                        return Ok(None);
+                    }
+                    self.flushed = true;
+                    if let Some(video) = &mut self.video {
+                        self.queue.extend(video.flush());
+                    }
+                    continue;
+                }
+            };
+
+            let decoded = if read_video {
+                let Some(video) = &mut self.video else {
+                    continue;
+                };
+                let sample_id = video.table.next;
+                video.table.next += 1;
+                match self.reader.read_sample(video.table.track_id, sample_id)? {
+                    Some(sample) => video.decode(&sample)?,
+                    None => None,
+                }
+            } else {
+                let Some(audio) = &mut self.audio else {
+                    continue;
+                };
+                let sample_id = audio.table.next;
+                audio.table.next += 1;
+                match self.reader.read_sample(audio.table.track_id, sample_id)? {
+                    Some(sample) => audio.decode(&sample)?,
+                    None => None,
+                }
+            };
+            self.queue.extend(decoded);
+        }
+    }
+
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        let position = position.as_nanos() as u64;
+        if let Some(video) = &mut self.video {
+            video.seek(position)?;
+        }
+        if let Some(audio) = &mut self.audio {
+            audio.table.seek(position, false);
+            audio.decoder.reset();
+        }
+        self.skip_before = position;
+        self.queue.clear();
+        self.flushed = false;
+
+        Ok(())
+    }
+}
+
+/// 纯音频文件读取器
+struct AudioFileReader {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn AudioDecoder>,
+    track_id: u32,
+    time_base: TimeBase,
+    duration: Option<Duration>,
+    /// 跳转后早于该时间（纳秒）的数据不输出
+    skip_before: u64,
+}
+
+impl AudioFileReader {
+    fn open(path: &Path, extension: &str) -> Result<Self> {
+        let stream = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension(extension);
+
+        let probed = symphonia::default::get_probe().format(
+            &hint,
+            stream,
+            &FormatOptions {
+                enable_gapless: true,
+                ..Default::default()
+            },
+            &MetadataOptions::default(),
+        )?;
+        let format = probed.format;
+        let track = format
+            .default_track()
+            .ok_or(anyhow!("no audio track in {}", path.display()))?;
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+
+        let time_base = track
+            .codec_params
+            .time_base
+            .or(track
+                .codec_params
+                .sample_rate
+                .map(|rate| TimeBase::new(1, rate)))
+            .ok_or(anyhow!("unknown time base in {}", path.display()))?;
+        let duration = track.codec_params.n_frames.map(|frames| {
+            let time = time_base.calc_time(frames);
+            Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac)
+        });
+
+        Ok(Self {
+            track_id: track.id,
+            format,
+            decoder,
+            time_base,
+            duration,
+            skip_before: 0,
+        })
+    }
+}
+
+impl MediaReader for AudioFileReader {
+    fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    fn has_video(&self) -> bool {
+        false
+    }
+
+    fn read(&mut self) -> Result<Option<Decoded>> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e))
+                    if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(None)
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let time = self.time_base.calc_time(packet.ts());
+            let timestamp = time.seconds * 1_000_000_000 + (time.frac * 1e9) as u64;
+            let Some(frame) = audio_frame(self.decoder.as_mut(), &packet, timestamp)? else {
+                continue;
+            };
+            if frame.timestamp + frame.duration_ns() <= self.skip_before {
+                continue;
+            }
+
+            return Ok(Some(Decoded::Audio(frame)));
+        }
+    }
+
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        self.format.seek(
+            SeekMode::Accurate,
+            SeekTo::Time {
+                time: Time::from(position.as_secs_f64()),
+                track_id: Some(self.track_id),
+            },
+        )?;
+        self.decoder.reset();
+        self.skip_before = position.as_nanos() as u64;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        media::{
+            aac::AacEncoder,
+            encoder::{EncoderPreset, H264Encoder, VideoEncoderSettings},
+            frame::{VideoFormat, VideoFrame},
+        },
+        outputs::{
+            encoders::EncoderKey,
+            recording::{mp4::Mp4Muxer, muxer::Muxer},
+        },
+        protocols::flv::flv_audio_settings,
+    };
+
+    const MS: u64 = 1_000_000;
+
+    /// 测试文件的视频帧间隔（毫秒）
+    const FRAME_MS: u64 = 40;
+
+    /// 测试文件的帧数，每秒一个关键帧
+    const FRAMES: u64 = 50;
+
+    /// 采样间隔 40，第 1、26 个采样为关键帧
+    fn table() -> SampleTable {
+        SampleTable {
+            track_id: 1,
+            timescale: 1000,
+            times: (0..FRAMES).map(|i| i * FRAME_MS).collect(),
+            sync: Some(vec![1, 26]),
+            next: 1,
+        }
+    }
+
+    /// 用录制的编码器和封装器写入 2 秒的测试文件
+    fn write_fixture(path: &Path) {
+        let key = EncoderKey {
+            canvas: None,
+            video: VideoEncoderSettings {
+                width: 64,
+                height: 64,
+                fps_num: 1000 / FRAME_MS as u32,
+                fps_den: 1,
+                bitrate: 500,
+                keyint_sec: 1,
+                preset: EncoderPreset::Quality,
+            },
+            audio: flv_audio_settings(2),
+        };
+        let mut video = H264Encoder::new(key.video).unwrap();
+        let mut audio = AacEncoder::new(key.audio).unwrap();
+        let samples = (key.audio.sample_rate as u64 * FRAME_MS / 1000) as usize;
+
+        let mut packets = vec![];
+        for frame in 0..FRAMES {
+            let pts = frame * FRAME_MS * MS;
+            let mut data = vec![128u8; 64 * 64 * 3 / 2];
+            for (i, luma) in data[..64 * 64].iter_mut().enumerate() {
+                *luma = ((i % 64) as u64 * 4 + frame * 5) as u8;
+            }
+            let picture = VideoFrame {
+                width: 64,
+                height: 64,
+                format: VideoFormat::I420,
+                timestamp: pts,
+                data,
+            };
+            packets.extend(video.encode(&picture, pts).unwrap());
+
+            let data = (0..samples * 2)
+                .map(|i| ((frame as usize * samples + i / 2) as f32 * 0.06).sin() * 0.3)
+                .collect();
+            let chunk = AudioFrame {
+                sample_rate: key.audio.sample_rate,
+                channels: 2,
+                timestamp: pts,
+                data,
+            };
+            packets.extend(audio.encode(&chunk, pts));
+        }
+        assert_eq!(
+            packets
+                .iter()
+                .filter(|packet| packet.kind == crate::media::encoder::PacketKind::Video)
+                .count() as u64,
+            FRAMES
+        );
+        packets.sort_by_key(|packet| packet.dts);
+
+        let mut muxer = Box::new(Mp4Muxer::create(path, &key, &video.header().unwrap()).unwrap());
+        for packet in &packets {
+            muxer.write_packet(packet, packet.dts).unwrap();
+        }
+        muxer.finish().unwrap();
+    }
+
+    /// 读取所有数据
+    fn read_all(reader: &mut Mp4MediaReader) -> Vec<Decoded> {
+        let mut decoded = vec![];
+        while let Some(item) = reader.read().unwrap() {
+            decoded.push(item);
+        }
+        decoded
+    }
+
+    #[test]
+    fn seek_selects_the_previous_keyframe() {
+        let mut table = table();
+
+        // 关键帧上跳转时停在该关键帧
+        table.seek(1000 * MS, true);
+        assert_eq!(table.next, 26);
+        // 关键帧之间跳转时回到前一个关键帧
+        table.seek(1500 * MS, true);
+        assert_eq!(table.next, 26);
+        table.seek(990 * MS, true);
+        assert_eq!(table.next, 1);
+        // 不要求关键帧时停在不晚于目标的采样
+        table.seek(1500 * MS, false);
+        assert_eq!(table.next, 38);
+        assert_eq!(table.next_time(), Some(1480 * MS));
+        table.seek(0, false);
+        assert_eq!(table.next, 1);
+
+        // 超过结尾时停在最后一个采样
+        table.seek(10_000 * MS, false);
+        assert_eq!(table.next, FRAMES as u32);
+        table.seek(10_000 * MS, true);
+        assert_eq!(table.next, 26);
+
+        // 没有关键帧表时所有采样都是关键帧
+        let mut table = SampleTable {
+            sync: None,
+            ..table
+        };
+        table.seek(1500 * MS, true);
+        assert_eq!(table.next, 38);
+    }
+
+    #[test]
+    fn mp4_reader_interleaves_tracks_and_skips_after_seek() {
+        let path = std::env::temp_dir().join(format!("decoder-{}.mp4", std::process::id()));
+        write_fixture(&path);
+        let mut reader = Mp4MediaReader::open(&path).unwrap();
+        assert!(reader.has_video());
+
+        let decoded = read_all(&mut reader);
+        let video: Vec<u64> = decoded
+            .iter()
+            .filter(|item| matches!(item, Decoded::Video { .. }))
+            .map(Decoded::pts)
+            .collect();
+        let audio: Vec<u64> = decoded
+            .iter()
+            .filter(|item| matches!(item, Decoded::Audio(_)))
+            .map(Decoded::pts)
+            .collect();
+        assert_eq!(video.len() as u64, FRAMES);
+        assert!(video
+            .windows(2)
+            .all(|pair| pair[1] - pair[0] == FRAME_MS * MS));
+        assert!(audio.len() > 60);
+        assert!(audio.windows(2).all(|pair| pair[0] < pair[1]));
+        // 两条轨道按时间交错，任何数据都不早于之前返回的数据一帧以上
+        let mut latest = 0;
+        for item in &decoded {
+            assert!(
+                item.pts() + FRAME_MS * MS >= latest,
+                "{} after {}",
+                item.pts(),
+                latest
+            );
+            latest = latest.max(item.pts());
+        }
+
+        // 从 1 秒的关键帧开始解码，只输出覆盖 1.3 秒及之后的数据
+        reader.seek(Duration::from_millis(1300)).unwrap();
+        let decoded = read_all(&mut reader);
+        let first_video = decoded
+            .iter()
+            .find(|item| matches!(item, Decoded::Video { .. }))
+            .unwrap();
+        assert_eq!(first_video.pts(), 1280 * MS);
+        let Some(Decoded::Audio(first_audio)) = decoded
+            .iter()
+            .find(|item| matches!(item, Decoded::Audio(_)))
+        else {
+            panic!("no audio after seek");
+        };
+        assert!(first_audio.timestamp <= 1300 * MS);
+        assert!(first_audio.timestamp + first_audio.duration_ns() > 1300 * MS);
+        assert_eq!(
+            decoded
+                .iter()
+                .filter(|item| matches!(item, Decoded::Video { .. }))
+                .count() as u64,
+            FRAMES - 32
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
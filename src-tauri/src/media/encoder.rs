@@ -0,0 +1,404 @@
+/// 编码器模块
+///
+/// 视频使用 OpenH264 编码为 H.264（Constrained Baseline，没有 B 帧），
+/// 音频由 `media::aac` 编码为 AAC-LC。视频包为带 4 字节长度前缀的 NAL 单元（AVCC），
+/// SPS/PPS 单独保存在 `AvcHeader` 中，音频包为原始 AAC 帧，都可以直接写入 FLV、MKV 和 MP4
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use openh264::{
+    encoder::{Encoder, EncoderConfig, FrameType, RateControlMode, UsageType},
+    formats::YUVSlices,
+    OpenH264API, Timestamp,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    media::{
+        aac::sample_rate_index,
+        frame::{VideoFormat, VideoFrame},
+    },
+    Result,
+};
+
+/// OpenH264 支持的最大长边
+pub const MAX_ENCODE_LONG_SIDE: u32 = 3840;
+
+/// OpenH264 支持的最大短边
+pub const MAX_ENCODE_SHORT_SIDE: u32 = 2160;
+
+/// AAC-LC 的对象类型
+const AAC_OBJECT_LC: u8 = 2;
+
+/// NAL 单元类型
+const NAL_TYPE_SPS: u8 = 7;
+const NAL_TYPE_PPS: u8 = 8;
+
+/// 编码包的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketKind {
+    Video,
+    Audio,
+}
+
+/// 拥塞时丢弃帧的优先级，值越小越先丢弃
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FramePriority {
+    /// B 帧，不被其他帧参考
+    Disposable,
+    /// P 帧
+    Inter,
+    /// I 帧
+    Key,
+    /// 音频，不丢弃
+    Audio,
+}
+
+/// H.264 的 SPS 和 PPS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvcHeader {
+    pub sps: Vec<u8>,
+    pub pps: Vec<u8>,
+}
+
+impl AvcHeader {
+    /// 生成 AVCDecoderConfigurationRecord（即 `avcC`）
+    pub fn decoder_config(&self) -> Vec<u8> {
+        let mut record = vec![
+            1,
+            self.sps.get(1).copied().unwrap_or(66),
+            self.sps.get(2).copied().unwrap_or(0),
+            self.sps.get(3).copied().unwrap_or(31),
+            // 长度前缀为 4 字节
+            0xff,
+            // 1 个 SPS
+            0xe1,
+        ];
+        record.extend_from_slice(&(self.sps.len() as u16).to_be_bytes());
+        record.extend_from_slice(&self.sps);
+        record.push(1);
+        record.extend_from_slice(&(self.pps.len() as u16).to_be_bytes());
+        record.extend_from_slice(&self.pps);
+
+        record
+    }
+}
+
+/// 编码后的音频或视频包
+#[derive(Debug, Clone)]
+pub struct EncodedPacket {
+    pub kind: PacketKind,
+    /// 显示时间戳（纳秒），从编码开始计时
+    pub pts: u64,
+    /// 解码时间戳（纳秒），从编码开始计时
+    pub dts: u64,
+    /// 是否为关键帧，音频包总是关键帧
+    pub keyframe: bool,
+    /// 拥塞时的丢弃优先级
+    pub priority: FramePriority,
+    /// 关键帧携带的 SPS/PPS，与上一次不同时输出需要重新发送
+    pub header: Option<Arc<AvcHeader>>,
+    /// 视频为 AVCC 格式的 NAL 单元，音频为不带 ADTS 头的 AAC 帧
+    pub data: Vec<u8>,
+}
+
+/// 编码器预设，在编码速度和画质之间取舍
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncoderPreset {
+    /// 按屏幕内容优化的实时编码，速度最快
+    Speed,
+    /// 按摄像头内容优化的实时编码
+    #[default]
+    Balanced,
+    /// 按摄像头内容优化且不跳帧，画质最好
+    Quality,
+}
+
+impl EncoderPreset {
+    /// 从配置文件中的名称解析，x264 的预设名称按速度归入最接近的预设
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "speed" | "ultrafast" | "superfast" | "veryfast" => Some(Self::Speed),
+            "balanced" | "faster" | "fast" | "medium" => Some(Self::Balanced),
+            "quality" | "slow" | "slower" | "veryslow" | "placebo" => Some(Self::Quality),
+            _ => None,
+        }
+    }
+}
+
+/// 视频编码设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VideoEncoderSettings {
+    pub width: u32,
+    pub height: u32,
+    /// 帧率分子
+    pub fps_num: u32,
+    /// 帧率分母
+    pub fps_den: u32,
+    /// 码率（kbps）
+    pub bitrate: u32,
+    /// 关键帧间隔（秒）
+    pub keyint_sec: u32,
+    pub preset: EncoderPreset,
+}
+
+impl VideoEncoderSettings {
+    /// 检查分辨率、帧率和码率
+    pub fn validate(&self) -> Result<()> {
+        let (long, short) = (self.width.max(self.height), self.width.min(self.height));
+        if short == 0 || long > MAX_ENCODE_LONG_SIDE || short > MAX_ENCODE_SHORT_SIDE {
+            return Err(anyhow!(
+                "unsupported encoder resolution: {}x{}",
+                self.width,
+                self.height
+            ));
+        }
+        if !self.width.is_multiple_of(2) || !self.height.is_multiple_of(2) {
+            return Err(anyhow!(
+                "encoder resolution must be even: {}x{}",
+                self.width,
+                self.height
+            ));
+        }
+        if self.fps_num == 0 || self.fps_den == 0 {
+            return Err(anyhow!(
+                "invalid encoder fps: {}/{}",
+                self.fps_num,
+                self.fps_den
+            ));
+        }
+        if self.bitrate == 0 {
+            return Err(anyhow!("invalid encoder bitrate: {}", self.bitrate));
+        }
+
+        Ok(())
+    }
+
+    /// 关键帧间隔（帧数），至少为 1
+    pub fn keyint_frames(&self) -> u64 {
+        let frames =
+            self.keyint_sec.max(1) as u64 * self.fps_num as u64 / self.fps_den.max(1) as u64;
+        frames.max(1)
+    }
+}
+
+/// 音频编码设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AudioEncoderSettings {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// 码率（kbps）
+    pub bitrate: u32,
+}
+
+impl AudioEncoderSettings {
+    /// 生成 AAC-LC 的 AudioSpecificConfig
+    pub fn decoder_config(&self) -> [u8; 2] {
+        // 对象类型 5 位、采样率序号 4 位、声道配置 4 位
+        let index = sample_rate_index(self.sample_rate).unwrap_or(4);
+        [
+            (AAC_OBJECT_LC << 3) | (index >> 1),
+            ((index & 1) << 7) | ((self.channels as u8) << 3),
+        ]
+    }
+}
+
+/// 去掉 NAL 单元的起始码
+fn strip_start_code(nal: &[u8]) -> &[u8] {
+    if nal.starts_with(&[0, 0, 0, 1]) {
+        &nal[4..]
+    } else if nal.starts_with(&[0, 0, 1]) {
+        &nal[3..]
+    } else {
+        nal
+    }
+}
+
+/// H.264 视频编码器
+pub struct H264Encoder {
+    settings: VideoEncoderSettings,
+    /// 当前码率（kbps），动态调整后可能低于设置值
+    bitrate: u32,
+    /// 底层编码器，码率变化后在下一帧重新创建
+    encoder: Option<Encoder>,
+    header: Option<Arc<AvcHeader>>,
+    /// 已编码的帧数
+    frames: u64,
+    /// 下一帧强制编码为关键帧
+    force_keyframe: bool,
+}
+
+impl H264Encoder {
+    /// 创建视频编码器
+    ///
+    /// # 参数
+    ///
+    /// * `settings` - 编码设置
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，设置无效时返回错误
+    pub fn new(settings: VideoEncoderSettings) -> Result<Self> {
+        settings.validate()?;
+
+        Ok(Self {
+            settings,
+            bitrate: settings.bitrate,
+            encoder: None,
+            header: None,
+            frames: 0,
+            force_keyframe: true,
+        })
+    }
+
+    /// 编码设置
+    pub fn settings(&self) -> &VideoEncoderSettings {
+        &self.settings
+    }
+
+    /// 当前码率（kbps）
+    pub fn bitrate(&self) -> u32 {
+        self.bitrate
+    }
+
+    /// 最近一次的 SPS/PPS
+    pub fn header(&self) -> Option<Arc<AvcHeader>> {
+        self.header.clone()
+    }
+
+    /// 修改码率，下一帧重新创建编码器并从关键帧开始
+    ///
+    /// # 参数
+    ///
+    /// * `bitrate` - 码率（kbps）
+    pub fn set_bitrate(&mut self, bitrate: u32) {
+        let bitrate = bitrate.max(1);
+        if bitrate != self.bitrate {
+            self.bitrate = bitrate;
+            self.encoder = None;
+        }
+    }
+
+    /// 下一帧编码为关键帧，如新的输出加入时
+    pub fn request_keyframe(&mut self) {
+        self.force_keyframe = true;
+    }
+
+    /// 创建底层编码器
+    fn create(&self) -> Result<Encoder> {
+        let settings = &self.settings;
+        let (usage, skip) = match settings.preset {
+            EncoderPreset::Speed => (UsageType::ScreenContentRealTime, true),
+            EncoderPreset::Balanced => (UsageType::CameraVideoRealTime, true),
+            EncoderPreset::Quality => (UsageType::CameraVideoRealTime, false),
+        };
+        let config = EncoderConfig::new()
+            .set_bitrate_bps(self.bitrate.saturating_mul(1000))
+            .max_frame_rate(settings.fps_num as f32 / settings.fps_den as f32)
+            .rate_control_mode(RateControlMode::Bitrate)
+            .usage_type(usage)
+            .enable_skip_frame(skip);
+
+        Ok(Encoder::with_api_config(
+            OpenH264API::from_source(),
+            config,
+        )?)
+    }
+
+    /// 编码一帧
+    ///
+    /// # 参数
+    ///
+    /// * `frame` - 与编码设置同尺寸的 I420 帧
+    /// * `pts` - 显示时间戳（纳秒）
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Option<EncodedPacket>>`，编码器为控制码率跳过该帧时返回 `None`
+    pub fn encode(&mut self, frame: &VideoFrame, pts: u64) -> Result<Option<EncodedPacket>> {
+        let settings = self.settings;
+        if frame.format != VideoFormat::I420
+            || frame.width != settings.width
+            || frame.height != settings.height
+        {
+            return Err(anyhow!(
+                "encoder expects {}x{} I420, got {}x{} {:?}",
+                settings.width,
+                settings.height,
+                frame.width,
+                frame.height,
+                frame.format
+            ));
+        }
+
+        if self.encoder.is_none() {
+            self.encoder = Some(self.create()?);
+            self.force_keyframe = true;
+        }
+        if self.frames.is_multiple_of(settings.keyint_frames()) {
+            self.force_keyframe = true;
+        }
+        self.frames += 1;
+
+        let (w, h) = (settings.width as usize, settings.height as usize);
+        let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+        let (y, chroma) = frame.data.split_at(w * h);
+        let (u, v) = chroma.split_at(cw * ch);
+        let yuv = YUVSlices::new((y, u, &v[..cw * ch]), (w, h), (w, cw, cw));
+
+        let encoder = self.encoder.as_mut().unwrap();
+        if std::mem::take(&mut self.force_keyframe) {
+            encoder.force_intra_frame();
+        }
+        let bitstream = encoder.encode_at(&yuv, Timestamp::from_millis(pts / 1_000_000))?;
+
+        let frame_type = bitstream.frame_type();
+        if matches!(frame_type, FrameType::Skip | FrameType::Invalid) {
+            return Ok(None);
+        }
+        let keyframe = matches!(frame_type, FrameType::IDR | FrameType::I);
+
+        let mut data = vec![];
+        let (mut sps, mut pps) = (None, None);
+        for layer in (0..bitstream.num_layers()).filter_map(|i| bitstream.layer(i)) {
+            for nal in (0..layer.nal_count()).filter_map(|i| layer.nal_unit(i)) {
+                let nal = strip_start_code(nal);
+                match nal.first().map(|byte| byte & 0x1f) {
+                    Some(NAL_TYPE_SPS) => sps = Some(nal.to_vec()),
+                    Some(NAL_TYPE_PPS) => pps = Some(nal.to_vec()),
+                    Some(_) => {
+                        data.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+                        data.extend_from_slice(nal);
+                    }
+                    None => {}
+                }
+            }
+        }
+        if let (Some(sps), Some(pps)) = (sps, pps) {
+            let header = AvcHeader { sps, pps };
+            if self.header.as_deref() != Some(&header) {
+                self.header = Some(Arc::new(header));
+            }
+        }
+        if data.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(EncodedPacket {
+            kind: PacketKind::Video,
+            pts,
+            dts: pts,
+            keyframe,
+            priority: if keyframe {
+                FramePriority::Key
+            } else {
+                FramePriority::Inter
+            },
+            header: if keyframe { self.header.clone() } else { None },
+            data,
+        }))
+    }
+}
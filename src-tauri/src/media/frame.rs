@@ -0,0 +1,176 @@
+/// 视频帧模块
+use serde::{Deserialize, Serialize};
+
+use crate::utils::profile::get_profile_config;
+
+/// 像素格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoFormat {
+    /// 8 位 RGBA，单平面
+    Rgba,
+    /// 打包的 4:2:2，Y0 U Y1 V
+    Yuyv,
+    /// 4:2:0，Y 平面加交错的 UV 平面
+    Nv12,
+    /// 4:2:0，Y、U、V 三个平面
+    I420,
+    /// 4:4:4，Y、U、V 三个平面
+    I444,
+    /// 10 位 4:2:0，16 位小端存储，高位对齐
+    P010,
+}
+
+impl VideoFormat {
+    /// 从配置文件中的格式名称解析，如 `NV12`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "RGBA" => Some(Self::Rgba),
+            "YUY2" | "YUYV" => Some(Self::Yuyv),
+            "NV12" => Some(Self::Nv12),
+            "I420" => Some(Self::I420),
+            "I444" => Some(Self::I444),
+            "P010" => Some(Self::P010),
+            _ => None,
+        }
+    }
+
+    /// 各平面的 (宽度字节数, 行数)
+    ///
+    /// # 参数
+    ///
+    /// * `width` - 图像宽度
+    /// * `height` - 图像高度
+    pub fn planes(&self, width: u32, height: u32) -> Vec<(usize, usize)> {
+        let (w, h) = (width as usize, height as usize);
+        let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+
+        match self {
+            Self::Rgba => vec![(w * 4, h)],
+            Self::Yuyv => vec![(cw * 4, h)],
+            Self::Nv12 => vec![(w, h), (cw * 2, ch)],
+            Self::I420 => vec![(w, h), (cw, ch), (cw, ch)],
+            Self::I444 => vec![(w, h), (w, h), (w, h)],
+            Self::P010 => vec![(w * 2, h), (cw * 4, ch)],
+        }
+    }
+
+    /// 一帧图像的字节数
+    pub fn frame_size(&self, width: u32, height: u32) -> usize {
+        self.planes(width, height)
+            .iter()
+            .map(|(row, rows)| row * rows)
+            .sum()
+    }
+}
+
+/// 视频帧，平面数据按顺序紧密排列在 `data` 中
+#[derive(Debug, Clone)]
+pub struct VideoFrame {
+    pub width: u32,
+    pub height: u32,
+    pub format: VideoFormat,
+    /// 时间戳（纳秒），使用合成时钟
+    pub timestamp: u64,
+    pub data: Vec<u8>,
+}
+
+impl VideoFrame {
+    /// 获取指定平面的数据
+    ///
+    /// # 参数
+    ///
+    /// * `index` - 平面索引
+    pub fn plane(&self, index: usize) -> Option<&[u8]> {
+        let planes = self.format.planes(self.width, self.height);
+        let offset: usize = planes[..index.min(planes.len())]
+            .iter()
+            .map(|(row, rows)| row * rows)
+            .sum();
+
+        planes
+            .get(index)
+            .and_then(|(row, rows)| self.data.get(offset..offset + row * rows))
+    }
+}
+
+/// 视频设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoInfo {
+    /// 画布宽度
+    pub base_width: u32,
+    /// 画布高度
+    pub base_height: u32,
+    /// 输出（缩放后）宽度
+    pub output_width: u32,
+    /// 输出（缩放后）高度
+    pub output_height: u32,
+    /// 帧率分子
+    pub fps_num: u32,
+    /// 帧率分母
+    pub fps_den: u32,
+}
+
+impl Default for VideoInfo {
+    fn default() -> Self {
+        Self {
+            base_width: 1920,
+            base_height: 1080,
+            output_width: 1280,
+            output_height: 720,
+            fps_num: 30,
+            fps_den: 1,
+        }
+    }
+}
+
+impl VideoInfo {
+    /// 从当前配置文件的 `[Video]` 节读取视频设置
+    pub fn load() -> Self {
+        let default = Self::default();
+        let get = |key: &str, default: u32| {
+            get_profile_config("Video", key)
+                .and_then(|value| value.parse::<u32>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(default)
+        };
+
+        let (fps_num, fps_den) = match get_profile_config("Video", "FPSType").as_deref() {
+            Some("1") => (get("FPSInt", 30), 1),
+            Some("2") => (get("FPSNum", 30), get("FPSDen", 1)),
+            _ => parse_common_fps(
+                &get_profile_config("Video", "FPSCommon").unwrap_or("30".to_string()),
+            ),
+        };
+
+        Self {
+            base_width: get("BaseCX", default.base_width),
+            base_height: get("BaseCY", default.base_height),
+            output_width: get("OutputCX", default.output_width),
+            output_height: get("OutputCY", default.output_height),
+            fps_num,
+            fps_den,
+        }
+    }
+
+    /// 帧间隔（纳秒）
+    pub fn frame_interval_ns(&self) -> u64 {
+        1_000_000_000 * self.fps_den as u64 / self.fps_num.max(1) as u64
+    }
+}
+
+/// 解析常用帧率，如 `29.97`、`24 NTSC`
+fn parse_common_fps(value: &str) -> (u32, u32) {
+    match value {
+        "10" => (10, 1),
+        "20" => (20, 1),
+        "24 NTSC" => (24000, 1001),
+        "25 PAL" | "25" => (25, 1),
+        "29.97" => (30000, 1001),
+        "48" => (48, 1),
+        "50 PAL" | "50" => (50, 1),
+        "59.94" => (60000, 1001),
+        "60" => (60, 1),
+        _ => (30, 1),
+    }
+}
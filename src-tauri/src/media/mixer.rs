@@ -0,0 +1,343 @@
+/// 音频混音模块
+///
+/// 来源推送带合成时钟时间戳的音频，混音线程按固定块大小把所有来源对齐到同一时间轴上混合，
+/// 再分发给已连接的输出
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use lazy_static::lazy_static;
+use log::info;
+
+use crate::{
+    media::{
+        audio::{AudioFrame, AudioInfo},
+        clock::{clock_ns, instant_at},
+    },
+    scene::scenes,
+    Result,
+};
+
+/// 每次混音的采样数（每声道）
+const MIX_FRAMES: u64 = 1024;
+
+/// 每个连接缓存的最大块数，输出处理不及时时丢弃新块
+const AUDIO_QUEUE_SIZE: usize = 16;
+
+/// 混音落后于合成时钟的时间（纳秒），给来源留出推送音频的余量
+const MIX_LATENCY_NS: u64 = 100_000_000;
+
+/// 时间戳与缓冲末尾相差超过该值（纳秒）时重新对齐
+const MAX_TIMESTAMP_JUMP_NS: u64 = 70_000_000;
+
+/// 缓冲末尾之后最多补齐的静音时长（纳秒），间隔更长时直接丢弃旧数据
+const MAX_GAP_NS: u64 = 2_000_000_000;
+
+/// 一个来源的音频缓冲，采样已转换为混音格式
+#[derive(Default)]
+struct Track {
+    /// 缓冲中第一个采样的时间戳
+    start: u64,
+    samples: VecDeque<f32>,
+}
+
+impl Track {
+    /// 追加已转换为混音格式的音频
+    ///
+    /// 时间戳与缓冲末尾衔接时直接追加，稍有间隔时补静音，相差过大时丢弃旧数据重新对齐
+    fn push(&mut self, frame: AudioFrame, info: AudioInfo) {
+        let channels = info.channels as usize;
+        let buffered = (self.samples.len() / channels) as u64;
+        let end = self.start + frames_to_ns(buffered, info.sample_rate);
+
+        if buffered == 0
+            || frame.timestamp + MAX_TIMESTAMP_JUMP_NS < end
+            || frame.timestamp > end + MAX_GAP_NS
+        {
+            self.samples.clear();
+            self.start = frame.timestamp;
+        } else if frame.timestamp > end + MAX_TIMESTAMP_JUMP_NS {
+            let gap = ns_to_frames(frame.timestamp - end, info.sample_rate) as usize;
+            self.samples.extend(std::iter::repeat_n(0., gap * channels));
+        }
+        self.samples.extend(frame.data);
+    }
+}
+
+/// 混音状态
+#[derive(Default)]
+struct MixerState {
+    /// 已连接的输出
+    senders: Vec<SyncSender<Arc<AudioFrame>>>,
+    /// 各来源的音频缓冲，按来源 UUID 索引
+    tracks: HashMap<String, Track>,
+    /// 混音格式，混音线程启动时确定
+    info: AudioInfo,
+    /// 混音线程是否在运行
+    running: bool,
+}
+
+lazy_static! {
+    static ref MIXER: Mutex<MixerState> = Mutex::new(MixerState::default());
+}
+
+/// 采样数对应的时长（纳秒）
+fn frames_to_ns(frames: u64, sample_rate: u32) -> u64 {
+    frames * 1_000_000_000 / sample_rate as u64
+}
+
+/// 时长（纳秒）对应的采样数
+fn ns_to_frames(ns: u64, sample_rate: u32) -> u64 {
+    ns * sample_rate as u64 / 1_000_000_000
+}
+
+/// 推送来源的音频
+///
+/// 时间戳与已缓冲的音频衔接时直接追加，稍有间隔时补静音，相差过大时丢弃旧数据重新对齐
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+/// * `frame` - 音频帧，时间戳使用合成时钟
+pub fn push_audio(uuid: &str, frame: &AudioFrame) {
+    let mut mixer = MIXER.lock().unwrap();
+    let info = if mixer.running {
+        mixer.info
+    } else {
+        AudioInfo::load()
+    };
+    let frame = frame.convert(info.sample_rate, info.channels);
+
+    mixer
+        .tracks
+        .entry(uuid.to_string())
+        .or_default()
+        .push(frame, info);
+}
+
+/// 清空来源已缓冲的音频，如暂停或跳转时
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+pub fn clear_audio(uuid: &str) {
+    MIXER.lock().unwrap().tracks.remove(uuid);
+}
+
+/// 连接混音输出，接收混音后的音频块
+///
+/// 丢弃返回的接收端即断开连接，所有连接断开后混音线程退出
+///
+/// # 返回值
+///
+/// 返回 `Result<Receiver<Arc<AudioFrame>>>`，表示音频块的接收端
+pub fn connect_audio() -> Result<Receiver<Arc<AudioFrame>>> {
+    let (sender, receiver) = sync_channel(AUDIO_QUEUE_SIZE);
+
+    let mut mixer = MIXER.lock().unwrap();
+    mixer.senders.push(sender);
+
+    if !mixer.running {
+        let info = AudioInfo::load();
+        thread::Builder::new()
+            .name("audio".to_string())
+            .spawn(move || run_mixer(info))?;
+        mixer.info = info;
+        mixer.running = true;
+    }
+
+    Ok(receiver)
+}
+
+/// 混音是否正在运行
+pub fn audio_active() -> bool {
+    MIXER.lock().unwrap().running
+}
+
+/// 从缓冲中取出 `[from, from + frames)` 时间段的音频并叠加到 `output`
+fn mix_track(track: &mut Track, info: AudioInfo, from: u64, gain: f32, output: &mut [f32]) {
+    let channels = info.channels as usize;
+    let frames = (output.len() / channels) as u64;
+
+    // 丢弃早于混音时间段的采样
+    if track.start < from {
+        let stale = ns_to_frames(from - track.start, info.sample_rate) as usize * channels;
+        let stale = stale.min(track.samples.len());
+        track.samples.drain(..stale);
+        track.start = from;
+    }
+
+    // 缓冲晚于时间段开始时前面留空
+    let offset = ns_to_frames(track.start - from, info.sample_rate);
+    if offset >= frames {
+        return;
+    }
+    let count = ((frames - offset) as usize * channels).min(track.samples.len());
+    for (i, sample) in track.samples.drain(..count).enumerate() {
+        output[offset as usize * channels + i] += sample * gain;
+    }
+    track.start = from + frames_to_ns(frames, info.sample_rate);
+}
+
+/// 混音线程
+fn run_mixer(info: AudioInfo) {
+    info!(
+        "audio started: {} Hz, {} channels",
+        info.sample_rate, info.channels
+    );
+
+    let base = clock_ns().saturating_sub(MIX_LATENCY_NS);
+    let mut mixed: u64 = 0;
+
+    loop {
+        let from = base + frames_to_ns(mixed, info.sample_rate);
+        mixed += MIX_FRAMES;
+        let to = base + frames_to_ns(mixed, info.sample_rate);
+
+        let target = instant_at(to + MIX_LATENCY_NS);
+        let now = std::time::Instant::now();
+        if target > now {
+            thread::sleep(target - now);
+        }
+
+        // 先读取音量，避免同时持有场景和混音两把锁
+        let uuids: Vec<String> = MIXER.lock().unwrap().tracks.keys().cloned().collect();
+        let gains: HashMap<String, f32> = {
+            let scenes = scenes();
+            uuids
+                .into_iter()
+                .map(|uuid| {
+                    let gain = scenes.source(&uuid).map_or(1., |source| {
+                        if source.muted {
+                            0.
+                        } else {
+                            source.volume
+                        }
+                    });
+                    (uuid, gain)
+                })
+                .collect()
+        };
+
+        let mut mixer = MIXER.lock().unwrap();
+        let mut data = vec![0f32; MIX_FRAMES as usize * info.channels as usize];
+        for (uuid, track) in mixer.tracks.iter_mut() {
+            let gain = gains.get(uuid).copied().unwrap_or(1.);
+            mix_track(track, info, from, gain, &mut data);
+        }
+        for sample in data.iter_mut() {
+            *sample = sample.clamp(-1., 1.);
+        }
+
+        let frame = Arc::new(AudioFrame {
+            sample_rate: info.sample_rate,
+            channels: info.channels,
+            timestamp: from,
+            data,
+        });
+        mixer
+            .senders
+            .retain(|sender| match sender.try_send(frame.clone()) {
+                Ok(_) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+
+        if mixer.senders.is_empty() {
+            mixer.running = false;
+            info!("audio stopped");
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    const INFO: AudioInfo = AudioInfo {
+        sample_rate: 1000,
+        channels: 1,
+    };
+
+    /// 每毫秒一个采样，值都为 `value`
+    fn frame(timestamp_ms: u64, frames: usize, value: f32) -> AudioFrame {
+        AudioFrame {
+            sample_rate: INFO.sample_rate,
+            channels: INFO.channels,
+            timestamp: timestamp_ms * MS,
+            data: vec![value; frames],
+        }
+    }
+
+    /// 取出 `[from, from + frames)` 毫秒的混音结果
+    fn mix(track: &mut Track, from_ms: u64, frames: usize) -> Vec<f32> {
+        let mut output = vec![0.; frames];
+        mix_track(track, INFO, from_ms * MS, 1., &mut output);
+        output
+    }
+
+    #[test]
+    fn tracks_are_aligned_to_the_mix_window() {
+        let mut track = Track::default();
+        track.push(frame(1005, 10, 1.), INFO);
+
+        // 缓冲晚于时间段开始时前面留空
+        let output = mix(&mut track, 1000, 8);
+        assert_eq!(output, [0., 0., 0., 0., 0., 1., 1., 1.]);
+        assert_eq!(track.start, 1008 * MS);
+        assert_eq!(track.samples.len(), 7);
+
+        // 早于时间段的采样被丢弃
+        let output = mix(&mut track, 1010, 8);
+        assert_eq!(output, [1., 1., 1., 1., 1., 0., 0., 0.]);
+        assert!(track.samples.is_empty());
+    }
+
+    #[test]
+    fn small_gaps_are_appended_and_larger_gaps_filled_with_silence() {
+        let mut track = Track::default();
+        track.push(frame(0, 10, 1.), INFO);
+        // 抖动不超过 MAX_TIMESTAMP_JUMP_NS 时直接衔接
+        track.push(frame(10 + 50, 10, 2.), INFO);
+        assert_eq!(track.samples.len(), 20);
+        assert_eq!(track.start, 0);
+
+        // 间隔超过 MAX_TIMESTAMP_JUMP_NS 时补齐静音
+        track.push(frame(20 + 100, 10, 3.), INFO);
+        assert_eq!(track.samples.len(), 20 + 100 + 10);
+        let output = mix(&mut track, 0, 130);
+        assert!(output[..10].iter().all(|sample| *sample == 1.));
+        assert!(output[10..20].iter().all(|sample| *sample == 2.));
+        assert!(output[20..120].iter().all(|sample| *sample == 0.));
+        assert!(output[120..].iter().all(|sample| *sample == 3.));
+    }
+
+    #[test]
+    fn large_jumps_realign_the_track() {
+        let mut track = Track::default();
+        track.push(frame(1000, 10, 1.), INFO);
+
+        // 时间戳倒退超过 MAX_TIMESTAMP_JUMP_NS 时丢弃旧数据
+        track.push(frame(1010 - 80, 10, 2.), INFO);
+        assert_eq!(track.start, 930 * MS);
+        assert_eq!(track.samples, [2.; 10]);
+
+        // 倒退不超过 MAX_TIMESTAMP_JUMP_NS 时仍然追加
+        track.push(frame(940 - 60, 10, 3.), INFO);
+        assert_eq!(track.start, 930 * MS);
+        assert_eq!(track.samples.len(), 20);
+
+        // 间隔超过 MAX_GAP_NS 时不补静音，直接从新位置开始
+        let end = 950 + MAX_GAP_NS / MS;
+        track.push(frame(end + 1, 10, 4.), INFO);
+        assert_eq!(track.start, (end + 1) * MS);
+        assert_eq!(track.samples, [4.; 10]);
+    }
+}
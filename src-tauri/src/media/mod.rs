@@ -0,0 +1,15 @@
+#![allow(dead_code)]
+
+pub mod aac;
+pub mod animation;
+pub mod audio;
+pub mod clock;
+pub mod color;
+pub mod convert;
+pub mod decoder;
+pub mod encoder;
+pub mod frame;
+pub mod mixer;
+pub mod scale;
+pub mod text;
+pub mod video;
@@ -0,0 +1,212 @@
+/// CPU 缩放模块
+///
+/// 与 `graphics::scaler` 的 GPU 缩放使用相同的滤镜核，也作为其参考实现
+use std::f32::consts::PI;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    media::frame::{VideoFormat, VideoFrame},
+    utils::profile::get_profile_config,
+    Result,
+};
+
+/// 缩放滤镜
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleFilter {
+    /// 最近邻
+    Point,
+    /// 双线性
+    Bilinear,
+    /// 双三次
+    #[default]
+    Bicubic,
+    /// Lanczos，3 瓣
+    Lanczos,
+    /// 区域平均
+    Area,
+}
+
+impl ScaleFilter {
+    /// 从配置文件中的名称解析，如 `bicubic`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "point" => Some(Self::Point),
+            "bilinear" => Some(Self::Bilinear),
+            "bicubic" => Some(Self::Bicubic),
+            "lanczos" => Some(Self::Lanczos),
+            "area" => Some(Self::Area),
+            _ => None,
+        }
+    }
+
+    /// 从当前配置文件的 `[Video]` 节读取缩放滤镜
+    pub fn load() -> Self {
+        get_profile_config("Video", "ScaleType")
+            .and_then(|name| Self::from_name(&name))
+            .unwrap_or_default()
+    }
+
+    /// 着色器中使用的编号
+    pub fn id(&self) -> u32 {
+        match self {
+            Self::Point => 0,
+            Self::Bilinear => 1,
+            Self::Bicubic => 2,
+            Self::Lanczos => 3,
+            Self::Area => 4,
+        }
+    }
+
+    /// 滤镜核半径（源像素），缩小时按缩放比例放大
+    pub fn radius(&self) -> f32 {
+        match self {
+            Self::Point | Self::Area => 0.,
+            Self::Bilinear => 1.,
+            Self::Bicubic => 2.,
+            Self::Lanczos => 3.,
+        }
+    }
+
+    /// 滤镜核权重
+    ///
+    /// # 参数
+    ///
+    /// * `x` - 到采样中心的距离（已按缩放比例归一化）
+    pub fn weight(&self, x: f32) -> f32 {
+        let x = x.abs();
+
+        match self {
+            Self::Bilinear => (1. - x).max(0.),
+            Self::Bicubic => {
+                // Keys 三次卷积，a = -0.5
+                let a = -0.5;
+                if x < 1. {
+                    (a + 2.) * x * x * x - (a + 3.) * x * x + 1.
+                } else if x < 2. {
+                    a * x * x * x - 5. * a * x * x + 8. * a * x - 4. * a
+                } else {
+                    0.
+                }
+            }
+            Self::Lanczos => {
+                if x < 1e-5 {
+                    1.
+                } else if x < 3. {
+                    let px = PI * x;
+                    3. * px.sin() * (px / 3.).sin() / (px * px)
+                } else {
+                    0.
+                }
+            }
+            Self::Point | Self::Area => 0.,
+        }
+    }
+}
+
+/// 计算一个方向上的采样位置和权重
+///
+/// # 参数
+///
+/// * `filter` - 缩放滤镜
+/// * `dst` - 目标像素坐标
+/// * `ratio` - 源尺寸 / 目标尺寸
+/// * `size` - 源尺寸
+///
+/// # 返回值
+///
+/// 返回 `(源像素坐标, 未归一化的权重)` 列表
+pub fn taps(filter: ScaleFilter, dst: u32, ratio: f32, size: u32) -> Vec<(u32, f32)> {
+    let max = size as i32 - 1;
+    let clamp = |i: i32| i.clamp(0, max) as u32;
+
+    match filter {
+        ScaleFilter::Point => {
+            vec![(clamp(((dst as f32 + 0.5) * ratio).floor() as i32), 1.)]
+        }
+        ScaleFilter::Area => {
+            let start = dst as f32 * ratio;
+            let end = (dst + 1) as f32 * ratio;
+            (start.floor() as i32..end.ceil() as i32)
+                .map(|i| {
+                    let weight = end.min(i as f32 + 1.) - start.max(i as f32);
+                    (clamp(i), weight)
+                })
+                .collect()
+        }
+        _ => {
+            let scale = ratio.max(1.);
+            let support = filter.radius() * scale;
+            let center = (dst as f32 + 0.5) * ratio - 0.5;
+            ((center - support).ceil() as i32..=(center + support).floor() as i32)
+                .map(|i| (clamp(i), filter.weight((i as f32 - center) / scale)))
+                .collect()
+        }
+    }
+}
+
+/// 缩放 RGBA 帧
+///
+/// # 参数
+///
+/// * `frame` - RGBA 帧
+/// * `width` - 目标宽度
+/// * `height` - 目标高度
+/// * `filter` - 缩放滤镜
+///
+/// # 返回值
+///
+/// 返回 `Result<VideoFrame>`，源帧不是 RGBA 时返回错误
+pub fn scale_rgba(
+    frame: &VideoFrame,
+    width: u32,
+    height: u32,
+    filter: ScaleFilter,
+) -> Result<VideoFrame> {
+    if frame.format != VideoFormat::Rgba {
+        return Err(anyhow!("source frame is not RGBA: {:?}", frame.format));
+    }
+    if width == 0 || height == 0 {
+        return Err(anyhow!("invalid scale size: {}x{}", width, height));
+    }
+
+    let ratio_x = frame.width as f32 / width as f32;
+    let ratio_y = frame.height as f32 / height as f32;
+    let columns: Vec<_> = (0..width)
+        .map(|x| taps(filter, x, ratio_x, frame.width))
+        .collect();
+
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        let rows = taps(filter, y, ratio_y, frame.height);
+        for column in &columns {
+            let mut sum = [0f32; 4];
+            let mut total = 0.;
+            for (sy, wy) in &rows {
+                for (sx, wx) in column {
+                    let weight = wx * wy;
+                    let i = ((sy * frame.width + sx) * 4) as usize;
+                    for (c, value) in sum.iter_mut().enumerate() {
+                        *value += frame.data[i + c] as f32 / 255. * weight;
+                    }
+                    total += weight;
+                }
+            }
+
+            for value in sum {
+                let value = if total != 0. { value / total } else { 0. };
+                data.push((value.clamp(0., 1.) * 255.).round() as u8);
+            }
+        }
+    }
+
+    Ok(VideoFrame {
+        width,
+        height,
+        format: VideoFormat::Rgba,
+        timestamp: frame.timestamp,
+        data,
+    })
+}
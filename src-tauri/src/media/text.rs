@@ -0,0 +1,417 @@
+/// 文字绘制模块
+///
+/// 使用系统字体在 CPU 上排版和光栅化文字，支持复杂文字整形、双向文字、自动换行，
+/// 以及描边、阴影和渐变
+use std::sync::Mutex;
+
+use cosmic_text::{
+    Align, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Style, SwashCache, Weight, Wrap,
+};
+use image::{Rgba, RgbaImage};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+/// 行高与字号的比例
+const LINE_HEIGHT: f32 = 1.2;
+
+lazy_static! {
+    /// 系统字体和字形缓存，加载字体较慢，全局共享
+    static ref FONTS: Mutex<(FontSystem, SwashCache)> =
+        Mutex::new((FontSystem::new(), SwashCache::new()));
+}
+
+/// 文字对齐方式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// 文字样式
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    /// 字体名称，为空时使用系统默认无衬线字体
+    pub face: String,
+    /// 字号（像素）
+    pub size: f32,
+    pub bold: bool,
+    pub italic: bool,
+    /// 文字颜色，有渐变时为顶部颜色
+    pub color: [u8; 4],
+    /// 渐变的底部颜色
+    pub gradient: Option<[u8; 4]>,
+    /// 描边宽度和颜色
+    pub outline: Option<(u32, [u8; 4])>,
+    /// 阴影偏移和颜色
+    pub shadow: Option<((i32, i32), [u8; 4])>,
+    /// 自动换行宽度，为 `None` 时只在换行符处换行
+    pub wrap_width: Option<u32>,
+    pub align: TextAlign,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            face: String::new(),
+            size: 32.,
+            bold: false,
+            italic: false,
+            color: [255; 4],
+            gradient: None,
+            outline: None,
+            shadow: None,
+            wrap_width: None,
+            align: TextAlign::Left,
+        }
+    }
+}
+
+/// 将 `0xAARRGGBB` 格式的颜色转换为 RGBA
+pub fn argb_to_rgba(color: u32) -> [u8; 4] {
+    let [a, r, g, b] = color.to_be_bytes();
+    [r, g, b, a]
+}
+
+/// 覆盖率蒙版
+struct Mask {
+    width: u32,
+    height: u32,
+    data: Vec<f32>,
+}
+
+impl Mask {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0.; (width * height) as usize],
+        }
+    }
+
+    fn get(&self, x: i64, y: i64) -> f32 {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return 0.;
+        }
+        self.data[(y as u32 * self.width + x as u32) as usize]
+    }
+
+    /// 按圆形半径膨胀，用于描边
+    fn dilate(&self, radius: u32) -> Self {
+        let r = radius as i64;
+        let offsets: Vec<(i64, i64)> = (-r..=r)
+            .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+            .filter(|(dx, dy)| dx * dx + dy * dy <= r * r)
+            .collect();
+
+        let mut dilated = Self::new(self.width, self.height);
+        for y in 0..self.height as i64 {
+            for x in 0..self.width as i64 {
+                let value = offsets
+                    .iter()
+                    .map(|(dx, dy)| self.get(x + dx, y + dy))
+                    .fold(0., f32::max);
+                dilated.data[(y as u32 * self.width + x as u32) as usize] = value;
+            }
+        }
+
+        dilated
+    }
+}
+
+/// 将一层颜色按透明度叠加到图像上
+///
+/// # 参数
+///
+/// * `image` - 目标图像
+/// * `mask` - 覆盖率蒙版
+/// * `offset` - 蒙版相对于图像的偏移
+/// * `color` - 按行返回颜色的函数
+fn composite(
+    image: &mut RgbaImage,
+    mask: &Mask,
+    offset: (i64, i64),
+    color: impl Fn(u32) -> [u8; 4],
+) {
+    for y in 0..image.height() {
+        let [r, g, b, a] = color(y);
+        for x in 0..image.width() {
+            let coverage = mask.get(x as i64 - offset.0, y as i64 - offset.1);
+            let src_a = coverage * a as f32 / 255.;
+            if src_a <= 0. {
+                continue;
+            }
+
+            let dst = image.get_pixel_mut(x, y);
+            let dst_a = dst[3] as f32 / 255.;
+            let out_a = src_a + dst_a * (1. - src_a);
+            let blend = |s: u8, d: u8| {
+                ((s as f32 * src_a + d as f32 * dst_a * (1. - src_a)) / out_a).round() as u8
+            };
+            *dst = Rgba([
+                blend(r, dst[0]),
+                blend(g, dst[1]),
+                blend(b, dst[2]),
+                (out_a * 255.).round() as u8,
+            ]);
+        }
+    }
+}
+
+/// 两个颜色之间插值
+fn lerp_color(from: [u8; 4], to: [u8; 4], t: f32) -> [u8; 4] {
+    let mut color = [0; 4];
+    for (i, value) in color.iter_mut().enumerate() {
+        *value = (from[i] as f32 + (to[i] as f32 - from[i] as f32) * t).round() as u8;
+    }
+    color
+}
+
+/// 排版并绘制文字
+///
+/// # 参数
+///
+/// * `text` - 文字内容，可以包含换行符
+/// * `style` - 文字样式
+///
+/// # 返回值
+///
+/// 返回绘制好的 RGBA 图像，尺寸包含描边和阴影，文字为空时返回 `None`
+pub fn render_text(text: &str, style: &TextStyle) -> Option<RgbaImage> {
+    if text.trim().is_empty() || style.size <= 0. {
+        return None;
+    }
+
+    let mut fonts = FONTS.lock().unwrap();
+    let (font_system, swash_cache) = &mut *fonts;
+
+    let metrics = Metrics::new(style.size, (style.size * LINE_HEIGHT).ceil());
+    let mut buffer = Buffer::new(font_system, metrics);
+    let family = if style.face.is_empty() {
+        Family::SansSerif
+    } else {
+        Family::Name(&style.face)
+    };
+    let attrs = Attrs::new()
+        .family(family)
+        .weight(if style.bold {
+            Weight::BOLD
+        } else {
+            Weight::NORMAL
+        })
+        .style(if style.italic {
+            Style::Italic
+        } else {
+            Style::Normal
+        });
+
+    buffer.set_wrap(
+        font_system,
+        if style.wrap_width.is_some() {
+            Wrap::WordOrGlyph
+        } else {
+            Wrap::None
+        },
+    );
+    buffer.set_size(
+        font_system,
+        style.wrap_width.map(|width| width as f32),
+        None,
+    );
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+
+    // 不换行时先测量最长的行，再按该宽度对齐
+    let measure = |buffer: &Buffer| {
+        buffer
+            .layout_runs()
+            .fold((0f32, 0f32), |(width, height), run| {
+                (
+                    width.max(run.line_w),
+                    height.max(run.line_top + run.line_height),
+                )
+            })
+    };
+    let (mut text_width, text_height) = measure(&buffer);
+    if let Some(width) = style.wrap_width {
+        text_width = width as f32;
+    }
+    let align = match style.align {
+        TextAlign::Left => Align::Left,
+        TextAlign::Center => Align::Center,
+        TextAlign::Right => Align::Right,
+    };
+    for line in buffer.lines.iter_mut() {
+        line.set_align(Some(align));
+    }
+    buffer.set_size(font_system, Some(text_width.ceil()), None);
+    buffer.shape_until_scroll(font_system, false);
+
+    // 描边和阴影需要额外的边距，斜体等字形也可能超出排版范围
+    let outline = style.outline.map_or(0, |(width, _)| width);
+    let (shadow_x, shadow_y) = style.shadow.map_or((0, 0), |(offset, _)| offset);
+    let pad = outline as i64 + (style.size / 4.).ceil() as i64;
+    let left = pad + (-shadow_x).max(0) as i64;
+    let top = pad + (-shadow_y).max(0) as i64;
+    let width = (text_width.ceil() as i64 + left + pad + shadow_x.max(0) as i64) as u32;
+    let height = (text_height.ceil() as i64 + top + pad + shadow_y.max(0) as i64) as u32;
+
+    let mut mask = Mask::new(width, height);
+    buffer.draw(
+        font_system,
+        swash_cache,
+        cosmic_text::Color::rgb(255, 255, 255),
+        |x, y, w, h, color| {
+            let coverage = color.a() as f32 / 255.;
+            for dy in 0..h as i64 {
+                for dx in 0..w as i64 {
+                    let (px, py) = (x as i64 + dx + left, y as i64 + dy + top);
+                    if px >= 0 && py >= 0 && px < width as i64 && py < height as i64 {
+                        let value = &mut mask.data[(py as u32 * width + px as u32) as usize];
+                        *value = value.max(coverage);
+                    }
+                }
+            }
+        },
+    );
+
+    let mut image = RgbaImage::new(width, height);
+    let outlined = style
+        .outline
+        .filter(|(width, _)| *width > 0)
+        .map(|(width, color)| (mask.dilate(width), color));
+    let shape = outlined.as_ref().map_or(&mask, |(outlined, _)| outlined);
+
+    if let Some(((x, y), color)) = style.shadow {
+        composite(&mut image, shape, (x as i64, y as i64), |_| color);
+    }
+    if let Some((outlined, color)) = &outlined {
+        composite(&mut image, outlined, (0, 0), |_| *color);
+    }
+
+    let gradient_height = text_height.max(1.);
+    composite(&mut image, &mask, (0, 0), |y| match style.gradient {
+        Some(bottom) => {
+            let t = ((y as f32 - top as f32) / gradient_height).clamp(0., 1.);
+            lerp_color(style.color, bottom, t)
+        }
+        None => style.color,
+    });
+
+    Some(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 系统中是否有包含该字符的字体
+    fn has_font_for(ch: char) -> bool {
+        let mut fonts = FONTS.lock().unwrap();
+        let font_system = &mut fonts.0;
+        let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+        ids.into_iter().any(|id| {
+            font_system
+                .get_font(id)
+                .is_some_and(|font| font.as_swash().charmap().map(ch) != 0)
+        })
+    }
+
+    /// 不透明度超过一半的像素数
+    fn covered(image: &RgbaImage) -> usize {
+        image.pixels().filter(|pixel| pixel[3] > 128).count()
+    }
+
+    #[test]
+    fn empty_text_is_not_rendered() {
+        assert!(render_text("", &TextStyle::default()).is_none());
+        assert!(render_text(" \n ", &TextStyle::default()).is_none());
+        let style = TextStyle {
+            size: 0.,
+            ..Default::default()
+        };
+        assert!(render_text("text", &style).is_none());
+    }
+
+    #[test]
+    fn bounds_grow_with_outline_and_shadow() {
+        if !has_font_for('H') {
+            eprintln!("skipping: no font");
+            return;
+        }
+        let plain = render_text("Hello", &TextStyle::default()).unwrap();
+        assert!(covered(&plain) > 0);
+        let (width, height) = plain.dimensions();
+
+        let outlined = render_text(
+            "Hello",
+            &TextStyle {
+                outline: Some((4, [0, 0, 0, 255])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(outlined.dimensions(), (width + 8, height + 8));
+        assert!(covered(&outlined) > covered(&plain));
+
+        for offset in [(5, 3), (-5, -3)] {
+            let shadowed = render_text(
+                "Hello",
+                &TextStyle {
+                    shadow: Some((offset, [0, 0, 0, 255])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert_eq!(shadowed.dimensions(), (width + 5, height + 3));
+            assert!(covered(&shadowed) > covered(&plain));
+        }
+    }
+
+    #[test]
+    fn gradient_runs_from_top_to_bottom() {
+        if !has_font_for('H') {
+            eprintln!("skipping: no font");
+            return;
+        }
+        let style = TextStyle {
+            size: 64.,
+            color: [255, 0, 0, 255],
+            gradient: Some([0, 0, 255, 255]),
+            ..Default::default()
+        };
+        let image = render_text("HH", &style).unwrap();
+
+        // 文字最上面和最下面一行的颜色
+        let rows: Vec<u32> = (0..image.height())
+            .filter(|y| (0..image.width()).any(|x| image.get_pixel(x, *y)[3] == 255))
+            .collect();
+        let row_color = |y: u32| {
+            *(0..image.width())
+                .map(|x| image.get_pixel(x, y))
+                .find(|pixel| pixel[3] == 255)
+                .unwrap()
+        };
+        let top = row_color(rows[0]);
+        let bottom = row_color(*rows.last().unwrap());
+        assert!(top[0] > top[2], "{top:?}");
+        assert!(bottom[2] > bottom[0], "{bottom:?}");
+    }
+
+    #[test]
+    fn cjk_and_arabic_text_render() {
+        for text in ["你好，世界", "مرحبا بالعالم"] {
+            if !text
+                .chars()
+                .filter(|ch| !ch.is_whitespace() && !ch.is_ascii_punctuation())
+                .all(has_font_for)
+            {
+                eprintln!("skipping {text}: no font");
+                continue;
+            }
+            let image = render_text(text, &TextStyle::default()).unwrap();
+            assert!(covered(&image) > 0, "{text} rendered empty");
+        }
+    }
+}
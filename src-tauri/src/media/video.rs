@@ -0,0 +1,968 @@
+/// 节目视频输出模块
+///
+/// 有输出连接时按设定帧率把画布当前场景中的来源合成为节目画面，
+/// 按各输出需要的格式在 GPU 上转换后异步回读，再分发给对应的输出。
+/// 每个画布有独立的合成线程，使用各自的分辨率和帧率；场景投影仪等需要单个场景或来源的画面时，
+/// 该场景或来源也有自己的合成线程，来源只在来源管理器中运行一个实例
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use log::{error, info};
+use wgpu::{
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoder, CommandEncoderDescriptor, Device,
+    ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, TextureAspect,
+    TextureFormat,
+};
+
+use crate::{
+    graphics::{
+        canvas::Canvas,
+        compositor::{Compositor, Layer},
+        convert::Converter,
+        multiview::{Multiview, MultiviewFrame, MultiviewScene, MultiviewSettings, Rect},
+        readback::Readback,
+        scaler::Scaler,
+        texture::Texture,
+    },
+    media::{
+        clock::{clock_ns, instant_at},
+        color::{ColorRange, ColorSpace},
+        frame::{VideoFormat, VideoFrame, VideoInfo},
+        scale::ScaleFilter,
+    },
+    scene::{canvas::canvas_video_info, collection::SceneCollection, item::SceneItem, scenes},
+    sources::{
+        manager::{visible_items, with_sources, SourceManager},
+        source_size,
+    },
+    stats::counters::{record_missed_frames, record_render_frame, record_skipped_frame},
+    Result,
+};
+
+/// 每个连接缓存的最大帧数，输出处理不及时时丢弃新帧
+const VIDEO_QUEUE_SIZE: usize = 2;
+
+/// 每种输出格式的暂存缓冲区数量
+const READBACK_DEPTH: usize = 3;
+
+/// 输出需要的视频格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VideoConversion {
+    pub format: VideoFormat,
+    pub space: ColorSpace,
+    pub range: ColorRange,
+    /// 输出分辨率，为 `None` 时与画布相同
+    pub size: Option<(u32, u32)>,
+    /// 缩放滤镜
+    pub filter: ScaleFilter,
+}
+
+impl VideoConversion {
+    /// 不做转换和缩放的 RGBA 输出
+    pub const RGBA: VideoConversion = VideoConversion {
+        format: VideoFormat::Rgba,
+        space: ColorSpace::Srgb,
+        range: ColorRange::Full,
+        size: None,
+        filter: ScaleFilter::Bicubic,
+    };
+
+    /// 使用配置文件中的输出分辨率和缩放滤镜
+    ///
+    /// # 参数
+    ///
+    /// * `format` - 像素格式
+    /// * `space` - 色彩空间
+    /// * `range` - 色彩范围
+    pub fn output(format: VideoFormat, space: ColorSpace, range: ColorRange) -> Self {
+        let info = VideoInfo::load();
+
+        Self {
+            format,
+            space,
+            range,
+            size: Some((info.output_width, info.output_height)),
+            filter: ScaleFilter::load(),
+        }
+    }
+
+    /// 使用画布的输出分辨率，主画布使用配置文件中的输出分辨率，附加画布不缩放
+    ///
+    /// # 参数
+    ///
+    /// * `canvas` - 画布 UUID，为 `None` 时为主画布
+    /// * `format` - 像素格式
+    /// * `space` - 色彩空间
+    /// * `range` - 色彩范围
+    pub fn canvas_output(
+        canvas: Option<&str>,
+        format: VideoFormat,
+        space: ColorSpace,
+        range: ColorRange,
+    ) -> Self {
+        match canvas {
+            Some(_) => Self {
+                size: None,
+                ..Self::output(format, space, range)
+            },
+            None => Self::output(format, space, range),
+        }
+    }
+
+    /// 为单个输出指定缩放分辨率
+    ///
+    /// # 参数
+    ///
+    /// * `width` - 输出宽度
+    /// * `height` - 输出高度
+    /// * `filter` - 缩放滤镜
+    pub fn rescaled(self, width: u32, height: u32, filter: ScaleFilter) -> Self {
+        Self {
+            size: Some((width, height)),
+            filter,
+            ..self
+        }
+    }
+}
+
+/// 合成线程绘制的画面
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VideoKey {
+    /// 画布当前场景的节目画面，为 `None` 时为主画布
+    Canvas(Option<String>),
+    /// 单个场景的画面，不论它是否为当前场景，使用场景所属画布的分辨率
+    Scene(String),
+    /// 主画布的多视图，每个场景绘制到自己的离屏纹理后按布局组合
+    Multiview,
+    /// 单个来源的画面，使用来源管理器中的实例，画面尺寸跟随来源
+    Source(String),
+}
+
+impl VideoKey {
+    /// 合成线程的名称
+    fn thread_name(&self) -> String {
+        let short = |uuid: &str| uuid.chars().take(8).collect::<String>();
+        match self {
+            Self::Canvas(None) => "video".to_string(),
+            Self::Canvas(Some(uuid)) => format!("video-{}", short(uuid)),
+            Self::Scene(uuid) => format!("video-scene-{}", short(uuid)),
+            Self::Multiview => "video-multiview".to_string(),
+            Self::Source(uuid) => format!("video-source-{}", short(uuid)),
+        }
+    }
+
+    /// 日志中使用的名称
+    fn label(&self) -> String {
+        match self {
+            Self::Canvas(None) => "canvas main".to_string(),
+            Self::Canvas(Some(uuid)) => format!("canvas {}", uuid),
+            Self::Scene(uuid) => format!("scene {}", uuid),
+            Self::Multiview => "multiview".to_string(),
+            Self::Source(uuid) => format!("source {}", uuid),
+        }
+    }
+
+    /// 画面的分辨率和帧率
+    fn video_info(&self) -> Result<VideoInfo> {
+        match self {
+            Self::Canvas(canvas) => canvas_video_info(canvas.as_deref()),
+            Self::Scene(uuid) => {
+                let canvas = scenes().try_scene(uuid)?.canvas.clone();
+                canvas_video_info(canvas.as_deref())
+            }
+            Self::Multiview => canvas_video_info(None),
+            // 来源还没有内容时先使用主画布的分辨率
+            Self::Source(uuid) => {
+                if scenes().source(uuid).is_none() {
+                    return Err(anyhow!("source not found: {}", uuid));
+                }
+                let mut info = canvas_video_info(None)?;
+                if let Some((width, height)) = source_size(uuid) {
+                    info.base_width = width;
+                    info.base_height = height;
+                }
+                Ok(info)
+            }
+        }
+    }
+
+    /// 复制要绘制的场景项，按从底到顶排列
+    ///
+    /// # 参数
+    ///
+    /// * `collection` - 场景集合
+    fn items(&self, collection: &SceneCollection) -> Vec<SceneItem> {
+        let scene = match self {
+            Self::Canvas(canvas) => collection.canvas_current_scene(canvas.as_deref()),
+            Self::Scene(uuid) => collection.scene(uuid),
+            Self::Multiview => None,
+            Self::Source(uuid) => {
+                return match collection.source(uuid) {
+                    Some(_) => vec![SceneItem::new(0, uuid)],
+                    None => vec![],
+                };
+            }
+        };
+        scene.map(visible_items).unwrap_or_default()
+    }
+}
+
+/// 节目视频的连接
+struct VideoSender {
+    conversion: VideoConversion,
+    sender: SyncSender<Arc<VideoFrame>>,
+    /// 是否为编码器的连接，编码器处理不及时而丢弃的帧计为编码延迟跳过的帧
+    encoder: bool,
+}
+
+/// 节目视频状态
+#[derive(Default)]
+struct VideoState {
+    /// 已连接的输出
+    senders: Vec<VideoSender>,
+    /// 合成线程是否在运行
+    running: bool,
+}
+
+lazy_static! {
+    /// 各画面的视频状态
+    static ref VIDEO: Mutex<HashMap<VideoKey, VideoState>> = Mutex::new(HashMap::new());
+}
+
+/// 连接节目视频，接收 RGBA 帧
+///
+/// 丢弃返回的接收端即断开连接，所有连接断开后合成线程退出
+///
+/// # 返回值
+///
+/// 返回 `Result<Receiver<Arc<VideoFrame>>>`，表示帧的接收端
+pub fn connect_video() -> Result<Receiver<Arc<VideoFrame>>> {
+    connect_video_converted(VideoConversion::RGBA)
+}
+
+/// 连接节目视频，接收在 GPU 上转换为指定格式的帧
+///
+/// # 参数
+///
+/// * `conversion` - 视频格式
+///
+/// # 返回值
+///
+/// 返回 `Result<Receiver<Arc<VideoFrame>>>`，表示帧的接收端
+pub fn connect_video_converted(conversion: VideoConversion) -> Result<Receiver<Arc<VideoFrame>>> {
+    connect_canvas_video(None, conversion)
+}
+
+/// 连接画布的节目视频，接收在 GPU 上转换为指定格式的帧
+///
+/// # 参数
+///
+/// * `canvas` - 画布 UUID，为 `None` 时为主画布
+/// * `conversion` - 视频格式
+///
+/// # 返回值
+///
+/// 返回 `Result<Receiver<Arc<VideoFrame>>>`，画布不存在时返回错误
+pub fn connect_canvas_video(
+    canvas: Option<&str>,
+    conversion: VideoConversion,
+) -> Result<Receiver<Arc<VideoFrame>>> {
+    connect_keyed_video(VideoKey::Canvas(canvas.map(str::to_string)), conversion)
+}
+
+/// 连接画布的节目视频供编码器使用，编码器处理不及时而丢弃的帧计入统计
+///
+/// # 参数
+///
+/// * `canvas` - 画布 UUID，为 `None` 时为主画布
+/// * `conversion` - 视频格式
+///
+/// # 返回值
+///
+/// 返回 `Result<Receiver<Arc<VideoFrame>>>`，画布不存在时返回错误
+pub fn connect_encoder_video(
+    canvas: Option<&str>,
+    conversion: VideoConversion,
+) -> Result<Receiver<Arc<VideoFrame>>> {
+    connect(
+        VideoKey::Canvas(canvas.map(str::to_string)),
+        conversion,
+        true,
+    )
+}
+
+/// 连接画面，接收在 GPU 上转换为指定格式的帧
+///
+/// 同一画面的所有连接共用一个合成线程
+///
+/// # 参数
+///
+/// * `key` - 画面
+/// * `conversion` - 视频格式
+///
+/// # 返回值
+///
+/// 返回 `Result<Receiver<Arc<VideoFrame>>>`，画布或场景不存在时返回错误
+pub fn connect_keyed_video(
+    key: VideoKey,
+    conversion: VideoConversion,
+) -> Result<Receiver<Arc<VideoFrame>>> {
+    connect(key, conversion, false)
+}
+
+/// 连接画面并在需要时启动合成线程
+///
+/// # 参数
+///
+/// * `key` - 画面
+/// * `conversion` - 视频格式
+/// * `encoder` - 是否为编码器的连接
+///
+/// # 返回值
+///
+/// 返回 `Result<Receiver<Arc<VideoFrame>>>`，画布或场景不存在时返回错误
+fn connect(
+    key: VideoKey,
+    conversion: VideoConversion,
+    encoder: bool,
+) -> Result<Receiver<Arc<VideoFrame>>> {
+    let info = key.video_info()?;
+    let (sender, receiver) = sync_channel(VIDEO_QUEUE_SIZE);
+
+    let mut video = VIDEO.lock().unwrap();
+    let state = video.entry(key.clone()).or_default();
+    state.senders.push(VideoSender {
+        conversion,
+        sender,
+        encoder,
+    });
+
+    if !state.running {
+        thread::Builder::new()
+            .name(key.thread_name())
+            .spawn(move || run_video(key, info))?;
+        state.running = true;
+    }
+
+    Ok(receiver)
+}
+
+/// 主画布的节目视频是否正在合成
+pub fn video_active() -> bool {
+    canvas_video_active(None)
+}
+
+/// 画布的节目视频是否正在合成
+///
+/// # 参数
+///
+/// * `canvas` - 画布 UUID，为 `None` 时为主画布
+pub fn canvas_video_active(canvas: Option<&str>) -> bool {
+    VIDEO
+        .lock()
+        .unwrap()
+        .get(&VideoKey::Canvas(canvas.map(str::to_string)))
+        .is_some_and(|state| state.running)
+}
+
+/// 多视图中要绘制的场景，在场景集合的锁内复制
+struct MultiviewSnapshot {
+    /// 主画布的当前场景 UUID
+    current: Option<String>,
+    /// 当前场景中可见的场景项
+    program: Vec<SceneItem>,
+    /// 主画布的场景，最多为布局能显示的数量：UUID、名称和可见的场景项
+    scenes: Vec<(String, String, Vec<SceneItem>)>,
+}
+
+impl MultiviewSnapshot {
+    /// 复制场景集合中的主画布场景
+    ///
+    /// # 参数
+    ///
+    /// * `collection` - 场景集合
+    /// * `count` - 最多复制的场景数
+    fn new(collection: &SceneCollection, count: usize) -> Self {
+        Self {
+            current: collection.current_scene.clone(),
+            program: collection
+                .current_scene()
+                .map(visible_items)
+                .unwrap_or_default(),
+            scenes: collection
+                .scenes
+                .iter()
+                .filter(|scene| scene.canvas.is_none())
+                .take(count)
+                .map(|scene| (scene.uuid.clone(), scene.name.clone(), visible_items(scene)))
+                .collect(),
+        }
+    }
+}
+
+/// 离屏绘制的一个场景，每个场景有自己的合成器，同一次提交中可以绘制多个场景
+struct SceneTarget {
+    compositor: Compositor,
+    texture: Texture,
+}
+
+impl SceneTarget {
+    fn new(device: &Device, size: (u32, u32)) -> Self {
+        Self {
+            compositor: Compositor::new(device, TextureFormat::Rgba8UnormSrgb),
+            texture: Texture::render_target(device, size.0, size.1, Some("multiview scene")),
+        }
+    }
+
+    /// 把场景绘制到离屏纹理，格子尺寸变化时重新创建纹理
+    #[allow(clippy::too_many_arguments)]
+    fn encode(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        layers: &[Layer],
+        canvas: (u32, u32),
+        size: (u32, u32),
+    ) {
+        let size = (size.0.max(1), size.1.max(1));
+        if (self.texture.texture.width(), self.texture.texture.height()) != size {
+            self.texture = Texture::render_target(device, size.0, size.1, Some("multiview scene"));
+        }
+        self.compositor
+            .encode(device, queue, encoder, layers, &self.texture.view, canvas);
+    }
+}
+
+/// 多视图合成，各场景按格子尺寸绘制到自己的离屏纹理，再按布局绘制到画布
+struct MultiviewRender {
+    multiview: Multiview,
+    /// 多视图画面，格式为 sRGB，绘制后复制到画布
+    output: Texture,
+    /// 预览和节目格子中的当前场景
+    program: SceneTarget,
+    /// 各场景的画面，以场景 UUID 为键
+    scenes: HashMap<String, SceneTarget>,
+}
+
+impl MultiviewRender {
+    fn new(canvas: &Canvas) -> Result<Self> {
+        let device = &canvas.device;
+        Ok(Self {
+            multiview: Multiview::new(
+                device,
+                &canvas.queue,
+                TextureFormat::Rgba8UnormSrgb,
+                MultiviewSettings::load(),
+            )?,
+            output: Texture::render_target(
+                device,
+                canvas.width(),
+                canvas.height(),
+                Some("multiview"),
+            ),
+            program: SceneTarget::new(device, (1, 1)),
+            scenes: HashMap::new(),
+        })
+    }
+
+    /// 绘制一帧多视图到画布
+    ///
+    /// # 参数
+    ///
+    /// * `canvas` - 画布
+    /// * `snapshot` - 要绘制的场景
+    /// * `sources` - 来源管理器，来源线程还没有启动时为 `None`
+    fn render(
+        &mut self,
+        canvas: &Canvas,
+        snapshot: &MultiviewSnapshot,
+        sources: Option<&SourceManager>,
+    ) {
+        let (device, queue) = (&*canvas.device, &*canvas.queue);
+        let size = (canvas.width(), canvas.height());
+        let cells = self
+            .multiview
+            .settings
+            .layout
+            .cells(size.0 as f32, size.1 as f32);
+        let cell_size = |rect: &Rect| (rect.width.ceil() as u32, rect.height.ceil() as u32);
+        let layers = |items| sources.map_or_else(Vec::new, |sources| sources.layers(items));
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+        if let Some(rect) = cells.program.or(cells.preview) {
+            self.program.encode(
+                device,
+                queue,
+                &mut encoder,
+                &layers(&snapshot.program),
+                size,
+                cell_size(&rect),
+            );
+        }
+
+        self.scenes
+            .retain(|uuid, _| snapshot.scenes.iter().any(|(scene, ..)| scene == uuid));
+        for ((uuid, _, items), rect) in snapshot.scenes.iter().zip(&cells.scenes) {
+            self.scenes
+                .entry(uuid.clone())
+                .or_insert_with(|| SceneTarget::new(device, cell_size(rect)))
+                .encode(
+                    device,
+                    queue,
+                    &mut encoder,
+                    &layers(items),
+                    size,
+                    cell_size(rect),
+                );
+        }
+
+        let program = cells.program.map(|_| &self.program.texture);
+        let scenes: Vec<MultiviewScene> = snapshot
+            .scenes
+            .iter()
+            .map(|(uuid, name, _)| MultiviewScene {
+                uuid,
+                name,
+                texture: self.scenes.get(uuid).map(|target| &target.texture),
+            })
+            .collect();
+        let frame = MultiviewFrame {
+            preview: program,
+            program,
+            preview_scene: snapshot.current.as_deref(),
+            program_scene: snapshot.current.as_deref(),
+            scenes: &scenes,
+        };
+        self.multiview
+            .encode(device, queue, &mut encoder, &frame, &self.output.view, size);
+
+        // 两种格式只差 sRGB 标记，复制后画布中是编码后的值
+        encoder.copy_texture_to_texture(
+            self.output.texture.as_image_copy(),
+            canvas.texture.as_image_copy(),
+            canvas.texture.size(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+/// 一种输出格式的转换和回读
+struct Stage {
+    conversion: VideoConversion,
+    /// 缩放器，输出分辨率与画布相同时为 `None`
+    scaler: Option<Scaler>,
+    /// GPU 转换器，RGBA 输出时为 `None`
+    converter: Option<Converter>,
+    /// RGBA 输出时画布复制到的缓冲区及其行跨度
+    rgba: Option<(Buffer, u32)>,
+    readback: Readback,
+}
+
+impl Stage {
+    fn new(canvas: &Canvas, conversion: VideoConversion) -> Result<Self> {
+        let source = (canvas.width(), canvas.height());
+        let scaler = match conversion.size {
+            Some(size) if size != source => Some(Scaler::new(
+                &canvas.device,
+                source,
+                size,
+                conversion.filter,
+            )?),
+            _ => None,
+        };
+        let (width, height) = conversion.size.unwrap_or(source);
+
+        let (converter, rgba, size) = if conversion.format == VideoFormat::Rgba {
+            let stride = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+            let buffer = canvas.device.create_buffer(&BufferDescriptor {
+                label: Some("canvas copy"),
+                size: stride as u64 * height as u64,
+                usage: BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let size = buffer.size();
+            (None, Some((buffer, stride)), size)
+        } else {
+            let converter = Converter::new(
+                &canvas.device,
+                width,
+                height,
+                conversion.format,
+                conversion.space,
+                conversion.range,
+            )?;
+            let size = converter.output_size();
+            (Some(converter), None, size)
+        };
+
+        Ok(Self {
+            conversion,
+            scaler,
+            converter,
+            rgba,
+            readback: Readback::new(&canvas.device, size, READBACK_DEPTH),
+        })
+    }
+
+    /// 记录转换和复制命令，暂存缓冲区用尽时丢弃这一帧
+    fn encode(&mut self, canvas: &Canvas, encoder: &mut wgpu::CommandEncoder, timestamp: u64) {
+        let (texture, view) = match &self.scaler {
+            Some(scaler) => {
+                scaler.encode(&canvas.device, encoder, &canvas.view);
+                (&scaler.texture, &scaler.view)
+            }
+            None => (&canvas.texture, &canvas.view),
+        };
+
+        let output = if let Some(converter) = &self.converter {
+            converter.encode(&canvas.device, encoder, view);
+            converter.output()
+        } else if let Some((buffer, stride)) = &self.rgba {
+            encoder.copy_texture_to_buffer(
+                ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                ImageCopyBuffer {
+                    buffer,
+                    layout: ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(*stride),
+                        rows_per_image: Some(texture.height()),
+                    },
+                },
+                texture.size(),
+            );
+            buffer
+        } else {
+            return;
+        };
+
+        self.readback.enqueue(encoder, output, timestamp);
+    }
+
+    /// 取出已完成回读的帧
+    fn read(&mut self, canvas: &Canvas) -> Vec<VideoFrame> {
+        let (width, height) = self
+            .conversion
+            .size
+            .unwrap_or((canvas.width(), canvas.height()));
+        let mut frames = vec![];
+
+        while let Some((timestamp, data)) = self.readback.read(&canvas.device, false) {
+            let data = if let Some(converter) = &self.converter {
+                converter.unpack(&data)
+            } else {
+                let row = (width * 4) as usize;
+                let stride = self
+                    .rgba
+                    .as_ref()
+                    .map_or(row, |(_, stride)| *stride as usize);
+                data.chunks(stride)
+                    .flat_map(|chunk| &chunk[..row])
+                    .copied()
+                    .collect()
+            };
+
+            frames.push(VideoFrame {
+                width,
+                height,
+                format: self.conversion.format,
+                timestamp,
+                data,
+            });
+        }
+
+        frames
+    }
+}
+
+/// 画面的合成线程
+fn run_video(key: VideoKey, info: VideoInfo) {
+    let label = key.label();
+    // 渲染统计只记录主画布的节目合成，其他画布、场景和预览不计入
+    let program = key == VideoKey::Canvas(None);
+    info!(
+        "video started on {}: {}x{} {}/{} fps",
+        label, info.base_width, info.base_height, info.fps_num, info.fps_den
+    );
+
+    let mut canvas = match Canvas::new(info.base_width, info.base_height) {
+        Ok(canvas) => canvas,
+        Err(e) => {
+            error!("failed to create canvas for {}: {}", label, e);
+
+            // 丢弃所有发送端，输出会收到断开连接
+            VIDEO.lock().unwrap().remove(&key);
+            return;
+        }
+    };
+
+    let mut multiview = match key {
+        VideoKey::Multiview => match MultiviewRender::new(&canvas) {
+            Ok(multiview) => Some(multiview),
+            Err(e) => {
+                error!("failed to create multiview: {}", e);
+                VIDEO.lock().unwrap().remove(&key);
+                return;
+            }
+        },
+        _ => None,
+    };
+
+    // 帧时间对齐到合成时钟，与音频和媒体来源使用同一时间轴
+    let interval_ns = info.frame_interval_ns();
+    let mut frame_index: u64 = clock_ns().div_ceil(interval_ns);
+    let mut stages: HashMap<VideoConversion, Stage> = HashMap::new();
+
+    loop {
+        let target = instant_at(frame_index * interval_ns);
+        let now = Instant::now();
+        if target > now {
+            thread::sleep(target - now);
+        } else {
+            // 渲染落后时跳过错过的帧
+            let behind = (now - target).as_nanos() as u64 / interval_ns;
+            if behind > 0 {
+                if program {
+                    record_missed_frames(behind);
+                }
+                frame_index += behind;
+            }
+        }
+
+        // 只为仍有连接的格式创建转换
+        let conversions: Vec<VideoConversion> = {
+            let video = VIDEO.lock().unwrap();
+            let mut conversions = vec![];
+            for sender in video.get(&key).iter().flat_map(|state| &state.senders) {
+                if !conversions.contains(&sender.conversion) {
+                    conversions.push(sender.conversion);
+                }
+            }
+            conversions
+        };
+        stages.retain(|conversion, _| conversions.contains(conversion));
+        for conversion in conversions {
+            if let Entry::Vacant(entry) = stages.entry(conversion) {
+                match Stage::new(&canvas, conversion) {
+                    Ok(stage) => {
+                        entry.insert(stage);
+                    }
+                    Err(e) => {
+                        error!("failed to create video conversion {:?}: {}", conversion, e);
+                        if let Some(state) = VIDEO.lock().unwrap().get_mut(&key) {
+                            state
+                                .senders
+                                .retain(|sender| sender.conversion != conversion);
+                        }
+                    }
+                }
+            }
+        }
+
+        // 来源尺寸变化时按新尺寸重新创建画布，格式转换在下一帧重新创建
+        if let VideoKey::Source(uuid) = &key {
+            let resized =
+                source_size(uuid).filter(|size| *size != (canvas.width(), canvas.height()));
+            if let Some((width, height)) = resized {
+                match Canvas::new(width, height) {
+                    Ok(resized) => {
+                        canvas = resized;
+                        stages.clear();
+                        continue;
+                    }
+                    Err(e) => error!("failed to resize canvas for {}: {}", label, e),
+                }
+            }
+        }
+
+        let render_start = Instant::now();
+        let timestamp = frame_index * interval_ns;
+        // 先复制场景项并释放场景集合的锁，来源更新时会读取场景集合
+        if let Some(multiview) = &mut multiview {
+            let count = multiview.multiview.settings.layout.scene_count();
+            let snapshot = MultiviewSnapshot::new(&scenes(), count);
+            if with_sources(|sources| multiview.render(&canvas, &snapshot, Some(sources))).is_none()
+            {
+                multiview.render(&canvas, &snapshot, None);
+            }
+        } else {
+            let items = key.items(&scenes());
+            if with_sources(|sources| canvas.render(&sources.layers(&items))).is_none() {
+                canvas.render(&[]);
+            }
+        }
+
+        let mut encoder = canvas
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        for stage in stages.values_mut() {
+            stage.encode(&canvas, &mut encoder, timestamp);
+        }
+        canvas.queue.submit(std::iter::once(encoder.finish()));
+        for stage in stages.values_mut() {
+            stage.readback.map_submitted();
+        }
+        if program {
+            record_render_frame(render_start.elapsed());
+        }
+        frame_index += 1;
+
+        let mut frames: Vec<(VideoConversion, Arc<VideoFrame>)> = vec![];
+        for stage in stages.values_mut() {
+            for frame in stage.read(&canvas) {
+                frames.push((stage.conversion, Arc::new(frame)));
+            }
+        }
+
+        let mut video = VIDEO.lock().unwrap();
+        let state = video.entry(key.clone()).or_default();
+        for (conversion, frame) in frames {
+            state.senders.retain(|sender| {
+                sender.conversion != conversion
+                    || match sender.sender.try_send(frame.clone()) {
+                        Ok(_) => true,
+                        Err(TrySendError::Full(_)) => {
+                            if sender.encoder {
+                                record_skipped_frame();
+                            }
+                            true
+                        }
+                        Err(TrySendError::Disconnected(_)) => false,
+                    }
+            });
+        }
+
+        if state.senders.is_empty() {
+            video.remove(&key);
+            info!("video stopped on {}", label);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+    use image::{Rgba, RgbaImage};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        graphics::canvas::shared_device,
+        scene::{
+            collection::Scene,
+            source::Source,
+            transform::{BoundsType, Transform},
+        },
+        sources::{manager::source_states, IMAGE_SOURCE},
+    };
+
+    #[test]
+    fn scene_keys_draw_their_own_scene() {
+        let mut collection = SceneCollection::new("test");
+        let mut current = Scene::new("Current");
+        current.items.push(SceneItem::new(1, "current-source"));
+        let mut other = Scene::new("Other");
+        other.items.push(SceneItem::new(1, "visible"));
+        let mut hidden = SceneItem::new(2, "hidden");
+        hidden.visible = false;
+        other.items.push(hidden);
+        other.items.push(SceneItem::new(3, "top"));
+        collection.current_scene = Some(current.uuid.clone());
+        let other_uuid = other.uuid.clone();
+        collection.scenes = vec![current, other];
+        let source = collection
+            .add_source(Source::new("Image", IMAGE_SOURCE, json!({})))
+            .unwrap();
+
+        let sources = |key: VideoKey| -> Vec<String> {
+            key.items(&collection)
+                .into_iter()
+                .map(|item| item.source)
+                .collect()
+        };
+        assert_eq!(sources(VideoKey::Canvas(None)), ["current-source"]);
+        // 场景不是当前场景时也绘制它自己的场景项
+        assert_eq!(sources(VideoKey::Scene(other_uuid)), ["visible", "top"]);
+        assert!(sources(VideoKey::Scene("missing".to_string())).is_empty());
+        assert!(sources(VideoKey::Canvas(Some("missing".to_string()))).is_empty());
+        // 来源画面只绘制来源本身
+        assert_eq!(sources(VideoKey::Source(source.clone())), [source.as_str()]);
+        assert!(sources(VideoKey::Source("missing".to_string())).is_empty());
+    }
+
+    #[test]
+    fn multiview_draws_every_scene() {
+        let Ok((device, queue)) = shared_device() else {
+            eprintln!("skipping: no adapter");
+            return;
+        };
+        let path = std::env::temp_dir().join(format!("multiview-{}.png", std::process::id()));
+        RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]))
+            .save(&path)
+            .unwrap();
+
+        // 三个场景：当前场景和另一个场景显示图片，第三个场景为空
+        let (width, height) = (320, 180);
+        let mut collection = SceneCollection::new("test");
+        let image = collection
+            .add_source(Source::new("Image", IMAGE_SOURCE, json!({ "file": path })))
+            .unwrap();
+        let fill = Transform {
+            bounds_type: BoundsType::Stretch,
+            bounds: Vec2::new(width as f32, height as f32),
+            ..Default::default()
+        };
+        for name in ["Current", "Other"] {
+            let scene = collection.add_scene(name).unwrap();
+            let item = collection.add_item(&scene, &image).unwrap();
+            collection.set_transform(&scene, item.id, fill).unwrap();
+        }
+        collection.add_scene("Empty").unwrap();
+
+        let mut sources = SourceManager::new(device, queue);
+        sources.sync(&source_states(&collection));
+        sources.tick(std::time::Duration::ZERO);
+
+        let canvas = Canvas::new(width, height).unwrap();
+        let mut multiview = MultiviewRender::new(&canvas).unwrap();
+        multiview.multiview.settings = MultiviewSettings::default();
+        let snapshot = MultiviewSnapshot::new(&collection, 8);
+        multiview.render(&canvas, &snapshot, Some(&sources));
+
+        let data = canvas.read_rgba().unwrap();
+        let pixel = |rect: Rect| {
+            let (x, y) = (
+                (rect.x + rect.width / 2.) as usize,
+                (rect.y + rect.height / 3.) as usize,
+            );
+            let offset = (y * width as usize + x) * 4;
+            [data[offset], data[offset + 1], data[offset + 2]]
+        };
+        let cells = MultiviewSettings::default()
+            .layout
+            .cells(width as f32, height as f32);
+        let is_red = |[r, g, b]: [u8; 3]| r > 200 && g < 40 && b < 40;
+        assert!(is_red(pixel(cells.program.unwrap())));
+        assert!(is_red(pixel(cells.scenes[0])));
+        // 不是当前场景的格子也显示场景画面
+        assert!(is_red(pixel(cells.scenes[1])));
+        assert_eq!(pixel(cells.scenes[2]), [0, 0, 0]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,542 @@
+/// 自动配置模块
+///
+/// 向选择的推流服务器发送测试数据测量上行带宽，在本机测试不同分辨率下的编码速度，
+/// 据此推荐推流的分辨率、帧率、码率和编码器预设。测试过程通过事件报告进度
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    media::{
+        encoder::{EncoderPreset, H264Encoder, VideoEncoderSettings},
+        frame::{VideoFormat, VideoFrame, VideoInfo},
+    },
+    outputs::{
+        services::find_service,
+        streaming::{DEFAULT_KEYINT_SEC, DEFAULT_STREAM_BITRATE},
+    },
+    protocols::{
+        congestion::send_queue_bytes,
+        flv::video_tag,
+        net::NetworkSettings,
+        rtmp::{RtmpPublisher, RtmpUrl},
+        stream::connect,
+    },
+    Result,
+};
+
+/// 自动配置进度事件名称
+pub const AUTOCONFIG_EVENT: &str = "autoconfig-progress";
+
+/// 带宽测试时追加在推流码后的参数，服务器据此丢弃数据而不开始直播
+const BANDWIDTH_TEST_SUFFIX: &str = "?bandwidthtest";
+
+/// 服务没有码率上限时带宽测试的最大发送速率（kbps）
+const BANDWIDTH_TEST_MAX_KBPS: u32 = 10_000;
+
+/// 带宽测试每秒发送的包数
+const BANDWIDTH_TEST_PACKET_RATE: u32 = 30;
+
+/// 带宽测试开始后不计入结果的预热时间，此时发送缓冲区尚未填满
+const BANDWIDTH_TEST_WARMUP: Duration = Duration::from_secs(1);
+
+/// 推荐码率占测得带宽的比例，为网络波动留出余量
+const BANDWIDTH_HEADROOM: f64 = 0.75;
+
+/// 每种设置最多编码的帧数
+const PROBE_FRAMES: u32 = 60;
+
+/// 每种设置最多的编码时间
+const PROBE_DURATION: Duration = Duration::from_secs(1);
+
+/// 编码速度至少达到目标帧率的倍数才认为可用
+const PROBE_MARGIN: f64 = 1.2;
+
+/// 估算所需码率时每个像素的比特数
+const BITS_PER_PIXEL: f64 = 0.07;
+
+/// 候选的输出高度，按从高到低排列
+const CANDIDATE_HEIGHTS: [u32; 8] = [2160, 1440, 1080, 900, 720, 540, 480, 360];
+
+/// 使用 30 以上帧率时的最低输出高度，低于该高度时改用 30 帧
+const MIN_HIGH_FPS_HEIGHT: u32 = 720;
+
+/// 是否有自动配置正在运行
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 取消正在运行的自动配置
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+fn default_test_secs() -> u64 {
+    10
+}
+
+/// 自动配置选项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoConfigOptions {
+    /// 带宽测试使用的推流服务器
+    pub server: String,
+    /// 推流码
+    pub key: String,
+    /// 服务目录中的服务名称，用于限制码率
+    #[serde(default)]
+    pub service: Option<String>,
+    /// 带宽测试时长（秒）
+    #[serde(default = "default_test_secs")]
+    pub test_secs: u64,
+    /// 跳过带宽测试，只测试编码速度
+    #[serde(default)]
+    pub skip_bandwidth_test: bool,
+}
+
+/// 自动配置的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoConfigStage {
+    BandwidthTest,
+    EncoderTest,
+    Done,
+}
+
+/// 自动配置进度
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoConfigProgress {
+    pub stage: AutoConfigStage,
+    /// 总进度百分比
+    pub percent: u32,
+    pub message: String,
+}
+
+/// 一次编码速度测试的结果
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EncoderProbe {
+    pub width: u32,
+    pub height: u32,
+    pub preset: EncoderPreset,
+    /// 每秒编码的帧数
+    pub fps: f64,
+}
+
+/// 自动配置结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AutoConfigResult {
+    /// 测得的上行带宽（kbps），跳过带宽测试时为 `None`
+    pub bandwidth_kbps: Option<u32>,
+    pub width: u32,
+    pub height: u32,
+    pub fps_num: u32,
+    pub fps_den: u32,
+    /// 推荐码率（kbps）
+    pub bitrate: u32,
+    pub preset: EncoderPreset,
+    /// 所有编码速度测试的结果
+    pub probes: Vec<EncoderProbe>,
+}
+
+/// 发送进度事件
+fn report(app: &AppHandle, stage: AutoConfigStage, percent: u32, message: String) {
+    info!("auto-config {}%: {}", percent, message);
+    let _ = app.emit(
+        AUTOCONFIG_EVENT,
+        AutoConfigProgress {
+            stage,
+            percent,
+            message,
+        },
+    );
+}
+
+/// 检查是否已取消
+fn check_cancelled() -> Result<()> {
+    if CANCELLED.load(Ordering::Relaxed) {
+        return Err(anyhow!("auto-config cancelled"));
+    }
+
+    Ok(())
+}
+
+/// 已被对方确认的字节数，不含仍在内核发送缓冲中排队的数据
+fn delivered_bytes(publisher: &RtmpPublisher) -> u64 {
+    let queued = send_queue_bytes(publisher.socket()).unwrap_or(0);
+    publisher.bytes_sent().saturating_sub(queued)
+}
+
+/// 按给定速率向服务器发送测试数据，返回实际达到的速率（kbps）
+///
+/// 连接使用当前配置文件的网络设置，与推流时的网络路径一致
+///
+/// # 参数
+///
+/// * `url` - 推流地址，推流码应带有带宽测试参数
+/// * `target_kbps` - 发送速率
+/// * `duration` - 测试时长
+/// * `on_progress` - 进度回调，参数为已完成的比例
+///
+/// # 返回值
+///
+/// 返回 `Result<u32>`，连接失败或发送中断时返回错误
+pub fn bandwidth_test(
+    url: &RtmpUrl,
+    target_kbps: u32,
+    duration: Duration,
+    on_progress: impl Fn(f64),
+) -> Result<u32> {
+    let mut publisher = RtmpPublisher::publish(connect(url, &NetworkSettings::load())?, url)?;
+    // 上行阻塞时写入不能超过测试时长
+    publisher
+        .socket()
+        .set_write_timeout(Some(duration + BANDWIDTH_TEST_WARMUP))?;
+
+    let interval = Duration::from_secs(1) / BANDWIDTH_TEST_PACKET_RATE;
+    let packet_size = (target_kbps as usize * 1000 / 8) / BANDWIDTH_TEST_PACKET_RATE as usize;
+    // 不可压缩的测试数据
+    let mut seed = 0x2545_f491_u32;
+    let data: Vec<u8> = (0..packet_size.max(1))
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as u8
+        })
+        .collect();
+
+    let start = Instant::now();
+    let mut measured: Option<(Instant, u64)> = None;
+    let mut packets = 0u32;
+    while start.elapsed() < duration {
+        check_cancelled()?;
+
+        let timestamp = start.elapsed().as_millis() as u32;
+        publisher.send_video(timestamp, video_tag(&data, packets == 0, 0))?;
+        packets += 1;
+
+        if measured.is_none() && start.elapsed() >= BANDWIDTH_TEST_WARMUP {
+            measured = Some((Instant::now(), delivered_bytes(&publisher)));
+        }
+        on_progress(start.elapsed().as_secs_f64() / duration.as_secs_f64());
+
+        let next = interval * packets;
+        if let Some(wait) = next.checked_sub(start.elapsed()) {
+            thread::sleep(wait);
+        }
+    }
+
+    let (since, base) = measured.unwrap_or((start, 0));
+    let elapsed = since.elapsed().as_secs_f64().max(0.001);
+    let kbps = delivered_bytes(&publisher).saturating_sub(base) as f64 * 8. / elapsed / 1000.;
+    publisher.close();
+
+    Ok(kbps.round() as u32)
+}
+
+/// 生成编码测试用的帧，画面随帧号移动并带有噪点
+fn test_frame(width: u32, height: u32, index: u32) -> VideoFrame {
+    let (w, h) = (width as usize, height as usize);
+    let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+    let mut data = Vec::with_capacity(w * h + cw * ch * 2);
+    let mut seed = index.wrapping_mul(0x9e37_79b9) | 1;
+    for y in 0..h {
+        for x in 0..w {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let gradient = (x + y + index as usize * 4) as u32;
+            data.push((gradient.wrapping_add(seed & 0x1f)) as u8);
+        }
+    }
+    for plane in 0..2 {
+        for y in 0..ch {
+            for x in 0..cw {
+                data.push(((x * (plane + 1) + y + index as usize * 2) & 0xff) as u8);
+            }
+        }
+    }
+
+    VideoFrame {
+        width,
+        height,
+        format: VideoFormat::I420,
+        timestamp: 0,
+        data,
+    }
+}
+
+/// 测试给定设置的编码速度
+///
+/// # 参数
+///
+/// * `settings` - 编码设置
+///
+/// # 返回值
+///
+/// 返回 `Result<f64>`，表示每秒编码的帧数
+pub fn probe_encoder(settings: VideoEncoderSettings) -> Result<f64> {
+    let mut encoder = H264Encoder::new(settings)?;
+    let frame_ns = 1_000_000_000 * settings.fps_den as u64 / settings.fps_num.max(1) as u64;
+    // 测试帧循环使用，生成时间不计入编码时间
+    let frames: Vec<VideoFrame> = (0..8)
+        .map(|index| test_frame(settings.width, settings.height, index))
+        .collect();
+
+    let start = Instant::now();
+    let mut encoded = 0;
+    while encoded < PROBE_FRAMES && start.elapsed() < PROBE_DURATION {
+        let frame = &frames[encoded as usize % frames.len()];
+        encoder.encode(frame, encoded as u64 * frame_ns)?;
+        encoded += 1;
+    }
+
+    Ok(encoded as f64 / start.elapsed().as_secs_f64().max(0.001))
+}
+
+/// 按画布宽高比生成候选分辨率，不超过画布大小，宽高都为偶数
+fn candidate_resolutions(base_width: u32, base_height: u32) -> Vec<(u32, u32)> {
+    let even = |value: f64| ((value / 2.).round() as u32).max(1) * 2;
+    let mut resolutions: Vec<(u32, u32)> = CANDIDATE_HEIGHTS
+        .iter()
+        .filter(|height| **height <= base_height)
+        .map(|height| {
+            let width = *height as f64 * base_width as f64 / base_height.max(1) as f64;
+            (even(width), *height)
+        })
+        .collect();
+    // 画布比候选分辨率都小时直接使用画布大小
+    if resolutions.is_empty() {
+        resolutions.push((even(base_width as f64), even(base_height as f64)));
+    }
+
+    resolutions
+}
+
+/// 以给定帧率输出该分辨率需要的码率（kbps）
+fn required_bitrate(width: u32, height: u32, fps: f64) -> u32 {
+    (width as f64 * height as f64 * fps * BITS_PER_PIXEL / 1000.) as u32
+}
+
+/// 运行自动配置
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `options` - 自动配置选项
+///
+/// # 返回值
+///
+/// 返回 `Result<AutoConfigResult>`，已有自动配置在运行、测试失败或被取消时返回错误
+pub fn run_autoconfig(app: &AppHandle, options: &AutoConfigOptions) -> Result<AutoConfigResult> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err(anyhow!("auto-config already running"));
+    }
+    CANCELLED.store(false, Ordering::Relaxed);
+    let result = autoconfig(app, options);
+    RUNNING.store(false, Ordering::SeqCst);
+
+    result
+}
+
+/// 取消正在运行的自动配置
+pub fn cancel_autoconfig() {
+    if RUNNING.load(Ordering::SeqCst) {
+        CANCELLED.store(true, Ordering::Relaxed);
+    }
+}
+
+fn autoconfig(app: &AppHandle, options: &AutoConfigOptions) -> Result<AutoConfigResult> {
+    let service = options.service.as_deref().map(find_service).transpose()?;
+    let max_bitrate = service.and_then(|service| service.recommended.max_video_bitrate);
+    let keyint_sec = service
+        .and_then(|service| service.recommended.keyint)
+        .unwrap_or(DEFAULT_KEYINT_SEC);
+
+    // 带宽测试占总进度的一半
+    let bandwidth_kbps = if options.skip_bandwidth_test {
+        None
+    } else {
+        let url = RtmpUrl::parse(
+            &options.server,
+            &format!("{}{}", options.key, BANDWIDTH_TEST_SUFFIX),
+        )?;
+        let target = max_bitrate.unwrap_or(BANDWIDTH_TEST_MAX_KBPS);
+        report(
+            app,
+            AutoConfigStage::BandwidthTest,
+            0,
+            format!("testing bandwidth to {}", url.display()),
+        );
+        let kbps = bandwidth_test(
+            &url,
+            target,
+            Duration::from_secs(options.test_secs.max(2)),
+            |done| {
+                let percent = (done.min(1.) * 50.) as u32;
+                let _ = app.emit(
+                    AUTOCONFIG_EVENT,
+                    AutoConfigProgress {
+                        stage: AutoConfigStage::BandwidthTest,
+                        percent,
+                        message: String::new(),
+                    },
+                );
+            },
+        )?;
+        report(
+            app,
+            AutoConfigStage::BandwidthTest,
+            50,
+            format!("measured bandwidth: {} kbps", kbps),
+        );
+        Some(kbps)
+    };
+
+    let bitrate = match bandwidth_kbps {
+        Some(kbps) => ((kbps as f64 * BANDWIDTH_HEADROOM) as u32).max(1),
+        None => DEFAULT_STREAM_BITRATE,
+    };
+    let bitrate = max_bitrate.map_or(bitrate, |max| bitrate.min(max));
+
+    let info = VideoInfo::load();
+    let resolutions = candidate_resolutions(info.base_width, info.base_height);
+    // 画布帧率高于 30 时先尝试画布帧率，分辨率过低时改用 30 帧
+    let mut rates = vec![(info.fps_num, info.fps_den)];
+    if info.fps_num > 30 * info.fps_den {
+        rates.push((30, 1));
+    }
+
+    let mut probes = vec![];
+    let total = (resolutions.len() * rates.len()) as u32 + 2;
+    let mut step = 0;
+    let mut progress = |message: String| {
+        step += 1;
+        let percent = if options.skip_bandwidth_test { 0 } else { 50 };
+        let span = 100 - percent;
+        report(
+            app,
+            AutoConfigStage::EncoderTest,
+            percent + (step * span / total).min(span - 1),
+            message,
+        );
+    };
+
+    let mut chosen = None;
+    'rates: for (index, (fps_num, fps_den)) in rates.iter().copied().enumerate() {
+        let fps = fps_num as f64 / fps_den.max(1) as f64;
+        let last_rate = index + 1 == rates.len();
+        for (width, height) in resolutions.iter().copied() {
+            check_cancelled()?;
+            if !last_rate && height < MIN_HIGH_FPS_HEIGHT {
+                continue 'rates;
+            }
+            // 码率不足的分辨率画质太差，最低的分辨率除外
+            let lowest = (width, height) == *resolutions.last().unwrap();
+            if required_bitrate(width, height, fps) > bitrate && !(last_rate && lowest) {
+                continue;
+            }
+
+            let settings = VideoEncoderSettings {
+                width,
+                height,
+                fps_num,
+                fps_den,
+                bitrate,
+                keyint_sec,
+                preset: EncoderPreset::Speed,
+            };
+            progress(format!(
+                "testing encoder at {}x{} {:.2} fps",
+                width, height, fps
+            ));
+            let speed = probe_encoder(settings)?;
+            probes.push(EncoderProbe {
+                width,
+                height,
+                preset: EncoderPreset::Speed,
+                fps: speed,
+            });
+            if speed >= fps * PROBE_MARGIN || (last_rate && lowest) {
+                chosen = Some(settings);
+                break 'rates;
+            }
+        }
+    }
+    let mut settings = chosen.ok_or(anyhow!("no usable encoder settings found"))?;
+
+    // 在选定的分辨率下选择速度足够的最高画质预设
+    let fps = settings.fps_num as f64 / settings.fps_den.max(1) as f64;
+    for preset in [EncoderPreset::Quality, EncoderPreset::Balanced] {
+        check_cancelled()?;
+        progress(format!("testing {:?} preset", preset));
+        let speed = probe_encoder(VideoEncoderSettings { preset, ..settings })?;
+        probes.push(EncoderProbe {
+            width: settings.width,
+            height: settings.height,
+            preset,
+            fps: speed,
+        });
+        if speed >= fps * PROBE_MARGIN {
+            settings.preset = preset;
+            break;
+        }
+    }
+
+    let result = AutoConfigResult {
+        bandwidth_kbps,
+        width: settings.width,
+        height: settings.height,
+        fps_num: settings.fps_num,
+        fps_den: settings.fps_den,
+        bitrate: settings.bitrate,
+        preset: settings.preset,
+        probes,
+    };
+    report(
+        app,
+        AutoConfigStage::Done,
+        100,
+        format!(
+            "{}x{} {}/{} fps, {} kbps, {:?}",
+            result.width,
+            result.height,
+            result.fps_num,
+            result.fps_den,
+            result.bitrate,
+            result.preset
+        ),
+    );
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocols::rtmp::server::RtmpServer;
+
+    fn test_url(server: &RtmpServer) -> RtmpUrl {
+        RtmpUrl::parse(&server.url("live"), &format!("key{BANDWIDTH_TEST_SUFFIX}")).unwrap()
+    }
+
+    #[test]
+    fn bandwidth_test_reaches_target_on_fast_link() {
+        let server = RtmpServer::bind("127.0.0.1:0").unwrap();
+        let kbps =
+            bandwidth_test(&test_url(&server), 2000, Duration::from_secs(3), |_| {}).unwrap();
+        assert!((1600..=2400).contains(&kbps), "measured {kbps} kbps");
+    }
+
+    #[test]
+    fn bandwidth_test_excludes_queued_data() {
+        let server = RtmpServer::bind("127.0.0.1:0").unwrap();
+        // 服务器每秒只读取 1000 kbps，其余数据积压在发送缓冲中
+        server.set_read_rate(Some(125_000));
+        let kbps =
+            bandwidth_test(&test_url(&server), 6000, Duration::from_secs(6), |_| {}).unwrap();
+        assert!((700..=1300).contains(&kbps), "measured {kbps} kbps");
+    }
+}
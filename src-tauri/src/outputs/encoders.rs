@@ -0,0 +1,782 @@
+/// 共享编码器模块
+///
+/// 画布、视频编码设置和音频格式都相同的输出共用一组编码器，只编码一次。
+/// 组内把音视频包按解码时间交错后分发给所有订阅者，各输出收到的包序列完全相同，
+/// 所有订阅者断开后编码线程退出。输出拥塞时可以限制码率，组内按最低的限制编码
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use log::{error, info};
+use serde::Serialize;
+
+use crate::{
+    media::{
+        aac::AacEncoder,
+        audio::AudioFrame,
+        color::load_color_settings,
+        encoder::{
+            AudioEncoderSettings, EncodedPacket, H264Encoder, PacketKind, VideoEncoderSettings,
+        },
+        frame::{VideoFormat, VideoFrame},
+        mixer::connect_audio,
+        scale::ScaleFilter,
+        video::{connect_encoder_video, VideoConversion},
+    },
+    stats::counters::record_encoded_frame,
+    Result,
+};
+
+/// 每个订阅者缓存的最大包数，输出发送不及时时丢弃新包
+const PACKET_QUEUE_SIZE: usize = 512;
+
+/// 只有一路有数据时，缓存超过该时长（纳秒）后不再等待另一路
+const MAX_INTERLEAVE_NS: u64 = 500_000_000;
+
+/// 等待视频帧的间隔，期间处理音频
+const VIDEO_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 共享编码器的键，键相同的输出共用编码器
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct EncoderKey {
+    /// 画布 UUID，为 `None` 时为主画布
+    pub canvas: Option<String>,
+    pub video: VideoEncoderSettings,
+    pub audio: AudioEncoderSettings,
+}
+
+/// 订阅者
+struct Subscriber {
+    id: String,
+    sender: SyncSender<Arc<EncodedPacket>>,
+    /// 队列已满而丢弃的视频包数
+    dropped: Arc<AtomicU64>,
+    /// 码率上限（kbps），为 0 时不限制
+    bitrate_limit: Arc<AtomicU32>,
+}
+
+/// 一组共享的编码器
+struct EncoderGroup {
+    id: u64,
+    key: EncoderKey,
+    subscribers: Mutex<Vec<Subscriber>>,
+    /// 下一帧编码为关键帧
+    keyframe: AtomicBool,
+    /// 当前的视频码率（kbps）
+    bitrate: AtomicU32,
+}
+
+impl EncoderGroup {
+    /// 设置码率和所有订阅者码率上限中的最小值
+    fn target_bitrate(&self) -> u32 {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|subscriber| subscriber.bitrate_limit.load(Ordering::Relaxed))
+            .filter(|limit| *limit > 0)
+            .fold(self.key.video.bitrate, u32::min)
+    }
+}
+
+lazy_static! {
+    /// 运行中的编码器组
+    static ref ENCODERS: Mutex<HashMap<EncoderKey, Arc<EncoderGroup>>> = Mutex::new(HashMap::new());
+}
+
+/// 编码器组编号
+static NEXT_GROUP_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 编码器组信息
+#[derive(Debug, Clone, Serialize)]
+pub struct EncoderGroupInfo {
+    pub id: u64,
+    pub key: EncoderKey,
+    /// 当前的视频码率（kbps）
+    pub bitrate: u32,
+    /// 订阅的输出 ID
+    pub outputs: Vec<String>,
+}
+
+/// 输出对编码器组的订阅，丢弃后退订
+pub struct EncoderSubscription {
+    /// 交错后的音视频包
+    pub packets: Receiver<Arc<EncodedPacket>>,
+    group: Arc<EncoderGroup>,
+    dropped: Arc<AtomicU64>,
+    bitrate_limit: Arc<AtomicU32>,
+}
+
+impl EncoderSubscription {
+    /// 编码器组编号
+    pub fn group_id(&self) -> u64 {
+        self.group.id
+    }
+
+    /// 编码设置
+    pub fn key(&self) -> &EncoderKey {
+        &self.group.key
+    }
+
+    /// 请求下一帧编码为关键帧，如重连后或丢包后，组内其他输出也会收到该关键帧
+    pub fn request_keyframe(&self) {
+        self.group.keyframe.store(true, Ordering::Relaxed);
+    }
+
+    /// 取出上次调用以来因队列已满而丢弃的视频包数
+    pub fn take_dropped(&self) -> u64 {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+
+    /// 限制编码码率，组内其他输出也会使用较低的码率
+    ///
+    /// # 参数
+    ///
+    /// * `limit` - 码率上限（kbps），为 `None` 时取消限制
+    pub fn set_bitrate_limit(&self, limit: Option<u32>) {
+        self.bitrate_limit
+            .store(limit.unwrap_or(0), Ordering::Relaxed);
+    }
+}
+
+/// 订阅编码器组，没有相同设置的组时创建新组
+///
+/// # 参数
+///
+/// * `id` - 输出 ID
+/// * `key` - 编码设置
+///
+/// # 返回值
+///
+/// 返回 `Result<EncoderSubscription>`，编码设置无效或画布不存在时返回错误
+pub fn subscribe(id: &str, key: EncoderKey) -> Result<EncoderSubscription> {
+    subscribe_with(id, key, start_group)
+}
+
+/// 订阅编码器组，没有相同设置的组时用 `start` 创建
+fn subscribe_with(
+    id: &str,
+    key: EncoderKey,
+    start: impl FnOnce(EncoderKey) -> Result<Arc<EncoderGroup>>,
+) -> Result<EncoderSubscription> {
+    let (sender, packets) = sync_channel(PACKET_QUEUE_SIZE);
+    let dropped = Arc::new(AtomicU64::new(0));
+    let bitrate_limit = Arc::new(AtomicU32::new(0));
+    let subscriber = Subscriber {
+        id: id.to_string(),
+        sender,
+        dropped: dropped.clone(),
+        bitrate_limit: bitrate_limit.clone(),
+    };
+
+    let mut encoders = ENCODERS.lock().unwrap();
+    let group = match encoders.get(&key) {
+        Some(group) => group.clone(),
+        None => {
+            let group = start(key.clone())?;
+            encoders.insert(key, group.clone());
+            group
+        }
+    };
+    group.subscribers.lock().unwrap().push(subscriber);
+    // 新订阅者从关键帧开始
+    group.keyframe.store(true, Ordering::Relaxed);
+
+    Ok(EncoderSubscription {
+        packets,
+        group,
+        dropped,
+        bitrate_limit,
+    })
+}
+
+/// 运行中的编码器组
+pub fn encoder_groups() -> Vec<EncoderGroupInfo> {
+    let mut groups: Vec<EncoderGroupInfo> = ENCODERS
+        .lock()
+        .unwrap()
+        .values()
+        .map(|group| EncoderGroupInfo {
+            id: group.id,
+            key: group.key.clone(),
+            bitrate: group.bitrate.load(Ordering::Relaxed),
+            outputs: group
+                .subscribers
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|subscriber| subscriber.id.clone())
+                .collect(),
+        })
+        .collect();
+    groups.sort_by_key(|group| group.id);
+
+    groups
+}
+
+/// 连接画布视频和混音输出，创建编码器组
+fn start_group(key: EncoderKey) -> Result<Arc<EncoderGroup>> {
+    let (space, range) = load_color_settings();
+    let conversion =
+        VideoConversion::canvas_output(key.canvas.as_deref(), VideoFormat::I420, space, range)
+            .rescaled(key.video.width, key.video.height, ScaleFilter::load());
+    let video = connect_encoder_video(key.canvas.as_deref(), conversion)?;
+    let audio = connect_audio()?;
+
+    spawn_group(key, video, audio)
+}
+
+/// 创建编码器并启动编码线程
+fn spawn_group(
+    key: EncoderKey,
+    video: Receiver<Arc<VideoFrame>>,
+    audio: Receiver<Arc<AudioFrame>>,
+) -> Result<Arc<EncoderGroup>> {
+    let video_encoder = H264Encoder::new(key.video)?;
+    let audio_encoder = AacEncoder::new(key.audio)?;
+
+    let group = Arc::new(EncoderGroup {
+        id: NEXT_GROUP_ID.fetch_add(1, Ordering::Relaxed),
+        bitrate: AtomicU32::new(key.video.bitrate),
+        key,
+        subscribers: Mutex::new(vec![]),
+        keyframe: AtomicBool::new(true),
+    });
+
+    let shared = group.clone();
+    thread::Builder::new()
+        .name(format!("encoder-{}", group.id))
+        .spawn(move || run_group(shared, video_encoder, audio_encoder, video, audio))?;
+
+    Ok(group)
+}
+
+/// 按解码时间交错音视频包
+#[derive(Default)]
+struct Interleaver {
+    video: VecDeque<EncodedPacket>,
+    audio: VecDeque<EncodedPacket>,
+}
+
+impl Interleaver {
+    fn push(&mut self, packet: EncodedPacket) {
+        match packet.kind {
+            PacketKind::Video => self.video.push_back(packet),
+            PacketKind::Audio => self.audio.push_back(packet),
+        }
+    }
+
+    /// 取出下一个可以发送的包：两路都有数据时取解码时间较早的，
+    /// 只有一路有数据时等待另一路，缓存过长时不再等待
+    fn pop(&mut self) -> Option<EncodedPacket> {
+        match (self.video.front(), self.audio.front()) {
+            (Some(video), Some(audio)) => {
+                if video.dts <= audio.dts {
+                    self.video.pop_front()
+                } else {
+                    self.audio.pop_front()
+                }
+            }
+            (Some(_), None) => Self::pop_stale(&mut self.video),
+            (None, Some(_)) => Self::pop_stale(&mut self.audio),
+            (None, None) => None,
+        }
+    }
+
+    fn pop_stale(queue: &mut VecDeque<EncodedPacket>) -> Option<EncodedPacket> {
+        let (front, back) = (queue.front()?, queue.back()?);
+        if back.dts - front.dts > MAX_INTERLEAVE_NS {
+            queue.pop_front()
+        } else {
+            None
+        }
+    }
+}
+
+/// 把包分发给所有订阅者，返回是否还有订阅者
+fn fan_out(group: &EncoderGroup, packet: EncodedPacket) -> bool {
+    let packet = Arc::new(packet);
+    let mut subscribers = group.subscribers.lock().unwrap();
+    subscribers.retain(
+        |subscriber| match subscriber.sender.try_send(packet.clone()) {
+            Ok(_) => true,
+            Err(TrySendError::Full(_)) => {
+                if packet.kind == PacketKind::Video {
+                    subscriber.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        },
+    );
+
+    !subscribers.is_empty()
+}
+
+/// 没有订阅者时移除编码器组，持有组表的锁检查，避免与新的订阅冲突
+fn try_remove(group: &Arc<EncoderGroup>, force: bool) -> bool {
+    let mut encoders = ENCODERS.lock().unwrap();
+    if !force && !group.subscribers.lock().unwrap().is_empty() {
+        return false;
+    }
+    if encoders
+        .get(&group.key)
+        .is_some_and(|current| Arc::ptr_eq(current, group))
+    {
+        encoders.remove(&group.key);
+    }
+
+    true
+}
+
+/// 编码线程
+fn run_group(
+    group: Arc<EncoderGroup>,
+    mut video_encoder: H264Encoder,
+    mut audio_encoder: AacEncoder,
+    video: Receiver<Arc<VideoFrame>>,
+    audio: Receiver<Arc<AudioFrame>>,
+) {
+    let settings = group.key.video;
+    info!(
+        "encoder {} started: {}x{}, {} kbps",
+        group.id, settings.width, settings.height, settings.bitrate
+    );
+
+    let mut interleaver = Interleaver::default();
+    // 第一帧视频的时间戳，编码包的时间戳从这里开始
+    let mut start: Option<u64> = None;
+
+    let result = loop {
+        match video.recv_timeout(VIDEO_POLL_INTERVAL) {
+            Ok(frame) => {
+                let start = *start.get_or_insert(frame.timestamp);
+                if group.keyframe.swap(false, Ordering::Relaxed) {
+                    video_encoder.request_keyframe();
+                }
+                let bitrate = group.target_bitrate();
+                if bitrate != video_encoder.bitrate() {
+                    info!(
+                        "encoder {} bitrate: {} -> {} kbps",
+                        group.id,
+                        video_encoder.bitrate(),
+                        bitrate
+                    );
+                    video_encoder.set_bitrate(bitrate);
+                    group.bitrate.store(bitrate, Ordering::Relaxed);
+                }
+                match video_encoder.encode(&frame, frame.timestamp.saturating_sub(start)) {
+                    // 为控制码率跳过的帧也计入编码帧，不算作编码延迟
+                    Ok(packet) => {
+                        record_encoded_frame();
+                        if let Some(packet) = packet {
+                            interleaver.push(packet);
+                        }
+                    }
+                    Err(e) => break Err(e),
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break Err(anyhow!("video output stopped")),
+        }
+
+        while let Ok(frame) = audio.try_recv() {
+            match start {
+                Some(start) if frame.timestamp >= start => {
+                    for packet in audio_encoder.encode(&frame, frame.timestamp - start) {
+                        interleaver.push(packet);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut subscribed = true;
+        while let Some(packet) = interleaver.pop() {
+            subscribed = fan_out(&group, packet);
+        }
+        let idle = !subscribed || group.subscribers.lock().unwrap().is_empty();
+        if idle && try_remove(&group, false) {
+            break Ok(());
+        }
+    };
+
+    match result {
+        Ok(_) => info!("encoder {} stopped", group.id),
+        Err(e) => {
+            error!("encoder {} failed: {}", group.id, e);
+            try_remove(&group, true);
+            // 断开所有订阅者，输出随后停止
+            group.subscribers.lock().unwrap().clear();
+        }
+    }
+}
+
+#[cfg(test)]
+impl EncoderSubscription {
+    /// 不属于运行中编码器组的订阅，测试直接处理构造的编码包
+    pub fn detached(key: EncoderKey) -> Self {
+        let (_, packets) = sync_channel(1);
+        let group = EncoderGroup {
+            id: 0,
+            bitrate: AtomicU32::new(key.video.bitrate),
+            key,
+            subscribers: Mutex::new(vec![]),
+            keyframe: AtomicBool::new(false),
+        };
+
+        Self {
+            packets,
+            group: Arc::new(group),
+            dropped: Arc::new(AtomicU64::new(0)),
+            bitrate_limit: Arc::new(AtomicU32::new(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::{
+        media::encoder::EncoderPreset,
+        protocols::{
+            congestion::CongestionState,
+            flv::flv_audio_settings,
+            rtmp::{
+                server::{ReceivedStream, RtmpServer},
+                RtmpUrl,
+            },
+            stream::{stream_output_id, StreamOutput, StreamSettings, StreamState, StreamStatus},
+        },
+        stats::counters,
+    };
+
+    const WIDTH: u32 = 64;
+    const HEIGHT: u32 = 64;
+    const FPS: u64 = 30;
+
+    fn test_key(name: &str) -> EncoderKey {
+        EncoderKey {
+            canvas: Some(format!("test-{}-{}", name, std::process::id())),
+            video: VideoEncoderSettings {
+                width: WIDTH,
+                height: HEIGHT,
+                fps_num: FPS as u32,
+                fps_den: 1,
+                bitrate: 300,
+                keyint_sec: 1,
+                // 屏幕内容模式会把带噪点的画面都编码为关键帧
+                preset: EncoderPreset::Balanced,
+            },
+            audio: flv_audio_settings(2),
+        }
+    }
+
+    /// 按实时速度送入变化的画面和正弦音频，接收端断开后退出
+    fn feed(
+        video: SyncSender<Arc<VideoFrame>>,
+        audio: SyncSender<Arc<AudioFrame>>,
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            let start = Instant::now();
+            let (width, height) = (WIDTH as usize, HEIGHT as usize);
+            let samples = 44100 / FPS as usize;
+            for frame in 0u64.. {
+                let timestamp = frame * 1_000_000_000 / FPS;
+                // 滚动的纹理加少量噪点，使编码码率接近设置值
+                let mut data = vec![128u8; width * height * 3 / 2];
+                let mut seed = (frame as u32).wrapping_mul(0x9e37_79b9) | 1;
+                for (i, luma) in data[..width * height].iter_mut().enumerate() {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 17;
+                    seed ^= seed << 5;
+                    let (x, y) = ((i % width) as u32 + frame as u32 * 2, (i / width) as u32);
+                    let texture = (x.wrapping_mul(0x2f0f_6f15) ^ y.wrapping_mul(0x6c8e_9cf5)) >> 24;
+                    *luma = (texture + (seed & 0x0f)) as u8;
+                }
+                let picture = VideoFrame {
+                    width: WIDTH,
+                    height: HEIGHT,
+                    format: VideoFormat::I420,
+                    timestamp,
+                    data,
+                };
+                if video.send(Arc::new(picture)).is_err() {
+                    break;
+                }
+
+                let data = (0..samples * 2)
+                    .map(|i| ((frame as usize * samples + i / 2) as f32 * 0.06).sin() * 0.3)
+                    .collect();
+                let _ = audio.try_send(Arc::new(AudioFrame {
+                    sample_rate: 44100,
+                    channels: 2,
+                    timestamp,
+                    data,
+                }));
+
+                let due = Duration::from_nanos((frame + 1) * 1_000_000_000 / FPS);
+                if let Some(wait) = due.checked_sub(start.elapsed()) {
+                    thread::sleep(wait);
+                }
+            }
+        })
+    }
+
+    /// 等待条件成立，超时时失败
+    fn wait_until(what: &str, condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out waiting for {what}");
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    /// 音视频包的到达顺序和时间戳
+    fn sequence(stream: &ReceivedStream) -> Vec<(bool, u32)> {
+        let (mut video, mut audio) = (
+            stream.video_timestamps.iter(),
+            stream.audio_timestamps.iter(),
+        );
+        stream
+            .order
+            .iter()
+            .map(|is_video| {
+                let timestamp = if *is_video {
+                    video.next()
+                } else {
+                    audio.next()
+                };
+                (*is_video, *timestamp.unwrap())
+            })
+            .collect()
+    }
+
+    fn assert_complete(stream: &ReceivedStream, key: &str) {
+        assert_eq!(stream.app, "live");
+        assert_eq!(stream.key, key);
+        assert!(stream.metadata);
+        assert!(stream.sequence_headers >= 1);
+        assert_eq!(stream.audio_sequence_headers, 1);
+        assert!(stream.starts_with_keyframe);
+        assert!(stream.video_frames > 0 && stream.audio_frames > 0);
+        let sequence = sequence(stream);
+        for is_video in [true, false] {
+            let timestamps: Vec<u32> = sequence
+                .iter()
+                .filter(|(video, _)| *video == is_video)
+                .map(|(_, timestamp)| *timestamp)
+                .collect();
+            assert!(timestamps.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+    }
+
+    #[test]
+    fn shared_encoder_feeds_several_receivers() {
+        let key = test_key("shared");
+        let (video_sender, video) = sync_channel(8);
+        let (audio_sender, audio) = sync_channel(64);
+        let mut inputs = Some((video, audio));
+
+        let count = 3;
+        let subscriptions: Vec<EncoderSubscription> = (0..count)
+            .map(|i| {
+                subscribe_with(&format!("output-{i}"), key.clone(), |key| {
+                    let (video, audio) = inputs.take().expect("group started twice");
+                    spawn_group(key, video, audio)
+                })
+                .unwrap()
+            })
+            .collect();
+        let group = subscriptions[0].group_id();
+        assert!(subscriptions.iter().all(|item| item.group_id() == group));
+        let info = encoder_groups()
+            .into_iter()
+            .find(|info| info.id == group)
+            .unwrap();
+        assert_eq!(info.outputs.len(), count);
+
+        let servers: Vec<RtmpServer> = (0..count)
+            .map(|_| RtmpServer::bind("127.0.0.1:0").unwrap())
+            .collect();
+        let mut settings = StreamSettings::default();
+        settings.reconnect.delay = Duration::from_millis(100);
+        let outputs: Vec<StreamOutput> = subscriptions
+            .into_iter()
+            .zip(&servers)
+            .enumerate()
+            .map(|(i, (subscription, server))| {
+                let url = RtmpUrl::parse(&server.url("live"), &format!("key-{i}")).unwrap();
+                StreamOutput::start(
+                    &format!("output-{i}"),
+                    url,
+                    subscription,
+                    settings.clone(),
+                    |_| {},
+                )
+                .unwrap()
+            })
+            .collect();
+        wait_until("all receivers publishing", || {
+            servers.iter().all(|server| {
+                server
+                    .streams()
+                    .first()
+                    .is_some_and(|stream| stream.publishing)
+            })
+        });
+
+        let feeder = feed(video_sender, audio_sender);
+        wait_until("video on all receivers", || {
+            servers.iter().all(|server| {
+                server.streams()[0].video_frames >= FPS && server.streams()[0].audio_frames >= 20
+            })
+        });
+
+        // 一个接收端断开后只有对应的输出重连，其他输出不受影响
+        servers[0].disconnect_all();
+        wait_until("reconnect", || {
+            servers[0]
+                .streams()
+                .get(1)
+                .is_some_and(|stream| stream.video_frames >= FPS / 2)
+        });
+        assert_eq!(outputs[0].status().state, StreamState::Live);
+        for (output, server) in outputs.iter().zip(&servers).skip(1) {
+            assert_eq!(server.streams().len(), 1);
+            assert!(!server.streams()[0].closed);
+            let status = output.status();
+            assert_eq!(status.state, StreamState::Live);
+            assert_eq!(status.attempts, 0);
+            assert_eq!(status.encoder, group);
+        }
+
+        for output in outputs {
+            output.stop();
+        }
+        feeder.join().unwrap();
+
+        let streams: Vec<Vec<ReceivedStream>> = servers.iter().map(RtmpServer::streams).collect();
+        for (i, received) in streams.iter().enumerate() {
+            for stream in received {
+                assert_complete(stream, &format!("key-{i}"));
+            }
+        }
+        assert_eq!(streams[0].len(), 2);
+
+        // 从同一个关键帧开始的连接收到完全相同的包序列
+        let reference = sequence(&streams[1][0]);
+        for stream in [&streams[0][0], &streams[2][0]] {
+            let sequence = sequence(stream);
+            let common = sequence.len().min(reference.len());
+            assert!(common > FPS as usize);
+            assert_eq!(sequence[..common], reference[..common]);
+        }
+    }
+
+    #[test]
+    fn throttled_receiver_lowers_bitrate_and_drops_frames() {
+        let mut key = test_key("congestion");
+        key.video.bitrate = 600;
+        let (video_sender, video) = sync_channel(8);
+        let (audio_sender, audio) = sync_channel(64);
+        let subscription = subscribe_with("throttled", key.clone(), |key| {
+            spawn_group(key, video, audio)
+        })
+        .unwrap();
+        let group = subscription.group_id();
+        let group_bitrate = || {
+            encoder_groups()
+                .into_iter()
+                .find(|info| info.id == group)
+                .map_or(0, |info| info.bitrate)
+        };
+
+        let server = RtmpServer::bind("127.0.0.1:0").unwrap();
+        let url = RtmpUrl::parse(&server.url("live"), "throttled").unwrap();
+        let history = Arc::new(Mutex::new(Vec::<StreamStatus>::new()));
+        let output =
+            StreamOutput::start("throttled", url, subscription, StreamSettings::default(), {
+                let history = history.clone();
+                move |status| history.lock().unwrap().push(status.clone())
+            })
+            .unwrap();
+        wait_until("publishing", || {
+            server
+                .streams()
+                .first()
+                .is_some_and(|stream| stream.publishing)
+        });
+        let feeder = feed(video_sender, audio_sender);
+        wait_until("video", || server.streams()[0].video_frames >= FPS);
+        assert_eq!(output.status().congestion, CongestionState::Clear);
+        assert_eq!(group_bitrate(), key.video.bitrate);
+
+        // 服务器只读取 480 kbps，低于音视频的总码率，降低码率后恢复通畅
+        server.set_read_rate(Some(60_000));
+        wait_until("bitrate decrease", || {
+            output.status().bitrate < key.video.bitrate && group_bitrate() < key.video.bitrate
+        });
+        assert!(history
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|status| status.congestion >= CongestionState::Congested));
+        wait_until("adapted bitrate", || {
+            output.status().congestion == CongestionState::Clear
+        });
+        let counters = counters::outputs()
+            .into_iter()
+            .find(|item| item.id == stream_output_id("throttled"))
+            .unwrap();
+        let dropped = counters.dropped_frames.load(Ordering::Relaxed);
+
+        // 速率低于最低码率时积压持续增长，按优先级丢弃视频帧
+        server.set_read_rate(Some(24_000));
+        wait_until("frame dropping", || {
+            history
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|status| status.congestion == CongestionState::DroppingInter)
+        });
+        wait_until("dropped frames", || {
+            counters.dropped_frames.load(Ordering::Relaxed) > dropped
+        });
+
+        // 网络恢复后码率逐步回升，期间不断开连接
+        server.set_read_rate(None);
+        wait_until("congestion cleared", || {
+            output.status().congestion == CongestionState::Clear
+        });
+        let lowest = output.status().bitrate;
+        wait_until("bitrate recovery", || {
+            output.status().bitrate > lowest && group_bitrate() > lowest
+        });
+        let status = output.status();
+        assert_eq!(status.state, StreamState::Live);
+        assert_eq!(status.attempts, 0);
+
+        output.stop();
+        feeder.join().unwrap();
+        let streams = server.streams();
+        assert_eq!(streams.len(), 1);
+        assert_complete(&streams[0], "throttled");
+        // 音频从不丢弃
+        assert!(streams[0]
+            .audio_timestamps
+            .windows(2)
+            .all(|pair| pair[1] - pair[0] <= 50));
+    }
+}
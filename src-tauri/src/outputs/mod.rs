@@ -0,0 +1,8 @@
+#![allow(dead_code)]
+
+pub mod autoconfig;
+pub mod encoders;
+pub mod recording;
+pub mod services;
+pub mod streaming;
+pub mod virtualcam;
@@ -0,0 +1,628 @@
+/// MKV 封装模块
+///
+/// 按簇写入 SimpleBlock，每个视频关键帧开始一个新簇。段大小、时长、索引和章节在结束时补写，
+/// 文件头预留的空间在结束时写入 SeekHead，异常退出时文件仍可按未知大小的段播放
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::{
+    media::{
+        aac::AAC_FRAME_SAMPLES,
+        encoder::{AudioEncoderSettings, AvcHeader, EncodedPacket, PacketKind},
+    },
+    outputs::{
+        encoders::EncoderKey,
+        recording::muxer::{Chapter, Muxer},
+    },
+    Result,
+};
+
+const ID_EBML: u32 = 0x1a45_dfa3;
+const ID_EBML_VERSION: u32 = 0x4286;
+const ID_EBML_READ_VERSION: u32 = 0x42f7;
+const ID_EBML_MAX_ID_LENGTH: u32 = 0x42f2;
+const ID_EBML_MAX_SIZE_LENGTH: u32 = 0x42f3;
+const ID_DOC_TYPE: u32 = 0x4282;
+const ID_DOC_TYPE_VERSION: u32 = 0x4287;
+const ID_DOC_TYPE_READ_VERSION: u32 = 0x4285;
+const ID_SEGMENT: u32 = 0x1853_8067;
+const ID_VOID: u32 = 0xec;
+const ID_SEEK_HEAD: u32 = 0x114d_9b74;
+const ID_SEEK: u32 = 0x4dbb;
+const ID_SEEK_ID: u32 = 0x53ab;
+const ID_SEEK_POSITION: u32 = 0x53ac;
+const ID_INFO: u32 = 0x1549_a966;
+const ID_TIMESTAMP_SCALE: u32 = 0x2a_d7b1;
+const ID_DURATION: u32 = 0x4489;
+const ID_MUXING_APP: u32 = 0x4d80;
+const ID_WRITING_APP: u32 = 0x5741;
+const ID_TRACKS: u32 = 0x1654_ae6b;
+const ID_TRACK_ENTRY: u32 = 0xae;
+const ID_TRACK_NUMBER: u32 = 0xd7;
+const ID_TRACK_UID: u32 = 0x73c5;
+const ID_TRACK_TYPE: u32 = 0x83;
+const ID_FLAG_LACING: u32 = 0x9c;
+const ID_CODEC_ID: u32 = 0x86;
+const ID_CODEC_PRIVATE: u32 = 0x63a2;
+const ID_DEFAULT_DURATION: u32 = 0x23_e383;
+const ID_VIDEO: u32 = 0xe0;
+const ID_PIXEL_WIDTH: u32 = 0xb0;
+const ID_PIXEL_HEIGHT: u32 = 0xba;
+const ID_AUDIO: u32 = 0xe1;
+const ID_SAMPLING_FREQUENCY: u32 = 0xb5;
+const ID_CHANNELS: u32 = 0x9f;
+const ID_CLUSTER: u32 = 0x1f43_b675;
+const ID_CLUSTER_TIMESTAMP: u32 = 0xe7;
+const ID_SIMPLE_BLOCK: u32 = 0xa3;
+const ID_CUES: u32 = 0x1c53_bb6b;
+const ID_CUE_POINT: u32 = 0xbb;
+const ID_CUE_TIME: u32 = 0xb3;
+const ID_CUE_TRACK_POSITIONS: u32 = 0xb7;
+const ID_CUE_TRACK: u32 = 0xf7;
+const ID_CUE_CLUSTER_POSITION: u32 = 0xf1;
+const ID_CHAPTERS: u32 = 0x1043_a770;
+const ID_EDITION_ENTRY: u32 = 0x45b9;
+const ID_EDITION_UID: u32 = 0x45bc;
+const ID_CHAPTER_ATOM: u32 = 0xb6;
+const ID_CHAPTER_UID: u32 = 0x73c4;
+const ID_CHAPTER_TIME_START: u32 = 0x91;
+const ID_CHAPTER_DISPLAY: u32 = 0x80;
+const ID_CHAP_STRING: u32 = 0x85;
+const ID_CHAP_LANGUAGE: u32 = 0x437c;
+
+/// 时间戳单位（纳秒），即毫秒
+const TIMESTAMP_SCALE: u64 = 1_000_000;
+
+/// 文件头中为 SeekHead 预留的字节数
+const SEEK_HEAD_SPACE: usize = 128;
+
+/// 簇内相对时间戳的上限（毫秒），超过时开始新簇
+const MAX_CLUSTER_SPAN: u64 = 30_000;
+
+const VIDEO_TRACK: u64 = 1;
+const AUDIO_TRACK: u64 = 2;
+
+/// 写入元素 ID，ID 自带长度标记
+fn put_id(buf: &mut Vec<u8>, id: u32) {
+    let bytes = id.to_be_bytes();
+    let skip = bytes.iter().take_while(|byte| **byte == 0).count().min(3);
+    buf.extend_from_slice(&bytes[skip..]);
+}
+
+/// 写入最短的变长大小
+fn put_size(buf: &mut Vec<u8>, size: u64) {
+    let length = (1..=8)
+        .find(|length| size < (1u64 << (7 * length)) - 1)
+        .unwrap_or(8);
+    let value = size | (1u64 << (7 * length));
+    buf.extend_from_slice(&value.to_be_bytes()[8 - length as usize..]);
+}
+
+fn put_element(buf: &mut Vec<u8>, id: u32, payload: &[u8]) {
+    put_id(buf, id);
+    put_size(buf, payload.len() as u64);
+    buf.extend_from_slice(payload);
+}
+
+fn put_uint(buf: &mut Vec<u8>, id: u32, value: u64) {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|byte| **byte == 0).count().min(7);
+    put_element(buf, id, &bytes[skip..]);
+}
+
+fn put_float(buf: &mut Vec<u8>, id: u32, value: f64) {
+    put_element(buf, id, &value.to_be_bytes());
+}
+
+fn put_string(buf: &mut Vec<u8>, id: u32, value: &str) {
+    put_element(buf, id, value.as_bytes());
+}
+
+/// 写入主元素，子元素由 `fill` 写入
+fn put_master(buf: &mut Vec<u8>, id: u32, fill: impl FnOnce(&mut Vec<u8>)) {
+    let mut payload = vec![];
+    fill(&mut payload);
+    put_element(buf, id, &payload);
+}
+
+/// 非零的随机 UID
+fn random_uid() -> u64 {
+    let bytes = uuid::Uuid::new_v4().into_bytes();
+    u64::from_be_bytes(bytes[..8].try_into().unwrap()).max(1)
+}
+
+/// 索引中的关键帧位置
+struct CuePoint {
+    /// 时间（毫秒）
+    time: u64,
+    /// 簇相对段数据开始的位置
+    position: u64,
+}
+
+/// MKV 封装器
+pub struct MkvMuxer {
+    file: BufWriter<File>,
+    /// 已写入的字节数，即当前位置
+    position: u64,
+    /// 段数据开始的位置
+    segment_start: u64,
+    /// 时长浮点数的位置
+    duration_position: u64,
+    /// Info 和 Tracks 相对段数据开始的位置
+    info_position: u64,
+    tracks_position: u64,
+    /// 当前簇的时间（毫秒）和已缓存的块
+    cluster: Option<(u64, Vec<u8>)>,
+    cues: Vec<CuePoint>,
+    chapters: Vec<Chapter>,
+    /// 一帧的时长（纳秒）
+    frame_duration: u64,
+    audio: AudioEncoderSettings,
+    /// 已写入内容的结束时间（纳秒）
+    end: u64,
+}
+
+impl MkvMuxer {
+    /// 创建文件并写入文件头和轨道信息
+    ///
+    /// # 参数
+    ///
+    /// * `path` - 文件路径
+    /// * `key` - 编码设置
+    /// * `header` - SPS/PPS
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，文件无法创建时返回错误
+    pub fn create(path: &Path, key: &EncoderKey, header: &AvcHeader) -> Result<Self> {
+        let video = &key.video;
+        let audio = &key.audio;
+        let frame_duration =
+            1_000_000_000 * video.fps_den.max(1) as u64 / video.fps_num.max(1) as u64;
+
+        let mut buf = vec![];
+        put_master(&mut buf, ID_EBML, |buf| {
+            put_uint(buf, ID_EBML_VERSION, 1);
+            put_uint(buf, ID_EBML_READ_VERSION, 1);
+            put_uint(buf, ID_EBML_MAX_ID_LENGTH, 4);
+            put_uint(buf, ID_EBML_MAX_SIZE_LENGTH, 8);
+            put_string(buf, ID_DOC_TYPE, "matroska");
+            put_uint(buf, ID_DOC_TYPE_VERSION, 4);
+            put_uint(buf, ID_DOC_TYPE_READ_VERSION, 2);
+        });
+
+        // 段大小在结束时补写，异常退出时保持未知大小
+        put_id(&mut buf, ID_SEGMENT);
+        buf.extend_from_slice(&[0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        let segment_start = buf.len() as u64;
+
+        put_id(&mut buf, ID_VOID);
+        put_size(&mut buf, (SEEK_HEAD_SPACE - 2) as u64);
+        buf.resize(segment_start as usize + SEEK_HEAD_SPACE, 0);
+
+        let info_position = buf.len() as u64 - segment_start;
+        let mut info = vec![];
+        put_uint(&mut info, ID_TIMESTAMP_SCALE, TIMESTAMP_SCALE);
+        put_string(&mut info, ID_MUXING_APP, "obs-studio");
+        put_string(&mut info, ID_WRITING_APP, "obs-studio");
+        put_float(&mut info, ID_DURATION, 0.);
+        put_element(&mut buf, ID_INFO, &info);
+        // 时长是 Info 的最后一个元素
+        let duration_position = buf.len() as u64 - 8;
+
+        let tracks_position = buf.len() as u64 - segment_start;
+        put_master(&mut buf, ID_TRACKS, |buf| {
+            put_master(buf, ID_TRACK_ENTRY, |buf| {
+                put_uint(buf, ID_TRACK_NUMBER, VIDEO_TRACK);
+                put_uint(buf, ID_TRACK_UID, random_uid());
+                put_uint(buf, ID_TRACK_TYPE, 1);
+                put_uint(buf, ID_FLAG_LACING, 0);
+                put_string(buf, ID_CODEC_ID, "V_MPEG4/ISO/AVC");
+                put_element(buf, ID_CODEC_PRIVATE, &header.decoder_config());
+                put_uint(buf, ID_DEFAULT_DURATION, frame_duration);
+                put_master(buf, ID_VIDEO, |buf| {
+                    put_uint(buf, ID_PIXEL_WIDTH, video.width as u64);
+                    put_uint(buf, ID_PIXEL_HEIGHT, video.height as u64);
+                });
+            });
+            put_master(buf, ID_TRACK_ENTRY, |buf| {
+                put_uint(buf, ID_TRACK_NUMBER, AUDIO_TRACK);
+                put_uint(buf, ID_TRACK_UID, random_uid());
+                put_uint(buf, ID_TRACK_TYPE, 2);
+                put_uint(buf, ID_FLAG_LACING, 0);
+                put_string(buf, ID_CODEC_ID, "A_AAC");
+                put_element(buf, ID_CODEC_PRIVATE, &audio.decoder_config());
+                put_master(buf, ID_AUDIO, |buf| {
+                    put_float(buf, ID_SAMPLING_FREQUENCY, audio.sample_rate as f64);
+                    put_uint(buf, ID_CHANNELS, audio.channels as u64);
+                });
+            });
+        });
+
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&buf)?;
+
+        Ok(Self {
+            file,
+            position: buf.len() as u64,
+            segment_start,
+            duration_position,
+            info_position,
+            tracks_position,
+            cluster: None,
+            cues: vec![],
+            chapters: vec![],
+            frame_duration,
+            audio: *audio,
+            end: 0,
+        })
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Result<()> {
+        self.file.write_all(buf)?;
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+
+    /// 写入缓存的簇
+    fn flush_cluster(&mut self) -> Result<()> {
+        let Some((time, blocks)) = self.cluster.take() else {
+            return Ok(());
+        };
+
+        let mut buf = vec![];
+        put_id(&mut buf, ID_CLUSTER);
+        let mut payload = vec![];
+        put_uint(&mut payload, ID_CLUSTER_TIMESTAMP, time);
+        put_size(&mut buf, (payload.len() + blocks.len()) as u64);
+        buf.extend_from_slice(&payload);
+        self.write(&buf)?;
+        self.write(&blocks)?;
+        self.file.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Muxer for MkvMuxer {
+    fn write_packet(&mut self, packet: &EncodedPacket, timestamp: u64) -> Result<u64> {
+        let video = packet.kind == PacketKind::Video;
+        // 块时间戳为显示时间戳
+        let pts = timestamp + packet.pts.saturating_sub(packet.dts);
+        let time = pts / TIMESTAMP_SCALE;
+
+        let new_cluster = match &self.cluster {
+            Some((start, _)) => {
+                (video && packet.keyframe) || time.saturating_sub(*start) > MAX_CLUSTER_SPAN
+            }
+            None => true,
+        };
+        if new_cluster {
+            self.flush_cluster()?;
+            if video && packet.keyframe {
+                self.cues.push(CuePoint {
+                    time,
+                    position: self.position - self.segment_start,
+                });
+            }
+            self.cluster = Some((time, vec![]));
+        }
+
+        let (start, blocks) = self.cluster.as_mut().unwrap();
+        let relative = (time as i64 - *start as i64).clamp(i16::MIN as i64, i16::MAX as i64);
+        let mut block = Vec::with_capacity(packet.data.len() + 4);
+        put_size(&mut block, if video { VIDEO_TRACK } else { AUDIO_TRACK });
+        block.extend_from_slice(&(relative as i16).to_be_bytes());
+        block.push(if packet.keyframe { 0x80 } else { 0 });
+        block.extend_from_slice(&packet.data);
+        let size = blocks.len();
+        put_element(blocks, ID_SIMPLE_BLOCK, &block);
+        let written = (blocks.len() - size) as u64;
+
+        let duration = if video {
+            self.frame_duration
+        } else {
+            AAC_FRAME_SAMPLES as u64 * 1_000_000_000 / self.audio.sample_rate.max(1) as u64
+        };
+        self.end = self.end.max(pts + duration);
+
+        Ok(written)
+    }
+
+    fn add_chapter(&mut self, chapter: Chapter) {
+        self.chapters.push(chapter);
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.flush_cluster()?;
+
+        let cues_position = self.position - self.segment_start;
+        let mut buf = vec![];
+        put_master(&mut buf, ID_CUES, |buf| {
+            for cue in &self.cues {
+                put_master(buf, ID_CUE_POINT, |buf| {
+                    put_uint(buf, ID_CUE_TIME, cue.time);
+                    put_master(buf, ID_CUE_TRACK_POSITIONS, |buf| {
+                        put_uint(buf, ID_CUE_TRACK, VIDEO_TRACK);
+                        put_uint(buf, ID_CUE_CLUSTER_POSITION, cue.position);
+                    });
+                });
+            }
+        });
+
+        let chapters_position = self.position + buf.len() as u64 - self.segment_start;
+        if !self.chapters.is_empty() {
+            put_master(&mut buf, ID_CHAPTERS, |buf| {
+                put_master(buf, ID_EDITION_ENTRY, |buf| {
+                    put_uint(buf, ID_EDITION_UID, random_uid());
+                    for chapter in &self.chapters {
+                        put_master(buf, ID_CHAPTER_ATOM, |buf| {
+                            put_uint(buf, ID_CHAPTER_UID, random_uid());
+                            put_uint(buf, ID_CHAPTER_TIME_START, chapter.timestamp);
+                            put_master(buf, ID_CHAPTER_DISPLAY, |buf| {
+                                put_string(buf, ID_CHAP_STRING, &chapter.name);
+                                put_string(buf, ID_CHAP_LANGUAGE, "und");
+                            });
+                        });
+                    }
+                });
+            });
+        }
+        self.write(&buf)?;
+
+        let mut seeks = vec![
+            (ID_INFO, self.info_position),
+            (ID_TRACKS, self.tracks_position),
+            (ID_CUES, cues_position),
+        ];
+        if !self.chapters.is_empty() {
+            seeks.push((ID_CHAPTERS, chapters_position));
+        }
+        let mut seek_head = vec![];
+        put_master(&mut seek_head, ID_SEEK_HEAD, |buf| {
+            for (id, position) in seeks {
+                put_master(buf, ID_SEEK, |buf| {
+                    put_element(buf, ID_SEEK_ID, &id.to_be_bytes());
+                    put_uint(buf, ID_SEEK_POSITION, position);
+                });
+            }
+        });
+        put_id(&mut seek_head, ID_VOID);
+        let void_size = SEEK_HEAD_SPACE - seek_head.len() - 1;
+        put_size(&mut seek_head, void_size as u64);
+        seek_head.resize(SEEK_HEAD_SPACE, 0);
+
+        let segment_size = self.position - self.segment_start;
+        let file = &mut self.file;
+        file.seek(SeekFrom::Start(self.segment_start - 8))?;
+        file.write_all(&((1u64 << 56) | segment_size).to_be_bytes())?;
+        file.write_all(&seek_head)?;
+        file.seek(SeekFrom::Start(self.duration_position))?;
+        file.write_all(&((self.end as f64) / TIMESTAMP_SCALE as f64).to_be_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        media::encoder::{EncoderPreset, FramePriority, VideoEncoderSettings},
+        protocols::flv::flv_audio_settings,
+    };
+
+    const MS: u64 = 1_000_000;
+
+    /// 视频帧间隔（毫秒）
+    const FRAME_MS: u64 = 40;
+
+    /// 音频帧间隔（毫秒），实际约为 23.2
+    const AUDIO_MS: u64 = 23;
+
+    fn key() -> EncoderKey {
+        EncoderKey {
+            canvas: None,
+            video: VideoEncoderSettings {
+                width: 64,
+                height: 64,
+                fps_num: (1000 / FRAME_MS) as u32,
+                fps_den: 1,
+                bitrate: 1000,
+                keyint_sec: 1,
+                preset: EncoderPreset::default(),
+            },
+            audio: flv_audio_settings(2),
+        }
+    }
+
+    fn header() -> AvcHeader {
+        AvcHeader {
+            sps: vec![0x67, 0x42, 0xc0, 0x1f],
+            pps: vec![0x68, 0xce, 0x3c, 0x80],
+        }
+    }
+
+    /// 编码时间戳为 `dts` 毫秒的包，非关键帧的显示时间戳晚一帧
+    fn packet(kind: PacketKind, dts: u64, keyframe: bool) -> EncodedPacket {
+        let offset = if kind == PacketKind::Video && !keyframe {
+            FRAME_MS
+        } else {
+            0
+        };
+        EncodedPacket {
+            kind,
+            pts: (dts + offset) * MS,
+            dts: dts * MS,
+            keyframe,
+            priority: FramePriority::Inter,
+            header: None,
+            data: vec![dts as u8; 8],
+        }
+    }
+
+    /// 读取变长整数，`marker` 为真时保留长度标记（元素 ID）
+    fn read_vint(data: &[u8], pos: &mut usize, marker: bool) -> u64 {
+        let length = data[*pos].leading_zeros() as usize + 1;
+        let mut value = if marker {
+            data[*pos] as u64
+        } else {
+            (data[*pos] as u64) & (0xff >> length)
+        };
+        for byte in &data[*pos + 1..*pos + length] {
+            value = (value << 8) | *byte as u64;
+        }
+        *pos += length;
+        value
+    }
+
+    /// 把数据拆分为同一层的元素
+    fn elements(data: &[u8]) -> Vec<(u32, &[u8])> {
+        let mut result = vec![];
+        let mut pos = 0;
+        while pos < data.len() {
+            let id = read_vint(data, &mut pos, true) as u32;
+            let size = read_vint(data, &mut pos, false) as usize;
+            result.push((id, &data[pos..pos + size]));
+            pos += size;
+        }
+        result
+    }
+
+    /// 第一个指定 ID 的子元素
+    fn child(data: &[u8], id: u32) -> &[u8] {
+        elements(data)
+            .into_iter()
+            .find(|(element, _)| *element == id)
+            .unwrap()
+            .1
+    }
+
+    fn uint(data: &[u8]) -> u64 {
+        data.iter()
+            .fold(0, |value, byte| (value << 8) | *byte as u64)
+    }
+
+    #[test]
+    fn clusters_cues_and_chapters_read_back() {
+        let path = std::env::temp_dir().join(format!("mkv-{}.mkv", std::process::id()));
+        let mut muxer = Box::new(MkvMuxer::create(&path, &key(), &header()).unwrap());
+
+        let mut packets: Vec<EncodedPacket> = (0..3000)
+            .step_by(FRAME_MS as usize)
+            .map(|ms| packet(PacketKind::Video, ms, ms % 1000 == 0))
+            .collect();
+        packets.extend(
+            (0..3000)
+                .step_by(AUDIO_MS as usize)
+                .map(|ms| packet(PacketKind::Audio, ms, true)),
+        );
+        packets.sort_by_key(|packet| packet.dts);
+        for packet in &packets {
+            muxer.write_packet(packet, packet.dts).unwrap();
+        }
+        muxer.add_chapter(Chapter {
+            timestamp: 0,
+            name: "Start".to_string(),
+        });
+        muxer.add_chapter(Chapter {
+            timestamp: 1500 * MS,
+            name: "章节".to_string(),
+        });
+        muxer.finish().unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        let top = elements(&data);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].0, ID_EBML);
+        assert_eq!(top[1].0, ID_SEGMENT);
+        let segment = top[1].1;
+        let children = elements(segment);
+
+        // 每个关键帧开始一个簇，簇时间为关键帧的时间（毫秒）
+        let clusters: Vec<&[u8]> = children
+            .iter()
+            .filter(|(id, _)| *id == ID_CLUSTER)
+            .map(|(_, payload)| *payload)
+            .collect();
+        let times: Vec<u64> = clusters
+            .iter()
+            .map(|cluster| uint(child(cluster, ID_CLUSTER_TIMESTAMP)))
+            .collect();
+        assert_eq!(times, [0, 1000, 2000]);
+
+        // 块时间戳为显示时间戳，相对簇时间
+        let mut video = vec![];
+        let mut audio = 0;
+        for (cluster, time) in clusters.iter().zip(&times) {
+            let blocks: Vec<&[u8]> = elements(cluster)
+                .into_iter()
+                .filter(|(id, _)| *id == ID_SIMPLE_BLOCK)
+                .map(|(_, block)| block)
+                .collect();
+            assert_eq!(blocks[0][0] & 0x7f, VIDEO_TRACK as u8);
+            assert_eq!(blocks[0][3], 0x80, "cluster starts with a keyframe");
+            for block in blocks {
+                let relative = i16::from_be_bytes([block[1], block[2]]) as i64;
+                let pts = (*time as i64 + relative) as u64;
+                if block[0] & 0x7f == VIDEO_TRACK as u8 {
+                    video.push(pts);
+                } else {
+                    audio += 1;
+                }
+            }
+        }
+        let expected: Vec<u64> = (0..3000)
+            .step_by(FRAME_MS as usize)
+            .map(|ms| if ms % 1000 == 0 { ms } else { ms + FRAME_MS })
+            .collect();
+        assert_eq!(video, expected);
+        assert_eq!(audio, 3000usize.div_ceil(AUDIO_MS as usize));
+
+        // 时长为最后一帧的显示时间加帧时长
+        let info = child(segment, ID_INFO);
+        let duration = f64::from_be_bytes(child(info, ID_DURATION).try_into().unwrap());
+        assert_eq!(duration, 3040.);
+
+        // 索引指向各个簇
+        let cues: Vec<(u64, u64)> = elements(child(segment, ID_CUES))
+            .into_iter()
+            .map(|(_, point)| {
+                let positions = child(point, ID_CUE_TRACK_POSITIONS);
+                (
+                    uint(child(point, ID_CUE_TIME)),
+                    uint(child(positions, ID_CUE_CLUSTER_POSITION)),
+                )
+            })
+            .collect();
+        assert_eq!(cues.len(), 3);
+        for ((time, position), expected) in cues.iter().zip(&times) {
+            assert_eq!(time, expected);
+            let mut pos = *position as usize;
+            assert_eq!(read_vint(segment, &mut pos, true) as u32, ID_CLUSTER);
+        }
+
+        let edition = child(child(segment, ID_CHAPTERS), ID_EDITION_ENTRY);
+        let chapters: Vec<(u64, String)> = elements(edition)
+            .into_iter()
+            .filter(|(id, _)| *id == ID_CHAPTER_ATOM)
+            .map(|(_, atom)| {
+                let display = child(atom, ID_CHAPTER_DISPLAY);
+                (
+                    uint(child(atom, ID_CHAPTER_TIME_START)),
+                    String::from_utf8(child(display, ID_CHAP_STRING).to_vec()).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            chapters,
+            [(0, "Start".to_string()), (1500 * MS, "章节".to_string())]
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,1236 @@
+/// 录制模块
+///
+/// 录制输出订阅共享编码器，把音视频包写入 MKV 或 MP4 文件。支持暂停和恢复录制、
+/// 手动分割文件以及添加章节标记。暂停的时长从之后的时间戳中扣除，文件中的时间戳保持连续；
+/// 分割后的每个文件都从关键帧开始，时间戳从 0 开始
+pub mod mkv;
+pub mod mp4;
+pub mod muxer;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    media::{
+        audio::AudioInfo,
+        encoder::{AvcHeader, EncodedPacket, EncoderPreset, PacketKind, VideoEncoderSettings},
+    },
+    outputs::{
+        encoders::{subscribe, EncoderKey, EncoderSubscription},
+        recording::muxer::{create_muxer, Chapter, Muxer, RecordingFormat},
+        streaming::{DEFAULT_KEYINT_SEC, DEFAULT_STREAM_BITRATE},
+    },
+    protocols::flv::flv_audio_settings,
+    scene::canvas::{canvas_video_info, output_canvas},
+    stats::counters::{register_output, OutputCounters, OutputKind},
+    ui::tray::update_tray_menu,
+    utils::{cli::cli, locale::t, profile::get_profile_config},
+    Result,
+};
+
+/// 录制状态变化时发送给前端的事件名称
+pub const RECORDING_EVENT: &str = "recording-state";
+
+/// 录制输出的计数器 ID
+pub const RECORDING_OUTPUT: &str = "recording_output";
+
+/// 配置文件中的录制配置节，保存录制绑定的画布
+pub const RECORDING_SECTION: &str = "Recording";
+
+/// 默认的文件名格式
+const DEFAULT_FILENAME_FORMAT: &str = "%CCYY-%MM-%DD %hh-%mm-%ss";
+
+/// 等待编码包的间隔，期间检查是否需要停止和处理录制命令
+const PACKET_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 录制设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingSettings {
+    /// 录制文件保存目录
+    pub dir: PathBuf,
+    pub format: RecordingFormat,
+    /// 文件名格式，如 `%CCYY-%MM-%DD %hh-%mm-%ss`
+    pub filename_format: String,
+    /// 是否把文件名中的空格替换为下划线
+    pub no_space: bool,
+    /// 画布 UUID，为 `None` 时为主画布
+    pub canvas: Option<String>,
+    /// 视频码率（kbps）
+    pub bitrate: u32,
+    pub preset: EncoderPreset,
+}
+
+impl RecordingSettings {
+    /// 从当前配置文件读取录制设置，按输出模式读取简单或高级输出的设置
+    ///
+    /// # 参数
+    ///
+    /// * `app` - 应用程序句柄
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，没有可用的保存目录时返回错误
+    pub fn load(app: &AppHandle) -> Result<Self> {
+        let advanced = get_profile_config("Output", "Mode").as_deref() == Some("Advanced");
+        let (section, no_space_key) = if advanced {
+            ("AdvOut", "RecFileNameWithoutSpace")
+        } else {
+            ("SimpleOutput", "FileNameWithoutSpace")
+        };
+
+        Ok(Self {
+            dir: recording_dir(app).ok_or(anyhow!("recording path not set"))?,
+            format: get_profile_config(section, "RecFormat2")
+                .and_then(|name| RecordingFormat::from_name(&name))
+                .unwrap_or_default(),
+            filename_format: get_profile_config("Output", "FilenameFormatting")
+                .filter(|format| !format.trim().is_empty())
+                .unwrap_or(DEFAULT_FILENAME_FORMAT.to_string()),
+            no_space: get_profile_config(section, no_space_key)
+                .is_some_and(|value| value == "true" || value == "1"),
+            canvas: output_canvas(RECORDING_SECTION),
+            bitrate: get_profile_config("SimpleOutput", "VBitrate")
+                .and_then(|value| value.parse::<u32>().ok())
+                .filter(|bitrate| *bitrate > 0)
+                .unwrap_or(DEFAULT_STREAM_BITRATE),
+            preset: get_profile_config(section, "Preset")
+                .and_then(|name| EncoderPreset::from_name(&name))
+                .unwrap_or_default(),
+        })
+    }
+
+    /// 共享编码器的键，使用绑定画布的输出分辨率，音频设置与推流相同以便共用编码器
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<EncoderKey>`，绑定的画布不存在时返回错误
+    pub fn encoder_key(&self) -> Result<EncoderKey> {
+        let info = canvas_video_info(self.canvas.as_deref())?;
+
+        Ok(EncoderKey {
+            canvas: self.canvas.clone(),
+            video: VideoEncoderSettings {
+                width: info.output_width,
+                height: info.output_height,
+                fps_num: info.fps_num,
+                fps_den: info.fps_den,
+                bitrate: self.bitrate,
+                keyint_sec: DEFAULT_KEYINT_SEC,
+                preset: self.preset,
+            },
+            audio: flv_audio_settings(AudioInfo::load().channels),
+        })
+    }
+
+    /// 下一个录制文件的路径，文件已存在时在文件名后加序号
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<PathBuf>`，保存目录无法创建时返回错误
+    pub fn next_path(&self) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+
+        let mut name = format_filename(&self.filename_format, chrono::Local::now());
+        if self.no_space {
+            name = name.replace(' ', "_");
+        }
+        let extension = self.format.extension();
+        let mut path = self.dir.join(format!("{}.{}", name, extension));
+        let mut index = 1;
+        while path.exists() {
+            index += 1;
+            path = self.dir.join(format!("{} ({}).{}", name, index, extension));
+        }
+
+        Ok(path)
+    }
+}
+
+/// 按文件名格式生成文件名，不含扩展名
+///
+/// 支持 `%CCYY`、`%YY`、`%MM`、`%DD`、`%hh`、`%mm` 和 `%ss`，其他内容原样保留，
+/// 路径分隔符替换为下划线
+///
+/// # 参数
+///
+/// * `format` - 文件名格式
+/// * `time` - 录制开始的时间
+pub fn format_filename(format: &str, time: chrono::DateTime<chrono::Local>) -> String {
+    const SPECIFIERS: [(&str, &str); 7] = [
+        ("CCYY", "%Y"),
+        ("YY", "%y"),
+        ("MM", "%m"),
+        ("DD", "%d"),
+        ("hh", "%H"),
+        ("mm", "%M"),
+        ("ss", "%S"),
+    ];
+
+    let mut pattern = String::new();
+    let mut rest = format;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        if c != '%' {
+            pattern.push(c);
+            continue;
+        }
+        match SPECIFIERS
+            .iter()
+            .find(|(specifier, _)| rest.starts_with(specifier))
+        {
+            Some((specifier, replacement)) => {
+                pattern.push_str(replacement);
+                rest = &rest[specifier.len()..];
+            }
+            None => pattern.push_str("%%"),
+        }
+    }
+
+    time.format(&pattern).to_string().replace(['/', '\\'], "_")
+}
+
+/// 录制文件保存目录，未配置时使用系统视频目录
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+pub fn recording_dir(app: &AppHandle) -> Option<PathBuf> {
+    let mode = get_profile_config("Output", "Mode").unwrap_or("Simple".to_string());
+    let path = if mode == "Advanced" {
+        get_profile_config("AdvOut", "RecFilePath")
+    } else {
+        get_profile_config("SimpleOutput", "FilePath")
+    };
+
+    match path {
+        Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => app.path().video_dir().ok(),
+    }
+}
+
+/// 录制时间线，把编码器的时间戳换算为文件中的时间戳（纳秒）
+///
+/// 文件从关键帧开始，该帧的时间戳为 0。暂停期间的包被丢弃，暂停的时长从之后的时间戳中扣除；
+/// 早于文件开始或恢复位置的包也被丢弃。音视频交错时，暂停前可能已写入晚于暂停位置的音频，
+/// 恢复处接在已写入的位置之后，时间戳不会倒退
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timeline {
+    /// 当前文件开始处的编码时间戳
+    base: Option<u64>,
+    /// 当前文件中已扣除的暂停时长
+    paused: u64,
+    /// 暂停开始处的编码时间戳
+    paused_at: Option<u64>,
+    /// 早于该编码时间戳的包被丢弃
+    floor: u64,
+    /// 已写入的包在文件中的最大时间戳
+    position: u64,
+}
+
+impl Timeline {
+    /// 在关键帧处开始新文件
+    ///
+    /// # 参数
+    ///
+    /// * `dts` - 关键帧的编码时间戳
+    pub fn start(&mut self, dts: u64) {
+        *self = Self {
+            base: Some(dts),
+            floor: dts,
+            ..Default::default()
+        };
+    }
+
+    /// 从该编码时间戳开始暂停，已暂停时不做任何操作
+    ///
+    /// # 参数
+    ///
+    /// * `dts` - 第一个被丢弃的视频帧的编码时间戳
+    pub fn pause(&mut self, dts: u64) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(dts);
+        }
+    }
+
+    /// 在关键帧处恢复，之后的时间戳接在暂停前的位置和已写入的位置之后
+    ///
+    /// # 参数
+    ///
+    /// * `dts` - 关键帧的编码时间戳
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `bool`，未暂停或关键帧早于已写入的位置时返回 `false`，需要等待下一个关键帧
+    pub fn resume(&mut self, dts: u64) -> bool {
+        let (Some(base), Some(paused_at)) = (self.base, self.paused_at) else {
+            return false;
+        };
+        let resumed = paused_at
+            .saturating_sub(base + self.paused)
+            .max(self.position);
+        if dts < base + resumed {
+            return false;
+        }
+
+        self.paused_at = None;
+        self.paused = dts - base - resumed;
+        self.floor = dts;
+        true
+    }
+
+    /// 是否已开始文件
+    pub fn is_started(&self) -> bool {
+        self.base.is_some()
+    }
+
+    /// 是否处于暂停中
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// 换算包在文件中的时间戳
+    ///
+    /// # 参数
+    ///
+    /// * `dts` - 包的编码时间戳
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Option<u64>`，文件未开始、暂停中或包早于丢弃界限时返回 `None`
+    pub fn timestamp(&mut self, dts: u64) -> Option<u64> {
+        let base = self.base?;
+        if self.is_paused() || dts < self.floor {
+            return None;
+        }
+
+        let timestamp = dts.saturating_sub(base + self.paused);
+        self.position = self.position.max(timestamp);
+        Some(timestamp)
+    }
+
+    /// 已写入的位置在文件中的时间戳，用于添加章节标记
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+/// 发送给录制线程的命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordingCommand {
+    /// 暂停录制，从下一个视频帧开始丢弃
+    Pause,
+    /// 恢复录制，从下一个关键帧开始写入
+    Resume,
+    /// 在下一个关键帧处结束当前文件并开始新文件
+    Split,
+    /// 在当前位置添加章节标记，名称为 `None` 时自动编号
+    Chapter(Option<String>),
+}
+
+/// 录制输出的状态信息
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecordingStatus {
+    /// 是否正在录制，包括暂停中
+    pub active: bool,
+    pub paused: bool,
+    /// 当前录制文件的路径
+    pub path: Option<PathBuf>,
+    /// 本次录制写入的所有文件，分割后依次增加
+    pub files: Vec<PathBuf>,
+    /// 当前文件的时长（毫秒），不包括暂停的时间
+    pub duration: u64,
+    /// 当前文件的章节数
+    pub chapters: usize,
+    /// 录制失败时的错误
+    pub error: Option<String>,
+}
+
+/// 修改状态并通知的回调
+type StatusUpdate<'a> = &'a dyn Fn(&dyn Fn(&mut RecordingStatus));
+
+/// 运行中的录制输出
+pub struct RecordingOutput {
+    stop: Arc<AtomicBool>,
+    commands: Sender<RecordingCommand>,
+    status: Arc<Mutex<RecordingStatus>>,
+    /// 当前文件中已写入的位置（纳秒）
+    position: Arc<AtomicU64>,
+    thread: JoinHandle<()>,
+}
+
+impl RecordingOutput {
+    /// 启动录制输出
+    ///
+    /// # 参数
+    ///
+    /// * `settings` - 录制设置
+    /// * `subscription` - 编码器订阅
+    /// * `on_change` - 状态变化时的回调
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，线程无法创建时返回错误
+    pub fn start(
+        settings: RecordingSettings,
+        subscription: EncoderSubscription,
+        on_change: impl Fn(&RecordingStatus) + Send + 'static,
+    ) -> Result<Self> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (commands, receiver) = mpsc::channel();
+        let status = Arc::new(Mutex::new(RecordingStatus {
+            active: true,
+            ..Default::default()
+        }));
+        let position = Arc::new(AtomicU64::new(0));
+        let counters = register_output(RECORDING_OUTPUT, OutputKind::Recording);
+
+        let stopped = stop.clone();
+        let shared = status.clone();
+        let written = position.clone();
+        let thread = thread::Builder::new()
+            .name("recording".to_string())
+            .spawn(move || {
+                let update = |change: &dyn Fn(&mut RecordingStatus)| {
+                    let status = {
+                        let mut status = shared.lock().unwrap();
+                        change(&mut status);
+                        status.duration = written.load(Ordering::Relaxed) / 1_000_000;
+                        status.clone()
+                    };
+                    on_change(&status);
+                };
+
+                counters.set_active(true);
+                update(&|_| {});
+                let mut recorder = Recorder {
+                    settings: &settings,
+                    create_muxer,
+                    subscription: &subscription,
+                    counters: &counters,
+                    update: &update,
+                    position: &written,
+                    timeline: Timeline::default(),
+                    muxer: None,
+                    pause_requested: false,
+                    resume_requested: false,
+                    split_requested: false,
+                    waiting_keyframe: false,
+                    pending_chapters: vec![],
+                    chapters: 0,
+                };
+                let result = recorder.run(&receiver, &stopped);
+                let result = recorder.finish().and(result);
+                counters.set_active(false);
+
+                match result {
+                    Ok(_) => {
+                        info!("recording stopped");
+                        update(&|status| {
+                            status.active = false;
+                            status.paused = false;
+                        });
+                    }
+                    Err(e) => {
+                        error!("recording failed: {}", e);
+                        let message = e.to_string();
+                        update(&|status| {
+                            status.active = false;
+                            status.paused = false;
+                            status.error = Some(message.clone());
+                        });
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            stop,
+            commands,
+            status,
+            position,
+            thread,
+        })
+    }
+
+    /// 当前状态
+    pub fn status(&self) -> RecordingStatus {
+        let mut status = self.status.lock().unwrap().clone();
+        status.duration = self.position.load(Ordering::Relaxed) / 1_000_000;
+        status
+    }
+
+    /// 录制线程是否在运行
+    pub fn is_running(&self) -> bool {
+        !self.thread.is_finished()
+    }
+
+    /// 发送录制命令
+    ///
+    /// # 参数
+    ///
+    /// * `command` - 录制命令
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，录制线程已退出时返回错误
+    pub fn send(&self, command: RecordingCommand) -> Result<()> {
+        self.commands
+            .send(command)
+            .map_err(|_| anyhow!("recording not active"))
+    }
+
+    /// 停止录制，等待录制线程写完文件后退出
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if self.thread.join().is_err() {
+            error!("recording thread panicked");
+        }
+    }
+}
+
+/// 创建录制文件的函数，参数与 [`create_muxer`] 相同，测试时替换为不写文件的封装器
+type MuxerFactory = fn(RecordingFormat, &Path, &EncoderKey, &AvcHeader) -> Result<Box<dyn Muxer>>;
+
+/// 录制线程的状态
+struct Recorder<'a> {
+    settings: &'a RecordingSettings,
+    create_muxer: MuxerFactory,
+    subscription: &'a EncoderSubscription,
+    counters: &'a OutputCounters,
+    update: StatusUpdate<'a>,
+    position: &'a AtomicU64,
+    timeline: Timeline,
+    /// 当前文件，收到第一个关键帧后创建
+    muxer: Option<Box<dyn Muxer>>,
+    /// 收到暂停命令，等待下一个视频帧开始暂停
+    pause_requested: bool,
+    /// 收到恢复命令，等待下一个关键帧恢复
+    resume_requested: bool,
+    /// 收到分割命令，等待下一个关键帧开始新文件
+    split_requested: bool,
+    /// 编码包被丢弃后等待关键帧
+    waiting_keyframe: bool,
+    /// 文件创建前或等待分割时添加的章节，新文件创建后放在开头
+    pending_chapters: Vec<String>,
+    /// 当前文件的章节数
+    chapters: usize,
+}
+
+impl Recorder<'_> {
+    /// 接收编码包和命令的主循环
+    fn run(&mut self, commands: &Receiver<RecordingCommand>, stop: &AtomicBool) -> Result<()> {
+        self.subscription.request_keyframe();
+
+        loop {
+            if stop.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            while let Ok(command) = commands.try_recv() {
+                self.command(command);
+            }
+
+            match self.subscription.packets.recv_timeout(PACKET_POLL_INTERVAL) {
+                Ok(packet) => self.packet(&packet)?,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Err(anyhow!("encoder stopped")),
+            }
+        }
+    }
+
+    /// 处理录制命令
+    fn command(&mut self, command: RecordingCommand) {
+        match command {
+            RecordingCommand::Pause => {
+                if self.timeline.is_paused() && self.resume_requested {
+                    self.resume_requested = false;
+                } else if !self.timeline.is_paused() {
+                    self.pause_requested = true;
+                }
+                (self.update)(&|status| status.paused = true);
+            }
+            RecordingCommand::Resume => {
+                if self.pause_requested {
+                    self.pause_requested = false;
+                } else if self.timeline.is_paused() {
+                    self.resume_requested = true;
+                    self.subscription.request_keyframe();
+                }
+                (self.update)(&|status| status.paused = false);
+            }
+            RecordingCommand::Split => {
+                if self.muxer.is_some() {
+                    self.split_requested = true;
+                    self.subscription.request_keyframe();
+                }
+            }
+            RecordingCommand::Chapter(name) => {
+                // 等待分割时章节放在新文件的开头
+                let pending = self.split_requested || self.muxer.is_none();
+                let number = if pending {
+                    self.pending_chapters.len()
+                } else {
+                    self.chapters
+                } + 1;
+                let name = name
+                    .filter(|name| !name.trim().is_empty())
+                    .unwrap_or_else(|| format!("Chapter {}", number));
+                match self.muxer.as_mut().filter(|_| !pending) {
+                    Some(muxer) => {
+                        info!("recording chapter: {}", name);
+                        muxer.add_chapter(Chapter {
+                            timestamp: self.timeline.position(),
+                            name,
+                        });
+                        self.chapters += 1;
+                        let chapters = self.chapters;
+                        (self.update)(&|status| status.chapters = chapters);
+                    }
+                    None => self.pending_chapters.push(name),
+                }
+            }
+        }
+    }
+
+    /// 按时间线写入一个编码包
+    fn packet(&mut self, packet: &EncodedPacket) -> Result<()> {
+        let video = packet.kind == PacketKind::Video;
+        let keyframe = video && packet.keyframe;
+
+        let dropped = self.subscription.take_dropped();
+        if dropped > 0 {
+            warn!("recording dropped {} packets", dropped);
+            self.counters.record_dropped(dropped);
+            self.waiting_keyframe = true;
+            self.subscription.request_keyframe();
+        }
+
+        // 在视频帧处开始暂停，暂停前已收到的音频照常写入
+        if video && self.pause_requested {
+            self.pause_requested = false;
+            self.timeline.pause(packet.dts);
+        }
+
+        if self.timeline.is_paused() {
+            if !(keyframe && self.resume_requested) {
+                return Ok(());
+            }
+            let resumed = if self.muxer.is_none() || self.split_requested {
+                self.open(packet)?
+            } else {
+                self.timeline.resume(packet.dts)
+            };
+            if !resumed {
+                // 关键帧没有携带 SPS/PPS，或暂停前已写入的音频晚于该关键帧，等待下一个关键帧
+                self.subscription.request_keyframe();
+                return Ok(());
+            }
+            self.resume_requested = false;
+            self.waiting_keyframe = false;
+        } else if keyframe && (self.muxer.is_none() || self.split_requested) {
+            if !self.open(packet)? {
+                return Ok(());
+            }
+            self.waiting_keyframe = false;
+        }
+
+        if self.waiting_keyframe {
+            if !keyframe {
+                return Ok(());
+            }
+            self.waiting_keyframe = false;
+        }
+
+        let Some(muxer) = self.muxer.as_mut() else {
+            return Ok(());
+        };
+        let Some(timestamp) = self.timeline.timestamp(packet.dts) else {
+            return Ok(());
+        };
+        let bytes = muxer.write_packet(packet, timestamp)?;
+        self.counters.record_sent(bytes, video as u64);
+        self.position
+            .store(self.timeline.position(), Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// 在关键帧处结束当前文件并创建新文件
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<bool>`，关键帧没有携带 SPS/PPS 时返回 `false`，等待下一个关键帧
+    fn open(&mut self, packet: &EncodedPacket) -> Result<bool> {
+        let Some(header) = packet.header.as_ref() else {
+            return Ok(false);
+        };
+
+        if let Some(muxer) = self.muxer.take() {
+            muxer.finish()?;
+        }
+        let path = self.settings.next_path()?;
+        let mut muxer =
+            (self.create_muxer)(self.settings.format, &path, self.subscription.key(), header)?;
+        info!("recording to {}", path.display());
+
+        self.timeline.start(packet.dts);
+        self.position.store(0, Ordering::Relaxed);
+        self.split_requested = false;
+        self.chapters = self.pending_chapters.len();
+        for name in self.pending_chapters.drain(..) {
+            muxer.add_chapter(Chapter { timestamp: 0, name });
+        }
+        self.muxer = Some(muxer);
+
+        let chapters = self.chapters;
+        (self.update)(&|status| {
+            status.path = Some(path.clone());
+            status.files.push(path.clone());
+            status.chapters = chapters;
+        });
+
+        Ok(true)
+    }
+
+    /// 写入当前文件的索引和章节
+    fn finish(&mut self) -> Result<()> {
+        match self.muxer.take() {
+            Some(muxer) => muxer.finish(),
+            None => Ok(()),
+        }
+    }
+}
+
+lazy_static! {
+    /// 录制输出，失败的输出保留到下次启动或停止
+    static ref RECORDING: Mutex<Option<RecordingOutput>> = Mutex::new(None);
+}
+
+/// 开始录制，已在录制时不做任何操作
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，设置无效或编码器无法启动时返回错误
+pub fn start_recording(app: &AppHandle) -> Result<()> {
+    let mut recording = RECORDING.lock().unwrap();
+    if recording.as_ref().is_some_and(RecordingOutput::is_running) {
+        return Ok(());
+    }
+
+    let settings = RecordingSettings::load(app)?;
+    let key = settings.encoder_key()?;
+    key.video.validate()?;
+    let subscription = subscribe(RECORDING_OUTPUT, key)?;
+    let handle = app.clone();
+    *recording = Some(RecordingOutput::start(
+        settings,
+        subscription,
+        move |status| {
+            let _ = handle.emit(RECORDING_EVENT, status);
+            update_tray_menu(&handle);
+        },
+    )?);
+
+    Ok(())
+}
+
+/// 停止录制，等待当前文件写完
+pub fn stop_recording() {
+    let output = RECORDING.lock().unwrap().take();
+    if let Some(output) = output {
+        output.stop();
+    }
+}
+
+/// 向正在运行的录制输出发送命令
+fn send_command(command: RecordingCommand) -> Result<()> {
+    RECORDING
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|output| output.is_running())
+        .ok_or(anyhow!("recording not active"))?
+        .send(command)
+}
+
+/// 暂停录制，暂停的时长不计入文件
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，没有在录制时返回错误
+pub fn pause_recording() -> Result<()> {
+    send_command(RecordingCommand::Pause)
+}
+
+/// 恢复录制，从下一个关键帧开始写入
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，没有在录制时返回错误
+pub fn resume_recording() -> Result<()> {
+    send_command(RecordingCommand::Resume)
+}
+
+/// 在下一个关键帧处分割录制文件
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，没有在录制时返回错误
+pub fn split_recording() -> Result<()> {
+    send_command(RecordingCommand::Split)
+}
+
+/// 在当前位置添加章节标记
+///
+/// # 参数
+///
+/// * `name` - 章节名称，为 `None` 时自动编号
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，没有在录制时返回错误
+pub fn add_chapter(name: Option<String>) -> Result<()> {
+    send_command(RecordingCommand::Chapter(name))
+}
+
+/// 是否正在录制，包括暂停中
+pub fn recording_active() -> bool {
+    RECORDING
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(RecordingOutput::is_running)
+}
+
+/// 录制是否处于暂停中
+pub fn recording_paused() -> bool {
+    let status = recording_status();
+    status.active && status.paused
+}
+
+/// 录制输出的状态，没有录制时为默认状态
+pub fn recording_status() -> RecordingStatus {
+    RECORDING
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(RecordingOutput::status)
+        .unwrap_or_default()
+}
+
+/// 设置录制，指定 `--startrecording` 时开始录制
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn setup_recording(app: &AppHandle) -> Result<()> {
+    if cli()?.opt_start_recording {
+        if let Err(e) = start_recording(app) {
+            error!("{}: {}", t("Output.StartRecordingFailed")?, e);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::media::encoder::FramePriority;
+
+    const MS: u64 = 1_000_000;
+
+    /// 视频帧间隔（毫秒）
+    const FRAME_MS: u64 = 40;
+
+    /// 音频帧间隔（毫秒），实际约为 23.2
+    const AUDIO_MS: u64 = 23;
+
+    /// 测试封装器写入的包
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Written {
+        /// 文件序号，从 1 开始
+        file: usize,
+        video: bool,
+        keyframe: bool,
+        /// 在文件中的时间戳（毫秒）
+        timestamp: u64,
+    }
+
+    /// 测试封装器的输出
+    #[derive(Default)]
+    struct TestOutput {
+        /// 已创建的文件数
+        files: usize,
+        written: Vec<Written>,
+        /// 文件序号和添加的章节
+        chapters: Vec<(usize, Chapter)>,
+    }
+
+    thread_local! {
+        static OUTPUT: RefCell<TestOutput> = const {
+            RefCell::new(TestOutput {
+                files: 0,
+                written: Vec::new(),
+                chapters: Vec::new(),
+            })
+        };
+    }
+
+    /// 记录写入内容而不创建文件的封装器
+    struct TestMuxer {
+        file: usize,
+    }
+
+    impl Muxer for TestMuxer {
+        fn write_packet(&mut self, packet: &EncodedPacket, timestamp: u64) -> Result<u64> {
+            OUTPUT.with_borrow_mut(|output| {
+                output.written.push(Written {
+                    file: self.file,
+                    video: packet.kind == PacketKind::Video,
+                    keyframe: packet.keyframe,
+                    timestamp: timestamp / MS,
+                })
+            });
+            Ok(packet.data.len() as u64)
+        }
+
+        fn add_chapter(&mut self, chapter: Chapter) {
+            OUTPUT.with_borrow_mut(|output| output.chapters.push((self.file, chapter)));
+        }
+
+        fn finish(self: Box<Self>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn create_test_muxer(
+        _format: RecordingFormat,
+        _path: &Path,
+        _key: &EncoderKey,
+        _header: &AvcHeader,
+    ) -> Result<Box<dyn Muxer>> {
+        let file = OUTPUT.with_borrow_mut(|output| {
+            output.files += 1;
+            output.files
+        });
+        Ok(Box::new(TestMuxer { file }))
+    }
+
+    /// 录制线程收到的输入
+    enum Input {
+        /// 视频帧的编码时间戳（毫秒）和是否为关键帧
+        Video(u64, bool),
+        /// 音频帧的编码时间戳（毫秒）
+        Audio(u64),
+        Command(RecordingCommand),
+    }
+
+    fn packet(kind: PacketKind, dts: u64, keyframe: bool) -> EncodedPacket {
+        let priority = match (kind, keyframe) {
+            (PacketKind::Audio, _) => FramePriority::Audio,
+            (_, true) => FramePriority::Key,
+            _ => FramePriority::Inter,
+        };
+        let header = AvcHeader {
+            sps: vec![0x67, 0x42, 0xc0, 0x1f],
+            pps: vec![0x68, 0xce, 0x3c, 0x80],
+        };
+
+        EncodedPacket {
+            kind,
+            pts: dts * MS,
+            dts: dts * MS,
+            keyframe,
+            priority,
+            header: (kind == PacketKind::Video && keyframe).then(|| Arc::new(header)),
+            data: vec![0; 16],
+        }
+    }
+
+    /// 按顺序处理输入，返回写入的包和章节
+    fn record(inputs: Vec<Input>) -> (Vec<Written>, Vec<(usize, Chapter)>) {
+        let settings = RecordingSettings {
+            dir: std::env::temp_dir().join(format!("recording-test-{}", std::process::id())),
+            format: RecordingFormat::Mkv,
+            filename_format: DEFAULT_FILENAME_FORMAT.to_string(),
+            no_space: false,
+            canvas: None,
+            bitrate: 1000,
+            preset: EncoderPreset::default(),
+        };
+        let key = EncoderKey {
+            canvas: None,
+            video: VideoEncoderSettings {
+                width: 64,
+                height: 64,
+                fps_num: 25,
+                fps_den: 1,
+                bitrate: settings.bitrate,
+                keyint_sec: 1,
+                preset: settings.preset,
+            },
+            audio: flv_audio_settings(2),
+        };
+        let subscription = EncoderSubscription::detached(key);
+        let counters = register_output("recording_test", OutputKind::Recording);
+        let position = AtomicU64::new(0);
+        let update = |_: &dyn Fn(&mut RecordingStatus)| {};
+        let mut recorder = Recorder {
+            settings: &settings,
+            create_muxer: create_test_muxer,
+            subscription: &subscription,
+            counters: &counters,
+            update: &update,
+            position: &position,
+            timeline: Timeline::default(),
+            muxer: None,
+            pause_requested: false,
+            resume_requested: false,
+            split_requested: false,
+            waiting_keyframe: false,
+            pending_chapters: vec![],
+            chapters: 0,
+        };
+
+        OUTPUT.take();
+        for input in inputs {
+            match input {
+                Input::Video(dts, keyframe) => recorder
+                    .packet(&packet(PacketKind::Video, dts, keyframe))
+                    .unwrap(),
+                Input::Audio(dts) => recorder
+                    .packet(&packet(PacketKind::Audio, dts, true))
+                    .unwrap(),
+                Input::Command(command) => recorder.command(command),
+            }
+        }
+        recorder.finish().unwrap();
+        let _ = fs::remove_dir_all(&settings.dir);
+
+        let output = OUTPUT.take();
+        (output.written, output.chapters)
+    }
+
+    /// 按编码时间戳交错的音视频，每秒一个关键帧
+    fn stream(range: std::ops::Range<u64>) -> Vec<Input> {
+        let mut inputs = vec![];
+        let mut audio = range.start.div_ceil(AUDIO_MS) * AUDIO_MS;
+        let mut video = range.start.div_ceil(FRAME_MS) * FRAME_MS;
+        while video < range.end || audio < range.end {
+            if video <= audio && video < range.end {
+                inputs.push(Input::Video(video, video.is_multiple_of(1000)));
+                video += FRAME_MS;
+            } else {
+                inputs.push(Input::Audio(audio));
+                audio += AUDIO_MS;
+            }
+        }
+        inputs
+    }
+
+    /// 检查每个文件中两路时间戳都不倒退，且都从关键帧开始
+    fn assert_monotonic(written: &[Written]) {
+        let files = written.iter().map(|item| item.file).max().unwrap_or(0);
+        for file in 1..=files {
+            let packets: Vec<&Written> = written.iter().filter(|item| item.file == file).collect();
+            assert!(packets[0].video && packets[0].keyframe);
+            assert_eq!(packets[0].timestamp, 0);
+            for video in [true, false] {
+                let timestamps: Vec<u64> = packets
+                    .iter()
+                    .filter(|item| item.video == video)
+                    .map(|item| item.timestamp)
+                    .collect();
+                assert!(timestamps.windows(2).all(|pair| pair[0] <= pair[1]));
+            }
+            // 任何包都不早于它之前已写入的包太多，音视频最多相差一帧
+            let mut position = 0;
+            for item in &packets {
+                assert!(
+                    item.timestamp + FRAME_MS >= position,
+                    "{item:?} after {position}"
+                );
+                position = position.max(item.timestamp);
+            }
+        }
+    }
+
+    #[test]
+    fn timeline_subtracts_paused_time() {
+        let mut timeline = Timeline::default();
+        assert_eq!(timeline.timestamp(500 * MS), None);
+
+        timeline.start(1000 * MS);
+        assert_eq!(timeline.timestamp(990 * MS), None);
+        assert_eq!(timeline.timestamp(1000 * MS), Some(0));
+        assert_eq!(timeline.timestamp(1040 * MS), Some(40 * MS));
+
+        timeline.pause(1080 * MS);
+        assert!(timeline.is_paused());
+        assert_eq!(timeline.timestamp(1500 * MS), None);
+        assert!(timeline.resume(3000 * MS));
+        // 恢复前的包被丢弃，恢复处接在暂停位置之后
+        assert_eq!(timeline.timestamp(2990 * MS), None);
+        assert_eq!(timeline.timestamp(3000 * MS), Some(80 * MS));
+        assert_eq!(timeline.timestamp(3040 * MS), Some(120 * MS));
+
+        timeline.pause(3080 * MS);
+        assert!(timeline.resume(4000 * MS));
+        assert_eq!(timeline.timestamp(4000 * MS), Some(160 * MS));
+        assert_eq!(timeline.position(), 160 * MS);
+
+        // 分割后的文件从 0 开始，不再扣除之前的暂停
+        timeline.start(5000 * MS);
+        assert_eq!(timeline.timestamp(5040 * MS), Some(40 * MS));
+        assert!(!timeline.resume(5080 * MS));
+    }
+
+    #[test]
+    fn timeline_resumes_after_written_audio() {
+        let mut timeline = Timeline::default();
+        timeline.start(0);
+        assert_eq!(timeline.timestamp(0), Some(0));
+        // 视频延迟时音频先写入，暂停从较早的视频帧开始
+        assert_eq!(timeline.timestamp(300 * MS), Some(300 * MS));
+        timeline.pause(40 * MS);
+
+        // 早于已写入音频的关键帧不能恢复
+        assert!(!timeline.resume(200 * MS));
+        assert!(timeline.is_paused());
+        assert_eq!(timeline.timestamp(250 * MS), None);
+
+        // 恢复处接在已写入的音频之后
+        assert!(timeline.resume(1000 * MS));
+        assert_eq!(timeline.timestamp(990 * MS), None);
+        assert_eq!(timeline.timestamp(1000 * MS), Some(300 * MS));
+        assert_eq!(timeline.timestamp(1023 * MS), Some(323 * MS));
+    }
+
+    #[test]
+    fn pause_and_resume_keep_timestamps_continuous() {
+        let mut inputs = stream(0..2000);
+        inputs.push(Input::Command(RecordingCommand::Pause));
+        inputs.extend(stream(2000..5000));
+        inputs.push(Input::Command(RecordingCommand::Resume));
+        inputs.extend(stream(5000..6500));
+        let (written, _) = record(inputs);
+
+        assert_monotonic(&written);
+        assert!(written.iter().all(|item| item.file == 1));
+        let video: Vec<&Written> = written.iter().filter(|item| item.video).collect();
+        // 暂停前的 2 秒和恢复后从关键帧开始的 1.5 秒，中间没有空隙
+        assert_eq!(video.len(), 50 + 38);
+        assert!(video
+            .windows(2)
+            .all(|pair| pair[1].timestamp - pair[0].timestamp == FRAME_MS));
+        let resumed = video[50];
+        assert!(resumed.keyframe);
+        assert_eq!(resumed.timestamp, 2000);
+        let audio = written.iter().filter(|item| !item.video).count();
+        assert_eq!(
+            audio,
+            (2000 / AUDIO_MS + 1) as usize + (1500 / AUDIO_MS) as usize
+        );
+    }
+
+    #[test]
+    fn resume_waits_for_keyframe_after_written_audio() {
+        // 600 毫秒后的视频延迟到 1400 毫秒的音频写入之后才到达
+        let mut inputs = stream(0..600);
+        inputs.extend((600..1400).step_by(AUDIO_MS as usize).map(Input::Audio));
+        // 暂停从延迟的第一个视频帧开始
+        inputs.push(Input::Command(RecordingCommand::Pause));
+        inputs.push(Input::Video(600, false));
+        inputs.push(Input::Command(RecordingCommand::Resume));
+        inputs.extend(
+            (640..1400)
+                .step_by(FRAME_MS as usize)
+                .map(|dts| Input::Video(dts, dts == 1000)),
+        );
+        inputs.extend(stream(1400..3500));
+        let (written, _) = record(inputs);
+
+        assert_monotonic(&written);
+        // 1000 毫秒的关键帧早于已写入的音频，在 2000 毫秒的关键帧处恢复，接在最后写入的音频处
+        let resumed = written
+            .iter()
+            .position(|item| item.video && item.timestamp > 560)
+            .unwrap();
+        let last_audio = written[..resumed]
+            .iter()
+            .filter(|item| !item.video)
+            .map(|item| item.timestamp)
+            .max()
+            .unwrap();
+        assert_eq!(last_audio, 1382);
+        let video: Vec<&Written> = written.iter().filter(|item| item.video).collect();
+        assert_eq!(video.len(), 15 + 38);
+        assert!(video[15].keyframe);
+        assert_eq!(video[15].timestamp, last_audio);
+        assert!(video[15..]
+            .windows(2)
+            .all(|pair| pair[1].timestamp - pair[0].timestamp == FRAME_MS));
+    }
+
+    #[test]
+    fn split_starts_new_file_at_keyframe() {
+        let mut inputs = stream(0..1500);
+        inputs.push(Input::Command(RecordingCommand::Split));
+        inputs.push(Input::Command(RecordingCommand::Chapter(Some(
+            "next".to_string(),
+        ))));
+        inputs.extend(stream(1500..3000));
+        inputs.push(Input::Command(RecordingCommand::Pause));
+        inputs.extend(stream(3000..3600));
+        inputs.push(Input::Command(RecordingCommand::Split));
+        inputs.push(Input::Command(RecordingCommand::Resume));
+        inputs.extend(stream(3600..4500));
+        let (written, chapters) = record(inputs);
+
+        assert_monotonic(&written);
+        let first: Vec<&Written> = written.iter().filter(|item| item.file == 1).collect();
+        let second: Vec<&Written> = written.iter().filter(|item| item.file == 2).collect();
+        let third: Vec<&Written> = written.iter().filter(|item| item.file == 3).collect();
+        // 分割等到 2000 毫秒的关键帧，暂停中分割在恢复的关键帧处开始新文件
+        assert_eq!(first.iter().filter(|item| item.video).count(), 50);
+        assert_eq!(second.iter().filter(|item| item.video).count(), 25);
+        assert_eq!(third.iter().filter(|item| item.video).count(), 13);
+        assert_eq!(
+            second
+                .iter()
+                .rev()
+                .find(|item| item.video)
+                .unwrap()
+                .timestamp,
+            960
+        );
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].0, 2);
+        assert_eq!(chapters[0].1.timestamp, 0);
+        assert_eq!(chapters[0].1.name, "next");
+    }
+}
@@ -0,0 +1,815 @@
+/// MP4 封装模块
+///
+/// 编码包依次写入 `mdat`，结束时在文件末尾写入 `moov` 索引。音频为 `mp4a` 格式的 AAC，
+/// 每帧是一个样本，时间戳出现空隙时补静音帧，保证音频与视频对齐。
+/// 章节写入 `moov/udta/chpl`
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::{
+    media::{
+        aac::{silent_frame, AAC_FRAME_SAMPLES},
+        encoder::{AudioEncoderSettings, AvcHeader, EncodedPacket, PacketKind},
+    },
+    outputs::{
+        encoders::EncoderKey,
+        recording::muxer::{Chapter, Muxer},
+    },
+    Result,
+};
+
+/// 影片的时间单位（每秒）
+const MOVIE_TIMESCALE: u64 = 1000;
+
+/// 视频轨道的时间单位（每秒）
+const VIDEO_TIMESCALE: u64 = 90_000;
+
+/// 章节时间的单位（每秒）
+const CHAPTER_TIMESCALE: u64 = 10_000_000;
+
+/// `chpl` 最多支持的章节数
+const MAX_CHAPTERS: usize = 255;
+
+/// 音频时间戳与已写入长度的偏差超过该时长（纳秒）时补静音帧或丢弃
+const AUDIO_SYNC_TOLERANCE: u64 = 20_000_000;
+
+/// MPEG-4 音频的对象类型（`esds` 中的 objectTypeIndication）
+const OBJECT_TYPE_AUDIO: u8 = 0x40;
+
+/// 音频流类型，最低位保留为 1
+const STREAM_TYPE_AUDIO: u8 = (0x05 << 2) | 1;
+
+/// 单位矩阵
+const MATRIX: [u32; 9] = [0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000];
+
+/// 写入盒子，内容由 `fill` 写入
+fn put_box(buf: &mut Vec<u8>, kind: &[u8; 4], fill: impl FnOnce(&mut Vec<u8>)) {
+    let start = buf.len();
+    buf.extend_from_slice(&[0; 4]);
+    buf.extend_from_slice(kind);
+    fill(buf);
+    let size = (buf.len() - start) as u32;
+    buf[start..start + 4].copy_from_slice(&size.to_be_bytes());
+}
+
+/// 写入带版本和标志的盒子
+fn put_full_box(
+    buf: &mut Vec<u8>,
+    kind: &[u8; 4],
+    version: u8,
+    flags: u32,
+    fill: impl FnOnce(&mut Vec<u8>),
+) {
+    put_box(buf, kind, |buf| {
+        buf.extend_from_slice(&((version as u32) << 24 | flags).to_be_bytes());
+        fill(buf);
+    });
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u64(buf: &mut Vec<u8>, value: u64) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+/// 写入 `esds` 中的描述符，内容由 `fill` 写入，长度不超过 127 字节
+fn put_descriptor(buf: &mut Vec<u8>, tag: u8, fill: impl FnOnce(&mut Vec<u8>)) {
+    buf.push(tag);
+    let start = buf.len();
+    buf.push(0);
+    fill(buf);
+    buf[start] = (buf.len() - start - 1) as u8;
+}
+
+/// 按时间单位换算纳秒
+fn rescale(nanos: u64, timescale: u64) -> u64 {
+    (nanos as u128 * timescale as u128 / 1_000_000_000) as u64
+}
+
+/// 连续写入同一轨道的一组样本
+struct ChunkInfo {
+    offset: u64,
+    samples: u32,
+}
+
+/// 视频样本
+struct VideoSample {
+    /// 解码时间戳（视频时间单位）
+    dts: u64,
+    /// 显示时间戳相对解码时间戳的偏移（视频时间单位）
+    offset: u32,
+    size: u32,
+    keyframe: bool,
+}
+
+/// MP4 封装器
+pub struct Mp4Muxer {
+    file: BufWriter<File>,
+    /// 已写入的字节数，即当前位置
+    position: u64,
+    /// `mdat` 开始的位置
+    mdat_start: u64,
+    width: u32,
+    height: u32,
+    avc_config: Vec<u8>,
+    /// 一帧的时长（视频时间单位）
+    frame_duration: u64,
+    audio: AudioEncoderSettings,
+    video_samples: Vec<VideoSample>,
+    video_chunks: Vec<ChunkInfo>,
+    /// 各音频帧的大小
+    audio_samples: Vec<u32>,
+    audio_chunks: Vec<ChunkInfo>,
+    /// 上一个写入的包的类型，类型相同时合并到同一个块
+    last_kind: Option<PacketKind>,
+    chapters: Vec<Chapter>,
+}
+
+impl Mp4Muxer {
+    /// 创建文件并写入 `ftyp` 和 `mdat` 头
+    ///
+    /// # 参数
+    ///
+    /// * `path` - 文件路径
+    /// * `key` - 编码设置
+    /// * `header` - SPS/PPS
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，文件无法创建时返回错误
+    pub fn create(path: &Path, key: &EncoderKey, header: &AvcHeader) -> Result<Self> {
+        let video = &key.video;
+
+        let mut buf = vec![];
+        put_box(&mut buf, b"ftyp", |buf| {
+            buf.extend_from_slice(b"isom");
+            put_u32(buf, 0x200);
+            for brand in [b"isom", b"iso2", b"avc1", b"mp41"] {
+                buf.extend_from_slice(brand);
+            }
+        });
+        // `mdat` 使用 64 位大小，结束时补写
+        let mdat_start = buf.len() as u64;
+        put_u32(&mut buf, 1);
+        buf.extend_from_slice(b"mdat");
+        put_u64(&mut buf, 0);
+
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&buf)?;
+
+        Ok(Self {
+            file,
+            position: buf.len() as u64,
+            mdat_start,
+            width: video.width,
+            height: video.height,
+            avc_config: header.decoder_config(),
+            frame_duration: VIDEO_TIMESCALE * video.fps_den.max(1) as u64
+                / video.fps_num.max(1) as u64,
+            audio: key.audio,
+            video_samples: vec![],
+            video_chunks: vec![],
+            audio_samples: vec![],
+            audio_chunks: vec![],
+            last_kind: None,
+            chapters: vec![],
+        })
+    }
+
+    /// 写入媒体数据，与上一次写入同一轨道时合并到同一个块
+    fn write_media(&mut self, kind: PacketKind, data: &[u8], samples: u32) -> Result<()> {
+        let chunks = match kind {
+            PacketKind::Video => &mut self.video_chunks,
+            PacketKind::Audio => &mut self.audio_chunks,
+        };
+        match chunks.last_mut() {
+            Some(chunk) if self.last_kind == Some(kind) => chunk.samples += samples,
+            _ => chunks.push(ChunkInfo {
+                offset: self.position,
+                samples,
+            }),
+        }
+        self.last_kind = Some(kind);
+
+        self.file.write_all(data)?;
+        self.position += data.len() as u64;
+
+        Ok(())
+    }
+
+    /// 写入音频帧，时间戳晚于已写入的长度时先补静音帧，早于已写入的长度时丢弃
+    fn write_audio(&mut self, data: &[u8], timestamp: u64) -> Result<u64> {
+        let rate = self.audio.sample_rate.max(1) as u64;
+        let time = rescale(timestamp, rate);
+        let tolerance = rescale(AUDIO_SYNC_TOLERANCE, rate);
+        if time + tolerance < self.audio_frames() {
+            return Ok(0);
+        }
+
+        let mut written = 0;
+        if time > self.audio_frames() + tolerance {
+            let silence = silent_frame(self.audio.channels);
+            let frames = (time - self.audio_frames()) / AAC_FRAME_SAMPLES as u64;
+            for _ in 0..frames {
+                self.write_media(PacketKind::Audio, &silence, 1)?;
+                self.audio_samples.push(silence.len() as u32);
+                written += silence.len() as u64;
+            }
+        }
+        self.write_media(PacketKind::Audio, data, 1)?;
+        self.audio_samples.push(data.len() as u32);
+        written += data.len() as u64;
+
+        Ok(written)
+    }
+
+    /// 已写入的音频采样帧数
+    fn audio_frames(&self) -> u64 {
+        self.audio_samples.len() as u64 * AAC_FRAME_SAMPLES as u64
+    }
+
+    /// 视频轨道的时长（视频时间单位）
+    fn video_duration(&self) -> u64 {
+        self.video_samples
+            .last()
+            .map_or(0, |sample| sample.dts + self.frame_duration)
+    }
+
+    /// 生成 `moov`
+    fn moov(&self) -> Vec<u8> {
+        let video_duration = self.video_duration();
+        let audio_rate = self.audio.sample_rate.max(1) as u64;
+        let movie_duration = (video_duration * MOVIE_TIMESCALE / VIDEO_TIMESCALE)
+            .max(self.audio_frames() * MOVIE_TIMESCALE / audio_rate);
+
+        let mut buf = vec![];
+        put_box(&mut buf, b"moov", |buf| {
+            put_full_box(buf, b"mvhd", 1, 0, |buf| {
+                put_u64(buf, 0);
+                put_u64(buf, 0);
+                put_u32(buf, MOVIE_TIMESCALE as u32);
+                put_u64(buf, movie_duration);
+                put_u32(buf, 0x0001_0000);
+                put_u16(buf, 0x0100);
+                buf.extend_from_slice(&[0; 10]);
+                MATRIX.iter().for_each(|value| put_u32(buf, *value));
+                buf.extend_from_slice(&[0; 24]);
+                put_u32(buf, 3);
+            });
+            self.put_video_track(buf, video_duration);
+            self.put_audio_track(buf);
+            if !self.chapters.is_empty() {
+                put_box(buf, b"udta", |buf| self.put_chapters(buf));
+            }
+        });
+
+        buf
+    }
+
+    /// 写入 `tkhd`
+    fn put_track_header(buf: &mut Vec<u8>, id: u32, duration: u64, audio: bool, size: (u32, u32)) {
+        put_full_box(buf, b"tkhd", 1, 0x3, |buf| {
+            put_u64(buf, 0);
+            put_u64(buf, 0);
+            put_u32(buf, id);
+            put_u32(buf, 0);
+            put_u64(buf, duration);
+            buf.extend_from_slice(&[0; 8]);
+            put_u16(buf, 0);
+            put_u16(buf, 0);
+            put_u16(buf, if audio { 0x0100 } else { 0 });
+            put_u16(buf, 0);
+            MATRIX.iter().for_each(|value| put_u32(buf, *value));
+            put_u32(buf, size.0 << 16);
+            put_u32(buf, size.1 << 16);
+        });
+    }
+
+    /// 写入 `mdhd` 和 `hdlr`
+    fn put_media_header(buf: &mut Vec<u8>, timescale: u64, duration: u64, handler: &[u8; 4]) {
+        put_full_box(buf, b"mdhd", 1, 0, |buf| {
+            put_u64(buf, 0);
+            put_u64(buf, 0);
+            put_u32(buf, timescale as u32);
+            put_u64(buf, duration);
+            // 语言 `und`
+            put_u16(buf, 0x55c4);
+            put_u16(buf, 0);
+        });
+        put_full_box(buf, b"hdlr", 0, 0, |buf| {
+            put_u32(buf, 0);
+            buf.extend_from_slice(handler);
+            buf.extend_from_slice(&[0; 12]);
+            let name: &[u8] = if handler == b"vide" {
+                b"VideoHandler\0"
+            } else {
+                b"SoundHandler\0"
+            };
+            buf.extend_from_slice(name);
+        });
+    }
+
+    /// 写入 `dinf`
+    fn put_data_info(buf: &mut Vec<u8>) {
+        put_box(buf, b"dinf", |buf| {
+            put_full_box(buf, b"dref", 0, 0, |buf| {
+                put_u32(buf, 1);
+                put_full_box(buf, b"url ", 0, 1, |_| {});
+            });
+        });
+    }
+
+    /// 写入 `stsc` 和 `co64`
+    fn put_chunks(buf: &mut Vec<u8>, chunks: &[ChunkInfo]) {
+        let mut entries: Vec<(u32, u32)> = vec![];
+        for (i, chunk) in chunks.iter().enumerate() {
+            if entries
+                .last()
+                .is_none_or(|(_, samples)| *samples != chunk.samples)
+            {
+                entries.push((i as u32 + 1, chunk.samples));
+            }
+        }
+        put_full_box(buf, b"stsc", 0, 0, |buf| {
+            put_u32(buf, entries.len() as u32);
+            for (first, samples) in entries {
+                put_u32(buf, first);
+                put_u32(buf, samples);
+                put_u32(buf, 1);
+            }
+        });
+        put_full_box(buf, b"co64", 0, 0, |buf| {
+            put_u32(buf, chunks.len() as u32);
+            chunks.iter().for_each(|chunk| put_u64(buf, chunk.offset));
+        });
+    }
+
+    fn put_video_track(&self, buf: &mut Vec<u8>, duration: u64) {
+        let samples = &self.video_samples;
+        put_box(buf, b"trak", |buf| {
+            Self::put_track_header(
+                buf,
+                1,
+                duration * MOVIE_TIMESCALE / VIDEO_TIMESCALE,
+                false,
+                (self.width, self.height),
+            );
+            put_box(buf, b"mdia", |buf| {
+                Self::put_media_header(buf, VIDEO_TIMESCALE, duration, b"vide");
+                put_box(buf, b"minf", |buf| {
+                    put_full_box(buf, b"vmhd", 0, 1, |buf| buf.extend_from_slice(&[0; 8]));
+                    Self::put_data_info(buf);
+                    put_box(buf, b"stbl", |buf| {
+                        put_full_box(buf, b"stsd", 0, 0, |buf| {
+                            put_u32(buf, 1);
+                            put_box(buf, b"avc1", |buf| {
+                                buf.extend_from_slice(&[0; 6]);
+                                put_u16(buf, 1);
+                                buf.extend_from_slice(&[0; 16]);
+                                put_u16(buf, self.width as u16);
+                                put_u16(buf, self.height as u16);
+                                put_u32(buf, 0x0048_0000);
+                                put_u32(buf, 0x0048_0000);
+                                put_u32(buf, 0);
+                                put_u16(buf, 1);
+                                buf.extend_from_slice(&[0; 32]);
+                                put_u16(buf, 0x18);
+                                put_u16(buf, 0xffff);
+                                put_box(buf, b"avcC", |buf| {
+                                    buf.extend_from_slice(&self.avc_config)
+                                });
+                            });
+                        });
+
+                        // 解码时间间隔，最后一帧使用帧时长
+                        let mut deltas: Vec<(u32, u32)> = vec![];
+                        for (i, sample) in samples.iter().enumerate() {
+                            let delta = samples
+                                .get(i + 1)
+                                .map_or(self.frame_duration, |next| next.dts - sample.dts)
+                                as u32;
+                            match deltas.last_mut() {
+                                Some((count, last)) if *last == delta => *count += 1,
+                                _ => deltas.push((1, delta)),
+                            }
+                        }
+                        put_full_box(buf, b"stts", 0, 0, |buf| {
+                            put_u32(buf, deltas.len() as u32);
+                            for (count, delta) in deltas {
+                                put_u32(buf, count);
+                                put_u32(buf, delta);
+                            }
+                        });
+
+                        if samples.iter().any(|sample| sample.offset != 0) {
+                            let mut offsets: Vec<(u32, u32)> = vec![];
+                            for sample in samples {
+                                match offsets.last_mut() {
+                                    Some((count, last)) if *last == sample.offset => *count += 1,
+                                    _ => offsets.push((1, sample.offset)),
+                                }
+                            }
+                            put_full_box(buf, b"ctts", 0, 0, |buf| {
+                                put_u32(buf, offsets.len() as u32);
+                                for (count, offset) in offsets {
+                                    put_u32(buf, count);
+                                    put_u32(buf, offset);
+                                }
+                            });
+                        }
+
+                        let keyframes: Vec<u32> = samples
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, sample)| sample.keyframe)
+                            .map(|(i, _)| i as u32 + 1)
+                            .collect();
+                        put_full_box(buf, b"stss", 0, 0, |buf| {
+                            put_u32(buf, keyframes.len() as u32);
+                            keyframes.iter().for_each(|number| put_u32(buf, *number));
+                        });
+
+                        Self::put_chunks(buf, &self.video_chunks);
+                        put_full_box(buf, b"stsz", 0, 0, |buf| {
+                            put_u32(buf, 0);
+                            put_u32(buf, samples.len() as u32);
+                            samples.iter().for_each(|sample| put_u32(buf, sample.size));
+                        });
+                    });
+                });
+            });
+        });
+    }
+
+    fn put_audio_track(&self, buf: &mut Vec<u8>) {
+        let rate = self.audio.sample_rate.max(1) as u64;
+        let channels = self.audio.channels.max(1);
+        let frames = self.audio_frames();
+        put_box(buf, b"trak", |buf| {
+            Self::put_track_header(buf, 2, frames * MOVIE_TIMESCALE / rate, true, (0, 0));
+            put_box(buf, b"mdia", |buf| {
+                Self::put_media_header(buf, rate, frames, b"soun");
+                put_box(buf, b"minf", |buf| {
+                    put_full_box(buf, b"smhd", 0, 0, |buf| put_u32(buf, 0));
+                    Self::put_data_info(buf);
+                    put_box(buf, b"stbl", |buf| {
+                        put_full_box(buf, b"stsd", 0, 0, |buf| {
+                            put_u32(buf, 1);
+                            put_box(buf, b"mp4a", |buf| {
+                                buf.extend_from_slice(&[0; 6]);
+                                put_u16(buf, 1);
+                                buf.extend_from_slice(&[0; 8]);
+                                put_u16(buf, channels);
+                                put_u16(buf, 16);
+                                put_u32(buf, 0);
+                                put_u32(buf, (rate as u32) << 16);
+                                put_full_box(buf, b"esds", 0, 0, |buf| self.put_es_descriptor(buf));
+                            });
+                        });
+                        put_full_box(buf, b"stts", 0, 0, |buf| {
+                            put_u32(buf, 1);
+                            put_u32(buf, self.audio_samples.len() as u32);
+                            put_u32(buf, AAC_FRAME_SAMPLES as u32);
+                        });
+                        Self::put_chunks(buf, &self.audio_chunks);
+                        put_full_box(buf, b"stsz", 0, 0, |buf| {
+                            put_u32(buf, 0);
+                            put_u32(buf, self.audio_samples.len() as u32);
+                            self.audio_samples
+                                .iter()
+                                .for_each(|size| put_u32(buf, *size));
+                        });
+                    });
+                });
+            });
+        });
+    }
+
+    /// 写入 `esds` 的 ES 描述符，携带 AudioSpecificConfig
+    fn put_es_descriptor(&self, buf: &mut Vec<u8>) {
+        let bitrate = self.audio.bitrate * 1000;
+        put_descriptor(buf, 0x03, |buf| {
+            put_u16(buf, 2);
+            buf.push(0);
+            put_descriptor(buf, 0x04, |buf| {
+                buf.push(OBJECT_TYPE_AUDIO);
+                buf.push(STREAM_TYPE_AUDIO);
+                // 缓冲区大小 24 位
+                buf.extend_from_slice(&[0; 3]);
+                put_u32(buf, bitrate);
+                put_u32(buf, bitrate);
+                put_descriptor(buf, 0x05, |buf| {
+                    buf.extend_from_slice(&self.audio.decoder_config())
+                });
+            });
+            put_descriptor(buf, 0x06, |buf| buf.push(0x02));
+        });
+    }
+
+    /// 写入 Nero 格式的章节 `chpl`
+    fn put_chapters(&self, buf: &mut Vec<u8>) {
+        let chapters = &self.chapters[..self.chapters.len().min(MAX_CHAPTERS)];
+        put_full_box(buf, b"chpl", 1, 0, |buf| {
+            put_u32(buf, 0);
+            buf.push(chapters.len() as u8);
+            for chapter in chapters {
+                put_u64(buf, rescale(chapter.timestamp, CHAPTER_TIMESCALE));
+                // 名称最长 255 字节，按字符边界截断
+                let mut end = chapter.name.len().min(255);
+                while !chapter.name.is_char_boundary(end) {
+                    end -= 1;
+                }
+                buf.push(end as u8);
+                buf.extend_from_slice(&chapter.name.as_bytes()[..end]);
+            }
+        });
+    }
+}
+
+impl Muxer for Mp4Muxer {
+    fn write_packet(&mut self, packet: &EncodedPacket, timestamp: u64) -> Result<u64> {
+        match packet.kind {
+            PacketKind::Video => {
+                let dts = rescale(timestamp, VIDEO_TIMESCALE);
+                // 解码时间戳必须递增
+                if self
+                    .video_samples
+                    .last()
+                    .is_some_and(|last| dts <= last.dts)
+                {
+                    return Ok(0);
+                }
+                self.write_media(PacketKind::Video, &packet.data, 1)?;
+                self.video_samples.push(VideoSample {
+                    dts,
+                    offset: rescale(packet.pts.saturating_sub(packet.dts), VIDEO_TIMESCALE) as u32,
+                    size: packet.data.len() as u32,
+                    keyframe: packet.keyframe,
+                });
+                Ok(packet.data.len() as u64)
+            }
+            PacketKind::Audio => self.write_audio(&packet.data, timestamp),
+        }
+    }
+
+    fn add_chapter(&mut self, chapter: Chapter) {
+        self.chapters.push(chapter);
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        let mdat_size = self.position - self.mdat_start;
+        let moov = self.moov();
+        self.file.write_all(&moov)?;
+        self.file.seek(SeekFrom::Start(self.mdat_start + 8))?;
+        self.file.write_all(&mdat_size.to_be_bytes())?;
+        self.file.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::BufReader, sync::Arc};
+
+    use super::*;
+    use crate::{
+        media::encoder::{EncoderPreset, FramePriority, VideoEncoderSettings},
+        outputs::recording::Timeline,
+        protocols::flv::flv_audio_settings,
+    };
+
+    const MS: u64 = 1_000_000;
+
+    /// 视频帧间隔（毫秒）
+    const FRAME_MS: u64 = 40;
+
+    fn key() -> EncoderKey {
+        EncoderKey {
+            canvas: None,
+            video: VideoEncoderSettings {
+                width: 64,
+                height: 64,
+                fps_num: (1000 / FRAME_MS) as u32,
+                fps_den: 1,
+                bitrate: 1000,
+                keyint_sec: 1,
+                preset: EncoderPreset::default(),
+            },
+            audio: flv_audio_settings(2),
+        }
+    }
+
+    fn header() -> AvcHeader {
+        AvcHeader {
+            sps: vec![0x67, 0x42, 0xc0, 0x1f],
+            pps: vec![0x68, 0xce, 0x3c, 0x80],
+        }
+    }
+
+    /// 编码时间戳为 `dts` 的包，非关键帧的显示时间戳晚一帧
+    fn packet(kind: PacketKind, dts: u64, keyframe: bool) -> EncodedPacket {
+        let offset = if kind == PacketKind::Video && !keyframe {
+            FRAME_MS * MS
+        } else {
+            0
+        };
+        EncodedPacket {
+            kind,
+            pts: dts + offset,
+            dts,
+            keyframe,
+            priority: FramePriority::Inter,
+            header: None,
+            data: vec![dts as u8; 8],
+        }
+    }
+
+    /// 按编码时间戳交错的音视频包，每秒一个关键帧
+    fn stream(range: std::ops::Range<u64>) -> Vec<EncodedPacket> {
+        let rate = flv_audio_settings(2).sample_rate as u64;
+        let mut packets: Vec<EncodedPacket> = (range.start / FRAME_MS..range.end / FRAME_MS)
+            .map(|i| i * FRAME_MS)
+            .map(|ms| packet(PacketKind::Video, ms * MS, ms % 1000 == 0))
+            .collect();
+        let first = (range.start * MS * rate).div_ceil(1_000_000_000 * AAC_FRAME_SAMPLES as u64);
+        packets.extend(
+            (first..)
+                .map(|i| i * AAC_FRAME_SAMPLES as u64 * 1_000_000_000 / rate)
+                .take_while(|dts| *dts < range.end * MS)
+                .map(|dts| packet(PacketKind::Audio, dts, true)),
+        );
+        packets.sort_by_key(|packet| packet.dts);
+        packets
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("mp4-{}-{}.mp4", std::process::id(), name))
+    }
+
+    /// 在盒子内容中查找子盒子，返回其内容
+    fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+        let mut rest = data;
+        while rest.len() >= 8 {
+            let mut size = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let mut header = 8;
+            if size == 1 {
+                size = u64::from_be_bytes(rest[8..16].try_into().unwrap()) as usize;
+                header = 16;
+            }
+            if &rest[4..8] == kind {
+                return Some(&rest[header..size]);
+            }
+            rest = &rest[size..];
+        }
+        None
+    }
+
+    #[test]
+    fn sample_tables_stay_continuous_across_pause_and_resume() {
+        let path = temp_path("pause");
+        let mut muxer = Box::new(Mp4Muxer::create(&path, &key(), &header()).unwrap());
+
+        // 暂停 2 秒到 3 秒，在 3 秒的关键帧处恢复
+        let mut timeline = Timeline::default();
+        timeline.start(0);
+        let mut audio = 0;
+        for packet in stream(0..4000) {
+            if packet.kind == PacketKind::Video && packet.dts == 2000 * MS {
+                timeline.pause(packet.dts);
+            }
+            if packet.kind == PacketKind::Video && packet.dts == 3000 * MS {
+                assert!(timeline.resume(packet.dts));
+            }
+            if let Some(timestamp) = timeline.timestamp(packet.dts) {
+                if muxer.write_packet(&packet, timestamp).unwrap() > 0
+                    && packet.kind == PacketKind::Audio
+                {
+                    audio += 1;
+                }
+            }
+        }
+        muxer.finish().unwrap();
+
+        let file = File::open(&path).unwrap();
+        let size = file.metadata().unwrap().len();
+        let reader = ::mp4::Mp4Reader::read_header(BufReader::new(file), size).unwrap();
+        let video = &reader.tracks()[&1].trak.mdia;
+        assert_eq!(video.mdhd.timescale, VIDEO_TIMESCALE as u32);
+        let stbl = &video.minf.stbl;
+        // 恢复后的解码时间接在暂停前之后，只有一种间隔
+        let stts: Vec<(u32, u32)> = stbl
+            .stts
+            .entries
+            .iter()
+            .map(|entry| (entry.sample_count, entry.sample_delta))
+            .collect();
+        assert_eq!(stts, [(75, 3600)]);
+        assert_eq!(video.mdhd.duration, 75 * 3600);
+        // 显示时间偏移随包保留，关键帧为 0
+        let ctts: Vec<(u32, i32)> = stbl
+            .ctts
+            .as_ref()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|entry| (entry.sample_count, entry.sample_offset))
+            .collect();
+        let mut expected = vec![];
+        for _ in 0..3 {
+            expected.extend([(1, 0), (24, 3600)]);
+        }
+        assert_eq!(ctts, expected);
+        assert_eq!(
+            stbl.stss.as_ref().unwrap().entries,
+            [1, 26, 51],
+            "keyframes at 0, 1 and 2 seconds in the file"
+        );
+
+        let audio_stbl = &reader.tracks()[&2].trak.mdia.minf.stbl;
+        let stts: Vec<(u32, u32)> = audio_stbl
+            .stts
+            .entries
+            .iter()
+            .map(|entry| (entry.sample_count, entry.sample_delta))
+            .collect();
+        assert_eq!(stts, [(audio, AAC_FRAME_SAMPLES as u32)]);
+        // 3 秒的音频
+        let seconds = audio as f64 * AAC_FRAME_SAMPLES as f64 / key().audio.sample_rate as f64;
+        assert!((seconds - 3.).abs() < 0.05, "{seconds}");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn audio_gaps_are_filled_with_silence() {
+        let path = temp_path("gap");
+        let mut muxer = Box::new(Mp4Muxer::create(&path, &key(), &header()).unwrap());
+        let mut packets = stream(0..1000);
+        packets.extend(stream(1500..2000));
+        for packet in &packets {
+            muxer.write_packet(packet, packet.dts).unwrap();
+        }
+        muxer.finish().unwrap();
+
+        let file = File::open(&path).unwrap();
+        let size = file.metadata().unwrap().len();
+        let reader = ::mp4::Mp4Reader::read_header(BufReader::new(file), size).unwrap();
+        let audio = &reader.tracks()[&2].trak.mdia;
+        let frames = audio.minf.stbl.stsz.sample_count as u64 * AAC_FRAME_SAMPLES as u64;
+        assert_eq!(audio.mdhd.duration, frames);
+        let seconds = frames as f64 / key().audio.sample_rate as f64;
+        assert!((seconds - 2.).abs() < 0.05, "{seconds}");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn chapters_are_written_to_chpl() {
+        let path = temp_path("chapters");
+        let mut muxer = Box::new(Mp4Muxer::create(&path, &key(), &header()).unwrap());
+        for packet in stream(0..2000) {
+            muxer.write_packet(&packet, packet.dts).unwrap();
+        }
+        muxer.add_chapter(Chapter {
+            timestamp: 0,
+            name: "Start".to_string(),
+        });
+        // 超过 255 字节的名称在字符边界截断
+        muxer.add_chapter(Chapter {
+            timestamp: 1500 * MS,
+            name: "章".repeat(100),
+        });
+        muxer.finish().unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        let moov = child(&data, b"moov").unwrap();
+        let chpl = child(child(moov, b"udta").unwrap(), b"chpl").unwrap();
+        // 版本 1，4 字节保留字段后是章节数
+        assert_eq!(chpl[0], 1);
+        assert_eq!(chpl[8], 2);
+        let mut rest = &chpl[9..];
+        let mut chapters = vec![];
+        for _ in 0..2 {
+            let time = u64::from_be_bytes(rest[..8].try_into().unwrap());
+            let len = rest[8] as usize;
+            let name = std::str::from_utf8(&rest[9..9 + len]).unwrap().to_string();
+            chapters.push((time, name));
+            rest = &rest[9 + len..];
+        }
+        assert!(rest.is_empty());
+        assert_eq!(chapters[0], (0, "Start".to_string()));
+        assert_eq!(chapters[1], (15_000_000, "章".repeat(85)));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,98 @@
+/// 录制文件封装模块
+///
+/// 把共享编码器输出的 H.264 和 AAC 包写入 MKV 或 MP4 文件，章节标记随文件一起写入
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    media::encoder::{AvcHeader, EncodedPacket},
+    outputs::{
+        encoders::EncoderKey,
+        recording::{mkv::MkvMuxer, mp4::Mp4Muxer},
+    },
+    Result,
+};
+
+/// 录制文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingFormat {
+    /// Matroska，异常退出时已写入的内容仍可播放
+    #[default]
+    Mkv,
+    /// MP4，结束录制时写入索引
+    Mp4,
+}
+
+impl RecordingFormat {
+    /// 按配置文件中的名称解析格式，混合 MP4 和分段 MP4 按 MP4 写入
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mkv" => Some(Self::Mkv),
+            "mp4" | "hybrid_mp4" | "fragmented_mp4" => Some(Self::Mp4),
+            _ => None,
+        }
+    }
+
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Mkv => "mkv",
+            Self::Mp4 => "mp4",
+        }
+    }
+}
+
+/// 录制文件的章节标记
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Chapter {
+    /// 在文件中的时间（纳秒）
+    pub timestamp: u64,
+    pub name: String,
+}
+
+/// 录制文件的封装器
+pub trait Muxer: Send {
+    /// 写入一个编码包
+    ///
+    /// # 参数
+    ///
+    /// * `packet` - 编码包
+    /// * `timestamp` - 在文件中的解码时间戳（纳秒），显示时间戳按包的偏移换算
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<u64>`，表示写入的字节数
+    fn write_packet(&mut self, packet: &EncodedPacket, timestamp: u64) -> Result<u64>;
+
+    /// 添加章节标记，结束文件时写入
+    fn add_chapter(&mut self, chapter: Chapter);
+
+    /// 写入索引和章节，结束文件
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// 创建录制文件并写入文件头
+///
+/// # 参数
+///
+/// * `format` - 文件格式
+/// * `path` - 文件路径
+/// * `key` - 编码设置
+/// * `header` - 第一个关键帧携带的 SPS/PPS
+///
+/// # 返回值
+///
+/// 返回 `Result<Box<dyn Muxer>>`，文件无法创建时返回错误
+pub fn create_muxer(
+    format: RecordingFormat,
+    path: &Path,
+    key: &EncoderKey,
+    header: &AvcHeader,
+) -> Result<Box<dyn Muxer>> {
+    Ok(match format {
+        RecordingFormat::Mkv => Box::new(MkvMuxer::create(path, key, header)?),
+        RecordingFormat::Mp4 => Box::new(Mp4Muxer::create(path, key, header)?),
+    })
+}
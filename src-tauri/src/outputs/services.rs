@@ -0,0 +1,584 @@
+/// 推流服务模块
+///
+/// 服务目录打包在 `resources/services.json` 中，列出各平台的推流服务器、码率上限、
+/// 关键帧间隔和支持的编码格式，用于检查推流设置和生成推荐设置
+use std::{fs, sync::OnceLock};
+
+use anyhow::anyhow;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    outputs::{encoders::EncoderKey, streaming::StreamDestination},
+    Result,
+};
+
+/// 服务目录的格式版本
+const FORMAT_VERSION: u32 = 1;
+
+/// 推流使用的视频编码格式
+const VIDEO_CODEC: &str = "h264";
+
+/// 推流使用的音频编码格式
+const AUDIO_CODEC: &str = "aac";
+
+/// 推流服务器
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamServer {
+    pub name: String,
+    pub url: String,
+}
+
+/// 服务的推荐设置，未指定的项没有限制
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecommendedSettings {
+    /// 关键帧间隔（秒）
+    #[serde(default)]
+    pub keyint: Option<u32>,
+    /// 视频码率上限（kbps）
+    #[serde(default)]
+    pub max_video_bitrate: Option<u32>,
+    /// 音频码率上限（kbps）
+    #[serde(default)]
+    pub max_audio_bitrate: Option<u32>,
+    /// 帧率上限
+    #[serde(default)]
+    pub max_fps: Option<u32>,
+    /// 支持的分辨率，如 `1920x1080`，为空时不限制
+    #[serde(default)]
+    pub supported_resolutions: Vec<String>,
+}
+
+/// 推流服务
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamService {
+    pub name: String,
+    /// 是否为常用服务
+    #[serde(default)]
+    pub common: bool,
+    pub servers: Vec<StreamServer>,
+    #[serde(default)]
+    pub recommended: RecommendedSettings,
+    /// 支持的视频编码格式，为空时不限制
+    #[serde(default)]
+    pub supported_video_codecs: Vec<String>,
+    /// 支持的音频编码格式，为空时不限制
+    #[serde(default)]
+    pub supported_audio_codecs: Vec<String>,
+}
+
+/// 服务目录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceCatalog {
+    pub format_version: u32,
+    pub services: Vec<StreamService>,
+}
+
+/// 全局服务目录，启动时从资源文件加载
+static SERVICES: OnceLock<ServiceCatalog> = OnceLock::new();
+
+/// 比较服务器地址时忽略末尾的 `/`
+fn same_server(a: &str, b: &str) -> bool {
+    a.trim().trim_end_matches('/') == b.trim().trim_end_matches('/')
+}
+
+/// 解析 `1920x1080` 格式的分辨率
+fn parse_resolution(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.split_once('x')?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+impl ServiceCatalog {
+    /// 解析服务目录
+    ///
+    /// # 参数
+    ///
+    /// * `json` - 服务目录内容
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，格式无效、版本不支持或服务名称重复时返回错误
+    pub fn parse(json: &str) -> Result<Self> {
+        let catalog: Self = serde_json::from_str(json)?;
+        if catalog.format_version != FORMAT_VERSION {
+            return Err(anyhow!(
+                "unsupported services format version: {}",
+                catalog.format_version
+            ));
+        }
+        for (i, service) in catalog.services.iter().enumerate() {
+            if catalog.services[..i]
+                .iter()
+                .any(|other| other.name == service.name)
+            {
+                return Err(anyhow!("duplicate service: {}", service.name));
+            }
+            if service.servers.is_empty() {
+                return Err(anyhow!("service has no server: {}", service.name));
+            }
+            for resolution in &service.recommended.supported_resolutions {
+                if parse_resolution(resolution).is_none() {
+                    return Err(anyhow!(
+                        "invalid resolution of service {}: {}",
+                        service.name,
+                        resolution
+                    ));
+                }
+            }
+        }
+
+        Ok(catalog)
+    }
+
+    /// 按名称查找服务
+    pub fn service(&self, name: &str) -> Option<&StreamService> {
+        self.services.iter().find(|service| service.name == name)
+    }
+}
+
+impl StreamService {
+    /// 支持的分辨率列表，为空时不限制
+    pub fn resolutions(&self) -> Vec<(u32, u32)> {
+        self.recommended
+            .supported_resolutions
+            .iter()
+            .filter_map(|resolution| parse_resolution(resolution))
+            .collect()
+    }
+
+    /// 检查推流服务器和编码设置是否符合服务的要求
+    ///
+    /// # 参数
+    ///
+    /// * `server` - 推流服务器地址
+    /// * `key` - 编码设置
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，服务器不在列表中或编码设置超出限制时返回错误
+    pub fn validate(&self, server: &str, key: &EncoderKey) -> Result<()> {
+        if !self
+            .servers
+            .iter()
+            .any(|item| same_server(&item.url, server))
+        {
+            return Err(anyhow!("server not listed by {}: {}", self.name, server));
+        }
+
+        let video = &key.video;
+        let recommended = &self.recommended;
+        if let Some(max) = recommended.max_video_bitrate {
+            if video.bitrate > max {
+                return Err(anyhow!(
+                    "video bitrate {} kbps exceeds the {} kbps limit of {}",
+                    video.bitrate,
+                    max,
+                    self.name
+                ));
+            }
+        }
+        if let Some(keyint) = recommended.keyint {
+            if video.keyint_sec > keyint {
+                return Err(anyhow!(
+                    "keyframe interval {}s exceeds the {}s limit of {}",
+                    video.keyint_sec,
+                    keyint,
+                    self.name
+                ));
+            }
+        }
+        if let Some(max) = recommended.max_fps {
+            if video.fps_num > max * video.fps_den.max(1) {
+                return Err(anyhow!(
+                    "frame rate {}/{} exceeds the {} fps limit of {}",
+                    video.fps_num,
+                    video.fps_den,
+                    max,
+                    self.name
+                ));
+            }
+        }
+        if let Some(max) = recommended.max_audio_bitrate {
+            if key.audio.bitrate > max {
+                return Err(anyhow!(
+                    "audio bitrate {} kbps exceeds the {} kbps limit of {}",
+                    key.audio.bitrate,
+                    max,
+                    self.name
+                ));
+            }
+        }
+        let resolutions = self.resolutions();
+        if !resolutions.is_empty() && !resolutions.contains(&(video.width, video.height)) {
+            return Err(anyhow!(
+                "resolution {}x{} not supported by {}",
+                video.width,
+                video.height,
+                self.name
+            ));
+        }
+        if !self.supported_video_codecs.is_empty()
+            && !self
+                .supported_video_codecs
+                .iter()
+                .any(|codec| codec == VIDEO_CODEC)
+        {
+            return Err(anyhow!("{} does not support {}", self.name, VIDEO_CODEC));
+        }
+        if !self.supported_audio_codecs.is_empty()
+            && !self
+                .supported_audio_codecs
+                .iter()
+                .any(|codec| codec == AUDIO_CODEC)
+        {
+            return Err(anyhow!("{} does not support {}", self.name, AUDIO_CODEC));
+        }
+
+        Ok(())
+    }
+
+    /// 按服务的推荐设置调整推流目标，帧率由画布决定，不做调整
+    ///
+    /// # 参数
+    ///
+    /// * `destination` - 推流目标
+    /// * `output` - 画布的输出分辨率
+    pub fn apply_recommended(&self, destination: &mut StreamDestination, output: (u32, u32)) {
+        if !self
+            .servers
+            .iter()
+            .any(|item| same_server(&item.url, &destination.server))
+        {
+            destination.server = self.servers[0].url.clone();
+        }
+
+        let recommended = &self.recommended;
+        if let Some(keyint) = recommended.keyint {
+            destination.keyint_sec = keyint;
+        }
+        if let Some(max) = recommended.max_video_bitrate {
+            destination.bitrate = destination.bitrate.min(max);
+        }
+
+        // 当前分辨率不受支持时，选择不超过画布输出的最大分辨率
+        let resolutions = self.resolutions();
+        let current = destination.resolution.unwrap_or(output);
+        if !resolutions.is_empty() && !resolutions.contains(&current) {
+            let area = |(width, height): (u32, u32)| width as u64 * height as u64;
+            destination.resolution = resolutions
+                .iter()
+                .filter(|resolution| area(**resolution) <= area(output))
+                .max_by_key(|resolution| area(**resolution))
+                .or_else(|| {
+                    resolutions
+                        .iter()
+                        .min_by_key(|resolution| area(**resolution))
+                })
+                .copied();
+        }
+    }
+}
+
+/// 从资源文件加载服务目录，加载失败时服务列表为空
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，资源路径无法解析时返回错误
+pub fn load_services(app: &AppHandle) -> Result<()> {
+    let path = app.path().resolve(
+        "resources/services.json",
+        tauri::path::BaseDirectory::Resource,
+    )?;
+
+    match fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| ServiceCatalog::parse(&json))
+    {
+        Ok(catalog) => {
+            info!("loaded {} stream services", catalog.services.len());
+            let _ = SERVICES.set(catalog);
+        }
+        Err(e) => error!("failed to load {}: {}", path.display(), e),
+    }
+
+    Ok(())
+}
+
+/// 所有推流服务，服务目录未加载时为空
+pub fn services() -> &'static [StreamService] {
+    SERVICES
+        .get()
+        .map_or(&[], |catalog| catalog.services.as_slice())
+}
+
+/// 按名称查找推流服务
+///
+/// # 参数
+///
+/// * `name` - 服务名称
+///
+/// # 返回值
+///
+/// 返回 `Result<&StreamService>`，服务不存在时返回错误
+pub fn find_service(name: &str) -> Result<&'static StreamService> {
+    SERVICES
+        .get()
+        .and_then(|catalog| catalog.service(name))
+        .ok_or(anyhow!("stream service not found: {}", name))
+}
+
+/// 推流目标使用服务推荐设置后的结果，未选择服务时原样返回
+///
+/// # 参数
+///
+/// * `destination` - 推流目标
+///
+/// # 返回值
+///
+/// 返回 `Result<StreamDestination>`，服务不存在或画布设置无效时返回错误
+pub fn recommended_settings(destination: &StreamDestination) -> Result<StreamDestination> {
+    let mut destination = destination.clone();
+    let Some(name) = destination.service.clone() else {
+        return Ok(destination);
+    };
+    let service = find_service(&name)?;
+
+    let output = StreamDestination {
+        resolution: None,
+        ..destination.clone()
+    }
+    .encoder_key()?
+    .video;
+    service.apply_recommended(&mut destination, (output.width, output.height));
+
+    Ok(destination)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::media::encoder::{AudioEncoderSettings, EncoderPreset, VideoEncoderSettings};
+
+    /// 限制了所有设置的服务
+    fn service() -> StreamService {
+        StreamService {
+            name: "Test".to_string(),
+            common: false,
+            servers: vec![
+                StreamServer {
+                    name: "Primary".to_string(),
+                    url: "rtmp://primary.example.com/app".to_string(),
+                },
+                StreamServer {
+                    name: "Backup".to_string(),
+                    url: "rtmp://backup.example.com/app/".to_string(),
+                },
+            ],
+            recommended: RecommendedSettings {
+                keyint: Some(2),
+                max_video_bitrate: Some(6000),
+                max_audio_bitrate: Some(160),
+                max_fps: Some(30),
+                supported_resolutions: vec!["1920x1080".to_string(), "1280x720".to_string()],
+            },
+            supported_video_codecs: vec![VIDEO_CODEC.to_string()],
+            supported_audio_codecs: vec![AUDIO_CODEC.to_string()],
+        }
+    }
+
+    /// 符合 `service()` 所有限制的编码设置
+    fn key() -> EncoderKey {
+        EncoderKey {
+            canvas: None,
+            video: VideoEncoderSettings {
+                width: 1280,
+                height: 720,
+                fps_num: 30000,
+                fps_den: 1001,
+                bitrate: 6000,
+                keyint_sec: 2,
+                preset: EncoderPreset::default(),
+            },
+            audio: AudioEncoderSettings {
+                sample_rate: 48000,
+                channels: 2,
+                bitrate: 160,
+            },
+        }
+    }
+
+    fn stream_destination(server: &str) -> StreamDestination {
+        serde_json::from_value(json!({
+            "id": "test",
+            "name": "Test",
+            "server": server,
+            "key": "secret",
+            "service": "Test",
+            "bitrate": 8000,
+            "keyint_sec": 4,
+        }))
+        .unwrap()
+    }
+
+    fn catalog(services: serde_json::Value) -> String {
+        json!({ "format_version": FORMAT_VERSION, "services": services }).to_string()
+    }
+
+    #[test]
+    fn validate_accepts_settings_within_the_limits() {
+        let service = service();
+        service
+            .validate("rtmp://primary.example.com/app", &key())
+            .unwrap();
+        // 服务器地址末尾的 `/` 不影响比较
+        service
+            .validate("rtmp://backup.example.com/app", &key())
+            .unwrap();
+        service
+            .validate("rtmp://primary.example.com/app/", &key())
+            .unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_settings_outside_the_limits() {
+        let service = service();
+        let server = "rtmp://primary.example.com/app";
+        assert!(service
+            .validate("rtmp://other.example.com/app", &key())
+            .is_err());
+
+        let changes: [fn(&mut EncoderKey); 6] = [
+            |key| key.video.bitrate = 6001,
+            |key| key.video.keyint_sec = 3,
+            |key| (key.video.fps_num, key.video.fps_den) = (60, 1),
+            |key| (key.video.fps_num, key.video.fps_den) = (31, 1),
+            |key| key.audio.bitrate = 192,
+            |key| (key.video.width, key.video.height) = (1600, 900),
+        ];
+        for change in changes {
+            let mut key = key();
+            change(&mut key);
+            assert!(service.validate(server, &key).is_err(), "{key:?}");
+        }
+
+        // 没有限制时任何设置都可以
+        let unrestricted = StreamService {
+            recommended: RecommendedSettings::default(),
+            supported_video_codecs: vec![],
+            supported_audio_codecs: vec![],
+            ..service.clone()
+        };
+        let mut custom = key();
+        custom.video.bitrate = 50_000;
+        (custom.video.width, custom.video.height) = (1600, 900);
+        unrestricted.validate(server, &custom).unwrap();
+
+        // 不支持推流使用的编码格式
+        let hevc = StreamService {
+            supported_video_codecs: vec!["hevc".to_string()],
+            ..service.clone()
+        };
+        assert!(hevc.validate(server, &key()).is_err());
+        let opus = StreamService {
+            supported_audio_codecs: vec!["opus".to_string()],
+            ..service
+        };
+        assert!(opus.validate(server, &key()).is_err());
+    }
+
+    #[test]
+    fn recommended_settings_fall_back_to_the_first_server() {
+        let service = service();
+
+        let mut destination = stream_destination("rtmp://backup.example.com/app");
+        service.apply_recommended(&mut destination, (1280, 720));
+        assert_eq!(destination.server, "rtmp://backup.example.com/app");
+        assert_eq!(destination.bitrate, 6000);
+        assert_eq!(destination.keyint_sec, 2);
+        assert_eq!(destination.resolution, None);
+
+        let mut destination = stream_destination("rtmp://other.example.com/app");
+        destination.bitrate = 2500;
+        service.apply_recommended(&mut destination, (1280, 720));
+        assert_eq!(destination.server, "rtmp://primary.example.com/app");
+        assert_eq!(destination.bitrate, 2500);
+    }
+
+    #[test]
+    fn recommended_resolution_fits_the_output() {
+        let service = service();
+        let apply = |resolution: Option<(u32, u32)>, output: (u32, u32)| {
+            let mut destination = stream_destination("rtmp://primary.example.com/app");
+            destination.resolution = resolution;
+            service.apply_recommended(&mut destination, output);
+            destination.resolution
+        };
+
+        // 支持的分辨率保持不变
+        assert_eq!(apply(None, (1920, 1080)), None);
+        assert_eq!(apply(Some((1280, 720)), (1920, 1080)), Some((1280, 720)));
+        // 选择不超过画布输出的最大分辨率
+        assert_eq!(apply(None, (2560, 1440)), Some((1920, 1080)));
+        assert_eq!(apply(Some((1600, 900)), (1600, 900)), Some((1280, 720)));
+        // 画布输出小于所有支持的分辨率时选择最小的
+        assert_eq!(apply(None, (640, 360)), Some((1280, 720)));
+    }
+
+    #[test]
+    fn parse_checks_the_catalog() {
+        let server = json!([{ "name": "Default", "url": "rtmp://example.com/app" }]);
+        let parsed = ServiceCatalog::parse(&catalog(json!([
+            { "name": "A", "servers": server },
+            {
+                "name": "B",
+                "servers": server,
+                "recommended": { "supported_resolutions": ["1920x1080", "1280 x 720"] },
+            },
+        ])))
+        .unwrap();
+        assert_eq!(parsed.services.len(), 2);
+        assert_eq!(parsed.service("A").unwrap().resolutions(), []);
+        assert_eq!(
+            parsed.service("B").unwrap().resolutions(),
+            [(1920, 1080), (1280, 720)]
+        );
+        assert!(parsed.service("C").is_none());
+
+        let duplicate = catalog(json!([
+            { "name": "A", "servers": server },
+            { "name": "A", "servers": server },
+        ]));
+        assert!(ServiceCatalog::parse(&duplicate).is_err());
+
+        let no_servers = catalog(json!([{ "name": "A", "servers": [] }]));
+        assert!(ServiceCatalog::parse(&no_servers).is_err());
+
+        let bad_resolution = catalog(json!([{
+            "name": "A",
+            "servers": server,
+            "recommended": { "supported_resolutions": ["1920*1080"] },
+        }]));
+        assert!(ServiceCatalog::parse(&bad_resolution).is_err());
+
+        let version = json!({ "format_version": FORMAT_VERSION + 1, "services": [] });
+        assert!(ServiceCatalog::parse(&version.to_string()).is_err());
+        assert!(ServiceCatalog::parse("{").is_err());
+    }
+
+    #[test]
+    fn bundled_catalog_is_valid() {
+        let catalog = ServiceCatalog::parse(include_str!("../../resources/services.json")).unwrap();
+        assert!(catalog.services.iter().any(|service| service.common));
+        assert!(catalog
+            .services
+            .iter()
+            .any(|service| !service.resolutions().is_empty()));
+    }
+}
@@ -0,0 +1,384 @@
+/// 多路推流模块
+///
+/// 推流目标保存在配置文件目录的 `streams.json` 中，每个目标有自己的服务器、推流码和编码设置。
+/// 开始推流时为每个启用的目标启动一个推流输出，编码设置相同的目标共用编码器
+use std::{collections::HashMap, fs, path::PathBuf, sync::Mutex};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    media::{
+        audio::AudioInfo,
+        encoder::{EncoderPreset, VideoEncoderSettings},
+        frame::VideoInfo,
+    },
+    outputs::{
+        encoders::{subscribe, EncoderKey},
+        services::find_service,
+    },
+    protocols::{
+        flv::flv_audio_settings,
+        rtmp::RtmpUrl,
+        stream::{stream_output_id, StreamOutput, StreamSettings, StreamStatus},
+    },
+    scene::{canvas::canvas_video_info, scenes},
+    stats::counters::unregister_output,
+    utils::{cli::cli, locale::t, profile::profile_dir},
+    Result,
+};
+
+/// 推流状态变化时发送给前端的事件名称
+pub const STREAMING_EVENT: &str = "streaming-state";
+
+/// 配置文件目录中保存推流目标的文件
+const STREAMS_FILE: &str = "streams.json";
+
+/// 默认视频码率（kbps）
+pub const DEFAULT_STREAM_BITRATE: u32 = 2500;
+
+/// 默认关键帧间隔（秒）
+pub const DEFAULT_KEYINT_SEC: u32 = 2;
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_bitrate() -> u32 {
+    DEFAULT_STREAM_BITRATE
+}
+
+fn default_keyint() -> u32 {
+    DEFAULT_KEYINT_SEC
+}
+
+/// 推流目标
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamDestination {
+    /// 推流目标唯一 ID
+    pub id: String,
+    pub name: String,
+    /// 开始推流时是否启动
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 服务器地址，如 `rtmp://live.example.com/app`
+    pub server: String,
+    /// 推流码
+    pub key: String,
+    /// 服务目录中的服务名称，为 `None` 时为自定义服务器，不检查服务限制
+    #[serde(default)]
+    pub service: Option<String>,
+    /// 画布 UUID，为 `None` 时为主画布
+    #[serde(default)]
+    pub canvas: Option<String>,
+    /// 视频码率（kbps）
+    #[serde(default = "default_bitrate")]
+    pub bitrate: u32,
+    /// 关键帧间隔（秒）
+    #[serde(default = "default_keyint")]
+    pub keyint_sec: u32,
+    #[serde(default)]
+    pub preset: EncoderPreset,
+    /// 推流分辨率，为 `None` 时使用画布的输出分辨率
+    #[serde(default)]
+    pub resolution: Option<(u32, u32)>,
+}
+
+impl StreamDestination {
+    /// 创建使用默认编码设置的推流目标
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 名称
+    /// * `server` - 服务器地址
+    /// * `key` - 推流码
+    pub fn new(name: &str, server: &str, key: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            enabled: true,
+            server: server.to_string(),
+            key: key.to_string(),
+            service: None,
+            canvas: None,
+            bitrate: DEFAULT_STREAM_BITRATE,
+            keyint_sec: DEFAULT_KEYINT_SEC,
+            preset: EncoderPreset::default(),
+            resolution: None,
+        }
+    }
+
+    /// 检查服务器地址和编码设置，选择了服务时同时检查服务的限制
+    pub fn validate(&self) -> Result<()> {
+        RtmpUrl::parse(&self.server, &self.key)?;
+        let key = self.encoder_key()?;
+        key.video.validate()?;
+        if let Some(service) = &self.service {
+            find_service(service)?.validate(&self.server, &key)?;
+        }
+
+        Ok(())
+    }
+
+    /// 共享编码器的键，绑定的画布已被删除时使用主画布
+    pub fn encoder_key(&self) -> Result<EncoderKey> {
+        let canvas = self.canvas.clone().filter(|uuid| {
+            let exists = scenes().canvas(uuid).is_some();
+            if !exists {
+                warn!(
+                    "canvas of stream {} not found, using main canvas: {}",
+                    self.name, uuid
+                );
+            }
+            exists
+        });
+        let info = match canvas {
+            Some(_) => canvas_video_info(canvas.as_deref())?,
+            None => VideoInfo::load(),
+        };
+        let (width, height) = self
+            .resolution
+            .unwrap_or((info.output_width, info.output_height));
+
+        Ok(EncoderKey {
+            canvas,
+            video: VideoEncoderSettings {
+                width,
+                height,
+                fps_num: info.fps_num,
+                fps_den: info.fps_den,
+                bitrate: self.bitrate,
+                keyint_sec: self.keyint_sec,
+                preset: self.preset,
+            },
+            audio: flv_audio_settings(AudioInfo::load().channels),
+        })
+    }
+}
+
+lazy_static! {
+    /// 各推流目标的输出，按目标 ID 索引，失败的输出保留到下次启动或停止
+    static ref STREAMS: Mutex<HashMap<String, StreamOutput>> = Mutex::new(HashMap::new());
+}
+
+/// 推流目标文件路径
+fn streams_file() -> Result<PathBuf> {
+    Ok(profile_dir()
+        .ok_or(anyhow!("profile not set up"))?
+        .join(STREAMS_FILE))
+}
+
+/// 读取当前配置文件的推流目标，文件不存在或无效时返回空列表
+pub fn load_destinations() -> Vec<StreamDestination> {
+    let path = match streams_file() {
+        Ok(path) if path.exists() => path,
+        _ => return vec![],
+    };
+
+    match fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(serde_json::from_str(&json)?))
+    {
+        Ok(destinations) => destinations,
+        Err(e) => {
+            error!("failed to load {}: {}", path.display(), e);
+            vec![]
+        }
+    }
+}
+
+/// 保存推流目标，已删除目标的统计随之注销
+///
+/// # 参数
+///
+/// * `destinations` - 推流目标列表
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，ID 重复、地址无效或写入失败时返回错误
+pub fn save_destinations(destinations: &[StreamDestination]) -> Result<()> {
+    for (i, destination) in destinations.iter().enumerate() {
+        if destinations[..i]
+            .iter()
+            .any(|other| other.id == destination.id)
+        {
+            return Err(anyhow!(
+                "duplicate stream destination id: {}",
+                destination.id
+            ));
+        }
+        destination.validate()?;
+    }
+
+    let path = streams_file()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(destinations)?)?;
+
+    STREAMS.lock().unwrap().retain(|id, output| {
+        let keep =
+            output.is_running() || destinations.iter().any(|destination| &destination.id == id);
+        if !keep {
+            unregister_output(&stream_output_id(id));
+        }
+        keep
+    });
+
+    Ok(())
+}
+
+/// 启动一个推流目标，已在运行时不做任何操作
+fn start_output(
+    app: &AppHandle,
+    streams: &mut HashMap<String, StreamOutput>,
+    destination: &StreamDestination,
+) -> Result<()> {
+    if streams
+        .get(&destination.id)
+        .is_some_and(StreamOutput::is_running)
+    {
+        return Ok(());
+    }
+
+    let url = RtmpUrl::parse(&destination.server, &destination.key)?;
+    let subscription = subscribe(
+        &stream_output_id(&destination.id),
+        destination.encoder_key()?,
+    )?;
+    let handle = app.clone();
+    let output = StreamOutput::start(
+        &destination.id,
+        url,
+        subscription,
+        StreamSettings::load(),
+        move |status| {
+            let _ = handle.emit(STREAMING_EVENT, status);
+        },
+    )?;
+    streams.insert(destination.id.clone(), output);
+
+    Ok(())
+}
+
+/// 启动所有启用的推流目标
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，没有启用的目标或所有目标都无法启动时返回错误
+pub fn start_streaming(app: &AppHandle) -> Result<()> {
+    let destinations: Vec<StreamDestination> = load_destinations()
+        .into_iter()
+        .filter(|destination| destination.enabled)
+        .collect();
+    if destinations.is_empty() {
+        return Err(anyhow!("no stream destination enabled"));
+    }
+
+    let mut streams = STREAMS.lock().unwrap();
+    let mut errors = vec![];
+    for destination in &destinations {
+        if let Err(e) = start_output(app, &mut streams, destination) {
+            error!("failed to start stream {}: {}", destination.name, e);
+            errors.push(format!("{}: {}", destination.name, e));
+        }
+    }
+
+    if errors.len() == destinations.len() {
+        return Err(anyhow!(errors.join("; ")));
+    }
+
+    Ok(())
+}
+
+/// 启动指定的推流目标，不要求目标已启用
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `id` - 推流目标 ID
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，目标不存在或无法启动时返回错误
+pub fn start_destination(app: &AppHandle, id: &str) -> Result<()> {
+    let destination = load_destinations()
+        .into_iter()
+        .find(|destination| destination.id == id)
+        .ok_or(anyhow!("stream destination not found: {}", id))?;
+
+    start_output(app, &mut STREAMS.lock().unwrap(), &destination)
+}
+
+/// 停止指定的推流目标，等待输出线程退出
+///
+/// # 参数
+///
+/// * `id` - 推流目标 ID
+pub fn stop_destination(id: &str) {
+    let output = STREAMS.lock().unwrap().remove(id);
+    if let Some(output) = output {
+        output.stop();
+    }
+}
+
+/// 停止所有推流目标
+pub fn stop_streaming() {
+    let outputs: Vec<StreamOutput> = STREAMS
+        .lock()
+        .unwrap()
+        .drain()
+        .map(|(_, output)| output)
+        .collect();
+    for output in outputs {
+        output.stop();
+    }
+}
+
+/// 是否有推流目标正在运行，包括正在重连的目标
+pub fn streaming_active() -> bool {
+    STREAMS
+        .lock()
+        .unwrap()
+        .values()
+        .any(StreamOutput::is_running)
+}
+
+/// 所有推流输出的状态，包括已失败的输出
+pub fn stream_statuses() -> Vec<StreamStatus> {
+    let mut statuses: Vec<StreamStatus> = STREAMS
+        .lock()
+        .unwrap()
+        .values()
+        .map(StreamOutput::status)
+        .collect();
+    statuses.sort_by(|a, b| a.id.cmp(&b.id));
+
+    statuses
+}
+
+/// 设置推流，指定 `--startstreaming` 时启动所有启用的推流目标
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn setup_streaming(app: &AppHandle) -> Result<()> {
+    if cli()?.opt_start_streaming {
+        if let Err(e) = start_streaming(app) {
+            error!("{}: {}", t("Output.StartStreamFailed")?, e);
+        }
+    }
+
+    Ok(())
+}
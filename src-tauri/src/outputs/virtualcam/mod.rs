@@ -0,0 +1,256 @@
+/// 虚拟摄像机输出模块
+///
+/// 将节目画面转换为 YUYV/NV12 后写入 v4l2loopback 设备或文件/命名管道
+pub mod sink;
+#[cfg(target_os = "linux")]
+pub mod v4l2;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::RecvTimeoutError,
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use log::{error, info};
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    media::{
+        color::load_color_settings,
+        convert::convert_rgba,
+        frame::VideoFormat,
+        video::{connect_canvas_video, VideoConversion},
+    },
+    outputs::virtualcam::sink::{FileSink, FrameSink},
+    scene::canvas::output_canvas,
+    stats::counters::{register_output, OutputKind},
+    utils::{cli::cli, locale::t, profile::get_profile_config},
+    Result,
+};
+
+/// 虚拟摄像机状态变化时发送给前端的事件名称
+pub const VIRTUALCAM_EVENT: &str = "virtualcam-state";
+
+/// 虚拟摄像机的输出 ID
+pub const VIRTUALCAM_OUTPUT: &str = "virtualcam_output";
+
+/// 配置文件中的虚拟摄像机配置节
+const VIRTUALCAM_SECTION: &str = "VirtualCam";
+
+/// 运行中的虚拟摄像机
+struct VirtualCam {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+lazy_static! {
+    static ref VIRTUALCAM: Mutex<Option<VirtualCam>> = Mutex::new(None);
+}
+
+/// 根据配置文件创建帧输出
+///
+/// `[VirtualCam]` 节中 `Sink` 为 `file` 时写入 `Path` 指定的文件或命名管道，
+/// 否则写入 `Device` 指定的 v4l2loopback 设备，未指定时自动查找
+///
+/// # 返回值
+///
+/// 返回 `Result<Box<dyn FrameSink>>`，表示帧输出
+pub fn create_sink() -> Result<Box<dyn FrameSink>> {
+    let sink = get_profile_config(VIRTUALCAM_SECTION, "Sink").unwrap_or("v4l2".to_string());
+
+    match sink.as_str() {
+        "file" => {
+            let path = get_profile_config(VIRTUALCAM_SECTION, "Path")
+                .filter(|path| !path.is_empty())
+                .ok_or(anyhow!("virtual camera file path not set"))?;
+            Ok(Box::new(FileSink::new(path)))
+        }
+        #[cfg(target_os = "linux")]
+        "v4l2" => {
+            let device = get_profile_config(VIRTUALCAM_SECTION, "Device")
+                .filter(|device| !device.is_empty())
+                .map(std::path::PathBuf::from);
+            Ok(Box::new(v4l2::V4l2Sink::new(device)))
+        }
+        _ => Err(anyhow!("unsupported virtual camera sink: {}", sink)),
+    }
+}
+
+/// 配置文件中的虚拟摄像机像素格式，默认为 YUYV
+pub fn virtualcam_format() -> VideoFormat {
+    get_profile_config(VIRTUALCAM_SECTION, "Format")
+        .and_then(|format| VideoFormat::from_name(&format))
+        .filter(|format| matches!(format, VideoFormat::Yuyv | VideoFormat::Nv12))
+        .unwrap_or(VideoFormat::Yuyv)
+}
+
+/// 虚拟摄像机是否正在运行
+pub fn virtualcam_active() -> bool {
+    VIRTUALCAM
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|virtualcam| !virtualcam.thread.is_finished())
+}
+
+/// 使用配置文件中的输出启动虚拟摄像机
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn start_virtualcam(app: &AppHandle) -> Result<()> {
+    start_virtualcam_with_sink(app, create_sink()?, virtualcam_format())
+}
+
+/// 使用指定的帧输出启动虚拟摄像机，已在运行时不做任何操作
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `sink` - 帧输出
+/// * `format` - 像素格式，`Yuyv` 或 `Nv12`
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn start_virtualcam_with_sink(
+    app: &AppHandle,
+    mut sink: Box<dyn FrameSink>,
+    format: VideoFormat,
+) -> Result<()> {
+    let mut virtualcam = VIRTUALCAM.lock().unwrap();
+    if virtualcam
+        .as_ref()
+        .is_some_and(|virtualcam| !virtualcam.thread.is_finished())
+    {
+        return Ok(());
+    }
+
+    // 使用绑定画布的输出分辨率，NV12 直接在 GPU 上转换，YUYV 在 CPU 上转换
+    let (space, range) = load_color_settings();
+    let canvas = output_canvas(VIRTUALCAM_SECTION);
+    let frames = connect_canvas_video(
+        canvas.as_deref(),
+        VideoConversion::canvas_output(
+            canvas.as_deref(),
+            match format {
+                VideoFormat::Nv12 => format,
+                _ => VideoFormat::Rgba,
+            },
+            space,
+            range,
+        ),
+    )?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let counters = register_output(VIRTUALCAM_OUTPUT, OutputKind::Other);
+
+    let handle = app.clone();
+    let stopped = stop.clone();
+    let thread = thread::Builder::new()
+        .name("virtualcam".to_string())
+        .spawn(move || {
+            let mut opened = false;
+            counters.set_active(true);
+            let _ = handle.emit(VIRTUALCAM_EVENT, true);
+
+            let result = loop {
+                if stopped.load(Ordering::Relaxed) {
+                    break Ok(());
+                }
+
+                let frame = match frames.recv_timeout(Duration::from_millis(100)) {
+                    Ok(frame) => frame,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        break Err(anyhow!("video output stopped"))
+                    }
+                };
+
+                if !opened {
+                    if let Err(e) = sink.open(frame.width, frame.height, format) {
+                        break Err(e);
+                    }
+                    info!("virtual camera started: {}", sink.name());
+                    opened = true;
+                }
+
+                let converted;
+                let frame = if frame.format == format {
+                    frame.as_ref()
+                } else {
+                    match convert_rgba(&frame, format, space, range) {
+                        Ok(frame) => {
+                            converted = frame;
+                            &converted
+                        }
+                        Err(e) => break Err(e),
+                    }
+                };
+                match sink.write_frame(frame) {
+                    Ok(true) => counters.record_sent(frame.data.len() as u64, 1),
+                    Ok(false) => counters.record_dropped(1),
+                    Err(e) => break Err(e),
+                }
+            };
+
+            sink.close();
+            counters.set_active(false);
+            let _ = handle.emit(VIRTUALCAM_EVENT, false);
+
+            match result {
+                Ok(_) => info!("virtual camera stopped"),
+                Err(e) => {
+                    error!(
+                        "{}: {}",
+                        t("Output.StartVirtualCamFailed").unwrap_or_default(),
+                        e
+                    );
+                }
+            }
+        })?;
+
+    *virtualcam = Some(VirtualCam { stop, thread });
+
+    Ok(())
+}
+
+/// 停止虚拟摄像机，等待输出线程退出
+pub fn stop_virtualcam() {
+    let virtualcam = VIRTUALCAM.lock().unwrap().take();
+
+    if let Some(virtualcam) = virtualcam {
+        virtualcam.stop.store(true, Ordering::Relaxed);
+        if virtualcam.thread.join().is_err() {
+            error!("virtual camera thread panicked");
+        }
+    }
+}
+
+/// 设置虚拟摄像机，指定 `--startvirtualcam` 时启动
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn setup_virtualcam(app: &AppHandle) -> Result<()> {
+    if cli()?.opt_start_virtualcam {
+        if let Err(e) = start_virtualcam(app) {
+            error!("{}: {}", t("Output.StartVirtualCamFailed")?, e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,301 @@
+/// 虚拟摄像机帧输出接口
+use std::{
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+
+use crate::{
+    media::frame::{VideoFormat, VideoFrame},
+    Result,
+};
+
+/// 已写入部分帧后等待读取端的最长时间，超时后断开读取端
+const PARTIAL_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 虚拟摄像机帧输出
+///
+/// 除 v4l2loopback 设备外，也可以输出到文件或命名管道，便于在没有内核模块的环境中测试
+pub trait FrameSink: Send {
+    /// 输出名称，用于日志
+    fn name(&self) -> String;
+
+    /// 打开输出
+    ///
+    /// # 参数
+    ///
+    /// * `width` - 帧宽度
+    /// * `height` - 帧高度
+    /// * `format` - 像素格式
+    fn open(&mut self, width: u32, height: u32, format: VideoFormat) -> Result<()>;
+
+    /// 写入一帧
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<bool>`，帧因读取端未就绪而被丢弃时返回 `false`
+    fn write_frame(&mut self, frame: &VideoFrame) -> Result<bool>;
+
+    /// 关闭输出
+    fn close(&mut self) {}
+}
+
+/// 文件或命名管道输出，逐帧写入原始像素数据
+pub struct FileSink {
+    path: PathBuf,
+    file: Option<File>,
+    /// 是否为命名管道
+    fifo: bool,
+    /// 单帧写入的最长等待时间
+    timeout: Duration,
+}
+
+impl FileSink {
+    /// 创建文件输出
+    ///
+    /// # 参数
+    ///
+    /// * `path` - 文件或命名管道路径
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file: None,
+            fifo: false,
+            timeout: Duration::from_millis(100),
+        }
+    }
+
+    /// 以非阻塞方式打开命名管道，没有读取端时返回 `None`
+    #[cfg(unix)]
+    fn open_fifo(&self) -> Result<Option<File>> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        match OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&self.path)
+        {
+            Ok(file) => Ok(Some(file)),
+            Err(e) if e.raw_os_error() == Some(libc::ENXIO) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn open_fifo(&self) -> Result<Option<File>> {
+        Ok(None)
+    }
+}
+
+impl FrameSink for FileSink {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn open(&mut self, _width: u32, _height: u32, _format: VideoFormat) -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+
+            self.fifo = std::fs::metadata(&self.path)
+                .map(|metadata| metadata.file_type().is_fifo())
+                .unwrap_or(false);
+        }
+
+        self.file = if self.fifo {
+            self.open_fifo()?
+        } else {
+            if let Some(dir) = self.path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            Some(File::create(&self.path)?)
+        };
+
+        Ok(())
+    }
+
+    fn write_frame(&mut self, frame: &VideoFrame) -> Result<bool> {
+        if self.file.is_none() && self.fifo {
+            self.file = self.open_fifo()?;
+        }
+        let Some(file) = self.file.as_mut() else {
+            return Ok(false);
+        };
+
+        let mut data = frame.data.as_slice();
+        let start = Instant::now();
+        while !data.is_empty() {
+            match file.write(data) {
+                Ok(0) => return Err(std::io::Error::from(ErrorKind::WriteZero).into()),
+                Ok(n) => data = &data[n..],
+                // 读取端来不及读取时，整帧未写入则丢弃，已写入部分则等待写完以保持帧边界
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    let elapsed = start.elapsed();
+                    if data.len() == frame.data.len() && elapsed > self.timeout {
+                        return Ok(false);
+                    }
+                    // 读取端停止读取时无法补齐帧，断开后由读取端重新打开
+                    if elapsed > PARTIAL_WRITE_TIMEOUT {
+                        warn!(
+                            "virtual camera reader stalled, disconnecting: {}",
+                            self.path.display()
+                        );
+                        self.file = None;
+                        return Ok(false);
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                // 读取端关闭后等待新的读取端
+                Err(e) if e.kind() == ErrorKind::BrokenPipe && self.fifo => {
+                    info!(
+                        "virtual camera reader disconnected: {}",
+                        self.path.display()
+                    );
+                    self.file = None;
+                    return Ok(false);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn close(&mut self) {
+        self.file = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    /// 测试帧的尺寸，YUYV 格式下大于管道缓冲区
+    const WIDTH: u32 = 320;
+    const HEIGHT: u32 = 240;
+
+    /// 用同一个字节填充的 YUYV 帧
+    fn frame(value: u8) -> VideoFrame {
+        VideoFrame {
+            width: WIDTH,
+            height: HEIGHT,
+            format: VideoFormat::Yuyv,
+            timestamp: 0,
+            data: vec![value; VideoFormat::Yuyv.frame_size(WIDTH, HEIGHT)],
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sink-{}-{}", std::process::id(), name))
+    }
+
+    #[cfg(unix)]
+    fn make_fifo(path: &std::path::Path) {
+        use std::os::unix::ffi::OsStrExt;
+
+        let _ = std::fs::remove_file(path);
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(path.as_ptr(), 0o600) }, 0);
+    }
+
+    #[test]
+    fn file_sink_appends_frames_to_a_regular_file() {
+        let path = temp_path("file");
+        let mut sink = FileSink::new(&path);
+        sink.open(WIDTH, HEIGHT, VideoFormat::Yuyv).unwrap();
+        assert!(sink.write_frame(&frame(1)).unwrap());
+        assert!(sink.write_frame(&frame(2)).unwrap());
+        sink.close();
+
+        let data = std::fs::read(&path).unwrap();
+        let size = VideoFormat::Yuyv.frame_size(WIDTH, HEIGHT);
+        assert_eq!(data.len(), size * 2);
+        assert!(data[..size].iter().all(|&value| value == 1));
+        assert!(data[size..].iter().all(|&value| value == 2));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fifo_sink_keeps_frame_boundaries_with_a_slow_reader() {
+        let path = temp_path("fifo");
+        make_fifo(&path);
+        let mut sink = FileSink::new(&path);
+        sink.open(WIDTH, HEIGHT, VideoFormat::Yuyv).unwrap();
+        // 没有读取端时丢弃帧
+        assert!(!sink.write_frame(&frame(0)).unwrap());
+
+        let reader = {
+            let path = path.clone();
+            thread::spawn(move || {
+                let mut file = File::open(path).unwrap();
+                let mut data = vec![];
+                let mut buffer = [0u8; 16 * 1024];
+                loop {
+                    match file.read(&mut buffer).unwrap() {
+                        0 => break data,
+                        n => data.extend_from_slice(&buffer[..n]),
+                    }
+                    thread::sleep(Duration::from_millis(2));
+                }
+            })
+        };
+
+        let mut written = vec![];
+        let deadline = Instant::now() + Duration::from_secs(10);
+        for value in 1..=8 {
+            while !sink.write_frame(&frame(value)).unwrap() {
+                assert!(Instant::now() < deadline, "reader never connected");
+                thread::sleep(Duration::from_millis(5));
+            }
+            written.push(value);
+        }
+        sink.close();
+
+        let data = reader.join().unwrap();
+        let size = VideoFormat::Yuyv.frame_size(WIDTH, HEIGHT);
+        assert_eq!(data.len(), size * written.len());
+        for (chunk, value) in data.chunks(size).zip(written) {
+            assert!(chunk.iter().all(|&byte| byte == value));
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fifo_sink_disconnects_a_stalled_reader() {
+        let path = temp_path("stalled");
+        make_fifo(&path);
+        let mut sink = FileSink::new(&path);
+        sink.open(WIDTH, HEIGHT, VideoFormat::Yuyv).unwrap();
+
+        // 读取端打开后不再读取，帧只能写入一部分
+        let mut reader = {
+            use std::os::unix::fs::OpenOptionsExt;
+
+            OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(&path)
+                .unwrap()
+        };
+        let start = Instant::now();
+        assert!(!sink.write_frame(&frame(1)).unwrap());
+        assert!(start.elapsed() >= PARTIAL_WRITE_TIMEOUT);
+        assert!(start.elapsed() < PARTIAL_WRITE_TIMEOUT * 3);
+        assert!(sink.file.is_none());
+
+        // 断开后读取端只收到部分帧，随后读到结尾
+        let mut data = vec![];
+        reader.read_to_end(&mut data).unwrap();
+        assert!(!data.is_empty() && data.len() < frame(1).data.len());
+        let _ = std::fs::remove_file(&path);
+    }
+}
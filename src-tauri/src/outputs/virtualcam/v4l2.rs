@@ -0,0 +1,241 @@
+/// v4l2loopback 设备输出
+use std::{
+    ffi::CStr,
+    fs::{File, OpenOptions},
+    io::Write,
+    os::fd::AsRawFd,
+    path::PathBuf,
+};
+
+use anyhow::anyhow;
+use log::info;
+
+use crate::{
+    media::frame::{VideoFormat, VideoFrame},
+    outputs::virtualcam::sink::FrameSink,
+    Result,
+};
+
+/// v4l2loopback 驱动名称
+const LOOPBACK_DRIVER: &str = "v4l2 loopback";
+
+const V4L2_CAP_VIDEO_OUTPUT: u32 = 0x0000_0002;
+const V4L2_CAP_DEVICE_CAPS: u32 = 0x8000_0000;
+const V4L2_BUF_TYPE_VIDEO_OUTPUT: u32 = 2;
+const V4L2_FIELD_NONE: u32 = 1;
+const V4L2_COLORSPACE_REC709: u32 = 3;
+
+/// 生成 FourCC
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    code[0] as u32 | (code[1] as u32) << 8 | (code[2] as u32) << 16 | (code[3] as u32) << 24
+}
+
+/// 生成 ioctl 请求码，同内核的 `_IOC` 宏
+const fn ioc(dir: u32, nr: u32, size: usize) -> u32 {
+    dir << 30 | (size as u32) << 16 | (b'V' as u32) << 8 | nr
+}
+
+/// `struct v4l2_capability`
+#[repr(C)]
+struct Capability {
+    driver: [u8; 16],
+    card: [u8; 32],
+    bus_info: [u8; 32],
+    version: u32,
+    capabilities: u32,
+    device_caps: u32,
+    reserved: [u32; 3],
+}
+
+/// `struct v4l2_pix_format`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PixFormat {
+    width: u32,
+    height: u32,
+    pixelformat: u32,
+    field: u32,
+    bytesperline: u32,
+    sizeimage: u32,
+    colorspace: u32,
+    priv_: u32,
+    flags: u32,
+    ycbcr_enc: u32,
+    quantization: u32,
+    xfer_func: u32,
+}
+
+/// `struct v4l2_format` 中的联合体，内核中包含指针，按 8 字节对齐
+#[repr(C)]
+union FormatUnion {
+    pix: PixFormat,
+    raw_data: [u8; 200],
+    _align: [u64; 25],
+}
+
+/// `struct v4l2_format`
+#[repr(C)]
+struct Format {
+    type_: u32,
+    fmt: FormatUnion,
+}
+
+const VIDIOC_QUERYCAP: u32 = ioc(2, 0, std::mem::size_of::<Capability>());
+const VIDIOC_S_FMT: u32 = ioc(3, 5, std::mem::size_of::<Format>());
+
+/// 查询设备能力
+fn query_capability(file: &File) -> Result<Capability> {
+    let mut cap: Capability = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::ioctl(file.as_raw_fd(), VIDIOC_QUERYCAP as _, &mut cap) } < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(cap)
+}
+
+/// 设备是否为 v4l2loopback 输出设备
+fn is_loopback_output(cap: &Capability) -> bool {
+    let driver = CStr::from_bytes_until_nul(&cap.driver)
+        .map(|driver| driver.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let caps = if cap.capabilities & V4L2_CAP_DEVICE_CAPS != 0 {
+        cap.device_caps
+    } else {
+        cap.capabilities
+    };
+
+    driver == LOOPBACK_DRIVER && caps & V4L2_CAP_VIDEO_OUTPUT != 0
+}
+
+/// 查找第一个可用的 v4l2loopback 设备
+///
+/// # 返回值
+///
+/// 返回 `Option<PathBuf>`，没有加载 v4l2loopback 模块时返回 `None`
+pub fn find_loopback_device() -> Option<PathBuf> {
+    let mut devices: Vec<PathBuf> = std::fs::read_dir("/dev")
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("video"))
+        })
+        .collect();
+    devices.sort();
+
+    devices.into_iter().find(|path| {
+        OpenOptions::new()
+            .write(true)
+            .open(path)
+            .and_then(|file| query_capability(&file).map_err(std::io::Error::other))
+            .is_ok_and(|cap| is_loopback_output(&cap))
+    })
+}
+
+/// v4l2loopback 设备输出
+pub struct V4l2Sink {
+    /// 设备路径，为空时自动查找
+    device: Option<PathBuf>,
+    file: Option<File>,
+}
+
+impl V4l2Sink {
+    /// 创建 v4l2loopback 输出
+    ///
+    /// # 参数
+    ///
+    /// * `device` - 设备路径，如 `/dev/video10`，为 `None` 时自动查找
+    pub fn new(device: Option<PathBuf>) -> Self {
+        Self { device, file: None }
+    }
+
+    /// 设置设备输出格式
+    fn set_format(file: &File, width: u32, height: u32, format: VideoFormat) -> Result<()> {
+        let (pixelformat, bytesperline) = match format {
+            VideoFormat::Yuyv => (fourcc(b"YUYV"), width.div_ceil(2) * 4),
+            VideoFormat::Nv12 => (fourcc(b"NV12"), width),
+            _ => return Err(anyhow!("unsupported v4l2 format: {:?}", format)),
+        };
+
+        let mut fmt: Format = unsafe { std::mem::zeroed() };
+        fmt.type_ = V4L2_BUF_TYPE_VIDEO_OUTPUT;
+        fmt.fmt.pix = PixFormat {
+            width,
+            height,
+            pixelformat,
+            field: V4L2_FIELD_NONE,
+            bytesperline,
+            sizeimage: format.frame_size(width, height) as u32,
+            colorspace: V4L2_COLORSPACE_REC709,
+            priv_: 0,
+            flags: 0,
+            ycbcr_enc: 0,
+            quantization: 0,
+            xfer_func: 0,
+        };
+
+        if unsafe { libc::ioctl(file.as_raw_fd(), VIDIOC_S_FMT as _, &mut fmt) } < 0 {
+            return Err(anyhow!(
+                "VIDIOC_S_FMT failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl FrameSink for V4l2Sink {
+    fn name(&self) -> String {
+        match &self.device {
+            Some(device) => device.display().to_string(),
+            None => "v4l2loopback".to_string(),
+        }
+    }
+
+    fn open(&mut self, width: u32, height: u32, format: VideoFormat) -> Result<()> {
+        let device = match &self.device {
+            Some(device) => device.clone(),
+            None => find_loopback_device().ok_or(anyhow!("no v4l2loopback device found"))?,
+        };
+
+        let file = OpenOptions::new().write(true).open(&device)?;
+        let cap = query_capability(&file)?;
+        if !is_loopback_output(&cap) {
+            return Err(anyhow!(
+                "{} is not a v4l2loopback output device",
+                device.display()
+            ));
+        }
+        Self::set_format(&file, width, height, format)?;
+
+        info!(
+            "virtual camera opened {} {}x{} {:?}",
+            device.display(),
+            width,
+            height,
+            format
+        );
+        self.device = Some(device);
+        self.file = Some(file);
+
+        Ok(())
+    }
+
+    fn write_frame(&mut self, frame: &VideoFrame) -> Result<bool> {
+        match self.file.as_mut() {
+            Some(file) => {
+                file.write_all(&frame.data)?;
+                Ok(true)
+            }
+            None => Err(anyhow!("v4l2 device is not open")),
+        }
+    }
+
+    fn close(&mut self) {
+        self.file = None;
+    }
+}
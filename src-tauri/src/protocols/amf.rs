@@ -0,0 +1,237 @@
+/// AMF0 编解码模块
+///
+/// RTMP 的命令消息（connect、createStream、publish 等）和数据消息（onMetaData）使用 AMF0 编码
+use anyhow::anyhow;
+
+use crate::Result;
+
+const NUMBER_MARKER: u8 = 0x00;
+const BOOLEAN_MARKER: u8 = 0x01;
+const STRING_MARKER: u8 = 0x02;
+const OBJECT_MARKER: u8 = 0x03;
+const NULL_MARKER: u8 = 0x05;
+const UNDEFINED_MARKER: u8 = 0x06;
+const ECMA_ARRAY_MARKER: u8 = 0x08;
+const OBJECT_END_MARKER: u8 = 0x09;
+const LONG_STRING_MARKER: u8 = 0x0c;
+
+/// AMF0 值，对象的属性保持原有顺序
+#[derive(Debug, Clone, PartialEq)]
+pub enum Amf0Value {
+    Number(f64),
+    Boolean(bool),
+    String(String),
+    Object(Vec<(String, Amf0Value)>),
+    Null,
+    Undefined,
+    EcmaArray(Vec<(String, Amf0Value)>),
+}
+
+impl Amf0Value {
+    /// 创建字符串值
+    pub fn string(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+
+    /// 创建对象值
+    pub fn object(properties: &[(&str, Amf0Value)]) -> Self {
+        Self::Object(
+            properties
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    /// 字符串值的内容
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// 数字值的内容
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// 对象或 ECMA 数组中指定属性的值
+    ///
+    /// # 参数
+    ///
+    /// * `key` - 属性名
+    pub fn property(&self, key: &str) -> Option<&Amf0Value> {
+        match self {
+            Self::Object(properties) | Self::EcmaArray(properties) => properties
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+/// 写入不带类型标记的字符串
+fn write_short_string(output: &mut Vec<u8>, value: &str) {
+    let bytes = value.as_bytes();
+    let len = bytes.len().min(u16::MAX as usize);
+    output.extend_from_slice(&(len as u16).to_be_bytes());
+    output.extend_from_slice(&bytes[..len]);
+}
+
+/// 写入对象属性和结束标记
+fn write_properties(output: &mut Vec<u8>, properties: &[(String, Amf0Value)]) {
+    for (key, value) in properties {
+        write_short_string(output, key);
+        write_value(output, value);
+    }
+    output.extend_from_slice(&[0, 0, OBJECT_END_MARKER]);
+}
+
+/// 写入一个值
+fn write_value(output: &mut Vec<u8>, value: &Amf0Value) {
+    match value {
+        Amf0Value::Number(value) => {
+            output.push(NUMBER_MARKER);
+            output.extend_from_slice(&value.to_be_bytes());
+        }
+        Amf0Value::Boolean(value) => {
+            output.push(BOOLEAN_MARKER);
+            output.push(*value as u8);
+        }
+        Amf0Value::String(value) if value.len() > u16::MAX as usize => {
+            output.push(LONG_STRING_MARKER);
+            output.extend_from_slice(&(value.len() as u32).to_be_bytes());
+            output.extend_from_slice(value.as_bytes());
+        }
+        Amf0Value::String(value) => {
+            output.push(STRING_MARKER);
+            write_short_string(output, value);
+        }
+        Amf0Value::Object(properties) => {
+            output.push(OBJECT_MARKER);
+            write_properties(output, properties);
+        }
+        Amf0Value::Null => output.push(NULL_MARKER),
+        Amf0Value::Undefined => output.push(UNDEFINED_MARKER),
+        Amf0Value::EcmaArray(properties) => {
+            output.push(ECMA_ARRAY_MARKER);
+            output.extend_from_slice(&(properties.len() as u32).to_be_bytes());
+            write_properties(output, properties);
+        }
+    }
+}
+
+/// 按顺序编码多个值
+///
+/// # 参数
+///
+/// * `values` - 要编码的值
+///
+/// # 返回值
+///
+/// 返回编码后的字节
+pub fn encode(values: &[Amf0Value]) -> Vec<u8> {
+    let mut output = vec![];
+    for value in values {
+        write_value(&mut output, value);
+    }
+
+    output
+}
+
+/// AMF0 读取器
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        let end = self.offset + len;
+        let bytes = self
+            .data
+            .get(self.offset..end)
+            .ok_or(anyhow!("truncated AMF0 data"))?;
+        self.offset = end;
+
+        Ok(bytes)
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_string(&mut self, len: usize) -> Result<String> {
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn read_properties(&mut self) -> Result<Vec<(String, Amf0Value)>> {
+        let mut properties = vec![];
+        loop {
+            let len = self.read_u16()? as usize;
+            if len == 0 && self.data.get(self.offset) == Some(&OBJECT_END_MARKER) {
+                self.offset += 1;
+                return Ok(properties);
+            }
+            let key = self.read_string(len)?;
+            properties.push((key, self.read_value()?));
+        }
+    }
+
+    fn read_value(&mut self) -> Result<Amf0Value> {
+        let marker = self.take(1)?[0];
+        match marker {
+            NUMBER_MARKER => {
+                let bytes: [u8; 8] = self.take(8)?.try_into()?;
+                Ok(Amf0Value::Number(f64::from_be_bytes(bytes)))
+            }
+            BOOLEAN_MARKER => Ok(Amf0Value::Boolean(self.take(1)?[0] != 0)),
+            STRING_MARKER => {
+                let len = self.read_u16()? as usize;
+                Ok(Amf0Value::String(self.read_string(len)?))
+            }
+            LONG_STRING_MARKER => {
+                let len = self.read_u32()? as usize;
+                Ok(Amf0Value::String(self.read_string(len)?))
+            }
+            OBJECT_MARKER => Ok(Amf0Value::Object(self.read_properties()?)),
+            NULL_MARKER => Ok(Amf0Value::Null),
+            UNDEFINED_MARKER => Ok(Amf0Value::Undefined),
+            ECMA_ARRAY_MARKER => {
+                self.read_u32()?;
+                Ok(Amf0Value::EcmaArray(self.read_properties()?))
+            }
+            _ => Err(anyhow!("unsupported AMF0 marker: {:#04x}", marker)),
+        }
+    }
+}
+
+/// 解码消息中的所有值
+///
+/// # 参数
+///
+/// * `data` - 消息内容
+///
+/// # 返回值
+///
+/// 返回 `Result<Vec<Amf0Value>>`，数据不完整或包含不支持的类型时返回错误
+pub fn decode(data: &[u8]) -> Result<Vec<Amf0Value>> {
+    let mut reader = Reader { data, offset: 0 };
+    let mut values = vec![];
+    while reader.offset < data.len() {
+        values.push(reader.read_value()?);
+    }
+
+    Ok(values)
+}
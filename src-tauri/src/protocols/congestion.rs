@@ -0,0 +1,377 @@
+/// 拥塞控制模块
+///
+/// 根据发送缓冲的积压和服务器确认的时间判断上行是否拥塞。
+/// 拥塞时逐级降低编码码率，网络恢复后缓慢回升；积压严重时按优先级丢帧，
+/// 先丢可丢弃帧，再丢帧间预测帧直到下一个关键帧，音频始终不丢
+use std::{
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{media::encoder::FramePriority, utils::profile::get_profile_config};
+
+/// 两次检测之间的间隔
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 积压低于该时长时认为网络通畅
+const CLEAR_DELAY: Duration = Duration::from_millis(150);
+
+/// 积压超过该时长时降低码率
+const CONGESTED_DELAY: Duration = Duration::from_millis(400);
+
+/// 积压超过该时长时丢弃可丢弃帧
+const DROP_DISPOSABLE_DELAY: Duration = Duration::from_millis(1000);
+
+/// 积压超过该时长时丢弃帧间预测帧
+const DROP_INTER_DELAY: Duration = Duration::from_millis(2000);
+
+/// 两次降低码率的最小间隔，等待新码率生效
+const DECREASE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 每次降低码率的比例
+const DECREASE_FACTOR: f64 = 0.7;
+
+/// 最低码率占设置码率的比例
+const MIN_BITRATE_RATIO: f64 = 0.2;
+
+/// 最低码率（kbps）
+const MIN_BITRATE: u32 = 100;
+
+/// 网络通畅持续该时长后开始回升码率
+const RECOVER_DELAY: Duration = Duration::from_secs(5);
+
+/// 两次回升码率的间隔
+const RECOVER_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 每次回升的码率占设置码率的比例
+const RECOVER_STEP: f64 = 0.05;
+
+/// 未确认的数据超过确认窗口的倍数时认为确认延迟
+const ACK_LAG_WINDOWS: u64 = 2;
+
+/// 确认间隔超过按发送速率估计的间隔的倍数时认为确认延迟
+const ACK_LATE_FACTOR: u32 = 3;
+
+/// 拥塞状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CongestionState {
+    /// 网络通畅
+    #[default]
+    Clear,
+    /// 拥塞，正在降低码率
+    Congested,
+    /// 积压严重，丢弃可丢弃帧
+    DroppingDisposable,
+    /// 积压非常严重，丢弃帧间预测帧直到下一个关键帧
+    DroppingInter,
+}
+
+/// 拥塞控制设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CongestionSettings {
+    /// 拥塞时是否动态调整码率
+    pub dynamic_bitrate: bool,
+    /// 积压严重时是否丢帧
+    pub drop_frames: bool,
+}
+
+impl Default for CongestionSettings {
+    fn default() -> Self {
+        Self {
+            dynamic_bitrate: true,
+            drop_frames: true,
+        }
+    }
+}
+
+impl CongestionSettings {
+    /// 从当前配置文件的 `[Output]` 节读取拥塞控制设置
+    pub fn load() -> Self {
+        let default = Self::default();
+        let get = |key: &str, default: bool| {
+            get_profile_config("Output", key)
+                .map_or(default, |value| value != "false" && value != "0")
+        };
+
+        Self {
+            dynamic_bitrate: get("DynamicBitrate", default.dynamic_bitrate),
+            drop_frames: get("DropFrames", default.drop_frames),
+        }
+    }
+}
+
+/// 一次检测的输入
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CongestionSample {
+    /// 已发送的字节数
+    pub bytes_sent: u64,
+    /// 服务器确认的字节数
+    pub acked_bytes: u64,
+    /// 服务器的确认窗口，为 0 时不检查确认
+    pub ack_window: u64,
+    /// 内核发送缓冲中尚未被对方确认的字节数，无法获取时为 `None`
+    pub queued_bytes: Option<u64>,
+}
+
+/// 内核发送缓冲中尚未被对方确认的字节数
+///
+/// # 参数
+///
+/// * `socket` - TCP 连接
+///
+/// # 返回值
+///
+/// 返回 `Option<u64>`，当前平台不支持时返回 `None`
+#[cfg(target_os = "linux")]
+pub fn send_queue_bytes(socket: &TcpStream) -> Option<u64> {
+    use std::os::fd::AsRawFd;
+
+    let mut queued: libc::c_int = 0;
+    let result = unsafe { libc::ioctl(socket.as_raw_fd(), libc::TIOCOUTQ, &mut queued) };
+    (result == 0).then_some(queued.max(0) as u64)
+}
+
+/// 内核发送缓冲中尚未被对方确认的字节数
+///
+/// # 参数
+///
+/// * `socket` - TCP 连接
+///
+/// # 返回值
+///
+/// 返回 `Option<u64>`，当前平台不支持时返回 `None`
+#[cfg(not(target_os = "linux"))]
+pub fn send_queue_bytes(_socket: &TcpStream) -> Option<u64> {
+    None
+}
+
+/// 单个推流输出的拥塞检测与码率控制
+pub struct CongestionController {
+    settings: CongestionSettings,
+    /// 设置的视频码率（kbps）
+    target: u32,
+    /// 当前的视频码率（kbps）
+    bitrate: u32,
+    /// 音频码率（kbps），用于估计发送速率
+    audio_bitrate: u32,
+    state: CongestionState,
+    /// 本次连接第一个包的时间和时间戳
+    origin: Option<(Instant, u64)>,
+    /// 本次连接中发送延迟的最小值（纳秒），作为没有积压时的基准
+    min_lag: i64,
+    /// 当前的发送延迟（纳秒）
+    lag: i64,
+    last_sample: Option<Instant>,
+    last_decrease: Option<Instant>,
+    last_increase: Option<Instant>,
+    /// 网络开始通畅的时间
+    clear_since: Option<Instant>,
+    /// 上次确认的字节数和收到的时间
+    last_ack: Option<(u64, Instant)>,
+    /// 丢弃帧间预测帧，直到下一个关键帧
+    skip_inter: bool,
+}
+
+impl CongestionController {
+    /// 创建拥塞控制器
+    ///
+    /// # 参数
+    ///
+    /// * `settings` - 拥塞控制设置
+    /// * `target` - 设置的视频码率（kbps）
+    /// * `audio_bitrate` - 音频码率（kbps）
+    pub fn new(settings: CongestionSettings, target: u32, audio_bitrate: u32) -> Self {
+        Self {
+            settings,
+            target,
+            bitrate: target,
+            audio_bitrate,
+            state: CongestionState::Clear,
+            origin: None,
+            min_lag: i64::MAX,
+            lag: 0,
+            last_sample: None,
+            last_decrease: None,
+            last_increase: None,
+            clear_since: None,
+            last_ack: None,
+            skip_inter: false,
+        }
+    }
+
+    /// 当前的视频码率（kbps）
+    pub fn bitrate(&self) -> u32 {
+        self.bitrate
+    }
+
+    /// 当前的拥塞状态
+    pub fn state(&self) -> CongestionState {
+        self.state
+    }
+
+    /// 新连接建立后重置积压的基准，保留当前码率
+    pub fn reset(&mut self) {
+        self.origin = None;
+        self.min_lag = i64::MAX;
+        self.lag = 0;
+        self.last_sample = None;
+        self.last_ack = None;
+        self.skip_inter = false;
+        self.state = CongestionState::Clear;
+    }
+
+    /// 最低码率（kbps）
+    fn min_bitrate(&self) -> u32 {
+        ((self.target as f64 * MIN_BITRATE_RATIO) as u32)
+            .max(MIN_BITRATE)
+            .min(self.target)
+    }
+
+    /// 取出一个包准备发送时调用，记录发送进度落后于时间戳的程度
+    ///
+    /// # 参数
+    ///
+    /// * `dts` - 包的解码时间戳（纳秒）
+    /// * `now` - 当前时间
+    pub fn on_packet(&mut self, dts: u64, now: Instant) {
+        let (start, base) = *self.origin.get_or_insert((now, dts));
+        let elapsed = now.duration_since(start).as_nanos() as i64;
+        self.lag = elapsed - (dts as i64 - base as i64);
+        self.min_lag = self.min_lag.min(self.lag);
+    }
+
+    /// 发送积压的估计，包括等待发送的包和内核发送缓冲中的数据
+    pub fn backlog(&self, queued_bytes: Option<u64>) -> Duration {
+        let waiting = if self.min_lag == i64::MAX {
+            0
+        } else {
+            (self.lag - self.min_lag).max(0) as u64
+        };
+        let rate = (self.bitrate + self.audio_bitrate).max(1) as u64;
+        let buffered = queued_bytes.map_or(0, |bytes| bytes * 8 * 1_000_000 / rate);
+
+        Duration::from_nanos(waiting + buffered)
+    }
+
+    /// 服务器确认是否明显落后于发送
+    fn ack_late(&mut self, sample: &CongestionSample, now: Instant) -> bool {
+        if sample.ack_window == 0 {
+            return false;
+        }
+        let (acked, since) = *self.last_ack.get_or_insert((sample.acked_bytes, now));
+        if sample.acked_bytes != acked {
+            self.last_ack = Some((sample.acked_bytes, now));
+            return false;
+        }
+
+        let delivered = sample
+            .bytes_sent
+            .saturating_sub(sample.queued_bytes.unwrap_or(0));
+        let unacked = delivered.saturating_sub(sample.acked_bytes);
+        if unacked > sample.ack_window * ACK_LAG_WINDOWS {
+            return true;
+        }
+        // 按当前速率发送一个确认窗口的数据需要的时间
+        let rate = (self.bitrate + self.audio_bitrate).max(1) as u64 * 1000 / 8;
+        let expected = Duration::from_secs_f64(sample.ack_window as f64 / rate as f64);
+        unacked >= sample.ack_window && now.duration_since(since) > expected * ACK_LATE_FACTOR
+    }
+
+    /// 定期检测拥塞并调整码率
+    ///
+    /// # 参数
+    ///
+    /// * `sample` - 连接的发送和确认情况
+    /// * `now` - 当前时间
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Option<u32>`，码率变化时返回新的视频码率（kbps）
+    pub fn update(&mut self, sample: CongestionSample, now: Instant) -> Option<u32> {
+        if self
+            .last_sample
+            .is_some_and(|last| now.duration_since(last) < SAMPLE_INTERVAL)
+        {
+            return None;
+        }
+        self.last_sample = Some(now);
+
+        let backlog = self.backlog(sample.queued_bytes);
+        let ack_late = self.ack_late(&sample, now);
+        self.state = if backlog >= DROP_INTER_DELAY {
+            CongestionState::DroppingInter
+        } else if backlog >= DROP_DISPOSABLE_DELAY {
+            CongestionState::DroppingDisposable
+        } else if backlog >= CONGESTED_DELAY || ack_late {
+            CongestionState::Congested
+        } else {
+            CongestionState::Clear
+        };
+        if backlog < CLEAR_DELAY && !ack_late {
+            self.clear_since.get_or_insert(now);
+        } else {
+            self.clear_since = None;
+        }
+
+        if !self.settings.dynamic_bitrate {
+            return None;
+        }
+        let previous = self.bitrate;
+        if self.state >= CongestionState::Congested {
+            if self
+                .last_decrease
+                .is_none_or(|last| now.duration_since(last) >= DECREASE_INTERVAL)
+            {
+                self.bitrate =
+                    ((self.bitrate as f64 * DECREASE_FACTOR) as u32).max(self.min_bitrate());
+                self.last_decrease = Some(now);
+            }
+        } else if self.bitrate < self.target
+            && self
+                .clear_since
+                .is_some_and(|since| now.duration_since(since) >= RECOVER_DELAY)
+            && self
+                .last_increase
+                .is_none_or(|last| now.duration_since(last) >= RECOVER_INTERVAL)
+        {
+            let step = ((self.target as f64 * RECOVER_STEP) as u32).max(1);
+            self.bitrate = (self.bitrate + step).min(self.target);
+            self.last_increase = Some(now);
+        }
+
+        (self.bitrate != previous).then_some(self.bitrate)
+    }
+
+    /// 是否丢弃该视频帧，音频不经过该检查
+    ///
+    /// # 参数
+    ///
+    /// * `priority` - 帧的优先级
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `bool`，需要丢弃时返回 `true`
+    pub fn should_drop(&mut self, priority: FramePriority) -> bool {
+        if !self.settings.drop_frames || priority == FramePriority::Audio {
+            return false;
+        }
+        if priority == FramePriority::Key {
+            self.skip_inter = false;
+            return false;
+        }
+        if self.state >= CongestionState::DroppingInter {
+            // 丢弃一帧后，后续参考它的帧都无法解码，一直丢到下一个关键帧
+            self.skip_inter = true;
+        }
+
+        match priority {
+            FramePriority::Disposable => {
+                self.skip_inter || self.state >= CongestionState::DroppingDisposable
+            }
+            _ => self.skip_inter,
+        }
+    }
+}
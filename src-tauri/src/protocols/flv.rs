@@ -0,0 +1,200 @@
+/// FLV 标签模块
+///
+/// RTMP 的音视频消息内容与 FLV 标签的数据部分相同：视频为 AVC，音频为 AAC。
+/// 两者都先发送携带解码配置的序列头，之后发送原始帧。推流时音频统一转换为 44.1 kHz
+use crate::{
+    media::encoder::{AudioEncoderSettings, AvcHeader, VideoEncoderSettings},
+    protocols::amf::Amf0Value,
+};
+
+/// FLV 视频编码 ID：AVC
+pub const VIDEO_CODEC_AVC: u8 = 7;
+
+/// FLV 音频编码 ID：AAC
+pub const AUDIO_CODEC_AAC: u8 = 10;
+
+/// 推流使用的音频采样率
+pub const FLV_SAMPLE_RATE: u32 = 44100;
+
+/// 推流使用的每个声道的音频码率（kbps）
+pub const FLV_CHANNEL_BITRATE: u32 = 80;
+
+/// AVC 包类型
+const AVC_SEQUENCE_HEADER: u8 = 0;
+const AVC_NALU: u8 = 1;
+
+/// AAC 包类型
+const AAC_SEQUENCE_HEADER: u8 = 0;
+const AAC_RAW: u8 = 1;
+
+/// AAC 音频标签的标志：44 kHz、16 位、立体声，AAC 的实际格式以序列头为准
+const AAC_FLAGS: u8 = (AUDIO_CODEC_AAC << 4) | (3 << 2) | (1 << 1) | 1;
+
+/// 视频帧类型
+const FRAME_TYPE_KEY: u8 = 1;
+const FRAME_TYPE_INTER: u8 = 2;
+
+/// 推流使用的音频设置，最多两个声道
+///
+/// # 参数
+///
+/// * `channels` - 混音声道数
+pub fn flv_audio_settings(channels: u16) -> AudioEncoderSettings {
+    let channels = channels.clamp(1, 2);
+    AudioEncoderSettings {
+        sample_rate: FLV_SAMPLE_RATE,
+        channels,
+        bitrate: FLV_CHANNEL_BITRATE * channels as u32,
+    }
+}
+
+/// AVC 序列头，即携带 `avcC` 的视频标签
+///
+/// # 参数
+///
+/// * `header` - SPS/PPS
+pub fn video_sequence_header(header: &AvcHeader) -> Vec<u8> {
+    let mut tag = vec![
+        (FRAME_TYPE_KEY << 4) | VIDEO_CODEC_AVC,
+        AVC_SEQUENCE_HEADER,
+        0,
+        0,
+        0,
+    ];
+    tag.extend_from_slice(&header.decoder_config());
+
+    tag
+}
+
+/// AVC 视频标签
+///
+/// # 参数
+///
+/// * `data` - AVCC 格式的 NAL 单元
+/// * `keyframe` - 是否为关键帧
+/// * `cts` - 显示时间与解码时间之差（毫秒）
+pub fn video_tag(data: &[u8], keyframe: bool, cts: i32) -> Vec<u8> {
+    let frame_type = if keyframe {
+        FRAME_TYPE_KEY
+    } else {
+        FRAME_TYPE_INTER
+    };
+    let cts = cts.to_be_bytes();
+    let mut tag = Vec::with_capacity(data.len() + 5);
+    tag.extend_from_slice(&[
+        (frame_type << 4) | VIDEO_CODEC_AVC,
+        AVC_NALU,
+        cts[1],
+        cts[2],
+        cts[3],
+    ]);
+    tag.extend_from_slice(data);
+
+    tag
+}
+
+/// AAC 序列头，即携带 AudioSpecificConfig 的音频标签
+///
+/// # 参数
+///
+/// * `audio` - 音频编码设置
+pub fn audio_sequence_header(audio: &AudioEncoderSettings) -> Vec<u8> {
+    let mut tag = vec![AAC_FLAGS, AAC_SEQUENCE_HEADER];
+    tag.extend_from_slice(&audio.decoder_config());
+
+    tag
+}
+
+/// AAC 音频标签
+///
+/// # 参数
+///
+/// * `data` - 原始 AAC 帧
+pub fn audio_tag(data: &[u8]) -> Vec<u8> {
+    let mut tag = Vec::with_capacity(data.len() + 2);
+    tag.extend_from_slice(&[AAC_FLAGS, AAC_RAW]);
+    tag.extend_from_slice(data);
+
+    tag
+}
+
+/// `@setDataFrame` 消息中的 `onMetaData`
+///
+/// # 参数
+///
+/// * `video` - 视频编码设置
+/// * `audio` - 音频编码设置
+pub fn metadata(video: &VideoEncoderSettings, audio: &AudioEncoderSettings) -> Vec<Amf0Value> {
+    let number = |value: f64| Amf0Value::Number(value);
+
+    vec![
+        Amf0Value::string("@setDataFrame"),
+        Amf0Value::string("onMetaData"),
+        Amf0Value::EcmaArray(vec![
+            ("width".to_string(), number(video.width as f64)),
+            ("height".to_string(), number(video.height as f64)),
+            (
+                "framerate".to_string(),
+                number(video.fps_num as f64 / video.fps_den.max(1) as f64),
+            ),
+            ("videocodecid".to_string(), number(VIDEO_CODEC_AVC as f64)),
+            ("videodatarate".to_string(), number(video.bitrate as f64)),
+            ("audiocodecid".to_string(), number(AUDIO_CODEC_AAC as f64)),
+            ("audiodatarate".to_string(), number(audio.bitrate as f64)),
+            (
+                "audiosamplerate".to_string(),
+                number(audio.sample_rate as f64),
+            ),
+            ("audiosamplesize".to_string(), number(16.)),
+            ("stereo".to_string(), Amf0Value::Boolean(audio.channels > 1)),
+            (
+                "encoder".to_string(),
+                Amf0Value::string(concat!(
+                    env!("CARGO_PKG_NAME"),
+                    " ",
+                    env!("CARGO_PKG_VERSION")
+                )),
+            ),
+        ]),
+    ]
+}
+
+/// 解析出的视频标签信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoTagInfo {
+    pub keyframe: bool,
+    /// 是否为 AVC 序列头
+    pub sequence_header: bool,
+}
+
+/// 解析视频标签的头部
+///
+/// # 参数
+///
+/// * `tag` - 视频消息内容
+///
+/// # 返回值
+///
+/// 返回 `Option<VideoTagInfo>`，不是 AVC 标签时返回 `None`
+pub fn parse_video_tag(tag: &[u8]) -> Option<VideoTagInfo> {
+    let (flags, packet_type) = (*tag.first()?, *tag.get(1)?);
+    if flags & 0x0f != VIDEO_CODEC_AVC {
+        return None;
+    }
+
+    Some(VideoTagInfo {
+        keyframe: flags >> 4 == FRAME_TYPE_KEY,
+        sequence_header: packet_type == AVC_SEQUENCE_HEADER,
+    })
+}
+
+/// 音频标签是否为 AAC 序列头
+///
+/// # 参数
+///
+/// * `tag` - 音频消息内容
+pub fn is_audio_sequence_header(tag: &[u8]) -> bool {
+    tag.first()
+        .is_some_and(|flags| flags >> 4 == AUDIO_CODEC_AAC)
+        && tag.get(1) == Some(&AAC_SEQUENCE_HEADER)
+}
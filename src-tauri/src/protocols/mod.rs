@@ -1,3 +1,8 @@
 #![allow(dead_code)]
 
+pub mod amf;
+pub mod congestion;
+pub mod flv;
+pub mod net;
+pub mod rtmp;
 pub mod stream;
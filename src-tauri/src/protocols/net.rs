@@ -0,0 +1,420 @@
+/// 网络输出模块
+///
+/// 推流连接的发送缓冲区大小、低延迟模式和本地绑定地址，保存在配置文件的 `[Output]` 节。
+/// 低延迟模式限制内核中未发送的数据量，积压留在输出线程中，由拥塞控制按优先级丢帧
+use std::{
+    net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::{
+    utils::profile::{get_profile_config, set_profile_config},
+    Result,
+};
+
+/// 不绑定本地地址时保存的值
+const BIND_DEFAULT: &str = "default";
+
+/// 发送缓冲区大小的范围（KB）
+const SEND_BUFFER_RANGE: std::ops::RangeInclusive<u32> = 8..=65536;
+
+/// 低延迟模式下内核中未发送数据的上限
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const LOW_LATENCY_UNSENT_BYTES: u32 = 16 * 1024;
+
+/// 低延迟模式下未设置发送缓冲区大小时使用的大小，用于不支持限制未发送数据的平台
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const LOW_LATENCY_SEND_BUFFER: usize = 64 * 1024;
+
+/// 网络输出设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkSettings {
+    /// 发送缓冲区大小（KB），为 `None` 时使用系统默认值
+    #[serde(default)]
+    pub send_buffer_kb: Option<u32>,
+    /// 是否启用低延迟模式，缩短内核中的发送队列
+    #[serde(default)]
+    pub low_latency: bool,
+    /// 绑定的本地 IP 或网络接口名称，为 `None` 时由系统选择
+    #[serde(default)]
+    pub bind: Option<String>,
+}
+
+/// 本机网络接口的地址
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NetworkInterface {
+    /// 接口名称，如 `eth0`
+    pub name: String,
+    pub address: IpAddr,
+}
+
+impl NetworkSettings {
+    /// 从当前配置文件的 `[Output]` 节读取网络设置
+    pub fn load() -> Self {
+        Self {
+            send_buffer_kb: get_profile_config("Output", "SendBufferSize")
+                .and_then(|value| value.parse::<u32>().ok())
+                .filter(|size| *size > 0),
+            low_latency: get_profile_config("Output", "LowLatencyEnable")
+                .is_some_and(|value| value != "false" && value != "0"),
+            bind: get_profile_config("Output", "BindIP")
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty() && value != BIND_DEFAULT),
+        }
+    }
+
+    /// 检查设置后保存到当前配置文件的 `[Output]` 节
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，设置无效或写入失败时返回错误
+    pub fn save(&self) -> Result<()> {
+        self.validate()?;
+
+        set_profile_config(
+            "Output",
+            "SendBufferSize",
+            &self.send_buffer_kb.unwrap_or(0).to_string(),
+        )?;
+        set_profile_config(
+            "Output",
+            "LowLatencyEnable",
+            if self.low_latency { "true" } else { "false" },
+        )?;
+        set_profile_config(
+            "Output",
+            "BindIP",
+            self.bind.as_deref().unwrap_or(BIND_DEFAULT),
+        )
+    }
+
+    /// 检查设置是否有效
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，发送缓冲区大小超出范围或绑定地址不存在时返回错误
+    pub fn validate(&self) -> Result<()> {
+        if let Some(size) = self.send_buffer_kb {
+            if !SEND_BUFFER_RANGE.contains(&size) {
+                return Err(anyhow!(
+                    "send buffer size must be between {} and {} KB: {}",
+                    SEND_BUFFER_RANGE.start(),
+                    SEND_BUFFER_RANGE.end(),
+                    size
+                ));
+            }
+        }
+        self.bind_addresses()?;
+        if let Some(address) = self
+            .bind
+            .as_deref()
+            .and_then(|bind| bind.parse::<IpAddr>().ok())
+        {
+            let interfaces = network_interfaces()?;
+            if !interfaces.is_empty()
+                && !interfaces
+                    .iter()
+                    .any(|interface| interface.address == address)
+            {
+                return Err(anyhow!("local address not found: {}", address));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 可以绑定的本地地址，未设置绑定时为空
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Vec<IpAddr>>`，接口不存在或没有地址时返回错误
+    fn bind_addresses(&self) -> Result<Vec<IpAddr>> {
+        let Some(bind) = self.bind.as_deref() else {
+            return Ok(Vec::new());
+        };
+        if let Ok(address) = bind.parse::<IpAddr>() {
+            return Ok(vec![address]);
+        }
+
+        let addresses: Vec<IpAddr> = network_interfaces()?
+            .into_iter()
+            .filter(|interface| interface.name == bind)
+            .map(|interface| interface.address)
+            .collect();
+        if addresses.is_empty() {
+            return Err(anyhow!("network interface not found: {}", bind));
+        }
+
+        Ok(addresses)
+    }
+
+    /// 按设置创建套接字并连接
+    fn connect_addr(
+        &self,
+        addr: SocketAddr,
+        local: Option<IpAddr>,
+        timeout: Duration,
+    ) -> Result<TcpStream> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        if let Some(local) = local {
+            socket.bind(&SocketAddr::new(local, 0).into())?;
+        }
+        if let Some(size) = self.send_buffer_kb {
+            socket.set_send_buffer_size(size as usize * 1024)?;
+        }
+        if self.low_latency {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            socket.set_tcp_notsent_lowat(LOW_LATENCY_UNSENT_BYTES)?;
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            if self.send_buffer_kb.is_none() {
+                socket.set_send_buffer_size(LOW_LATENCY_SEND_BUFFER)?;
+            }
+        }
+
+        socket.connect_timeout(&addr.into(), timeout)?;
+        socket.set_tcp_nodelay(true)?;
+
+        Ok(socket.into())
+    }
+}
+
+/// 按网络设置建立 TCP 连接，依次尝试解析出的地址
+///
+/// # 参数
+///
+/// * `host` - 主机名或 IP
+/// * `port` - 端口
+/// * `settings` - 网络设置
+/// * `timeout` - 每个地址的连接超时
+///
+/// # 返回值
+///
+/// 返回 `Result<TcpStream>`，所有地址都无法连接时返回最后一个错误
+pub fn connect(
+    host: &str,
+    port: u16,
+    settings: &NetworkSettings,
+    timeout: Duration,
+) -> Result<TcpStream> {
+    let locals = settings.bind_addresses()?;
+    let mut last_error = anyhow!("no address found for {}", host);
+    for addr in (host, port).to_socket_addrs()? {
+        // 绑定时只连接与本地地址协议族相同的地址
+        let local = locals
+            .iter()
+            .find(|local| local.is_ipv4() == addr.is_ipv4())
+            .copied();
+        if !locals.is_empty() && local.is_none() {
+            last_error = anyhow!(
+                "no local address on {} to reach {}",
+                settings.bind.as_deref().unwrap_or_default(),
+                addr
+            );
+            continue;
+        }
+
+        match settings.connect_addr(addr, local, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e,
+        }
+    }
+
+    Err(last_error)
+}
+
+/// 本机网络接口的地址，不包括 IPv6 链路本地地址
+///
+/// # 返回值
+///
+/// 返回 `Result<Vec<NetworkInterface>>`，无法读取接口列表时返回错误
+#[cfg(unix)]
+pub fn network_interfaces() -> Result<Vec<NetworkInterface>> {
+    use std::{
+        ffi::CStr,
+        net::{Ipv4Addr, Ipv6Addr},
+    };
+
+    let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut list) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let mut interfaces = Vec::new();
+    let mut cursor = list;
+    while !cursor.is_null() {
+        let entry = unsafe { &*cursor };
+        cursor = entry.ifa_next;
+        if entry.ifa_addr.is_null() {
+            continue;
+        }
+
+        let address = match unsafe { (*entry.ifa_addr).sa_family } as libc::c_int {
+            libc::AF_INET => {
+                let addr = unsafe { &*(entry.ifa_addr as *const libc::sockaddr_in) };
+                IpAddr::V4(Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)))
+            }
+            libc::AF_INET6 => {
+                let addr = unsafe { &*(entry.ifa_addr as *const libc::sockaddr_in6) };
+                let address = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+                // 链路本地地址需要指定作用域，不能用于推流
+                if address.is_unicast_link_local() {
+                    continue;
+                }
+                IpAddr::V6(address)
+            }
+            _ => continue,
+        };
+        interfaces.push(NetworkInterface {
+            name: unsafe { CStr::from_ptr(entry.ifa_name) }
+                .to_string_lossy()
+                .into_owned(),
+            address,
+        });
+    }
+    unsafe { libc::freeifaddrs(list) };
+
+    Ok(interfaces)
+}
+
+/// 本机网络接口的地址，当前平台不支持按接口名称绑定，只能绑定 IP
+///
+/// # 返回值
+///
+/// 返回 `Result<Vec<NetworkInterface>>`，始终为空
+#[cfg(not(unix))]
+pub fn network_interfaces() -> Result<Vec<NetworkInterface>> {
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, TcpListener};
+
+    use ini::Ini;
+    use socket2::SockRef;
+
+    use super::*;
+    use crate::utils::profile::{profile_dir, setup_test_profile_config};
+
+    const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    #[test]
+    fn settings_round_trip_through_the_profile() {
+        setup_test_profile_config();
+        let settings = NetworkSettings {
+            send_buffer_kb: Some(512),
+            low_latency: true,
+            bind: Some("127.0.0.1".to_string()),
+        };
+        settings.save().unwrap();
+        assert_eq!(NetworkSettings::load(), settings);
+
+        let file = Ini::load_from_file(profile_dir().unwrap().join("basic.ini")).unwrap();
+        let output = file.section(Some("Output")).unwrap();
+        assert_eq!(output.get("SendBufferSize"), Some("512"));
+        assert_eq!(output.get("LowLatencyEnable"), Some("true"));
+        assert_eq!(output.get("BindIP"), Some("127.0.0.1"));
+
+        // 默认设置保存为 0 和 `default`，读取后仍为默认值
+        NetworkSettings::default().save().unwrap();
+        assert_eq!(get_profile_config("Output", "SendBufferSize").unwrap(), "0");
+        assert_eq!(
+            get_profile_config("Output", "BindIP").unwrap(),
+            BIND_DEFAULT
+        );
+        assert_eq!(NetworkSettings::load(), NetworkSettings::default());
+
+        // 无效的设置不保存
+        let invalid = NetworkSettings {
+            send_buffer_kb: Some(1),
+            ..Default::default()
+        };
+        assert!(invalid.save().is_err());
+        assert_eq!(NetworkSettings::load(), NetworkSettings::default());
+    }
+
+    #[test]
+    fn send_buffer_size_is_limited() {
+        let settings = |size| NetworkSettings {
+            send_buffer_kb: size,
+            ..Default::default()
+        };
+        assert!(settings(None).validate().is_ok());
+        assert!(settings(Some(*SEND_BUFFER_RANGE.start()))
+            .validate()
+            .is_ok());
+        assert!(settings(Some(*SEND_BUFFER_RANGE.end())).validate().is_ok());
+        assert!(settings(Some(SEND_BUFFER_RANGE.start() - 1))
+            .validate()
+            .is_err());
+        assert!(settings(Some(SEND_BUFFER_RANGE.end() + 1))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn bind_must_name_a_local_address_or_interface() {
+        let bind = |bind: &str| NetworkSettings {
+            bind: Some(bind.to_string()),
+            ..Default::default()
+        };
+        assert!(bind("127.0.0.1").validate().is_ok());
+        assert!(bind("no-such-interface0").validate().is_err());
+        if !network_interfaces().unwrap().is_empty() {
+            assert!(bind("203.0.113.7").validate().is_err());
+        }
+        if let Some(interface) = network_interfaces()
+            .unwrap()
+            .into_iter()
+            .find(|interface| interface.address == LOCALHOST)
+        {
+            assert!(bind(&interface.name)
+                .bind_addresses()
+                .unwrap()
+                .contains(&LOCALHOST));
+        }
+    }
+
+    #[test]
+    fn connect_binds_and_applies_socket_options() {
+        let listener = TcpListener::bind((LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let settings = NetworkSettings {
+            send_buffer_kb: Some(64),
+            low_latency: true,
+            bind: Some("127.0.0.1".to_string()),
+        };
+
+        let stream = connect("127.0.0.1", port, &settings, Duration::from_secs(5)).unwrap();
+        let (accepted, peer) = listener.accept().unwrap();
+        assert_eq!(stream.local_addr().unwrap().ip(), LOCALHOST);
+        assert_eq!(peer, stream.local_addr().unwrap());
+        drop(accepted);
+
+        let socket = SockRef::from(&stream);
+        assert!(socket.tcp_nodelay().unwrap());
+        // Linux 会把设置的发送缓冲区大小加倍
+        let size = socket.send_buffer_size().unwrap();
+        assert!((64 * 1024..=128 * 1024).contains(&size), "{size}");
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(
+            socket.tcp_notsent_lowat().unwrap(),
+            LOW_LATENCY_UNSENT_BYTES
+        );
+
+        // 未启用时保留系统默认值
+        let stream = connect(
+            "127.0.0.1",
+            port,
+            &NetworkSettings::default(),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(SockRef::from(&stream).tcp_notsent_lowat().unwrap(), 0);
+    }
+}
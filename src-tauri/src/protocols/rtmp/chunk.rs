@@ -0,0 +1,371 @@
+/// RTMP 握手与分块模块
+///
+/// 消息被切分为块（chunk）在连接上传输，每个块流记住上一条消息的头部，
+/// 后续块可以省略相同的字段。这里只实现简单握手，不校验摘要
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
+
+use anyhow::anyhow;
+
+use crate::Result;
+
+/// 握手包 C1/S1/C2/S2 的长度
+pub const HANDSHAKE_SIZE: usize = 1536;
+
+/// 协议版本
+const RTMP_VERSION: u8 = 3;
+
+/// 协议规定的初始块大小
+pub const DEFAULT_CHUNK_SIZE: usize = 128;
+
+/// 单条消息的最大长度，消息头中只有 3 字节
+const MAX_MESSAGE_SIZE: usize = 0xff_ffff;
+
+/// 时间戳达到该值时使用扩展时间戳
+const EXTENDED_TIMESTAMP: u32 = 0xff_ffff;
+
+/// 消息类型
+pub const MSG_SET_CHUNK_SIZE: u8 = 1;
+pub const MSG_ABORT: u8 = 2;
+pub const MSG_ACKNOWLEDGEMENT: u8 = 3;
+pub const MSG_USER_CONTROL: u8 = 4;
+pub const MSG_WINDOW_ACK_SIZE: u8 = 5;
+pub const MSG_SET_PEER_BANDWIDTH: u8 = 6;
+pub const MSG_AUDIO: u8 = 8;
+pub const MSG_VIDEO: u8 = 9;
+pub const MSG_DATA_AMF0: u8 = 18;
+pub const MSG_COMMAND_AMF0: u8 = 20;
+
+/// 用户控制事件
+pub const USER_CONTROL_STREAM_BEGIN: u16 = 0;
+pub const USER_CONTROL_PING_REQUEST: u16 = 6;
+pub const USER_CONTROL_PING_RESPONSE: u16 = 7;
+
+/// 块流 ID
+pub const CSID_PROTOCOL: u32 = 2;
+pub const CSID_COMMAND: u32 = 3;
+pub const CSID_AUDIO: u32 = 4;
+pub const CSID_DATA: u32 = 5;
+pub const CSID_VIDEO: u32 = 6;
+
+/// RTMP 消息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtmpMessage {
+    pub type_id: u8,
+    /// 消息流 ID
+    pub stream_id: u32,
+    /// 时间戳（毫秒）
+    pub timestamp: u32,
+    pub payload: Vec<u8>,
+}
+
+impl RtmpMessage {
+    /// 创建协议控制消息，使用消息流 0
+    ///
+    /// # 参数
+    ///
+    /// * `type_id` - 消息类型
+    /// * `payload` - 消息内容
+    pub fn control(type_id: u8, payload: Vec<u8>) -> Self {
+        Self {
+            type_id,
+            stream_id: 0,
+            timestamp: 0,
+            payload,
+        }
+    }
+
+    /// 创建用户控制消息
+    ///
+    /// # 参数
+    ///
+    /// * `event` - 事件类型
+    /// * `data` - 事件数据
+    pub fn user_control(event: u16, data: u32) -> Self {
+        let mut payload = event.to_be_bytes().to_vec();
+        payload.extend_from_slice(&data.to_be_bytes());
+        Self::control(MSG_USER_CONTROL, payload)
+    }
+
+    /// 读取内容开头的 32 位整数，用于块大小、确认序号等控制消息
+    pub fn read_u32(&self) -> Option<u32> {
+        let bytes = self.payload.get(..4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// 块流上一条消息的头部，用于解析省略了字段的块
+#[derive(Default)]
+struct ChunkStream {
+    timestamp: u32,
+    delta: u32,
+    length: usize,
+    type_id: u8,
+    stream_id: u32,
+    /// 是否使用扩展时间戳
+    extended: bool,
+    /// 未收完的消息内容
+    buffer: Vec<u8>,
+}
+
+/// 块读取器
+pub struct ChunkReader {
+    chunk_size: usize,
+    streams: HashMap<u32, ChunkStream>,
+    /// 已读取的字节数，用于发送确认
+    bytes_read: u64,
+}
+
+impl Default for ChunkReader {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            streams: HashMap::new(),
+            bytes_read: 0,
+        }
+    }
+}
+
+impl ChunkReader {
+    /// 已读取的字节数
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    fn read_exact(&mut self, reader: &mut impl Read, buf: &mut [u8]) -> Result<()> {
+        reader.read_exact(buf)?;
+        self.bytes_read += buf.len() as u64;
+        Ok(())
+    }
+
+    fn read_u24(&mut self, reader: &mut impl Read) -> Result<u32> {
+        let mut buf = [0; 3];
+        self.read_exact(reader, &mut buf)?;
+        Ok(u32::from_be_bytes([0, buf[0], buf[1], buf[2]]))
+    }
+
+    fn read_u32(&mut self, reader: &mut impl Read) -> Result<u32> {
+        let mut buf = [0; 4];
+        self.read_exact(reader, &mut buf)?;
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    /// 读取一条完整的消息，收到设置块大小的消息时同时更新读取块大小
+    ///
+    /// # 参数
+    ///
+    /// * `reader` - 连接
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<RtmpMessage>`，连接关闭或数据无效时返回错误
+    pub fn read_message(&mut self, reader: &mut impl Read) -> Result<RtmpMessage> {
+        loop {
+            let mut byte = [0; 1];
+            self.read_exact(reader, &mut byte)?;
+            let fmt = byte[0] >> 6;
+            let csid = match byte[0] & 0x3f {
+                0 => {
+                    self.read_exact(reader, &mut byte)?;
+                    64 + byte[0] as u32
+                }
+                1 => {
+                    let mut buf = [0; 2];
+                    self.read_exact(reader, &mut buf)?;
+                    64 + buf[0] as u32 + buf[1] as u32 * 256
+                }
+                csid => csid as u32,
+            };
+
+            let mut stream = self.streams.remove(&csid).unwrap_or_default();
+            let starting = stream.buffer.is_empty();
+            if fmt <= 2 {
+                let timestamp = self.read_u24(reader)?;
+                if fmt <= 1 {
+                    stream.length = self.read_u24(reader)? as usize;
+                    self.read_exact(reader, &mut byte)?;
+                    stream.type_id = byte[0];
+                }
+                if fmt == 0 {
+                    let mut buf = [0; 4];
+                    self.read_exact(reader, &mut buf)?;
+                    stream.stream_id = u32::from_le_bytes(buf);
+                }
+                stream.extended = timestamp == EXTENDED_TIMESTAMP;
+                let timestamp = if stream.extended {
+                    self.read_u32(reader)?
+                } else {
+                    timestamp
+                };
+                if fmt == 0 {
+                    stream.timestamp = timestamp;
+                    stream.delta = 0;
+                } else {
+                    stream.delta = timestamp;
+                    stream.timestamp = stream.timestamp.wrapping_add(timestamp);
+                }
+            } else {
+                if stream.extended {
+                    self.read_u32(reader)?;
+                }
+                if starting {
+                    stream.timestamp = stream.timestamp.wrapping_add(stream.delta);
+                }
+            }
+
+            if stream.length > MAX_MESSAGE_SIZE {
+                return Err(anyhow!("RTMP message too large: {}", stream.length));
+            }
+            let remaining = stream.length - stream.buffer.len();
+            let size = remaining.min(self.chunk_size);
+            let start = stream.buffer.len();
+            stream.buffer.resize(start + size, 0);
+            let mut data = std::mem::take(&mut stream.buffer);
+            self.read_exact(reader, &mut data[start..])?;
+
+            if data.len() < stream.length {
+                stream.buffer = data;
+                self.streams.insert(csid, stream);
+                continue;
+            }
+
+            let message = RtmpMessage {
+                type_id: stream.type_id,
+                stream_id: stream.stream_id,
+                timestamp: stream.timestamp,
+                payload: data,
+            };
+            self.streams.insert(csid, stream);
+
+            if message.type_id == MSG_SET_CHUNK_SIZE {
+                let size = message.read_u32().unwrap_or_default() & 0x7fff_ffff;
+                if size == 0 {
+                    return Err(anyhow!("invalid RTMP chunk size"));
+                }
+                self.chunk_size = size as usize;
+            }
+
+            return Ok(message);
+        }
+    }
+}
+
+/// 块写入器，每条消息的第一个块使用完整头部，后续块只有一个字节的头部
+pub struct ChunkWriter {
+    chunk_size: usize,
+}
+
+impl Default for ChunkWriter {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+impl ChunkWriter {
+    /// 修改写入块大小，需要先把设置块大小的消息发给对方
+    pub fn set_chunk_size(&mut self, size: usize) {
+        self.chunk_size = size.max(1);
+    }
+
+    /// 把消息切分为块追加到 `output`
+    ///
+    /// # 参数
+    ///
+    /// * `output` - 输出缓冲
+    /// * `csid` - 块流 ID，2-63
+    /// * `message` - 消息
+    pub fn write(&self, output: &mut Vec<u8>, csid: u32, message: &RtmpMessage) {
+        let csid = csid.clamp(2, 63) as u8;
+        let extended = message.timestamp >= EXTENDED_TIMESTAMP;
+        let timestamp = message.timestamp.min(EXTENDED_TIMESTAMP).to_be_bytes();
+        let length = (message.payload.len() as u32).to_be_bytes();
+
+        output.push(csid);
+        output.extend_from_slice(&timestamp[1..]);
+        output.extend_from_slice(&length[1..]);
+        output.push(message.type_id);
+        output.extend_from_slice(&message.stream_id.to_le_bytes());
+        if extended {
+            output.extend_from_slice(&message.timestamp.to_be_bytes());
+        }
+
+        for (i, chunk) in message.payload.chunks(self.chunk_size).enumerate() {
+            if i > 0 {
+                output.push(0xc0 | csid);
+                if extended {
+                    output.extend_from_slice(&message.timestamp.to_be_bytes());
+                }
+            }
+            output.extend_from_slice(chunk);
+        }
+    }
+}
+
+/// 握手包的随机数据
+fn handshake_packet() -> Vec<u8> {
+    let mut packet = vec![0; HANDSHAKE_SIZE];
+    for chunk in packet[8..].chunks_mut(16) {
+        let random = uuid::Uuid::new_v4();
+        chunk.copy_from_slice(&random.as_bytes()[..chunk.len()]);
+    }
+
+    packet
+}
+
+/// 客户端握手：发送 C0/C1，收到 S0/S1/S2 后回应 C2
+///
+/// # 参数
+///
+/// * `stream` - 连接
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，服务器版本不支持或连接断开时返回错误
+pub fn client_handshake(stream: &mut (impl Read + Write)) -> Result<()> {
+    let mut c0c1 = vec![RTMP_VERSION];
+    c0c1.extend_from_slice(&handshake_packet());
+    stream.write_all(&c0c1)?;
+
+    let mut s0s1 = vec![0; 1 + HANDSHAKE_SIZE];
+    stream.read_exact(&mut s0s1)?;
+    if s0s1[0] != RTMP_VERSION {
+        return Err(anyhow!("unsupported RTMP version: {}", s0s1[0]));
+    }
+    stream.write_all(&s0s1[1..])?;
+
+    let mut s2 = vec![0; HANDSHAKE_SIZE];
+    stream.read_exact(&mut s2)?;
+
+    Ok(())
+}
+
+/// 服务器握手：收到 C0/C1 后发送 S0/S1/S2，再读取 C2
+///
+/// # 参数
+///
+/// * `stream` - 连接
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，客户端版本不支持或连接断开时返回错误
+pub fn server_handshake(stream: &mut (impl Read + Write)) -> Result<()> {
+    let mut c0c1 = vec![0; 1 + HANDSHAKE_SIZE];
+    stream.read_exact(&mut c0c1)?;
+    if c0c1[0] != RTMP_VERSION {
+        return Err(anyhow!("unsupported RTMP version: {}", c0c1[0]));
+    }
+
+    let mut response = vec![RTMP_VERSION];
+    response.extend_from_slice(&handshake_packet());
+    response.extend_from_slice(&c0c1[1..]);
+    stream.write_all(&response)?;
+
+    let mut c2 = vec![0; HANDSHAKE_SIZE];
+    stream.read_exact(&mut c2)?;
+
+    Ok(())
+}
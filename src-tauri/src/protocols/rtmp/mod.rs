@@ -0,0 +1,540 @@
+/// RTMP 推流客户端模块
+///
+/// 完成握手和 connect/createStream/publish 之后发送 FLV 格式的音视频消息。
+/// 连接建立后由读取线程处理服务器的确认、ping 和错误通知，需要回复的消息交给发送端在下一次发送时带上
+pub mod chunk;
+pub mod server;
+
+use std::{
+    io::Write,
+    net::{Shutdown, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use log::{debug, warn};
+
+use crate::{
+    protocols::{
+        amf::{decode, encode, Amf0Value},
+        rtmp::chunk::{
+            client_handshake, ChunkReader, ChunkWriter, RtmpMessage, CSID_AUDIO, CSID_COMMAND,
+            CSID_DATA, CSID_PROTOCOL, CSID_VIDEO, MSG_ACKNOWLEDGEMENT, MSG_AUDIO, MSG_COMMAND_AMF0,
+            MSG_DATA_AMF0, MSG_SET_CHUNK_SIZE, MSG_SET_PEER_BANDWIDTH, MSG_USER_CONTROL, MSG_VIDEO,
+            MSG_WINDOW_ACK_SIZE, USER_CONTROL_PING_REQUEST, USER_CONTROL_PING_RESPONSE,
+        },
+    },
+    Result,
+};
+
+/// 默认端口
+pub const DEFAULT_RTMP_PORT: u16 = 1935;
+
+/// 推流时使用的块大小
+const OUTGOING_CHUNK_SIZE: usize = 4096;
+
+/// 建立推流过程中等待服务器响应的超时
+const SETUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 推流地址
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtmpUrl {
+    pub host: String,
+    pub port: u16,
+    /// 应用名，即地址中主机之后的路径
+    pub app: String,
+    /// connect 命令中的 tcUrl
+    pub tc_url: String,
+    /// 推流码，即发布的流名称
+    pub key: String,
+}
+
+impl RtmpUrl {
+    /// 解析服务器地址，如 `rtmp://live.example.com/app`
+    ///
+    /// # 参数
+    ///
+    /// * `server` - 服务器地址
+    /// * `key` - 推流码
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，地址无效或协议不是 `rtmp` 时返回错误
+    pub fn parse(server: &str, key: &str) -> Result<Self> {
+        let server = server.trim().trim_end_matches('/');
+        let (scheme, rest) = server
+            .split_once("://")
+            .ok_or(anyhow!("invalid stream server: {}", server))?;
+        if !scheme.eq_ignore_ascii_case("rtmp") {
+            return Err(anyhow!("unsupported stream protocol: {}", scheme));
+        }
+
+        let (authority, app) = rest.split_once('/').unwrap_or((rest, ""));
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|_| anyhow!("invalid stream server port: {}", server))?;
+                (host, port)
+            }
+            _ => (authority, DEFAULT_RTMP_PORT),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() || app.is_empty() {
+            return Err(anyhow!("invalid stream server: {}", server));
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            app: app.to_string(),
+            tc_url: server.to_string(),
+            key: key.trim().to_string(),
+        })
+    }
+
+    /// 用于日志的地址，不包含推流码
+    pub fn display(&self) -> String {
+        self.tc_url.clone()
+    }
+}
+
+/// 读取线程与发送端共享的连接状态
+#[derive(Debug, Default)]
+pub struct PeerState {
+    /// 连接已被服务器关闭或出错
+    pub closed: AtomicBool,
+    /// 服务器确认收到的字节数
+    pub acked_bytes: AtomicU64,
+    /// 服务器通知的确认窗口，服务器通常也按该窗口确认收到的数据，为 0 时未通知
+    pub ack_window: AtomicU64,
+    /// 服务器报告的错误
+    pub error: Mutex<Option<String>>,
+}
+
+/// RTMP 推流连接
+pub struct RtmpPublisher {
+    stream: TcpStream,
+    writer: ChunkWriter,
+    /// 发布的消息流 ID
+    stream_id: u32,
+    /// 已发送的字节数
+    bytes_sent: u64,
+    /// 读取线程要求回复的消息
+    replies: Receiver<RtmpMessage>,
+    peer: Arc<PeerState>,
+    /// 待发送的数据
+    buffer: Vec<u8>,
+}
+
+/// 从 `_result`/`_error`/`onStatus` 命令中取出命令名、事务 ID 和参数
+fn parse_command(message: &RtmpMessage) -> Option<(String, f64, Vec<Amf0Value>)> {
+    let mut values = decode(&message.payload).ok()?.into_iter();
+    let name = values.next()?.as_str()?.to_string();
+    let transaction = values.next()?.as_number().unwrap_or_default();
+    Some((name, transaction, values.collect()))
+}
+
+/// 命令参数中的 `code`/`description` 信息
+fn status_info(values: &[Amf0Value]) -> (String, String) {
+    let info = values.iter().find(|value| value.property("code").is_some());
+    let field = |key: &str| {
+        info.and_then(|info| info.property(key))
+            .and_then(Amf0Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    (field("code"), field("description"))
+}
+
+impl RtmpPublisher {
+    /// 在已建立的 TCP 连接上握手并开始发布
+    ///
+    /// # 参数
+    ///
+    /// * `stream` - 已连接到服务器的 TCP 连接，调用方负责设置套接字选项
+    /// * `url` - 推流地址
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，握手失败、服务器拒绝或超时时返回错误
+    pub fn publish(mut stream: TcpStream, url: &RtmpUrl) -> Result<Self> {
+        stream.set_read_timeout(Some(SETUP_TIMEOUT))?;
+        stream.set_write_timeout(Some(SETUP_TIMEOUT))?;
+        client_handshake(&mut stream)?;
+
+        let (sender, replies) = channel();
+        let mut publisher = Self {
+            stream,
+            writer: ChunkWriter::default(),
+            stream_id: 0,
+            bytes_sent: 0,
+            replies,
+            peer: Arc::new(PeerState::default()),
+            buffer: vec![],
+        };
+        let mut reader = ChunkReader::default();
+
+        publisher.queue(
+            CSID_PROTOCOL,
+            RtmpMessage::control(
+                MSG_SET_CHUNK_SIZE,
+                (OUTGOING_CHUNK_SIZE as u32).to_be_bytes().to_vec(),
+            ),
+        );
+        publisher.writer.set_chunk_size(OUTGOING_CHUNK_SIZE);
+        publisher.command(
+            0,
+            &[
+                Amf0Value::string("connect"),
+                Amf0Value::Number(1.),
+                Amf0Value::object(&[
+                    ("app", Amf0Value::string(&url.app)),
+                    ("type", Amf0Value::string("nonprivate")),
+                    (
+                        "flashVer",
+                        Amf0Value::string("FMLE/3.0 (compatible; FMSc/1.0)"),
+                    ),
+                    ("swfUrl", Amf0Value::string(&url.tc_url)),
+                    ("tcUrl", Amf0Value::string(&url.tc_url)),
+                ]),
+            ],
+        );
+        publisher.flush()?;
+        publisher.wait_result(&mut reader, 1.)?;
+
+        let key = Amf0Value::string(&url.key);
+        publisher.command(
+            0,
+            &[
+                Amf0Value::string("releaseStream"),
+                Amf0Value::Number(2.),
+                Amf0Value::Null,
+                key.clone(),
+            ],
+        );
+        publisher.command(
+            0,
+            &[
+                Amf0Value::string("FCPublish"),
+                Amf0Value::Number(3.),
+                Amf0Value::Null,
+                key.clone(),
+            ],
+        );
+        publisher.command(
+            0,
+            &[
+                Amf0Value::string("createStream"),
+                Amf0Value::Number(4.),
+                Amf0Value::Null,
+            ],
+        );
+        publisher.flush()?;
+        let result = publisher.wait_result(&mut reader, 4.)?;
+        publisher.stream_id = result
+            .iter()
+            .find_map(Amf0Value::as_number)
+            .ok_or(anyhow!("createStream returned no stream id"))?
+            as u32;
+
+        publisher.command(
+            publisher.stream_id,
+            &[
+                Amf0Value::string("publish"),
+                Amf0Value::Number(5.),
+                Amf0Value::Null,
+                key,
+                Amf0Value::string("live"),
+            ],
+        );
+        publisher.flush()?;
+        loop {
+            let message = publisher.read_setup_message(&mut reader)?;
+            let Some((name, _, values)) = parse_command(&message) else {
+                continue;
+            };
+            if name == "onStatus" {
+                let (code, description) = status_info(&values);
+                if code == "NetStream.Publish.Start" {
+                    break;
+                }
+                if code.contains("Failed") || code.contains("Bad") || code.contains("Error") {
+                    return Err(anyhow!("publish rejected: {} {}", code, description));
+                }
+            } else if name == "_error" {
+                let (code, description) = status_info(&values);
+                return Err(anyhow!("publish rejected: {} {}", code, description));
+            }
+        }
+
+        publisher.stream.set_read_timeout(None)?;
+        publisher.stream.set_write_timeout(None)?;
+        publisher.spawn_reader(reader, sender)?;
+        debug!("RTMP publishing started: {}", url.display());
+
+        Ok(publisher)
+    }
+
+    /// 把消息追加到待发送数据
+    fn queue(&mut self, csid: u32, message: RtmpMessage) {
+        self.writer.write(&mut self.buffer, csid, &message);
+    }
+
+    /// 追加命令消息
+    fn command(&mut self, stream_id: u32, values: &[Amf0Value]) {
+        self.queue(
+            CSID_COMMAND,
+            RtmpMessage {
+                type_id: MSG_COMMAND_AMF0,
+                stream_id,
+                timestamp: 0,
+                payload: encode(values),
+            },
+        );
+    }
+
+    /// 发送待发送数据
+    fn flush(&mut self) -> Result<usize> {
+        let size = self.buffer.len();
+        self.stream.write_all(&self.buffer)?;
+        self.bytes_sent += size as u64;
+        self.buffer.clear();
+
+        Ok(size)
+    }
+
+    /// 建立推流过程中读取一条消息，顺便回应服务器的控制消息
+    fn read_setup_message(&mut self, reader: &mut ChunkReader) -> Result<RtmpMessage> {
+        let message = reader.read_message(&mut self.stream)?;
+        if let Some(reply) = control_reply(&message) {
+            self.queue(CSID_PROTOCOL, reply);
+            self.flush()?;
+        }
+
+        Ok(message)
+    }
+
+    /// 等待指定事务的 `_result`
+    fn wait_result(
+        &mut self,
+        reader: &mut ChunkReader,
+        transaction: f64,
+    ) -> Result<Vec<Amf0Value>> {
+        loop {
+            let message = self.read_setup_message(reader)?;
+            if message.type_id != MSG_COMMAND_AMF0 {
+                continue;
+            }
+            match parse_command(&message) {
+                Some((name, id, values)) if name == "_result" && id == transaction => {
+                    return Ok(values)
+                }
+                Some((name, id, values)) if name == "_error" && id == transaction => {
+                    let (code, description) = status_info(&values);
+                    return Err(anyhow!("RTMP command failed: {} {}", code, description));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// 启动读取线程
+    fn spawn_reader(&self, mut reader: ChunkReader, replies: Sender<RtmpMessage>) -> Result<()> {
+        let mut stream = self.stream.try_clone()?;
+        let peer = self.peer.clone();
+        let mut window = 0u64;
+        let mut acked = 0u64;
+
+        thread::Builder::new()
+            .name("rtmp-reader".to_string())
+            .spawn(move || {
+                let error = loop {
+                    let message = match reader.read_message(&mut stream) {
+                        Ok(message) => message,
+                        Err(e) => break e.to_string(),
+                    };
+                    match message.type_id {
+                        MSG_ACKNOWLEDGEMENT => {
+                            let sequence = message.read_u32().unwrap_or_default() as u64;
+                            // 序号是 32 位的，超过后回绕
+                            let previous = peer.acked_bytes.load(Ordering::Relaxed);
+                            let wraps = previous >> 32;
+                            let mut total = (wraps << 32) | sequence;
+                            if total < previous {
+                                total += 1 << 32;
+                            }
+                            peer.acked_bytes.store(total, Ordering::Relaxed);
+                        }
+                        MSG_WINDOW_ACK_SIZE => {
+                            window = message.read_u32().unwrap_or_default() as u64;
+                            peer.ack_window.store(window, Ordering::Relaxed);
+                        }
+                        MSG_COMMAND_AMF0 => {
+                            if let Some((name, _, values)) = parse_command(&message) {
+                                let (code, description) = status_info(&values);
+                                if name == "onStatus" && code.starts_with("NetStream.Publish") {
+                                    warn!("RTMP server status: {} {}", code, description);
+                                }
+                                if name == "_error" || code.contains("Failed") {
+                                    break format!("{} {}", code, description);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                    if let Some(reply) = control_reply(&message) {
+                        let _ = replies.send(reply);
+                    }
+                    // 按服务器要求的窗口回复确认
+                    if window > 0 && reader.bytes_read() - acked >= window {
+                        acked = reader.bytes_read();
+                        let _ = replies.send(RtmpMessage::control(
+                            MSG_ACKNOWLEDGEMENT,
+                            (acked as u32).to_be_bytes().to_vec(),
+                        ));
+                    }
+                };
+
+                debug!("RTMP connection closed: {}", error);
+                *peer.error.lock().unwrap() = Some(error);
+                peer.closed.store(true, Ordering::Relaxed);
+            })?;
+
+        Ok(())
+    }
+
+    /// 连接状态
+    pub fn peer(&self) -> &Arc<PeerState> {
+        &self.peer
+    }
+
+    /// 已发送的字节数
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// 底层 TCP 连接
+    pub fn socket(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// 连接是否已断开，断开时返回原因
+    fn check_closed(&self) -> Result<()> {
+        if self.peer.closed.load(Ordering::Relaxed) {
+            let error = self.peer.error.lock().unwrap().clone().unwrap_or_default();
+            return Err(anyhow!("RTMP connection closed: {}", error));
+        }
+
+        Ok(())
+    }
+
+    /// 发送一条媒体或数据消息，先带上读取线程要求的回复
+    fn send(&mut self, csid: u32, type_id: u8, timestamp: u32, payload: Vec<u8>) -> Result<usize> {
+        self.check_closed()?;
+        while let Ok(reply) = self.replies.try_recv() {
+            self.queue(CSID_PROTOCOL, reply);
+        }
+        let message = RtmpMessage {
+            type_id,
+            stream_id: self.stream_id,
+            timestamp,
+            payload,
+        };
+        self.queue(csid, message);
+
+        self.flush()
+    }
+
+    /// 发送 `@setDataFrame` 元数据
+    ///
+    /// # 参数
+    ///
+    /// * `values` - AMF0 值
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<usize>`，表示发送的字节数
+    pub fn send_metadata(&mut self, values: &[Amf0Value]) -> Result<usize> {
+        self.send(CSID_DATA, MSG_DATA_AMF0, 0, encode(values))
+    }
+
+    /// 发送视频标签
+    ///
+    /// # 参数
+    ///
+    /// * `timestamp` - 解码时间戳（毫秒）
+    /// * `tag` - FLV 视频标签数据
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<usize>`，表示发送的字节数
+    pub fn send_video(&mut self, timestamp: u32, tag: Vec<u8>) -> Result<usize> {
+        self.send(CSID_VIDEO, MSG_VIDEO, timestamp, tag)
+    }
+
+    /// 发送音频标签
+    ///
+    /// # 参数
+    ///
+    /// * `timestamp` - 时间戳（毫秒）
+    /// * `tag` - FLV 音频标签数据
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<usize>`，表示发送的字节数
+    pub fn send_audio(&mut self, timestamp: u32, tag: Vec<u8>) -> Result<usize> {
+        self.send(CSID_AUDIO, MSG_AUDIO, timestamp, tag)
+    }
+
+    /// 停止发布并关闭连接
+    pub fn close(mut self) {
+        let stream_id = self.stream_id;
+        self.command(
+            stream_id,
+            &[
+                Amf0Value::string("deleteStream"),
+                Amf0Value::Number(6.),
+                Amf0Value::Null,
+                Amf0Value::Number(stream_id as f64),
+            ],
+        );
+        let _ = self.flush();
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+impl Drop for RtmpPublisher {
+    fn drop(&mut self) {
+        // 让读取线程退出
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// 需要回复的控制消息：ping 请求和对端带宽设置
+fn control_reply(message: &RtmpMessage) -> Option<RtmpMessage> {
+    match message.type_id {
+        MSG_USER_CONTROL => {
+            let event = u16::from_be_bytes([*message.payload.first()?, *message.payload.get(1)?]);
+            let data = message.payload.get(2..6)?;
+            (event == USER_CONTROL_PING_REQUEST).then(|| {
+                RtmpMessage::user_control(
+                    USER_CONTROL_PING_RESPONSE,
+                    u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+                )
+            })
+        }
+        MSG_SET_PEER_BANDWIDTH => Some(RtmpMessage::control(
+            MSG_WINDOW_ACK_SIZE,
+            message.payload.get(..4)?.to_vec(),
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,443 @@
+/// 本地 RTMP 接收端模块
+///
+/// 只接受推流，不转发也不保存数据，记录每个连接收到的消息，
+/// 用作带宽测试的本地替身和多路推流的回环接收端
+use std::{
+    io::{ErrorKind, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use log::{debug, error};
+use serde::Serialize;
+
+use crate::{
+    protocols::{
+        amf::{decode, encode, Amf0Value},
+        flv::{is_audio_sequence_header, parse_video_tag},
+        rtmp::chunk::{
+            server_handshake, ChunkReader, ChunkWriter, RtmpMessage, CSID_COMMAND, CSID_PROTOCOL,
+            MSG_ACKNOWLEDGEMENT, MSG_AUDIO, MSG_COMMAND_AMF0, MSG_DATA_AMF0,
+            MSG_SET_PEER_BANDWIDTH, MSG_VIDEO, MSG_WINDOW_ACK_SIZE, USER_CONTROL_STREAM_BEGIN,
+        },
+    },
+    Result,
+};
+
+/// 默认的确认窗口大小
+pub const DEFAULT_ACK_WINDOW: u32 = 2_500_000;
+
+/// 接收端分配的消息流 ID
+const PUBLISH_STREAM_ID: u32 = 1;
+
+/// 等待新连接时的轮询间隔
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(20);
+
+/// 一个推流连接收到的数据
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReceivedStream {
+    /// connect 命令中的应用名
+    pub app: String,
+    /// publish 命令中的流名称，即推流码
+    pub key: String,
+    /// 是否已开始发布
+    pub publishing: bool,
+    /// 连接是否已关闭
+    pub closed: bool,
+    /// 是否收到 `onMetaData`
+    pub metadata: bool,
+    /// AVC 序列头数量
+    pub sequence_headers: u64,
+    /// 关键帧数量
+    pub keyframes: u64,
+    /// 视频帧数量，不含序列头
+    pub video_frames: u64,
+    /// AAC 序列头数量
+    pub audio_sequence_headers: u64,
+    /// 音频帧数量，不含序列头
+    pub audio_frames: u64,
+    /// 收到的字节总数
+    pub bytes: u64,
+    /// 第一个视频帧是否为关键帧
+    pub starts_with_keyframe: bool,
+    /// 视频帧的时间戳（毫秒）
+    pub video_timestamps: Vec<u32>,
+    /// 音频消息的时间戳（毫秒）
+    pub audio_timestamps: Vec<u32>,
+    /// 音视频消息的到达顺序，`true` 为视频
+    pub order: Vec<bool>,
+    /// 从发布开始到最后一条消息的时长（毫秒）
+    pub duration_ms: u64,
+}
+
+/// 接收端共享状态
+#[derive(Default)]
+struct ServerState {
+    streams: Vec<ReceivedStream>,
+    /// 所有连接的副本，用于断开连接
+    connections: Vec<TcpStream>,
+    /// 每个连接的读取速率上限（字节/秒）
+    read_rate: Option<u64>,
+}
+
+/// 本地 RTMP 接收端，丢弃时停止监听并断开所有连接
+pub struct RtmpServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    state: Arc<Mutex<ServerState>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RtmpServer {
+    /// 监听指定地址，如 `127.0.0.1:0`
+    ///
+    /// # 参数
+    ///
+    /// * `addr` - 监听地址，端口为 0 时自动分配
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，地址无法监听时返回错误
+    pub fn bind(addr: &str) -> Result<Self> {
+        Self::bind_with_window(addr, DEFAULT_ACK_WINDOW)
+    }
+
+    /// 监听指定地址，使用指定的确认窗口
+    ///
+    /// # 参数
+    ///
+    /// * `addr` - 监听地址
+    /// * `window` - 确认窗口大小（字节），每收到这么多数据回复一次确认
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，地址无法监听时返回错误
+    pub fn bind_with_window(addr: &str, window: u32) -> Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let state = Arc::new(Mutex::new(ServerState::default()));
+
+        let stopped = stop.clone();
+        let shared = state.clone();
+        let thread = thread::Builder::new()
+            .name("rtmp-server".to_string())
+            .spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    let stream = match listener.accept() {
+                        Ok((stream, _)) => stream,
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                            thread::sleep(ACCEPT_INTERVAL);
+                            continue;
+                        }
+                        Err(e) => {
+                            error!("RTMP server accept failed: {}", e);
+                            continue;
+                        }
+                    };
+
+                    let index = {
+                        let mut state = shared.lock().unwrap();
+                        if let Ok(clone) = stream.try_clone() {
+                            state.connections.push(clone);
+                        }
+                        state.streams.push(ReceivedStream::default());
+                        state.streams.len() - 1
+                    };
+                    let state = shared.clone();
+                    let spawned = thread::Builder::new()
+                        .name("rtmp-session".to_string())
+                        .spawn(move || {
+                            if let Err(e) = run_session(stream, window, &state, index) {
+                                debug!("RTMP session {} ended: {}", index, e);
+                            }
+                            state.lock().unwrap().streams[index].closed = true;
+                        });
+                    if let Err(e) = spawned {
+                        error!("failed to spawn RTMP session: {}", e);
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            addr,
+            stop,
+            state,
+            thread: Some(thread),
+        })
+    }
+
+    /// 监听地址
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 指定应用名的推流地址
+    ///
+    /// # 参数
+    ///
+    /// * `app` - 应用名
+    pub fn url(&self, app: &str) -> String {
+        format!("rtmp://{}/{}", self.addr, app)
+    }
+
+    /// 所有连接收到的数据，按连接顺序排列
+    pub fn streams(&self) -> Vec<ReceivedStream> {
+        self.state.lock().unwrap().streams.clone()
+    }
+
+    /// 限制每个连接的读取速率，用于模拟上行拥塞
+    ///
+    /// # 参数
+    ///
+    /// * `rate` - 读取速率上限（字节/秒），为 `None` 时不限制
+    pub fn set_read_rate(&self, rate: Option<u64>) {
+        self.state.lock().unwrap().read_rate = rate;
+    }
+
+    /// 断开所有连接，用于模拟服务器中断
+    pub fn disconnect_all(&self) {
+        for connection in self.state.lock().unwrap().connections.drain(..) {
+            let _ = connection.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl Drop for RtmpServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.disconnect_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// 命令的 `_result`/`onStatus` 响应
+fn command(stream_id: u32, values: &[Amf0Value]) -> RtmpMessage {
+    RtmpMessage {
+        type_id: MSG_COMMAND_AMF0,
+        stream_id,
+        timestamp: 0,
+        payload: encode(values),
+    }
+}
+
+/// 处理一个推流连接
+fn run_session(
+    mut stream: TcpStream,
+    window: u32,
+    state: &Mutex<ServerState>,
+    index: usize,
+) -> Result<()> {
+    stream.set_nodelay(true)?;
+    server_handshake(&mut stream)?;
+
+    let mut reader = ChunkReader::default();
+    let writer = ChunkWriter::default();
+    let mut acked = 0u64;
+    let mut started: Option<Instant> = None;
+    // 限速的速率、开始时间和开始时已读取的字节数
+    let mut throttle: Option<(u64, Instant, u64)> = None;
+
+    loop {
+        let message = reader.read_message(&mut stream)?;
+        let mut output = vec![];
+
+        match message.type_id {
+            MSG_COMMAND_AMF0 => {
+                let values = decode(&message.payload)?;
+                let name = values
+                    .first()
+                    .and_then(Amf0Value::as_str)
+                    .unwrap_or_default();
+                let transaction = values
+                    .get(1)
+                    .and_then(Amf0Value::as_number)
+                    .unwrap_or_default();
+                let transaction = Amf0Value::Number(transaction);
+
+                match name {
+                    "connect" => {
+                        let app = values
+                            .get(2)
+                            .and_then(|object| object.property("app"))
+                            .and_then(Amf0Value::as_str)
+                            .unwrap_or_default();
+                        state.lock().unwrap().streams[index].app = app.to_string();
+
+                        let window = window.to_be_bytes().to_vec();
+                        writer.write(
+                            &mut output,
+                            CSID_PROTOCOL,
+                            &RtmpMessage::control(MSG_WINDOW_ACK_SIZE, window.clone()),
+                        );
+                        let mut bandwidth = window;
+                        bandwidth.push(2);
+                        writer.write(
+                            &mut output,
+                            CSID_PROTOCOL,
+                            &RtmpMessage::control(MSG_SET_PEER_BANDWIDTH, bandwidth),
+                        );
+                        writer.write(
+                            &mut output,
+                            CSID_COMMAND,
+                            &command(
+                                0,
+                                &[
+                                    Amf0Value::string("_result"),
+                                    transaction,
+                                    Amf0Value::object(&[
+                                        ("fmsVer", Amf0Value::string("FMS/3,0,1,123")),
+                                        ("capabilities", Amf0Value::Number(31.)),
+                                    ]),
+                                    Amf0Value::object(&[
+                                        ("level", Amf0Value::string("status")),
+                                        (
+                                            "code",
+                                            Amf0Value::string("NetConnection.Connect.Success"),
+                                        ),
+                                    ]),
+                                ],
+                            ),
+                        );
+                    }
+                    "createStream" => writer.write(
+                        &mut output,
+                        CSID_COMMAND,
+                        &command(
+                            0,
+                            &[
+                                Amf0Value::string("_result"),
+                                transaction,
+                                Amf0Value::Null,
+                                Amf0Value::Number(PUBLISH_STREAM_ID as f64),
+                            ],
+                        ),
+                    ),
+                    "publish" => {
+                        let key = values
+                            .get(3)
+                            .and_then(Amf0Value::as_str)
+                            .unwrap_or_default();
+                        {
+                            let mut state = state.lock().unwrap();
+                            let received = &mut state.streams[index];
+                            received.key = key.to_string();
+                            received.publishing = true;
+                        }
+                        started = Some(Instant::now());
+
+                        writer.write(
+                            &mut output,
+                            CSID_PROTOCOL,
+                            &RtmpMessage::user_control(
+                                USER_CONTROL_STREAM_BEGIN,
+                                PUBLISH_STREAM_ID,
+                            ),
+                        );
+                        writer.write(
+                            &mut output,
+                            CSID_COMMAND,
+                            &command(
+                                PUBLISH_STREAM_ID,
+                                &[
+                                    Amf0Value::string("onStatus"),
+                                    Amf0Value::Number(0.),
+                                    Amf0Value::Null,
+                                    Amf0Value::object(&[
+                                        ("level", Amf0Value::string("status")),
+                                        ("code", Amf0Value::string("NetStream.Publish.Start")),
+                                        ("description", Amf0Value::string(key)),
+                                    ]),
+                                ],
+                            ),
+                        );
+                    }
+                    "deleteStream" | "FCUnpublish" => {
+                        state.lock().unwrap().streams[index].publishing = false;
+                    }
+                    _ => {}
+                }
+            }
+            MSG_DATA_AMF0 => {
+                let values = decode(&message.payload)?;
+                if values
+                    .iter()
+                    .any(|value| value.as_str() == Some("onMetaData"))
+                {
+                    state.lock().unwrap().streams[index].metadata = true;
+                }
+            }
+            MSG_VIDEO => {
+                let mut state = state.lock().unwrap();
+                let received = &mut state.streams[index];
+                if let Some(tag) = parse_video_tag(&message.payload) {
+                    if tag.sequence_header {
+                        received.sequence_headers += 1;
+                    } else {
+                        if received.video_frames == 0 {
+                            received.starts_with_keyframe = tag.keyframe;
+                        }
+                        received.video_frames += 1;
+                        received.keyframes += tag.keyframe as u64;
+                        received.video_timestamps.push(message.timestamp);
+                        received.order.push(true);
+                    }
+                }
+            }
+            MSG_AUDIO => {
+                let mut state = state.lock().unwrap();
+                let received = &mut state.streams[index];
+                if is_audio_sequence_header(&message.payload) {
+                    received.audio_sequence_headers += 1;
+                } else {
+                    received.audio_frames += 1;
+                    received.audio_timestamps.push(message.timestamp);
+                    received.order.push(false);
+                }
+            }
+            _ => {}
+        }
+
+        {
+            let mut state = state.lock().unwrap();
+            let received = &mut state.streams[index];
+            received.bytes = reader.bytes_read();
+            if let Some(started) = started {
+                received.duration_ms = started.elapsed().as_millis() as u64;
+            }
+        }
+
+        let rate = state.lock().unwrap().read_rate;
+        if throttle.map(|(current, _, _)| current) != rate {
+            throttle = rate.map(|rate| (rate, Instant::now(), reader.bytes_read()));
+        }
+        if let Some((rate, since, base)) = throttle {
+            let due =
+                Duration::from_secs_f64((reader.bytes_read() - base) as f64 / rate.max(1) as f64);
+            if let Some(wait) = due.checked_sub(since.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+
+        if reader.bytes_read() - acked >= window as u64 {
+            acked = reader.bytes_read();
+            writer.write(
+                &mut output,
+                CSID_PROTOCOL,
+                &RtmpMessage::control(MSG_ACKNOWLEDGEMENT, (acked as u32).to_be_bytes().to_vec()),
+            );
+        }
+        if !output.is_empty() {
+            stream.write_all(&output)?;
+        }
+    }
+}
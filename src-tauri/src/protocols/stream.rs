@@ -1,5 +1,481 @@
-use crate::Result;
+/// 推流输出模块
+///
+/// 每个推流目标有独立的输出线程、重连状态和统计，音视频包来自共享编码器。
+/// 连接断开后按配置的间隔重连，重连期间的包被丢弃，重连成功后从关键帧开始发送。
+/// 上行拥塞时通过共享编码器降低码率，积压严重时按优先级丢帧
+use std::{
+    net::TcpStream,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::RecvTimeoutError,
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-pub fn setup_stream_protocol() -> Result<()> {
-    Ok(())
+use anyhow::anyhow;
+use log::{error, info, warn};
+use serde::Serialize;
+
+use crate::{
+    media::encoder::{AvcHeader, PacketKind},
+    outputs::encoders::EncoderSubscription,
+    protocols::{
+        congestion::{
+            send_queue_bytes, CongestionController, CongestionSample, CongestionSettings,
+            CongestionState,
+        },
+        flv::{audio_sequence_header, audio_tag, metadata, video_sequence_header, video_tag},
+        net::{self, NetworkSettings},
+        rtmp::{RtmpPublisher, RtmpUrl},
+    },
+    stats::counters::{register_output, OutputCounters, OutputKind},
+    utils::profile::get_profile_config,
+    Result,
+};
+
+/// 建立 TCP 连接的超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 等待编码包的间隔，期间检查是否需要停止
+const PACKET_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 重连间隔的上限
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// 推流输出的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamState {
+    /// 正在建立第一次连接
+    Connecting,
+    /// 正在推流
+    Live,
+    /// 连接断开，等待重连
+    Reconnecting,
+    /// 已停止
+    Stopped,
+    /// 连接失败或重连次数用尽
+    Failed,
+}
+
+/// 推流输出的状态信息
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamStatus {
+    /// 推流目标 ID
+    pub id: String,
+    pub state: StreamState,
+    /// 当前的重连次数
+    pub attempts: u32,
+    /// 最近一次错误
+    pub error: Option<String>,
+    /// 使用的编码器组编号
+    pub encoder: u64,
+    /// 当前的视频码率（kbps），拥塞时低于设置值
+    pub bitrate: u32,
+    pub congestion: CongestionState,
+}
+
+/// 重连设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectSettings {
+    /// 是否自动重连
+    pub enabled: bool,
+    /// 第一次重连前的等待时间，之后每次加倍
+    pub delay: Duration,
+    /// 最大重连次数
+    pub max_retries: u32,
+}
+
+impl Default for ReconnectSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            delay: Duration::from_secs(2),
+            max_retries: 25,
+        }
+    }
+}
+
+impl ReconnectSettings {
+    /// 从当前配置文件的 `[Output]` 节读取重连设置
+    pub fn load() -> Self {
+        let default = Self::default();
+        let get = |key: &str| {
+            get_profile_config("Output", key).and_then(|value| value.parse::<u32>().ok())
+        };
+
+        Self {
+            enabled: get_profile_config("Output", "Reconnect")
+                .map_or(default.enabled, |value| value != "false" && value != "0"),
+            delay: get("RetryDelay").map_or(default.delay, |secs| {
+                Duration::from_secs(secs.max(1) as u64)
+            }),
+            max_retries: get("MaxRetries").unwrap_or(default.max_retries),
+        }
+    }
+
+    /// 第 `attempt` 次重连前的等待时间
+    fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << attempt.saturating_sub(1).min(16);
+        self.delay.saturating_mul(factor).min(MAX_RETRY_DELAY)
+    }
+}
+
+/// 推流输出的设置
+#[derive(Debug, Clone, Default)]
+pub struct StreamSettings {
+    pub reconnect: ReconnectSettings,
+    pub congestion: CongestionSettings,
+    pub network: NetworkSettings,
+}
+
+impl StreamSettings {
+    /// 从当前配置文件读取推流输出的设置
+    pub fn load() -> Self {
+        Self {
+            reconnect: ReconnectSettings::load(),
+            congestion: CongestionSettings::load(),
+            network: NetworkSettings::load(),
+        }
+    }
+}
+
+/// 修改状态并通知的回调
+type StatusUpdate<'a> = &'a dyn Fn(&dyn Fn(&mut StreamStatus));
+
+/// 推流输出的计数器 ID
+///
+/// # 参数
+///
+/// * `id` - 推流目标 ID
+pub fn stream_output_id(id: &str) -> String {
+    format!("stream_output_{}", id)
+}
+
+/// 运行中的推流输出
+pub struct StreamOutput {
+    stop: Arc<AtomicBool>,
+    status: Arc<Mutex<StreamStatus>>,
+    thread: JoinHandle<()>,
+}
+
+/// 一次连接结束的原因
+enum SessionEnd {
+    /// 输出被停止
+    Stopped,
+    /// 编码器已停止，无法继续
+    EncoderStopped,
+    /// 连接断开或发送失败
+    Disconnected(anyhow::Error),
+}
+
+impl StreamOutput {
+    /// 启动推流输出
+    ///
+    /// # 参数
+    ///
+    /// * `id` - 推流目标 ID
+    /// * `url` - 推流地址
+    /// * `subscription` - 编码器订阅
+    /// * `settings` - 重连、拥塞控制和网络设置
+    /// * `on_change` - 状态变化时的回调
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，线程无法创建时返回错误
+    pub fn start(
+        id: &str,
+        url: RtmpUrl,
+        subscription: EncoderSubscription,
+        settings: StreamSettings,
+        on_change: impl Fn(&StreamStatus) + Send + 'static,
+    ) -> Result<Self> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let status = Arc::new(Mutex::new(StreamStatus {
+            id: id.to_string(),
+            state: StreamState::Connecting,
+            attempts: 0,
+            error: None,
+            encoder: subscription.group_id(),
+            bitrate: subscription.key().video.bitrate,
+            congestion: CongestionState::Clear,
+        }));
+        let counters = register_output(&stream_output_id(id), OutputKind::Stream);
+
+        let stopped = stop.clone();
+        let shared = status.clone();
+        let thread = thread::Builder::new()
+            .name(format!("stream-{}", id.chars().take(8).collect::<String>()))
+            .spawn(move || {
+                let update = |change: &dyn Fn(&mut StreamStatus)| {
+                    let status = {
+                        let mut status = shared.lock().unwrap();
+                        change(&mut status);
+                        status.clone()
+                    };
+                    on_change(&status);
+                };
+
+                counters.set_active(true);
+                update(&|_| {});
+                let result =
+                    run_stream(&url, &subscription, &settings, &stopped, &counters, &update);
+                counters.set_active(false);
+
+                match result {
+                    Ok(_) => {
+                        info!("stream stopped: {}", url.display());
+                        update(&|status| status.state = StreamState::Stopped);
+                    }
+                    Err(e) => {
+                        error!("stream failed: {}: {}", url.display(), e);
+                        let message = e.to_string();
+                        update(&|status| {
+                            status.state = StreamState::Failed;
+                            status.error = Some(message.clone());
+                        });
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            stop,
+            status,
+            thread,
+        })
+    }
+
+    /// 当前状态
+    pub fn status(&self) -> StreamStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// 输出线程是否在运行
+    pub fn is_running(&self) -> bool {
+        !self.thread.is_finished()
+    }
+
+    /// 停止推流，等待输出线程退出
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if self.thread.join().is_err() {
+            error!("stream thread panicked");
+        }
+    }
+}
+
+/// 建立到推流服务器的 TCP 连接
+///
+/// # 参数
+///
+/// * `url` - 推流地址
+/// * `network` - 网络设置
+///
+/// # 返回值
+///
+/// 返回 `Result<TcpStream>`，所有地址都无法连接时返回最后一个错误
+pub fn connect(url: &RtmpUrl, network: &NetworkSettings) -> Result<TcpStream> {
+    net::connect(&url.host, url.port, network, CONNECT_TIMEOUT)
+}
+
+/// 连接、发送和重连的主循环
+fn run_stream(
+    url: &RtmpUrl,
+    subscription: &EncoderSubscription,
+    settings: &StreamSettings,
+    stop: &AtomicBool,
+    counters: &OutputCounters,
+    update: StatusUpdate,
+) -> Result<()> {
+    let mut attempts = 0;
+    let mut connected = false;
+    // 码率调整跨重连保留，网络通常没有变化
+    let key = subscription.key();
+    let mut congestion =
+        CongestionController::new(settings.congestion, key.video.bitrate, key.audio.bitrate);
+
+    loop {
+        let error = match connect(url, &settings.network)
+            .and_then(|stream| RtmpPublisher::publish(stream, url))
+        {
+            Ok(publisher) => {
+                info!("stream connected: {}", url.display());
+                connected = true;
+                attempts = 0;
+                counters.set_reconnecting(false);
+                update(&|status| {
+                    status.state = StreamState::Live;
+                    status.attempts = 0;
+                });
+
+                congestion.reset();
+                match send_packets(
+                    publisher,
+                    subscription,
+                    stop,
+                    counters,
+                    &mut congestion,
+                    update,
+                ) {
+                    SessionEnd::Stopped => return Ok(()),
+                    SessionEnd::EncoderStopped => return Err(anyhow!("encoder stopped")),
+                    SessionEnd::Disconnected(e) => e,
+                }
+            }
+            Err(e) => e,
+        };
+        warn!("stream disconnected: {}: {}", url.display(), error);
+
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        // 从未连接成功时直接失败，通常是地址或推流码错误
+        if !connected || !settings.reconnect.enabled || attempts >= settings.reconnect.max_retries {
+            return Err(error);
+        }
+
+        attempts += 1;
+        counters.set_reconnecting(true);
+        let message = error.to_string();
+        update(&|status| {
+            status.state = StreamState::Reconnecting;
+            status.attempts = attempts;
+            status.error = Some(message.clone());
+        });
+
+        // 等待期间丢弃编码包，避免队列积压
+        let deadline = Instant::now() + settings.reconnect.delay_for(attempts);
+        while Instant::now() < deadline {
+            if stop.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            match subscription.packets.recv_timeout(PACKET_POLL_INTERVAL) {
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Err(anyhow!("encoder stopped")),
+            }
+        }
+        subscription.take_dropped();
+    }
+}
+
+/// 在一次连接上发送编码包，从关键帧开始，时间戳从 0 开始
+fn send_packets(
+    mut publisher: RtmpPublisher,
+    subscription: &EncoderSubscription,
+    stop: &AtomicBool,
+    counters: &OutputCounters,
+    congestion: &mut CongestionController,
+    update: StatusUpdate,
+) -> SessionEnd {
+    let key = subscription.key();
+    if let Err(e) = publisher
+        .send_metadata(&metadata(&key.video, &key.audio))
+        .and_then(|_| publisher.send_audio(0, audio_sequence_header(&key.audio)))
+    {
+        return SessionEnd::Disconnected(e);
+    }
+    subscription.request_keyframe();
+
+    let mut base: Option<u64> = None;
+    let mut header: Option<Arc<AvcHeader>> = None;
+    let mut waiting_keyframe = true;
+
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            publisher.close();
+            return SessionEnd::Stopped;
+        }
+
+        let packet = match subscription.packets.recv_timeout(PACKET_POLL_INTERVAL) {
+            Ok(packet) => packet,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return SessionEnd::EncoderStopped,
+        };
+
+        let now = Instant::now();
+        congestion.on_packet(packet.dts, now);
+        let state = congestion.state();
+        let peer = publisher.peer();
+        let sample = CongestionSample {
+            bytes_sent: publisher.bytes_sent(),
+            acked_bytes: peer.acked_bytes.load(Ordering::Relaxed),
+            ack_window: peer.ack_window.load(Ordering::Relaxed),
+            queued_bytes: send_queue_bytes(publisher.socket()),
+        };
+        let changed = congestion.update(sample, now);
+        if let Some(bitrate) = changed {
+            info!("stream bitrate: {} kbps", bitrate);
+            subscription.set_bitrate_limit((bitrate < key.video.bitrate).then_some(bitrate));
+        }
+        if changed.is_some() || congestion.state() != state {
+            if congestion.state() > state {
+                warn!("stream congestion: {:?}", congestion.state());
+            }
+            let (bitrate, state) = (congestion.bitrate(), congestion.state());
+            update(&|status| {
+                status.bitrate = bitrate;
+                status.congestion = state;
+            });
+        }
+
+        // 队列满时丢弃的帧会破坏参考关系，需要等待下一个关键帧
+        let dropped = subscription.take_dropped();
+        if dropped > 0 {
+            counters.record_dropped(dropped);
+            waiting_keyframe = true;
+            subscription.request_keyframe();
+        }
+        if waiting_keyframe {
+            if packet.kind == PacketKind::Video && packet.keyframe {
+                waiting_keyframe = false;
+            } else {
+                continue;
+            }
+        }
+
+        let base = *base.get_or_insert(packet.dts);
+        if packet.dts < base {
+            continue;
+        }
+        let timestamp = ((packet.dts - base) / 1_000_000) as u32;
+
+        // 拥塞严重时按优先级丢弃视频帧，音频不丢
+        if packet.kind == PacketKind::Video && congestion.should_drop(packet.priority) {
+            counters.record_dropped(1);
+            continue;
+        }
+
+        let result = match packet.kind {
+            PacketKind::Video => {
+                if let Some(new_header) = packet.header.as_ref() {
+                    if header.as_ref() != Some(new_header) {
+                        if let Err(e) =
+                            publisher.send_video(timestamp, video_sequence_header(new_header))
+                        {
+                            return SessionEnd::Disconnected(e);
+                        }
+                        header = Some(new_header.clone());
+                    }
+                }
+                if header.is_none() {
+                    continue;
+                }
+
+                let cts = ((packet.pts - packet.dts) / 1_000_000) as i32;
+                publisher
+                    .send_video(timestamp, video_tag(&packet.data, packet.keyframe, cts))
+                    .map(|bytes| (bytes, 1))
+            }
+            PacketKind::Audio => publisher
+                .send_audio(timestamp, audio_tag(&packet.data))
+                .map(|bytes| (bytes, 0)),
+        };
+
+        match result {
+            Ok((bytes, frames)) => counters.record_sent(bytes as u64, frames),
+            Err(e) => return SessionEnd::Disconnected(e),
+        }
+    }
 }
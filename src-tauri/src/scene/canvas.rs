@@ -0,0 +1,282 @@
+/// 画布模块
+///
+/// 主画布使用配置文件中的视频设置，附加画布保存在场景集合中，
+/// 各自有分辨率、帧率、场景和当前场景，例如与横屏节目同时输出的 1080x1920 竖屏画面。
+/// 输出通过配置文件中所在节的 `Canvas` 项绑定到画布
+use anyhow::anyhow;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    hotkeys::{registry::unregister_hotkey, unregister_source_hotkeys},
+    media::{frame::VideoInfo, video::canvas_video_active},
+    scene::{notify_scenes_changed, projector::ProjectorKind, scenes, source::Source},
+    utils::profile::{get_profile_config, remove_profile_config, set_profile_config},
+    Result,
+};
+
+/// 画布的最小边长
+pub const MIN_CANVAS_SIZE: u32 = 8;
+
+/// 画布的最大边长
+pub const MAX_CANVAS_SIZE: u32 = 16384;
+
+/// 输出绑定画布的配置项
+const CANVAS_KEY: &str = "Canvas";
+
+/// 附加画布
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CanvasConfig {
+    /// 画布唯一 ID
+    pub uuid: String,
+    /// 画布名称
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// 帧率分子
+    pub fps_num: u32,
+    /// 帧率分母
+    pub fps_den: u32,
+    /// 画布的当前场景 UUID
+    #[serde(default)]
+    pub current_scene: Option<String>,
+}
+
+impl CanvasConfig {
+    /// 创建附加画布
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 画布名称
+    /// * `width` - 画布宽度
+    /// * `height` - 画布高度
+    /// * `fps_num` - 帧率分子
+    /// * `fps_den` - 帧率分母
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Self>`，尺寸或帧率无效时返回错误
+    pub fn new(name: &str, width: u32, height: u32, fps_num: u32, fps_den: u32) -> Result<Self> {
+        let canvas = Self {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            width,
+            height,
+            fps_num,
+            fps_den,
+            current_scene: None,
+        };
+        canvas.validate()?;
+
+        Ok(canvas)
+    }
+
+    /// 检查尺寸和帧率
+    pub fn validate(&self) -> Result<()> {
+        let size = MIN_CANVAS_SIZE..=MAX_CANVAS_SIZE;
+        if !size.contains(&self.width) || !size.contains(&self.height) {
+            return Err(anyhow!(
+                "invalid canvas size: {}x{}",
+                self.width,
+                self.height
+            ));
+        }
+        if self.fps_num == 0 || self.fps_den == 0 {
+            return Err(anyhow!(
+                "invalid canvas fps: {}/{}",
+                self.fps_num,
+                self.fps_den
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 画布的视频设置，输出分辨率与画布相同
+    pub fn video_info(&self) -> VideoInfo {
+        VideoInfo {
+            base_width: self.width,
+            base_height: self.height,
+            output_width: self.width,
+            output_height: self.height,
+            fps_num: self.fps_num,
+            fps_den: self.fps_den,
+        }
+    }
+}
+
+/// 获取画布的视频设置
+///
+/// # 参数
+///
+/// * `canvas` - 画布 UUID，为 `None` 时为主画布
+///
+/// # 返回值
+///
+/// 返回 `Result<VideoInfo>`，画布不存在时返回错误
+pub fn canvas_video_info(canvas: Option<&str>) -> Result<VideoInfo> {
+    match canvas {
+        Some(uuid) => scenes().try_canvas(uuid).map(CanvasConfig::video_info),
+        None => Ok(VideoInfo::load()),
+    }
+}
+
+/// 添加附加画布
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `canvas` - 画布设置
+///
+/// # 返回值
+///
+/// 返回 `Result<String>`，表示新画布的 UUID
+pub fn add_canvas(app: &AppHandle, canvas: CanvasConfig) -> Result<String> {
+    let uuid = scenes().add_canvas(canvas)?;
+    notify_scenes_changed(app);
+
+    Ok(uuid)
+}
+
+/// 修改附加画布的名称、尺寸和帧率，画布有输出时不能修改尺寸和帧率
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `uuid` - 画布 UUID
+/// * `name` - 画布名称
+/// * `width` - 画布宽度
+/// * `height` - 画布高度
+/// * `fps_num` - 帧率分子
+/// * `fps_den` - 帧率分母
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn update_canvas(
+    app: &AppHandle,
+    uuid: &str,
+    name: &str,
+    width: u32,
+    height: u32,
+    fps_num: u32,
+    fps_den: u32,
+) -> Result<()> {
+    let active = canvas_video_active(Some(uuid));
+    {
+        let mut collection = scenes();
+        if collection
+            .canvases
+            .iter()
+            .any(|canvas| canvas.uuid != uuid && canvas.name == name)
+        {
+            return Err(anyhow!("canvas name already in use: {}", name));
+        }
+
+        let canvas = collection.try_canvas_mut(uuid)?;
+        let updated = CanvasConfig {
+            name: name.to_string(),
+            width,
+            height,
+            fps_num,
+            fps_den,
+            ..canvas.clone()
+        };
+        updated.validate()?;
+        if active && updated.video_info() != canvas.video_info() {
+            return Err(anyhow!("canvas is in use by an active output: {}", name));
+        }
+        *canvas = updated;
+    }
+
+    notify_scenes_changed(app);
+
+    Ok(())
+}
+
+/// 删除附加画布及其场景，画布有输出时不能删除
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `uuid` - 画布 UUID
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn remove_canvas(app: &AppHandle, uuid: &str) -> Result<()> {
+    if canvas_video_active(Some(uuid)) {
+        return Err(anyhow!("canvas is in use by an active output: {}", uuid));
+    }
+
+    let (removed, sources) = {
+        let mut collection = scenes();
+        let before = collection.sources.clone();
+        let removed = collection.remove_canvas(uuid)?;
+        // 只在被删除场景中使用的来源随之移除
+        let sources: Vec<Source> = before
+            .into_values()
+            .filter(|source| !collection.sources.contains_key(&source.uuid))
+            .collect();
+        collection
+            .saved_projectors
+            .retain(|projector| match &projector.kind {
+                ProjectorKind::Scene(scene) => !removed.contains(scene),
+                _ => true,
+            });
+        (removed, sources)
+    };
+    for scene in removed {
+        unregister_hotkey(&format!("OBSBasic.SelectScene.{}", scene));
+    }
+    for source in sources {
+        unregister_source_hotkeys(&source.uuid, &source.kind);
+    }
+
+    notify_scenes_changed(app);
+
+    Ok(())
+}
+
+/// 获取输出绑定的画布，画布已被删除时回到主画布
+///
+/// # 参数
+///
+/// * `section` - 输出在配置文件中的配置节，如 `VirtualCam`
+///
+/// # 返回值
+///
+/// 返回画布 UUID，为 `None` 时为主画布
+pub fn output_canvas(section: &str) -> Option<String> {
+    let uuid = get_profile_config(section, CANVAS_KEY).filter(|uuid| !uuid.is_empty())?;
+    if scenes().canvas(&uuid).is_none() {
+        warn!(
+            "canvas of {} not found, using main canvas: {}",
+            section, uuid
+        );
+        return None;
+    }
+
+    Some(uuid)
+}
+
+/// 把输出绑定到画布，下次启动输出时生效
+///
+/// # 参数
+///
+/// * `section` - 输出在配置文件中的配置节
+/// * `canvas` - 画布 UUID，为 `None` 时绑定到主画布
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn set_output_canvas(section: &str, canvas: Option<&str>) -> Result<()> {
+    match canvas {
+        Some(uuid) => {
+            scenes().try_canvas(uuid)?;
+            set_profile_config(section, CANVAS_KEY, uuid)
+        }
+        None => remove_profile_config(section, CANVAS_KEY),
+    }
+}
@@ -0,0 +1,583 @@
+/// 场景项剪贴板模块
+///
+/// 复制时保存来源和场景项的快照；粘贴时可引用原来源，或深拷贝出新的来源。
+/// 变换单独保存，可粘贴到任意场景项
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use log::error;
+use tauri::AppHandle;
+
+use crate::{
+    scene::{
+        collection::SceneCollection,
+        edit::{transform_items, update_source_hotkeys},
+        item::SceneItem,
+        notify_scenes_changed, scenes,
+        source::Source,
+        transform::Transform,
+        undo::{push_undo, UndoCommand},
+    },
+    ui::menu::set_menu_enabled,
+    utils::locale::tf,
+    Result,
+};
+
+/// 剪贴板条目
+#[derive(Debug, Clone)]
+struct ClipboardEntry {
+    /// 复制时的来源快照
+    source: Source,
+    /// 复制时的变换
+    transform: Transform,
+    /// 复制时是否可见
+    visible: bool,
+}
+
+/// 粘贴结果
+struct PastedItems {
+    /// 新场景项及其在场景中的位置
+    items: Vec<(usize, SceneItem)>,
+    /// 深拷贝出的新来源
+    sources: Vec<Source>,
+}
+
+lazy_static! {
+    /// 场景项剪贴板
+    static ref CLIPBOARD: Mutex<Vec<ClipboardEntry>> = Mutex::new(vec![]);
+    /// 变换剪贴板
+    static ref TRANSFORM_CLIPBOARD: Mutex<Option<Transform>> = Mutex::new(None);
+}
+
+/// 按场景中从底到顶的顺序生成场景项的剪贴板条目，粘贴后保持相对层级
+///
+/// # 参数
+///
+/// * `collection` - 场景集合
+/// * `scene` - 场景 UUID
+/// * `ids` - 场景项 ID 列表
+///
+/// # 返回值
+///
+/// 返回 `Result<Vec<ClipboardEntry>>`，场景不存在时返回错误
+fn clipboard_entries(
+    collection: &SceneCollection,
+    scene: &str,
+    ids: &[u64],
+) -> Result<Vec<ClipboardEntry>> {
+    Ok(collection
+        .try_scene(scene)?
+        .items
+        .iter()
+        .filter(|item| ids.contains(&item.id))
+        .filter_map(|item| {
+            collection
+                .source(&item.source)
+                .map(|source| ClipboardEntry {
+                    source: source.clone(),
+                    transform: item.transform,
+                    visible: item.visible,
+                })
+        })
+        .collect())
+}
+
+/// 复制场景项到剪贴板
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `ids` - 场景项 ID 列表
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn copy_items(app: &AppHandle, scene: &str, ids: &[u64]) -> Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let entries = clipboard_entries(&scenes(), scene, ids)?;
+    *CLIPBOARD.lock().unwrap() = entries;
+
+    update_clipboard_menu(app)
+}
+
+/// 复制当前场景中被选中的场景项
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn copy_selected_items(app: &AppHandle) -> Result<()> {
+    let (scene, ids) = match scenes().current_scene() {
+        Some(scene) => (scene.uuid.clone(), scene.selected_items()),
+        None => return Ok(()),
+    };
+
+    copy_items(app, &scene, &ids)
+}
+
+/// 把剪贴板条目粘贴到场景集合中的场景，粘贴后仅选中新粘贴的场景项
+///
+/// # 参数
+///
+/// * `collection` - 场景集合
+/// * `scene` - 场景 UUID
+/// * `entries` - 剪贴板条目
+/// * `duplicate` - 是否深拷贝来源
+///
+/// # 返回值
+///
+/// 返回 `Result<PastedItems>`，表示新场景项和新创建的来源。
+/// 粘贴引用而原来源已被删除时返回错误，且不修改场景集合
+fn paste_entries(
+    collection: &mut SceneCollection,
+    scene: &str,
+    entries: &[ClipboardEntry],
+    duplicate: bool,
+) -> Result<PastedItems> {
+    if !duplicate {
+        if let Some(entry) = entries
+            .iter()
+            .find(|entry| collection.source(&entry.source.uuid).is_none())
+        {
+            return Err(anyhow!("source no longer exists: {}", entry.source.name));
+        }
+    }
+
+    for item in collection.try_scene_mut(scene)?.items.iter_mut() {
+        item.selected = false;
+    }
+
+    let mut sources = vec![];
+    let mut items = vec![];
+    for entry in entries {
+        let source = if duplicate {
+            let mut source = entry.source.clone();
+            source.uuid = uuid::Uuid::new_v4().to_string();
+            source.name = collection.unique_name(&entry.source.name);
+            collection.add_source(source.clone())?;
+            sources.push(source.clone());
+            source.uuid
+        } else {
+            entry.source.uuid.clone()
+        };
+
+        let mut item = collection.add_item(scene, &source)?;
+        let scene_mut = collection.try_scene_mut(scene)?;
+        if let Some(added) = scene_mut.item_mut(item.id) {
+            added.transform = entry.transform;
+            added.visible = entry.visible;
+            added.selected = true;
+            item = added.clone();
+        }
+
+        items.push((scene_mut.items.len() - 1, item));
+    }
+
+    Ok(PastedItems { items, sources })
+}
+
+/// 将剪贴板中的场景项粘贴到场景
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `duplicate` - 为 `true` 时深拷贝来源（包括设置和滤镜），否则创建引用原来源的场景项
+///
+/// # 返回值
+///
+/// 返回 `Result<Vec<SceneItem>>`，表示新创建的场景项
+pub fn paste_items(app: &AppHandle, scene: &str, duplicate: bool) -> Result<Vec<SceneItem>> {
+    let entries = CLIPBOARD.lock().unwrap().clone();
+    if entries.is_empty() {
+        return Err(anyhow!("clipboard is empty"));
+    }
+
+    let (pasted, sources, name, command) = {
+        let mut collection = scenes();
+        let scene_name = collection.try_scene(scene)?.name.clone();
+        let PastedItems { items, sources } =
+            paste_entries(&mut collection, scene, &entries, duplicate)?;
+
+        let name = if duplicate {
+            tf("Undo.PasteSource", &[&scene_name])?
+        } else {
+            tf("Undo.PasteSourceRef", &[&scene_name])?
+        };
+        let pasted: Vec<SceneItem> = items.iter().map(|(_, item)| item.clone()).collect();
+        let command = UndoCommand::AddItems {
+            scene: scene.to_string(),
+            items,
+            sources: sources.clone(),
+        };
+
+        (pasted, sources, name, command)
+    };
+
+    // 深拷贝出的来源需要注册自己的静音和媒体快捷键
+    update_source_hotkeys(app, &sources, &[])?;
+    push_undo(app, &name, command, false);
+    notify_scenes_changed(app);
+
+    Ok(pasted)
+}
+
+/// 将剪贴板中的场景项粘贴到当前场景
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `duplicate` - 是否深拷贝来源
+///
+/// # 返回值
+///
+/// 返回 `Result<Vec<SceneItem>>`，表示新创建的场景项
+pub fn paste_to_current_scene(app: &AppHandle, duplicate: bool) -> Result<Vec<SceneItem>> {
+    let scene = match scenes().current_scene() {
+        Some(scene) => scene.uuid.clone(),
+        None => return Err(anyhow!("no current scene")),
+    };
+
+    paste_items(app, &scene, duplicate)
+}
+
+/// 复制场景项的完整变换（位置、旋转、缩放、裁剪、边界框和对齐方式）
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `id` - 场景项 ID
+///
+/// # 返回值
+///
+/// 返回 `Result<Transform>`，表示被复制的变换
+pub fn copy_transform(app: &AppHandle, scene: &str, id: u64) -> Result<Transform> {
+    let transform = scenes()
+        .try_scene(scene)?
+        .item(id)
+        .map(|item| item.transform)
+        .ok_or_else(|| anyhow!("scene item not found: {}", id))?;
+
+    *TRANSFORM_CLIPBOARD.lock().unwrap() = Some(transform);
+
+    update_clipboard_menu(app)?;
+
+    Ok(transform)
+}
+
+/// 复制当前场景中第一个被选中场景项的变换
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn copy_selected_transform(app: &AppHandle) -> Result<()> {
+    let (scene, ids) = match scenes().current_scene() {
+        Some(scene) => (scene.uuid.clone(), scene.selected_items()),
+        None => return Ok(()),
+    };
+
+    match ids.first() {
+        Some(id) => copy_transform(app, &scene, *id).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// 将复制的变换应用到场景项
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `ids` - 场景项 ID 列表
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn paste_transform(app: &AppHandle, scene: &str, ids: &[u64]) -> Result<()> {
+    let transform = match *TRANSFORM_CLIPBOARD.lock().unwrap() {
+        Some(transform) => transform,
+        None => return Err(anyhow!("no transform copied")),
+    };
+
+    let transforms: Vec<(u64, Transform)> = ids.iter().map(|id| (*id, transform)).collect();
+
+    transform_items(app, scene, &transforms, "Undo.Transform.Paste", false)
+}
+
+/// 将复制的变换应用到当前场景中被选中的场景项
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn paste_selected_transform(app: &AppHandle) -> Result<()> {
+    let (scene, ids) = match scenes().current_scene() {
+        Some(scene) => (scene.uuid.clone(), scene.selected_items()),
+        None => return Ok(()),
+    };
+
+    paste_transform(app, &scene, &ids)
+}
+
+/// 剪贴板是否可以粘贴
+///
+/// # 返回值
+///
+/// 返回 `(bool, bool)`，分别表示能否粘贴引用和能否粘贴副本
+pub fn can_paste() -> (bool, bool) {
+    let entries = CLIPBOARD.lock().unwrap().clone();
+
+    paste_state(&scenes(), &entries)
+}
+
+/// 剪贴板条目能否粘贴到场景集合
+///
+/// # 参数
+///
+/// * `collection` - 场景集合
+/// * `entries` - 剪贴板条目
+///
+/// # 返回值
+///
+/// 返回 `(bool, bool)`，分别表示能否粘贴引用和能否粘贴副本
+fn paste_state(collection: &SceneCollection, entries: &[ClipboardEntry]) -> (bool, bool) {
+    if entries.is_empty() {
+        return (false, false);
+    }
+
+    // 原来源已被删除时只能粘贴副本
+    let reference = entries
+        .iter()
+        .all(|entry| collection.source(&entry.source.uuid).is_some());
+
+    (reference, true)
+}
+
+/// 编辑菜单中复制/粘贴项的可用状态
+///
+/// # 参数
+///
+/// * `collection` - 场景集合
+/// * `entries` - 剪贴板条目
+/// * `has_transform` - 是否已复制变换
+///
+/// # 返回值
+///
+/// 返回菜单项 ID 和是否可用的列表
+fn clipboard_menu_states(
+    collection: &SceneCollection,
+    entries: &[ClipboardEntry],
+    has_transform: bool,
+) -> [(&'static str, bool); 7] {
+    let has_selection = collection
+        .current_scene()
+        .map(|scene| !scene.selected_items().is_empty())
+        .unwrap_or(false);
+    let (reference, duplicate) = paste_state(collection, entries);
+
+    [
+        ("Copy", has_selection),
+        ("PasteReference", reference),
+        ("PasteDuplicate", duplicate),
+        ("Basic.MainMenu.Edit.Transform.EditTransform", has_selection),
+        ("Basic.MainMenu.Edit.Transform.CopyTransform", has_selection),
+        (
+            "Basic.MainMenu.Edit.Transform.PasteTransform",
+            has_selection && has_transform,
+        ),
+        (
+            "Basic.MainMenu.Edit.Transform.ResetTransform",
+            has_selection,
+        ),
+    ]
+}
+
+/// 更新编辑菜单中复制/粘贴项的可用状态
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn update_clipboard_menu(app: &AppHandle) -> Result<()> {
+    let entries = CLIPBOARD.lock().unwrap().clone();
+    let has_transform = TRANSFORM_CLIPBOARD.lock().unwrap().is_some();
+    let states = clipboard_menu_states(&scenes(), &entries, has_transform);
+
+    for (id, enabled) in states {
+        set_menu_enabled(app, id, enabled)?;
+    }
+
+    Ok(())
+}
+
+/// 更新编辑菜单，出错时仅记录日志
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+pub fn refresh_clipboard_menu(app: &AppHandle) {
+    if let Err(e) = update_clipboard_menu(app) {
+        error!("failed to update clipboard menu: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+    use serde_json::json;
+
+    use super::*;
+    use crate::scene::source::Filter;
+
+    /// 创建包含两个场景的集合，第一个场景中有一个选中的场景项
+    fn collection() -> (SceneCollection, String, String, u64) {
+        let mut collection = SceneCollection::new("test");
+        let scene = collection.add_scene("Scene").unwrap();
+        let other = collection.add_scene("Other").unwrap();
+        let mut source = Source::new("Media", "ffmpeg_source", json!({ "local_file": "a.mp4" }));
+        source.filters.push(Filter {
+            name: "Color".to_string(),
+            kind: "color_filter".to_string(),
+            settings: json!({ "gamma": 0.5 }),
+            enabled: true,
+        });
+        let source = collection.add_source(source).unwrap();
+        let id = collection.add_item(&scene, &source).unwrap().id;
+
+        let item = collection.scene_mut(&scene).unwrap().item_mut(id).unwrap();
+        item.transform.pos = Vec2::new(10., 20.);
+        item.visible = false;
+        item.selected = true;
+        collection.set_current_scene(&scene).unwrap();
+
+        (collection, scene, other, id)
+    }
+
+    #[test]
+    fn reference_paste_shares_the_source() {
+        let (mut collection, scene, other, id) = collection();
+        let entries = clipboard_entries(&collection, &scene, &[id]).unwrap();
+
+        let PastedItems { items, sources } =
+            paste_entries(&mut collection, &other, &entries, false).unwrap();
+
+        assert!(sources.is_empty());
+        assert_eq!(items.len(), 1);
+        let (index, item) = &items[0];
+        assert_eq!(*index, 0);
+        assert_eq!(item.source, entries[0].source.uuid);
+        assert_eq!(item.transform.pos, Vec2::new(10., 20.));
+        assert!(!item.visible);
+        assert!(item.selected);
+        assert_eq!(collection.sources.len(), 1);
+    }
+
+    #[test]
+    fn duplicate_paste_copies_settings_and_filters() {
+        let (mut collection, scene, _, id) = collection();
+        let entries = clipboard_entries(&collection, &scene, &[id]).unwrap();
+
+        let PastedItems { items, sources } =
+            paste_entries(&mut collection, &scene, &entries, true).unwrap();
+
+        assert_eq!(sources.len(), 1);
+        let source = collection.source(&sources[0].uuid).unwrap();
+        assert_ne!(source.uuid, entries[0].source.uuid);
+        assert_ne!(source.name, "Media");
+        assert_eq!(source.settings, entries[0].source.settings);
+        assert_eq!(source.filters, entries[0].source.filters);
+        assert_eq!(items[0].1.source, source.uuid);
+
+        // 粘贴后仅选中新粘贴的场景项
+        let scene = collection.scene(&scene).unwrap();
+        assert_eq!(scene.selected_items(), vec![items[0].1.id]);
+        assert_eq!(items[0].0, 1);
+    }
+
+    #[test]
+    fn deleted_source_can_only_be_pasted_as_duplicate() {
+        let (mut collection, scene, other, id) = collection();
+        let entries = clipboard_entries(&collection, &scene, &[id]).unwrap();
+        assert_eq!(paste_state(&collection, &entries), (true, true));
+
+        collection.remove_item(&scene, id).unwrap();
+        collection
+            .remove_unused_source(&entries[0].source.uuid)
+            .unwrap();
+        assert_eq!(paste_state(&collection, &entries), (false, true));
+
+        assert!(paste_entries(&mut collection, &other, &entries, false).is_err());
+        assert!(collection.scene(&other).unwrap().items.is_empty());
+
+        let PastedItems { items, sources } =
+            paste_entries(&mut collection, &other, &entries, true).unwrap();
+        assert_eq!(items.len(), 1);
+        // 原名称已空出，副本沿用原来的名称
+        assert_eq!(sources[0].name, "Media");
+        assert_eq!(sources[0].filters, entries[0].source.filters);
+    }
+
+    #[test]
+    fn menu_states_follow_selection_and_clipboard() {
+        let (mut collection, scene, _, id) = collection();
+        let enabled = |states: [(&'static str, bool); 7], id: &str| {
+            states.iter().find(|(name, _)| *name == id).unwrap().1
+        };
+
+        let states = clipboard_menu_states(&collection, &[], false);
+        assert!(enabled(states, "Copy"));
+        assert!(!enabled(states, "PasteReference"));
+        assert!(!enabled(states, "PasteDuplicate"));
+        assert!(!enabled(
+            states,
+            "Basic.MainMenu.Edit.Transform.PasteTransform"
+        ));
+
+        let entries = clipboard_entries(&collection, &scene, &[id]).unwrap();
+        let states = clipboard_menu_states(&collection, &entries, true);
+        assert!(enabled(states, "PasteReference"));
+        assert!(enabled(states, "PasteDuplicate"));
+        assert!(enabled(
+            states,
+            "Basic.MainMenu.Edit.Transform.PasteTransform"
+        ));
+
+        // 没有选中项时只能粘贴场景项，不能编辑或粘贴变换
+        collection
+            .scene_mut(&scene)
+            .unwrap()
+            .item_mut(id)
+            .unwrap()
+            .selected = false;
+        let states = clipboard_menu_states(&collection, &entries, true);
+        assert!(!enabled(states, "Copy"));
+        assert!(enabled(states, "PasteReference"));
+        assert!(!enabled(
+            states,
+            "Basic.MainMenu.Edit.Transform.EditTransform"
+        ));
+        assert!(!enabled(
+            states,
+            "Basic.MainMenu.Edit.Transform.PasteTransform"
+        ));
+    }
+}
@@ -0,0 +1,573 @@
+/// 场景集合模块
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    scene::{
+        canvas::CanvasConfig,
+        item::SceneItem,
+        projector::SavedProjector,
+        source::{Filter, Source},
+        transform::Transform,
+    },
+    Result,
+};
+
+/// 场景
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    /// 场景唯一 ID
+    pub uuid: String,
+    /// 场景名称
+    pub name: String,
+    /// 场景项，按从底到顶的顺序排列
+    #[serde(default)]
+    pub items: Vec<SceneItem>,
+    /// 下一个场景项 ID
+    #[serde(default = "default_item_id")]
+    pub next_item_id: u64,
+    /// 所属的附加画布 UUID，为 `None` 时属于主画布
+    #[serde(default)]
+    pub canvas: Option<String>,
+}
+
+impl Scene {
+    /// 创建新的场景
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 场景名称
+    ///
+    /// # 返回值
+    ///
+    /// 返回新创建的 `Scene`
+    pub fn new(name: &str) -> Self {
+        Self {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            items: vec![],
+            next_item_id: default_item_id(),
+            canvas: None,
+        }
+    }
+
+    /// 根据 ID 查找场景项
+    pub fn item(&self, id: u64) -> Option<&SceneItem> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    /// 根据 ID 查找可变场景项
+    pub fn item_mut(&mut self, id: u64) -> Option<&mut SceneItem> {
+        self.items.iter_mut().find(|item| item.id == id)
+    }
+
+    /// 获取场景项在列表中的位置
+    pub fn item_index(&self, id: u64) -> Option<usize> {
+        self.items.iter().position(|item| item.id == id)
+    }
+
+    /// 获取场景项的顺序（从底到顶）
+    pub fn item_order(&self) -> Vec<u64> {
+        self.items.iter().map(|item| item.id).collect()
+    }
+
+    /// 获取被选中的场景项 ID
+    pub fn selected_items(&self) -> Vec<u64> {
+        self.items
+            .iter()
+            .filter(|item| item.selected)
+            .map(|item| item.id)
+            .collect()
+    }
+}
+
+/// 场景集合
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneCollection {
+    /// 场景集合名称
+    pub name: String,
+    /// 所有来源，按 UUID 索引
+    #[serde(default)]
+    pub sources: BTreeMap<String, Source>,
+    /// 所有场景
+    #[serde(default)]
+    pub scenes: Vec<Scene>,
+    /// 主画布的当前场景 UUID
+    #[serde(default)]
+    pub current_scene: Option<String>,
+    /// 附加画布
+    #[serde(default)]
+    pub canvases: Vec<CanvasConfig>,
+    /// 退出时打开的投影仪
+    #[serde(default)]
+    pub saved_projectors: Vec<SavedProjector>,
+}
+
+impl SceneCollection {
+    /// 创建新的场景集合
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 场景集合名称
+    ///
+    /// # 返回值
+    ///
+    /// 返回新创建的 `SceneCollection`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 根据 UUID 查找来源
+    pub fn source(&self, uuid: &str) -> Option<&Source> {
+        self.sources.get(uuid)
+    }
+
+    /// 根据 UUID 查找可变来源
+    pub fn source_mut(&mut self, uuid: &str) -> Option<&mut Source> {
+        self.sources.get_mut(uuid)
+    }
+
+    /// 根据名称查找来源
+    pub fn source_by_name(&self, name: &str) -> Option<&Source> {
+        self.sources.values().find(|source| source.name == name)
+    }
+
+    /// 检查名称是否已被来源或场景占用
+    pub fn name_in_use(&self, name: &str) -> bool {
+        self.source_by_name(name).is_some() || self.scenes.iter().any(|scene| scene.name == name)
+    }
+
+    /// 生成不重复的名称，例如 `Image 2`
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 期望的名称
+    ///
+    /// # 返回值
+    ///
+    /// 返回未被占用的名称
+    pub fn unique_name(&self, name: &str) -> String {
+        if !self.name_in_use(name) {
+            return name.to_string();
+        }
+
+        let mut index = 2;
+        loop {
+            let candidate = format!("{} {}", name, index);
+            if !self.name_in_use(&candidate) {
+                return candidate;
+            }
+            index += 1;
+        }
+    }
+
+    /// 根据 UUID 查找场景
+    pub fn scene(&self, uuid: &str) -> Option<&Scene> {
+        self.scenes.iter().find(|scene| scene.uuid == uuid)
+    }
+
+    /// 根据 UUID 查找可变场景
+    pub fn scene_mut(&mut self, uuid: &str) -> Option<&mut Scene> {
+        self.scenes.iter_mut().find(|scene| scene.uuid == uuid)
+    }
+
+    /// 根据 UUID 查找场景，不存在时返回错误
+    pub fn try_scene(&self, uuid: &str) -> Result<&Scene> {
+        self.scene(uuid)
+            .ok_or_else(|| anyhow!("scene not found: {}", uuid))
+    }
+
+    /// 根据 UUID 查找可变场景，不存在时返回错误
+    pub fn try_scene_mut(&mut self, uuid: &str) -> Result<&mut Scene> {
+        self.scene_mut(uuid)
+            .ok_or_else(|| anyhow!("scene not found: {}", uuid))
+    }
+
+    /// 根据名称查找场景
+    pub fn scene_by_name(&self, name: &str) -> Option<&Scene> {
+        self.scenes.iter().find(|scene| scene.name == name)
+    }
+
+    /// 获取主画布的当前场景
+    pub fn current_scene(&self) -> Option<&Scene> {
+        self.current_scene
+            .as_ref()
+            .and_then(|uuid| self.scene(uuid))
+    }
+
+    /// 获取画布的当前场景
+    ///
+    /// # 参数
+    ///
+    /// * `canvas` - 画布 UUID，为 `None` 时为主画布
+    pub fn canvas_current_scene(&self, canvas: Option<&str>) -> Option<&Scene> {
+        match canvas {
+            Some(uuid) => self
+                .canvas(uuid)
+                .and_then(|canvas| canvas.current_scene.as_ref())
+                .and_then(|uuid| self.scene(uuid)),
+            None => self.current_scene(),
+        }
+    }
+
+    /// 把场景设为所属画布的当前场景
+    ///
+    /// # 参数
+    ///
+    /// * `uuid` - 场景 UUID
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，场景或画布不存在时返回错误
+    pub fn set_current_scene(&mut self, uuid: &str) -> Result<()> {
+        match self.try_scene(uuid)?.canvas.clone() {
+            Some(canvas) => self.try_canvas_mut(&canvas)?.current_scene = Some(uuid.to_string()),
+            None => self.current_scene = Some(uuid.to_string()),
+        }
+
+        Ok(())
+    }
+
+    /// 添加场景到主画布
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 场景名称
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<String>`，表示新场景的 UUID
+    pub fn add_scene(&mut self, name: &str) -> Result<String> {
+        self.add_canvas_scene(name, None)
+    }
+
+    /// 添加场景到画布，画布没有当前场景时设为当前场景
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 场景名称
+    /// * `canvas` - 画布 UUID，为 `None` 时为主画布
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<String>`，表示新场景的 UUID
+    pub fn add_canvas_scene(&mut self, name: &str, canvas: Option<&str>) -> Result<String> {
+        if self.name_in_use(name) {
+            return Err(anyhow!("name already in use: {}", name));
+        }
+        if let Some(canvas) = canvas {
+            self.try_canvas(canvas)?;
+        }
+
+        let mut scene = Scene::new(name);
+        scene.canvas = canvas.map(str::to_string);
+        let uuid = scene.uuid.clone();
+        self.scenes.push(scene);
+
+        if self.canvas_current_scene(canvas).is_none() {
+            self.set_current_scene(&uuid)?;
+        }
+
+        Ok(uuid)
+    }
+
+    /// 根据 UUID 查找附加画布
+    pub fn canvas(&self, uuid: &str) -> Option<&CanvasConfig> {
+        self.canvases.iter().find(|canvas| canvas.uuid == uuid)
+    }
+
+    /// 根据 UUID 查找附加画布，不存在时返回错误
+    pub fn try_canvas(&self, uuid: &str) -> Result<&CanvasConfig> {
+        self.canvas(uuid)
+            .ok_or_else(|| anyhow!("canvas not found: {}", uuid))
+    }
+
+    /// 根据 UUID 查找可变附加画布，不存在时返回错误
+    pub fn try_canvas_mut(&mut self, uuid: &str) -> Result<&mut CanvasConfig> {
+        self.canvases
+            .iter_mut()
+            .find(|canvas| canvas.uuid == uuid)
+            .ok_or_else(|| anyhow!("canvas not found: {}", uuid))
+    }
+
+    /// 添加附加画布
+    ///
+    /// # 参数
+    ///
+    /// * `canvas` - 画布设置
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<String>`，表示画布的 UUID
+    pub fn add_canvas(&mut self, canvas: CanvasConfig) -> Result<String> {
+        canvas.validate()?;
+        if self.canvases.iter().any(|other| other.name == canvas.name) {
+            return Err(anyhow!("canvas name already in use: {}", canvas.name));
+        }
+
+        let uuid = canvas.uuid.clone();
+        self.canvases.push(canvas);
+
+        Ok(uuid)
+    }
+
+    /// 删除附加画布及其场景，并移除不再被引用的来源
+    ///
+    /// # 参数
+    ///
+    /// * `uuid` - 画布 UUID
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Vec<String>>`，表示被删除的场景 UUID
+    pub fn remove_canvas(&mut self, uuid: &str) -> Result<Vec<String>> {
+        self.try_canvas(uuid)?;
+        self.canvases.retain(|canvas| canvas.uuid != uuid);
+
+        let (removed, kept): (Vec<Scene>, Vec<Scene>) = std::mem::take(&mut self.scenes)
+            .into_iter()
+            .partition(|scene| scene.canvas.as_deref() == Some(uuid));
+        self.scenes = kept;
+
+        let mut sources: Vec<String> = removed
+            .iter()
+            .flat_map(|scene| scene.items.iter().map(|item| item.source.clone()))
+            .collect();
+        sources.dedup();
+        for source in sources {
+            self.remove_unused_source(&source);
+        }
+
+        Ok(removed.into_iter().map(|scene| scene.uuid).collect())
+    }
+
+    /// 添加来源（不会添加到任何场景）
+    ///
+    /// # 参数
+    ///
+    /// * `source` - 来源
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<String>`，表示来源的 UUID
+    pub fn add_source(&mut self, source: Source) -> Result<String> {
+        if self.name_in_use(&source.name) {
+            return Err(anyhow!("name already in use: {}", source.name));
+        }
+
+        let uuid = source.uuid.clone();
+        self.sources.insert(uuid.clone(), source);
+
+        Ok(uuid)
+    }
+
+    /// 移除来源，仅在没有场景项引用时才真正移除
+    ///
+    /// # 参数
+    ///
+    /// * `uuid` - 来源 UUID
+    ///
+    /// # 返回值
+    ///
+    /// 返回被移除的来源
+    pub fn remove_unused_source(&mut self, uuid: &str) -> Option<Source> {
+        let in_use = self
+            .scenes
+            .iter()
+            .any(|scene| scene.items.iter().any(|item| item.source == uuid));
+
+        if in_use {
+            None
+        } else {
+            self.sources.remove(uuid)
+        }
+    }
+
+    /// 创建引用指定来源的场景项并添加到场景顶部
+    ///
+    /// # 参数
+    ///
+    /// * `scene` - 场景 UUID
+    /// * `source` - 来源 UUID
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<SceneItem>`，表示新创建的场景项
+    pub fn add_item(&mut self, scene: &str, source: &str) -> Result<SceneItem> {
+        if self.source(source).is_none() {
+            return Err(anyhow!("source not found: {}", source));
+        }
+
+        let scene = self.try_scene_mut(scene)?;
+        let item = SceneItem::new(scene.next_item_id, source);
+        scene.next_item_id += 1;
+        scene.items.push(item.clone());
+
+        Ok(item)
+    }
+
+    /// 在场景的指定位置插入场景项，用于撤销删除
+    ///
+    /// # 参数
+    ///
+    /// * `scene` - 场景 UUID
+    /// * `index` - 插入位置
+    /// * `item` - 场景项
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，表示操作是否成功
+    pub fn insert_item(&mut self, scene: &str, index: usize, item: SceneItem) -> Result<()> {
+        let scene = self.try_scene_mut(scene)?;
+        if scene.item(item.id).is_some() {
+            return Err(anyhow!("scene item already exists: {}", item.id));
+        }
+
+        scene.next_item_id = scene.next_item_id.max(item.id + 1);
+        let index = index.min(scene.items.len());
+        scene.items.insert(index, item);
+
+        Ok(())
+    }
+
+    /// 从场景中移除场景项
+    ///
+    /// # 参数
+    ///
+    /// * `scene` - 场景 UUID
+    /// * `id` - 场景项 ID
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<(usize, SceneItem)>`，表示被移除场景项的原位置和场景项
+    pub fn remove_item(&mut self, scene: &str, id: u64) -> Result<(usize, SceneItem)> {
+        let scene = self.try_scene_mut(scene)?;
+        let index = scene
+            .item_index(id)
+            .ok_or_else(|| anyhow!("scene item not found: {}", id))?;
+
+        Ok((index, scene.items.remove(index)))
+    }
+
+    /// 设置场景项的变换
+    ///
+    /// # 参数
+    ///
+    /// * `scene` - 场景 UUID
+    /// * `id` - 场景项 ID
+    /// * `transform` - 新的变换
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，表示操作是否成功
+    pub fn set_transform(&mut self, scene: &str, id: u64, transform: Transform) -> Result<()> {
+        let item = self
+            .try_scene_mut(scene)?
+            .item_mut(id)
+            .ok_or_else(|| anyhow!("scene item not found: {}", id))?;
+        item.transform = transform;
+
+        Ok(())
+    }
+
+    /// 设置场景项的顺序
+    ///
+    /// # 参数
+    ///
+    /// * `scene` - 场景 UUID
+    /// * `order` - 场景项 ID 列表（从底到顶），必须与场景现有场景项一一对应
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，表示操作是否成功
+    pub fn set_item_order(&mut self, scene: &str, order: &[u64]) -> Result<()> {
+        let scene = self.try_scene_mut(scene)?;
+
+        let mut current = scene.item_order();
+        let mut requested = order.to_vec();
+        current.sort_unstable();
+        requested.sort_unstable();
+        if current != requested {
+            return Err(anyhow!("item order does not match scene items"));
+        }
+
+        let mut items = std::mem::take(&mut scene.items);
+        for id in order {
+            if let Some(index) = items.iter().position(|item| item.id == *id) {
+                scene.items.push(items.remove(index));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 重命名来源或场景
+    ///
+    /// # 参数
+    ///
+    /// * `uuid` - 来源或场景 UUID
+    /// * `name` - 新名称
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<String>`，表示原名称
+    pub fn rename(&mut self, uuid: &str, name: &str) -> Result<String> {
+        if name.trim().is_empty() {
+            return Err(anyhow!("name must not be empty"));
+        }
+
+        let current = match self.source(uuid) {
+            Some(source) => source.name.clone(),
+            None => self.try_scene(uuid)?.name.clone(),
+        };
+        if current != name && self.name_in_use(name) {
+            return Err(anyhow!("name already in use: {}", name));
+        }
+
+        if let Some(source) = self.source_mut(uuid) {
+            source.name = name.to_string();
+        } else {
+            self.try_scene_mut(uuid)?.name = name.to_string();
+        }
+
+        Ok(current)
+    }
+
+    /// 设置来源的滤镜列表
+    ///
+    /// # 参数
+    ///
+    /// * `uuid` - 来源 UUID
+    /// * `filters` - 新的滤镜列表
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Vec<Filter>>`，表示原滤镜列表
+    pub fn set_filters(&mut self, uuid: &str, filters: Vec<Filter>) -> Result<Vec<Filter>> {
+        let source = self
+            .source_mut(uuid)
+            .ok_or_else(|| anyhow!("source not found: {}", uuid))?;
+
+        Ok(std::mem::replace(&mut source.filters, filters))
+    }
+
+    /// 获取场景或来源的显示名称
+    pub fn display_name(&self, uuid: &str) -> String {
+        match self.source(uuid) {
+            Some(source) => source.name.clone(),
+            None => self
+                .scene(uuid)
+                .map(|scene| scene.name.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn default_item_id() -> u64 {
+    1
+}
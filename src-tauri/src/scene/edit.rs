@@ -0,0 +1,634 @@
+/// 场景编辑模块
+///
+/// 所有会修改场景集合的编辑操作都经由此处执行，并记录到撤销栈
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    hotkeys::{register_scene_hotkey, register_source_hotkeys, unregister_source_hotkeys},
+    scene::{
+        item::SceneItem,
+        notify_scenes_changed, scenes,
+        source::{Filter, Source},
+        transform::Transform,
+        undo::{push_undo, UndoCommand},
+    },
+    utils::locale::tf,
+    Result,
+};
+
+/// 请求前端打开编辑变换面板的事件名称
+pub const EDIT_TRANSFORM_EVENT: &str = "edit-transform";
+
+/// 编辑变换面板的目标场景项
+#[derive(Debug, Clone, Serialize)]
+pub struct EditTransformTarget {
+    pub scene: String,
+    pub item: u64,
+    pub transform: Transform,
+}
+
+/// 场景项顺序调整方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderMovement {
+    Up,
+    Down,
+    Top,
+    Bottom,
+}
+
+/// 设置当前场景
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn set_current_scene(app: &AppHandle, scene: &str) -> Result<()> {
+    scenes().set_current_scene(scene)?;
+    notify_scenes_changed(app);
+
+    Ok(())
+}
+
+/// 设置场景中被选中的场景项
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `ids` - 被选中的场景项 ID
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn select_items(app: &AppHandle, scene: &str, ids: &[u64]) -> Result<()> {
+    {
+        let mut collection = scenes();
+        for item in collection.try_scene_mut(scene)?.items.iter_mut() {
+            item.selected = ids.contains(&item.id);
+        }
+    }
+
+    notify_scenes_changed(app);
+
+    Ok(())
+}
+
+/// 添加场景
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `name` - 场景名称
+/// * `canvas` - 画布 UUID，为 `None` 时添加到主画布
+///
+/// # 返回值
+///
+/// 返回 `Result<String>`，表示新场景的 UUID
+pub fn add_scene(app: &AppHandle, name: &str, canvas: Option<&str>) -> Result<String> {
+    let uuid = scenes().add_canvas_scene(name, canvas)?;
+
+    register_scene_hotkey(app, &uuid, name)?;
+    notify_scenes_changed(app);
+
+    Ok(uuid)
+}
+
+/// 创建新来源并添加到场景
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `name` - 来源名称
+/// * `kind` - 来源类型 ID
+/// * `settings` - 来源设置
+///
+/// # 返回值
+///
+/// 返回 `Result<SceneItem>`，表示新创建的场景项
+pub fn add_source(
+    app: &AppHandle,
+    scene: &str,
+    name: &str,
+    kind: &str,
+    settings: Value,
+) -> Result<SceneItem> {
+    let (item, command) = {
+        let mut collection = scenes();
+        collection.try_scene(scene)?;
+
+        let source = Source::new(name, kind, settings);
+        let uuid = collection.add_source(source.clone())?;
+        let item = collection.add_item(scene, &uuid)?;
+        let index = collection.try_scene(scene)?.items.len() - 1;
+
+        let command = UndoCommand::AddItems {
+            scene: scene.to_string(),
+            items: vec![(index, item.clone())],
+            sources: vec![source],
+        };
+
+        (item, command)
+    };
+
+    register_source_hotkeys(app, &item.source, name, kind)?;
+    push_undo(app, &tf("Undo.Add", &[name])?, command, false);
+    notify_scenes_changed(app);
+
+    Ok(item)
+}
+
+/// 将已存在的来源添加到场景
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `source` - 来源 UUID
+///
+/// # 返回值
+///
+/// 返回 `Result<SceneItem>`，表示新创建的场景项
+pub fn add_existing_source(app: &AppHandle, scene: &str, source: &str) -> Result<SceneItem> {
+    let (item, name, command) = {
+        let mut collection = scenes();
+        let item = collection.add_item(scene, source)?;
+        let index = collection.try_scene(scene)?.items.len() - 1;
+
+        let command = UndoCommand::AddItems {
+            scene: scene.to_string(),
+            items: vec![(index, item.clone())],
+            sources: vec![],
+        };
+
+        (item, collection.display_name(source), command)
+    };
+
+    push_undo(app, &tf("Undo.Add", &[&name])?, command, false);
+    notify_scenes_changed(app);
+
+    Ok(item)
+}
+
+/// 从场景中删除场景项，不再被引用的来源随之移除
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `ids` - 场景项 ID 列表
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn remove_items(app: &AppHandle, scene: &str, ids: &[u64]) -> Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let (name, command) = {
+        let mut collection = scenes();
+
+        // 先确认全部存在，避免部分删除
+        let scene_ref = collection.try_scene(scene)?;
+        let mut indexes = vec![];
+        for id in ids {
+            let index = scene_ref
+                .item_index(*id)
+                .ok_or_else(|| anyhow!("scene item not found: {}", id))?;
+            indexes.push(index);
+        }
+        indexes.sort_unstable();
+        indexes.dedup();
+
+        let mut items: Vec<(usize, SceneItem)> = indexes
+            .iter()
+            .map(|index| (*index, scene_ref.items[*index].clone()))
+            .collect();
+
+        for (_, item) in items.iter().rev() {
+            collection.remove_item(scene, item.id)?;
+        }
+
+        let mut sources = vec![];
+        for (_, item) in &items {
+            if let Some(source) = collection.remove_unused_source(&item.source) {
+                sources.push(source);
+            }
+        }
+
+        let name = if items.len() == 1 {
+            tf(
+                "Undo.Delete",
+                &[&collection.display_name(&items[0].1.source)],
+            )?
+        } else {
+            tf("Undo.Sources.Multi", &[&items.len().to_string()])?
+        };
+
+        for (_, item) in items.iter_mut() {
+            item.selected = false;
+        }
+
+        for source in &sources {
+            unregister_source_hotkeys(&source.uuid, &source.kind);
+        }
+
+        let command = UndoCommand::RemoveItems {
+            scene: scene.to_string(),
+            items,
+            sources,
+        };
+
+        (name, command)
+    };
+
+    push_undo(app, &name, command, false);
+    notify_scenes_changed(app);
+
+    Ok(())
+}
+
+/// 设置场景项的变换
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `transforms` - 场景项 ID 与新变换的列表
+/// * `undo_key` - 撤销名称的翻译键，例如 `Undo.Transform`
+/// * `mergeable` - 是否与相邻的同类变换合并，拖动过程中应为 `true`
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn transform_items(
+    app: &AppHandle,
+    scene: &str,
+    transforms: &[(u64, Transform)],
+    undo_key: &str,
+    mergeable: bool,
+) -> Result<()> {
+    if transforms.is_empty() {
+        return Ok(());
+    }
+
+    let (name, command) = {
+        let mut collection = scenes();
+        let scene_ref = collection.try_scene(scene)?;
+
+        let mut before = vec![];
+        for (id, _) in transforms {
+            let item = scene_ref
+                .item(*id)
+                .ok_or_else(|| anyhow!("scene item not found: {}", id))?;
+            before.push((*id, item.transform));
+        }
+        let name = tf(undo_key, &[&scene_ref.name])?;
+
+        for (id, transform) in transforms {
+            collection.set_transform(scene, *id, *transform)?;
+        }
+
+        let command = UndoCommand::Transform {
+            scene: scene.to_string(),
+            before,
+            after: transforms.to_vec(),
+        };
+
+        (name, command)
+    };
+
+    push_undo(app, &name, command, mergeable);
+    notify_scenes_changed(app);
+
+    Ok(())
+}
+
+/// 重置当前场景中被选中场景项的变换
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn reset_selected_transform(app: &AppHandle) -> Result<()> {
+    let (scene, ids) = match scenes().current_scene() {
+        Some(scene) => (scene.uuid.clone(), scene.selected_items()),
+        None => return Ok(()),
+    };
+
+    let transforms: Vec<(u64, Transform)> =
+        ids.iter().map(|id| (*id, Transform::default())).collect();
+
+    transform_items(app, &scene, &transforms, "Undo.Transform.Reset", false)
+}
+
+/// 请求前端为当前场景中第一个被选中的场景项打开编辑变换面板
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn request_edit_transform(app: &AppHandle) -> Result<()> {
+    let target = {
+        let collection = scenes();
+        let scene = match collection.current_scene() {
+            Some(scene) => scene,
+            None => return Ok(()),
+        };
+
+        match scene.items.iter().find(|item| item.selected) {
+            Some(item) => EditTransformTarget {
+                scene: scene.uuid.clone(),
+                item: item.id,
+                transform: item.transform,
+            },
+            None => return Ok(()),
+        }
+    };
+
+    app.emit(EDIT_TRANSFORM_EVENT, target)?;
+
+    Ok(())
+}
+
+/// 按指定方式调整场景项的顺序
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `ids` - 要移动的场景项 ID
+/// * `movement` - 移动方式
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn move_items(
+    app: &AppHandle,
+    scene: &str,
+    ids: &[u64],
+    movement: OrderMovement,
+) -> Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let (name, order) = {
+        let collection = scenes();
+        let scene_ref = collection.try_scene(scene)?;
+        let mut order = scene_ref.item_order();
+
+        match movement {
+            OrderMovement::Up => {
+                for index in (0..order.len().saturating_sub(1)).rev() {
+                    if ids.contains(&order[index]) && !ids.contains(&order[index + 1]) {
+                        order.swap(index, index + 1);
+                    }
+                }
+            }
+            OrderMovement::Down => {
+                for index in 1..order.len() {
+                    if ids.contains(&order[index]) && !ids.contains(&order[index - 1]) {
+                        order.swap(index, index - 1);
+                    }
+                }
+            }
+            OrderMovement::Top => {
+                let (moved, rest): (Vec<u64>, Vec<u64>) =
+                    order.iter().partition(|id| ids.contains(id));
+                order = rest.into_iter().chain(moved).collect();
+            }
+            OrderMovement::Bottom => {
+                let (moved, rest): (Vec<u64>, Vec<u64>) =
+                    order.iter().partition(|id| ids.contains(id));
+                order = moved.into_iter().chain(rest).collect();
+            }
+        }
+
+        let name = if ids.len() == 1 {
+            let source = scene_ref
+                .item(ids[0])
+                .map(|item| collection.display_name(&item.source))
+                .unwrap_or_default();
+            let key = match movement {
+                OrderMovement::Up => "Undo.MoveUp",
+                OrderMovement::Down => "Undo.MoveDown",
+                OrderMovement::Top => "Undo.MoveToTop",
+                OrderMovement::Bottom => "Undo.MoveToBottom",
+            };
+            tf(key, &[&source, &scene_ref.name])?
+        } else {
+            tf("Undo.ReorderSources", &[&scene_ref.name])?
+        };
+
+        (name, order)
+    };
+
+    reorder_items(app, scene, &order, &name)
+}
+
+/// 设置场景项的完整顺序
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `scene` - 场景 UUID
+/// * `order` - 场景项 ID 列表（从底到顶）
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn set_item_order(app: &AppHandle, scene: &str, order: &[u64]) -> Result<()> {
+    let name = {
+        let collection = scenes();
+        tf("Undo.ReorderSources", &[&collection.try_scene(scene)?.name])?
+    };
+
+    reorder_items(app, scene, order, &name)
+}
+
+fn reorder_items(app: &AppHandle, scene: &str, order: &[u64], name: &str) -> Result<()> {
+    let command = {
+        let mut collection = scenes();
+        let before = collection.try_scene(scene)?.item_order();
+        if before == order {
+            return Ok(());
+        }
+
+        collection.set_item_order(scene, order)?;
+
+        UndoCommand::Reorder {
+            scene: scene.to_string(),
+            before,
+            after: order.to_vec(),
+        }
+    };
+
+    push_undo(app, name, command, false);
+    notify_scenes_changed(app);
+
+    Ok(())
+}
+
+/// 重命名来源或场景
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `uuid` - 来源或场景 UUID
+/// * `name` - 新名称
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn rename(app: &AppHandle, uuid: &str, name: &str) -> Result<()> {
+    let command = {
+        let mut collection = scenes();
+        let before = collection.rename(uuid, name)?;
+        if before == name {
+            return Ok(());
+        }
+
+        UndoCommand::Rename {
+            uuid: uuid.to_string(),
+            before,
+            after: name.to_string(),
+        }
+    };
+
+    update_renamed_hotkeys(app, uuid, name)?;
+
+    push_undo(app, &tf("Undo.Rename", &[name])?, command, false);
+    notify_scenes_changed(app);
+
+    Ok(())
+}
+
+/// 场景或来源重命名后更新场景切换、静音和媒体控制热键的描述，撤销和重做重命名时同样调用
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `uuid` - 场景或来源 UUID
+/// * `name` - 当前名称
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn update_renamed_hotkeys(app: &AppHandle, uuid: &str, name: &str) -> Result<()> {
+    if scenes().scene(uuid).is_some() {
+        register_scene_hotkey(app, uuid, name)?;
+    }
+    let kind = scenes().source(uuid).map(|source| source.kind.clone());
+    if let Some(kind) = kind {
+        register_source_hotkeys(app, uuid, name, &kind)?;
+    }
+
+    Ok(())
+}
+
+/// 来源加入或移出场景集合后注册或注销它们的热键，撤销和重做添加、删除时调用
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `added` - 加入的来源
+/// * `removed` - 移出的来源
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn update_source_hotkeys(app: &AppHandle, added: &[Source], removed: &[Source]) -> Result<()> {
+    for source in added {
+        register_source_hotkeys(app, &source.uuid, &source.name, &source.kind)?;
+    }
+    for source in removed {
+        unregister_source_hotkeys(&source.uuid, &source.kind);
+    }
+
+    Ok(())
+}
+
+/// 设置来源是否静音，由静音热键调用，不记录到撤销栈
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `source` - 来源 UUID
+/// * `muted` - 是否静音
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn set_source_muted(app: &AppHandle, source: &str, muted: bool) -> Result<()> {
+    {
+        let mut collection = scenes();
+        let source = collection
+            .source_mut(source)
+            .ok_or_else(|| anyhow!("source not found: {}", source))?;
+        if source.muted == muted {
+            return Ok(());
+        }
+        source.muted = muted;
+    }
+
+    notify_scenes_changed(app);
+
+    Ok(())
+}
+
+/// 设置来源的滤镜列表
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `source` - 来源 UUID
+/// * `filters` - 新的滤镜列表
+/// * `mergeable` - 是否与相邻的滤镜变化合并，例如拖动滑块
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn set_filters(
+    app: &AppHandle,
+    source: &str,
+    filters: Vec<Filter>,
+    mergeable: bool,
+) -> Result<()> {
+    let (name, command) = {
+        let mut collection = scenes();
+        let before = collection.set_filters(source, filters.clone())?;
+        if before == filters {
+            return Ok(());
+        }
+
+        let name = tf("Undo.Filters", &[&collection.display_name(source)])?;
+        let command = UndoCommand::Filters {
+            source: source.to_string(),
+            before,
+            after: filters,
+        };
+
+        (name, command)
+    };
+
+    push_undo(app, &name, command, mergeable);
+    notify_scenes_changed(app);
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+/// 场景项模块
+use serde::{Deserialize, Serialize};
+
+use crate::scene::transform::Transform;
+
+/// 场景项，引用场景集合中的一个来源
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneItem {
+    /// 场景项 ID，在所属场景内唯一
+    pub id: u64,
+    /// 引用的来源 UUID
+    pub source: String,
+    /// 变换
+    #[serde(default)]
+    pub transform: Transform,
+    /// 是否可见
+    #[serde(default = "default_true")]
+    pub visible: bool,
+    /// 是否锁定
+    #[serde(default)]
+    pub locked: bool,
+    /// 是否被选中
+    #[serde(default)]
+    pub selected: bool,
+}
+
+impl SceneItem {
+    /// 创建新的场景项
+    ///
+    /// # 参数
+    ///
+    /// * `id` - 场景项 ID
+    /// * `source` - 引用的来源 UUID
+    ///
+    /// # 返回值
+    ///
+    /// 返回新创建的 `SceneItem`
+    pub fn new(id: u64, source: &str) -> Self {
+        Self {
+            id,
+            source: source.to_string(),
+            transform: Transform::default(),
+            visible: true,
+            locked: false,
+            selected: false,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
@@ -0,0 +1,139 @@
+#![allow(dead_code)]
+
+pub mod canvas;
+pub mod clipboard;
+pub mod collection;
+pub mod edit;
+pub mod item;
+pub mod projector;
+pub mod source;
+pub mod transform;
+pub mod undo;
+
+use std::{
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, OnceLock},
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use log::{error, warn};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    scene::collection::SceneCollection,
+    utils::{cli::cli, config::get_config, locale::t},
+    Result,
+};
+
+/// 场景集合变化时发送给前端的事件名称
+pub const SCENES_CHANGED_EVENT: &str = "scenes-changed";
+
+/// 默认场景集合名称
+pub const DEFAULT_COLLECTION: &str = "Untitled";
+
+/// 场景集合文件路径
+static SCENES_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+lazy_static! {
+    /// 当前场景集合
+    static ref SCENES: Mutex<SceneCollection> = Mutex::new(SceneCollection::default());
+}
+
+/// 获取当前场景集合
+///
+/// # 返回值
+///
+/// 返回场景集合的锁守卫
+pub fn scenes() -> MutexGuard<'static, SceneCollection> {
+    SCENES.lock().unwrap()
+}
+
+/// 设置场景集合，从磁盘加载，不存在时创建默认场景
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn setup_scenes(app: &AppHandle) -> Result<()> {
+    let name = match cli()?.opt_starting_collection {
+        Some(name) => name,
+        None => get_config("Basic", "SceneCollection").unwrap_or(DEFAULT_COLLECTION.to_string()),
+    };
+    let file_name = get_config("Basic", "SceneCollectionFile").unwrap_or(name.clone());
+
+    let scenes_file = app.path().resolve(
+        PathBuf::from("basic/scenes").join(file_name + ".json"),
+        tauri::path::BaseDirectory::AppLocalData,
+    )?;
+    SCENES_FILE.get_or_init(|| scenes_file.clone());
+
+    let mut collection = match std::fs::read_to_string(&scenes_file) {
+        Ok(json) => match serde_json::from_str::<SceneCollection>(&json) {
+            Ok(collection) => collection,
+            Err(e) => {
+                error!("failed to parse {}: {}", scenes_file.display(), e);
+                SceneCollection::new(&name)
+            }
+        },
+        Err(_) => SceneCollection::new(&name),
+    };
+
+    if collection.scenes.iter().all(|scene| scene.canvas.is_some()) {
+        let name = collection.unique_name(&t("Basic.Scene")?);
+        collection.add_scene(&name)?;
+    }
+
+    if let Some(scene) = cli()?.opt_starting_scene {
+        match collection.scene_by_name(&scene) {
+            Some(scene) => collection.set_current_scene(&scene.uuid.clone())?,
+            None => warn!("starting scene not found: {}", scene),
+        }
+    }
+
+    *scenes() = collection;
+
+    Ok(())
+}
+
+/// 保存场景集合到磁盘
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn save_scenes() -> Result<()> {
+    let scenes_file = match SCENES_FILE.get() {
+        Some(scenes_file) => scenes_file,
+        None => return Err(anyhow!("scene collection not initialized")),
+    };
+
+    if let Some(dir) = scenes_file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let json = serde_json::to_string_pretty(&*scenes())?;
+    std::fs::write(scenes_file, json)?;
+
+    Ok(())
+}
+
+/// 通知前端场景集合已变化，并保存到磁盘
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+pub fn notify_scenes_changed(app: &AppHandle) {
+    if let Err(e) = save_scenes() {
+        error!("failed to save scene collection: {}", e);
+    }
+
+    if let Err(e) = app.emit(SCENES_CHANGED_EVENT, ()) {
+        error!("failed to emit {}: {}", SCENES_CHANGED_EVENT, e);
+    }
+
+    // 选中项或来源变化会影响复制/粘贴菜单的可用状态
+    clipboard::refresh_clipboard_menu(app);
+}
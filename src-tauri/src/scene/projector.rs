@@ -0,0 +1,66 @@
+/// 投影仪模块
+///
+/// 保存在场景集合中的投影仪，下次启动时重新打开
+use serde::{Deserialize, Serialize};
+
+/// 投影仪显示的内容
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "uuid", rename_all = "snake_case")]
+pub enum ProjectorKind {
+    Preview,
+    Program,
+    Multiview,
+    /// 场景，参数为场景 UUID
+    Scene(String),
+    /// 来源，参数为来源 UUID
+    Source(String),
+}
+
+/// 投影仪窗口的位置和尺寸（物理像素）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectorGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 保存的投影仪
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedProjector {
+    pub kind: ProjectorKind,
+    /// 全屏显示的显示器序号，为 `None` 时为窗口模式
+    #[serde(default)]
+    pub monitor: Option<usize>,
+    /// 窗口模式下的位置和尺寸
+    #[serde(default)]
+    pub geometry: Option<ProjectorGeometry>,
+    /// 窗口置顶
+    #[serde(default)]
+    pub always_on_top: bool,
+    /// 调整窗口大小时保持内容的宽高比
+    #[serde(default = "default_true")]
+    pub aspect_lock: bool,
+}
+
+impl SavedProjector {
+    /// 创建投影仪设置
+    ///
+    /// # 参数
+    ///
+    /// * `kind` - 显示的内容
+    /// * `monitor` - 全屏显示的显示器序号，为 `None` 时为窗口模式
+    pub fn new(kind: ProjectorKind, monitor: Option<usize>) -> Self {
+        Self {
+            kind,
+            monitor,
+            geometry: None,
+            always_on_top: false,
+            aspect_lock: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
@@ -0,0 +1,74 @@
+/// 来源模块
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 滤镜
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Filter {
+    /// 滤镜名称
+    pub name: String,
+    /// 滤镜类型 ID
+    pub kind: String,
+    /// 滤镜设置
+    #[serde(default)]
+    pub settings: Value,
+    /// 是否启用
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 来源
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    /// 来源唯一 ID
+    pub uuid: String,
+    /// 来源名称，在场景集合内唯一
+    pub name: String,
+    /// 来源类型 ID
+    pub kind: String,
+    /// 来源设置
+    #[serde(default)]
+    pub settings: Value,
+    /// 滤镜列表
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+    /// 是否静音
+    #[serde(default)]
+    pub muted: bool,
+    /// 音量（线性倍率）
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+}
+
+impl Source {
+    /// 创建新的来源
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 来源名称
+    /// * `kind` - 来源类型 ID
+    /// * `settings` - 来源设置
+    ///
+    /// # 返回值
+    ///
+    /// 返回新创建的 `Source`
+    pub fn new(name: &str, kind: &str, settings: Value) -> Self {
+        Self {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            kind: kind.to_string(),
+            settings,
+            filters: vec![],
+            muted: false,
+            volume: default_volume(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_volume() -> f32 {
+    1.
+}
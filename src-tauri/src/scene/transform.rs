@@ -0,0 +1,258 @@
+/// 场景项变换模块
+use anyhow::anyhow;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// 对齐方式：居中
+pub const ALIGN_CENTER: u32 = 0;
+/// 对齐方式：左
+pub const ALIGN_LEFT: u32 = 1 << 0;
+/// 对齐方式：右
+pub const ALIGN_RIGHT: u32 = 1 << 1;
+/// 对齐方式：上
+pub const ALIGN_TOP: u32 = 1 << 2;
+/// 对齐方式：下
+pub const ALIGN_BOTTOM: u32 = 1 << 3;
+
+/// 边界框类型
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundsType {
+    /// 无边界框
+    #[default]
+    None,
+    /// 拉伸到边界框
+    Stretch,
+    /// 缩放到内侧
+    ScaleInner,
+    /// 缩放到外侧
+    ScaleOuter,
+    /// 缩放到宽度
+    ScaleToWidth,
+    /// 缩放到高度
+    ScaleToHeight,
+    /// 仅限最大尺寸
+    MaxOnly,
+}
+
+/// 裁剪像素
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Crop {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+/// 场景项变换
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    /// 位置
+    pub pos: Vec2,
+    /// 旋转角度（度）
+    pub rot: f32,
+    /// 缩放
+    pub scale: Vec2,
+    /// 定位点对齐方式
+    pub alignment: u32,
+    /// 边界框类型
+    pub bounds_type: BoundsType,
+    /// 边界框内的对齐方式
+    pub bounds_alignment: u32,
+    /// 边界框尺寸
+    pub bounds: Vec2,
+    /// 裁剪
+    pub crop: Crop,
+    /// 是否裁剪到边界框
+    pub crop_to_bounds: bool,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            pos: Vec2::ZERO,
+            rot: 0.,
+            scale: Vec2::ONE,
+            alignment: ALIGN_LEFT | ALIGN_TOP,
+            bounds_type: BoundsType::None,
+            bounds_alignment: ALIGN_CENTER,
+            bounds: Vec2::ZERO,
+            crop: Crop::default(),
+            crop_to_bounds: false,
+        }
+    }
+}
+
+impl Transform {
+    /// 校验变换参数，并返回规范化后的变换（旋转角度归一到 `[0, 360)`）
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<Transform>`，参数无效时返回错误
+    pub fn validated(&self) -> Result<Transform> {
+        let values = [
+            self.pos.x,
+            self.pos.y,
+            self.rot,
+            self.scale.x,
+            self.scale.y,
+            self.bounds.x,
+            self.bounds.y,
+        ];
+        if values.iter().any(|value| !value.is_finite()) {
+            return Err(anyhow!("transform contains non-finite values"));
+        }
+
+        // 负缩放表示翻转，但不能为零
+        if self.scale.x == 0. || self.scale.y == 0. {
+            return Err(anyhow!("scale must not be zero"));
+        }
+
+        validate_alignment(self.alignment)?;
+        validate_alignment(self.bounds_alignment)?;
+
+        if self.bounds_type != BoundsType::None && (self.bounds.x < 1. || self.bounds.y < 1.) {
+            return Err(anyhow!("bounds must be at least 1x1"));
+        }
+        if self.bounds.x < 0. || self.bounds.y < 0. {
+            return Err(anyhow!("bounds must not be negative"));
+        }
+
+        let mut transform = *self;
+        // 极小的负角度取余后会得到 360
+        let rot = self.rot.rem_euclid(360.);
+        transform.rot = if rot >= 360. { 0. } else { rot };
+
+        Ok(transform)
+    }
+
+    /// 校验裁剪是否在来源尺寸之内，裁剪后至少保留 1 像素
+    ///
+    /// # 参数
+    ///
+    /// * `width` - 来源宽度
+    /// * `height` - 来源高度
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，裁剪超出来源尺寸时返回错误
+    pub fn validate_crop(&self, width: u32, height: u32) -> Result<()> {
+        let crop = &self.crop;
+        if crop.left as u64 + crop.right as u64 >= width as u64
+            || crop.top as u64 + crop.bottom as u64 >= height as u64
+        {
+            return Err(anyhow!(
+                "crop {}/{}/{}/{} exceeds source size {}x{}",
+                crop.left,
+                crop.top,
+                crop.right,
+                crop.bottom,
+                width,
+                height
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// 校验对齐方式，不能同时包含左右或上下
+fn validate_alignment(alignment: u32) -> Result<()> {
+    let all = ALIGN_LEFT | ALIGN_RIGHT | ALIGN_TOP | ALIGN_BOTTOM;
+
+    if alignment & !all != 0
+        || alignment & (ALIGN_LEFT | ALIGN_RIGHT) == ALIGN_LEFT | ALIGN_RIGHT
+        || alignment & (ALIGN_TOP | ALIGN_BOTTOM) == ALIGN_TOP | ALIGN_BOTTOM
+    {
+        return Err(anyhow!("invalid alignment: {}", alignment));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_is_normalized_to_range() {
+        for (rot, expected) in [
+            (0., 0.),
+            (90., 90.),
+            (360., 0.),
+            (-90., 270.),
+            (725., 5.),
+            (-1e-14, 0.),
+            (-1e-6, 0.),
+        ] {
+            let transform = Transform {
+                rot,
+                ..Default::default()
+            }
+            .validated()
+            .unwrap();
+            assert!(
+                (0. ..360.).contains(&transform.rot),
+                "{} -> {}",
+                rot,
+                transform.rot
+            );
+            assert!(
+                (transform.rot - expected).abs() < 1e-3,
+                "{} -> {}",
+                rot,
+                transform.rot
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let invalid = [
+            Transform {
+                pos: Vec2::new(f32::NAN, 0.),
+                ..Default::default()
+            },
+            Transform {
+                scale: Vec2::new(0., 1.),
+                ..Default::default()
+            },
+            Transform {
+                alignment: ALIGN_LEFT | ALIGN_RIGHT,
+                ..Default::default()
+            },
+            Transform {
+                bounds_type: BoundsType::Stretch,
+                bounds: Vec2::new(0.5, 10.),
+                ..Default::default()
+            },
+        ];
+        for transform in invalid {
+            assert!(transform.validated().is_err(), "{:?}", transform);
+        }
+    }
+
+    #[test]
+    fn crop_must_leave_visible_pixels() {
+        let crop = |left, top, right, bottom| Transform {
+            crop: Crop {
+                left,
+                top,
+                right,
+                bottom,
+            },
+            ..Default::default()
+        };
+
+        assert!(crop(0, 0, 0, 0).validate_crop(1920, 1080).is_ok());
+        assert!(crop(959, 0, 960, 1079).validate_crop(1920, 1080).is_ok());
+        assert!(crop(960, 0, 960, 0).validate_crop(1920, 1080).is_err());
+        assert!(crop(0, 2000, 0, 0).validate_crop(1920, 1080).is_err());
+        assert!(crop(u32::MAX, 0, u32::MAX, 0)
+            .validate_crop(1920, 1080)
+            .is_err());
+    }
+}
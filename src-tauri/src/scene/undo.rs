@@ -0,0 +1,826 @@
+/// 撤销/重做模块
+///
+/// 每个 `UndoCommand` 记录一次编辑前后的状态，可在场景集合上双向执行
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use log::error;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{
+    scene::{
+        collection::SceneCollection,
+        edit::{update_renamed_hotkeys, update_source_hotkeys},
+        item::SceneItem,
+        notify_scenes_changed, scenes,
+        source::{Filter, Source},
+        transform::Transform,
+    },
+    ui::menu::{set_menu_enabled, set_menu_text},
+    utils::locale::{t, tf},
+    Result,
+};
+
+/// 撤销栈默认内存上限（字节）
+pub const MAX_UNDO_MEMORY: usize = 16 * 1024 * 1024;
+/// 撤销栈默认最大条目数
+pub const MAX_UNDO_ENTRIES: usize = 5000;
+/// 连续拖动合并的最大间隔
+pub const MERGE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 可撤销的编辑命令
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum UndoCommand {
+    /// 场景项变换
+    Transform {
+        scene: String,
+        before: Vec<(u64, Transform)>,
+        after: Vec<(u64, Transform)>,
+    },
+    /// 场景项顺序调整
+    Reorder {
+        scene: String,
+        before: Vec<u64>,
+        after: Vec<u64>,
+    },
+    /// 添加场景项，`sources` 为随之新建的来源
+    AddItems {
+        scene: String,
+        items: Vec<(usize, SceneItem)>,
+        sources: Vec<Source>,
+    },
+    /// 删除场景项，`sources` 为随之移除的来源
+    RemoveItems {
+        scene: String,
+        items: Vec<(usize, SceneItem)>,
+        sources: Vec<Source>,
+    },
+    /// 来源滤镜变化
+    Filters {
+        source: String,
+        before: Vec<Filter>,
+        after: Vec<Filter>,
+    },
+    /// 来源或场景重命名
+    Rename {
+        uuid: String,
+        before: String,
+        after: String,
+    },
+}
+
+/// 将场景项和来源加入场景集合
+fn insert_items(
+    collection: &mut SceneCollection,
+    scene: &str,
+    items: &[(usize, SceneItem)],
+    sources: &[Source],
+) -> Result<()> {
+    for source in sources {
+        collection
+            .sources
+            .insert(source.uuid.clone(), source.clone());
+    }
+
+    // 按原位置从小到大插入，保证位置正确
+    for (index, item) in items {
+        collection.insert_item(scene, *index, item.clone())?;
+    }
+
+    Ok(())
+}
+
+/// 从场景集合中移除场景项和来源
+fn remove_items(
+    collection: &mut SceneCollection,
+    scene: &str,
+    items: &[(usize, SceneItem)],
+    sources: &[Source],
+) -> Result<()> {
+    for (_, item) in items.iter().rev() {
+        collection.remove_item(scene, item.id)?;
+    }
+
+    for source in sources {
+        collection.sources.remove(&source.uuid);
+    }
+
+    Ok(())
+}
+
+/// 设置多个场景项的变换
+fn apply_transforms(
+    collection: &mut SceneCollection,
+    scene: &str,
+    transforms: &[(u64, Transform)],
+) -> Result<()> {
+    for (id, transform) in transforms {
+        collection.set_transform(scene, *id, *transform)?;
+    }
+
+    Ok(())
+}
+
+impl UndoCommand {
+    /// 撤销命令
+    ///
+    /// # 参数
+    ///
+    /// * `collection` - 场景集合
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，表示操作是否成功
+    pub fn undo(&self, collection: &mut SceneCollection) -> Result<()> {
+        match self {
+            UndoCommand::Transform { scene, before, .. } => {
+                apply_transforms(collection, scene, before)
+            }
+            UndoCommand::Reorder { scene, before, .. } => collection.set_item_order(scene, before),
+            UndoCommand::AddItems {
+                scene,
+                items,
+                sources,
+            } => remove_items(collection, scene, items, sources),
+            UndoCommand::RemoveItems {
+                scene,
+                items,
+                sources,
+            } => insert_items(collection, scene, items, sources),
+            UndoCommand::Filters { source, before, .. } => {
+                collection.set_filters(source, before.clone()).map(|_| ())
+            }
+            UndoCommand::Rename { uuid, before, .. } => collection.rename(uuid, before).map(|_| ()),
+        }
+    }
+
+    /// 重做命令
+    ///
+    /// # 参数
+    ///
+    /// * `collection` - 场景集合
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<()>`，表示操作是否成功
+    pub fn redo(&self, collection: &mut SceneCollection) -> Result<()> {
+        match self {
+            UndoCommand::Transform { scene, after, .. } => {
+                apply_transforms(collection, scene, after)
+            }
+            UndoCommand::Reorder { scene, after, .. } => collection.set_item_order(scene, after),
+            UndoCommand::AddItems {
+                scene,
+                items,
+                sources,
+            } => insert_items(collection, scene, items, sources),
+            UndoCommand::RemoveItems {
+                scene,
+                items,
+                sources,
+            } => remove_items(collection, scene, items, sources),
+            UndoCommand::Filters { source, after, .. } => {
+                collection.set_filters(source, after.clone()).map(|_| ())
+            }
+            UndoCommand::Rename { uuid, after, .. } => collection.rename(uuid, after).map(|_| ()),
+        }
+    }
+
+    /// 尝试将后续命令合并到当前命令，仅对同一组场景项的变换或同一来源的滤镜变化生效
+    ///
+    /// # 参数
+    ///
+    /// * `next` - 后续命令
+    ///
+    /// # 返回值
+    ///
+    /// 返回是否合并成功
+    fn merge(&mut self, next: &UndoCommand) -> bool {
+        match (self, next) {
+            (
+                UndoCommand::Transform { scene, after, .. },
+                UndoCommand::Transform {
+                    scene: next_scene,
+                    before: next_before,
+                    after: next_after,
+                },
+            ) => {
+                let same_items = after.len() == next_before.len()
+                    && after
+                        .iter()
+                        .zip(next_before.iter())
+                        .all(|((id, _), (next_id, _))| id == next_id);
+
+                if scene != next_scene || !same_items {
+                    return false;
+                }
+
+                *after = next_after.clone();
+                true
+            }
+            (
+                UndoCommand::Filters { source, after, .. },
+                UndoCommand::Filters {
+                    source: next_source,
+                    after: next_after,
+                    ..
+                },
+            ) => {
+                if source != next_source {
+                    return false;
+                }
+
+                *after = next_after.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 重命名命令撤销或重做后的 UUID 和名称，用于更新热键描述
+    ///
+    /// # 参数
+    ///
+    /// * `undo` - 是否为撤销
+    fn renamed(&self, undo: bool) -> Option<(String, String)> {
+        match self {
+            UndoCommand::Rename {
+                uuid,
+                before,
+                after,
+            } => Some((uuid.clone(), if undo { before } else { after }.clone())),
+            _ => None,
+        }
+    }
+
+    /// 撤销或重做后加入和移出场景集合的来源，用于注册和注销来源热键
+    ///
+    /// # 参数
+    ///
+    /// * `undo` - 是否为撤销
+    ///
+    /// # 返回值
+    ///
+    /// 返回加入的来源和移出的来源
+    fn changed_sources(&self, undo: bool) -> (Vec<Source>, Vec<Source>) {
+        match self {
+            UndoCommand::AddItems { sources, .. } if undo => (vec![], sources.clone()),
+            UndoCommand::AddItems { sources, .. } => (sources.clone(), vec![]),
+            UndoCommand::RemoveItems { sources, .. } if undo => (sources.clone(), vec![]),
+            UndoCommand::RemoveItems { sources, .. } => (vec![], sources.clone()),
+            _ => (vec![], vec![]),
+        }
+    }
+
+    /// 估算命令占用的内存
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>() + serde_json::to_vec(self).map(|v| v.len()).unwrap_or(0)
+    }
+}
+
+/// 撤销栈条目
+#[derive(Debug, Clone)]
+struct UndoEntry {
+    /// 显示在菜单中的名称
+    name: String,
+    /// 命令
+    command: UndoCommand,
+    /// 是否允许与后续命令合并
+    mergeable: bool,
+    /// 最后更新时间
+    updated: Instant,
+    /// 估算的内存占用
+    size: usize,
+}
+
+/// 撤销栈
+#[derive(Debug)]
+pub struct UndoStack {
+    undo_entries: VecDeque<UndoEntry>,
+    redo_entries: Vec<UndoEntry>,
+    memory: usize,
+    max_memory: usize,
+    max_entries: usize,
+}
+
+impl Default for UndoStack {
+    fn default() -> Self {
+        Self::new(MAX_UNDO_MEMORY, MAX_UNDO_ENTRIES)
+    }
+}
+
+impl UndoStack {
+    /// 创建撤销栈
+    ///
+    /// # 参数
+    ///
+    /// * `max_memory` - 内存上限（字节），超出时丢弃最早的条目
+    /// * `max_entries` - 最大条目数
+    ///
+    /// # 返回值
+    ///
+    /// 返回新创建的 `UndoStack`
+    pub fn new(max_memory: usize, max_entries: usize) -> Self {
+        Self {
+            undo_entries: VecDeque::new(),
+            redo_entries: vec![],
+            memory: 0,
+            max_memory,
+            max_entries,
+        }
+    }
+
+    /// 添加已执行的命令
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 显示名称
+    /// * `command` - 命令
+    /// * `mergeable` - 是否允许与相邻的同类命令合并（例如拖动中的连续变换）
+    pub fn push(&mut self, name: &str, command: UndoCommand, mergeable: bool) {
+        self.clear_redo();
+
+        if mergeable {
+            if let Some(top) = self.undo_entries.back_mut() {
+                if top.mergeable
+                    && top.name == name
+                    && top.updated.elapsed() <= MERGE_INTERVAL
+                    && top.command.merge(&command)
+                {
+                    let size = top.command.size();
+                    self.memory = self.memory - top.size + size;
+                    top.size = size;
+                    top.updated = Instant::now();
+                    return;
+                }
+            }
+        }
+
+        let size = command.size();
+        self.memory += size;
+        self.undo_entries.push_back(UndoEntry {
+            name: name.to_string(),
+            command,
+            mergeable,
+            updated: Instant::now(),
+            size,
+        });
+
+        while self.undo_entries.len() > 1
+            && (self.memory > self.max_memory || self.undo_entries.len() > self.max_entries)
+        {
+            if let Some(entry) = self.undo_entries.pop_front() {
+                self.memory -= entry.size;
+            }
+        }
+    }
+
+    /// 撤销最近的命令
+    ///
+    /// # 参数
+    ///
+    /// * `collection` - 场景集合
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<String>`，表示被撤销命令的名称
+    pub fn undo(&mut self, collection: &mut SceneCollection) -> Result<String> {
+        let entry = match self.undo_entries.pop_back() {
+            Some(entry) => entry,
+            None => return Err(anyhow!("nothing to undo")),
+        };
+        self.memory -= entry.size;
+
+        // 执行失败说明状态已与记录不一致，丢弃该条目
+        entry.command.undo(collection)?;
+
+        let name = entry.name.clone();
+        self.redo_entries.push(UndoEntry {
+            mergeable: false,
+            ..entry
+        });
+
+        Ok(name)
+    }
+
+    /// 重做最近撤销的命令
+    ///
+    /// # 参数
+    ///
+    /// * `collection` - 场景集合
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<String>`，表示被重做命令的名称
+    pub fn redo(&mut self, collection: &mut SceneCollection) -> Result<String> {
+        let entry = match self.redo_entries.pop() {
+            Some(entry) => entry,
+            None => return Err(anyhow!("nothing to redo")),
+        };
+
+        entry.command.redo(collection)?;
+
+        let name = entry.name.clone();
+        self.memory += entry.size;
+        self.undo_entries.push_back(entry);
+
+        Ok(name)
+    }
+
+    /// 清空撤销栈，切换场景集合时调用
+    pub fn clear(&mut self) {
+        self.undo_entries.clear();
+        self.redo_entries.clear();
+        self.memory = 0;
+    }
+
+    fn clear_redo(&mut self) {
+        self.redo_entries.clear();
+    }
+
+    /// 可撤销命令的名称
+    pub fn undo_name(&self) -> Option<&str> {
+        self.undo_entries.back().map(|entry| entry.name.as_str())
+    }
+
+    /// 可重做命令的名称
+    pub fn redo_name(&self) -> Option<&str> {
+        self.redo_entries.last().map(|entry| entry.name.as_str())
+    }
+
+    /// 估算的内存占用
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+}
+
+lazy_static! {
+    /// 全局撤销栈
+    static ref UNDO: Mutex<UndoStack> = Mutex::new(UndoStack::default());
+}
+
+/// 更新编辑菜单中撤销/重做项的状态和文本
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn update_undo_menu(app: &AppHandle) -> Result<()> {
+    let (undo_name, redo_name) = {
+        let stack = UNDO.lock().unwrap();
+        (
+            stack.undo_name().map(|name| name.to_string()),
+            stack.redo_name().map(|name| name.to_string()),
+        )
+    };
+
+    match undo_name {
+        Some(name) => {
+            set_menu_text(app, "Undo.Undo", &tf("Undo.Item.Undo", &[&name])?)?;
+            set_menu_enabled(app, "Undo.Undo", true)?;
+        }
+        None => {
+            set_menu_text(app, "Undo.Undo", &t("Undo.Undo")?)?;
+            set_menu_enabled(app, "Undo.Undo", false)?;
+        }
+    }
+
+    match redo_name {
+        Some(name) => {
+            set_menu_text(app, "Undo.Redo", &tf("Undo.Item.Redo", &[&name])?)?;
+            set_menu_enabled(app, "Undo.Redo", true)?;
+        }
+        None => {
+            set_menu_text(app, "Undo.Redo", &t("Undo.Redo")?)?;
+            set_menu_enabled(app, "Undo.Redo", false)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 记录已执行的编辑命令并更新菜单
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `name` - 显示名称
+/// * `command` - 命令
+/// * `mergeable` - 是否允许与相邻的同类命令合并
+pub fn push_undo(app: &AppHandle, name: &str, command: UndoCommand, mergeable: bool) {
+    UNDO.lock().unwrap().push(name, command, mergeable);
+
+    if let Err(e) = update_undo_menu(app) {
+        error!("failed to update undo menu: {}", e);
+    }
+}
+
+/// 撤销最近的编辑
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<String>`，表示被撤销命令的名称
+pub fn undo(app: &AppHandle) -> Result<String> {
+    let (result, renamed, (added, removed)) = {
+        let mut collection = scenes();
+        let mut stack = UNDO.lock().unwrap();
+        let command = stack.undo_entries.back().map(|entry| &entry.command);
+        let renamed = command.and_then(|command| command.renamed(true));
+        let changed = command
+            .map(|command| command.changed_sources(true))
+            .unwrap_or_default();
+        (stack.undo(&mut collection), renamed, changed)
+    };
+
+    if let (Ok(_), Some((uuid, name))) = (&result, renamed) {
+        update_renamed_hotkeys(app, &uuid, &name)?;
+    }
+    if result.is_ok() {
+        update_source_hotkeys(app, &added, &removed)?;
+    }
+    update_undo_menu(app)?;
+    notify_scenes_changed(app);
+
+    result
+}
+
+/// 重做最近撤销的编辑
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<String>`，表示被重做命令的名称
+pub fn redo(app: &AppHandle) -> Result<String> {
+    let (result, renamed, (added, removed)) = {
+        let mut collection = scenes();
+        let mut stack = UNDO.lock().unwrap();
+        let command = stack.redo_entries.last().map(|entry| &entry.command);
+        let renamed = command.and_then(|command| command.renamed(false));
+        let changed = command
+            .map(|command| command.changed_sources(false))
+            .unwrap_or_default();
+        (stack.redo(&mut collection), renamed, changed)
+    };
+
+    if let (Ok(_), Some((uuid, name))) = (&result, renamed) {
+        update_renamed_hotkeys(app, &uuid, &name)?;
+    }
+    if result.is_ok() {
+        update_source_hotkeys(app, &added, &removed)?;
+    }
+    update_undo_menu(app)?;
+    notify_scenes_changed(app);
+
+    result
+}
+
+/// 清空撤销栈
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn clear_undo(app: &AppHandle) -> Result<()> {
+    UNDO.lock().unwrap().clear();
+
+    update_undo_menu(app)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+    use serde_json::json;
+
+    use super::*;
+
+    /// 创建包含一个场景和一个场景项的集合
+    fn collection() -> (SceneCollection, String, u64) {
+        let mut collection = SceneCollection::new("test");
+        let scene = collection.add_scene("Scene").unwrap();
+        let source = collection
+            .add_source(Source::new("Image", "image_source", json!({})))
+            .unwrap();
+        let item = collection.add_item(&scene, &source).unwrap().id;
+
+        (collection, scene, item)
+    }
+
+    fn position(collection: &SceneCollection, scene: &str, item: u64) -> Vec2 {
+        collection
+            .scene(scene)
+            .unwrap()
+            .item(item)
+            .unwrap()
+            .transform
+            .pos
+    }
+
+    /// 移动场景项并返回对应的命令
+    fn move_item(collection: &mut SceneCollection, scene: &str, item: u64, x: f32) -> UndoCommand {
+        let before = collection
+            .scene(scene)
+            .unwrap()
+            .item(item)
+            .unwrap()
+            .transform;
+        let after = Transform {
+            pos: Vec2::new(x, 0.),
+            ..before
+        };
+        collection.set_transform(scene, item, after).unwrap();
+
+        UndoCommand::Transform {
+            scene: scene.to_string(),
+            before: vec![(item, before)],
+            after: vec![(item, after)],
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_restore_states() {
+        let (mut collection, scene, item) = collection();
+        let mut stack = UndoStack::default();
+
+        let command = move_item(&mut collection, &scene, item, 10.);
+        stack.push("Move", command, false);
+        let before = collection.rename(&scene, "Renamed").unwrap();
+        stack.push(
+            "Rename",
+            UndoCommand::Rename {
+                uuid: scene.clone(),
+                before,
+                after: "Renamed".to_string(),
+            },
+            false,
+        );
+
+        assert_eq!(stack.undo(&mut collection).unwrap(), "Rename");
+        assert_eq!(collection.scene(&scene).unwrap().name, "Scene");
+        assert_eq!(stack.undo(&mut collection).unwrap(), "Move");
+        assert_eq!(position(&collection, &scene, item), Vec2::ZERO);
+        assert!(stack.undo(&mut collection).is_err());
+
+        assert_eq!(stack.redo(&mut collection).unwrap(), "Move");
+        assert_eq!(position(&collection, &scene, item), Vec2::new(10., 0.));
+        assert_eq!(stack.redo_name(), Some("Rename"));
+
+        // 新的编辑清空重做栈
+        let command = move_item(&mut collection, &scene, item, 20.);
+        stack.push("Move", command, false);
+        assert!(stack.redo(&mut collection).is_err());
+        assert_eq!(collection.scene(&scene).unwrap().name, "Scene");
+    }
+
+    #[test]
+    fn undo_rename_reports_restored_name() {
+        let command = UndoCommand::Rename {
+            uuid: "uuid".to_string(),
+            before: "Old".to_string(),
+            after: "New".to_string(),
+        };
+
+        assert_eq!(
+            command.renamed(true),
+            Some(("uuid".to_string(), "Old".to_string()))
+        );
+        assert_eq!(
+            command.renamed(false),
+            Some(("uuid".to_string(), "New".to_string()))
+        );
+    }
+
+    #[test]
+    fn consecutive_moves_merge_into_one_entry() {
+        let (mut collection, scene, item) = collection();
+        let mut stack = UndoStack::default();
+
+        for x in [1., 2., 3.] {
+            let command = move_item(&mut collection, &scene, item, x);
+            stack.push("Move", command, true);
+        }
+        // 不可合并的命令单独成为一条
+        let command = move_item(&mut collection, &scene, item, 4.);
+        stack.push("Move", command, false);
+
+        assert_eq!(stack.undo(&mut collection).unwrap(), "Move");
+        assert_eq!(position(&collection, &scene, item), Vec2::new(3., 0.));
+        assert_eq!(stack.undo(&mut collection).unwrap(), "Move");
+        assert_eq!(position(&collection, &scene, item), Vec2::ZERO);
+        assert!(stack.undo_name().is_none());
+    }
+
+    #[test]
+    fn moves_of_different_items_do_not_merge() {
+        let (mut collection, scene, item) = collection();
+        let other = collection
+            .add_item(
+                &scene,
+                &collection.scene(&scene).unwrap().items[0].source.clone(),
+            )
+            .unwrap()
+            .id;
+        let mut stack = UndoStack::default();
+
+        let command = move_item(&mut collection, &scene, item, 1.);
+        stack.push("Move", command, true);
+        let command = move_item(&mut collection, &scene, other, 2.);
+        stack.push("Move", command, true);
+
+        stack.undo(&mut collection).unwrap();
+        assert_eq!(position(&collection, &scene, item), Vec2::new(1., 0.));
+        assert_eq!(position(&collection, &scene, other), Vec2::ZERO);
+    }
+
+    #[test]
+    fn memory_cap_drops_oldest_entries() {
+        let (mut collection, scene, item) = collection();
+        // 位置都是两位数，每条命令的大小相同
+        move_item(&mut collection, &scene, item, 10.);
+        let first = move_item(&mut collection, &scene, item, 11.);
+        let size = first.size();
+        let mut stack = UndoStack::new(size * 3, MAX_UNDO_ENTRIES);
+        stack.push("Move 11", first, false);
+
+        for x in 12..=20 {
+            let command = move_item(&mut collection, &scene, item, x as f32);
+            stack.push(&format!("Move {}", x), command, false);
+            assert!(stack.memory() <= size * 3);
+        }
+
+        let mut names = vec![];
+        while let Ok(name) = stack.undo(&mut collection) {
+            names.push(name);
+        }
+        assert_eq!(names, ["Move 20", "Move 19", "Move 18"]);
+        assert_eq!(stack.memory(), 0);
+        assert_eq!(position(&collection, &scene, item), Vec2::new(17., 0.));
+    }
+
+    #[test]
+    fn entry_cap_drops_oldest_entries() {
+        let (mut collection, scene, item) = collection();
+        let mut stack = UndoStack::new(MAX_UNDO_MEMORY, 2);
+
+        for x in 1..=5 {
+            let command = move_item(&mut collection, &scene, item, x as f32);
+            stack.push(&format!("Move {}", x), command, false);
+        }
+
+        assert_eq!(stack.undo(&mut collection).unwrap(), "Move 5");
+        assert_eq!(stack.undo(&mut collection).unwrap(), "Move 4");
+        assert!(stack.undo(&mut collection).is_err());
+        assert_eq!(position(&collection, &scene, item), Vec2::new(3., 0.));
+    }
+
+    #[test]
+    fn changed_sources_follow_the_direction() {
+        let source = Source::new("Mic", "pulse_input_capture", json!({}));
+        let added = UndoCommand::AddItems {
+            scene: "scene".to_string(),
+            items: vec![],
+            sources: vec![source.clone()],
+        };
+        let removed = UndoCommand::RemoveItems {
+            scene: "scene".to_string(),
+            items: vec![],
+            sources: vec![source.clone()],
+        };
+
+        // 撤销添加移出来源，撤销删除加回来源
+        assert_eq!(added.changed_sources(true), (vec![], vec![source.clone()]));
+        assert_eq!(added.changed_sources(false), (vec![source.clone()], vec![]));
+        assert_eq!(
+            removed.changed_sources(true),
+            (vec![source.clone()], vec![])
+        );
+        assert_eq!(removed.changed_sources(false), (vec![], vec![source]));
+        assert_eq!(
+            UndoCommand::Rename {
+                uuid: "uuid".to_string(),
+                before: "A".to_string(),
+                after: "B".to_string(),
+            }
+            .changed_sources(true),
+            (vec![], vec![])
+        );
+    }
+}
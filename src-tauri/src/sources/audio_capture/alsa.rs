@@ -0,0 +1,366 @@
+/// ALSA 采集
+///
+/// 没有 PulseAudio 服务时直接打开内核的 PCM 设备，按 `hw:卡号,设备号` 选择设备。
+/// 设备拔出后读取失败，由采集线程重新打开
+use std::{
+    fs::{File, OpenOptions},
+    os::fd::AsRawFd,
+    path::PathBuf,
+};
+
+use anyhow::anyhow;
+
+use crate::{
+    media::audio::AudioFrame,
+    sources::audio_capture::{AudioDevice, CaptureStream, Captured, DEFAULT_DEVICE},
+    utils::locale::t,
+    Result,
+};
+
+/// 列出 PCM 设备的文件
+const PCM_LIST: &str = "/proc/asound/pcm";
+
+/// 首选的采样率和声道数
+const PREFERRED_RATE: u32 = 48000;
+const PREFERRED_CHANNELS: u32 = 2;
+
+/// 每次读取的采样数，约 20 毫秒
+const PERIOD_FRAMES: u32 = 1024;
+
+const SNDRV_PCM_HW_PARAM_ACCESS: usize = 0;
+const SNDRV_PCM_HW_PARAM_FORMAT: usize = 1;
+const SNDRV_PCM_HW_PARAM_FIRST_INTERVAL: usize = 8;
+const SNDRV_PCM_HW_PARAM_CHANNELS: usize = 10;
+const SNDRV_PCM_HW_PARAM_RATE: usize = 11;
+const SNDRV_PCM_HW_PARAM_PERIOD_SIZE: usize = 13;
+
+const SNDRV_PCM_ACCESS_RW_INTERLEAVED: u32 = 3;
+const SNDRV_PCM_FORMAT_S16_LE: u32 = 2;
+const SNDRV_PCM_FORMAT_S32_LE: u32 = 10;
+const SNDRV_PCM_FORMAT_FLOAT_LE: u32 = 14;
+
+/// 支持的采样格式，按优先级排列
+const FORMATS: [u32; 3] = [
+    SNDRV_PCM_FORMAT_S16_LE,
+    SNDRV_PCM_FORMAT_S32_LE,
+    SNDRV_PCM_FORMAT_FLOAT_LE,
+];
+
+/// 生成 ioctl 请求码，同内核的 `_IOC` 宏
+const fn ioc(dir: u32, nr: u32, size: usize) -> u32 {
+    dir << 30 | (size as u32) << 16 | (b'A' as u32) << 8 | nr
+}
+
+/// `struct snd_mask`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Mask {
+    bits: [u32; 8],
+}
+
+/// `struct snd_interval`，`flags` 为内核中的位域
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Interval {
+    min: u32,
+    max: u32,
+    flags: u32,
+}
+
+/// `struct snd_pcm_hw_params`
+#[repr(C)]
+struct HwParams {
+    flags: u32,
+    masks: [Mask; 3],
+    mres: [Mask; 5],
+    intervals: [Interval; 12],
+    ires: [Interval; 9],
+    rmask: u32,
+    cmask: u32,
+    info: u32,
+    msbits: u32,
+    rate_num: u32,
+    rate_den: u32,
+    fifo_size: libc::c_ulong,
+    reserved: [u8; 64],
+}
+
+impl HwParams {
+    /// 不限制任何参数
+    fn any() -> Self {
+        let mut params: HwParams = unsafe { std::mem::zeroed() };
+        params.masks = [Mask { bits: [!0; 8] }; 3];
+        params.intervals = [Interval {
+            min: 0,
+            max: u32::MAX,
+            flags: 0,
+        }; 12];
+        params.rmask = !0;
+        params
+    }
+
+    /// 把掩码参数限制为指定的值
+    fn set_mask(&mut self, param: usize, values: &[u32]) {
+        let mask = &mut self.masks[param];
+        mask.bits = [0; 8];
+        for value in values {
+            mask.bits[*value as usize / 32] |= 1 << (value % 32);
+        }
+    }
+
+    /// 掩码参数选定的值
+    fn mask(&self, param: usize) -> Option<u32> {
+        let mask = &self.masks[param];
+        (0..256).find(|value| mask.bits[*value as usize / 32] & 1 << (value % 32) != 0)
+    }
+
+    fn interval_mut(&mut self, param: usize) -> &mut Interval {
+        &mut self.intervals[param - SNDRV_PCM_HW_PARAM_FIRST_INTERVAL]
+    }
+
+    /// 把区间参数限制为指定的值
+    fn set_interval(&mut self, param: usize, value: u32) {
+        let interval = self.interval_mut(param);
+        interval.min = value;
+        interval.max = value;
+    }
+
+    /// 区间参数选定的值
+    fn interval(&self, param: usize) -> u32 {
+        self.intervals[param - SNDRV_PCM_HW_PARAM_FIRST_INTERVAL].min
+    }
+}
+
+/// `struct snd_xferi`
+#[repr(C)]
+struct Xferi {
+    result: libc::c_long,
+    buf: *mut libc::c_void,
+    frames: libc::c_ulong,
+}
+
+const SNDRV_PCM_IOCTL_HW_PARAMS: u32 = ioc(3, 0x11, std::mem::size_of::<HwParams>());
+const SNDRV_PCM_IOCTL_PREPARE: u32 = ioc(0, 0x40, 0);
+const SNDRV_PCM_IOCTL_START: u32 = ioc(0, 0x42, 0);
+const SNDRV_PCM_IOCTL_READI_FRAMES: u32 = ioc(2, 0x51, std::mem::size_of::<Xferi>());
+
+/// 执行没有参数的 ioctl
+fn ioctl_none(file: &File, request: u32) -> std::io::Result<()> {
+    if unsafe { libc::ioctl(file.as_raw_fd(), request as _) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// PCM 设备
+struct PcmDevice {
+    card: u32,
+    device: u32,
+    name: String,
+}
+
+impl PcmDevice {
+    fn id(&self) -> String {
+        format!("hw:{},{}", self.card, self.device)
+    }
+
+    fn path(&self) -> PathBuf {
+        PathBuf::from(format!("/dev/snd/pcmC{}D{}c", self.card, self.device))
+    }
+}
+
+/// 读取支持采集的 PCM 设备
+///
+/// `/proc/asound/pcm` 每行格式为 `00-00: ID : 名称 : playback 1 : capture 1`
+fn capture_devices() -> Result<Vec<PcmDevice>> {
+    let list = std::fs::read_to_string(PCM_LIST).map_err(|e| anyhow!("ALSA unavailable: {}", e))?;
+
+    Ok(list
+        .lines()
+        .filter(|line| line.contains(": capture"))
+        .filter_map(|line| {
+            let mut fields = line.split(':').map(str::trim);
+            let (card, device) = fields.next()?.split_once('-')?;
+            let name = fields.nth(1).unwrap_or_default();
+            Some(PcmDevice {
+                card: card.parse().ok()?,
+                device: device.parse().ok()?,
+                name: name.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// 列出 ALSA 输入设备，第一项为默认设备
+pub fn list_devices() -> Result<Vec<AudioDevice>> {
+    let mut devices = vec![AudioDevice {
+        id: DEFAULT_DEVICE.to_string(),
+        name: t("Default").unwrap_or(DEFAULT_DEVICE.to_string()),
+    }];
+    devices.extend(capture_devices()?.into_iter().map(|device| AudioDevice {
+        id: device.id(),
+        name: format!("{} ({})", device.name, device.id()),
+    }));
+
+    Ok(devices)
+}
+
+/// ALSA 采集流
+pub struct AlsaCapture {
+    file: File,
+    format: u32,
+    sample_rate: u32,
+    channels: u16,
+    period: u32,
+    buffer: Vec<u8>,
+}
+
+impl AlsaCapture {
+    /// 打开采集流
+    ///
+    /// # 参数
+    ///
+    /// * `device` - 设备 ID，如 `hw:0,0`，`default` 为第一个输入设备
+    pub fn open(device: &str) -> Result<Self> {
+        let devices = capture_devices()?;
+        let pcm = if device == DEFAULT_DEVICE {
+            devices.first()
+        } else {
+            devices.iter().find(|pcm| pcm.id() == device)
+        }
+        .ok_or(anyhow!("ALSA device not found: {}", device))?;
+
+        let file = OpenOptions::new().read(true).write(true).open(pcm.path())?;
+
+        // 优先使用 48 kHz 立体声，设备不支持时由驱动选择
+        let mut params = None;
+        for (rate, channels) in [
+            (Some(PREFERRED_RATE), Some(PREFERRED_CHANNELS)),
+            (Some(PREFERRED_RATE), None),
+            (None, None),
+        ] {
+            let mut candidate = HwParams::any();
+            candidate.set_mask(
+                SNDRV_PCM_HW_PARAM_ACCESS,
+                &[SNDRV_PCM_ACCESS_RW_INTERLEAVED],
+            );
+            candidate.set_mask(SNDRV_PCM_HW_PARAM_FORMAT, &FORMATS);
+            if let Some(rate) = rate {
+                candidate.set_interval(SNDRV_PCM_HW_PARAM_RATE, rate);
+                candidate.set_interval(SNDRV_PCM_HW_PARAM_PERIOD_SIZE, PERIOD_FRAMES);
+            }
+            if let Some(channels) = channels {
+                candidate.set_interval(SNDRV_PCM_HW_PARAM_CHANNELS, channels);
+            }
+
+            let result = unsafe {
+                libc::ioctl(
+                    file.as_raw_fd(),
+                    SNDRV_PCM_IOCTL_HW_PARAMS as _,
+                    &mut candidate,
+                )
+            };
+            if result == 0 {
+                params = Some(candidate);
+                break;
+            }
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() != Some(libc::EINVAL) {
+                return Err(error.into());
+            }
+        }
+        let params = params.ok_or(anyhow!("unsupported ALSA device: {}", device))?;
+
+        let format = params
+            .mask(SNDRV_PCM_HW_PARAM_FORMAT)
+            .ok_or(anyhow!("ALSA device has no sample format"))?;
+        let channels = params.interval(SNDRV_PCM_HW_PARAM_CHANNELS);
+        let period = params.interval(SNDRV_PCM_HW_PARAM_PERIOD_SIZE).max(1);
+
+        let mut capture = Self {
+            file,
+            format,
+            sample_rate: params.interval(SNDRV_PCM_HW_PARAM_RATE),
+            channels: channels as u16,
+            period,
+            buffer: vec![0; period as usize * channels as usize * sample_size(format)],
+        };
+        capture.start()?;
+
+        Ok(capture)
+    }
+
+    /// 准备并开始采集
+    fn start(&mut self) -> Result<()> {
+        ioctl_none(&self.file, SNDRV_PCM_IOCTL_PREPARE)?;
+        ioctl_none(&self.file, SNDRV_PCM_IOCTL_START)?;
+
+        Ok(())
+    }
+
+    /// 转换为 32 位浮点数
+    fn to_f32(&self, frames: usize) -> Vec<f32> {
+        let bytes = &self.buffer[..frames * self.channels as usize * sample_size(self.format)];
+        match self.format {
+            SNDRV_PCM_FORMAT_S16_LE => bytes
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.)
+                .collect(),
+            SNDRV_PCM_FORMAT_S32_LE => bytes
+                .chunks_exact(4)
+                .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.)
+                .collect(),
+            _ => bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        }
+    }
+}
+
+/// 每个采样的字节数
+fn sample_size(format: u32) -> usize {
+    match format {
+        SNDRV_PCM_FORMAT_S16_LE => 2,
+        _ => 4,
+    }
+}
+
+impl CaptureStream for AlsaCapture {
+    fn read(&mut self) -> Result<Captured> {
+        let mut xferi = Xferi {
+            result: 0,
+            buf: self.buffer.as_mut_ptr() as *mut libc::c_void,
+            frames: self.period as libc::c_ulong,
+        };
+
+        let result = unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                SNDRV_PCM_IOCTL_READI_FRAMES as _,
+                &mut xferi,
+            )
+        };
+        if result < 0 {
+            let error = std::io::Error::last_os_error();
+            return match error.raw_os_error() {
+                // 缓冲区溢出，重新开始
+                Some(libc::EPIPE) => {
+                    self.start()?;
+                    Ok(Captured::Idle)
+                }
+                Some(libc::EINTR) | Some(libc::EAGAIN) => Ok(Captured::Idle),
+                // 设备拔出时返回 ENODEV，由采集线程重新打开
+                _ => Err(error.into()),
+            };
+        }
+
+        Ok(Captured::Audio(AudioFrame {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            timestamp: 0,
+            data: self.to_f32(xferi.result.max(0) as usize),
+        }))
+    }
+}
@@ -0,0 +1,335 @@
+/// 音频采集来源模块
+///
+/// 采集线程从 PulseAudio（包括 PipeWire 的 PulseAudio 兼容服务）或 ALSA 设备读取音频，
+/// 按采样数生成连续的合成时钟时间戳后推送给混音器。设备断开后每秒重试，重新插入后自动恢复
+pub mod alsa;
+pub mod pulse;
+pub mod test_device;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    graphics::texture::Texture,
+    media::{
+        audio::AudioFrame,
+        clock::CaptureTimestamps,
+        mixer::{clear_audio, push_audio},
+    },
+    sources::{audio_capture::test_device::TestCapture, SourceContext, VideoSource},
+    Result,
+};
+
+/// 默认设备 ID，跟随系统默认设备变化
+pub const DEFAULT_DEVICE: &str = "default";
+
+/// 正弦波测试设备 ID，输出 440 Hz 的正弦波，不需要音频服务
+pub const SINE_TEST_DEVICE: &str = "test:sine";
+
+/// 静音测试设备 ID，输出静音，不需要音频服务
+pub const NULL_TEST_DEVICE: &str = "test:null";
+
+/// 设备打开失败后重试的间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 采集的设备类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureKind {
+    /// PulseAudio 输入设备
+    PulseInput,
+    /// PulseAudio 输出设备的监听
+    PulseOutput,
+    /// ALSA 输入设备
+    AlsaInput,
+}
+
+/// 音频设备
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AudioDevice {
+    pub id: String,
+    pub name: String,
+}
+
+/// 采集流读取的结果
+pub enum Captured {
+    /// 一块音频，时间戳由采集线程生成
+    Audio(AudioFrame),
+    /// 暂时没有数据
+    Idle,
+    /// 设备被移除或默认设备变化，需要重新打开
+    DeviceChanged,
+}
+
+/// 打开的采集流
+pub trait CaptureStream: Send {
+    /// 读取音频，最多阻塞约 100 毫秒，以便及时响应停止
+    fn read(&mut self) -> Result<Captured>;
+}
+
+/// 列出可以采集的设备，不包括测试设备
+///
+/// # 参数
+///
+/// * `kind` - 设备类型
+pub fn list_devices(kind: CaptureKind) -> Result<Vec<AudioDevice>> {
+    match kind {
+        CaptureKind::PulseInput => pulse::list_devices(false),
+        CaptureKind::PulseOutput => pulse::list_devices(true),
+        CaptureKind::AlsaInput => alsa::list_devices(),
+    }
+}
+
+/// 打开采集流
+///
+/// PulseAudio 不可用时，默认输入设备回退到 ALSA 的默认设备
+fn open_stream(kind: CaptureKind, device: &str) -> Result<Box<dyn CaptureStream>> {
+    match device {
+        SINE_TEST_DEVICE => return Ok(Box::new(TestCapture::new(true))),
+        NULL_TEST_DEVICE => return Ok(Box::new(TestCapture::new(false))),
+        _ => {}
+    }
+
+    match kind {
+        CaptureKind::PulseInput => match pulse::PulseCapture::open(device, false) {
+            Ok(stream) => Ok(Box::new(stream)),
+            Err(e) if device == DEFAULT_DEVICE && pulse::server_unavailable(&e) => {
+                warn!("PulseAudio unavailable, falling back to ALSA: {}", e);
+                Ok(Box::new(alsa::AlsaCapture::open(device)?))
+            }
+            Err(e) => Err(e),
+        },
+        CaptureKind::PulseOutput => Ok(Box::new(pulse::PulseCapture::open(device, true)?)),
+        CaptureKind::AlsaInput => Ok(Box::new(alsa::AlsaCapture::open(device)?)),
+    }
+}
+
+/// 音频采集设置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+struct AudioCaptureSettings {
+    /// 设备 ID，保存在场景集合中
+    device_id: String,
+}
+
+impl Default for AudioCaptureSettings {
+    fn default() -> Self {
+        Self {
+            device_id: DEFAULT_DEVICE.to_string(),
+        }
+    }
+}
+
+/// 运行中的采集线程
+struct CaptureThread {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+/// 音频采集来源，没有画面
+pub struct AudioCaptureSource {
+    uuid: String,
+    kind: CaptureKind,
+    settings: Option<AudioCaptureSettings>,
+    capture: Option<CaptureThread>,
+}
+
+impl AudioCaptureSource {
+    /// 创建音频采集来源
+    ///
+    /// # 参数
+    ///
+    /// * `uuid` - 来源 UUID，用于向混音器推送音频
+    /// * `kind` - 设备类型
+    pub fn new(uuid: &str, kind: CaptureKind) -> Self {
+        Self {
+            uuid: uuid.to_string(),
+            kind,
+            settings: None,
+            capture: None,
+        }
+    }
+
+    /// 启动采集线程
+    fn start(&mut self, device: &str) -> Result<()> {
+        self.stop();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let uuid = self.uuid.clone();
+        let kind = self.kind;
+        let device = device.to_string();
+        let thread = thread::Builder::new()
+            .name("audio-capture".to_string())
+            .spawn({
+                let stop = stop.clone();
+                move || run_capture(&uuid, kind, &device, &stop)
+            })?;
+        self.capture = Some(CaptureThread { stop, thread });
+
+        Ok(())
+    }
+
+    /// 停止采集线程
+    fn stop(&mut self) {
+        if let Some(capture) = self.capture.take() {
+            capture.stop.store(true, Ordering::Relaxed);
+            let _ = capture.thread.join();
+        }
+        clear_audio(&self.uuid);
+    }
+}
+
+impl VideoSource for AudioCaptureSource {
+    fn update(&mut self, settings: &Value) -> Result<()> {
+        let settings: AudioCaptureSettings = serde_json::from_value(settings.clone())?;
+        if settings.device_id.is_empty() {
+            return Err(anyhow!("audio device not set"));
+        }
+        if self.settings.as_ref() != Some(&settings) {
+            self.start(&settings.device_id)?;
+        }
+        self.settings = Some(settings);
+
+        Ok(())
+    }
+
+    fn tick(&mut self, _context: &mut SourceContext, _delta: Duration) -> Result<()> {
+        Ok(())
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (0, 0)
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        None
+    }
+}
+
+impl Drop for AudioCaptureSource {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 采集线程，设备断开后重新打开
+fn run_capture(uuid: &str, kind: CaptureKind, device: &str, stop: &AtomicBool) {
+    let mut last_error = String::new();
+
+    while !stop.load(Ordering::Relaxed) {
+        let mut stream = match open_stream(kind, device) {
+            Ok(stream) => {
+                info!("audio capture started: {:?} {}", kind, device);
+                last_error.clear();
+                stream
+            }
+            Err(e) => {
+                // 设备拔出期间只记录一次
+                if e.to_string() != last_error {
+                    warn!("failed to open audio device {}: {}", device, e);
+                    last_error = e.to_string();
+                }
+                wait_retry(stop);
+                continue;
+            }
+        };
+
+        let mut timestamps = CaptureTimestamps::default();
+        let reopen_now = loop {
+            if stop.load(Ordering::Relaxed) {
+                break true;
+            }
+            match stream.read() {
+                Ok(Captured::Audio(mut frame)) => {
+                    frame.timestamp = timestamps.stamp(frame.frames(), frame.sample_rate);
+                    push_audio(uuid, &frame);
+                }
+                Ok(Captured::Idle) => {}
+                Ok(Captured::DeviceChanged) => {
+                    info!("audio device changed: {}", device);
+                    break true;
+                }
+                Err(e) => {
+                    warn!("audio capture failed: {}", e);
+                    break false;
+                }
+            }
+        };
+
+        drop(stream);
+        clear_audio(uuid);
+        if !reopen_now {
+            wait_retry(stop);
+        }
+    }
+}
+
+/// 等待重试，期间响应停止
+fn wait_retry(stop: &AtomicBool) {
+    let until = Instant::now() + RETRY_INTERVAL;
+    while !stop.load(Ordering::Relaxed) && Instant::now() < until {
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::media::mixer::connect_audio;
+
+    /// 等待混音输出的峰值满足条件
+    fn wait_peak(
+        receiver: &std::sync::mpsc::Receiver<Arc<AudioFrame>>,
+        accept: impl Fn(f32) -> bool,
+    ) -> f32 {
+        let start = Instant::now();
+        let mut peak = f32::NAN;
+        while start.elapsed() < Duration::from_secs(5) {
+            let Ok(frame) = receiver.recv_timeout(Duration::from_millis(500)) else {
+                continue;
+            };
+            peak = frame
+                .data
+                .iter()
+                .fold(0f32, |peak, sample| peak.max(sample.abs()));
+            if accept(peak) {
+                break;
+            }
+        }
+
+        peak
+    }
+
+    #[test]
+    fn test_devices_reach_the_mixer() {
+        let receiver = connect_audio().unwrap();
+        let mut source = AudioCaptureSource::new("audio-capture-test", CaptureKind::PulseInput);
+
+        source
+            .update(&json!({ "device_id": SINE_TEST_DEVICE }))
+            .unwrap();
+        let peak = wait_peak(&receiver, |peak| peak > 0.45);
+        assert!((0.45..=0.51).contains(&peak), "sine peak {}", peak);
+
+        // 切换设备时清空旧的缓冲
+        source
+            .update(&json!({ "device_id": NULL_TEST_DEVICE }))
+            .unwrap();
+        let peak = wait_peak(&receiver, |peak| peak == 0.);
+        assert_eq!(peak, 0.);
+
+        assert!(source.update(&json!({ "device_id": "" })).is_err());
+    }
+}
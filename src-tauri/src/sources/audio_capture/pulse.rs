@@ -0,0 +1,413 @@
+/// PulseAudio 采集
+///
+/// 直接使用 PulseAudio 原生协议，不依赖 libpulse，同样适用于 PipeWire 的 PulseAudio 兼容服务。
+/// 订阅设备和服务器事件，采集的设备被移除或默认设备变化时通知采集线程重新打开
+use std::{
+    collections::VecDeque,
+    ffi::{CStr, CString},
+    fmt,
+    io::{BufReader, Cursor, Read},
+    os::{fd::AsRawFd, unix::net::UnixStream},
+};
+
+use anyhow::anyhow;
+use pulseaudio::protocol::{
+    self, Command, CommandReply, SampleFormat, SubscriptionEventFacility, SubscriptionEventType,
+    SubscriptionMask,
+};
+
+use crate::{
+    media::audio::AudioFrame,
+    sources::audio_capture::{AudioDevice, CaptureStream, Captured, DEFAULT_DEVICE},
+    utils::locale::t,
+    Result,
+};
+
+/// 客户端名称，显示在系统音量控制中
+const CLIENT_NAME: &CStr = c"OBS Studio";
+
+/// 控制消息的通道号
+const COMMAND_CHANNEL: u32 = u32::MAX;
+
+/// 最多采集的声道数
+const MAX_CHANNELS: u8 = 8;
+
+/// 等待数据的超时（毫秒）
+const POLL_TIMEOUT_MS: i32 = 100;
+
+/// PulseAudio 服务不可用，如没有运行或找不到套接字
+#[derive(Debug)]
+pub struct PulseUnavailable(String);
+
+impl fmt::Display for PulseUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PulseAudio unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for PulseUnavailable {}
+
+/// 错误是否因为 PulseAudio 服务不可用
+pub fn server_unavailable(error: &anyhow::Error) -> bool {
+    error.is::<PulseUnavailable>()
+}
+
+/// 读取到的消息
+struct Message {
+    channel: u32,
+    /// 消息头和内容
+    raw: Vec<u8>,
+}
+
+impl Message {
+    fn payload(&self) -> &[u8] {
+        &self.raw[protocol::DESCRIPTOR_SIZE..]
+    }
+}
+
+/// PulseAudio 连接
+struct Connection {
+    sock: BufReader<UnixStream>,
+    version: u16,
+    seq: u32,
+    /// 等待应答期间收到的其他消息
+    pending: VecDeque<Message>,
+}
+
+impl Connection {
+    /// 连接 PulseAudio 服务并完成认证
+    fn connect() -> Result<Self> {
+        let path = pulseaudio::socket_path_from_env()
+            .ok_or(PulseUnavailable("socket not found".to_string()))?;
+        let sock = UnixStream::connect(&path).map_err(|e| PulseUnavailable(e.to_string()))?;
+        let cookie = pulseaudio::cookie_path_from_env()
+            .and_then(|path| std::fs::read(path).ok())
+            .unwrap_or_default();
+
+        let mut connection = Self {
+            sock: BufReader::new(sock),
+            version: protocol::MAX_VERSION,
+            seq: 0,
+            pending: VecDeque::new(),
+        };
+        let auth: protocol::AuthReply =
+            connection.request(Command::Auth(protocol::AuthParams {
+                version: protocol::MAX_VERSION,
+                supports_shm: false,
+                supports_memfd: false,
+                cookie,
+            }))?;
+        connection.version = protocol::MAX_VERSION.min(auth.version);
+
+        let mut props = protocol::Props::new();
+        props.set(protocol::Prop::ApplicationName, CLIENT_NAME);
+        let _: protocol::SetClientNameReply = connection.request(Command::SetClientName(props))?;
+
+        Ok(connection)
+    }
+
+    /// 读取一条消息
+    fn read_message(&mut self) -> Result<Message> {
+        let mut raw = vec![0; protocol::DESCRIPTOR_SIZE];
+        self.sock.read_exact(&mut raw)?;
+        let descriptor = protocol::read_descriptor(&mut raw.as_slice())?;
+        raw.resize(protocol::DESCRIPTOR_SIZE + descriptor.length as usize, 0);
+        self.sock
+            .read_exact(&mut raw[protocol::DESCRIPTOR_SIZE..])?;
+
+        Ok(Message {
+            channel: descriptor.channel,
+            raw,
+        })
+    }
+
+    /// 发送命令，等待对应的应答
+    fn send(&mut self, command: Command) -> Result<Message> {
+        self.seq += 1;
+        let seq = self.seq;
+        protocol::write_command_message(self.sock.get_mut(), seq, &command, self.version)?;
+
+        loop {
+            let message = self.read_message()?;
+            if message.channel == COMMAND_CHANNEL {
+                let (reply_seq, command) =
+                    Command::read_tag_prefixed(&mut Cursor::new(message.payload()), self.version)?;
+                if reply_seq == seq {
+                    return match command {
+                        Command::Error(error) => Err(anyhow!("PulseAudio error: {:?}", error)),
+                        _ => Ok(message),
+                    };
+                }
+            }
+            self.pending.push_back(message);
+        }
+    }
+
+    /// 发送命令并解析应答
+    fn request<R: CommandReply>(&mut self, command: Command) -> Result<R> {
+        let message = self.send(command)?;
+        let (_, reply) =
+            protocol::read_reply_message::<R>(&mut Cursor::new(message.raw), self.version)?;
+        Ok(reply)
+    }
+
+    /// 下一条消息，等待超时时返回 `None`
+    fn next_message(&mut self) -> Result<Option<Message>> {
+        if let Some(message) = self.pending.pop_front() {
+            return Ok(Some(message));
+        }
+
+        if self.sock.buffer().is_empty() {
+            let mut fd = libc::pollfd {
+                fd: self.sock.get_ref().as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let ready = unsafe { libc::poll(&mut fd, 1, POLL_TIMEOUT_MS) };
+            if ready < 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            if ready == 0 {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(self.read_message()?))
+    }
+
+    /// 服务器信息，包含默认设备名称
+    fn server_info(&mut self) -> Result<protocol::ServerInfo> {
+        self.request(Command::GetServerInfo)
+    }
+}
+
+/// 设备名称为 `default` 时使用 PulseAudio 的默认设备
+fn device_name(device: &str, default: &CStr) -> Result<CString> {
+    if device == DEFAULT_DEVICE {
+        Ok(default.to_owned())
+    } else {
+        Ok(CString::new(device)?)
+    }
+}
+
+fn to_string(value: &CStr) -> String {
+    value.to_string_lossy().into_owned()
+}
+
+/// 列出 PulseAudio 设备，第一项为默认设备
+///
+/// # 参数
+///
+/// * `monitor` - 列出输出设备还是输入设备
+pub fn list_devices(monitor: bool) -> Result<Vec<AudioDevice>> {
+    let mut connection = Connection::connect()?;
+
+    let mut devices = vec![AudioDevice {
+        id: DEFAULT_DEVICE.to_string(),
+        name: t("Default").unwrap_or(DEFAULT_DEVICE.to_string()),
+    }];
+    if monitor {
+        let sinks: protocol::SinkInfoList = connection.request(Command::GetSinkInfoList)?;
+        devices.extend(sinks.into_iter().map(|sink| AudioDevice {
+            id: to_string(&sink.name),
+            name: to_string(sink.description.as_deref().unwrap_or(&sink.name)),
+        }));
+    } else {
+        // 输出设备的监听源在输出采集中列出
+        let sources: protocol::SourceInfoList = connection.request(Command::GetSourceInfoList)?;
+        devices.extend(
+            sources
+                .into_iter()
+                .filter(|source| source.monitor_of_sink_index.is_none())
+                .map(|source| AudioDevice {
+                    id: to_string(&source.name),
+                    name: to_string(source.description.as_deref().unwrap_or(&source.name)),
+                }),
+        );
+    }
+
+    Ok(devices)
+}
+
+/// PulseAudio 采集流
+pub struct PulseCapture {
+    connection: Connection,
+    /// 设置中的设备 ID
+    device: String,
+    /// 采集输出设备的监听
+    monitor: bool,
+    /// 采集的输入设备或输出设备名称
+    name: CString,
+    /// 实际采集的源序号
+    source_index: u32,
+    /// 录音流的数据通道
+    channel: u32,
+    sample_rate: u32,
+    channels: u16,
+}
+
+impl PulseCapture {
+    /// 打开采集流
+    ///
+    /// # 参数
+    ///
+    /// * `device` - 设备 ID，`default` 为默认设备
+    /// * `monitor` - 采集输出设备的监听还是输入设备
+    pub fn open(device: &str, monitor: bool) -> Result<Self> {
+        let mut connection = Connection::connect()?;
+
+        // 先订阅，避免错过打开期间的设备变化
+        connection.send(Command::Subscribe(
+            SubscriptionMask::SINK | SubscriptionMask::SOURCE | SubscriptionMask::SERVER,
+        ))?;
+
+        let (name, source) = if monitor {
+            let sink: protocol::SinkInfo =
+                connection.request(Command::GetSinkInfo(protocol::GetSinkInfo {
+                    index: None,
+                    name: Some(device_name(device, protocol::DEFAULT_SINK)?),
+                }))?;
+            let index = sink
+                .monitor_source_index
+                .ok_or(anyhow!("output device has no monitor"))?;
+            let source: protocol::SourceInfo =
+                connection.request(Command::GetSourceInfo(protocol::GetSourceInfo {
+                    index: Some(index),
+                    name: None,
+                }))?;
+            (sink.name, source)
+        } else {
+            let source: protocol::SourceInfo =
+                connection.request(Command::GetSourceInfo(protocol::GetSourceInfo {
+                    index: None,
+                    name: Some(device_name(device, protocol::DEFAULT_SOURCE)?),
+                }))?;
+            (source.name.clone(), source)
+        };
+
+        let channels = source.channel_map.num_channels().min(MAX_CHANNELS);
+        let channel_map = if channels == source.channel_map.num_channels() {
+            source.channel_map
+        } else {
+            protocol::ChannelMap::stereo()
+        };
+        let channels = channel_map.num_channels();
+        let sample_rate = source.sample_spec.sample_rate;
+        // 每 10 毫秒发送一次数据
+        let fragment_size = sample_rate / 100 * channels as u32 * 4;
+
+        let stream: protocol::CreateRecordStreamReply =
+            connection.request(Command::CreateRecordStream(protocol::RecordStreamParams {
+                sample_spec: protocol::SampleSpec {
+                    format: SampleFormat::Float32Le,
+                    channels,
+                    sample_rate,
+                },
+                channel_map,
+                source_index: Some(source.index),
+                buffer_attr: protocol::stream::BufferAttr {
+                    fragment_size,
+                    ..Default::default()
+                },
+                flags: protocol::stream::StreamFlags {
+                    adjust_latency: true,
+                    // 指定的设备被移除时结束录音流，不移动到其他设备
+                    no_move: device != DEFAULT_DEVICE,
+                    ..Default::default()
+                },
+                props: {
+                    let mut props = protocol::Props::new();
+                    props.set(protocol::Prop::MediaName, CLIENT_NAME);
+                    props
+                },
+                ..Default::default()
+            }))?;
+        if stream.sample_spec.format != SampleFormat::Float32Le {
+            return Err(anyhow!(
+                "unsupported PulseAudio sample format: {:?}",
+                stream.sample_spec.format
+            ));
+        }
+
+        Ok(Self {
+            connection,
+            device: device.to_string(),
+            monitor,
+            name,
+            source_index: source.index,
+            channel: stream.channel,
+            sample_rate: stream.sample_spec.sample_rate,
+            channels: stream.sample_spec.channels as u16,
+        })
+    }
+
+    /// 默认设备是否已变为其他设备
+    fn default_changed(&mut self) -> Result<bool> {
+        if self.device != DEFAULT_DEVICE {
+            return Ok(false);
+        }
+
+        let info = self.connection.server_info()?;
+        let default = if self.monitor {
+            info.default_sink_name
+        } else {
+            info.default_source_name
+        };
+        Ok(default.is_some_and(|default| default != self.name))
+    }
+
+    /// 处理控制消息
+    fn handle_command(&mut self, message: &Message) -> Result<Captured> {
+        let (_, command) = Command::read_tag_prefixed(
+            &mut Cursor::new(message.payload()),
+            self.connection.version,
+        )?;
+
+        match command {
+            Command::SubscribeEvent(event) => {
+                let removed = event.event_type == SubscriptionEventType::Removed
+                    && event.event_facility == SubscriptionEventFacility::Source
+                    && event.index == Some(self.source_index);
+                let server_changed = event.event_facility == SubscriptionEventFacility::Server;
+                if removed || (server_changed && self.default_changed()?) {
+                    return Ok(Captured::DeviceChanged);
+                }
+            }
+            Command::RecordStreamKilled(_) => return Ok(Captured::DeviceChanged),
+            Command::RecordStreamMoved(moved) => self.source_index = moved.device_index,
+            _ => {}
+        }
+
+        Ok(Captured::Idle)
+    }
+}
+
+impl CaptureStream for PulseCapture {
+    fn read(&mut self) -> Result<Captured> {
+        let Some(message) = self.connection.next_message()? else {
+            return Ok(Captured::Idle);
+        };
+
+        if message.channel == COMMAND_CHANNEL {
+            return self.handle_command(&message);
+        }
+        if message.channel != self.channel {
+            return Ok(Captured::Idle);
+        }
+
+        let frame_size = self.channels as usize * 4;
+        let payload = message.payload();
+        let payload = &payload[..payload.len() / frame_size * frame_size];
+        let data = payload
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+
+        Ok(Captured::Audio(AudioFrame {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            timestamp: 0,
+            data,
+        }))
+    }
+}
@@ -0,0 +1,138 @@
+/// 测试用的虚拟音频设备
+///
+/// 按实际时间生成 48 kHz 立体声的正弦波或静音，不依赖音频服务和硬件
+use std::{
+    f32::consts::TAU,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    media::audio::AudioFrame,
+    sources::audio_capture::{CaptureStream, Captured},
+    Result,
+};
+
+const SAMPLE_RATE: u32 = 48000;
+const CHANNELS: u16 = 2;
+
+/// 每次读取的采样数（10 毫秒）
+const CHUNK_FRAMES: u64 = 480;
+
+/// 正弦波频率
+const FREQUENCY: f32 = 440.;
+
+/// 正弦波振幅
+const AMPLITUDE: f32 = 0.5;
+
+/// 测试设备的采集流
+pub struct TestCapture {
+    /// 输出正弦波还是静音
+    sine: bool,
+    start: Instant,
+    /// 已生成的采样数
+    generated: u64,
+}
+
+impl TestCapture {
+    /// 创建测试设备
+    ///
+    /// # 参数
+    ///
+    /// * `sine` - 输出正弦波还是静音
+    pub fn new(sine: bool) -> Self {
+        Self {
+            sine,
+            start: Instant::now(),
+            generated: 0,
+        }
+    }
+}
+
+impl CaptureStream for TestCapture {
+    fn read(&mut self) -> Result<Captured> {
+        // 等到这一块的最后一个采样“采集”完成
+        let end = self.generated + CHUNK_FRAMES;
+        let due = self.start + Duration::from_nanos(end * 1_000_000_000 / SAMPLE_RATE as u64);
+        let now = Instant::now();
+        if due > now {
+            thread::sleep(due - now);
+        }
+
+        let mut data = Vec::with_capacity(CHUNK_FRAMES as usize * CHANNELS as usize);
+        for frame in self.generated..end {
+            let sample = if self.sine {
+                let phase = (frame % SAMPLE_RATE as u64) as f32 / SAMPLE_RATE as f32;
+                (phase * FREQUENCY * TAU).sin() * AMPLITUDE
+            } else {
+                0.
+            };
+            data.extend(std::iter::repeat_n(sample, CHANNELS as usize));
+        }
+        self.generated = end;
+
+        Ok(Captured::Audio(AudioFrame {
+            sample_rate: SAMPLE_RATE,
+            channels: CHANNELS,
+            timestamp: 0,
+            data,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(capture: &mut TestCapture) -> AudioFrame {
+        match capture.read().unwrap() {
+            Captured::Audio(frame) => frame,
+            _ => panic!("test device returned no audio"),
+        }
+    }
+
+    #[test]
+    fn sine_is_continuous_across_chunks() {
+        let mut capture = TestCapture::new(true);
+        let mut samples = vec![];
+        for _ in 0..10 {
+            let frame = read(&mut capture);
+            assert_eq!((frame.sample_rate, frame.channels), (SAMPLE_RATE, CHANNELS));
+            assert_eq!(frame.frames(), CHUNK_FRAMES as usize);
+            for pair in frame.data.chunks_exact(2) {
+                assert_eq!(pair[0], pair[1]);
+                samples.push(pair[0]);
+            }
+        }
+
+        let peak = samples
+            .iter()
+            .fold(0f32, |peak, sample| peak.max(sample.abs()));
+        assert!((peak - AMPLITUDE).abs() < 0.01, "peak {}", peak);
+
+        // 相邻采样的差不超过正弦波的最大斜率，块之间没有跳变
+        let max_step = AMPLITUDE * TAU * FREQUENCY / SAMPLE_RATE as f32 * 1.01;
+        for pair in samples.windows(2) {
+            assert!((pair[1] - pair[0]).abs() <= max_step);
+        }
+
+        // 100 毫秒内 440 Hz 正弦波有 88 次过零
+        let crossings = samples
+            .windows(2)
+            .filter(|pair| (pair[0] < 0.) != (pair[1] < 0.))
+            .count();
+        assert!((87..=89).contains(&crossings), "{} crossings", crossings);
+    }
+
+    #[test]
+    fn null_device_is_silent_and_paced() {
+        let mut capture = TestCapture::new(false);
+        let start = Instant::now();
+        for _ in 0..5 {
+            assert!(read(&mut capture).data.iter().all(|sample| *sample == 0.));
+        }
+
+        // 按实际时间输出，5 块共 50 毫秒
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+}
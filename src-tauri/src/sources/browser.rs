@@ -0,0 +1,432 @@
+/// 浏览器来源模块
+///
+/// 在离屏网页视图中打开网址或本地 HTML 文件，按设置的帧率截取画面上传到纹理。
+/// 支持注入自定义 CSS、把页面音频转到混音器，以及通过 `window.obsstudio` 向页面提供场景和输出状态
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use image::RgbaImage;
+use log::warn;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    graphics::texture::Texture,
+    media::{
+        audio::AudioFrame,
+        clock::CaptureTimestamps,
+        frame::VideoInfo,
+        mixer::{clear_audio, push_audio},
+    },
+    outputs::{
+        recording::{recording_active, recording_paused},
+        streaming::streaming_active,
+        virtualcam::virtualcam_active,
+    },
+    scene::scenes,
+    sources::{upload_image, SourceContext, VideoSource},
+    ui::browser::{
+        close_browser, drain_browser_audio, file_url, open_browser, push_bridge_state,
+        set_browser_css, snapshot_browser, BridgeScene, BridgeState, BridgeStatus, BrowserOptions,
+    },
+    Result,
+};
+
+/// 重复推送桥接状态的间隔，页面加载完成前推送的状态会丢失
+const BRIDGE_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 默认注入的 CSS，与 OBS 相同，使页面背景透明
+const DEFAULT_CSS: &str =
+    "body { background-color: rgba(0, 0, 0, 0); margin: 0px auto; overflow: hidden; }";
+
+/// 下一个浏览器实例的编号
+static NEXT_BROWSER: AtomicU64 = AtomicU64::new(1);
+
+/// 截图线程共享的状态
+#[derive(Default)]
+struct Shared {
+    /// 最新一帧，来源更新时取走
+    frame: Option<RgbaImage>,
+    /// 截图失败的原因
+    error: Option<String>,
+}
+
+/// 浏览器来源设置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+struct BrowserSettings {
+    url: String,
+    /// 使用本地文件而不是网址
+    is_local_file: bool,
+    local_file: String,
+    width: u32,
+    height: u32,
+    fps: u32,
+    /// 自定义 CSS
+    css: String,
+    /// 把页面音频转到混音器
+    reroute_audio: bool,
+    /// 来源隐藏时关闭页面
+    shutdown: bool,
+    /// 来源显示时刷新页面
+    restart_when_active: bool,
+}
+
+impl Default for BrowserSettings {
+    fn default() -> Self {
+        Self {
+            url: "https://obsproject.com/browser-source".to_string(),
+            is_local_file: false,
+            local_file: String::new(),
+            width: 800,
+            height: 600,
+            fps: 30,
+            css: DEFAULT_CSS.to_string(),
+            reroute_audio: false,
+            shutdown: false,
+            restart_when_active: false,
+        }
+    }
+}
+
+impl BrowserSettings {
+    /// 打开页面使用的选项
+    fn options(&self) -> Result<BrowserOptions> {
+        let url = if self.is_local_file {
+            if self.local_file.is_empty() {
+                return Err(anyhow!("browser local file not set"));
+            }
+            file_url(&self.local_file)?
+        } else {
+            if self.url.is_empty() {
+                return Err(anyhow!("browser url not set"));
+            }
+            self.url.clone()
+        };
+
+        Ok(BrowserOptions {
+            url,
+            width: self.width.max(1),
+            height: self.height.max(1),
+            css: self.css.clone(),
+            reroute_audio: self.reroute_audio,
+        })
+    }
+
+    /// 截图间隔
+    fn frame_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.fps.clamp(1, 60)
+    }
+}
+
+/// 浏览器来源
+pub struct BrowserSource {
+    uuid: String,
+    /// 网页视图的键，每个实例不同，同一来源的旧实例释放时不会关闭新实例的页面
+    key: String,
+    settings: BrowserSettings,
+    options: Option<BrowserOptions>,
+    /// 页面是否已打开
+    open: bool,
+    active: bool,
+    shared: Arc<Mutex<Shared>>,
+    /// 是否有截图请求尚未完成
+    pending: Arc<AtomicBool>,
+    timestamps: Arc<Mutex<CaptureTimestamps>>,
+    /// 距上次截图经过的时间
+    elapsed: Duration,
+    /// 上次推送给页面的状态
+    bridge: Option<BridgeState>,
+    /// 距上次推送状态经过的时间
+    bridge_elapsed: Duration,
+    /// 推送给页面的画布分辨率，与状态一起按间隔重新读取
+    video_info: Option<VideoInfo>,
+    texture: Option<Texture>,
+}
+
+impl BrowserSource {
+    /// 创建浏览器来源
+    ///
+    /// # 参数
+    ///
+    /// * `uuid` - 来源 UUID，用于混音轨道
+    pub fn new(uuid: &str) -> Self {
+        Self {
+            uuid: uuid.to_string(),
+            key: format!("{}-{}", uuid, NEXT_BROWSER.fetch_add(1, Ordering::Relaxed)),
+            settings: BrowserSettings::default(),
+            options: None,
+            open: false,
+            active: false,
+            shared: Arc::new(Mutex::new(Shared::default())),
+            pending: Arc::new(AtomicBool::new(false)),
+            timestamps: Arc::new(Mutex::new(CaptureTimestamps::default())),
+            elapsed: Duration::ZERO,
+            bridge: None,
+            bridge_elapsed: Duration::ZERO,
+            video_info: None,
+            texture: None,
+        }
+    }
+
+    /// 打开或重新打开页面
+    fn open(&mut self) -> Result<()> {
+        let Some(options) = self.options.clone() else {
+            return Ok(());
+        };
+        self.close();
+        open_browser(&self.key, &options)?;
+        self.open = true;
+
+        Ok(())
+    }
+
+    /// 关闭页面
+    fn close(&mut self) {
+        if self.open {
+            close_browser(&self.key);
+            clear_audio(&self.uuid);
+            self.open = false;
+        }
+        self.pending.store(false, Ordering::Relaxed);
+        *self.shared.lock().unwrap() = Shared::default();
+        *self.timestamps.lock().unwrap() = CaptureTimestamps::default();
+        self.bridge = None;
+        self.texture = None;
+    }
+
+    /// 请求截取下一帧
+    fn request_frame(&self) -> Result<()> {
+        if self.pending.swap(true, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let shared = self.shared.clone();
+        let pending = self.pending.clone();
+        let result = snapshot_browser(&self.key, move |result| {
+            let mut shared = shared.lock().unwrap();
+            match result {
+                Ok(frame) => shared.frame = Some(frame),
+                Err(e) => shared.error = Some(e.to_string()),
+            }
+            pending.store(false, Ordering::Relaxed);
+        });
+        if result.is_err() {
+            self.pending.store(false, Ordering::Relaxed);
+        }
+
+        result
+    }
+
+    /// 取出页面转出的音频送入混音器
+    fn drain_audio(&self) -> Result<()> {
+        let uuid = self.uuid.clone();
+        let timestamps = self.timestamps.clone();
+        drain_browser_audio(&self.key, move |audio| {
+            let mut frame = AudioFrame {
+                sample_rate: audio.sample_rate,
+                channels: audio.channels,
+                timestamp: 0,
+                data: audio.data,
+            };
+            frame.timestamp = timestamps
+                .lock()
+                .unwrap()
+                .stamp(frame.frames(), frame.sample_rate);
+            push_audio(&uuid, &frame);
+        })
+    }
+
+    /// 场景和输出状态变化时推送给页面
+    fn update_bridge(&mut self) -> Result<()> {
+        let info = match self.video_info {
+            Some(info) if self.bridge_elapsed < BRIDGE_REFRESH_INTERVAL => info,
+            _ => *self.video_info.insert(VideoInfo::load()),
+        };
+        let name = scenes()
+            .current_scene()
+            .map(|scene| scene.name.clone())
+            .unwrap_or_default();
+        let state = BridgeState {
+            scene: BridgeScene {
+                name,
+                width: info.base_width,
+                height: info.base_height,
+            },
+            status: BridgeStatus {
+                streaming: streaming_active(),
+                recording: recording_active(),
+                recording_paused: recording_paused(),
+                virtualcam: virtualcam_active(),
+            },
+            active: self.active,
+        };
+        if self.bridge.as_ref() != Some(&state) || self.bridge_elapsed >= BRIDGE_REFRESH_INTERVAL {
+            // 页面只在状态变化时触发事件，重复推送是安全的
+            push_bridge_state(&self.key, &state)?;
+            self.bridge = Some(state);
+            self.bridge_elapsed = Duration::ZERO;
+        }
+
+        Ok(())
+    }
+}
+
+impl VideoSource for BrowserSource {
+    fn update(&mut self, settings: &Value) -> Result<()> {
+        let settings: BrowserSettings = serde_json::from_value(settings.clone())?;
+        let options = settings.options()?;
+        let previous = self.options.replace(options.clone());
+        self.settings = settings;
+        if self.settings.shutdown && !self.active {
+            self.close();
+            return Ok(());
+        }
+
+        // 只修改了 CSS 时不需要重新加载页面
+        let same_page = previous.as_ref().is_some_and(|previous| {
+            BrowserOptions {
+                css: options.css.clone(),
+                ..previous.clone()
+            } == options
+        });
+        if !self.open || !same_page {
+            self.open()?;
+        } else if previous.as_ref() != Some(&options) {
+            set_browser_css(&self.key, &options.css)?;
+        }
+
+        Ok(())
+    }
+
+    fn tick(&mut self, context: &mut SourceContext, delta: Duration) -> Result<()> {
+        if !self.open {
+            return Ok(());
+        }
+
+        let interval = self.settings.frame_interval();
+        self.elapsed += delta;
+        self.bridge_elapsed += delta;
+        if self.elapsed >= interval {
+            // 落后太多时不补帧
+            self.elapsed = (self.elapsed - interval).min(interval);
+            self.request_frame()?;
+            if self.settings.reroute_audio {
+                self.drain_audio()?;
+            }
+            if let Err(e) = self.update_bridge() {
+                warn!("failed to update browser bridge: {}", e);
+            }
+        }
+
+        let (frame, error) = {
+            let mut shared = self.shared.lock().unwrap();
+            (shared.frame.take(), shared.error.take())
+        };
+        if let Some(error) = error {
+            return Err(anyhow!(error));
+        }
+
+        match frame {
+            Some(frame) => upload_image(&mut self.texture, context, &frame, "browser"),
+            None => Ok(()),
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        match &self.options {
+            Some(options) => (options.width, options.height),
+            None => (0, 0),
+        }
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        self.texture.as_ref()
+    }
+
+    fn set_active(&mut self, active: bool) {
+        if active == self.active {
+            return;
+        }
+        self.active = active;
+
+        let result = if active && (!self.open || self.settings.restart_when_active) {
+            self.open()
+        } else if !active && self.settings.shutdown {
+            self.close();
+            Ok(())
+        } else {
+            Ok(())
+        };
+        if let Err(e) = result {
+            warn!("failed to open browser source: {}", e);
+        }
+    }
+}
+
+impl Drop for BrowserSource {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn settings(value: Value) -> BrowserSettings {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn options_use_the_url_or_local_file() {
+        let options = settings(json!({ "url": "https://example.com/", "width": 0 }))
+            .options()
+            .unwrap();
+        assert_eq!(options.url, "https://example.com/");
+        assert_eq!((options.width, options.height), (1, 600));
+        assert_eq!(options.css, DEFAULT_CSS);
+
+        // 本地文件转换为 file:// 网址，忽略网址设置
+        let options = settings(json!({
+            "url": "https://example.com/",
+            "is_local_file": true,
+            "local_file": "/tmp/overlay.html",
+        }))
+        .options()
+        .unwrap();
+        assert_eq!(options.url, "file:///tmp/overlay.html");
+    }
+
+    #[test]
+    fn options_require_a_url_or_local_file() {
+        assert!(settings(json!({ "url": "" })).options().is_err());
+        assert!(settings(json!({ "is_local_file": true }))
+            .options()
+            .is_err());
+        // 使用本地文件时不需要网址
+        assert!(settings(json!({
+            "url": "",
+            "is_local_file": true,
+            "local_file": "/tmp/overlay.html",
+        }))
+        .options()
+        .is_ok());
+    }
+
+    #[test]
+    fn instances_of_one_source_use_different_pages() {
+        let first = BrowserSource::new("browser-source");
+        let second = BrowserSource::new("browser-source");
+        assert_ne!(first.key, second.key);
+        assert!(first.key.starts_with("browser-source-"));
+    }
+}
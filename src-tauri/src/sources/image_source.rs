@@ -0,0 +1,209 @@
+/// 图片来源模块
+///
+/// 显示静态图片或 GIF、APNG、WebP 动画，文件被修改后自动重新加载
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    graphics::{cache::AnimatedTexture, texture::Texture},
+    sources::{SourceContext, VideoSource},
+    Result,
+};
+
+/// 检查文件是否被修改的间隔
+const FILE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 图片来源设置
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ImageSettings {
+    /// 图片路径
+    file: String,
+    /// 不显示时释放图片
+    unload: bool,
+    /// 生成多级渐远纹理，适合缩小显示的大图
+    mipmaps: bool,
+}
+
+/// 图片来源
+#[derive(Default)]
+pub struct ImageSource {
+    file: Option<PathBuf>,
+    unload: bool,
+    mipmaps: bool,
+    active: bool,
+    image: Option<AnimatedTexture>,
+    /// 动画播放时间
+    elapsed: Duration,
+    /// 距上次检查文件经过的时间
+    since_check: Duration,
+    /// 设置变化后需要立即重新加载
+    dirty: bool,
+}
+
+impl ImageSource {
+    /// 从缓存获取文件，文件变化时重新加载
+    ///
+    /// 加载失败时保留上一次成功加载的图片，文件恢复后会在下次检查时重新加载
+    fn reload(&mut self, context: &mut SourceContext) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+
+        let image = context
+            .cache
+            .get(context.device, context.queue, file, self.mipmaps)?;
+        // 文件变化后动画从头播放
+        if !self
+            .image
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current.image(), &image))
+        {
+            self.elapsed = Duration::ZERO;
+            self.image = Some(AnimatedTexture::new(context.device, context.queue, image)?);
+        }
+
+        Ok(())
+    }
+}
+
+impl VideoSource for ImageSource {
+    fn update(&mut self, settings: &Value) -> Result<()> {
+        let settings: ImageSettings = serde_json::from_value(settings.clone())?;
+        let file = Some(PathBuf::from(settings.file)).filter(|file| !file.as_os_str().is_empty());
+
+        if file != self.file || settings.mipmaps != self.mipmaps {
+            self.image = None;
+            self.dirty = true;
+        }
+        self.file = file;
+        self.unload = settings.unload;
+        self.mipmaps = settings.mipmaps;
+
+        Ok(())
+    }
+
+    fn tick(&mut self, context: &mut SourceContext, delta: Duration) -> Result<()> {
+        if self.unload && !self.active {
+            self.image = None;
+            self.dirty = true;
+            return Ok(());
+        }
+        if self.file.is_none() {
+            return Ok(());
+        }
+
+        self.elapsed += delta;
+        self.since_check += delta;
+        // 加载失败后也按检查间隔重试
+        let result = if self.dirty || self.since_check >= FILE_CHECK_INTERVAL {
+            self.since_check = Duration::ZERO;
+            self.dirty = false;
+            self.reload(context)
+        } else {
+            Ok(())
+        };
+
+        if let Some(image) = &mut self.image {
+            image.texture_at(context.queue, self.elapsed);
+        }
+
+        result
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.image
+            .as_ref()
+            .map(|image| &image.image().image)
+            .map_or((0, 0), |image| (image.width, image.height))
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        self.image.as_ref().map(AnimatedTexture::texture)
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, File},
+        time::SystemTime,
+    };
+
+    use image::{Rgba, RgbaImage};
+    use serde_json::json;
+
+    use super::*;
+    use crate::graphics::{cache::TextureCache, canvas::create_headless_device};
+
+    /// 写入图片并设置修改时间，保证每次写入都能被检测到
+    fn write_file(path: &std::path::Path, bytes: &[u8], version: u64) {
+        fs::write(path, bytes).unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(version))
+            .unwrap();
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = vec![];
+        RgbaImage::from_pixel(width, height, Rgba([0, 128, 255, 255]))
+            .write_to(
+                &mut std::io::Cursor::new(&mut bytes),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn reload_failure_keeps_last_good_image() {
+        let Ok((device, queue)) = create_headless_device(true) else {
+            eprintln!("skipping: no fallback adapter");
+            return;
+        };
+        let mut cache = TextureCache::default();
+        let mut context = SourceContext {
+            device: &device,
+            queue: &queue,
+            cache: &mut cache,
+        };
+
+        let path = std::env::temp_dir().join(format!("image-source-{}.png", std::process::id()));
+        write_file(&path, &png(4, 3), 1);
+
+        let mut source = ImageSource::default();
+        source.update(&json!({ "file": path })).unwrap();
+        source.set_active(true);
+        source.tick(&mut context, Duration::ZERO).unwrap();
+        assert_eq!(source.size(), (4, 3));
+
+        // 文件被替换为无法解码的内容时保留原来的图片
+        write_file(&path, b"not an image", 2);
+        assert!(source.tick(&mut context, FILE_CHECK_INTERVAL).is_err());
+        assert_eq!(source.size(), (4, 3));
+        assert!(source.texture().is_some());
+
+        // 文件恢复后加载新的图片
+        write_file(&path, &png(5, 2), 3);
+        source.tick(&mut context, FILE_CHECK_INTERVAL).unwrap();
+        assert_eq!(source.size(), (5, 2));
+
+        // 换成不存在的文件时不再显示原来的图片
+        source
+            .update(&json!({ "file": path.with_extension("missing.png") }))
+            .unwrap();
+        assert!(source.tick(&mut context, Duration::ZERO).is_err());
+        assert!(source.texture().is_none());
+
+        let _ = fs::remove_file(&path);
+    }
+}
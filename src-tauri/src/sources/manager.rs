@@ -0,0 +1,537 @@
+/// 来源管理模块
+///
+/// 为场景集合中的每个来源运行一个实例，跟随来源的添加、删除和设置变化，
+/// 按场景中的可见性激活或停用来源。所有实例在来源线程中按主画布帧率更新，
+/// 共用画布的设备和同一个纹理缓存，画布合成时直接使用来源的纹理
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use serde_json::Value;
+use tauri::AppHandle;
+use wgpu::{Device, Queue};
+
+use crate::{
+    graphics::{cache::TextureCache, canvas::shared_device, compositor::Layer, texture::Texture},
+    media::frame::VideoInfo,
+    scene::{
+        collection::{Scene, SceneCollection},
+        item::SceneItem,
+        scenes,
+    },
+    sources::{clear_media_commands, create_source, set_source_size, SourceContext, VideoSource},
+    Result,
+};
+
+/// 重新读取帧率的间隔
+const SETTINGS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    /// 来源管理器，来源线程启动后创建
+    static ref SOURCES: Mutex<Option<SourceManager>> = Mutex::new(None);
+}
+
+/// 场景集合中一个来源的状态，在场景集合的锁内读取
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceState {
+    pub uuid: String,
+    pub kind: String,
+    pub settings: Value,
+    /// 来源在节目中可见
+    pub active: bool,
+}
+
+/// 读取场景集合中所有来源的状态
+///
+/// 来源在任一画布当前场景中有可见的场景项时为激活状态
+///
+/// # 参数
+///
+/// * `collection` - 场景集合
+pub fn source_states(collection: &SceneCollection) -> Vec<SourceState> {
+    let current = std::iter::once(collection.current_scene.as_ref())
+        .chain(
+            collection
+                .canvases
+                .iter()
+                .map(|canvas| canvas.current_scene.as_ref()),
+        )
+        .flatten()
+        .filter_map(|uuid| collection.scene(uuid));
+    let active: HashSet<&str> = current
+        .flat_map(|scene| scene.items.iter())
+        .filter(|item| item.visible)
+        .map(|item| item.source.as_str())
+        .collect();
+
+    collection
+        .sources
+        .values()
+        .map(|source| SourceState {
+            uuid: source.uuid.clone(),
+            kind: source.kind.clone(),
+            settings: source.settings.clone(),
+            active: active.contains(source.uuid.as_str()),
+        })
+        .collect()
+}
+
+/// 复制场景中可见的场景项，供合成时在场景集合的锁外使用
+///
+/// # 参数
+///
+/// * `scene` - 场景
+pub fn visible_items(scene: &Scene) -> Vec<SceneItem> {
+    scene
+        .items
+        .iter()
+        .filter(|item| item.visible)
+        .cloned()
+        .collect()
+}
+
+/// 运行中的来源实例
+struct Instance {
+    kind: String,
+    /// 最近一次应用的设置
+    settings: Value,
+    source: Box<dyn VideoSource>,
+    active: bool,
+    /// 最近一次更新失败的原因，相同的错误只记录一次
+    error: Option<String>,
+}
+
+impl Instance {
+    /// 记录更新结果，错误变化时输出日志
+    fn report(&mut self, uuid: &str, result: Result<()>) {
+        match result {
+            Ok(_) => self.error = None,
+            Err(e) => {
+                let message = e.to_string();
+                if self.error.as_ref() != Some(&message) {
+                    warn!("source {} ({}): {}", uuid, self.kind, message);
+                    self.error = Some(message);
+                }
+            }
+        }
+    }
+}
+
+/// 来源管理器
+pub struct SourceManager {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    /// 所有来源共用的纹理缓存
+    cache: TextureCache,
+    /// 来源实例，以来源 UUID 为键
+    instances: HashMap<String, Instance>,
+    /// 创建失败的来源及当时的类型和设置，设置变化前不再重试
+    failed: HashMap<String, (String, Value)>,
+}
+
+impl SourceManager {
+    /// 创建来源管理器
+    ///
+    /// # 参数
+    ///
+    /// * `device` - 来源和画布共用的设备
+    /// * `queue` - 设备的队列
+    pub fn new(device: Arc<Device>, queue: Arc<Queue>) -> Self {
+        Self {
+            device,
+            queue,
+            cache: TextureCache::default(),
+            instances: HashMap::new(),
+            failed: HashMap::new(),
+        }
+    }
+
+    /// 按场景集合创建、更新和删除来源实例，并更新激活状态
+    ///
+    /// # 参数
+    ///
+    /// * `states` - 场景集合中所有来源的状态
+    pub fn sync(&mut self, states: &[SourceState]) {
+        let wanted: HashMap<&str, &SourceState> = states
+            .iter()
+            .map(|state| (state.uuid.as_str(), state))
+            .collect();
+
+        // 来源被删除或类型变化时丢弃实例，新实例在旧实例释放后创建，
+        // 旧实例没有处理的媒体控制命令一起丢弃
+        let count = self.instances.len();
+        self.instances.retain(|uuid, instance| {
+            let keep = wanted
+                .get(uuid.as_str())
+                .is_some_and(|state| state.kind == instance.kind);
+            if !keep {
+                set_source_size(uuid, None);
+                clear_media_commands(uuid);
+            }
+            keep
+        });
+        self.failed
+            .retain(|uuid, _| wanted.contains_key(uuid.as_str()));
+        let mut changed = self.instances.len() != count;
+
+        for state in states {
+            match self.instances.get_mut(&state.uuid) {
+                Some(instance) => {
+                    if instance.settings != state.settings {
+                        changed = true;
+                        instance.settings = state.settings.clone();
+                        let result = instance.source.update(&state.settings);
+                        instance.report(&state.uuid, result);
+                    }
+                    if instance.active != state.active {
+                        changed = true;
+                        instance.active = state.active;
+                        instance.source.set_active(state.active);
+                    }
+                }
+                None => self.create(state),
+            }
+        }
+
+        // 创建失败的来源没有实例处理命令
+        for uuid in self.failed.keys() {
+            clear_media_commands(uuid);
+        }
+
+        // 不再被任何来源使用的文件在下一帧前释放
+        if changed {
+            self.cache.purge();
+        }
+    }
+
+    /// 创建来源实例，失败时记录设置，设置变化后再重试
+    fn create(&mut self, state: &SourceState) {
+        let attempt = (state.kind.clone(), state.settings.clone());
+        if self.failed.get(&state.uuid) == Some(&attempt) {
+            return;
+        }
+
+        match create_source(&state.uuid, &state.kind, &state.settings) {
+            Ok(mut source) => {
+                source.set_active(state.active);
+                self.failed.remove(&state.uuid);
+                self.instances.insert(
+                    state.uuid.clone(),
+                    Instance {
+                        kind: state.kind.clone(),
+                        settings: state.settings.clone(),
+                        source,
+                        active: state.active,
+                        error: None,
+                    },
+                );
+            }
+            Err(e) => {
+                warn!(
+                    "failed to create source {} ({}): {}",
+                    state.uuid, state.kind, e
+                );
+                self.failed.insert(state.uuid.clone(), attempt);
+            }
+        }
+    }
+
+    /// 推进所有来源的时间并准备当前帧
+    ///
+    /// # 参数
+    ///
+    /// * `delta` - 距上次更新经过的时间
+    pub fn tick(&mut self, delta: Duration) {
+        let mut context = SourceContext {
+            device: &self.device,
+            queue: &self.queue,
+            cache: &mut self.cache,
+        };
+        for (uuid, instance) in self.instances.iter_mut() {
+            let result = instance.source.tick(&mut context, delta);
+            instance.report(uuid, result);
+            set_source_size(uuid, Some(instance.source.size()));
+        }
+    }
+
+    /// 来源是否有运行中的实例
+    pub fn contains(&self, uuid: &str) -> bool {
+        self.instances.contains_key(uuid)
+    }
+
+    /// 来源当前帧的纹理
+    pub fn texture(&self, uuid: &str) -> Option<&Texture> {
+        self.instances
+            .get(uuid)
+            .and_then(|instance| instance.source.texture())
+    }
+
+    /// 来源尺寸，没有运行或没有内容时为 `(0, 0)`
+    pub fn size(&self, uuid: &str) -> (u32, u32) {
+        self.instances
+            .get(uuid)
+            .map_or((0, 0), |instance| instance.source.size())
+    }
+
+    /// 为场景项生成合成用的层，跳过没有画面的来源
+    ///
+    /// # 参数
+    ///
+    /// * `items` - 可见的场景项，按从底到顶排列
+    pub fn layers<'a>(&'a self, items: &'a [SceneItem]) -> Vec<Layer<'a>> {
+        items
+            .iter()
+            .filter_map(|item| {
+                Some(Layer {
+                    texture: self.texture(&item.source)?,
+                    size: self.size(&item.source),
+                    transform: &item.transform,
+                })
+            })
+            .collect()
+    }
+
+    /// 纹理缓存中的文件数量
+    pub fn cached_files(&self) -> usize {
+        self.cache.len()
+    }
+}
+
+impl Drop for SourceManager {
+    fn drop(&mut self) {
+        for uuid in self.instances.keys() {
+            set_source_size(uuid, None);
+            clear_media_commands(uuid);
+        }
+    }
+}
+
+/// 使用来源管理器，来源线程还没有启动时返回 `None`
+///
+/// 来源线程更新来源时持有同一把锁，因此回调中看到的总是完整的一帧。
+/// 浏览器来源会等待主线程创建窗口，主线程中不能调用
+///
+/// # 参数
+///
+/// * `f` - 回调
+pub fn with_sources<R>(f: impl FnOnce(&SourceManager) -> R) -> Option<R> {
+    SOURCES.lock().unwrap().as_ref().map(f)
+}
+
+/// 启动来源线程
+///
+/// # 参数
+///
+/// * `_app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示线程是否启动
+pub fn setup_sources(_app: &AppHandle) -> Result<()> {
+    thread::Builder::new()
+        .name("sources".to_string())
+        .spawn(run_sources)?;
+
+    Ok(())
+}
+
+/// 来源线程，按主画布帧率同步场景集合并更新来源
+fn run_sources() {
+    let (device, queue) = match shared_device() {
+        Ok(shared) => shared,
+        Err(e) => {
+            error!("failed to create source device: {}", e);
+            return;
+        }
+    };
+    *SOURCES.lock().unwrap() = Some(SourceManager::new(device, queue));
+    info!("sources started");
+
+    let mut interval = Duration::from_nanos(VideoInfo::load().frame_interval_ns());
+    let mut last_check = Instant::now();
+    let mut last_tick = Instant::now();
+    loop {
+        let next = last_tick + interval;
+        let now = Instant::now();
+        if next > now {
+            thread::sleep(next - now);
+        }
+        if last_check.elapsed() >= SETTINGS_CHECK_INTERVAL {
+            interval = Duration::from_nanos(VideoInfo::load().frame_interval_ns());
+            last_check = Instant::now();
+        }
+
+        // 先释放场景集合的锁，来源更新时可能需要读取场景集合
+        let states = source_states(&scenes());
+        let now = Instant::now();
+        let delta = now - last_tick;
+        last_tick = now;
+
+        if let Some(manager) = SOURCES.lock().unwrap().as_mut() {
+            manager.sync(&states);
+            manager.tick(delta);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        graphics::canvas::create_headless_device,
+        scene::{canvas::CanvasConfig, source::Source},
+        sources::{
+            send_media_command, source_size, take_media_commands, MediaCommand, IMAGE_SOURCE,
+            SLIDESHOW_SOURCE,
+        },
+    };
+
+    fn manager() -> Option<SourceManager> {
+        let (device, queue) = create_headless_device(true).ok()?;
+        Some(SourceManager::new(Arc::new(device), Arc::new(queue)))
+    }
+
+    #[test]
+    fn states_follow_visibility_on_every_canvas() {
+        let mut collection = SceneCollection::new("test");
+        let main = collection.add_scene("Main").unwrap();
+        let other = collection.add_scene("Other").unwrap();
+        let shown = collection
+            .add_source(Source::new("Shown", IMAGE_SOURCE, json!({})))
+            .unwrap();
+        let hidden = collection
+            .add_source(Source::new("Hidden", IMAGE_SOURCE, json!({})))
+            .unwrap();
+        let elsewhere = collection
+            .add_source(Source::new("Elsewhere", IMAGE_SOURCE, json!({})))
+            .unwrap();
+        collection.add_item(&main, &shown).unwrap();
+        let item = collection.add_item(&main, &hidden).unwrap();
+        collection
+            .scene_mut(&main)
+            .unwrap()
+            .item_mut(item.id)
+            .unwrap()
+            .visible = false;
+        collection.add_item(&other, &elsewhere).unwrap();
+
+        let active = |collection: &SceneCollection| {
+            let mut active: Vec<String> = source_states(collection)
+                .into_iter()
+                .filter(|state| state.active)
+                .map(|state| state.uuid)
+                .collect();
+            active.sort();
+            active
+        };
+        assert_eq!(active(&collection), vec![shown.clone()]);
+
+        // 附加画布的当前场景也在节目中
+        let canvas = collection
+            .add_canvas(CanvasConfig::new("Vertical", 1080, 1920, 30, 1).unwrap())
+            .unwrap();
+        collection.scene_mut(&other).unwrap().canvas = Some(canvas.clone());
+        collection.try_canvas_mut(&canvas).unwrap().current_scene = Some(other.clone());
+        let mut expected = vec![shown, elsewhere];
+        expected.sort();
+        assert_eq!(active(&collection), expected);
+    }
+
+    #[test]
+    fn instances_follow_the_collection() {
+        let Some(mut manager) = manager() else {
+            eprintln!("skipping: no fallback adapter");
+            return;
+        };
+
+        let dir = std::env::temp_dir().join(format!("source-manager-{}", std::process::id()));
+        write_images(&dir);
+        let (a, b) = (dir.join("a.png"), dir.join("b.png"));
+
+        let mut collection = SceneCollection::new("test");
+        let scene = collection.add_scene("Scene").unwrap();
+        let first = collection
+            .add_source(Source::new("First", IMAGE_SOURCE, json!({ "file": a })))
+            .unwrap();
+        let second = collection
+            .add_source(Source::new("Second", IMAGE_SOURCE, json!({ "file": a })))
+            .unwrap();
+        collection.add_item(&scene, &first).unwrap();
+
+        manager.sync(&source_states(&collection));
+        manager.tick(Duration::ZERO);
+        assert!(manager.contains(&first) && manager.contains(&second));
+        assert_eq!(manager.size(&first), (4, 4));
+        assert_eq!(source_size(&first), Some((4, 4)));
+        // 两个来源显示同一文件，只解码一次
+        assert_eq!(manager.cached_files(), 1);
+
+        // 设置变化时更新实例
+        collection.source_mut(&second).unwrap().settings = json!({ "file": b });
+        manager.sync(&source_states(&collection));
+        manager.tick(Duration::ZERO);
+        assert_eq!(manager.size(&second), (2, 3));
+        assert_eq!(source_size(&second), Some((2, 3)));
+        assert!(manager.texture(&second).is_some());
+
+        // 类型变化时重新创建，删除后丢弃实例并释放纹理
+        collection.source_mut(&first).unwrap().kind = SLIDESHOW_SOURCE.to_string();
+        collection.source_mut(&first).unwrap().settings = json!({ "files": [] });
+        collection.sources.remove(&second);
+        send_media_command(&second, MediaCommand::Play);
+        manager.sync(&source_states(&collection));
+        manager.tick(Duration::ZERO);
+        assert!(manager.contains(&first));
+        assert!(!manager.contains(&second));
+        assert_eq!(manager.cached_files(), 0);
+        // 停止的实例没有处理的命令不会留给之后的实例
+        assert!(take_media_commands(&second).is_empty());
+        // 删除的来源不再有尺寸，空的幻灯片没有内容
+        assert_eq!(source_size(&second), None);
+        assert_eq!(source_size(&first), None);
+
+        // 无法创建的来源在设置变化前不再重试
+        let broken = collection
+            .add_source(Source::new("Broken", "unknown_source", json!({})))
+            .unwrap();
+        manager.sync(&source_states(&collection));
+        assert!(!manager.contains(&broken));
+        assert!(manager.failed.contains_key(&broken));
+        send_media_command(&broken, MediaCommand::Play);
+        manager.sync(&source_states(&collection));
+        assert!(take_media_commands(&broken).is_empty());
+        collection.sources.remove(&broken);
+        manager.sync(&source_states(&collection));
+        assert!(!manager.failed.contains_key(&broken));
+
+        // 管理器释放时移除所有尺寸
+        collection.source_mut(&first).unwrap().kind = IMAGE_SOURCE.to_string();
+        collection.source_mut(&first).unwrap().settings = json!({ "file": a });
+        manager.sync(&source_states(&collection));
+        manager.tick(Duration::ZERO);
+        assert_eq!(source_size(&first), Some((4, 4)));
+        drop(manager);
+        assert_eq!(source_size(&first), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// 写入 4x4 的 `a.png` 和 2x3 的 `b.png`
+    fn write_images(dir: &std::path::Path) {
+        std::fs::create_dir_all(dir).unwrap();
+        RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]))
+            .save(dir.join("a.png"))
+            .unwrap();
+        RgbaImage::from_pixel(2, 3, Rgba([0, 255, 0, 255]))
+            .save(dir.join("b.png"))
+            .unwrap();
+    }
+}
@@ -0,0 +1,621 @@
+/// 媒体文件来源模块
+///
+/// 在后台线程中解码本地视频或音频文件，按合成时钟显示视频帧并把音频送入混音。
+/// 支持循环播放、显示时从头播放、播放速度和媒体控制命令
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use image::RgbaImage;
+use log::{error, warn};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    graphics::texture::Texture,
+    media::{
+        clock::clock_ns,
+        decoder::{open_media, Decoded},
+        mixer::{clear_audio, push_audio},
+    },
+    sources::{
+        set_media_status, take_media_commands, MediaCommand, MediaState, MediaStatus,
+        SourceContext, VideoSource,
+    },
+    Result,
+};
+
+/// 解码线程最多领先的数据份数
+const DECODE_QUEUE_SIZE: usize = 16;
+
+/// 最多缓存的视频帧数
+const MAX_BUFFERED_FRAMES: usize = 4;
+
+/// 音频提前送入混音的时长（纳秒）
+const AUDIO_LOOKAHEAD_NS: u64 = 200_000_000;
+
+/// 媒体文件来源设置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+struct MediaSettings {
+    /// 媒体文件路径
+    local_file: String,
+    /// 循环播放
+    looping: bool,
+    /// 来源显示时从头播放
+    restart_on_activate: bool,
+    /// 播放速度（百分比）
+    speed_percent: u32,
+    /// 来源隐藏时关闭文件
+    close_when_inactive: bool,
+    /// 播放结束后不再显示最后一帧
+    clear_on_media_end: bool,
+}
+
+impl Default for MediaSettings {
+    fn default() -> Self {
+        Self {
+            local_file: String::new(),
+            looping: false,
+            restart_on_activate: true,
+            speed_percent: 100,
+            close_when_inactive: false,
+            clear_on_media_end: true,
+        }
+    }
+}
+
+/// 发送给解码线程的命令
+enum Control {
+    /// 跳转到指定位置，之后的数据使用新的序号
+    Seek(Duration, u64),
+    Looping(bool),
+}
+
+/// 解码线程发出的事件，带有产生该事件时的跳转序号
+enum Event {
+    Opened(Option<Duration>),
+    Data(u64, Decoded),
+    End(u64),
+    Error(u64, String),
+}
+
+/// 后台解码线程，来源丢弃它时线程退出
+struct Player {
+    control: Sender<Control>,
+    events: Receiver<Event>,
+}
+
+impl Player {
+    fn new(path: PathBuf, looping: bool) -> Result<Self> {
+        let (control, control_rx) = channel();
+        let (event_tx, events) = sync_channel(DECODE_QUEUE_SIZE);
+
+        thread::Builder::new()
+            .name("media-decoder".to_string())
+            .spawn(move || run_decoder(&path, looping, control_rx, event_tx))?;
+
+        Ok(Self { control, events })
+    }
+}
+
+/// 解码线程
+///
+/// 循环播放时在解码线程中直接跳回开头，时间戳加上已播放的时长继续递增，保证衔接无缝
+fn run_decoder(
+    path: &Path,
+    mut looping: bool,
+    control: Receiver<Control>,
+    events: SyncSender<Event>,
+) {
+    let mut reader = match open_media(path) {
+        Ok(reader) => reader,
+        Err(e) => {
+            let _ = events.send(Event::Error(0, e.to_string()));
+            return;
+        }
+    };
+    if events.send(Event::Opened(reader.duration())).is_err() {
+        return;
+    }
+
+    let mut generation = 0;
+    // 循环播放累计的时间偏移和本轮数据的结束时间
+    let mut offset = 0;
+    let mut end = 0;
+    let mut finished = false;
+
+    loop {
+        let command = if finished {
+            match control.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return,
+            }
+        } else {
+            match control.try_recv() {
+                Ok(command) => Some(command),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        };
+        match command {
+            Some(Control::Seek(position, seek_generation)) => {
+                generation = seek_generation;
+                offset = 0;
+                end = 0;
+                finished = false;
+                if let Err(e) = reader.seek(position) {
+                    finished = true;
+                    if events
+                        .send(Event::Error(generation, e.to_string()))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                continue;
+            }
+            Some(Control::Looping(value)) => {
+                looping = value;
+                continue;
+            }
+            None => {}
+        }
+
+        let event = match reader.read() {
+            Ok(Some(mut decoded)) => {
+                match &mut decoded {
+                    Decoded::Video { pts, .. } => {
+                        *pts += offset;
+                        end = end.max(*pts);
+                    }
+                    Decoded::Audio(frame) => {
+                        frame.timestamp += offset;
+                        end = end.max(frame.timestamp + frame.duration_ns());
+                    }
+                }
+                Event::Data(generation, decoded)
+            }
+            Ok(None) => {
+                let duration = reader
+                    .duration()
+                    .map_or(0, |duration| duration.as_nanos() as u64);
+                if looping && (duration > 0 || end > offset) {
+                    match reader.seek(Duration::ZERO) {
+                        Ok(_) => {
+                            offset += duration.max(end - offset);
+                            end = offset;
+                            continue;
+                        }
+                        Err(e) => {
+                            finished = true;
+                            Event::Error(generation, e.to_string())
+                        }
+                    }
+                } else {
+                    finished = true;
+                    Event::End(generation)
+                }
+            }
+            Err(e) => {
+                finished = true;
+                Event::Error(generation, e.to_string())
+            }
+        };
+
+        if events.send(event).is_err() {
+            return;
+        }
+    }
+}
+
+/// 媒体文件来源
+pub struct MediaSource {
+    uuid: String,
+    settings: MediaSettings,
+    player: Option<Player>,
+    state: MediaState,
+    active: bool,
+    /// 当前跳转序号，序号不同的数据已经过时
+    generation: u64,
+    /// 播放位置（纳秒），循环播放时持续递增
+    position: u64,
+    duration: Option<Duration>,
+    /// 等待时间到达的视频帧
+    frames: VecDeque<(u64, RgbaImage)>,
+    /// 还没到送入时间的数据
+    next: Option<Decoded>,
+    /// 已收到数据的结束时间（纳秒）
+    received_end: u64,
+    /// 打开或跳转后还没有收到数据，位置暂停推进
+    buffering: bool,
+    /// 解码线程已经读完文件
+    decoder_finished: bool,
+    /// 正在显示的帧的时间戳
+    shown: Option<u64>,
+    texture: Option<Texture>,
+}
+
+impl MediaSource {
+    /// 创建媒体文件来源
+    ///
+    /// # 参数
+    ///
+    /// * `uuid` - 来源 UUID，用于接收媒体控制命令和推送音频
+    pub fn new(uuid: &str) -> Self {
+        Self {
+            uuid: uuid.to_string(),
+            settings: MediaSettings::default(),
+            player: None,
+            state: MediaState::None,
+            active: false,
+            generation: 0,
+            position: 0,
+            duration: None,
+            frames: VecDeque::new(),
+            next: None,
+            received_end: 0,
+            buffering: true,
+            decoder_finished: false,
+            shown: None,
+            texture: None,
+        }
+    }
+
+    /// 播放速度
+    fn speed(&self) -> f64 {
+        self.settings.speed_percent.clamp(1, 200) as f64 / 100.
+    }
+
+    /// 清空已缓冲的数据
+    fn reset_buffers(&mut self) {
+        self.frames.clear();
+        self.next = None;
+        self.buffering = true;
+        self.decoder_finished = false;
+        clear_audio(&self.uuid);
+    }
+
+    /// 打开文件并从头播放
+    fn open(&mut self) {
+        self.close();
+        if self.settings.local_file.is_empty() {
+            return;
+        }
+
+        match Player::new(
+            PathBuf::from(&self.settings.local_file),
+            self.settings.looping,
+        ) {
+            Ok(player) => {
+                self.player = Some(player);
+                self.state = MediaState::Opening;
+            }
+            Err(e) => {
+                error!("failed to start media decoder: {}", e);
+                self.state = MediaState::Error;
+            }
+        }
+    }
+
+    /// 关闭文件
+    fn close(&mut self) {
+        self.player = None;
+        self.generation = 0;
+        self.position = 0;
+        self.received_end = 0;
+        self.duration = None;
+        self.shown = None;
+        self.texture = None;
+        self.reset_buffers();
+    }
+
+    /// 跳转到指定位置
+    fn seek(&mut self, position: Duration) {
+        let Some(player) = &self.player else {
+            return;
+        };
+
+        self.generation += 1;
+        let _ = player
+            .control
+            .send(Control::Seek(position, self.generation));
+        self.position = position.as_nanos() as u64;
+        self.received_end = self.position;
+        self.shown = None;
+        self.reset_buffers();
+        if self.state == MediaState::Ended {
+            self.state = MediaState::Paused;
+        }
+    }
+
+    /// 从头播放，文件没有打开时重新打开
+    fn restart(&mut self) {
+        if self.player.is_some() && self.state != MediaState::Error {
+            self.seek(Duration::ZERO);
+            self.state = MediaState::Playing;
+        } else {
+            self.open();
+        }
+    }
+
+    /// 处理媒体控制命令
+    fn handle_commands(&mut self) {
+        for command in take_media_commands(&self.uuid) {
+            match command {
+                MediaCommand::Play => match self.state {
+                    MediaState::Paused => self.state = MediaState::Playing,
+                    MediaState::Stopped | MediaState::Ended | MediaState::None => self.restart(),
+                    _ => {}
+                },
+                MediaCommand::Pause => {
+                    if self.state == MediaState::Playing {
+                        self.state = MediaState::Paused;
+                        clear_audio(&self.uuid);
+                        self.rewind_audio();
+                    }
+                }
+                MediaCommand::Restart => self.restart(),
+                MediaCommand::Stop => {
+                    self.close();
+                    self.state = MediaState::Stopped;
+                }
+                MediaCommand::Seek(time) => {
+                    let mut position = Duration::from_millis(time);
+                    if let Some(duration) = self.duration {
+                        position = position.min(duration);
+                    }
+                    self.seek(position);
+                }
+                MediaCommand::Next | MediaCommand::Previous => {}
+            }
+        }
+    }
+
+    /// 当前位置在文件中的时间（纳秒），循环播放时去掉已播放的轮数
+    fn media_time(&self) -> u64 {
+        match self.duration.map(|duration| duration.as_nanos() as u64) {
+            Some(duration) if duration > 0 && self.settings.looping => self.position % duration,
+            Some(duration) => self.position.min(duration),
+            None => self.position,
+        }
+    }
+
+    /// 暂停时已送入混音的音频会被清空，跳转回当前位置，恢复播放时重新解码
+    fn rewind_audio(&mut self) {
+        if self.received_end > self.position {
+            self.seek(Duration::from_nanos(self.media_time()));
+        }
+    }
+
+    /// 接收解码线程的事件
+    fn receive(&mut self) {
+        loop {
+            if self.next.is_some() {
+                return;
+            }
+            let Some(player) = &self.player else {
+                return;
+            };
+
+            let event = match player.events.try_recv() {
+                Ok(event) => event,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.player = None;
+                    return;
+                }
+            };
+            match event {
+                Event::Opened(duration) => {
+                    self.duration = duration;
+                    if self.state == MediaState::Opening {
+                        self.state = MediaState::Playing;
+                    }
+                }
+                Event::Data(generation, decoded) if generation == self.generation => {
+                    self.buffering = false;
+                    self.next = Some(decoded);
+                }
+                Event::End(generation) if generation == self.generation => {
+                    self.buffering = false;
+                    self.decoder_finished = true;
+                }
+                Event::Error(generation, message) if generation == self.generation => {
+                    warn!(
+                        "media source '{}' error: {}",
+                        self.settings.local_file, message
+                    );
+                    self.buffering = false;
+                    self.decoder_finished = true;
+                    self.state = MediaState::Error;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// 把收到的数据送入视频帧队列或混音
+    fn dispatch(&mut self) {
+        loop {
+            self.receive();
+            let Some(decoded) = self.next.take() else {
+                return;
+            };
+
+            match decoded {
+                Decoded::Video { pts, image } => {
+                    if self.frames.len() >= MAX_BUFFERED_FRAMES {
+                        self.next = Some(Decoded::Video { pts, image });
+                        return;
+                    }
+                    // 最后一帧也要显示一帧的时长
+                    let interval = self
+                        .frames
+                        .back()
+                        .map_or(0, |(previous, _)| pts.saturating_sub(*previous));
+                    self.received_end = self.received_end.max(pts + interval);
+                    self.frames.push_back((pts, image));
+                }
+                Decoded::Audio(mut frame) => {
+                    if self.state != MediaState::Playing
+                        || frame.timestamp > self.position + AUDIO_LOOKAHEAD_NS
+                    {
+                        self.next = Some(Decoded::Audio(frame));
+                        return;
+                    }
+                    self.received_end =
+                        self.received_end.max(frame.timestamp + frame.duration_ns());
+
+                    // 媒体时间换算到合成时钟，变速时按速度改变采样率
+                    let speed = self.speed();
+                    let ahead = frame.timestamp as i64 - self.position as i64;
+                    frame.timestamp =
+                        (clock_ns() as i64 + (ahead as f64 / speed) as i64).max(0) as u64;
+                    frame.sample_rate = (frame.sample_rate as f64 * speed).round() as u32;
+                    push_audio(&self.uuid, &frame);
+                }
+            }
+        }
+    }
+
+    /// 上传当前位置应显示的视频帧
+    fn present(&mut self, context: &mut SourceContext) -> Result<()> {
+        while self.frames.len() > 1 && self.frames[1].0 <= self.position {
+            self.frames.pop_front();
+        }
+        let Some((pts, image)) = self.frames.front() else {
+            return Ok(());
+        };
+        // 跳转后先显示第一帧，不必等到时间到达
+        if *pts > self.position && self.shown.is_some() {
+            return Ok(());
+        }
+        if self.shown == Some(*pts) {
+            return Ok(());
+        }
+        self.shown = Some(*pts);
+
+        let reuse = self.texture.as_ref().is_some_and(|texture| {
+            (texture.texture.width(), texture.texture.height()) == image.dimensions()
+        });
+        match &self.texture {
+            Some(texture) if reuse => texture.write(context.queue, image)?,
+            _ => {
+                self.texture = Some(Texture::from_rgba(
+                    context.device,
+                    context.queue,
+                    image,
+                    Some("media"),
+                    false,
+                )?)
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 发布播放进度
+    fn publish_status(&self) {
+        set_media_status(
+            &self.uuid,
+            Some(MediaStatus {
+                state: self.state,
+                time: self.media_time() / 1_000_000,
+                duration: self
+                    .duration
+                    .map_or(0, |duration| duration.as_millis() as u64),
+            }),
+        );
+    }
+}
+
+impl VideoSource for MediaSource {
+    fn update(&mut self, settings: &Value) -> Result<()> {
+        let settings: MediaSettings = serde_json::from_value(settings.clone())?;
+        let reopen = settings.local_file != self.settings.local_file;
+        if settings.looping != self.settings.looping {
+            if let Some(player) = &self.player {
+                let _ = player.control.send(Control::Looping(settings.looping));
+            }
+        }
+        self.settings = settings;
+
+        if reopen {
+            self.close();
+            self.state = MediaState::None;
+            if !self.settings.close_when_inactive || self.active {
+                self.open();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn tick(&mut self, context: &mut SourceContext, delta: Duration) -> Result<()> {
+        self.handle_commands();
+
+        if self.state == MediaState::Playing && !self.buffering {
+            self.position += (delta.as_nanos() as f64 * self.speed()) as u64;
+        }
+        self.dispatch();
+        let result = self.present(context);
+
+        // 文件读完且所有数据都已播放
+        let drained = self.next.is_none() && self.frames.len() <= 1;
+        if self.decoder_finished
+            && drained
+            && self.position >= self.received_end
+            && self.state == MediaState::Playing
+        {
+            self.state = MediaState::Ended;
+            if self.settings.clear_on_media_end {
+                self.texture = None;
+                self.frames.clear();
+            }
+        }
+
+        self.publish_status();
+        result
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.texture.as_ref().map_or((0, 0), |texture| {
+            (texture.texture.width(), texture.texture.height())
+        })
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        self.texture.as_ref()
+    }
+
+    fn set_active(&mut self, active: bool) {
+        if active == self.active {
+            return;
+        }
+        self.active = active;
+
+        if active {
+            let closed = self.player.is_none() && self.state != MediaState::Stopped;
+            if closed || self.settings.restart_on_activate {
+                self.restart();
+            }
+        } else if self.settings.close_when_inactive {
+            self.close();
+        } else if self.settings.restart_on_activate && self.state == MediaState::Playing {
+            self.state = MediaState::Paused;
+            clear_audio(&self.uuid);
+        }
+    }
+}
+
+impl Drop for MediaSource {
+    fn drop(&mut self) {
+        clear_audio(&self.uuid);
+        set_media_status(&self.uuid, None);
+    }
+}
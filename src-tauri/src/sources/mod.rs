@@ -0,0 +1,402 @@
+#![allow(dead_code)]
+
+#[cfg(target_os = "linux")]
+pub mod audio_capture;
+pub mod browser;
+pub mod image_source;
+pub mod manager;
+pub mod media_source;
+#[cfg(all(target_os = "linux", feature = "pipewire"))]
+pub mod pipewire;
+pub mod slideshow;
+pub mod text_source;
+#[cfg(target_os = "linux")]
+pub mod v4l2;
+#[cfg(target_os = "linux")]
+pub mod x11;
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use anyhow::anyhow;
+use image::RgbaImage;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wgpu::{Device, Queue};
+
+use crate::{
+    graphics::{cache::TextureCache, texture::Texture},
+    sources::{
+        browser::BrowserSource, image_source::ImageSource, media_source::MediaSource,
+        slideshow::SlideshowSource, text_source::TextSource,
+    },
+    Result,
+};
+
+/// 图片来源类型 ID
+pub const IMAGE_SOURCE: &str = "image_source";
+
+/// 媒体文件来源类型 ID
+pub const MEDIA_SOURCE: &str = "ffmpeg_source";
+
+/// 幻灯片来源类型 ID
+pub const SLIDESHOW_SOURCE: &str = "slideshow";
+
+/// 文字来源类型 ID
+pub const TEXT_SOURCE: &str = "text_ft2_source";
+
+/// X11 屏幕采集来源类型 ID
+pub const XSHM_SOURCE: &str = "xshm_input";
+
+/// X11 窗口采集来源类型 ID
+pub const XCOMPOSITE_SOURCE: &str = "xcomposite_input";
+
+/// PipeWire 屏幕采集来源类型 ID
+pub const PIPEWIRE_SCREEN_SOURCE: &str = "pipewire-screen-capture-source";
+
+/// PipeWire 窗口采集来源类型 ID
+pub const PIPEWIRE_WINDOW_SOURCE: &str = "pipewire-window-capture-source";
+
+/// PulseAudio 输入采集来源类型 ID
+pub const PULSE_INPUT_SOURCE: &str = "pulse_input_capture";
+
+/// PulseAudio 输出采集来源类型 ID
+pub const PULSE_OUTPUT_SOURCE: &str = "pulse_output_capture";
+
+/// ALSA 输入采集来源类型 ID
+pub const ALSA_INPUT_SOURCE: &str = "alsa_input_capture";
+
+/// V4L2 视频采集设备来源类型 ID
+pub const V4L2_SOURCE: &str = "v4l2_input";
+
+/// 浏览器来源类型 ID
+pub const BROWSER_SOURCE: &str = "browser_source";
+
+/// 媒体控制命令，由热键或前端发送给来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaCommand {
+    Play,
+    Pause,
+    Restart,
+    Stop,
+    Next,
+    Previous,
+    /// 跳转到指定位置（毫秒）
+    Seek(u64),
+}
+
+impl MediaCommand {
+    /// 热键动作 ID 中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Play => "Play",
+            Self::Pause => "Pause",
+            Self::Restart => "Restart",
+            Self::Stop => "Stop",
+            Self::Next => "PlaylistNext",
+            Self::Previous => "PlaylistPrevious",
+            Self::Seek(_) => "Seek",
+        }
+    }
+
+    /// 热键描述的翻译键
+    pub fn locale_key(&self) -> &'static str {
+        match self {
+            Self::Play => "ContextBar.MediaControls.PlayMedia",
+            Self::Pause => "ContextBar.MediaControls.PauseMedia",
+            Self::Restart => "ContextBar.MediaControls.RestartMedia",
+            Self::Stop => "ContextBar.MediaControls.StopMedia",
+            Self::Next => "ContextBar.MediaControls.PlaylistNext",
+            Self::Previous => "ContextBar.MediaControls.PlaylistPrevious",
+            Self::Seek(_) => "ContextBar.MediaControls.BlindSeek",
+        }
+    }
+}
+
+/// 媒体播放状态
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaState {
+    #[default]
+    None,
+    Opening,
+    Playing,
+    Paused,
+    Stopped,
+    Ended,
+    Error,
+}
+
+/// 媒体播放进度，供前端显示
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MediaStatus {
+    pub state: MediaState,
+    /// 当前位置（毫秒）
+    pub time: u64,
+    /// 总时长（毫秒），未知时为 0
+    pub duration: u64,
+}
+
+lazy_static! {
+    /// 等待来源处理的媒体控制命令，按来源 UUID 分组
+    static ref MEDIA_COMMANDS: Mutex<HashMap<String, Vec<MediaCommand>>> = Mutex::new(HashMap::new());
+    /// 媒体来源的播放进度，按来源 UUID 索引
+    static ref MEDIA_STATUS: Mutex<HashMap<String, MediaStatus>> = Mutex::new(HashMap::new());
+    /// 运行中的来源最近一帧的尺寸，按来源 UUID 索引
+    static ref SOURCE_SIZES: Mutex<HashMap<String, (u32, u32)>> = Mutex::new(HashMap::new());
+}
+
+/// 来源类型支持的媒体控制命令
+///
+/// # 参数
+///
+/// * `kind` - 来源类型 ID
+pub fn media_commands(kind: &str) -> &'static [MediaCommand] {
+    match kind {
+        SLIDESHOW_SOURCE => &[
+            MediaCommand::Play,
+            MediaCommand::Pause,
+            MediaCommand::Restart,
+            MediaCommand::Stop,
+            MediaCommand::Next,
+            MediaCommand::Previous,
+        ],
+        MEDIA_SOURCE => &[
+            MediaCommand::Play,
+            MediaCommand::Pause,
+            MediaCommand::Restart,
+            MediaCommand::Stop,
+        ],
+        _ => &[],
+    }
+}
+
+/// 来源类型是否支持媒体控制命令，媒体来源还支持跳转
+///
+/// # 参数
+///
+/// * `kind` - 来源类型 ID
+/// * `command` - 媒体控制命令
+pub fn supports_media_command(kind: &str, command: MediaCommand) -> bool {
+    match command {
+        MediaCommand::Seek(_) => kind == MEDIA_SOURCE,
+        _ => media_commands(kind).contains(&command),
+    }
+}
+
+/// 来源类型是否向混音器输出音频，有音频的来源可以用热键静音
+///
+/// # 参数
+///
+/// * `kind` - 来源类型 ID
+pub fn has_audio(kind: &str) -> bool {
+    matches!(
+        kind,
+        MEDIA_SOURCE
+            | BROWSER_SOURCE
+            | PULSE_INPUT_SOURCE
+            | PULSE_OUTPUT_SOURCE
+            | ALSA_INPUT_SOURCE
+    )
+}
+
+/// 向来源发送媒体控制命令，在来源下一次更新时处理
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+/// * `command` - 媒体控制命令
+pub fn send_media_command(uuid: &str, command: MediaCommand) {
+    MEDIA_COMMANDS
+        .lock()
+        .unwrap()
+        .entry(uuid.to_string())
+        .or_default()
+        .push(command);
+}
+
+/// 取出来源等待处理的媒体控制命令
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+pub fn take_media_commands(uuid: &str) -> Vec<MediaCommand> {
+    MEDIA_COMMANDS
+        .lock()
+        .unwrap()
+        .remove(uuid)
+        .unwrap_or_default()
+}
+
+/// 丢弃来源等待处理的媒体控制命令，来源实例停止时调用
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+pub fn clear_media_commands(uuid: &str) {
+    MEDIA_COMMANDS.lock().unwrap().remove(uuid);
+}
+
+/// 更新来源的播放进度
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+/// * `status` - 播放进度，为 `None` 时移除
+pub fn set_media_status(uuid: &str, status: Option<MediaStatus>) {
+    let mut statuses = MEDIA_STATUS.lock().unwrap();
+    match status {
+        Some(status) => statuses.insert(uuid.to_string(), status),
+        None => statuses.remove(uuid),
+    };
+}
+
+/// 获取来源的播放进度，来源没有运行时返回 `None`
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+pub fn media_status(uuid: &str) -> Option<MediaStatus> {
+    MEDIA_STATUS.lock().unwrap().get(uuid).copied()
+}
+
+/// 记录运行中来源的尺寸，供校验裁剪使用
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+/// * `size` - 来源尺寸，为 `None` 或没有内容时移除
+pub fn set_source_size(uuid: &str, size: Option<(u32, u32)>) {
+    let mut sizes = SOURCE_SIZES.lock().unwrap();
+    match size.filter(|(width, height)| *width > 0 && *height > 0) {
+        Some(size) => sizes.insert(uuid.to_string(), size),
+        None => sizes.remove(uuid),
+    };
+}
+
+/// 获取来源的尺寸，来源没有运行或没有内容时返回 `None`
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID
+pub fn source_size(uuid: &str) -> Option<(u32, u32)> {
+    SOURCE_SIZES.lock().unwrap().get(uuid).copied()
+}
+
+/// 来源渲染时使用的 GPU 资源
+pub struct SourceContext<'a> {
+    pub device: &'a Device,
+    pub queue: &'a Queue,
+    pub cache: &'a mut TextureCache,
+}
+
+/// 视频来源的运行时实例
+pub trait VideoSource: Send {
+    /// 应用来源设置
+    ///
+    /// # 参数
+    ///
+    /// * `settings` - 场景集合中保存的来源设置
+    fn update(&mut self, settings: &Value) -> Result<()>;
+
+    /// 推进时间并准备当前帧
+    ///
+    /// # 参数
+    ///
+    /// * `context` - GPU 资源
+    /// * `delta` - 距上次调用经过的时间
+    fn tick(&mut self, context: &mut SourceContext, delta: Duration) -> Result<()>;
+
+    /// 来源尺寸，没有内容时为 `(0, 0)`
+    fn size(&self) -> (u32, u32);
+
+    /// 当前帧的纹理
+    fn texture(&self) -> Option<&Texture>;
+
+    /// 来源在节目或预览中显示或隐藏
+    fn set_active(&mut self, _active: bool) {}
+}
+
+/// 上传来源的图像，尺寸不变时复用纹理
+///
+/// # 参数
+///
+/// * `texture` - 来源的纹理
+/// * `context` - GPU 资源
+/// * `image` - 图像
+/// * `label` - 纹理标签
+pub fn upload_image(
+    texture: &mut Option<Texture>,
+    context: &mut SourceContext,
+    image: &RgbaImage,
+    label: &str,
+) -> Result<()> {
+    let reuse = texture.as_ref().is_some_and(|texture| {
+        (texture.texture.width(), texture.texture.height()) == image.dimensions()
+    });
+    match texture {
+        Some(texture) if reuse => texture.write(context.queue, image)?,
+        _ => {
+            *texture = Some(Texture::from_rgba(
+                context.device,
+                context.queue,
+                image,
+                Some(label),
+                false,
+            )?)
+        }
+    }
+
+    Ok(())
+}
+
+/// 根据类型创建来源实例
+///
+/// # 参数
+///
+/// * `uuid` - 来源 UUID，用于接收媒体控制命令
+/// * `kind` - 来源类型 ID
+/// * `settings` - 来源设置
+///
+/// # 返回值
+///
+/// 返回 `Result<Box<dyn VideoSource>>`，类型未知或设置无效时返回错误
+pub fn create_source(uuid: &str, kind: &str, settings: &Value) -> Result<Box<dyn VideoSource>> {
+    let mut source: Box<dyn VideoSource> = match kind {
+        IMAGE_SOURCE => Box::new(ImageSource::default()),
+        MEDIA_SOURCE => Box::new(MediaSource::new(uuid)),
+        SLIDESHOW_SOURCE => Box::new(SlideshowSource::new(uuid)),
+        TEXT_SOURCE => Box::new(TextSource::default()),
+        #[cfg(target_os = "linux")]
+        XSHM_SOURCE => Box::new(x11::xshm::XshmSource::default()),
+        #[cfg(target_os = "linux")]
+        XCOMPOSITE_SOURCE => Box::new(x11::xcomposite::XCompositeSource::default()),
+        #[cfg(all(target_os = "linux", feature = "pipewire"))]
+        PIPEWIRE_SCREEN_SOURCE => Box::new(pipewire::PipeWireSource::new(uuid, false)),
+        #[cfg(all(target_os = "linux", feature = "pipewire"))]
+        PIPEWIRE_WINDOW_SOURCE => Box::new(pipewire::PipeWireSource::new(uuid, true)),
+        #[cfg(target_os = "linux")]
+        PULSE_INPUT_SOURCE => Box::new(audio_capture::AudioCaptureSource::new(
+            uuid,
+            audio_capture::CaptureKind::PulseInput,
+        )),
+        #[cfg(target_os = "linux")]
+        PULSE_OUTPUT_SOURCE => Box::new(audio_capture::AudioCaptureSource::new(
+            uuid,
+            audio_capture::CaptureKind::PulseOutput,
+        )),
+        #[cfg(target_os = "linux")]
+        ALSA_INPUT_SOURCE => Box::new(audio_capture::AudioCaptureSource::new(
+            uuid,
+            audio_capture::CaptureKind::AlsaInput,
+        )),
+        #[cfg(target_os = "linux")]
+        V4L2_SOURCE => Box::new(v4l2::V4l2Source::default()),
+        BROWSER_SOURCE => Box::new(BrowserSource::new(uuid)),
+        _ => return Err(anyhow!("unknown source kind: {}", kind)),
+    };
+    source.update(settings)?;
+
+    Ok(source)
+}
@@ -0,0 +1,446 @@
+/// PipeWire 屏幕采集来源
+///
+/// 通过 xdg-desktop-portal 的 ScreenCast 接口让用户选择屏幕或窗口，再从 PipeWire 流读取画面，
+/// 用于 Wayland 等无法直接读取屏幕的环境。门户返回的恢复令牌保存在来源设置中，下次启动时不再询问
+use std::{
+    os::fd::OwnedFd,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use ::pipewire as pw;
+use anyhow::anyhow;
+use ashpd::{
+    desktop::{
+        screencast::{CursorMode, Screencast, SourceType},
+        PersistMode,
+    },
+    WindowIdentifier,
+};
+use image::RgbaImage;
+use log::{error, info};
+use pw::spa::{
+    self,
+    param::{format::FormatProperties, video::VideoFormat, ParamType},
+    pod::{serialize::PodSerializer, Pod},
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    graphics::texture::Texture,
+    scene::{save_scenes, scenes},
+    sources::{upload_image, SourceContext, VideoSource},
+    Result,
+};
+
+/// 采集线程共享的状态
+#[derive(Default)]
+struct Shared {
+    /// 最新一帧，来源更新时取走
+    frame: Option<RgbaImage>,
+    /// 会话或流出错的原因
+    error: Option<String>,
+}
+
+/// PipeWire 采集设置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+struct PipeWireSettings {
+    show_cursor: bool,
+    /// 门户返回的恢复令牌
+    restore_token: String,
+}
+
+impl Default for PipeWireSettings {
+    fn default() -> Self {
+        Self {
+            show_cursor: true,
+            restore_token: String::new(),
+        }
+    }
+}
+
+/// 运行中的采集会话
+struct Session {
+    stop: pw::channel::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+/// PipeWire 屏幕采集来源
+pub struct PipeWireSource {
+    uuid: String,
+    source_type: SourceType,
+    settings: Option<PipeWireSettings>,
+    shared: Arc<Mutex<Shared>>,
+    session: Option<Session>,
+    texture: Option<Texture>,
+}
+
+impl PipeWireSource {
+    /// 创建 PipeWire 采集来源
+    ///
+    /// # 参数
+    ///
+    /// * `uuid` - 来源 UUID，用于保存恢复令牌
+    /// * `window` - 采集窗口还是屏幕
+    pub fn new(uuid: &str, window: bool) -> Self {
+        Self {
+            uuid: uuid.to_string(),
+            source_type: if window {
+                SourceType::Window
+            } else {
+                SourceType::Monitor
+            },
+            settings: None,
+            shared: Arc::new(Mutex::new(Shared::default())),
+            session: None,
+            texture: None,
+        }
+    }
+
+    /// 启动采集会话
+    fn start(&mut self, settings: &PipeWireSettings) -> Result<()> {
+        self.stop();
+
+        let (stop, stop_receiver) = pw::channel::channel();
+        let uuid = self.uuid.clone();
+        let source_type = self.source_type;
+        let settings = settings.clone();
+        let shared = self.shared.clone();
+        let thread = thread::Builder::new()
+            .name("pipewire-capture".to_string())
+            .spawn(move || {
+                let result = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|runtime| {
+                        runtime.block_on(run_session(
+                            &uuid,
+                            source_type,
+                            &settings,
+                            &shared,
+                            stop_receiver,
+                        ))
+                    });
+                if let Err(e) = result {
+                    error!("PipeWire capture failed: {}", e);
+                    shared.lock().unwrap().error = Some(e.to_string());
+                }
+            })?;
+        self.session = Some(Session { stop, thread });
+
+        Ok(())
+    }
+
+    /// 停止采集会话
+    fn stop(&mut self) {
+        if let Some(session) = self.session.take() {
+            let _ = session.stop.send(());
+            let _ = session.thread.join();
+        }
+        *self.shared.lock().unwrap() = Shared::default();
+        self.texture = None;
+    }
+}
+
+impl VideoSource for PipeWireSource {
+    fn update(&mut self, settings: &Value) -> Result<()> {
+        let settings: PipeWireSettings = serde_json::from_value(settings.clone())?;
+        // 保存恢复令牌也会更新设置，此时不需要重新选择
+        let restart = self
+            .settings
+            .as_ref()
+            .is_none_or(|current| current.show_cursor != settings.show_cursor);
+        if restart {
+            self.start(&settings)?;
+        }
+        self.settings = Some(settings);
+
+        Ok(())
+    }
+
+    fn tick(&mut self, context: &mut SourceContext, _delta: Duration) -> Result<()> {
+        let (frame, error) = {
+            let mut shared = self.shared.lock().unwrap();
+            (shared.frame.take(), shared.error.take())
+        };
+        if let Some(error) = error {
+            self.texture = None;
+            return Err(anyhow!(error));
+        }
+
+        match frame {
+            Some(frame) => upload_image(&mut self.texture, context, &frame, "pipewire"),
+            None => Ok(()),
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.texture.as_ref().map_or((0, 0), |texture| {
+            (texture.texture.width(), texture.texture.height())
+        })
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        self.texture.as_ref()
+    }
+}
+
+impl Drop for PipeWireSource {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 通过门户打开采集会话，并读取 PipeWire 流直到停止
+async fn run_session(
+    uuid: &str,
+    source_type: SourceType,
+    settings: &PipeWireSettings,
+    shared: &Arc<Mutex<Shared>>,
+    stop: pw::channel::Receiver<()>,
+) -> Result<()> {
+    let proxy = Screencast::new().await?;
+    let session = proxy.create_session().await?;
+
+    let cursor_mode = if settings.show_cursor {
+        CursorMode::Embedded
+    } else {
+        CursorMode::Hidden
+    };
+    let restore_token = Some(settings.restore_token.as_str()).filter(|token| !token.is_empty());
+    proxy
+        .select_sources(
+            &session,
+            cursor_mode,
+            source_type.into(),
+            false,
+            restore_token,
+            PersistMode::ExplicitlyRevoked,
+        )
+        .await?
+        .response()?;
+
+    let streams = proxy
+        .start(&session, &WindowIdentifier::default())
+        .await?
+        .response()?;
+    let node = streams
+        .streams()
+        .first()
+        .ok_or(anyhow!("no screen cast stream selected"))?
+        .pipe_wire_node_id();
+    if let Some(token) = streams.restore_token() {
+        save_restore_token(uuid, token);
+    }
+
+    let fd = proxy.open_pipe_wire_remote(&session).await?;
+    info!("PipeWire capture started: node {}", node);
+    let result = run_stream(fd, node, shared, stop);
+
+    session.close().await?;
+    result
+}
+
+/// 保存门户返回的恢复令牌
+fn save_restore_token(uuid: &str, token: &str) {
+    if let Some(source) = scenes().source_mut(uuid) {
+        if let Some(settings) = source.settings.as_object_mut() {
+            settings.insert("restore_token".to_string(), Value::from(token));
+        }
+    }
+    if let Err(e) = save_scenes() {
+        error!("failed to save restore token: {}", e);
+    }
+}
+
+/// 读取 PipeWire 流，把每帧转换为 RGBA 放入共享状态
+fn run_stream(
+    fd: OwnedFd,
+    node: u32,
+    shared: &Arc<Mutex<Shared>>,
+    stop: pw::channel::Receiver<()>,
+) -> Result<()> {
+    pw::init();
+
+    let mainloop = pw::main_loop::MainLoop::new(None)?;
+    let context = pw::context::Context::new(&mainloop)?;
+    let core = context.connect_fd(fd, None)?;
+
+    let _stop = stop.attach(mainloop.loop_(), {
+        let mainloop = mainloop.clone();
+        move |_| mainloop.quit()
+    });
+
+    let stream = pw::stream::Stream::new(
+        &core,
+        "obs-screen-capture",
+        pw::properties::properties! {
+            *pw::keys::MEDIA_TYPE => "Video",
+            *pw::keys::MEDIA_CATEGORY => "Capture",
+            *pw::keys::MEDIA_ROLE => "Screen",
+        },
+    )?;
+
+    let shared = shared.clone();
+    let _listener = stream
+        .add_local_listener_with_user_data(spa::param::video::VideoInfoRaw::default())
+        .param_changed(|_, format, id, param| {
+            let Some(param) = param else {
+                return;
+            };
+            if id != ParamType::Format.as_raw() {
+                return;
+            }
+            if let Err(e) = format.parse(param) {
+                error!("invalid PipeWire video format: {}", e);
+                return;
+            }
+            info!(
+                "PipeWire video format: {:?} {}x{}",
+                format.format(),
+                format.size().width,
+                format.size().height
+            );
+        })
+        .process(move |stream, format| {
+            let Some(mut buffer) = stream.dequeue_buffer() else {
+                return;
+            };
+            let datas = buffer.datas_mut();
+            let Some(data) = datas.first_mut() else {
+                return;
+            };
+
+            let size = format.size();
+            let chunk = data.chunk();
+            let (offset, stride) = (chunk.offset() as usize, chunk.stride() as usize);
+            let Some(bytes) = data.data() else {
+                return;
+            };
+            if let Some(image) = convert_frame(
+                &bytes[offset.min(bytes.len())..],
+                format.format(),
+                size.width,
+                size.height,
+                stride,
+            ) {
+                shared.lock().unwrap().frame = Some(image);
+            }
+        })
+        .register()?;
+
+    let params = format_params()?;
+    let mut params = [Pod::from_bytes(&params).ok_or(anyhow!("invalid format pod"))?];
+    stream.connect(
+        spa::utils::Direction::Input,
+        Some(node),
+        pw::stream::StreamFlags::AUTOCONNECT | pw::stream::StreamFlags::MAP_BUFFERS,
+        &mut params,
+    )?;
+
+    mainloop.run();
+    info!("PipeWire capture stopped");
+
+    Ok(())
+}
+
+/// 支持的视频格式，只接受 32 位 RGB 格式
+fn format_params() -> Result<Vec<u8>> {
+    let object = spa::pod::object!(
+        spa::utils::SpaTypes::ObjectParamFormat,
+        ParamType::EnumFormat,
+        spa::pod::property!(
+            FormatProperties::MediaType,
+            Id,
+            spa::param::format::MediaType::Video
+        ),
+        spa::pod::property!(
+            FormatProperties::MediaSubtype,
+            Id,
+            spa::param::format::MediaSubtype::Raw
+        ),
+        spa::pod::property!(
+            FormatProperties::VideoFormat,
+            Choice,
+            Enum,
+            Id,
+            VideoFormat::BGRx,
+            VideoFormat::BGRx,
+            VideoFormat::BGRA,
+            VideoFormat::RGBx,
+            VideoFormat::RGBA,
+        ),
+        spa::pod::property!(
+            FormatProperties::VideoSize,
+            Choice,
+            Range,
+            Rectangle,
+            spa::utils::Rectangle {
+                width: 1920,
+                height: 1080
+            },
+            spa::utils::Rectangle {
+                width: 1,
+                height: 1
+            },
+            spa::utils::Rectangle {
+                width: 8192,
+                height: 8192
+            }
+        ),
+        spa::pod::property!(
+            FormatProperties::VideoFramerate,
+            Choice,
+            Range,
+            Fraction,
+            spa::utils::Fraction { num: 60, denom: 1 },
+            spa::utils::Fraction { num: 0, denom: 1 },
+            spa::utils::Fraction { num: 360, denom: 1 }
+        ),
+    );
+
+    Ok(PodSerializer::serialize(
+        std::io::Cursor::new(Vec::new()),
+        &spa::pod::Value::Object(object),
+    )?
+    .0
+    .into_inner())
+}
+
+/// 把 PipeWire 帧转换为 RGBA 图像
+fn convert_frame(
+    data: &[u8],
+    format: VideoFormat,
+    width: u32,
+    height: u32,
+    stride: usize,
+) -> Option<RgbaImage> {
+    let row = width as usize * 4;
+    let stride = if stride == 0 { row } else { stride };
+    if width == 0
+        || height == 0
+        || stride < row
+        || data.len() < stride * (height as usize - 1) + row
+    {
+        return None;
+    }
+
+    let mut rgba = Vec::with_capacity(row * height as usize);
+    for y in 0..height as usize {
+        for pixel in data[y * stride..y * stride + row].chunks_exact(4) {
+            rgba.extend_from_slice(&match format {
+                VideoFormat::BGRx => [pixel[2], pixel[1], pixel[0], 255],
+                VideoFormat::BGRA => [pixel[2], pixel[1], pixel[0], pixel[3]],
+                VideoFormat::RGBx => [pixel[0], pixel[1], pixel[2], 255],
+                VideoFormat::RGBA => [pixel[0], pixel[1], pixel[2], pixel[3]],
+                _ => return None,
+            });
+        }
+    }
+
+    RgbaImage::from_raw(width, height, rgba)
+}
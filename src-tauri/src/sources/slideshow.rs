@@ -0,0 +1,904 @@
+/// 幻灯片来源模块
+///
+/// 按顺序或随机播放文件列表中的图片，列表中的目录会被监视，新增或删除的图片自动加入或移出播放列表。
+/// 下一张图片在后台线程中预先解码，切换时只需要上传纹理
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::{error, warn};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    graphics::{
+        cache::{AnimatedTexture, CachedImage},
+        texture::Texture,
+        transition::{progress, Transition, TransitionKind},
+    },
+    media::animation::AnimatedImage,
+    sources::{take_media_commands, MediaCommand, SourceContext, VideoSource},
+    Result,
+};
+
+/// 幻灯片支持的图片扩展名
+pub const IMAGE_EXTENSIONS: [&str; 8] = ["png", "apng", "jpg", "jpeg", "gif", "webp", "bmp", "tga"];
+
+/// 检查目录是否变化的间隔
+const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 幻灯片来源设置
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct SlideshowSettings {
+    /// 图片文件或目录
+    files: Vec<String>,
+    /// 每张幻灯片的显示时长（毫秒）
+    slide_time: u64,
+    /// 转场类型
+    transition: TransitionKind,
+    /// 转场时长（毫秒）
+    transition_speed: u64,
+    /// 播放到最后一张后从头开始
+    #[serde(rename = "loop")]
+    looping: bool,
+    /// 随机顺序
+    randomize: bool,
+    /// 固定的输出尺寸，如 `1920x1080`，为空时使用第一张图片的尺寸
+    custom_size: String,
+}
+
+impl Default for SlideshowSettings {
+    fn default() -> Self {
+        Self {
+            files: vec![],
+            slide_time: 8000,
+            transition: TransitionKind::Fade,
+            transition_speed: 700,
+            looping: true,
+            randomize: false,
+            custom_size: String::new(),
+        }
+    }
+}
+
+/// 后台解码结果
+type Decoded = (PathBuf, SystemTime, Result<AnimatedImage>);
+
+/// 后台解码线程，请求发送端被丢弃时退出
+struct Preloader {
+    requests: Sender<PathBuf>,
+    results: Receiver<Decoded>,
+}
+
+impl Preloader {
+    fn new() -> Result<Self> {
+        let (requests, request_rx) = channel::<PathBuf>();
+        let (result_tx, results) = channel();
+
+        thread::Builder::new()
+            .name("slideshow-loader".to_string())
+            .spawn(move || {
+                for path in request_rx {
+                    let result = fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .map_err(anyhow::Error::from)
+                        .and_then(|modified| Ok((modified, AnimatedImage::load(&path)?)));
+                    let decoded = match result {
+                        Ok((modified, image)) => (path, modified, Ok(image)),
+                        Err(e) => (path, UNIX_EPOCH, Err(e)),
+                    };
+
+                    if result_tx.send(decoded).is_err() {
+                        break;
+                    }
+                }
+            })?;
+
+        Ok(Self { requests, results })
+    }
+}
+
+/// 幻灯片来源
+pub struct SlideshowSource {
+    uuid: String,
+    settings: SlideshowSettings,
+    /// 展开目录后的图片列表
+    files: Vec<PathBuf>,
+    /// 播放顺序，元素为 `files` 的索引
+    order: Vec<usize>,
+    /// 监视的目录及其修改时间
+    directories: Vec<(PathBuf, Option<SystemTime>)>,
+    /// 当前播放到 `order` 中的位置
+    position: usize,
+    /// 正在显示的图片路径
+    shown: Option<PathBuf>,
+    current: Option<AnimatedTexture>,
+    previous: Option<AnimatedTexture>,
+    /// 已上传的图片
+    loaded: HashMap<PathBuf, Arc<CachedImage>>,
+    /// 已请求解码但尚未完成的图片
+    pending: Vec<PathBuf>,
+    /// 无法加载的图片，播放时跳过
+    failed: Vec<PathBuf>,
+    preloader: Option<Preloader>,
+    slide_elapsed: Duration,
+    /// 切换时上一张图片的播放时间，用于在转场中继续播放动画
+    previous_elapsed: Duration,
+    transition_elapsed: Duration,
+    since_scan: Duration,
+    /// 用户通过媒体控制暂停
+    paused: bool,
+    /// 来源不在节目或预览中显示，与用户暂停分开记录，重新显示时不会取消用户暂停
+    inactive: bool,
+    stopped: bool,
+    /// 非循环播放时已经到达最后一张
+    finished: bool,
+    /// 最近一次切换的方向，跳过无法加载的图片时沿用
+    forward: bool,
+    /// 第一张显示的图片尺寸，没有固定尺寸时作为输出尺寸
+    base_size: Option<(u32, u32)>,
+    transition: Option<Transition>,
+    output: Option<Texture>,
+    /// 随机数状态
+    seed: u64,
+}
+
+impl SlideshowSource {
+    /// 创建幻灯片来源
+    ///
+    /// # 参数
+    ///
+    /// * `uuid` - 来源 UUID，用于接收媒体控制命令
+    pub fn new(uuid: &str) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0x9e37_79b9_7f4a_7c15, |elapsed| elapsed.as_nanos() as u64)
+            | 1;
+
+        Self {
+            uuid: uuid.to_string(),
+            settings: SlideshowSettings::default(),
+            files: vec![],
+            order: vec![],
+            directories: vec![],
+            position: 0,
+            shown: None,
+            current: None,
+            previous: None,
+            loaded: HashMap::new(),
+            pending: vec![],
+            failed: vec![],
+            preloader: None,
+            slide_elapsed: Duration::ZERO,
+            previous_elapsed: Duration::ZERO,
+            transition_elapsed: Duration::ZERO,
+            since_scan: Duration::ZERO,
+            paused: false,
+            inactive: false,
+            stopped: false,
+            finished: false,
+            forward: true,
+            base_size: None,
+            transition: None,
+            output: None,
+            seed,
+        }
+    }
+
+    /// 展开文件列表中的目录，并记录目录修改时间
+    fn scan(&mut self) {
+        let mut files = vec![];
+        let mut directories = vec![];
+
+        for entry in &self.settings.files {
+            let path = PathBuf::from(entry);
+            if path.is_dir() {
+                directories.push((path.clone(), modified(&path)));
+
+                let mut images: Vec<PathBuf> = match fs::read_dir(&path) {
+                    Ok(entries) => entries
+                        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                        .filter(|path| path.is_file() && is_image(path))
+                        .collect(),
+                    Err(e) => {
+                        warn!(
+                            "failed to read slideshow directory {}: {}",
+                            path.display(),
+                            e
+                        );
+                        vec![]
+                    }
+                };
+                images.sort();
+                files.extend(images);
+            } else if is_image(&path) {
+                files.push(path);
+            }
+        }
+
+        self.files = files;
+        self.directories = directories;
+        self.failed.clear();
+    }
+
+    /// 目录内容变化后重新扫描，尽量保持当前图片的位置
+    fn rescan_if_changed(&mut self) {
+        let changed = self
+            .directories
+            .iter()
+            .any(|(path, time)| modified(path) != *time);
+        if !changed {
+            return;
+        }
+
+        self.scan();
+        self.reorder();
+        if let Some(shown) = &self.shown {
+            if let Some(position) = self.order.iter().position(|i| &self.files[*i] == shown) {
+                self.position = position;
+            }
+        }
+        self.position = self.position.min(self.order.len().saturating_sub(1));
+        self.preload();
+    }
+
+    /// 生成播放顺序
+    fn reorder(&mut self) {
+        self.order = (0..self.files.len()).collect();
+
+        if self.settings.randomize {
+            // Fisher-Yates 洗牌
+            for i in (1..self.order.len()).rev() {
+                let j = (self.next_random() % (i as u64 + 1)) as usize;
+                self.order.swap(i, j);
+            }
+        }
+    }
+
+    /// xorshift64 伪随机数
+    fn next_random(&mut self) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+
+    /// 当前位置的图片路径
+    fn target(&self) -> Option<&PathBuf> {
+        self.order
+            .get(self.position)
+            .and_then(|index| self.files.get(*index))
+    }
+
+    /// 下一个位置，非循环播放到达末尾时返回 `None`
+    fn next_position(&self, looping: bool) -> Option<usize> {
+        if self.order.is_empty() {
+            return None;
+        }
+        if self.position + 1 < self.order.len() {
+            Some(self.position + 1)
+        } else if looping {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /// 移动到相邻的幻灯片
+    ///
+    /// # 参数
+    ///
+    /// * `forward` - 是否向后
+    /// * `manual` - 是否由用户触发，手动切换时总是循环
+    fn advance(&mut self, forward: bool, manual: bool) {
+        if self.order.is_empty() {
+            return;
+        }
+
+        let looping = manual || self.settings.looping;
+        let position = if forward {
+            self.next_position(looping)
+        } else if self.position > 0 {
+            Some(self.position - 1)
+        } else {
+            looping.then(|| self.order.len() - 1)
+        };
+
+        match position {
+            Some(position) => {
+                // 随机播放完一轮后重新洗牌
+                if forward && position == 0 && self.settings.randomize && self.order.len() > 2 {
+                    self.reorder();
+                }
+                self.position = position;
+                self.finished = false;
+            }
+            None => self.finished = true,
+        }
+        self.forward = forward;
+        self.slide_elapsed = Duration::ZERO;
+        self.preload();
+    }
+
+    /// 请求解码当前和下一张图片
+    fn preload(&mut self) {
+        let mut wanted: Vec<PathBuf> = self.target().cloned().into_iter().collect();
+        if let Some(next) = self
+            .next_position(true)
+            .and_then(|position| self.order.get(position))
+            .and_then(|index| self.files.get(*index))
+        {
+            wanted.push(next.clone());
+        }
+
+        // 只保留正在显示、即将显示的图片
+        self.loaded
+            .retain(|path, _| wanted.contains(path) || self.shown.as_ref() == Some(path));
+
+        if self.preloader.is_none() {
+            match Preloader::new() {
+                Ok(preloader) => self.preloader = Some(preloader),
+                Err(e) => {
+                    error!("failed to start slideshow loader: {}", e);
+                    return;
+                }
+            }
+        }
+        let Some(preloader) = &self.preloader else {
+            return;
+        };
+
+        for path in wanted {
+            if self.loaded.contains_key(&path)
+                || self.pending.contains(&path)
+                || self.failed.contains(&path)
+            {
+                continue;
+            }
+            if preloader.requests.send(path.clone()).is_ok() {
+                self.pending.push(path);
+            }
+        }
+    }
+
+    /// 上传后台解码完成的图片
+    fn receive(&mut self, context: &mut SourceContext) {
+        let Some(preloader) = &self.preloader else {
+            return;
+        };
+
+        while let Ok((path, modified, result)) = preloader.results.try_recv() {
+            self.pending.retain(|pending| pending != &path);
+
+            let image =
+                result.and_then(|image| match context.cache.lookup(&path, modified, true) {
+                    Some(cached) => Ok(cached),
+                    None => context.cache.insert(
+                        context.device,
+                        context.queue,
+                        &path,
+                        modified,
+                        image,
+                        true,
+                    ),
+                });
+            match image {
+                Ok(image) => {
+                    self.loaded.insert(path, image);
+                }
+                Err(e) => {
+                    warn!("failed to load slide {}: {}", path.display(), e);
+                    self.failed.push(path);
+                }
+            }
+        }
+    }
+
+    /// 处理媒体控制命令
+    fn handle_commands(&mut self) {
+        for command in take_media_commands(&self.uuid) {
+            match command {
+                MediaCommand::Play => {
+                    self.paused = false;
+                    self.stopped = false;
+                }
+                MediaCommand::Pause => self.paused = true,
+                MediaCommand::Restart => {
+                    self.reorder();
+                    self.position = 0;
+                    self.slide_elapsed = Duration::ZERO;
+                    self.paused = false;
+                    self.stopped = false;
+                    self.finished = false;
+                    self.preload();
+                }
+                MediaCommand::Stop => {
+                    self.stopped = true;
+                    self.shown = None;
+                    self.current = None;
+                    self.previous = None;
+                }
+                MediaCommand::Next => self.advance(true, true),
+                MediaCommand::Previous => self.advance(false, true),
+                MediaCommand::Seek(_) => {}
+            }
+        }
+    }
+
+    /// 输出尺寸
+    fn output_size(&self) -> Option<(u32, u32)> {
+        parse_size(&self.settings.custom_size).or(self.base_size)
+    }
+
+    /// 绘制当前画面
+    fn render(&mut self, context: &mut SourceContext) {
+        let Some((width, height)) = self.output_size().filter(|_| self.current.is_some()) else {
+            return;
+        };
+
+        let output_matches = self.output.as_ref().is_some_and(|output| {
+            output.texture.width() == width && output.texture.height() == height
+        });
+        if !output_matches {
+            self.output = Some(Texture::render_target(
+                context.device,
+                width,
+                height,
+                Some("slideshow"),
+            ));
+        }
+        if self.transition.as_ref().map(|transition| transition.kind)
+            != Some(self.settings.transition)
+        {
+            self.transition = Some(Transition::new(
+                context.device,
+                wgpu::TextureFormat::Rgba8UnormSrgb,
+                self.settings.transition,
+            ));
+        }
+        let (Some(output), Some(transition)) = (&self.output, &self.transition) else {
+            return;
+        };
+
+        let duration = Duration::from_millis(self.settings.transition_speed);
+        let t = progress(self.transition_elapsed, duration);
+        let from = self.previous.as_mut().filter(|_| t < 1.).map(|previous| {
+            previous.texture_at(
+                context.queue,
+                self.previous_elapsed + self.transition_elapsed,
+            )
+        });
+        let Some(current) = self.current.as_mut() else {
+            return;
+        };
+
+        let mut encoder = context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("slideshow"),
+            });
+        transition.encode(
+            context.device,
+            context.queue,
+            &mut encoder,
+            from,
+            current.texture_at(context.queue, self.slide_elapsed),
+            t,
+            output,
+        );
+        context.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl VideoSource for SlideshowSource {
+    fn update(&mut self, settings: &Value) -> Result<()> {
+        let settings: SlideshowSettings = serde_json::from_value(settings.clone())?;
+        let reorder =
+            settings.files != self.settings.files || settings.randomize != self.settings.randomize;
+        self.settings = settings;
+
+        if reorder {
+            self.scan();
+            self.reorder();
+            self.position = 0;
+            self.finished = false;
+            self.base_size = None;
+            self.preload();
+        }
+
+        Ok(())
+    }
+
+    fn tick(&mut self, context: &mut SourceContext, delta: Duration) -> Result<()> {
+        self.handle_commands();
+
+        self.since_scan += delta;
+        if self.since_scan >= SCAN_INTERVAL {
+            self.since_scan = Duration::ZERO;
+            self.rescan_if_changed();
+        }
+
+        self.receive(context);
+        if self.stopped {
+            return Ok(());
+        }
+
+        // 目标图片就绪后开始转场
+        if let Some(target) = self.target().cloned() {
+            if self.shown.as_ref() != Some(&target) {
+                if let Some(image) = self.loaded.get(&target).cloned() {
+                    self.base_size
+                        .get_or_insert((image.image.width, image.image.height));
+                    let image = AnimatedTexture::new(context.device, context.queue, image)?;
+                    self.previous = self.current.replace(image);
+                    self.shown = Some(target);
+                    self.previous_elapsed = self.slide_elapsed;
+                    self.slide_elapsed = Duration::ZERO;
+                    self.transition_elapsed = Duration::ZERO;
+                    self.preload();
+                } else if self.failed.contains(&target) {
+                    // 无法加载的图片直接跳过，全部失败时停在原处
+                    if self.failed.len() < self.order.len() {
+                        self.advance(self.forward, !self.forward);
+                    }
+                } else if !self.pending.contains(&target) {
+                    self.preload();
+                }
+            }
+        }
+
+        if !self.paused && !self.inactive {
+            self.transition_elapsed += delta;
+            if self.shown.is_some() {
+                self.slide_elapsed += delta;
+            }
+            if self.slide_elapsed >= Duration::from_millis(self.settings.slide_time)
+                && !self.finished
+                && self.order.len() > 1
+            {
+                self.advance(true, false);
+            }
+        }
+
+        self.render(context);
+
+        Ok(())
+    }
+
+    fn size(&self) -> (u32, u32) {
+        if self.stopped {
+            return (0, 0);
+        }
+
+        self.output_size().unwrap_or((0, 0))
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        if self.stopped || self.current.is_none() {
+            return None;
+        }
+
+        self.output.as_ref()
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.inactive = !active;
+    }
+}
+
+/// 文件修改时间
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// 是否是支持的图片文件
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// 解析 `宽x高` 格式的尺寸
+fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let (width, height) = size.split_once('x')?;
+    let width = width.trim().parse().ok()?;
+    let height = height.trim().parse().ok()?;
+
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use image::{Rgba, RgbaImage};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        graphics::{cache::TextureCache, canvas::create_headless_device},
+        sources::send_media_command,
+    };
+
+    /// 在临时目录中写入 4x4 的图片，名称为 `.png` 之前的部分
+    fn write_images(dir: &Path, names: &[&str]) {
+        fs::create_dir_all(dir).unwrap();
+        for (i, name) in names.iter().enumerate() {
+            RgbaImage::from_pixel(4, 4, Rgba([i as u8 * 60, 0, 0, 255]))
+                .save(dir.join(format!("{name}.png")))
+                .unwrap();
+        }
+    }
+
+    /// 当前播放顺序中的文件名
+    fn names(source: &SlideshowSource) -> Vec<String> {
+        source
+            .order
+            .iter()
+            .map(|i| {
+                source.files[*i]
+                    .file_stem()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    /// 不存在的图片文件，播放顺序的测试不需要解码
+    fn fake_files(count: usize) -> Vec<String> {
+        (0..count)
+            .map(|i| format!("/nonexistent/slideshow/{i}.png"))
+            .collect()
+    }
+
+    /// 推进到第一张图片显示出来
+    fn wait_shown(source: &mut SlideshowSource, context: &mut SourceContext) {
+        let start = Instant::now();
+        while source.shown.is_none() {
+            assert!(start.elapsed() < Duration::from_secs(5), "slide not loaded");
+            thread::sleep(Duration::from_millis(10));
+            source.tick(context, Duration::ZERO).unwrap();
+        }
+    }
+
+    #[test]
+    fn activity_does_not_override_manual_pause() {
+        let Ok((device, queue)) = create_headless_device(true) else {
+            eprintln!("skipping: no fallback adapter");
+            return;
+        };
+        let mut cache = TextureCache::default();
+        let mut context = SourceContext {
+            device: &device,
+            queue: &queue,
+            cache: &mut cache,
+        };
+
+        let dir = std::env::temp_dir().join(format!("slideshow-{}", std::process::id()));
+        write_images(&dir, &["a", "b", "c"]);
+
+        let uuid = "slideshow-pause-test";
+        let mut source = SlideshowSource::new(uuid);
+        source
+            .update(&json!({
+                "files": [dir],
+                "slide_time": 1000,
+                "transition_speed": 0,
+            }))
+            .unwrap();
+        source.set_active(true);
+        wait_shown(&mut source, &mut context);
+        assert_eq!(source.position, 0);
+
+        // 用户暂停后，来源隐藏再显示仍保持暂停
+        send_media_command(uuid, MediaCommand::Pause);
+        source.tick(&mut context, Duration::ZERO).unwrap();
+        source.set_active(false);
+        source.set_active(true);
+        source.tick(&mut context, Duration::from_secs(2)).unwrap();
+        assert_eq!(source.position, 0);
+
+        // 继续播放后按时切换
+        send_media_command(uuid, MediaCommand::Play);
+        source.tick(&mut context, Duration::from_secs(2)).unwrap();
+        assert_eq!(source.position, 1);
+
+        // 隐藏时暂停，重新显示后继续
+        source.set_active(false);
+        source.tick(&mut context, Duration::from_secs(2)).unwrap();
+        assert_eq!(source.position, 1);
+        source.set_active(true);
+        source.tick(&mut context, Duration::from_secs(2)).unwrap();
+        assert_eq!(source.position, 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rescan_keeps_the_shown_slide() {
+        let dir = std::env::temp_dir().join(format!("slideshow-rescan-{}", std::process::id()));
+        write_images(&dir, &["a", "b", "c"]);
+
+        let mut source = SlideshowSource::new("slideshow-rescan-test");
+        source.update(&json!({ "files": [dir] })).unwrap();
+        assert_eq!(names(&source), ["a", "b", "c"]);
+        source.position = 1;
+        source.shown = Some(dir.join("b.png"));
+
+        // 新增的图片按名称排序，位置跟随正在显示的图片
+        write_images(&dir, &["0"]);
+        source.rescan_if_changed();
+        assert_eq!(names(&source), ["0", "a", "b", "c"]);
+        assert_eq!(source.position, 2);
+
+        fs::remove_file(dir.join("a.png")).unwrap();
+        source.rescan_if_changed();
+        assert_eq!(names(&source), ["0", "b", "c"]);
+        assert_eq!(source.position, 1);
+
+        // 正在显示的图片被删除时停在原位置，即下一张
+        fs::remove_file(dir.join("b.png")).unwrap();
+        source.rescan_if_changed();
+        assert_eq!(names(&source), ["0", "c"]);
+        assert_eq!(source.position, 1);
+
+        // 没有变化时不重新扫描
+        source.position = 0;
+        source.rescan_if_changed();
+        assert_eq!(source.position, 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn randomized_order_is_reshuffled_at_the_wrap() {
+        let mut source = SlideshowSource::new("slideshow-random-test");
+        source.seed = 0x1234_5678_9abc_def1;
+        source
+            .update(&json!({ "files": fake_files(8), "randomize": true }))
+            .unwrap();
+        let first = source.order.clone();
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..8).collect::<Vec<_>>());
+
+        for position in 1..8 {
+            source.advance(true, false);
+            assert_eq!(source.position, position);
+            assert_eq!(source.order, first);
+        }
+        source.advance(true, false);
+        assert_eq!(source.position, 0);
+        assert_ne!(source.order, first);
+        let mut sorted = source.order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..8).collect::<Vec<_>>());
+
+        // 向前切换回到末尾时不重新洗牌
+        let order = source.order.clone();
+        source.advance(false, true);
+        assert_eq!(source.position, 7);
+        assert_eq!(source.order, order);
+    }
+
+    #[test]
+    fn playback_stops_at_the_end_without_loop() {
+        let mut source = SlideshowSource::new("slideshow-end-test");
+        source
+            .update(&json!({ "files": fake_files(3), "loop": false }))
+            .unwrap();
+
+        source.advance(true, false);
+        source.advance(true, false);
+        assert_eq!(source.position, 2);
+        assert!(!source.finished);
+        source.advance(true, false);
+        assert_eq!(source.position, 2);
+        assert!(source.finished);
+
+        // 手动切换总是循环，并重新开始自动播放
+        source.advance(true, true);
+        assert_eq!(source.position, 0);
+        assert!(!source.finished);
+        source.advance(false, false);
+        assert_eq!(source.position, 0);
+        assert!(source.finished);
+    }
+
+    #[test]
+    fn undecodable_slides_are_skipped() {
+        let Ok((device, queue)) = create_headless_device(true) else {
+            eprintln!("skipping: no fallback adapter");
+            return;
+        };
+        let mut cache = TextureCache::default();
+        let mut context = SourceContext {
+            device: &device,
+            queue: &queue,
+            cache: &mut cache,
+        };
+
+        let dir = std::env::temp_dir().join(format!("slideshow-broken-{}", std::process::id()));
+        write_images(&dir, &["a", "c"]);
+        fs::write(dir.join("b.png"), b"not a png").unwrap();
+
+        let mut source = SlideshowSource::new("slideshow-broken-test");
+        source
+            .update(&json!({ "files": [dir], "slide_time": 1000, "transition_speed": 0 }))
+            .unwrap();
+        wait_shown(&mut source, &mut context);
+        assert_eq!(source.shown, Some(dir.join("a.png")));
+
+        // 切换到无法解码的图片后跳到下一张
+        source.tick(&mut context, Duration::from_secs(1)).unwrap();
+        let start = Instant::now();
+        while source.shown != Some(dir.join("c.png")) {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "broken slide not skipped"
+            );
+            thread::sleep(Duration::from_millis(10));
+            source.tick(&mut context, Duration::ZERO).unwrap();
+        }
+        assert_eq!(source.failed, [dir.join("b.png")]);
+        assert_eq!(source.position, 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn all_undecodable_slides_do_not_spin() {
+        let Ok((device, queue)) = create_headless_device(true) else {
+            eprintln!("skipping: no fallback adapter");
+            return;
+        };
+        let mut cache = TextureCache::default();
+        let mut context = SourceContext {
+            device: &device,
+            queue: &queue,
+            cache: &mut cache,
+        };
+
+        let dir = std::env::temp_dir().join(format!("slideshow-failed-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["a.png", "b.png"] {
+            fs::write(dir.join(name), b"not a png").unwrap();
+        }
+
+        let mut source = SlideshowSource::new("slideshow-failed-test");
+        source.update(&json!({ "files": [dir] })).unwrap();
+        let start = Instant::now();
+        while source.failed.len() < 2 {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "slides not loaded"
+            );
+            thread::sleep(Duration::from_millis(10));
+            source.tick(&mut context, Duration::ZERO).unwrap();
+        }
+
+        // 全部失败后停在原处，不再请求解码
+        let position = source.position;
+        for _ in 0..10 {
+            source.tick(&mut context, Duration::ZERO).unwrap();
+            assert_eq!(source.position, position);
+        }
+        assert!(source.pending.is_empty());
+        assert!(source.shown.is_none());
+        assert!(source.texture().is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
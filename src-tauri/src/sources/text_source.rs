@@ -0,0 +1,301 @@
+/// 文字来源模块
+///
+/// 显示设置中的文字或文件内容，文件被修改后自动重新读取。
+/// 聊天记录模式只显示文件的最后几行
+use std::{
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    graphics::texture::Texture,
+    media::text::{argb_to_rgba, render_text, TextAlign, TextStyle},
+    sources::{SourceContext, VideoSource},
+    Result,
+};
+
+/// 检查文件是否被修改的间隔
+const FILE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 聊天记录模式最多读取文件末尾的字节数
+const LOG_TAIL_BYTES: u64 = 256 * 1024;
+
+/// 字体设置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+struct FontSettings {
+    face: String,
+    size: u32,
+    bold: bool,
+    italic: bool,
+}
+
+impl Default for FontSettings {
+    fn default() -> Self {
+        Self {
+            face: String::new(),
+            size: 32,
+            bold: false,
+            italic: false,
+        }
+    }
+}
+
+/// 文字来源设置，颜色为 `0xAARRGGBB` 格式
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+struct TextSettings {
+    text: String,
+    font: FontSettings,
+    color1: u32,
+    /// 渐变的底部颜色
+    color2: u32,
+    gradient: bool,
+    outline: bool,
+    outline_size: u32,
+    outline_color: u32,
+    drop_shadow: bool,
+    shadow_offset_x: i32,
+    shadow_offset_y: i32,
+    shadow_color: u32,
+    word_wrap: bool,
+    /// 自动换行宽度
+    custom_width: u32,
+    align: TextAlign,
+    /// 从文件读取文字
+    from_file: bool,
+    text_file: String,
+    /// 聊天记录模式，只显示最后几行
+    log_mode: bool,
+    log_lines: u32,
+}
+
+impl Default for TextSettings {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            font: FontSettings::default(),
+            color1: 0xFFFFFFFF,
+            color2: 0xFFFFFFFF,
+            gradient: false,
+            outline: false,
+            outline_size: 2,
+            outline_color: 0xFF000000,
+            drop_shadow: false,
+            shadow_offset_x: 4,
+            shadow_offset_y: 4,
+            shadow_color: 0xFF000000,
+            word_wrap: false,
+            custom_width: 0,
+            align: TextAlign::Left,
+            from_file: false,
+            text_file: String::new(),
+            log_mode: false,
+            log_lines: 6,
+        }
+    }
+}
+
+impl TextSettings {
+    fn style(&self) -> TextStyle {
+        TextStyle {
+            face: self.font.face.clone(),
+            size: self.font.size as f32,
+            bold: self.font.bold,
+            italic: self.font.italic,
+            color: argb_to_rgba(self.color1),
+            gradient: self.gradient.then(|| argb_to_rgba(self.color2)),
+            outline: self
+                .outline
+                .then(|| (self.outline_size, argb_to_rgba(self.outline_color))),
+            shadow: self.drop_shadow.then(|| {
+                (
+                    (self.shadow_offset_x, self.shadow_offset_y),
+                    argb_to_rgba(self.shadow_color),
+                )
+            }),
+            wrap_width: (self.word_wrap && self.custom_width > 0).then_some(self.custom_width),
+            align: self.align,
+        }
+    }
+}
+
+/// 读取文件的最后几行
+///
+/// # 参数
+///
+/// * `path` - 文件路径
+/// * `lines` - 行数
+///
+/// # 返回值
+///
+/// 返回 `Result<String>`，只读取文件末尾的一部分，忽略末尾的空行
+pub fn read_last_lines(path: &Path, lines: usize) -> Result<String> {
+    let mut file = File::open(path)?;
+    let length = file.metadata()?.len();
+    let start = length.saturating_sub(LOG_TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut bytes = vec![];
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    // 从文件中间开始读取时第一行可能不完整
+    let mut all: Vec<&str> = text.lines().skip(usize::from(start > 0)).collect();
+    while all.last().is_some_and(|line| line.trim().is_empty()) {
+        all.pop();
+    }
+
+    Ok(all[all.len().saturating_sub(lines)..].join("\n"))
+}
+
+/// 文字来源
+#[derive(Default)]
+pub struct TextSource {
+    settings: TextSettings,
+    /// 当前显示的文字
+    text: String,
+    /// 读取文件时的修改时间和长度
+    file_state: Option<(SystemTime, u64)>,
+    since_check: Duration,
+    /// 文字或样式变化后需要重新绘制
+    dirty: bool,
+    texture: Option<Texture>,
+}
+
+impl TextSource {
+    /// 文件变化时重新读取
+    fn reload_file(&mut self) -> Result<()> {
+        let path = PathBuf::from(&self.settings.text_file);
+        let metadata = fs::metadata(&path)?;
+        let state = (metadata.modified()?, metadata.len());
+        if self.file_state == Some(state) {
+            return Ok(());
+        }
+        self.file_state = Some(state);
+
+        let text = if self.settings.log_mode {
+            read_last_lines(&path, self.settings.log_lines as usize)?
+        } else {
+            String::from_utf8_lossy(&fs::read(&path)?).into_owned()
+        };
+        if text != self.text {
+            self.text = text;
+            self.dirty = true;
+        }
+
+        Ok(())
+    }
+}
+
+impl VideoSource for TextSource {
+    fn update(&mut self, settings: &Value) -> Result<()> {
+        let settings: TextSettings = serde_json::from_value(settings.clone())?;
+        if settings == self.settings && !self.dirty {
+            return Ok(());
+        }
+
+        if !settings.from_file {
+            self.text = settings.text.clone();
+        }
+        self.settings = settings;
+        self.file_state = None;
+        self.since_check = FILE_CHECK_INTERVAL;
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn tick(&mut self, context: &mut SourceContext, delta: Duration) -> Result<()> {
+        let mut result = Ok(());
+
+        self.since_check += delta;
+        if self.settings.from_file && self.since_check >= FILE_CHECK_INTERVAL {
+            self.since_check = Duration::ZERO;
+            result = self.reload_file();
+        }
+
+        if !self.dirty {
+            return result;
+        }
+        self.dirty = false;
+
+        let Some(image) = render_text(&self.text, &self.settings.style()) else {
+            self.texture = None;
+            return result;
+        };
+        let reuse = self.texture.as_ref().is_some_and(|texture| {
+            (texture.texture.width(), texture.texture.height()) == image.dimensions()
+        });
+        match &self.texture {
+            Some(texture) if reuse => texture.write(context.queue, &image)?,
+            _ => {
+                self.texture = Some(Texture::from_rgba(
+                    context.device,
+                    context.queue,
+                    &image,
+                    Some("text"),
+                    false,
+                )?)
+            }
+        }
+
+        result
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.texture.as_ref().map_or((0, 0), |texture| {
+            (texture.texture.width(), texture.texture.height())
+        })
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        self.texture.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("text-{}-{}.txt", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn last_lines_ignore_trailing_blank_lines() {
+        let path = temp_file("blank", "one\ntwo\nthree\n\n  \n\n");
+        assert_eq!(read_last_lines(&path, 2).unwrap(), "two\nthree");
+        assert_eq!(read_last_lines(&path, 10).unwrap(), "one\ntwo\nthree");
+        assert_eq!(read_last_lines(&path, 0).unwrap(), "");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn long_tail_drops_the_partial_first_line() {
+        // 每行 63 字节，读取范围的开始落在一行中间
+        let line = |i: usize| format!("line {:06} {}", i, "x".repeat(50));
+        let count = 5000;
+        let contents: String = (0..count).map(|i| line(i) + "\n").collect();
+        let path = temp_file("tail", &contents);
+
+        let start = contents.len() - LOG_TAIL_BYTES as usize;
+        assert_ne!(start % 63, 0);
+        let first = start / 63 + 1;
+        let text = read_last_lines(&path, count).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), count - first);
+        assert_eq!(lines[0], line(first));
+        assert_eq!(lines.last().unwrap(), &line(count - 1));
+
+        assert_eq!(read_last_lines(&path, 1).unwrap(), line(count - 1));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,954 @@
+/// V4L2 设备
+///
+/// 通过内核 ioctl 枚举格式、分辨率和帧率，使用内存映射的缓冲区采集。
+/// 设备节点上的系统调用经过 `DeviceIo`，测试时替换为模拟的驱动
+use std::{
+    ffi::CStr,
+    fs::{File, OpenOptions},
+    io,
+    os::{fd::AsRawFd, unix::fs::OpenOptionsExt},
+    path::PathBuf,
+};
+
+use anyhow::anyhow;
+
+use crate::{
+    sources::v4l2::{CameraDevice, CaptureDevice, PixelFormat, VideoMode},
+    Result,
+};
+
+const V4L2_CAP_VIDEO_CAPTURE: u32 = 0x0000_0001;
+const V4L2_CAP_STREAMING: u32 = 0x0400_0000;
+const V4L2_CAP_DEVICE_CAPS: u32 = 0x8000_0000;
+const V4L2_BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+const V4L2_MEMORY_MMAP: u32 = 1;
+const V4L2_FIELD_ANY: u32 = 0;
+const V4L2_FRMSIZE_TYPE_DISCRETE: u32 = 1;
+const V4L2_FRMIVAL_TYPE_DISCRETE: u32 = 1;
+
+/// 请求的缓冲区数量
+const BUFFER_COUNT: u32 = 4;
+
+/// 等待新帧的超时（毫秒）
+const POLL_TIMEOUT_MS: i32 = 100;
+
+/// 生成 FourCC
+pub const fn fourcc(code: &[u8; 4]) -> u32 {
+    code[0] as u32 | (code[1] as u32) << 8 | (code[2] as u32) << 16 | (code[3] as u32) << 24
+}
+
+/// 生成 ioctl 请求码，同内核的 `_IOC` 宏
+const fn ioc(dir: u32, nr: u32, size: usize) -> u32 {
+    dir << 30 | (size as u32) << 16 | (b'V' as u32) << 8 | nr
+}
+
+/// `struct v4l2_capability`
+#[repr(C)]
+struct Capability {
+    driver: [u8; 16],
+    card: [u8; 32],
+    bus_info: [u8; 32],
+    version: u32,
+    capabilities: u32,
+    device_caps: u32,
+    reserved: [u32; 3],
+}
+
+/// `struct v4l2_fmtdesc`
+#[repr(C)]
+struct FmtDesc {
+    index: u32,
+    type_: u32,
+    flags: u32,
+    description: [u8; 32],
+    pixelformat: u32,
+    mbus_code: u32,
+    reserved: [u32; 3],
+}
+
+/// `struct v4l2_frmsizeenum`，联合体展开为步进形式，离散尺寸使用前两项
+#[repr(C)]
+struct FrmSizeEnum {
+    index: u32,
+    pixel_format: u32,
+    type_: u32,
+    min_width: u32,
+    max_width: u32,
+    step_width: u32,
+    min_height: u32,
+    max_height: u32,
+    step_height: u32,
+    reserved: [u32; 2],
+}
+
+/// `struct v4l2_fract`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Fract {
+    numerator: u32,
+    denominator: u32,
+}
+
+/// `struct v4l2_frmivalenum`，联合体展开为步进形式，离散间隔使用 `min`
+#[repr(C)]
+struct FrmIvalEnum {
+    index: u32,
+    pixel_format: u32,
+    width: u32,
+    height: u32,
+    type_: u32,
+    min: Fract,
+    max: Fract,
+    step: Fract,
+    reserved: [u32; 2],
+}
+
+/// `struct v4l2_pix_format`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PixFormat {
+    width: u32,
+    height: u32,
+    pixelformat: u32,
+    field: u32,
+    bytesperline: u32,
+    sizeimage: u32,
+    colorspace: u32,
+    priv_: u32,
+    flags: u32,
+    ycbcr_enc: u32,
+    quantization: u32,
+    xfer_func: u32,
+}
+
+/// `struct v4l2_format` 中的联合体，内核中包含指针，按 8 字节对齐
+#[repr(C)]
+union FormatUnion {
+    pix: PixFormat,
+    raw_data: [u8; 200],
+    _align: [u64; 25],
+}
+
+/// `struct v4l2_format`
+#[repr(C)]
+struct Format {
+    type_: u32,
+    fmt: FormatUnion,
+}
+
+/// `struct v4l2_captureparm`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CaptureParm {
+    capability: u32,
+    capturemode: u32,
+    timeperframe: Fract,
+    extendedmode: u32,
+    readbuffers: u32,
+    reserved: [u32; 4],
+}
+
+/// `struct v4l2_streamparm` 中的联合体
+#[repr(C)]
+union StreamParmUnion {
+    capture: CaptureParm,
+    raw_data: [u8; 200],
+}
+
+/// `struct v4l2_streamparm`
+#[repr(C)]
+struct StreamParm {
+    type_: u32,
+    parm: StreamParmUnion,
+}
+
+/// `struct v4l2_requestbuffers`
+#[repr(C)]
+struct RequestBuffers {
+    count: u32,
+    type_: u32,
+    memory: u32,
+    capabilities: u32,
+    flags: u8,
+    reserved: [u8; 3],
+}
+
+/// `struct v4l2_timecode`
+#[repr(C)]
+struct Timecode {
+    type_: u32,
+    flags: u32,
+    frames: u8,
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    userbits: [u8; 4],
+}
+
+/// `struct v4l2_buffer`，联合体 `m` 按 64 位的 `offset` 和填充表示
+#[repr(C)]
+struct Buffer {
+    index: u32,
+    type_: u32,
+    bytesused: u32,
+    flags: u32,
+    field: u32,
+    timestamp: libc::timeval,
+    timecode: Timecode,
+    sequence: u32,
+    memory: u32,
+    offset: u32,
+    _m_pad: u32,
+    length: u32,
+    reserved2: u32,
+    request_fd: i32,
+}
+
+const VIDIOC_QUERYCAP: u32 = ioc(2, 0, std::mem::size_of::<Capability>());
+const VIDIOC_ENUM_FMT: u32 = ioc(3, 2, std::mem::size_of::<FmtDesc>());
+const VIDIOC_S_FMT: u32 = ioc(3, 5, std::mem::size_of::<Format>());
+const VIDIOC_REQBUFS: u32 = ioc(3, 8, std::mem::size_of::<RequestBuffers>());
+const VIDIOC_QUERYBUF: u32 = ioc(3, 9, std::mem::size_of::<Buffer>());
+const VIDIOC_QBUF: u32 = ioc(3, 15, std::mem::size_of::<Buffer>());
+const VIDIOC_DQBUF: u32 = ioc(3, 17, std::mem::size_of::<Buffer>());
+const VIDIOC_STREAMON: u32 = ioc(1, 18, std::mem::size_of::<i32>());
+const VIDIOC_STREAMOFF: u32 = ioc(1, 19, std::mem::size_of::<i32>());
+const VIDIOC_S_PARM: u32 = ioc(3, 22, std::mem::size_of::<StreamParm>());
+const VIDIOC_ENUM_FRAMESIZES: u32 = ioc(3, 74, std::mem::size_of::<FrmSizeEnum>());
+const VIDIOC_ENUM_FRAMEINTERVALS: u32 = ioc(3, 75, std::mem::size_of::<FrmIvalEnum>());
+
+/// 设备节点上的系统调用
+trait DeviceIo: Send {
+    /// 执行 ioctl，`arg` 指向请求码对应的结构体
+    fn ioctl(&self, request: u32, arg: *mut libc::c_void) -> io::Result<()>;
+
+    /// 映射驱动的缓冲区
+    ///
+    /// # 参数
+    ///
+    /// * `length` - 缓冲区长度
+    /// * `offset` - `VIDIOC_QUERYBUF` 返回的偏移
+    fn mmap(&self, length: usize, offset: u32) -> io::Result<*mut libc::c_void>;
+
+    /// 解除缓冲区映射
+    fn munmap(&self, ptr: *mut libc::c_void, length: usize);
+
+    /// 等待新帧
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `io::Result<i16>`，表示 `poll` 的 `revents`，超时为 0
+    fn poll(&self, timeout_ms: i32) -> io::Result<i16>;
+}
+
+impl DeviceIo for File {
+    fn ioctl(&self, request: u32, arg: *mut libc::c_void) -> io::Result<()> {
+        if unsafe { libc::ioctl(self.as_raw_fd(), request as _, arg) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    fn mmap(&self, length: usize, offset: u32) -> io::Result<*mut libc::c_void> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                length,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                self.as_raw_fd(),
+                offset as libc::off_t,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(ptr)
+    }
+
+    fn munmap(&self, ptr: *mut libc::c_void, length: usize) {
+        unsafe { libc::munmap(ptr, length) };
+    }
+
+    fn poll(&self, timeout_ms: i32) -> io::Result<i16> {
+        let mut fd = libc::pollfd {
+            fd: self.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if unsafe { libc::poll(&mut fd, 1, timeout_ms) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(fd.revents)
+    }
+}
+
+/// 执行 ioctl，失败时返回系统错误
+fn ioctl<T>(io: &dyn DeviceIo, request: u32, arg: &mut T) -> io::Result<()> {
+    io.ioctl(request, arg as *mut T as *mut libc::c_void)
+}
+
+/// 执行枚举类 ioctl，枚举结束时返回 `false`
+fn ioctl_enum<T>(io: &dyn DeviceIo, request: u32, arg: &mut T) -> Result<bool> {
+    match ioctl(io, request, arg) {
+        Ok(()) => Ok(true),
+        Err(e) if e.raw_os_error() == Some(libc::EINVAL) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 读取以 0 结尾的字符串
+fn c_string(bytes: &[u8]) -> String {
+    CStr::from_bytes_until_nul(bytes)
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// 查询设备能力
+fn query_capability(io: &dyn DeviceIo) -> Result<Capability> {
+    let mut cap: Capability = unsafe { std::mem::zeroed() };
+    ioctl(io, VIDIOC_QUERYCAP, &mut cap)?;
+
+    Ok(cap)
+}
+
+/// 设备是否支持流式视频采集
+fn is_capture(cap: &Capability) -> bool {
+    let caps = if cap.capabilities & V4L2_CAP_DEVICE_CAPS != 0 {
+        cap.device_caps
+    } else {
+        cap.capabilities
+    };
+
+    caps & V4L2_CAP_VIDEO_CAPTURE != 0 && caps & V4L2_CAP_STREAMING != 0
+}
+
+/// 打开设备节点
+fn open_file(path: &str) -> Result<File> {
+    Ok(OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)?)
+}
+
+/// 列出支持视频采集的 V4L2 设备
+pub fn list_devices() -> Result<Vec<CameraDevice>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir("/dev")?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("video"))
+        })
+        .collect();
+    paths.sort();
+
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| {
+            let id = path.to_str()?.to_string();
+            let file = open_file(&id).ok()?;
+            let cap = query_capability(&file).ok()?;
+            is_capture(&cap).then(|| CameraDevice {
+                id,
+                name: c_string(&cap.card),
+                bus_info: c_string(&cap.bus_info),
+            })
+        })
+        .collect())
+}
+
+/// 内存映射的缓冲区，停止采集时解除映射
+struct MappedBuffer {
+    ptr: *mut libc::c_void,
+    length: usize,
+}
+
+/// 打开的 V4L2 设备
+pub struct V4l2Device {
+    io: Box<dyn DeviceIo>,
+    bus_info: String,
+    buffers: Vec<MappedBuffer>,
+    streaming: bool,
+}
+
+// 映射的缓冲区只在采集线程中访问
+unsafe impl Send for V4l2Device {}
+
+impl V4l2Device {
+    /// 打开设备
+    ///
+    /// # 参数
+    ///
+    /// * `path` - 设备节点，如 `/dev/video0`
+    pub fn open(path: &str) -> Result<Self> {
+        Self::with_io(Box::new(open_file(path)?), path)
+    }
+
+    /// 在已打开的设备节点上创建设备
+    fn with_io(io: Box<dyn DeviceIo>, path: &str) -> Result<Self> {
+        let cap = query_capability(io.as_ref())?;
+        if !is_capture(&cap) {
+            return Err(anyhow!("{} is not a video capture device", path));
+        }
+
+        Ok(Self {
+            io,
+            bus_info: c_string(&cap.bus_info),
+            buffers: Vec::new(),
+            streaming: false,
+        })
+    }
+
+    /// 设备的总线信息
+    pub fn bus_info(&self) -> &str {
+        &self.bus_info
+    }
+
+    /// 枚举格式支持的分辨率，连续或步进的范围只取最大和最小分辨率
+    fn frame_sizes(&self, format: u32) -> Result<Vec<(u32, u32)>> {
+        let mut sizes = Vec::new();
+        for index in 0.. {
+            let mut size: FrmSizeEnum = unsafe { std::mem::zeroed() };
+            size.index = index;
+            size.pixel_format = format;
+            if !ioctl_enum(self.io.as_ref(), VIDIOC_ENUM_FRAMESIZES, &mut size)? {
+                break;
+            }
+
+            if size.type_ == V4L2_FRMSIZE_TYPE_DISCRETE {
+                // 离散尺寸的宽高在联合体的前两项
+                sizes.push((size.min_width, size.max_width));
+            } else {
+                sizes.push((size.max_width, size.max_height));
+                sizes.push((size.min_width, size.min_height));
+                break;
+            }
+        }
+
+        Ok(sizes)
+    }
+
+    /// 枚举分辨率支持的帧间隔，连续或步进的范围只取最短和最长间隔
+    fn frame_intervals(&self, format: u32, width: u32, height: u32) -> Result<Vec<Fract>> {
+        let mut intervals = Vec::new();
+        for index in 0.. {
+            let mut interval: FrmIvalEnum = unsafe { std::mem::zeroed() };
+            interval.index = index;
+            interval.pixel_format = format;
+            interval.width = width;
+            interval.height = height;
+            if !ioctl_enum(self.io.as_ref(), VIDIOC_ENUM_FRAMEINTERVALS, &mut interval)? {
+                break;
+            }
+
+            intervals.push(interval.min);
+            if interval.type_ != V4L2_FRMIVAL_TYPE_DISCRETE {
+                intervals.push(interval.max);
+                break;
+            }
+        }
+
+        Ok(intervals)
+    }
+
+    /// 停止采集并释放缓冲区
+    fn stop(&mut self) {
+        if self.streaming {
+            let mut type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE as i32;
+            let _ = ioctl(self.io.as_ref(), VIDIOC_STREAMOFF, &mut type_);
+            self.streaming = false;
+        }
+        for buffer in self.buffers.drain(..) {
+            self.io.munmap(buffer.ptr, buffer.length);
+        }
+
+        let mut request: RequestBuffers = unsafe { std::mem::zeroed() };
+        request.type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        request.memory = V4L2_MEMORY_MMAP;
+        let _ = ioctl(self.io.as_ref(), VIDIOC_REQBUFS, &mut request);
+    }
+
+    /// 把缓冲区交给驱动填充
+    fn queue_buffer(&self, index: u32) -> Result<()> {
+        let mut buffer: Buffer = unsafe { std::mem::zeroed() };
+        buffer.type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        buffer.memory = V4L2_MEMORY_MMAP;
+        buffer.index = index;
+        ioctl(self.io.as_ref(), VIDIOC_QBUF, &mut buffer)?;
+
+        Ok(())
+    }
+}
+
+impl CaptureDevice for V4l2Device {
+    fn modes(&self) -> Result<Vec<VideoMode>> {
+        let mut modes = Vec::new();
+        for index in 0.. {
+            let mut desc: FmtDesc = unsafe { std::mem::zeroed() };
+            desc.index = index;
+            desc.type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+            if !ioctl_enum(self.io.as_ref(), VIDIOC_ENUM_FMT, &mut desc)? {
+                break;
+            }
+            let Some(format) = PixelFormat::from_fourcc(desc.pixelformat) else {
+                continue;
+            };
+
+            for (width, height) in self.frame_sizes(desc.pixelformat)? {
+                for interval in self.frame_intervals(desc.pixelformat, width, height)? {
+                    // 帧率是帧间隔的倒数
+                    modes.push(VideoMode {
+                        format,
+                        width,
+                        height,
+                        fps_num: interval.denominator,
+                        fps_den: interval.numerator,
+                    });
+                }
+            }
+        }
+
+        Ok(modes)
+    }
+
+    fn start(&mut self, mode: &VideoMode) -> Result<VideoMode> {
+        self.stop();
+
+        let mut format: Format = unsafe { std::mem::zeroed() };
+        format.type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        format.fmt.pix = PixFormat {
+            width: mode.width,
+            height: mode.height,
+            pixelformat: mode.format.fourcc(),
+            field: V4L2_FIELD_ANY,
+            ..unsafe { std::mem::zeroed() }
+        };
+        ioctl(self.io.as_ref(), VIDIOC_S_FMT, &mut format)
+            .map_err(|e| anyhow!("VIDIOC_S_FMT failed: {}", e))?;
+        // 驱动可能调整分辨率
+        let pix = unsafe { format.fmt.pix };
+        let format = PixelFormat::from_fourcc(pix.pixelformat).ok_or(anyhow!(
+            "video device changed pixel format: {:#x}",
+            pix.pixelformat
+        ))?;
+
+        let mut parm: StreamParm = unsafe { std::mem::zeroed() };
+        parm.type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        parm.parm.capture.timeperframe = Fract {
+            numerator: mode.fps_den,
+            denominator: mode.fps_num,
+        };
+        // 部分设备不支持设置帧率，使用驱动的默认帧率
+        let interval = match ioctl(self.io.as_ref(), VIDIOC_S_PARM, &mut parm) {
+            Ok(()) => unsafe { parm.parm.capture.timeperframe },
+            Err(_) => Fract {
+                numerator: mode.fps_den,
+                denominator: mode.fps_num,
+            },
+        };
+
+        let mut request: RequestBuffers = unsafe { std::mem::zeroed() };
+        request.count = BUFFER_COUNT;
+        request.type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        request.memory = V4L2_MEMORY_MMAP;
+        ioctl(self.io.as_ref(), VIDIOC_REQBUFS, &mut request)
+            .map_err(|e| anyhow!("VIDIOC_REQBUFS failed: {}", e))?;
+
+        for index in 0..request.count {
+            let mut buffer: Buffer = unsafe { std::mem::zeroed() };
+            buffer.type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+            buffer.memory = V4L2_MEMORY_MMAP;
+            buffer.index = index;
+            ioctl(self.io.as_ref(), VIDIOC_QUERYBUF, &mut buffer)?;
+
+            let ptr = self.io.mmap(buffer.length as usize, buffer.offset)?;
+            self.buffers.push(MappedBuffer {
+                ptr,
+                length: buffer.length as usize,
+            });
+            self.queue_buffer(index)?;
+        }
+
+        let mut type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE as i32;
+        ioctl(self.io.as_ref(), VIDIOC_STREAMON, &mut type_)
+            .map_err(|e| anyhow!("VIDIOC_STREAMON failed: {}", e))?;
+        self.streaming = true;
+
+        Ok(VideoMode {
+            format,
+            width: pix.width,
+            height: pix.height,
+            fps_num: interval.denominator,
+            fps_den: interval.numerator,
+        })
+    }
+
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        if !self.streaming {
+            return Err(anyhow!("video device is not streaming"));
+        }
+
+        let revents = self.io.poll(POLL_TIMEOUT_MS)?;
+        // 设备拔出后返回 POLLERR
+        if revents & (libc::POLLERR | libc::POLLHUP) != 0 {
+            return Err(anyhow!("video device disconnected"));
+        }
+        if revents & libc::POLLIN == 0 {
+            return Ok(None);
+        }
+
+        let mut buffer: Buffer = unsafe { std::mem::zeroed() };
+        buffer.type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        buffer.memory = V4L2_MEMORY_MMAP;
+        match ioctl(self.io.as_ref(), VIDIOC_DQBUF, &mut buffer) {
+            Ok(()) => {}
+            Err(e) if e.raw_os_error() == Some(libc::EAGAIN) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+
+        let mapped = self
+            .buffers
+            .get(buffer.index as usize)
+            .ok_or(anyhow!("invalid buffer index: {}", buffer.index))?;
+        let used = (buffer.bytesused as usize).min(mapped.length);
+        let data = unsafe { std::slice::from_raw_parts(mapped.ptr as *const u8, used) }.to_vec();
+        self.queue_buffer(buffer.index)?;
+
+        Ok(Some(data))
+    }
+}
+
+impl Drop for V4l2Device {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::sources::v4l2::decode_frame;
+
+    const V4L2_FRMSIZE_TYPE_STEPWISE: u32 = 3;
+    const V4L2_FRMIVAL_TYPE_CONTINUOUS: u32 = 2;
+
+    /// 模拟驱动的缓冲区偏移间隔
+    const PAGE: u32 = 0x1000;
+
+    /// 模拟驱动的状态
+    ///
+    /// YUYV 支持 640x480 和 320x240，帧率 30 和 15；MJPEG 支持 160x120 到 1280x720 的步进尺寸和连续帧率；
+    /// 另有一个不支持的 H264 格式
+    #[derive(Default)]
+    struct MockState {
+        capabilities: u32,
+        width: u32,
+        height: u32,
+        pixelformat: u32,
+        interval: (u32, u32),
+        buffers: Vec<Vec<u8>>,
+        queued: VecDeque<u32>,
+        streaming: bool,
+        frames: u8,
+        mapped: usize,
+        unplugged: bool,
+    }
+
+    struct MockDevice(Arc<Mutex<MockState>>);
+
+    fn err(code: i32) -> io::Result<()> {
+        Err(io::Error::from_raw_os_error(code))
+    }
+
+    /// 把 ioctl 参数转换为请求码对应的结构体
+    fn arg<'a, T>(arg: *mut libc::c_void) -> &'a mut T {
+        unsafe { &mut *(arg as *mut T) }
+    }
+
+    impl DeviceIo for MockDevice {
+        fn ioctl(&self, request: u32, ptr: *mut libc::c_void) -> io::Result<()> {
+            let mut state = self.0.lock().unwrap();
+            let formats = [fourcc(b"YUYV"), fourcc(b"H264"), fourcc(b"MJPG")];
+
+            match request {
+                VIDIOC_QUERYCAP => {
+                    let cap = arg::<Capability>(ptr);
+                    cap.card[..11].copy_from_slice(b"Mock Camera");
+                    cap.bus_info[..10].copy_from_slice(b"usb-mock-1");
+                    cap.capabilities = state.capabilities | V4L2_CAP_DEVICE_CAPS;
+                    cap.device_caps = state.capabilities;
+                }
+                VIDIOC_ENUM_FMT => {
+                    let desc = arg::<FmtDesc>(ptr);
+                    match formats.get(desc.index as usize) {
+                        Some(format) => desc.pixelformat = *format,
+                        None => return err(libc::EINVAL),
+                    }
+                }
+                VIDIOC_ENUM_FRAMESIZES => {
+                    let size = arg::<FrmSizeEnum>(ptr);
+                    if size.pixel_format == fourcc(b"YUYV") {
+                        let (width, height) = match size.index {
+                            0 => (640, 480),
+                            1 => (320, 240),
+                            _ => return err(libc::EINVAL),
+                        };
+                        size.type_ = V4L2_FRMSIZE_TYPE_DISCRETE;
+                        size.min_width = width;
+                        size.max_width = height;
+                    } else if size.index == 0 {
+                        size.type_ = V4L2_FRMSIZE_TYPE_STEPWISE;
+                        (size.min_width, size.max_width, size.step_width) = (160, 1280, 16);
+                        (size.min_height, size.max_height, size.step_height) = (120, 720, 8);
+                    } else {
+                        return err(libc::EINVAL);
+                    }
+                }
+                VIDIOC_ENUM_FRAMEINTERVALS => {
+                    let interval = arg::<FrmIvalEnum>(ptr);
+                    if interval.pixel_format == fourcc(b"YUYV") {
+                        interval.type_ = V4L2_FRMIVAL_TYPE_DISCRETE;
+                        interval.min = match interval.index {
+                            0 => Fract {
+                                numerator: 1,
+                                denominator: 30,
+                            },
+                            1 => Fract {
+                                numerator: 1,
+                                denominator: 15,
+                            },
+                            _ => return err(libc::EINVAL),
+                        };
+                    } else if interval.index == 0 {
+                        interval.type_ = V4L2_FRMIVAL_TYPE_CONTINUOUS;
+                        interval.min = Fract {
+                            numerator: 1,
+                            denominator: 60,
+                        };
+                        interval.max = Fract {
+                            numerator: 1,
+                            denominator: 5,
+                        };
+                    } else {
+                        return err(libc::EINVAL);
+                    }
+                }
+                VIDIOC_S_FMT => {
+                    if state.streaming {
+                        return err(libc::EBUSY);
+                    }
+                    // 驱动把分辨率限制在支持的范围内，不支持的格式换成 YUYV
+                    let pix = unsafe { &mut arg::<Format>(ptr).fmt.pix };
+                    pix.pixelformat = fourcc(b"YUYV");
+                    pix.width = pix.width.min(640);
+                    pix.height = pix.height.min(480);
+                    pix.bytesperline = pix.width * 2;
+                    pix.sizeimage = pix.bytesperline * pix.height;
+                    (state.width, state.height) = (pix.width, pix.height);
+                    state.pixelformat = pix.pixelformat;
+                }
+                VIDIOC_S_PARM => {
+                    let capture = unsafe { &mut arg::<StreamParm>(ptr).parm.capture };
+                    // 最高 30 帧
+                    if capture.timeperframe.denominator > 30 * capture.timeperframe.numerator {
+                        capture.timeperframe = Fract {
+                            numerator: 1,
+                            denominator: 30,
+                        };
+                    }
+                    state.interval = (
+                        capture.timeperframe.numerator,
+                        capture.timeperframe.denominator,
+                    );
+                }
+                VIDIOC_REQBUFS => {
+                    let request = arg::<RequestBuffers>(ptr);
+                    if state.mapped > 0 && request.count > 0 {
+                        return err(libc::EBUSY);
+                    }
+                    // 驱动只给两个缓冲区
+                    request.count = request.count.min(2);
+                    let size = (state.width * state.height * 2) as usize;
+                    state.buffers = vec![vec![0; size]; request.count as usize];
+                    state.queued.clear();
+                }
+                VIDIOC_QUERYBUF => {
+                    let buffer = arg::<Buffer>(ptr);
+                    let Some(data) = state.buffers.get(buffer.index as usize) else {
+                        return err(libc::EINVAL);
+                    };
+                    buffer.length = data.len() as u32;
+                    buffer.offset = buffer.index * PAGE;
+                }
+                VIDIOC_QBUF => {
+                    let index = arg::<Buffer>(ptr).index;
+                    if index as usize >= state.buffers.len() || state.queued.contains(&index) {
+                        return err(libc::EINVAL);
+                    }
+                    state.queued.push_back(index);
+                }
+                VIDIOC_DQBUF => {
+                    let Some(index) = state.queued.pop_front() else {
+                        return err(libc::EAGAIN);
+                    };
+                    // 每帧的亮度递增，色度为中性
+                    state.frames = state.frames.wrapping_add(1);
+                    let luma = 16 + state.frames * 10;
+                    let data = &mut state.buffers[index as usize];
+                    for pixel in data.chunks_exact_mut(4) {
+                        pixel.copy_from_slice(&[luma, 128, luma, 128]);
+                    }
+                    let buffer = arg::<Buffer>(ptr);
+                    buffer.index = index;
+                    buffer.bytesused = data.len() as u32;
+                }
+                VIDIOC_STREAMON => state.streaming = true,
+                VIDIOC_STREAMOFF => state.streaming = false,
+                _ => return err(libc::ENOTTY),
+            }
+
+            Ok(())
+        }
+
+        fn mmap(&self, length: usize, offset: u32) -> io::Result<*mut libc::c_void> {
+            let mut state = self.0.lock().unwrap();
+            let data = state
+                .buffers
+                .get_mut((offset / PAGE) as usize)
+                .filter(|data| data.len() == length)
+                .ok_or(io::Error::from_raw_os_error(libc::EINVAL))?;
+            let ptr = data.as_mut_ptr() as *mut libc::c_void;
+            state.mapped += 1;
+
+            Ok(ptr)
+        }
+
+        fn munmap(&self, _ptr: *mut libc::c_void, _length: usize) {
+            self.0.lock().unwrap().mapped -= 1;
+        }
+
+        fn poll(&self, _timeout_ms: i32) -> io::Result<i16> {
+            let state = self.0.lock().unwrap();
+            Ok(if state.unplugged {
+                libc::POLLERR
+            } else if state.streaming && !state.queued.is_empty() {
+                libc::POLLIN
+            } else {
+                0
+            })
+        }
+    }
+
+    fn mock_device() -> (V4l2Device, Arc<Mutex<MockState>>) {
+        let state = Arc::new(Mutex::new(MockState {
+            capabilities: V4L2_CAP_VIDEO_CAPTURE | V4L2_CAP_STREAMING,
+            ..Default::default()
+        }));
+        let device =
+            V4l2Device::with_io(Box::new(MockDevice(state.clone())), "/dev/video9").unwrap();
+
+        (device, state)
+    }
+
+    fn mode(format: PixelFormat, width: u32, height: u32, fps: u32) -> VideoMode {
+        VideoMode {
+            format,
+            width,
+            height,
+            fps_num: fps,
+            fps_den: 1,
+        }
+    }
+
+    #[test]
+    fn capability_is_checked_on_open() {
+        let (device, _) = mock_device();
+        assert_eq!(device.bus_info(), "usb-mock-1");
+
+        let state = Arc::new(Mutex::new(MockState {
+            capabilities: V4L2_CAP_VIDEO_CAPTURE,
+            ..Default::default()
+        }));
+        assert!(V4l2Device::with_io(Box::new(MockDevice(state)), "/dev/video9").is_err());
+    }
+
+    #[test]
+    fn modes_are_enumerated() {
+        let (device, _) = mock_device();
+        let modes = device.modes().unwrap();
+
+        assert_eq!(
+            modes,
+            vec![
+                mode(PixelFormat::Yuyv, 640, 480, 30),
+                mode(PixelFormat::Yuyv, 640, 480, 15),
+                mode(PixelFormat::Yuyv, 320, 240, 30),
+                mode(PixelFormat::Yuyv, 320, 240, 15),
+                mode(PixelFormat::Mjpeg, 1280, 720, 60),
+                mode(PixelFormat::Mjpeg, 1280, 720, 5),
+                mode(PixelFormat::Mjpeg, 160, 120, 60),
+                mode(PixelFormat::Mjpeg, 160, 120, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn start_uses_the_negotiated_mode() {
+        let (mut device, state) = mock_device();
+        assert!(device.read_frame().is_err());
+
+        let started = device
+            .start(&mode(PixelFormat::Mjpeg, 1280, 720, 60))
+            .unwrap();
+        assert_eq!(started, mode(PixelFormat::Yuyv, 640, 480, 30));
+        assert_eq!(state.lock().unwrap().mapped, 2);
+
+        // 读取的帧数超过缓冲区数量，缓冲区被重新交给驱动
+        for frame in 1..=5u8 {
+            let data = device.read_frame().unwrap().unwrap();
+            assert_eq!(data.len(), 640 * 480 * 2);
+            let image = decode_frame(&started, &data).unwrap();
+            assert_eq!(image.dimensions(), (640, 480));
+            let expected = ((16 + frame * 10 - 16) as f32 / 219. * 255.).round() as u8;
+            assert!(image.get_pixel(5, 5)[0].abs_diff(expected) <= 1);
+        }
+
+        // 重新开始时先释放旧的缓冲区
+        device
+            .start(&mode(PixelFormat::Yuyv, 320, 240, 15))
+            .unwrap();
+        assert_eq!(state.lock().unwrap().mapped, 2);
+
+        drop(device);
+        let state = state.lock().unwrap();
+        assert_eq!(state.mapped, 0);
+        assert!(!state.streaming);
+        assert!(state.buffers.is_empty());
+    }
+
+    #[test]
+    fn unplugged_device_reports_an_error() {
+        let (mut device, state) = mock_device();
+        device
+            .start(&mode(PixelFormat::Yuyv, 640, 480, 30))
+            .unwrap();
+        assert!(device.read_frame().unwrap().is_some());
+
+        state.lock().unwrap().queued.clear();
+        assert!(device.read_frame().unwrap().is_none());
+
+        state.lock().unwrap().unplugged = true;
+        assert!(device.read_frame().is_err());
+    }
+}
@@ -0,0 +1,616 @@
+/// 视频采集设备来源模块
+///
+/// 采集线程从 V4L2 设备读取 MJPEG 或 YUYV 画面，解码为 RGBA 后交给来源上传到纹理。
+/// 设备断开后每秒重试，重新插入后按总线信息找回设备，即使设备节点编号变化
+pub mod device;
+pub mod test_device;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use image::{ImageFormat, Rgba, RgbaImage};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    graphics::texture::Texture,
+    media::color::{ColorParams, ColorRange, ColorSpace},
+    sources::{
+        upload_image,
+        v4l2::{device::V4l2Device, test_device::TestCamera},
+        SourceContext, VideoSource,
+    },
+    Result,
+};
+
+/// 测试摄像头 ID，输出移动的彩条，不需要设备
+pub const TEST_CAMERA: &str = "test:camera";
+
+/// 设备打开失败后重试的间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 没有指定帧率时优先选择的最高帧率
+const PREFERRED_FPS: f64 = 30.;
+
+/// 支持的像素格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PixelFormat {
+    Mjpeg,
+    Yuyv,
+}
+
+impl PixelFormat {
+    /// 格式的 FourCC
+    pub fn fourcc(&self) -> u32 {
+        match self {
+            Self::Mjpeg => device::fourcc(b"MJPG"),
+            Self::Yuyv => device::fourcc(b"YUYV"),
+        }
+    }
+
+    /// 从 FourCC 解析，不支持的格式返回 `None`
+    pub fn from_fourcc(fourcc: u32) -> Option<Self> {
+        [Self::Mjpeg, Self::Yuyv]
+            .into_iter()
+            .find(|format| format.fourcc() == fourcc)
+    }
+}
+
+/// 采集模式，由像素格式、分辨率和帧率组成
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoMode {
+    pub format: PixelFormat,
+    pub width: u32,
+    pub height: u32,
+    /// 帧率为 `fps_num / fps_den`
+    pub fps_num: u32,
+    pub fps_den: u32,
+}
+
+impl VideoMode {
+    /// 帧率
+    pub fn fps(&self) -> f64 {
+        self.fps_num as f64 / self.fps_den.max(1) as f64
+    }
+}
+
+/// 视频采集设备
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CameraDevice {
+    /// 设备节点，如 `/dev/video0`
+    pub id: String,
+    pub name: String,
+    /// 总线信息，设备重新插入后节点变化时用于找回设备
+    pub bus_info: String,
+}
+
+/// 打开的采集设备
+pub trait CaptureDevice: Send {
+    /// 设备支持的采集模式
+    fn modes(&self) -> Result<Vec<VideoMode>>;
+
+    /// 按指定模式开始采集
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<VideoMode>`，表示驱动实际使用的模式
+    fn start(&mut self, mode: &VideoMode) -> Result<VideoMode>;
+
+    /// 读取一帧，最多阻塞约 100 毫秒，没有新帧时返回 `None`
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>>;
+}
+
+/// 列出视频采集设备，不包括测试设备
+pub fn list_devices() -> Result<Vec<CameraDevice>> {
+    device::list_devices()
+}
+
+/// 列出设备支持的采集模式
+///
+/// # 参数
+///
+/// * `id` - 设备 ID
+pub fn list_modes(id: &str) -> Result<Vec<VideoMode>> {
+    open_device(id, "")?.modes()
+}
+
+/// 打开设备，设备节点不存在或已是其他设备时按总线信息查找
+fn open_device(id: &str, bus_info: &str) -> Result<Box<dyn CaptureDevice>> {
+    if id == TEST_CAMERA {
+        return Ok(Box::new(TestCamera::new()));
+    }
+
+    match V4l2Device::open(id) {
+        Ok(device) if bus_info.is_empty() || device.bus_info() == bus_info => {
+            return Ok(Box::new(device))
+        }
+        Ok(_) => {}
+        Err(e) if bus_info.is_empty() => return Err(e),
+        Err(_) => {}
+    }
+
+    let found = list_devices()?
+        .into_iter()
+        .find(|device| device.bus_info == bus_info)
+        .ok_or(anyhow!("video device not found: {}", id))?;
+    Ok(Box::new(V4l2Device::open(&found.id)?))
+}
+
+/// 选择采集模式
+///
+/// 设置的模式可用时直接使用，否则选择帧率不超过 30 的最大分辨率，同等条件下优先 MJPEG
+fn choose_mode(modes: &[VideoMode], settings: &V4l2Settings) -> Option<VideoMode> {
+    let requested = modes.iter().find(|mode| {
+        settings.format.is_none_or(|format| format == mode.format)
+            && (settings.width, settings.height) == (mode.width, mode.height)
+            && (settings.fps_num == 0
+                || mode.fps_num as u64 * settings.fps_den as u64
+                    == settings.fps_num as u64 * mode.fps_den as u64)
+    });
+    if let Some(mode) = requested {
+        return Some(*mode);
+    }
+
+    modes
+        .iter()
+        .filter(|mode| settings.format.is_none_or(|format| format == mode.format))
+        .max_by(|a, b| {
+            let key = |mode: &VideoMode| {
+                (
+                    mode.width * mode.height,
+                    mode.fps() <= PREFERRED_FPS + 0.01,
+                    mode.format == PixelFormat::Mjpeg,
+                )
+            };
+            key(a)
+                .cmp(&key(b))
+                .then(a.fps().total_cmp(&b.fps()).reverse())
+        })
+        .copied()
+}
+
+/// 把一帧数据解码为 RGBA
+///
+/// YUYV 按有限范围解码，高清分辨率使用 BT.709，标清使用 BT.601
+///
+/// # 参数
+///
+/// * `mode` - 采集模式
+/// * `data` - 一帧数据
+pub fn decode_frame(mode: &VideoMode, data: &[u8]) -> Result<RgbaImage> {
+    match mode.format {
+        PixelFormat::Mjpeg => {
+            Ok(image::load_from_memory_with_format(data, ImageFormat::Jpeg)?.to_rgba8())
+        }
+        PixelFormat::Yuyv => {
+            let (width, height) = (mode.width as usize, mode.height as usize);
+            let stride = width.div_ceil(2) * 4;
+            if data.len() < stride * height {
+                return Err(anyhow!(
+                    "incomplete YUYV frame: {} < {}",
+                    data.len(),
+                    stride * height
+                ));
+            }
+
+            let space = if mode.height >= 720 {
+                ColorSpace::Bt709
+            } else {
+                ColorSpace::Bt601
+            };
+            let params = ColorParams::new(space, ColorRange::Partial, 8);
+            Ok(RgbaImage::from_fn(mode.width, mode.height, |x, y| {
+                let i = y as usize * stride + x as usize / 2 * 4;
+                let luma = data[i + (x as usize % 2) * 2];
+                let [r, g, b] =
+                    params.yuv_to_rgb([luma as f32, data[i + 1] as f32, data[i + 3] as f32]);
+                Rgba([
+                    (r * 255.).round() as u8,
+                    (g * 255.).round() as u8,
+                    (b * 255.).round() as u8,
+                    255,
+                ])
+            }))
+        }
+    }
+}
+
+/// 采集线程共享的状态
+#[derive(Default)]
+struct Shared {
+    /// 最新一帧，来源更新时取走
+    frame: Option<RgbaImage>,
+    /// 设备打开失败或断开的原因
+    error: Option<String>,
+}
+
+/// 视频采集设备设置
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+struct V4l2Settings {
+    /// 设备 ID，保存在场景集合中
+    device_id: String,
+    /// 设备的总线信息，设备节点变化时用于找回设备
+    bus_info: String,
+    /// 像素格式，为空时自动选择
+    format: Option<PixelFormat>,
+    /// 分辨率，与设备支持的模式不符时自动选择
+    width: u32,
+    height: u32,
+    /// 帧率，`fps_num` 为 0 时自动选择
+    fps_num: u32,
+    fps_den: u32,
+}
+
+/// 运行中的采集线程
+struct CaptureThread {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+/// 视频采集设备来源
+#[derive(Default)]
+pub struct V4l2Source {
+    settings: Option<V4l2Settings>,
+    shared: Arc<Mutex<Shared>>,
+    capture: Option<CaptureThread>,
+    texture: Option<Texture>,
+}
+
+impl V4l2Source {
+    /// 启动采集线程
+    fn start(&mut self, settings: &V4l2Settings) -> Result<()> {
+        self.stop();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let settings = settings.clone();
+        let shared = self.shared.clone();
+        let thread = thread::Builder::new()
+            .name("v4l2-capture".to_string())
+            .spawn({
+                let stop = stop.clone();
+                move || run_capture(&settings, &shared, &stop, open_capture)
+            })?;
+        self.capture = Some(CaptureThread { stop, thread });
+
+        Ok(())
+    }
+
+    /// 停止采集线程
+    fn stop(&mut self) {
+        if let Some(capture) = self.capture.take() {
+            capture.stop.store(true, Ordering::Relaxed);
+            let _ = capture.thread.join();
+        }
+        *self.shared.lock().unwrap() = Shared::default();
+        self.texture = None;
+    }
+}
+
+impl VideoSource for V4l2Source {
+    fn update(&mut self, settings: &Value) -> Result<()> {
+        let settings: V4l2Settings = serde_json::from_value(settings.clone())?;
+        if settings.device_id.is_empty() {
+            return Err(anyhow!("video device not set"));
+        }
+        if self.settings.as_ref() != Some(&settings) {
+            self.start(&settings)?;
+        }
+        self.settings = Some(settings);
+
+        Ok(())
+    }
+
+    fn tick(&mut self, context: &mut SourceContext, _delta: Duration) -> Result<()> {
+        let (frame, error) = {
+            let mut shared = self.shared.lock().unwrap();
+            (shared.frame.take(), shared.error.take())
+        };
+        if let Some(error) = error {
+            self.texture = None;
+            return Err(anyhow!(error));
+        }
+
+        match frame {
+            Some(frame) => upload_image(&mut self.texture, context, &frame, "v4l2"),
+            None => Ok(()),
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.texture.as_ref().map_or((0, 0), |texture| {
+            (texture.texture.width(), texture.texture.height())
+        })
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        self.texture.as_ref()
+    }
+}
+
+impl Drop for V4l2Source {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 打开设备并开始采集
+fn open_capture(settings: &V4l2Settings) -> Result<(Box<dyn CaptureDevice>, VideoMode)> {
+    let mut device = open_device(&settings.device_id, &settings.bus_info)?;
+    let modes = device.modes()?;
+    let mode = choose_mode(&modes, settings).ok_or(anyhow!(
+        "video device has no MJPEG or YUYV mode: {}",
+        settings.device_id
+    ))?;
+    let mode = device.start(&mode)?;
+
+    Ok((device, mode))
+}
+
+/// 打开设备并开始采集的函数，测试时替换为模拟的设备
+type OpenCapture = fn(&V4l2Settings) -> Result<(Box<dyn CaptureDevice>, VideoMode)>;
+
+/// 采集线程，设备断开后重新打开
+fn run_capture(
+    settings: &V4l2Settings,
+    shared: &Mutex<Shared>,
+    stop: &AtomicBool,
+    open: OpenCapture,
+) {
+    let mut last_error = String::new();
+
+    while !stop.load(Ordering::Relaxed) {
+        let (mut device, mode) = match open(settings) {
+            Ok(opened) => opened,
+            Err(e) => {
+                report_error(shared, &mut last_error, e.to_string());
+                wait_retry(stop);
+                continue;
+            }
+        };
+        info!(
+            "video capture started: {} {:?} {}x{} {:.2} fps",
+            settings.device_id,
+            mode.format,
+            mode.width,
+            mode.height,
+            mode.fps()
+        );
+        last_error.clear();
+
+        while !stop.load(Ordering::Relaxed) {
+            let data = match device.read_frame() {
+                Ok(Some(data)) => data,
+                Ok(None) => continue,
+                Err(e) => {
+                    report_error(shared, &mut last_error, e.to_string());
+                    break;
+                }
+            };
+            // 个别帧损坏时跳过，不中断采集
+            match decode_frame(&mode, &data) {
+                Ok(image) => shared.lock().unwrap().frame = Some(image),
+                Err(e) => warn!("failed to decode video frame: {}", e),
+            }
+        }
+
+        drop(device);
+        wait_retry(stop);
+    }
+}
+
+/// 报告采集错误，设备拔出期间只报告一次
+fn report_error(shared: &Mutex<Shared>, last_error: &mut String, error: String) {
+    if error != *last_error {
+        warn!("video capture failed: {}", error);
+        shared.lock().unwrap().error = Some(error.clone());
+        *last_error = error;
+    }
+}
+
+/// 等待重试，期间响应停止
+fn wait_retry(stop: &AtomicBool) {
+    let until = Instant::now() + RETRY_INTERVAL;
+    while !stop.load(Ordering::Relaxed) && Instant::now() < until {
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    fn mode(format: PixelFormat, width: u32, height: u32, fps: u32) -> VideoMode {
+        VideoMode {
+            format,
+            width,
+            height,
+            fps_num: fps,
+            fps_den: 1,
+        }
+    }
+
+    #[test]
+    fn requested_mode_is_used_when_available() {
+        let modes = TestCamera::new().modes().unwrap();
+        let settings = V4l2Settings {
+            device_id: TEST_CAMERA.to_string(),
+            format: Some(PixelFormat::Mjpeg),
+            width: 1280,
+            height: 720,
+            fps_num: 60,
+            fps_den: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            choose_mode(&modes, &settings),
+            Some(mode(PixelFormat::Mjpeg, 1280, 720, 60))
+        );
+
+        // 没有指定帧率和格式时使用第一个尺寸相同的模式
+        let settings = V4l2Settings {
+            format: None,
+            fps_num: 0,
+            ..settings
+        };
+        assert_eq!(
+            choose_mode(&modes, &settings),
+            Some(mode(PixelFormat::Yuyv, 1280, 720, 10))
+        );
+    }
+
+    #[test]
+    fn fallback_prefers_30_fps_and_mjpeg() {
+        let settings = V4l2Settings {
+            width: 800,
+            height: 600,
+            ..Default::default()
+        };
+
+        // 最大分辨率优先
+        let modes = TestCamera::new().modes().unwrap();
+        assert_eq!(
+            choose_mode(&modes, &settings),
+            Some(mode(PixelFormat::Mjpeg, 1920, 1080, 30))
+        );
+
+        // 同一分辨率下帧率不超过 30 优先于 MJPEG，再优先 MJPEG
+        let modes = [
+            mode(PixelFormat::Mjpeg, 1280, 720, 60),
+            mode(PixelFormat::Yuyv, 1280, 720, 10),
+            mode(PixelFormat::Yuyv, 640, 480, 30),
+        ];
+        assert_eq!(
+            choose_mode(&modes, &settings),
+            Some(mode(PixelFormat::Yuyv, 1280, 720, 10))
+        );
+        let modes = [
+            mode(PixelFormat::Yuyv, 1280, 720, 30),
+            mode(PixelFormat::Mjpeg, 1280, 720, 30),
+            mode(PixelFormat::Mjpeg, 1280, 720, 60),
+        ];
+        assert_eq!(
+            choose_mode(&modes, &settings),
+            Some(mode(PixelFormat::Mjpeg, 1280, 720, 30))
+        );
+        // 都超过 30 时选择较低的帧率
+        let modes = [
+            mode(PixelFormat::Mjpeg, 1280, 720, 120),
+            mode(PixelFormat::Mjpeg, 1280, 720, 60),
+        ];
+        assert_eq!(
+            choose_mode(&modes, &settings),
+            Some(mode(PixelFormat::Mjpeg, 1280, 720, 60))
+        );
+
+        // 指定格式时只在该格式中选择
+        let settings = V4l2Settings {
+            format: Some(PixelFormat::Yuyv),
+            ..settings
+        };
+        assert_eq!(
+            choose_mode(&modes, &settings),
+            None,
+            "no YUYV mode available"
+        );
+    }
+
+    /// 模拟设备已打开的次数
+    static OPENED: AtomicUsize = AtomicUsize::new(0);
+
+    /// 第一次打开失败，第二次打开后读取 3 帧断开，之后正常采集
+    struct FlakyCamera {
+        camera: TestCamera,
+        /// 断开前还能读取的帧数
+        remaining: Option<usize>,
+    }
+
+    impl CaptureDevice for FlakyCamera {
+        fn modes(&self) -> Result<Vec<VideoMode>> {
+            self.camera.modes()
+        }
+
+        fn start(&mut self, mode: &VideoMode) -> Result<VideoMode> {
+            self.camera.start(mode)
+        }
+
+        fn read_frame(&mut self) -> Result<Option<Vec<u8>>> {
+            match &mut self.remaining {
+                Some(0) => Err(anyhow!("device disconnected")),
+                Some(remaining) => {
+                    *remaining -= 1;
+                    self.camera.read_frame()
+                }
+                None => self.camera.read_frame(),
+            }
+        }
+    }
+
+    fn open_flaky(_settings: &V4l2Settings) -> Result<(Box<dyn CaptureDevice>, VideoMode)> {
+        let remaining = match OPENED.fetch_add(1, Ordering::Relaxed) {
+            0 => return Err(anyhow!("device not found")),
+            1 => Some(3),
+            _ => None,
+        };
+        let mut camera = FlakyCamera {
+            camera: TestCamera::new(),
+            remaining,
+        };
+        let mode = camera.start(&mode(PixelFormat::Yuyv, 64, 48, 30))?;
+        Ok((Box::new(camera), mode))
+    }
+
+    #[test]
+    fn capture_reconnects_after_errors() {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::spawn({
+            let (shared, stop) = (shared.clone(), stop.clone());
+            move || run_capture(&V4l2Settings::default(), &shared, &stop, open_flaky)
+        });
+
+        // 依次记录错误和每段连续的帧数
+        let mut events: Vec<String> = vec![];
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while OPENED.load(Ordering::Relaxed) < 3 || events.last().is_none_or(|last| last != "frame")
+        {
+            assert!(
+                Instant::now() < deadline,
+                "capture did not recover: {events:?}"
+            );
+            let (frame, error) = {
+                let mut shared = shared.lock().unwrap();
+                (shared.frame.take(), shared.error.take())
+            };
+            if let Some(error) = error {
+                events.push(error);
+            }
+            if let Some(frame) = frame {
+                assert_eq!(frame.dimensions(), (64, 48));
+                if events.last().is_none_or(|last| last != "frame") {
+                    events.push("frame".to_string());
+                }
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        stop.store(true, Ordering::Relaxed);
+        thread.join().unwrap();
+
+        assert_eq!(
+            events,
+            ["device not found", "frame", "device disconnected", "frame"]
+        );
+        assert_eq!(OPENED.load(Ordering::Relaxed), 3);
+    }
+}
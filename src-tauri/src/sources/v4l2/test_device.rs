@@ -0,0 +1,243 @@
+/// 测试用的虚拟摄像头
+///
+/// 按帧率生成向左移动的彩条，支持 YUYV 和 MJPEG 两种格式，不依赖 V4L2 设备
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use image::{codecs::jpeg::JpegEncoder, Rgb, RgbImage};
+
+use crate::{
+    media::color::{ColorParams, ColorRange, ColorSpace},
+    sources::v4l2::{CaptureDevice, PixelFormat, VideoMode},
+    Result,
+};
+
+/// 彩条颜色，依次为白、黄、青、绿、品红、红、蓝、黑
+const BARS: [[u8; 3]; 8] = [
+    [255, 255, 255],
+    [255, 255, 0],
+    [0, 255, 255],
+    [0, 255, 0],
+    [255, 0, 255],
+    [255, 0, 0],
+    [0, 0, 255],
+    [0, 0, 0],
+];
+
+/// 每帧移动的像素数
+const SCROLL_SPEED: u32 = 4;
+
+/// JPEG 质量
+const JPEG_QUALITY: u8 = 90;
+
+/// 测试摄像头支持的模式
+const MODES: [VideoMode; 5] = [
+    VideoMode {
+        format: PixelFormat::Yuyv,
+        width: 640,
+        height: 480,
+        fps_num: 30,
+        fps_den: 1,
+    },
+    VideoMode {
+        format: PixelFormat::Yuyv,
+        width: 1280,
+        height: 720,
+        fps_num: 10,
+        fps_den: 1,
+    },
+    VideoMode {
+        format: PixelFormat::Mjpeg,
+        width: 1280,
+        height: 720,
+        fps_num: 30,
+        fps_den: 1,
+    },
+    VideoMode {
+        format: PixelFormat::Mjpeg,
+        width: 1280,
+        height: 720,
+        fps_num: 60,
+        fps_den: 1,
+    },
+    VideoMode {
+        format: PixelFormat::Mjpeg,
+        width: 1920,
+        height: 1080,
+        fps_num: 30,
+        fps_den: 1,
+    },
+];
+
+/// 测试摄像头
+pub struct TestCamera {
+    mode: Option<VideoMode>,
+    start: Instant,
+    /// 已生成的帧数
+    frames: u64,
+}
+
+impl TestCamera {
+    /// 创建测试摄像头
+    pub fn new() -> Self {
+        Self {
+            mode: None,
+            start: Instant::now(),
+            frames: 0,
+        }
+    }
+
+    /// 生成当前帧的彩条
+    fn pattern(&self, mode: &VideoMode) -> RgbImage {
+        let offset = (self.frames as u32).wrapping_mul(SCROLL_SPEED);
+        let bar_width = mode.width.div_ceil(BARS.len() as u32).max(1);
+        RgbImage::from_fn(mode.width, mode.height, |x, _| {
+            let bar = (x.wrapping_add(offset) / bar_width) as usize % BARS.len();
+            Rgb(BARS[bar])
+        })
+    }
+}
+
+impl Default for TestCamera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 把 RGB 图像编码为 YUYV，与 `decode_frame` 使用相同的色彩参数
+fn encode_yuyv(image: &RgbImage) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let space = if height >= 720 {
+        ColorSpace::Bt709
+    } else {
+        ColorSpace::Bt601
+    };
+    let params = ColorParams::new(space, ColorRange::Partial, 8);
+    let yuv = |x: u32, y: u32| {
+        let Rgb(rgb) = image.get_pixel(x.min(width - 1), y);
+        params.rgb_to_yuv(rgb.map(|value| value as f32 / 255.))
+    };
+
+    let mut data = Vec::with_capacity(width.div_ceil(2) as usize * 4 * height as usize);
+    for y in 0..height {
+        for x in (0..width).step_by(2) {
+            let [y0, u0, v0] = yuv(x, y);
+            let [y1, u1, v1] = yuv(x + 1, y);
+            data.extend_from_slice(&[
+                y0 as u8,
+                ((u0 + u1) / 2.).round() as u8,
+                y1 as u8,
+                ((v0 + v1) / 2.).round() as u8,
+            ]);
+        }
+    }
+
+    data
+}
+
+impl CaptureDevice for TestCamera {
+    fn modes(&self) -> Result<Vec<VideoMode>> {
+        Ok(MODES.to_vec())
+    }
+
+    fn start(&mut self, mode: &VideoMode) -> Result<VideoMode> {
+        self.mode = Some(*mode);
+        self.start = Instant::now();
+        self.frames = 0;
+
+        Ok(*mode)
+    }
+
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        let Some(mode) = self.mode else {
+            return Ok(None);
+        };
+
+        let due = self.start
+            + Duration::from_secs_f64(
+                self.frames as f64 * mode.fps_den as f64 / mode.fps_num as f64,
+            );
+        let now = Instant::now();
+        if due > now {
+            thread::sleep(due - now);
+        }
+
+        let image = self.pattern(&mode);
+        self.frames += 1;
+        let data = match mode.format {
+            PixelFormat::Yuyv => encode_yuyv(&image),
+            PixelFormat::Mjpeg => {
+                let mut data = Vec::new();
+                JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY).encode_image(&image)?;
+                data
+            }
+        };
+
+        Ok(Some(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::v4l2::decode_frame;
+
+    /// 解码后与彩条比较，跳过彩条边缘色度混合和压缩振铃的像素
+    fn assert_matches_bars(format: PixelFormat, tolerance: i32) {
+        let mode = MODES
+            .into_iter()
+            .find(|mode| mode.format == format)
+            .unwrap();
+        let mut camera = TestCamera::new();
+        camera.start(&mode).unwrap();
+        camera.frames = 3;
+        let expected = camera.pattern(&mode);
+        let data = camera.read_frame().unwrap().unwrap();
+        let decoded = decode_frame(&mode, &data).unwrap();
+        assert_eq!(decoded.dimensions(), (mode.width, mode.height));
+
+        let bar_width = mode.width.div_ceil(BARS.len() as u32);
+        let offset = 3 * SCROLL_SPEED;
+        for y in (0..mode.height).step_by(7) {
+            for x in 0..mode.width {
+                let position = (x + offset) % bar_width;
+                if position < 8 || position + 8 >= bar_width {
+                    continue;
+                }
+                let Rgb(want) = expected.get_pixel(x, y);
+                let got = decoded.get_pixel(x, y);
+                assert_eq!(got[3], 255);
+                for c in 0..3 {
+                    assert!(
+                        (got[c] as i32 - want[c] as i32).abs() <= tolerance,
+                        "{format:?} ({x}, {y}): {got:?} != {want:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn yuyv_frames_decode_to_the_bars() {
+        assert_matches_bars(PixelFormat::Yuyv, 2);
+    }
+
+    #[test]
+    fn mjpeg_frames_decode_to_the_bars() {
+        assert_matches_bars(PixelFormat::Mjpeg, 12);
+    }
+
+    #[test]
+    fn bars_scroll_between_frames() {
+        let mode = MODES[0];
+        let mut camera = TestCamera::new();
+        camera.start(&mode).unwrap();
+        let first = camera.pattern(&mode);
+        camera.frames = 1;
+        let second = camera.pattern(&mode);
+        assert_eq!(second.get_pixel(0, 0), first.get_pixel(SCROLL_SPEED, 0));
+        assert_eq!(first.get_pixel(0, 0), &Rgb(BARS[0]));
+    }
+}
@@ -0,0 +1,625 @@
+/// X11 采集模块
+///
+/// 屏幕采集通过 XSHM 共享内存读取根窗口，窗口采集通过 XComposite 把窗口重定向到离屏像素图后读取。
+/// 设置中的 `server` 可以指向 Xvfb 等虚拟显示（如 `:99`），在没有桌面的环境中也能采集
+pub mod xcomposite;
+pub mod xshm;
+
+use std::ptr;
+
+use anyhow::anyhow;
+use image::RgbaImage;
+use log::warn;
+use serde::Serialize;
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    protocol::{
+        randr::ConnectionExt as _,
+        shm::{self, ConnectionExt as _},
+        xfixes::{self, ConnectionExt as _},
+        xproto::{self, AtomEnum, ConnectionExt as _, ImageFormat, ImageOrder},
+    },
+    rust_connection::RustConnection,
+};
+
+use crate::Result;
+
+/// X11 屏幕，启用 RandR 时为各个显示器，否则为整个根窗口
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct X11Screen {
+    pub name: String,
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// X11 顶层窗口
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct X11Window {
+    pub id: u32,
+    pub title: String,
+    /// `WM_CLASS` 中的类名
+    pub class: String,
+}
+
+/// SysV 共享内存段，已附加到 X 服务器
+struct ShmSegment {
+    seg: shm::Seg,
+    id: i32,
+    addr: *mut u8,
+    size: usize,
+}
+
+// 共享内存段只在所属连接上使用
+unsafe impl Send for ShmSegment {}
+
+impl ShmSegment {
+    /// 创建共享内存段并附加到 X 服务器
+    fn new(conn: &RustConnection, size: usize) -> Result<Self> {
+        let id = unsafe { libc::shmget(libc::IPC_PRIVATE, size, libc::IPC_CREAT | 0o600) };
+        if id < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let addr = unsafe { libc::shmat(id, ptr::null(), 0) };
+        if addr as isize == -1 {
+            let err = std::io::Error::last_os_error();
+            unsafe { libc::shmctl(id, libc::IPC_RMID, ptr::null_mut()) };
+            return Err(err.into());
+        }
+
+        let mut segment = Self {
+            seg: 0,
+            id,
+            addr: addr as *mut u8,
+            size,
+        };
+        segment.seg = conn.generate_id()?;
+        conn.shm_attach(segment.seg, id as u32, false)?.check()?;
+
+        // 双方都附加后即可标记删除，进程退出时自动释放
+        unsafe { libc::shmctl(id, libc::IPC_RMID, ptr::null_mut()) };
+
+        Ok(segment)
+    }
+
+    fn data(&self, len: usize) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.addr, len.min(self.size)) }
+    }
+}
+
+impl Drop for ShmSegment {
+    fn drop(&mut self) {
+        unsafe {
+            libc::shmdt(self.addr as *const libc::c_void);
+            libc::shmctl(self.id, libc::IPC_RMID, ptr::null_mut());
+        }
+    }
+}
+
+/// X11 连接，读取图像时优先使用共享内存
+pub struct X11Connection {
+    pub conn: RustConnection,
+    pub root: xproto::Window,
+    /// 是否支持 XSHM，远程连接或服务器未启用扩展时为 `false`
+    use_shm: bool,
+    shm: Option<ShmSegment>,
+    /// 是否支持 XFixes，用于读取光标图像
+    xfixes: bool,
+}
+
+impl X11Connection {
+    /// 连接 X 服务器
+    ///
+    /// # 参数
+    ///
+    /// * `server` - X 服务器地址，如 `:99`，为空时使用 `DISPLAY` 环境变量
+    pub fn connect(server: &str) -> Result<Self> {
+        let (conn, screen) = x11rb::connect((!server.is_empty()).then_some(server))?;
+        let setup = conn.setup();
+        let root = setup.roots[screen].root;
+
+        // 只支持 32 位小端像素
+        let depth = setup.roots[screen].root_depth;
+        let supported = setup.image_byte_order == ImageOrder::LSB_FIRST
+            && setup
+                .pixmap_formats
+                .iter()
+                .any(|format| format.depth == depth && format.bits_per_pixel == 32);
+        if !supported {
+            return Err(anyhow!("unsupported X11 pixel format, depth {}", depth));
+        }
+
+        let use_shm = conn
+            .extension_information(shm::X11_EXTENSION_NAME)?
+            .is_some()
+            && conn.shm_query_version()?.reply().is_ok();
+        let xfixes = conn
+            .extension_information(xfixes::X11_EXTENSION_NAME)?
+            .is_some()
+            && conn.xfixes_query_version(4, 0)?.reply().is_ok();
+
+        Ok(Self {
+            conn,
+            root,
+            use_shm,
+            shm: None,
+            xfixes,
+        })
+    }
+
+    /// 列出屏幕
+    pub fn screens(&self) -> Result<Vec<X11Screen>> {
+        let randr = self
+            .conn
+            .extension_information(x11rb::protocol::randr::X11_EXTENSION_NAME)?
+            .is_some();
+        if randr {
+            if let Ok(reply) = self.conn.randr_get_monitors(self.root, true)?.reply() {
+                let mut screens = vec![];
+                for monitor in reply.monitors {
+                    let name = self.conn.get_atom_name(monitor.name)?.reply()?.name;
+                    screens.push(X11Screen {
+                        name: String::from_utf8_lossy(&name).into_owned(),
+                        x: monitor.x,
+                        y: monitor.y,
+                        width: monitor.width,
+                        height: monitor.height,
+                    });
+                }
+                if !screens.is_empty() {
+                    return Ok(screens);
+                }
+            }
+        }
+
+        let geometry = self.conn.get_geometry(self.root)?.reply()?;
+        Ok(vec![X11Screen {
+            name: "Screen".to_string(),
+            x: 0,
+            y: 0,
+            width: geometry.width,
+            height: geometry.height,
+        }])
+    }
+
+    /// 列出顶层窗口
+    ///
+    /// 优先读取窗口管理器维护的 `_NET_CLIENT_LIST`，没有窗口管理器时列出根窗口下已映射的子窗口
+    pub fn windows(&self) -> Result<Vec<X11Window>> {
+        let client_list = self.intern_atom("_NET_CLIENT_LIST")?;
+        let reply = self
+            .conn
+            .get_property(false, self.root, client_list, AtomEnum::WINDOW, 0, u32::MAX)?
+            .reply()?;
+        let mut ids: Vec<u32> = reply.value32().map(|ids| ids.collect()).unwrap_or_default();
+
+        if ids.is_empty() {
+            for child in self.conn.query_tree(self.root)?.reply()?.children {
+                let attributes = self.conn.get_window_attributes(child)?.reply()?;
+                if attributes.map_state == xproto::MapState::VIEWABLE
+                    && !attributes.override_redirect
+                {
+                    ids.push(child);
+                }
+            }
+        }
+
+        let mut windows = vec![];
+        for id in ids {
+            // 窗口可能在列出后被关闭
+            let (Ok(title), Ok(class)) = (self.window_title(id), self.window_class(id)) else {
+                continue;
+            };
+            windows.push(X11Window { id, title, class });
+        }
+
+        Ok(windows)
+    }
+
+    fn intern_atom(&self, name: &str) -> Result<xproto::Atom> {
+        Ok(self.conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+    }
+
+    /// 窗口标题，优先读取 UTF-8 的 `_NET_WM_NAME`
+    pub fn window_title(&self, window: xproto::Window) -> Result<String> {
+        let net_wm_name = self.intern_atom("_NET_WM_NAME")?;
+        let utf8_string = self.intern_atom("UTF8_STRING")?;
+        let reply = self
+            .conn
+            .get_property(false, window, net_wm_name, utf8_string, 0, u32::MAX)?
+            .reply()?;
+        if !reply.value.is_empty() {
+            return Ok(String::from_utf8_lossy(&reply.value).into_owned());
+        }
+
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                window,
+                AtomEnum::WM_NAME,
+                AtomEnum::STRING,
+                0,
+                u32::MAX,
+            )?
+            .reply()?;
+        Ok(String::from_utf8_lossy(&reply.value).into_owned())
+    }
+
+    /// 窗口类名，`WM_CLASS` 由实例名和类名两个字符串组成
+    pub fn window_class(&self, window: xproto::Window) -> Result<String> {
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                window,
+                AtomEnum::WM_CLASS,
+                AtomEnum::STRING,
+                0,
+                u32::MAX,
+            )?
+            .reply()?;
+        let class = reply
+            .value
+            .split(|byte| *byte == 0)
+            .rfind(|part| !part.is_empty())
+            .unwrap_or_default();
+        Ok(String::from_utf8_lossy(class).into_owned())
+    }
+
+    /// 读取窗口或像素图的一块区域
+    ///
+    /// # 参数
+    ///
+    /// * `drawable` - 窗口或像素图
+    /// * `x`, `y`, `width`, `height` - 区域
+    /// * `alpha` - 是否保留 Alpha 通道，只对 32 位深度有效
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `Result<RgbaImage>`，表示读取的图像
+    pub fn get_image(
+        &mut self,
+        drawable: xproto::Drawable,
+        x: i16,
+        y: i16,
+        width: u16,
+        height: u16,
+        alpha: bool,
+    ) -> Result<RgbaImage> {
+        let len = width as usize * height as usize * 4;
+
+        if self.use_shm && self.shm.as_ref().is_none_or(|shm| shm.size < len) {
+            self.shm = None;
+            match ShmSegment::new(&self.conn, len) {
+                Ok(segment) => self.shm = Some(segment),
+                Err(e) => {
+                    // 远程连接无法共享内存，之后改用普通请求
+                    warn!("XSHM unavailable, falling back to GetImage: {}", e);
+                    self.use_shm = false;
+                }
+            }
+        }
+
+        let (depth, bgra) = match self.shm.as_ref().filter(|_| self.use_shm) {
+            Some(segment) => {
+                let reply = self
+                    .conn
+                    .shm_get_image(
+                        drawable,
+                        x,
+                        y,
+                        width,
+                        height,
+                        !0,
+                        ImageFormat::Z_PIXMAP.into(),
+                        segment.seg,
+                        0,
+                    )?
+                    .reply()?;
+                (reply.depth, segment.data(len))
+            }
+            None => {
+                let reply = self
+                    .conn
+                    .get_image(ImageFormat::Z_PIXMAP, drawable, x, y, width, height, !0)?
+                    .reply()?;
+                return bgra_to_rgba(&reply.data, width, height, alpha && reply.depth == 32);
+            }
+        };
+
+        bgra_to_rgba(bgra, width, height, alpha && depth == 32)
+    }
+
+    /// 在图像上绘制光标
+    ///
+    /// # 参数
+    ///
+    /// * `image` - 图像
+    /// * `origin` - 图像左上角在根窗口中的位置
+    pub fn draw_cursor(&self, image: &mut RgbaImage, origin: (i32, i32)) -> Result<()> {
+        if !self.xfixes {
+            return Ok(());
+        }
+
+        let cursor = self.conn.xfixes_get_cursor_image()?.reply()?;
+        let left = cursor.x as i32 - cursor.xhot as i32 - origin.0;
+        let top = cursor.y as i32 - cursor.yhot as i32 - origin.1;
+
+        for (i, argb) in cursor.cursor_image.iter().enumerate() {
+            let x = left + (i % cursor.width as usize) as i32;
+            let y = top + (i / cursor.width as usize) as i32;
+            if x < 0 || y < 0 || x >= image.width() as i32 || y >= image.height() as i32 {
+                continue;
+            }
+
+            // 光标像素是预乘 Alpha 的 ARGB
+            let a = (argb >> 24) & 0xFF;
+            if a == 0 {
+                continue;
+            }
+            let source = [(argb >> 16) & 0xFF, (argb >> 8) & 0xFF, argb & 0xFF];
+            let pixel = image.get_pixel_mut(x as u32, y as u32);
+            for (channel, value) in source.into_iter().enumerate() {
+                let blended = value + pixel[channel] as u32 * (255 - a) / 255;
+                pixel[channel] = blended.min(255) as u8;
+            }
+            pixel[3] = pixel[3].max(a as u8);
+        }
+
+        Ok(())
+    }
+}
+
+/// 把 X 服务器的 BGRX/BGRA 像素转换为 RGBA
+fn bgra_to_rgba(data: &[u8], width: u16, height: u16, alpha: bool) -> Result<RgbaImage> {
+    let len = width as usize * height as usize * 4;
+    if data.len() < len {
+        return Err(anyhow!("X11 image data too short"));
+    }
+
+    let mut rgba = Vec::with_capacity(len);
+    for pixel in data[..len].chunks_exact(4) {
+        rgba.extend_from_slice(&[
+            pixel[2],
+            pixel[1],
+            pixel[0],
+            if alpha { pixel[3] } else { 255 },
+        ]);
+    }
+
+    RgbaImage::from_raw(width as u32, height as u32, rgba).ok_or(anyhow!("invalid X11 image size"))
+}
+
+/// 列出 X 服务器的屏幕
+///
+/// # 参数
+///
+/// * `server` - X 服务器地址，为空时使用 `DISPLAY` 环境变量
+pub fn list_screens(server: &str) -> Result<Vec<X11Screen>> {
+    X11Connection::connect(server)?.screens()
+}
+
+/// 列出 X 服务器的顶层窗口
+///
+/// # 参数
+///
+/// * `server` - X 服务器地址，为空时使用 `DISPLAY` 环境变量
+pub fn list_windows(server: &str) -> Result<Vec<X11Window>> {
+    X11Connection::connect(server)?.windows()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        process::{Child, Command, Stdio},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use serde_json::json;
+    use x11rb::{
+        protocol::xproto::{CreateWindowAux, PropMode, WindowClass},
+        wrapper::ConnectionExt as _,
+        COPY_DEPTH_FROM_PARENT,
+    };
+
+    use super::*;
+    use crate::{
+        graphics::{cache::TextureCache, canvas::create_headless_device},
+        sources::{
+            x11::{xcomposite::XCompositeSource, xshm::XshmSource},
+            SourceContext, VideoSource,
+        },
+    };
+
+    /// 测试用的 Xvfb，黑色根窗口，离开作用域时关闭
+    struct Xvfb {
+        display: String,
+        child: Child,
+    }
+
+    impl Xvfb {
+        /// 启动 Xvfb，没有安装或无法启动时返回 `None`
+        ///
+        /// # 参数
+        ///
+        /// * `offset` - 同一进程中各个测试使用不同的显示编号
+        fn start(offset: u32) -> Option<Self> {
+            let display = format!(":{}", 100 + std::process::id() % 100 * 4 + offset);
+            let child = Command::new("Xvfb")
+                .args([
+                    &display,
+                    "-screen",
+                    "0",
+                    "320x240x24",
+                    "-br",
+                    "-nolisten",
+                    "tcp",
+                ])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let mut xvfb = Self { display, child };
+
+            let start = Instant::now();
+            while x11rb::connect(Some(&xvfb.display)).is_err() {
+                if start.elapsed() > Duration::from_secs(5)
+                    || xvfb.child.try_wait().ok().flatten().is_some()
+                {
+                    return None;
+                }
+                thread::sleep(Duration::from_millis(20));
+            }
+
+            Some(xvfb)
+        }
+    }
+
+    impl Drop for Xvfb {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    /// 创建纯色的顶层窗口，窗口在返回的连接关闭时销毁
+    fn create_window(
+        display: &str,
+        geometry: (i16, i16, u16, u16),
+        color: u32,
+    ) -> (RustConnection, xproto::Window) {
+        let (conn, screen) = x11rb::connect(Some(display)).unwrap();
+        let root = conn.setup().roots[screen].root;
+        let window = conn.generate_id().unwrap();
+        let (x, y, width, height) = geometry;
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            x,
+            y,
+            width,
+            height,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            0,
+            &CreateWindowAux::new().background_pixel(color),
+        )
+        .unwrap();
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            AtomEnum::WM_NAME,
+            AtomEnum::STRING,
+            b"capture test",
+        )
+        .unwrap();
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            AtomEnum::WM_CLASS,
+            AtomEnum::STRING,
+            b"test\0CaptureTest\0",
+        )
+        .unwrap();
+        conn.map_window(window).unwrap();
+        conn.sync().unwrap();
+
+        (conn, window)
+    }
+
+    #[test]
+    fn bgrx_pixels_are_converted_to_rgba() {
+        let data = [1, 2, 3, 4, 10, 20, 30, 40];
+        let image = bgra_to_rgba(&data, 2, 1, false).unwrap();
+        assert_eq!(image.as_raw(), &[3, 2, 1, 255, 30, 20, 10, 255]);
+
+        let image = bgra_to_rgba(&data, 2, 1, true).unwrap();
+        assert_eq!(image.as_raw(), &[3, 2, 1, 4, 30, 20, 10, 40]);
+
+        assert!(bgra_to_rgba(&data, 2, 2, false).is_err());
+    }
+
+    #[test]
+    fn xvfb_screen_pixels() {
+        let Some(xvfb) = Xvfb::start(0) else {
+            eprintln!("skipping: Xvfb not available");
+            return;
+        };
+        let (_conn, _window) = create_window(&xvfb.display, (40, 30, 100, 80), 0xff0000);
+
+        let screens = list_screens(&xvfb.display).unwrap();
+        assert_eq!((screens[0].width, screens[0].height), (320, 240));
+
+        let mut connection = X11Connection::connect(&xvfb.display).unwrap();
+        let image = connection
+            .get_image(connection.root, 0, 0, 320, 240, false)
+            .unwrap();
+        assert_eq!(image.dimensions(), (320, 240));
+        assert_eq!(image.get_pixel(60, 50).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(5, 5).0, [0, 0, 0, 255]);
+
+        // 读取子区域，并复用已有的共享内存段
+        let image = connection
+            .get_image(connection.root, 35, 25, 10, 10, false)
+            .unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(9, 9).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn xvfb_capture_sources() {
+        let Some(xvfb) = Xvfb::start(1) else {
+            eprintln!("skipping: Xvfb not available");
+            return;
+        };
+        let Ok((device, queue)) = create_headless_device(true) else {
+            eprintln!("skipping: no fallback adapter");
+            return;
+        };
+        let mut cache = TextureCache::default();
+        let mut context = SourceContext {
+            device: &device,
+            queue: &queue,
+            cache: &mut cache,
+        };
+
+        let (_conn, window) = create_window(&xvfb.display, (10, 20, 64, 48), 0x00ff00);
+        let windows = list_windows(&xvfb.display).unwrap();
+        assert!(windows.contains(&X11Window {
+            id: window,
+            title: "capture test".to_string(),
+            class: "CaptureTest".to_string(),
+        }));
+
+        let mut screen = XshmSource::default();
+        screen
+            .update(&json!({ "server": xvfb.display, "show_cursor": false }))
+            .unwrap();
+        screen.tick(&mut context, Duration::ZERO).unwrap();
+        assert_eq!(screen.size(), (320, 240));
+
+        // 按类名查找窗口，窗口 ID 不匹配时也能找到
+        let mut capture = XCompositeSource::default();
+        capture
+            .update(&json!({
+                "server": xvfb.display,
+                "window": 0,
+                "window_class": "CaptureTest",
+                "show_cursor": false,
+            }))
+            .unwrap();
+        capture.tick(&mut context, Duration::ZERO).unwrap();
+        assert_eq!(capture.size(), (64, 48));
+
+        // 连接到不存在的服务器时返回错误，不保留旧的画面
+        screen
+            .update(&json!({ "server": ":9999", "show_cursor": false }))
+            .unwrap();
+        assert!(screen.tick(&mut context, Duration::ZERO).is_err());
+        assert_eq!(screen.size(), (0, 0));
+    }
+}
@@ -0,0 +1,253 @@
+/// X11 窗口采集来源
+///
+/// 通过 XComposite 把窗口重定向到离屏像素图，窗口被遮挡时也能采集。
+/// 窗口关闭后按标题和类名重新查找，如程序重启后自动恢复采集
+use std::time::Duration;
+
+use anyhow::anyhow;
+use log::warn;
+use serde::Deserialize;
+use serde_json::Value;
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    protocol::{
+        composite::{self, ConnectionExt as _, Redirect},
+        xproto::{self, ConnectionExt as _},
+    },
+};
+
+use crate::{
+    graphics::texture::Texture,
+    sources::{upload_image, x11::X11Connection, SourceContext, VideoSource},
+    Result,
+};
+
+/// 重新连接和查找窗口的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 窗口采集设置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+struct XCompositeSettings {
+    /// X 服务器地址，如 `:99`，为空时使用 `DISPLAY` 环境变量
+    server: String,
+    /// 窗口 ID
+    window: u32,
+    /// 窗口标题和类名，窗口 ID 失效时用于重新查找
+    window_title: String,
+    window_class: String,
+    show_cursor: bool,
+    include_border: bool,
+    /// 忽略窗口的 Alpha 通道
+    exclude_alpha: bool,
+}
+
+impl Default for XCompositeSettings {
+    fn default() -> Self {
+        Self {
+            server: String::new(),
+            window: 0,
+            window_title: String::new(),
+            window_class: String::new(),
+            show_cursor: true,
+            include_border: false,
+            exclude_alpha: false,
+        }
+    }
+}
+
+/// 正在采集的窗口
+struct CapturedWindow {
+    id: xproto::Window,
+    /// 窗口内容的像素图，窗口尺寸变化后重新获取
+    pixmap: Option<xproto::Pixmap>,
+    /// 像素图对应的窗口尺寸和边框宽度
+    geometry: (u16, u16, u16),
+}
+
+/// X11 窗口采集来源
+#[derive(Default)]
+pub struct XCompositeSource {
+    settings: XCompositeSettings,
+    connection: Option<X11Connection>,
+    window: Option<CapturedWindow>,
+    since_check: Duration,
+    texture: Option<Texture>,
+}
+
+impl XCompositeSource {
+    /// 连接 X 服务器并查找要采集的窗口
+    fn refresh(&mut self) -> Result<()> {
+        if self.connection.is_none() {
+            let connection = X11Connection::connect(&self.settings.server)?;
+            if connection
+                .conn
+                .extension_information(composite::X11_EXTENSION_NAME)?
+                .is_none()
+            {
+                return Err(anyhow!("X server does not support XComposite"));
+            }
+            connection.conn.composite_query_version(0, 4)?.reply()?;
+            self.connection = Some(connection);
+        }
+        let Some(connection) = &self.connection else {
+            return Ok(());
+        };
+        if self.window.is_some() {
+            return Ok(());
+        }
+
+        let settings = &self.settings;
+        let windows = connection.windows()?;
+        let found = windows
+            .iter()
+            .find(|window| window.id == settings.window)
+            .or_else(|| {
+                windows.iter().find(|window| {
+                    window.class == settings.window_class && window.title == settings.window_title
+                })
+            })
+            .or_else(|| {
+                windows.iter().find(|window| {
+                    !settings.window_class.is_empty() && window.class == settings.window_class
+                })
+            });
+        let Some(found) = found else {
+            return Ok(());
+        };
+
+        connection
+            .conn
+            .composite_redirect_window(found.id, Redirect::AUTOMATIC)?
+            .check()?;
+        self.window = Some(CapturedWindow {
+            id: found.id,
+            pixmap: None,
+            geometry: (0, 0, 0),
+        });
+
+        Ok(())
+    }
+
+    /// 停止采集当前窗口
+    fn release_window(&mut self) {
+        let (Some(connection), Some(window)) = (&self.connection, self.window.take()) else {
+            return;
+        };
+
+        // 窗口已关闭时请求会失败，忽略错误
+        if let Some(pixmap) = window.pixmap {
+            let _ = connection.conn.free_pixmap(pixmap);
+        }
+        let _ = connection
+            .conn
+            .composite_unredirect_window(window.id, Redirect::AUTOMATIC);
+        let _ = connection.conn.flush();
+        self.texture = None;
+    }
+
+    /// 采集一帧
+    fn capture(&mut self, context: &mut SourceContext) -> Result<()> {
+        let (Some(connection), Some(window)) = (&mut self.connection, &mut self.window) else {
+            return Ok(());
+        };
+
+        let geometry = connection.conn.get_geometry(window.id)?.reply()?;
+        let size = (geometry.width, geometry.height, geometry.border_width);
+        if window.pixmap.is_none() || window.geometry != size {
+            if let Some(pixmap) = window.pixmap.take() {
+                connection.conn.free_pixmap(pixmap)?;
+            }
+            let pixmap = connection.conn.generate_id()?;
+            connection
+                .conn
+                .composite_name_window_pixmap(window.id, pixmap)?
+                .check()?;
+            window.pixmap = Some(pixmap);
+            window.geometry = size;
+        }
+        let Some(pixmap) = window.pixmap else {
+            return Ok(());
+        };
+
+        // 像素图包含边框
+        let (width, height, border) = size;
+        let (x, y, width, height) = if self.settings.include_border {
+            (0, 0, width + border * 2, height + border * 2)
+        } else {
+            (border as i16, border as i16, width, height)
+        };
+        let mut image =
+            connection.get_image(pixmap, x, y, width, height, !self.settings.exclude_alpha)?;
+
+        if self.settings.show_cursor {
+            let origin = connection
+                .conn
+                .translate_coordinates(window.id, connection.root, 0, 0)?
+                .reply()?;
+            let origin = (
+                origin.dst_x as i32 - border as i32 + x as i32,
+                origin.dst_y as i32 - border as i32 + y as i32,
+            );
+            connection.draw_cursor(&mut image, origin)?;
+        }
+
+        upload_image(&mut self.texture, context, &image, "xcomposite")
+    }
+}
+
+impl VideoSource for XCompositeSource {
+    fn update(&mut self, settings: &Value) -> Result<()> {
+        let settings: XCompositeSettings = serde_json::from_value(settings.clone())?;
+        if settings.server != self.settings.server {
+            self.release_window();
+            self.connection = None;
+        } else if settings.window != self.settings.window
+            || settings.window_class != self.settings.window_class
+            || settings.window_title != self.settings.window_title
+        {
+            self.release_window();
+        }
+        self.settings = settings;
+        self.since_check = CHECK_INTERVAL;
+
+        Ok(())
+    }
+
+    fn tick(&mut self, context: &mut SourceContext, delta: Duration) -> Result<()> {
+        self.since_check += delta;
+        if self.since_check >= CHECK_INTERVAL {
+            self.since_check = Duration::ZERO;
+            if let Err(e) = self.refresh() {
+                self.window = None;
+                self.connection = None;
+                self.texture = None;
+                return Err(e);
+            }
+        }
+
+        if let Err(e) = self.capture(context) {
+            // 窗口关闭或隐藏，稍后重新查找
+            warn!("window capture failed: {}", e);
+            self.release_window();
+        }
+
+        Ok(())
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.texture.as_ref().map_or((0, 0), |texture| {
+            (texture.texture.width(), texture.texture.height())
+        })
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        self.texture.as_ref()
+    }
+}
+
+impl Drop for XCompositeSource {
+    fn drop(&mut self) {
+        self.release_window();
+    }
+}
@@ -0,0 +1,140 @@
+/// X11 屏幕采集来源
+///
+/// 通过 XSHM 读取根窗口中选中屏幕的区域，连接断开后每秒重试
+use std::time::Duration;
+
+use log::warn;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    graphics::texture::Texture,
+    sources::{
+        upload_image,
+        x11::{X11Connection, X11Screen},
+        SourceContext, VideoSource,
+    },
+    Result,
+};
+
+/// 重新连接和刷新屏幕布局的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 屏幕采集设置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+struct XshmSettings {
+    /// X 服务器地址，如 `:99`，为空时使用 `DISPLAY` 环境变量
+    server: String,
+    /// 屏幕序号
+    screen: u32,
+    show_cursor: bool,
+}
+
+impl Default for XshmSettings {
+    fn default() -> Self {
+        Self {
+            server: String::new(),
+            screen: 0,
+            show_cursor: true,
+        }
+    }
+}
+
+/// X11 屏幕采集来源
+#[derive(Default)]
+pub struct XshmSource {
+    settings: XshmSettings,
+    connection: Option<X11Connection>,
+    /// 当前采集的屏幕区域
+    screen: Option<X11Screen>,
+    since_check: Duration,
+    texture: Option<Texture>,
+}
+
+impl XshmSource {
+    /// 连接 X 服务器并刷新选中屏幕的区域
+    fn refresh(&mut self) -> Result<()> {
+        if self.connection.is_none() {
+            self.connection = Some(X11Connection::connect(&self.settings.server)?);
+        }
+        let Some(connection) = &self.connection else {
+            return Ok(());
+        };
+
+        let screens = connection.screens()?;
+        // 屏幕被移除时采集第一个屏幕
+        self.screen = screens
+            .get(self.settings.screen as usize)
+            .or(screens.first())
+            .cloned();
+
+        Ok(())
+    }
+
+    /// 采集一帧
+    fn capture(&mut self, context: &mut SourceContext) -> Result<()> {
+        let (Some(connection), Some(screen)) = (&mut self.connection, &self.screen) else {
+            return Ok(());
+        };
+
+        let mut image = connection.get_image(
+            connection.root,
+            screen.x,
+            screen.y,
+            screen.width,
+            screen.height,
+            false,
+        )?;
+        if self.settings.show_cursor {
+            connection.draw_cursor(&mut image, (screen.x as i32, screen.y as i32))?;
+        }
+
+        upload_image(&mut self.texture, context, &image, "xshm")
+    }
+}
+
+impl VideoSource for XshmSource {
+    fn update(&mut self, settings: &Value) -> Result<()> {
+        let settings: XshmSettings = serde_json::from_value(settings.clone())?;
+        if settings.server != self.settings.server {
+            self.connection = None;
+            self.texture = None;
+        }
+        self.settings = settings;
+        self.since_check = CHECK_INTERVAL;
+
+        Ok(())
+    }
+
+    fn tick(&mut self, context: &mut SourceContext, delta: Duration) -> Result<()> {
+        self.since_check += delta;
+        if self.since_check >= CHECK_INTERVAL {
+            self.since_check = Duration::ZERO;
+            if let Err(e) = self.refresh() {
+                self.connection = None;
+                self.texture = None;
+                return Err(e);
+            }
+        }
+
+        if let Err(e) = self.capture(context) {
+            // 连接出错后重新连接
+            warn!("screen capture failed: {}", e);
+            self.connection = None;
+            self.texture = None;
+        }
+
+        Ok(())
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.texture.as_ref().map_or((0, 0), |texture| {
+            (texture.texture.width(), texture.texture.height())
+        })
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        self.texture.as_ref()
+    }
+}
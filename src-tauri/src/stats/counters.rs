@@ -0,0 +1,204 @@
+/// 统计计数器模块
+///
+/// 合成器、编码器和输出在运行时更新这些计数器，由采集器定期读取
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+/// 主画布合成的渲染帧总数
+static RENDER_FRAMES: AtomicU64 = AtomicU64::new(0);
+/// 因渲染延迟而错过的帧数
+static MISSED_FRAMES: AtomicU64 = AtomicU64::new(0);
+/// 累计渲染耗时（纳秒）
+static RENDER_TIME_NS: AtomicU64 = AtomicU64::new(0);
+/// 编码帧总数
+static ENCODED_FRAMES: AtomicU64 = AtomicU64::new(0);
+/// 因编码延迟而跳过的帧数
+static SKIPPED_FRAMES: AtomicU64 = AtomicU64::new(0);
+
+/// 记录主画布合成的一帧渲染
+///
+/// # 参数
+///
+/// * `render_time` - 本帧渲染耗时
+pub fn record_render_frame(render_time: Duration) {
+    RENDER_FRAMES.fetch_add(1, Ordering::Relaxed);
+    RENDER_TIME_NS.fetch_add(render_time.as_nanos() as u64, Ordering::Relaxed);
+}
+
+/// 记录主画布合成因渲染延迟而错过的帧
+///
+/// # 参数
+///
+/// * `frames` - 错过的帧数
+pub fn record_missed_frames(frames: u64) {
+    MISSED_FRAMES.fetch_add(frames, Ordering::Relaxed);
+}
+
+/// 记录一帧编码，包括编码器为控制码率而跳过的帧
+pub fn record_encoded_frame() {
+    ENCODED_FRAMES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 记录因编码器处理不及时而丢弃、未送入编码器的一帧
+pub fn record_skipped_frame() {
+    ENCODED_FRAMES.fetch_add(1, Ordering::Relaxed);
+    SKIPPED_FRAMES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 合成器与编码器计数器快照
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CounterValues {
+    pub render_frames: u64,
+    pub missed_frames: u64,
+    pub render_time_ns: u64,
+    pub encoded_frames: u64,
+    pub skipped_frames: u64,
+}
+
+impl CounterValues {
+    /// 读取当前计数器
+    pub fn load() -> Self {
+        Self {
+            render_frames: RENDER_FRAMES.load(Ordering::Relaxed),
+            missed_frames: MISSED_FRAMES.load(Ordering::Relaxed),
+            render_time_ns: RENDER_TIME_NS.load(Ordering::Relaxed),
+            encoded_frames: ENCODED_FRAMES.load(Ordering::Relaxed),
+            skipped_frames: SKIPPED_FRAMES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 计算相对于基准值的增量
+    pub fn since(&self, base: &CounterValues) -> CounterValues {
+        CounterValues {
+            render_frames: self.render_frames.saturating_sub(base.render_frames),
+            missed_frames: self.missed_frames.saturating_sub(base.missed_frames),
+            render_time_ns: self.render_time_ns.saturating_sub(base.render_time_ns),
+            encoded_frames: self.encoded_frames.saturating_sub(base.encoded_frames),
+            skipped_frames: self.skipped_frames.saturating_sub(base.skipped_frames),
+        }
+    }
+}
+
+/// 输出类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    /// 推流
+    Stream,
+    /// 录制
+    Recording,
+    /// 虚拟摄像机等其他输出
+    Other,
+}
+
+/// 单个输出的计数器
+#[derive(Debug)]
+pub struct OutputCounters {
+    /// 输出 ID
+    pub id: String,
+    /// 输出类型
+    pub kind: OutputKind,
+    /// 是否正在运行
+    pub active: AtomicBool,
+    /// 是否正在重连
+    pub reconnecting: AtomicBool,
+    /// 发送/写入的帧总数
+    pub total_frames: AtomicU64,
+    /// 因网络原因丢弃的帧数
+    pub dropped_frames: AtomicU64,
+    /// 发送/写入的字节总数
+    pub total_bytes: AtomicU64,
+}
+
+impl OutputCounters {
+    /// 记录发送/写入的数据
+    ///
+    /// # 参数
+    ///
+    /// * `bytes` - 字节数
+    /// * `frames` - 帧数
+    pub fn record_sent(&self, bytes: u64, frames: u64) {
+        self.total_bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.total_frames.fetch_add(frames, Ordering::Relaxed);
+    }
+
+    /// 记录丢弃的帧
+    ///
+    /// # 参数
+    ///
+    /// * `frames` - 帧数
+    pub fn record_dropped(&self, frames: u64) {
+        self.dropped_frames.fetch_add(frames, Ordering::Relaxed);
+        self.total_frames.fetch_add(frames, Ordering::Relaxed);
+    }
+
+    /// 设置运行状态
+    pub fn set_active(&self, active: bool) {
+        self.active.store(active, Ordering::Relaxed);
+        if !active {
+            self.reconnecting.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// 设置重连状态
+    pub fn set_reconnecting(&self, reconnecting: bool) {
+        self.reconnecting.store(reconnecting, Ordering::Relaxed);
+    }
+}
+
+lazy_static! {
+    /// 已注册的输出计数器
+    static ref OUTPUTS: Mutex<BTreeMap<String, Arc<OutputCounters>>> = Mutex::new(BTreeMap::new());
+}
+
+/// 注册输出计数器，同一 ID 重复注册时返回已有的计数器
+///
+/// # 参数
+///
+/// * `id` - 输出 ID
+/// * `kind` - 输出类型
+///
+/// # 返回值
+///
+/// 返回输出计数器
+pub fn register_output(id: &str, kind: OutputKind) -> Arc<OutputCounters> {
+    OUTPUTS
+        .lock()
+        .unwrap()
+        .entry(id.to_string())
+        .or_insert_with(|| {
+            Arc::new(OutputCounters {
+                id: id.to_string(),
+                kind,
+                active: AtomicBool::new(false),
+                reconnecting: AtomicBool::new(false),
+                total_frames: AtomicU64::new(0),
+                dropped_frames: AtomicU64::new(0),
+                total_bytes: AtomicU64::new(0),
+            })
+        })
+        .clone()
+}
+
+/// 注销输出计数器
+///
+/// # 参数
+///
+/// * `id` - 输出 ID
+pub fn unregister_output(id: &str) {
+    OUTPUTS.lock().unwrap().remove(id);
+}
+
+/// 获取所有已注册的输出计数器
+pub fn outputs() -> Vec<Arc<OutputCounters>> {
+    OUTPUTS.lock().unwrap().values().cloned().collect()
+}
@@ -0,0 +1,349 @@
+#![allow(dead_code)]
+
+pub mod counters;
+pub mod system;
+
+use std::{
+    collections::HashMap,
+    sync::{atomic::Ordering, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use log::error;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    outputs::recording::recording_dir,
+    stats::{
+        counters::{CounterValues, OutputKind},
+        system::CpuSample,
+    },
+    Result,
+};
+
+/// 统计数据更新时发送给前端的事件名称
+pub const STATS_EVENT: &str = "stats";
+
+/// 统计数据采样间隔
+pub const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 输出统计
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputStats {
+    /// 输出 ID
+    pub id: String,
+    /// 输出类型
+    pub kind: OutputKind,
+    /// 状态对应的本地化键，如 `Basic.Stats.Status.Live`
+    pub status: &'static str,
+    /// 丢弃的帧数
+    pub dropped_frames: u64,
+    /// 帧总数
+    pub total_frames: u64,
+    /// 已发送/写入的数据量（MB）
+    pub megabytes_sent: f64,
+    /// 当前码率（kb/s）
+    pub bitrate: f64,
+}
+
+/// 统计数据快照，字段与 `Basic.Stats.*` 文本一一对应
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Stats {
+    /// CPU 占用率（%）
+    pub cpu_usage: Option<f64>,
+    /// 录制路径所在磁盘的可用空间（MB）
+    pub hdd_space_available: Option<f64>,
+    /// 按当前录制码率估算的磁盘写满时间（秒）
+    pub disk_full_in: Option<u64>,
+    /// 内存占用（MB）
+    pub memory_usage: Option<f64>,
+    /// 主画布合成的平均渲染耗时（毫秒）
+    pub average_time_to_render: f64,
+    /// 因编码延迟而跳过的帧数
+    pub skipped_frames: u64,
+    /// 编码帧总数
+    pub encoded_frames: u64,
+    /// 主画布合成因渲染延迟而错过的帧数
+    pub missed_frames: u64,
+    /// 主画布合成的渲染帧总数
+    pub rendered_frames: u64,
+    /// 各输出统计
+    pub outputs: Vec<OutputStats>,
+}
+
+/// 输出计数器的上一次采样
+#[derive(Debug, Default, Clone, Copy)]
+struct OutputSample {
+    bytes: u64,
+    at: Option<Instant>,
+}
+
+/// 输出计数器的基准值
+#[derive(Debug, Default, Clone, Copy)]
+struct OutputBase {
+    bytes: u64,
+    frames: u64,
+    dropped: u64,
+}
+
+/// 统计采集器状态
+#[derive(Debug, Default)]
+struct Collector {
+    /// 重置时的计数器基准值
+    base: CounterValues,
+    /// 上一次采样的计数器，用于计算区间内的平均渲染耗时
+    prev: CounterValues,
+    /// 上一次的 CPU 采样
+    prev_cpu: Option<CpuSample>,
+    /// 重置时的输出计数器基准值
+    output_bases: HashMap<String, OutputBase>,
+    /// 上一次的输出采样，用于计算码率
+    output_samples: HashMap<String, OutputSample>,
+    /// 最近一次的统计数据
+    last: Stats,
+}
+
+lazy_static! {
+    /// 统计采集器
+    static ref COLLECTOR: Mutex<Collector> = Mutex::new(Collector::default());
+}
+
+/// 启动统计采集线程，定期发送 [`STATS_EVENT`] 事件
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn setup_stats(app: &AppHandle) -> Result<()> {
+    reset_stats();
+
+    let app = app.clone();
+    thread::Builder::new()
+        .name("stats".to_string())
+        .spawn(move || loop {
+            thread::sleep(STATS_INTERVAL);
+
+            let stats = collect(&app);
+            if let Err(e) = app.emit(STATS_EVENT, &stats) {
+                error!("failed to emit stats: {}", e);
+            }
+        })?;
+
+    Ok(())
+}
+
+/// 获取最近一次的统计数据
+pub fn stats() -> Stats {
+    COLLECTOR.lock().unwrap().last.clone()
+}
+
+/// 重置统计数据，之后的计数从零开始
+pub fn reset_stats() {
+    let mut collector = COLLECTOR.lock().unwrap();
+
+    collector.base = CounterValues::load();
+    collector.prev = collector.base;
+    collector.output_bases = counters::outputs()
+        .into_iter()
+        .map(|output| {
+            let base = OutputBase {
+                bytes: output.total_bytes.load(Ordering::Relaxed),
+                frames: output.total_frames.load(Ordering::Relaxed),
+                dropped: output.dropped_frames.load(Ordering::Relaxed),
+            };
+            (output.id.clone(), base)
+        })
+        .collect();
+    collector.last = Stats::default();
+}
+
+/// 采集一次统计数据
+fn collect(app: &AppHandle) -> Stats {
+    let now = Instant::now();
+    let values = CounterValues::load();
+    let cpu = system::sample_cpu();
+
+    let mut collector = COLLECTOR.lock().unwrap();
+
+    let total = values.since(&collector.base);
+    let interval = values.since(&collector.prev);
+    let average_time_to_render = if interval.render_frames > 0 {
+        interval.render_time_ns as f64 / interval.render_frames as f64 / 1_000_000.
+    } else {
+        collector.last.average_time_to_render
+    };
+
+    let cpu_usage = match (&collector.prev_cpu, &cpu) {
+        (Some(prev), Some(cpu)) => Some(system::cpu_usage(prev, cpu)),
+        _ => None,
+    };
+
+    let (outputs, recording_bitrate) = collect_outputs(&mut collector, now);
+
+    let available = recording_dir(app).and_then(|path| system::disk_available(&path));
+    let disk_full_in = match available {
+        Some(available) if recording_bitrate > 0. => {
+            Some((available as f64 * 8. / 1000. / recording_bitrate) as u64)
+        }
+        _ => None,
+    };
+
+    let stats = Stats {
+        cpu_usage,
+        hdd_space_available: available.map(|available| available as f64 / 1024. / 1024.),
+        disk_full_in,
+        memory_usage: system::memory_usage_mb(),
+        average_time_to_render,
+        skipped_frames: total.skipped_frames,
+        encoded_frames: total.encoded_frames,
+        missed_frames: total.missed_frames,
+        rendered_frames: total.render_frames,
+        outputs,
+    };
+
+    collector.prev = values;
+    collector.prev_cpu = cpu;
+    collector.last = stats.clone();
+
+    stats
+}
+
+/// 采集各输出的统计，计数从上一次重置开始
+///
+/// # 参数
+///
+/// * `collector` - 采集器状态
+/// * `now` - 采样时刻
+///
+/// # 返回值
+///
+/// 返回各输出统计和正在录制的总码率（kb/s）
+fn collect_outputs(collector: &mut Collector, now: Instant) -> (Vec<OutputStats>, f64) {
+    let mut outputs = vec![];
+    let mut recording_bitrate = 0.;
+    for output in counters::outputs() {
+        let bytes = output.total_bytes.load(Ordering::Relaxed);
+        let frames = output.total_frames.load(Ordering::Relaxed);
+        let dropped = output.dropped_frames.load(Ordering::Relaxed);
+        let active = output.active.load(Ordering::Relaxed);
+        let reconnecting = output.reconnecting.load(Ordering::Relaxed);
+
+        let base = *collector.output_bases.entry(output.id.clone()).or_default();
+        let sample = collector
+            .output_samples
+            .insert(
+                output.id.clone(),
+                OutputSample {
+                    bytes,
+                    at: Some(now),
+                },
+            )
+            .unwrap_or_default();
+
+        let bitrate = match sample.at {
+            Some(at) if active && now > at => {
+                bytes.saturating_sub(sample.bytes) as f64 * 8.
+                    / now.duration_since(at).as_secs_f64()
+                    / 1000.
+            }
+            _ => 0.,
+        };
+
+        let status = match (output.kind, active, reconnecting) {
+            (_, true, true) => "Basic.Stats.Status.Reconnecting",
+            (OutputKind::Stream, true, false) => "Basic.Stats.Status.Live",
+            (OutputKind::Recording, true, false) => "Basic.Stats.Status.Recording",
+            (_, true, false) => "Basic.Stats.Status.Active",
+            (_, false, _) => "Basic.Stats.Status.Inactive",
+        };
+
+        if output.kind == OutputKind::Recording && active {
+            recording_bitrate += bitrate;
+        }
+
+        outputs.push(OutputStats {
+            id: output.id.clone(),
+            kind: output.kind,
+            status,
+            dropped_frames: dropped.saturating_sub(base.dropped),
+            total_frames: frames.saturating_sub(base.frames),
+            megabytes_sent: bytes.saturating_sub(base.bytes) as f64 / 1024. / 1024.,
+            bitrate,
+        });
+    }
+    let ids: Vec<&String> = outputs.iter().map(|output| &output.id).collect();
+    collector.output_samples.retain(|id, _| ids.contains(&id));
+
+    (outputs, recording_bitrate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::counters::{register_output, unregister_output};
+
+    #[test]
+    fn counter_deltas_saturate_at_zero() {
+        let base = CounterValues {
+            render_frames: 100,
+            missed_frames: 2,
+            render_time_ns: 1_000,
+            encoded_frames: 90,
+            skipped_frames: 5,
+        };
+        let values = CounterValues {
+            render_frames: 160,
+            missed_frames: 2,
+            render_time_ns: 4_000,
+            encoded_frames: 150,
+            skipped_frames: 1,
+        };
+
+        let delta = values.since(&base);
+        assert_eq!(delta.render_frames, 60);
+        assert_eq!(delta.missed_frames, 0);
+        assert_eq!(delta.render_time_ns, 3_000);
+        assert_eq!(delta.encoded_frames, 60);
+        assert_eq!(delta.skipped_frames, 0);
+        assert_eq!(values.since(&values), CounterValues::default());
+    }
+
+    #[test]
+    fn reset_restarts_output_counts() {
+        let id = "stats-test-recording";
+        let output = register_output(id, OutputKind::Recording);
+        output.set_active(true);
+        output.record_sent(4 * 1024 * 1024, 40);
+        output.record_dropped(3);
+
+        reset_stats();
+        output.record_sent(1024 * 1024, 10);
+        output.record_dropped(1);
+
+        let (outputs, recording_bitrate) =
+            collect_outputs(&mut COLLECTOR.lock().unwrap(), Instant::now());
+        let stats = outputs.iter().find(|stats| stats.id == id).unwrap();
+        assert_eq!(stats.status, "Basic.Stats.Status.Recording");
+        assert_eq!(stats.total_frames, 11);
+        assert_eq!(stats.dropped_frames, 1);
+        assert_eq!(stats.megabytes_sent, 1.);
+        assert!(recording_bitrate >= stats.bitrate);
+
+        output.set_active(false);
+        reset_stats();
+        let (outputs, _) = collect_outputs(&mut COLLECTOR.lock().unwrap(), Instant::now());
+        let stats = outputs.iter().find(|stats| stats.id == id).unwrap();
+        assert_eq!(stats.status, "Basic.Stats.Status.Inactive");
+        assert_eq!(stats.total_frames, 0);
+        assert_eq!(stats.megabytes_sent, 0.);
+        assert_eq!(stats.bitrate, 0.);
+
+        unregister_output(id);
+    }
+}
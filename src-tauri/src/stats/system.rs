@@ -0,0 +1,196 @@
+/// 系统资源采样模块
+///
+/// Linux 下通过 `/proc` 读取进程 CPU 和内存占用，其他平台暂不支持
+use std::{path::Path, time::Instant};
+
+/// 进程 CPU 时间采样
+#[derive(Debug, Clone, Copy)]
+pub struct CpuSample {
+    /// 进程累计 CPU 时间（秒）
+    cpu_seconds: f64,
+    /// 采样时刻
+    at: Instant,
+}
+
+/// 采样进程累计 CPU 时间
+///
+/// # 返回值
+///
+/// 返回 `Option<CpuSample>`，不支持的平台返回 `None`
+#[cfg(target_os = "linux")]
+pub fn sample_cpu() -> Option<CpuSample> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    let cpu_ticks = parse_cpu_ticks(&stat)?;
+
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks <= 0 {
+        return None;
+    }
+
+    Some(CpuSample {
+        cpu_seconds: cpu_ticks as f64 / ticks as f64,
+        at: Instant::now(),
+    })
+}
+
+/// 从 `/proc/<pid>/stat` 的内容中解析进程累计 CPU 时间
+///
+/// # 参数
+///
+/// * `stat` - 文件内容
+///
+/// # 返回值
+///
+/// 返回 `Option<u64>`，表示用户态和内核态时钟周期之和，格式不正确时返回 `None`
+fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    // 进程名可能包含空格，从最后一个 ')' 之后开始解析，之后第一个字段是第 3 个字段
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+
+    Some(utime + stime)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn sample_cpu() -> Option<CpuSample> {
+    None
+}
+
+/// 计算两次采样之间的 CPU 占用率
+///
+/// # 参数
+///
+/// * `prev` - 上一次采样
+/// * `now` - 本次采样
+///
+/// # 返回值
+///
+/// 返回占全部逻辑核心的百分比
+pub fn cpu_usage(prev: &CpuSample, now: &CpuSample) -> f64 {
+    let wall = now.at.duration_since(prev.at).as_secs_f64();
+    if wall <= 0. {
+        return 0.;
+    }
+
+    let cores = std::thread::available_parallelism()
+        .map(|cores| cores.get())
+        .unwrap_or(1) as f64;
+
+    ((now.cpu_seconds - prev.cpu_seconds) / wall / cores * 100.).clamp(0., 100.)
+}
+
+/// 获取进程常驻内存（MB）
+///
+/// # 返回值
+///
+/// 返回 `Option<f64>`，不支持的平台返回 `None`
+#[cfg(target_os = "linux")]
+pub fn memory_usage_mb() -> Option<f64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+
+    parse_rss_kb(&status).map(|kb| kb / 1024.)
+}
+
+/// 从 `/proc/<pid>/status` 的内容中解析常驻内存
+///
+/// # 参数
+///
+/// * `status` - 文件内容
+///
+/// # 返回值
+///
+/// 返回 `Option<f64>`，表示常驻内存（KB），没有 `VmRSS` 行时返回 `None`
+fn parse_rss_kb(status: &str) -> Option<f64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn memory_usage_mb() -> Option<f64> {
+    None
+}
+
+/// 获取指定路径所在磁盘的可用空间（字节）
+///
+/// # 参数
+///
+/// * `path` - 路径，不存在时向上查找已存在的父目录
+///
+/// # 返回值
+///
+/// 返回 `Option<u64>`，不支持的平台返回 `None`
+#[cfg(unix)]
+pub fn disk_available(path: &Path) -> Option<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = path.ancestors().find(|path| path.exists())?;
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn disk_available(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_ticks_skip_process_names_with_spaces_and_parens() {
+        let stat = "4242 (my (weird) app) S 1 4242 4242 0 -1 4194560 1234 0 0 0 \
+                    150 75 3 4 20 0 12 0 98765 123456789 4321 18446744073709551615";
+
+        assert_eq!(parse_cpu_ticks(stat), Some(225));
+    }
+
+    #[test]
+    fn cpu_ticks_reject_truncated_or_malformed_stat() {
+        assert_eq!(parse_cpu_ticks("4242 (app) S 1 4242"), None);
+        assert_eq!(
+            parse_cpu_ticks("4242 app S 1 2 3 4 5 6 7 8 9 10 11 12"),
+            None
+        );
+        assert_eq!(
+            parse_cpu_ticks("1 (a) S 1 1 1 0 -1 0 0 0 0 0 x 75 0 0"),
+            None
+        );
+    }
+
+    #[test]
+    fn rss_is_read_from_vmrss_line() {
+        let status = "Name:\tapp\nVmPeak:\t  900000 kB\nVmRSS:\t  204800 kB\nThreads:\t12\n";
+
+        assert_eq!(parse_rss_kb(status), Some(204800.));
+        assert_eq!(parse_rss_kb("Name:\tapp\nVmPeak:\t900000 kB\n"), None);
+        assert_eq!(parse_rss_kb("VmRSS:\n"), None);
+    }
+
+    #[test]
+    fn cpu_usage_is_relative_to_all_cores() {
+        let cores = std::thread::available_parallelism()
+            .map(|cores| cores.get())
+            .unwrap_or(1) as f64;
+        let at = Instant::now();
+        let prev = CpuSample {
+            cpu_seconds: 10.,
+            at,
+        };
+        let now = CpuSample {
+            cpu_seconds: 10.5,
+            at: at + std::time::Duration::from_secs(1),
+        };
+
+        let expected = (50. / cores).min(100.);
+        assert!((cpu_usage(&prev, &now) - expected).abs() < 1e-9);
+        assert_eq!(cpu_usage(&now, &now), 0.);
+    }
+}
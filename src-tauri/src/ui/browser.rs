@@ -0,0 +1,390 @@
+/// 此模块管理浏览器来源使用的离屏网页视图
+///
+/// 每个浏览器来源实例对应一个放在屏幕外的窗口，窗口中的子网页视图加载来源的网址，
+/// 画面通过平台网页视图的截图接口读取，页面音频和状态桥接通过注入的脚本完成。
+///
+/// 窗口保持映射而不是隐藏：WebKitGTK 会暂停未映射窗口中页面的 `requestAnimationFrame`
+/// 并限制定时器，隐藏窗口中的动画不会更新
+use anyhow::anyhow;
+use image::RgbaImage;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{
+    webview::WebviewBuilder, window::WindowBuilder, AppHandle, Manager, PhysicalPosition,
+    PhysicalSize, Url, WebviewUrl,
+};
+
+use crate::Result;
+
+/// 浏览器窗口标签前缀
+const BROWSER_WINDOW_PREFIX: &str = "browser-window-";
+
+/// 浏览器网页视图标签前缀
+const BROWSER_WEBVIEW_PREFIX: &str = "browser-";
+
+/// 浏览器窗口与屏幕左上角之间的距离，窗口完全位于屏幕外
+const OFFSCREEN_MARGIN: f64 = 10000.;
+
+/// 注入页面的桥接脚本
+const BRIDGE_SCRIPT: &str = include_str!("browser_bridge.js");
+
+/// 桥接脚本中配置的占位符
+const BRIDGE_CONFIG_PLACEHOLDER: &str = "__OBS_BRIDGE_CONFIG__";
+
+/// 桥接脚本报告的插件版本
+const BRIDGE_VERSION: &str = "2.24.0";
+
+/// 应用程序句柄，浏览器窗口需要在来源线程中创建
+static APP: OnceCell<AppHandle> = OnceCell::new();
+
+/// 打开浏览器时的选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrowserOptions {
+    /// 网址，本地文件使用 `file://` 网址
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
+    /// 注入页面的自定义 CSS
+    pub css: String,
+    /// 是否把页面音频转到混音器
+    pub reroute_audio: bool,
+}
+
+/// 页面转出的一块音频
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageAudio {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// 交错排列的采样
+    pub data: Vec<f32>,
+}
+
+/// 桥接脚本中 `window.obsstudio` 可以读取的状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BridgeState {
+    /// 当前场景
+    pub scene: BridgeScene,
+    /// 输出状态
+    pub status: BridgeStatus,
+    /// 来源是否在节目中显示
+    pub active: bool,
+}
+
+/// 桥接的场景信息
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BridgeScene {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 桥接的输出状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeStatus {
+    pub streaming: bool,
+    pub recording: bool,
+    pub recording_paused: bool,
+    pub virtualcam: bool,
+}
+
+/// 设置浏览器来源使用的应用程序句柄
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn setup_browser(app: &AppHandle) -> Result<()> {
+    APP.set(app.clone())
+        .map_err(|_| anyhow!("browser already set up"))?;
+
+    Ok(())
+}
+
+/// 获取应用程序句柄
+fn app() -> Result<&'static AppHandle> {
+    APP.get().ok_or(anyhow!("browser not set up"))
+}
+
+/// 浏览器窗口标签
+fn window_label(key: &str) -> String {
+    format!("{}{}", BROWSER_WINDOW_PREFIX, key)
+}
+
+/// 浏览器网页视图标签
+fn webview_label(key: &str) -> String {
+    format!("{}{}", BROWSER_WEBVIEW_PREFIX, key)
+}
+
+/// 把本地文件路径转换为网址
+///
+/// # 参数
+///
+/// * `path` - 本地文件的绝对路径
+pub fn file_url(path: &str) -> Result<String> {
+    Url::from_file_path(path)
+        .map(|url| url.to_string())
+        .map_err(|_| anyhow!("invalid local file path: {}", path))
+}
+
+/// 生成注入页面的桥接脚本
+fn bridge_script(options: &BrowserOptions) -> String {
+    let config = json!({
+        "css": options.css,
+        "rerouteAudio": options.reroute_audio,
+        "version": BRIDGE_VERSION,
+    });
+    BRIDGE_SCRIPT.replace(BRIDGE_CONFIG_PLACEHOLDER, &config.to_string())
+}
+
+/// 打开浏览器实例的网页视图，已经打开时先关闭
+///
+/// # 参数
+///
+/// * `key` - 浏览器实例的键
+/// * `options` - 打开选项
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn open_browser(key: &str, options: &BrowserOptions) -> Result<()> {
+    let app = app()?;
+    close_browser(key);
+
+    let url = Url::parse(&options.url)?;
+    let size = PhysicalSize::new(options.width.max(1), options.height.max(1));
+    // 窗口映射在屏幕外，不出现在任务栏中，也不获取焦点
+    let window = WindowBuilder::new(app, window_label(key))
+        .inner_size(size.width as f64, size.height as f64)
+        .position(
+            -(size.width as f64 + OFFSCREEN_MARGIN),
+            -(size.height as f64 + OFFSCREEN_MARGIN),
+        )
+        .visible(true)
+        .decorations(false)
+        .shadow(false)
+        .skip_taskbar(true)
+        .always_on_bottom(true)
+        .transparent(true)
+        .focused(false)
+        .build()?;
+    window.add_child(
+        WebviewBuilder::new(webview_label(key), WebviewUrl::External(url))
+            .transparent(true)
+            .initialization_script(bridge_script(options)),
+        PhysicalPosition::new(0, 0),
+        size,
+    )?;
+
+    Ok(())
+}
+
+/// 关闭浏览器实例的网页视图
+///
+/// # 参数
+///
+/// * `key` - 浏览器实例的键
+pub fn close_browser(key: &str) {
+    let Ok(app) = app() else {
+        return;
+    };
+    if let Some(window) = app.get_window(&window_label(key)) {
+        let _ = window.destroy();
+    }
+}
+
+/// 获取浏览器实例的网页视图
+fn webview(key: &str) -> Result<tauri::Webview> {
+    app()?
+        .get_webview(&webview_label(key))
+        .ok_or(anyhow!("browser not open: {}", key))
+}
+
+/// 替换页面中的自定义 CSS
+///
+/// # 参数
+///
+/// * `key` - 浏览器实例的键
+/// * `css` - 自定义 CSS
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn set_browser_css(key: &str, css: &str) -> Result<()> {
+    webview(key)?.eval(format!(
+        "window.obsstudio && window.obsstudio.__setCss({})",
+        serde_json::to_string(css)?
+    ))?;
+
+    Ok(())
+}
+
+/// 把场景和输出状态推送给页面，页面据此触发 `obsSceneChanged` 等事件
+///
+/// # 参数
+///
+/// * `key` - 浏览器实例的键
+/// * `state` - 当前状态
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn push_bridge_state(key: &str, state: &BridgeState) -> Result<()> {
+    webview(key)?.eval(format!(
+        "window.obsstudio && window.obsstudio.__update({})",
+        serde_json::to_string(state)?
+    ))?;
+
+    Ok(())
+}
+
+/// 取出页面转出的音频，结果在主线程中回调
+///
+/// # 参数
+///
+/// * `key` - 浏览器实例的键
+/// * `callback` - 收到音频时调用，页面没有新音频时不调用
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示请求是否发出
+pub fn drain_browser_audio(key: &str, callback: impl Fn(PageAudio) + Send + 'static) -> Result<()> {
+    webview(key)?.eval_with_callback(
+        "window.obsstudio ? window.obsstudio.__drainAudio() : null",
+        move |result| {
+            if let Ok(Some(audio)) = serde_json::from_str::<Option<PageAudio>>(&result) {
+                callback(audio);
+            }
+        },
+    )?;
+
+    Ok(())
+}
+
+/// 截取浏览器来源当前的画面，结果在主线程中回调
+///
+/// # 参数
+///
+/// * `key` - 浏览器实例的键
+/// * `callback` - 截图完成或失败时调用
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示请求是否发出
+#[cfg(target_os = "linux")]
+pub fn snapshot_browser(
+    key: &str,
+    callback: impl FnOnce(Result<RgbaImage>) + Send + 'static,
+) -> Result<()> {
+    use webkit2gtk::{SnapshotOptions, SnapshotRegion, WebViewExt};
+
+    webview(key)?.with_webview(move |webview| {
+        webview.inner().snapshot(
+            SnapshotRegion::Visible,
+            SnapshotOptions::TRANSPARENT_BACKGROUND,
+            None::<&webkit2gtk::gio::Cancellable>,
+            move |result| {
+                callback(
+                    result
+                        .map_err(anyhow::Error::from)
+                        .and_then(surface_to_image),
+                )
+            },
+        );
+    })?;
+
+    Ok(())
+}
+
+/// 截取浏览器来源当前的画面，结果在主线程中回调
+///
+/// # 参数
+///
+/// * `key` - 浏览器实例的键
+/// * `callback` - 截图完成或失败时调用
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示请求是否发出
+#[cfg(not(target_os = "linux"))]
+pub fn snapshot_browser(
+    key: &str,
+    callback: impl FnOnce(Result<RgbaImage>) + Send + 'static,
+) -> Result<()> {
+    webview(key)?;
+    callback(Err(anyhow!(
+        "browser capture is not supported on this platform"
+    )));
+
+    Ok(())
+}
+
+/// 把预乘 alpha 的 BGRA 截图转换为 RGBA 图像
+#[cfg(target_os = "linux")]
+fn surface_to_image(surface: cairo::Surface) -> Result<RgbaImage> {
+    let surface = cairo::ImageSurface::try_from(surface)
+        .map_err(|_| anyhow!("browser snapshot is not an image surface"))?;
+    if surface.format() != cairo::Format::ARgb32 {
+        return Err(anyhow!(
+            "unsupported browser snapshot format: {:?}",
+            surface.format()
+        ));
+    }
+
+    let width = surface.width().max(0) as u32;
+    let height = surface.height().max(0) as u32;
+    let stride = surface.stride().max(0) as usize;
+    let mut image = RgbaImage::new(width, height);
+    surface.with_data(|data| {
+        for (y, row) in image.rows_mut().enumerate() {
+            let line = &data[y * stride..];
+            for (x, pixel) in row.enumerate() {
+                // 小端序的 ARGB32 在内存中为 B G R A
+                let [b, g, r, a] = [0, 1, 2, 3].map(|i| line[x * 4 + i]);
+                let unpremultiply = |value: u8| match a {
+                    0 => 0,
+                    _ => ((value as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8,
+                };
+                pixel.0 = [unpremultiply(r), unpremultiply(g), unpremultiply(b), a];
+            }
+        }
+    })?;
+
+    Ok(image)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surface_to_image_unpremultiplies_alpha() {
+        // 每行两个像素，行尾有 4 字节填充，内存中为 B G R A
+        #[rustfmt::skip]
+        let data = vec![
+            0, 0, 128, 128,  0, 0, 0, 0,     0, 0, 0, 0,
+            10, 20, 30, 255, 128, 64, 0, 128, 0, 0, 0, 0,
+        ];
+        let surface =
+            cairo::ImageSurface::create_for_data(data, cairo::Format::ARgb32, 2, 2, 12).unwrap();
+
+        let image = surface_to_image((*surface).clone()).unwrap();
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 128]);
+        // 完全透明的像素不做除法
+        assert_eq!(image.get_pixel(1, 0).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(0, 1).0, [30, 20, 10, 255]);
+        assert_eq!(image.get_pixel(1, 1).0, [0, 128, 255, 128]);
+    }
+
+    #[test]
+    fn surface_to_image_rejects_other_formats() {
+        let surface = cairo::ImageSurface::create(cairo::Format::Rgb24, 2, 2).unwrap();
+        assert!(surface_to_image((*surface).clone()).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+// 注入浏览器来源页面的桥接脚本
+//
+// 提供与 OBS 浏览器来源兼容的 window.obsstudio 接口，注入自定义 CSS，
+// 并在启用音频转出时截获页面的音频，由来源定期取走送入混音器
+(() => {
+    if (window.obsstudio) {
+        return;
+    }
+
+    const config = __OBS_BRIDGE_CONFIG__;
+
+    // 音频最多缓存的秒数，来源停止读取时丢弃旧数据
+    const MAX_BUFFER_SECONDS = 1;
+    const PROCESSOR_SIZE = 4096;
+    const CHANNELS = 2;
+
+    let state = {
+        scene: { name: '', width: 0, height: 0 },
+        status: {
+            streaming: false,
+            recording: false,
+            recordingPaused: false,
+            replaybuffer: false,
+            virtualcam: false,
+        },
+        active: false,
+    };
+
+    const dispatch = (name, detail) => {
+        window.dispatchEvent(new CustomEvent(name, { detail }));
+    };
+
+    // 状态字段变化时触发的事件，依次为变为 true 和 false 时的事件名
+    const STATUS_EVENTS = {
+        streaming: ['obsStreamingStarted', 'obsStreamingStopped'],
+        recording: ['obsRecordingStarted', 'obsRecordingStopped'],
+        recordingPaused: ['obsRecordingPaused', 'obsRecordingUnpaused'],
+        replaybuffer: ['obsReplaybufferStarted', 'obsReplaybufferStopped'],
+        virtualcam: ['obsVirtualcamStarted', 'obsVirtualcamStopped'],
+    };
+
+    const applyCss = (css) => {
+        let style = document.getElementById('__obs_css');
+        if (!style) {
+            style = document.createElement('style');
+            style.id = '__obs_css';
+            (document.head || document.documentElement).appendChild(style);
+        }
+        style.textContent = css;
+    };
+
+    let css = config.css;
+    if (document.readyState === 'loading') {
+        document.addEventListener('DOMContentLoaded', () => applyCss(css));
+    } else {
+        applyCss(css);
+    }
+
+    // 每个 AudioContext 对应一个截获节点和待取走的音频块
+    const captures = new Map();
+    let mediaContext = null;
+    const originalConnect = AudioNode.prototype.connect;
+
+    const captureNode = (context) => {
+        let capture = captures.get(context);
+        if (capture) {
+            return capture.node;
+        }
+
+        const node = context.createScriptProcessor(PROCESSOR_SIZE, CHANNELS, CHANNELS);
+        capture = { node, chunks: [], frames: 0 };
+        node.onaudioprocess = (event) => {
+            const input = event.inputBuffer;
+            const frames = input.length;
+            const data = new Float32Array(frames * CHANNELS);
+            for (let channel = 0; channel < CHANNELS; channel++) {
+                const source = Math.min(channel, input.numberOfChannels - 1);
+                const samples = input.getChannelData(source);
+                for (let i = 0; i < frames; i++) {
+                    data[i * CHANNELS + channel] = samples[i];
+                }
+                // 页面音频只送入混音器，不在本地播放
+                event.outputBuffer.getChannelData(channel).fill(0);
+            }
+
+            capture.chunks.push(data);
+            capture.frames += frames;
+            while (capture.frames > context.sampleRate * MAX_BUFFER_SECONDS) {
+                capture.frames -= capture.chunks.shift().length / CHANNELS;
+            }
+        };
+        originalConnect.call(node, context.destination);
+        captures.set(context, capture);
+
+        return node;
+    };
+
+    if (config.rerouteAudio) {
+        AudioNode.prototype.connect = function (destination, ...args) {
+            if (destination instanceof AudioDestinationNode) {
+                destination = captureNode(destination.context);
+            }
+            return originalConnect.call(this, destination, ...args);
+        };
+
+        // 媒体元素开始播放时接入共享的 AudioContext
+        document.addEventListener(
+            'play',
+            (event) => {
+                const element = event.target;
+                if (!(element instanceof HTMLMediaElement) || element.__obsRouted) {
+                    return;
+                }
+                element.__obsRouted = true;
+                mediaContext = mediaContext || new AudioContext();
+                mediaContext.createMediaElementSource(element).connect(mediaContext.destination);
+                mediaContext.resume();
+            },
+            true,
+        );
+    }
+
+    // 混合所有 AudioContext 缓存的音频，只混合与第一个采样率相同的音频
+    const drainAudio = () => {
+        let sampleRate = 0;
+        let length = 0;
+        const pending = [];
+        for (const [context, capture] of captures) {
+            if (capture.frames === 0) {
+                continue;
+            }
+            if (sampleRate === 0) {
+                sampleRate = context.sampleRate;
+            }
+            if (context.sampleRate === sampleRate) {
+                pending.push(capture.chunks);
+                length = Math.max(length, capture.frames * CHANNELS);
+            }
+            capture.chunks = [];
+            capture.frames = 0;
+        }
+        if (length === 0) {
+            return null;
+        }
+
+        const data = new Float32Array(length);
+        for (const chunks of pending) {
+            let offset = 0;
+            for (const chunk of chunks) {
+                for (let i = 0; i < chunk.length; i++) {
+                    data[offset + i] += chunk[i];
+                }
+                offset += chunk.length;
+            }
+        }
+
+        return { sampleRate, channels: CHANNELS, data: Array.from(data) };
+    };
+
+    window.obsstudio = {
+        pluginVersion: config.version,
+        getCurrentScene: (callback) => callback({ ...state.scene }),
+        getStatus: (callback) => callback({ ...state.status }),
+        // 只读权限，页面不能控制场景和输出
+        getControlLevel: (callback) => callback(1),
+        __update: (next) => {
+            const previous = state;
+            state = {
+                scene: next.scene,
+                status: { ...previous.status, ...next.status },
+                active: next.active,
+            };
+
+            if (
+                next.scene.name !== previous.scene.name ||
+                next.scene.width !== previous.scene.width ||
+                next.scene.height !== previous.scene.height
+            ) {
+                dispatch('obsSceneChanged', { ...state.scene });
+            }
+            for (const [key, [started, stopped]] of Object.entries(STATUS_EVENTS)) {
+                if (state.status[key] !== previous.status[key]) {
+                    dispatch(state.status[key] ? started : stopped);
+                }
+            }
+            if (state.active !== previous.active) {
+                dispatch('obsSourceActiveChanged', { active: state.active });
+            }
+        },
+        __setCss: (next) => {
+            css = next;
+            applyCss(css);
+        },
+        __drainAudio: drainAudio,
+    };
+})();
@@ -1,13 +1,26 @@
 /// 菜单相关的模块
 use std::{collections::HashMap, sync::OnceLock};
 
-use log::debug;
+use anyhow::anyhow;
+use log::{debug, error};
 use tauri::{
     menu::{CheckMenuItem, Menu, MenuId, MenuItem, MenuItemKind, PredefinedMenuItem, Submenu},
     AppHandle, Wry,
 };
 
-use crate::{utils::locale::t, Result};
+use crate::{
+    scene::{
+        clipboard::{
+            copy_selected_items, copy_selected_transform, paste_selected_transform,
+            paste_to_current_scene, update_clipboard_menu,
+        },
+        edit::{move_items, request_edit_transform, reset_selected_transform, OrderMovement},
+        scenes,
+        undo::{redo, undo, update_undo_menu},
+    },
+    utils::locale::t,
+    Result,
+};
 
 /// 菜单映射类型，用于存储菜单项
 type MenuMap = HashMap<MenuId, MenuItemKind<Wry>>;
@@ -82,6 +95,71 @@ fn find_menu_by_id(app: &AppHandle, id: &MenuId) -> Option<MenuItemKind<Wry>> {
     menus.get(id).cloned()
 }
 
+/// 设置菜单项是否可用
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `id` - 菜单项ID
+/// * `enabled` - 是否可用
+///
+/// # 返回值
+///
+/// 返回Result<()>，表示操作是否成功
+pub fn set_menu_enabled(app: &AppHandle, id: &str, enabled: bool) -> Result<()> {
+    match find_menu_by_id(app, &MenuId::new(id)) {
+        Some(MenuItemKind::MenuItem(item)) => item.set_enabled(enabled)?,
+        Some(MenuItemKind::Check(item)) => item.set_enabled(enabled)?,
+        Some(MenuItemKind::Submenu(item)) => item.set_enabled(enabled)?,
+        Some(_) => {}
+        None => return Err(anyhow!("menu not found: {}", id)),
+    }
+
+    Ok(())
+}
+
+/// 设置菜单项文本
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `id` - 菜单项ID
+/// * `text` - 菜单项文本
+///
+/// # 返回值
+///
+/// 返回Result<()>，表示操作是否成功
+pub fn set_menu_text(app: &AppHandle, id: &str, text: &str) -> Result<()> {
+    match find_menu_by_id(app, &MenuId::new(id)) {
+        Some(MenuItemKind::MenuItem(item)) => item.set_text(text)?,
+        Some(MenuItemKind::Check(item)) => item.set_text(text)?,
+        Some(MenuItemKind::Submenu(item)) => item.set_text(text)?,
+        Some(_) => {}
+        None => return Err(anyhow!("menu not found: {}", id)),
+    }
+
+    Ok(())
+}
+
+/// 对当前场景中被选中的场景项调整顺序
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `movement` - 移动方式
+///
+/// # 返回值
+///
+/// 返回Result<()>，表示操作是否成功
+fn move_selected_items(app: &AppHandle, movement: OrderMovement) -> Result<()> {
+    let (scene, ids) = match scenes().current_scene() {
+        Some(scene) => (scene.uuid.clone(), scene.selected_items()),
+        None => return Ok(()),
+    };
+
+    move_items(app, &scene, &ids, movement)
+}
+
 /// 设置应用程序菜单
 ///
 /// # 参数
@@ -156,7 +234,7 @@ pub fn setup_menus(app: &AppHandle) -> Result<()> {
                     &MenuItem::with_id(app, "Undo.Undo", t("Undo.Undo")?, false, None::<&str>)?,
                     &MenuItem::with_id(app, "Undo.Redo", t("Undo.Redo")?, false, None::<&str>)?,
                     &PredefinedMenuItem::separator(app)?,
-                    &MenuItem::with_id(app, "Copy", t("Copy")?, true, None::<&str>)?,
+                    &MenuItem::with_id(app, "Copy", t("Copy")?, false, None::<&str>)?,
                     &MenuItem::with_id(
                         app,
                         "PasteReference",
@@ -168,7 +246,7 @@ pub fn setup_menus(app: &AppHandle) -> Result<()> {
                         app,
                         "PasteDuplicate",
                         t("PasteDuplicate")?,
-                        true,
+                        false,
                         None::<&str>,
                     )?,
                     &PredefinedMenuItem::separator(app)?,
@@ -197,14 +275,14 @@ pub fn setup_menus(app: &AppHandle) -> Result<()> {
                                 app,
                                 "Basic.MainMenu.Edit.Transform.EditTransform",
                                 t("Basic.MainMenu.Edit.Transform.EditTransform")?,
-                                true,
+                                false,
                                 None::<&str>,
                             )?,
                             &MenuItem::with_id(
                                 app,
                                 "Basic.MainMenu.Edit.Transform.CopyTransform",
                                 t("Basic.MainMenu.Edit.Transform.CopyTransform")?,
-                                true,
+                                false,
                                 None::<&str>,
                             )?,
                             &MenuItem::with_id(
@@ -218,7 +296,7 @@ pub fn setup_menus(app: &AppHandle) -> Result<()> {
                                 app,
                                 "Basic.MainMenu.Edit.Transform.ResetTransform",
                                 t("Basic.MainMenu.Edit.Transform.ResetTransform")?,
-                                true,
+                                false,
                                 None::<&str>,
                             )?,
                             &PredefinedMenuItem::separator(app)?,
@@ -384,7 +462,32 @@ pub fn setup_menus(app: &AppHandle) -> Result<()> {
         if event.id == MenuId::new("Basic.MainMenu.File.Exit") {
             app.exit(0);
         }
+
+        let result = match event.id.as_ref() {
+            "Undo.Undo" => undo(app).map(|_| ()),
+            "Undo.Redo" => redo(app).map(|_| ()),
+            "Copy" => copy_selected_items(app),
+            "PasteReference" => paste_to_current_scene(app, false).map(|_| ()),
+            "PasteDuplicate" => paste_to_current_scene(app, true).map(|_| ()),
+            "Basic.MainMenu.Edit.Transform.EditTransform" => request_edit_transform(app),
+            "Basic.MainMenu.Edit.Transform.CopyTransform" => copy_selected_transform(app),
+            "Basic.MainMenu.Edit.Transform.PasteTransform" => paste_selected_transform(app),
+            "Basic.MainMenu.Edit.Transform.ResetTransform" => reset_selected_transform(app),
+            "Basic.MainMenu.Edit.Order.MoveUp" => move_selected_items(app, OrderMovement::Up),
+            "Basic.MainMenu.Edit.Order.MoveDown" => move_selected_items(app, OrderMovement::Down),
+            "Basic.MainMenu.Edit.Order.MoveToTop" => move_selected_items(app, OrderMovement::Top),
+            "Basic.MainMenu.Edit.Order.MoveToBottom" => {
+                move_selected_items(app, OrderMovement::Bottom)
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            error!("menu {:?} failed: {}", event.id, e);
+        }
     });
 
+    update_undo_menu(app)?;
+    update_clipboard_menu(app)?;
+
     Ok(())
 }
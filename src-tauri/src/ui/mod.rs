@@ -1,3 +1,5 @@
+pub mod browser;
 pub mod layout;
 pub mod menu;
+pub mod projector;
 pub mod tray;
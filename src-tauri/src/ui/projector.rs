@@ -0,0 +1,593 @@
+/// 此模块管理投影仪窗口
+///
+/// 每个投影仪是一个独立的窗口，拥有自己的图形上下文，可以全屏显示在指定的显示器上，
+/// 也可以作为普通窗口显示。打开的投影仪保存在场景集合中，下次启动时重新打开
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::Receiver,
+        Arc, Mutex,
+    },
+};
+
+use anyhow::anyhow;
+use image::RgbaImage;
+use lazy_static::lazy_static;
+use log::{error, warn};
+use serde::Serialize;
+use tauri::{
+    window::WindowBuilder, AppHandle, Manager, PhysicalPosition, PhysicalSize, RunEvent,
+    WindowEvent,
+};
+use wgpu::{CommandEncoder, Device, Queue, TextureView};
+
+use crate::{
+    graphics::{
+        context::Context,
+        texture::Texture,
+        transition::{Transition, TransitionKind},
+    },
+    media::{
+        frame::{VideoFrame, VideoInfo},
+        video::{connect_keyed_video, VideoConversion, VideoKey},
+    },
+    scene::{
+        canvas::canvas_video_info,
+        projector::{ProjectorGeometry, ProjectorKind, SavedProjector},
+        save_scenes, scenes,
+    },
+    utils::{config::get_config, locale::t},
+    Result, MAIN_WINDOW_ID,
+};
+
+/// 投影仪窗口标签前缀
+const PROJECTOR_PREFIX: &str = "projector-";
+
+lazy_static! {
+    /// 打开的投影仪，以窗口标签为键
+    static ref PROJECTORS: Mutex<HashMap<String, Projector>> = Mutex::new(HashMap::new());
+}
+
+/// 下一个投影仪窗口的编号
+static NEXT_PROJECTOR: AtomicU64 = AtomicU64::new(1);
+
+/// 主窗口关闭后不再更新保存的投影仪列表
+static CLOSING: AtomicBool = AtomicBool::new(false);
+
+/// 投影仪信息，返回给前端
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectorInfo {
+    /// 窗口标签
+    pub label: String,
+    #[serde(flatten)]
+    pub config: SavedProjector,
+}
+
+/// 显示器信息，返回给前端
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorInfo {
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+/// 合成线程的画面，接收最新一帧上传到投影仪的设备
+struct ProgramFeed {
+    receiver: Receiver<Arc<VideoFrame>>,
+    texture: Option<Texture>,
+}
+
+impl ProgramFeed {
+    /// 连接画面，如画布的节目画面或单个场景的画面
+    fn new(key: VideoKey) -> Result<Self> {
+        Ok(Self {
+            receiver: connect_keyed_video(key, VideoConversion::RGBA)?,
+            texture: None,
+        })
+    }
+
+    /// 取出最新一帧并上传，尺寸不变时复用纹理
+    fn update(&mut self, device: &Device, queue: &Queue) -> Result<()> {
+        let Some(frame) = self.receiver.try_iter().last() else {
+            return Ok(());
+        };
+        let image = RgbaImage::from_raw(frame.width, frame.height, frame.data.clone())
+            .ok_or(anyhow!("invalid program frame"))?;
+
+        match &self.texture {
+            Some(texture)
+                if (texture.texture.width(), texture.texture.height()) == image.dimensions() =>
+            {
+                texture.write(queue, &image)?
+            }
+            _ => {
+                self.texture = Some(Texture::from_rgba(
+                    device,
+                    queue,
+                    &image,
+                    Some("projector"),
+                    false,
+                )?)
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 打开的投影仪
+struct Projector {
+    config: SavedProjector,
+    context: Context,
+    /// 按比例居中绘制纹理
+    fit: Transition,
+    /// 合成线程的画面，来源投影仪显示来源管理器中同一实例的画面
+    feed: ProgramFeed,
+}
+
+impl Projector {
+    /// 内容尺寸，用于锁定窗口宽高比
+    fn content_size(&self) -> (u32, u32) {
+        match &self.feed.texture {
+            // 场景可能属于尺寸不同的附加画布，来源使用自身的尺寸
+            Some(texture) => (texture.texture.width(), texture.texture.height()),
+            None => {
+                let info = VideoInfo::load();
+                (info.base_width, info.base_height)
+            }
+        }
+    }
+
+    /// 渲染一帧
+    fn render(&mut self) -> Result<()> {
+        self.feed
+            .update(self.context.device(), self.context.queue())?;
+
+        let fit = &self.fit;
+        let feed = &self.feed;
+        // 窗口最小化时无法获取交换链纹理，跳过这一帧
+        let _ = self
+            .context
+            .render_with(|device, queue, encoder, view, size| match &feed.texture {
+                Some(texture) => {
+                    fit.encode_view(device, queue, encoder, None, texture, 1., view, size)
+                }
+                None => clear(encoder, view),
+            });
+
+        Ok(())
+    }
+}
+
+/// 没有内容时清空为黑色
+fn clear(encoder: &mut CommandEncoder, view: &TextureView) {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("projector clear"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+}
+
+/// 投影仪窗口标题，例如 `Windowed Projector (Scene) - Scene 1`
+fn projector_title(kind: &ProjectorKind, fullscreen: bool) -> Result<String> {
+    let key = match (kind, fullscreen) {
+        (ProjectorKind::Preview, true) => "PreviewProjector",
+        (ProjectorKind::Preview, false) => "PreviewWindow",
+        (ProjectorKind::Program, true) => "StudioProgramProjector",
+        (ProjectorKind::Program, false) => "StudioProgramWindow",
+        (ProjectorKind::Multiview, true) => "MultiviewProjector",
+        (ProjectorKind::Multiview, false) => "MultiviewWindowed",
+        (ProjectorKind::Scene(_), true) => "SceneProjector",
+        (ProjectorKind::Scene(_), false) => "SceneWindow",
+        (ProjectorKind::Source(_), true) => "SourceProjector",
+        (ProjectorKind::Source(_), false) => "SourceWindow",
+    };
+
+    let mut title = t(key)?;
+    if let ProjectorKind::Scene(uuid) | ProjectorKind::Source(uuid) = kind {
+        title += " - ";
+        title += &scenes().display_name(uuid);
+    }
+
+    Ok(title)
+}
+
+/// 投影仪显示的画面
+fn projector_video(kind: &ProjectorKind) -> VideoKey {
+    match kind {
+        ProjectorKind::Preview | ProjectorKind::Program => VideoKey::Canvas(None),
+        // 场景投影仪显示它自己的场景，不论该场景是否为当前场景
+        ProjectorKind::Scene(uuid) => VideoKey::Scene(uuid.clone()),
+        // 多视图在合成线程中绘制
+        ProjectorKind::Multiview => VideoKey::Multiview,
+        // 来源不在投影仪中另建实例，音频采集等来源不会重复运行
+        ProjectorKind::Source(uuid) => VideoKey::Source(uuid.clone()),
+    }
+}
+
+/// 打开投影仪
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `config` - 投影仪设置，显示器不存在时改为窗口模式
+///
+/// # 返回值
+///
+/// 返回 `Result<String>`，表示投影仪的窗口标签
+pub fn open_projector(app: &AppHandle, mut config: SavedProjector) -> Result<String> {
+    let monitors = app.available_monitors()?;
+    let monitor = config.monitor.and_then(|index| monitors.get(index));
+    if monitor.is_none() {
+        config.monitor = None;
+    }
+
+    // 每个显示器只保留一个全屏投影仪
+    if let Some(index) = config.monitor {
+        if get_config("BasicWindow", "CloseExistingProjectors").is_some_and(|value| value == "true")
+        {
+            let existing: Vec<String> = PROJECTORS
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, projector)| projector.config.monitor == Some(index))
+                .map(|(label, _)| label.clone())
+                .collect();
+            for label in existing {
+                close_projector(app, &label)?;
+            }
+        }
+    }
+
+    let label = format!(
+        "{}{}",
+        PROJECTOR_PREFIX,
+        NEXT_PROJECTOR.fetch_add(1, Ordering::Relaxed)
+    );
+    let info = match &config.kind {
+        ProjectorKind::Scene(uuid) => {
+            let canvas = scenes().scene(uuid).and_then(|scene| scene.canvas.clone());
+            canvas_video_info(canvas.as_deref())?
+        }
+        _ => VideoInfo::load(),
+    };
+    let window = WindowBuilder::new(app, &label)
+        .title(projector_title(&config.kind, monitor.is_some())?)
+        .inner_size(info.base_width as f64 / 2., info.base_height as f64 / 2.)
+        .visible(false)
+        .build()?;
+
+    match monitor {
+        Some(monitor) => {
+            window.set_position(*monitor.position())?;
+            window.set_fullscreen(true)?;
+        }
+        None => {
+            if let Some(geometry) = config.geometry {
+                window.set_position(PhysicalPosition::new(geometry.x, geometry.y))?;
+                window.set_size(PhysicalSize::new(geometry.width, geometry.height))?;
+            }
+        }
+    }
+    window.set_always_on_top(config.always_on_top)?;
+
+    let projector = Context::new(app, &window).and_then(|context| {
+        let feed = ProgramFeed::new(projector_video(&config.kind))?;
+        Ok(Projector {
+            config,
+            fit: Transition::new(context.device(), context.format(), TransitionKind::Cut),
+            context,
+            feed,
+        })
+    });
+    let projector = match projector {
+        Ok(projector) => projector,
+        Err(e) => {
+            window.destroy()?;
+            return Err(e);
+        }
+    };
+    PROJECTORS.lock().unwrap().insert(label.clone(), projector);
+    window.show()?;
+
+    sync_projectors();
+
+    Ok(label)
+}
+
+/// 关闭投影仪
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `label` - 投影仪窗口标签
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn close_projector(app: &AppHandle, label: &str) -> Result<()> {
+    if PROJECTORS.lock().unwrap().remove(label).is_none() {
+        return Err(anyhow!("projector not found: {}", label));
+    }
+    if let Some(window) = app.get_window(label) {
+        window.destroy()?;
+    }
+    sync_projectors();
+
+    Ok(())
+}
+
+/// 设置投影仪是否置顶和锁定宽高比
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `label` - 投影仪窗口标签
+/// * `always_on_top` - 窗口置顶
+/// * `aspect_lock` - 调整窗口大小时保持内容的宽高比
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn set_projector_options(
+    app: &AppHandle,
+    label: &str,
+    always_on_top: bool,
+    aspect_lock: bool,
+) -> Result<()> {
+    {
+        let mut projectors = PROJECTORS.lock().unwrap();
+        let projector = projectors
+            .get_mut(label)
+            .ok_or(anyhow!("projector not found: {}", label))?;
+        projector.config.always_on_top = always_on_top;
+        projector.config.aspect_lock = aspect_lock;
+    }
+
+    if let Some(window) = app.get_window(label) {
+        window.set_always_on_top(always_on_top)?;
+        if aspect_lock {
+            lock_aspect(app, label, window.inner_size()?)?;
+        }
+    }
+    sync_projectors();
+
+    Ok(())
+}
+
+/// 获取打开的投影仪
+pub fn projectors() -> Vec<ProjectorInfo> {
+    let mut projectors: Vec<ProjectorInfo> = PROJECTORS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(label, projector)| ProjectorInfo {
+            label: label.clone(),
+            config: projector.config.clone(),
+        })
+        .collect();
+    projectors.sort_by(|a, b| a.label.cmp(&b.label));
+
+    projectors
+}
+
+/// 获取可以全屏显示投影仪的显示器，序号即 `monitor` 参数
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+pub fn monitors(app: &AppHandle) -> Result<Vec<MonitorInfo>> {
+    let monitors = app
+        .available_monitors()?
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| MonitorInfo {
+            name: monitor
+                .name()
+                .cloned()
+                .unwrap_or_else(|| format!("{}", index + 1)),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale_factor: monitor.scale_factor(),
+        })
+        .collect();
+
+    Ok(monitors)
+}
+
+/// 窗口模式下按内容宽高比调整窗口高度
+fn lock_aspect(app: &AppHandle, label: &str, size: PhysicalSize<u32>) -> Result<()> {
+    let (width, height) = match PROJECTORS.lock().unwrap().get(label) {
+        Some(projector) if projector.config.monitor.is_none() => projector.content_size(),
+        _ => return Ok(()),
+    };
+    if width == 0 || height == 0 || size.width == 0 {
+        return Ok(());
+    }
+
+    let locked = (size.width as u64 * height as u64 / width as u64) as u32;
+    // 只在相差超过一个像素时调整，避免调整后的尺寸事件再次触发调整
+    if locked.abs_diff(size.height) > 1 {
+        if let Some(window) = app.get_window(label) {
+            window.set_size(PhysicalSize::new(size.width, locked))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 记录窗口模式下投影仪的位置和尺寸，退出时保存
+fn update_geometry(app: &AppHandle, label: &str) -> Result<()> {
+    let Some(window) = app.get_window(label) else {
+        return Ok(());
+    };
+    let position = window.outer_position()?;
+    let size = window.inner_size()?;
+    if let Some(projector) = PROJECTORS.lock().unwrap().get_mut(label) {
+        if projector.config.monitor.is_none() && !window.is_minimized()? {
+            projector.config.geometry = Some(ProjectorGeometry {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// 把打开的投影仪写入场景集合，未开启保存投影仪时清空
+fn sync_projectors() {
+    if CLOSING.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let saved = match get_config("BasicWindow", "SaveProjectors") {
+        Some(value) if value == "false" => vec![],
+        _ => projectors()
+            .into_iter()
+            .map(|projector| projector.config)
+            .collect(),
+    };
+    if scenes().saved_projectors == saved {
+        return;
+    }
+    scenes().saved_projectors = saved;
+    if let Err(e) = save_scenes() {
+        error!("failed to save projectors: {}", e);
+    }
+}
+
+/// 关闭所有投影仪，保留保存的列表
+fn close_all(app: &AppHandle) {
+    sync_projectors();
+    CLOSING.store(true, Ordering::Relaxed);
+
+    let labels: Vec<String> = PROJECTORS
+        .lock()
+        .unwrap()
+        .drain()
+        .map(|(label, _)| label)
+        .collect();
+    for label in labels {
+        if let Some(window) = app.get_window(&label) {
+            let _ = window.destroy();
+        }
+    }
+}
+
+/// 处理投影仪相关的事件
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+/// * `event` - 运行时事件
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn projector_event(app: &AppHandle, event: &RunEvent) -> Result<()> {
+    match event {
+        // 主窗口关闭时一起关闭投影仪，否则应用程序不会退出
+        RunEvent::WindowEvent {
+            label,
+            event: WindowEvent::Destroyed,
+            ..
+        } if label == MAIN_WINDOW_ID => close_all(app),
+        RunEvent::WindowEvent { label, event, .. } if label.starts_with(PROJECTOR_PREFIX) => {
+            match event {
+                WindowEvent::Resized(size) => {
+                    let aspect_lock = {
+                        let mut projectors = PROJECTORS.lock().unwrap();
+                        let Some(projector) = projectors.get_mut(label) else {
+                            return Ok(());
+                        };
+                        if size.width > 0 && size.height > 0 {
+                            projector.context.resize(size);
+                        }
+                        projector.config.aspect_lock
+                    };
+                    update_geometry(app, label)?;
+                    if aspect_lock {
+                        lock_aspect(app, label, *size)?;
+                    }
+                }
+                WindowEvent::Moved(_) => update_geometry(app, label)?,
+                WindowEvent::CloseRequested { .. } => {
+                    PROJECTORS.lock().unwrap().remove(label);
+                    sync_projectors();
+                }
+                WindowEvent::Destroyed => {
+                    PROJECTORS.lock().unwrap().remove(label);
+                }
+                _ => {}
+            }
+        }
+        RunEvent::MainEventsCleared => {
+            for (label, projector) in PROJECTORS.lock().unwrap().iter_mut() {
+                if let Err(e) = projector.render() {
+                    warn!("failed to render projector {}: {}", label, e);
+                }
+            }
+        }
+        RunEvent::ExitRequested { .. } => sync_projectors(),
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// 重新打开场景集合中保存的投影仪
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn setup_projectors(app: &AppHandle) -> Result<()> {
+    if get_config("BasicWindow", "SaveProjectors").is_some_and(|value| value == "false") {
+        return Ok(());
+    }
+
+    let saved = scenes().saved_projectors.clone();
+    for config in saved {
+        let kind = config.kind.clone();
+        if let Err(e) = open_projector(app, config) {
+            warn!("failed to restore projector {:?}: {}", kind, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// 创建新投影仪的设置，置顶默认使用全局配置
+///
+/// # 参数
+///
+/// * `kind` - 显示的内容
+/// * `monitor` - 全屏显示的显示器序号，为 `None` 时为窗口模式
+pub fn new_projector(kind: ProjectorKind, monitor: Option<usize>) -> SavedProjector {
+    SavedProjector {
+        always_on_top: get_config("BasicWindow", "ProjectorAlwaysOnTop")
+            .is_some_and(|value| value == "true"),
+        ..SavedProjector::new(kind, monitor)
+    }
+}
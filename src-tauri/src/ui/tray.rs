@@ -1,11 +1,19 @@
 /// 系统托盘相关功能模块
+use log::error;
 use tauri::{
     menu::{Menu, MenuId, MenuItem, PredefinedMenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconEvent},
     AppHandle, Manager, Wry,
 };
 
-use crate::{utils::locale::t, Result, MAIN_TRAY_ID, MAIN_WINDOW_ID};
+use crate::{
+    outputs::recording::{
+        add_chapter, pause_recording, recording_status, resume_recording, split_recording,
+        start_recording, stop_recording,
+    },
+    utils::locale::t,
+    Result, MAIN_TRAY_ID, MAIN_WINDOW_ID,
+};
 
 /// 设置系统托盘菜单
 ///
@@ -24,11 +32,41 @@ fn setup_tray_menu(app: &AppHandle, show: bool) -> Result<Option<Menu<Wry>>> {
         t("Basic.SystemTray.Hide")?
     };
 
+    let recording = recording_status();
+    // 菜单项 ID 使用对应的翻译键
+    let record = if recording.active {
+        "Basic.Main.StopRecording"
+    } else {
+        "Basic.Main.StartRecording"
+    };
+    let pause = if recording.paused {
+        "Basic.Main.UnpauseRecording"
+    } else {
+        "Basic.Main.PauseRecording"
+    };
+
     let tray_menu = Menu::with_items(
         app,
         &[
             &MenuItem::with_id(app, "Basic.SystemTray.Show", title, true, None::<&str>)?,
             &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, record, t(record)?, true, None::<&str>)?,
+            &MenuItem::with_id(app, pause, t(pause)?, recording.active, None::<&str>)?,
+            &MenuItem::with_id(
+                app,
+                "Basic.Main.SplitFile",
+                t("Basic.Main.SplitFile")?,
+                recording.active,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app,
+                "Basic.Main.AddChapterMarker",
+                t("Basic.Main.AddChapterMarker")?,
+                recording.active,
+                None::<&str>,
+            )?,
+            &PredefinedMenuItem::separator(app)?,
             &MenuItem::with_id(app, "Basic.SystemTray.Exit", t("Exit")?, true, None::<&str>)?,
         ],
     )?;
@@ -61,6 +99,32 @@ fn toggle_main_window(app: &AppHandle) {
     }
 }
 
+/// 按录制状态和主窗口的显示状态重建系统托盘菜单
+///
+/// 在主线程中执行，可以从输出线程调用
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+pub fn update_tray_menu(app: &AppHandle) {
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        let (Some(system_tray), Some(main_window)) = (
+            handle.tray_by_id(MAIN_TRAY_ID),
+            handle.get_window(MAIN_WINDOW_ID),
+        ) else {
+            return;
+        };
+
+        let show = !main_window.is_visible().unwrap_or(true);
+        if let Err(e) =
+            setup_tray_menu(&handle, show).and_then(|menu| Ok(system_tray.set_menu(menu)?))
+        {
+            error!("failed to update tray menu: {}", e);
+        }
+    });
+}
+
 /// 设置系统托盘
 ///
 /// # 参数
@@ -85,6 +149,22 @@ pub fn setup_tray(app: &AppHandle) -> Result<()> {
             toggle_main_window(app);
         }
 
+        let result = match event.id.as_ref() {
+            "Basic.Main.StartRecording" => start_recording(app),
+            "Basic.Main.StopRecording" => {
+                stop_recording();
+                Ok(())
+            }
+            "Basic.Main.PauseRecording" => pause_recording(),
+            "Basic.Main.UnpauseRecording" => resume_recording(),
+            "Basic.Main.SplitFile" => split_recording(),
+            "Basic.Main.AddChapterMarker" => add_chapter(None),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            error!("{}: {}", event.id.as_ref(), e);
+        }
+
         if event.id == MenuId::new("Basic.SystemTray.Exit") {
             app.exit(0);
         }
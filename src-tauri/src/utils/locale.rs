@@ -205,3 +205,24 @@ pub fn t(key: &str) -> Result<String> {
         None => Ok(key.to_string()),
     }
 }
+
+/// 获取指定键的翻译，并依次替换其中的 `%1`、`%2` 等占位符
+///
+/// # 参数
+///
+/// * `key` - 翻译键
+/// * `args` - 占位符参数
+///
+/// # 返回值
+///
+/// 返回 `Result<String>`，表示格式化后的文本
+pub fn tf(key: &str, args: &[&str]) -> Result<String> {
+    let mut message = t(key)?;
+
+    // 倒序替换，避免 %1 误替换 %10 的前缀
+    for (index, arg) in args.iter().enumerate().rev() {
+        message = message.replace(&format!("%{}", index + 1), arg);
+    }
+
+    Ok(message)
+}
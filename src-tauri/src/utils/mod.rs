@@ -5,3 +5,4 @@ pub mod config;
 pub mod dialog;
 pub mod locale;
 pub mod log;
+pub mod profile;
@@ -0,0 +1,203 @@
+/// 配置文件(Profile)模块
+use std::{
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use anyhow::anyhow;
+use ini::Ini;
+use log::error;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    utils::{cli::cli, config::get_config, dialog::message},
+    Result,
+};
+
+/// 默认配置文件名称
+pub const DEFAULT_PROFILE: &str = "Untitled";
+
+/// 当前配置文件路径
+static PROFILE_FILE: OnceLock<PathBuf> = OnceLock::new();
+/// 当前配置文件对象
+static PROFILE_CONFIG: OnceLock<Mutex<Ini>> = OnceLock::new();
+
+/// 获取当前配置文件名称
+///
+/// 命令行 `--profile` 参数优先，其次为全局配置中的 `Basic.Profile`
+///
+/// # 返回值
+///
+/// 返回当前配置文件名称
+pub fn current_profile() -> String {
+    if let Ok(cli) = cli() {
+        if let Some(profile) = cli.opt_starting_profile {
+            return profile;
+        }
+    }
+
+    get_config("Basic", "Profile").unwrap_or(DEFAULT_PROFILE.to_string())
+}
+
+/// 设置当前配置文件
+///
+/// # 参数
+///
+/// * `app` - 应用程序句柄
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn setup_profile_config(app: &AppHandle) -> Result<()> {
+    PROFILE_FILE.get_or_init(|| {
+        let profile_dir = get_config("Basic", "ProfileDir").unwrap_or(current_profile());
+        let profile_file = PathBuf::from("basic/profiles")
+            .join(profile_dir)
+            .join("basic.ini");
+
+        match app
+            .path()
+            .resolve(&profile_file, tauri::path::BaseDirectory::AppLocalData)
+        {
+            Ok(profile_file) => profile_file,
+            Err(e) => {
+                message(
+                    app,
+                    "error",
+                    "Error",
+                    format!("{} {}", profile_file.display(), e).as_str(),
+                    1,
+                );
+                panic!("profile file path not found {}", e);
+            }
+        }
+    });
+
+    PROFILE_CONFIG.get_or_init(|| {
+        let config = match Ini::load_from_file(PROFILE_FILE.get().unwrap()) {
+            Ok(config) => config,
+            Err(_) => Ini::new(),
+        };
+
+        Mutex::new(config)
+    });
+
+    Ok(())
+}
+
+/// 测试时使用临时目录中的空配置文件
+#[cfg(test)]
+pub fn setup_test_profile_config() {
+    PROFILE_FILE.get_or_init(|| {
+        std::env::temp_dir()
+            .join(format!("profile-test-{}", std::process::id()))
+            .join("basic.ini")
+    });
+    PROFILE_CONFIG.get_or_init(|| Mutex::new(Ini::new()));
+}
+
+/// 获取配置文件所在目录
+///
+/// # 返回值
+///
+/// 返回 `Option<PathBuf>`，配置文件尚未初始化时返回 `None`
+pub fn profile_dir() -> Option<PathBuf> {
+    PROFILE_FILE
+        .get()
+        .and_then(|file| file.parent().map(|dir| dir.to_path_buf()))
+}
+
+/// 获取配置文件中配置项的值
+///
+/// # 参数
+///
+/// * `section` - 配置节名
+/// * `key` - 配置项名
+///
+/// # 返回值
+///
+/// 返回 `Option<String>`，表示配置项的值
+pub fn get_profile_config(section: &str, key: &str) -> Option<String> {
+    match PROFILE_CONFIG.get() {
+        Some(mutex) => match mutex.lock() {
+            Ok(config) => config
+                .section(Some(section))
+                .and_then(|section| section.get(key))
+                .map(|value| value.to_string()),
+            Err(e) => {
+                error!("failed to lock profile config: {}", e);
+                None
+            }
+        },
+        None => None,
+    }
+}
+
+/// 设置配置文件中配置项的值
+///
+/// # 参数
+///
+/// * `section` - 配置节名
+/// * `key` - 配置项名
+/// * `value` - 配置项的值
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn set_profile_config(section: &str, key: &str, value: &str) -> Result<()> {
+    match PROFILE_CONFIG.get() {
+        Some(mutex) => match mutex.lock() {
+            Ok(mut config) => {
+                config
+                    .with_section(Some(section))
+                    .set(key, value.to_string());
+
+                let profile_file = PROFILE_FILE.get().unwrap();
+                if let Some(dir) = profile_file.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+
+                match config.write_to_file(profile_file) {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(anyhow!("failed to write profile config: {}", e)),
+                }
+            }
+            Err(e) => Err(anyhow!("failed to lock profile config: {}", e)),
+        },
+        None => Err(anyhow!("profile config not initialized")),
+    }
+}
+
+/// 删除配置文件中的配置项
+///
+/// # 参数
+///
+/// * `section` - 配置节名
+/// * `key` - 配置项名
+///
+/// # 返回值
+///
+/// 返回 `Result<()>`，表示操作是否成功
+pub fn remove_profile_config(section: &str, key: &str) -> Result<()> {
+    match PROFILE_CONFIG.get() {
+        Some(mutex) => match mutex.lock() {
+            Ok(mut config) => {
+                if let Some(section) = config.section_mut(Some(section)) {
+                    section.remove(key);
+                }
+
+                let profile_file = PROFILE_FILE.get().unwrap();
+                if let Some(dir) = profile_file.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+
+                match config.write_to_file(profile_file) {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(anyhow!("failed to write profile config: {}", e)),
+                }
+            }
+            Err(e) => Err(anyhow!("failed to lock profile config: {}", e)),
+        },
+        None => Err(anyhow!("profile config not initialized")),
+    }
+}